@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use colored::Colorize;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+/// The file observed query shapes are written to, relative to the working
+/// directory.
+pub(crate) const SHAPES_FILE: &str = ".teo/query-shapes.json";
+
+/// The fields a query filters by equality, filters by range and sorts by, in
+/// this order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Shape {
+    equality: Vec<String>,
+    range: Vec<String>,
+    order: Vec<String>,
+}
+
+impl Shape {
+
+    /// The index which serves the shape: equality fields first, then the first
+    /// range field, then the sort fields.
+    fn suggested_index(&self) -> Vec<String> {
+        let mut fields = self.equality.clone();
+        for field in self.range.iter().take(1).chain(self.order.iter()) {
+            if !fields.contains(field) {
+                fields.push(field.clone());
+            }
+        }
+        fields
+    }
+}
+
+/// Observed shapes and how many times they were seen, keyed by model path.
+static SHAPES: Lazy<Mutex<BTreeMap<String, BTreeMap<String, (Shape, u64)>>>> = Lazy::new(|| Mutex::new(load_shapes().unwrap_or_default()));
+
+fn load_shapes() -> Option<BTreeMap<String, BTreeMap<String, (Shape, u64)>>> {
+    serde_json::from_str(&fs::read_to_string(SHAPES_FILE).ok()?).ok()
+}
+
+/// Record the filter and sort shape of a find query. The shapes are written
+/// to disk whenever a new one is seen, and every 100 queries otherwise.
+pub(crate) fn record(model: &Model, args: &JsonValue) {
+    let mut shape = Shape { equality: vec![], range: vec![], order: vec![] };
+    if let Some(JsonValue::Object(r#where)) = args.get("where") {
+        for (key, filter) in r#where {
+            if model.field(key).is_none() {
+                continue
+            }
+            let is_equality = match filter {
+                JsonValue::Object(operators) => operators.keys().all(|k| k == "equals" || k == "mode"),
+                _ => true,
+            };
+            if is_equality { shape.equality.push(key.clone()) } else { shape.range.push(key.clone()) }
+        }
+    }
+    let orders = match args.get("orderBy") {
+        Some(JsonValue::Array(orders)) => orders.clone(),
+        Some(order) => vec![order.clone()],
+        None => vec![],
+    };
+    for order in orders {
+        if let JsonValue::Object(order) = order {
+            shape.order.extend(order.keys().filter(|k| model.field(k).is_some()).cloned());
+        }
+    }
+    if shape.equality.is_empty() && shape.range.is_empty() && shape.order.is_empty() {
+        return;
+    }
+    shape.equality.sort();
+    shape.range.sort();
+    let key = serde_json::to_string(&shape).unwrap_or_default();
+    let Ok(mut shapes) = SHAPES.lock() else { return };
+    let entry = shapes.entry(model.path().join(".")).or_default().entry(key).or_insert((shape, 0));
+    entry.1 += 1;
+    if entry.1 == 1 || entry.1 % 100 == 0 {
+        if let Ok(content) = serde_json::to_string_pretty(&*shapes) {
+            let path = PathBuf::from(SHAPES_FILE);
+            let _ = path.parent().map(fs::create_dir_all);
+            let _ = fs::write(path, content);
+        }
+    }
+}
+
+/// An index which would serve observed queries of a model.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Advice {
+    pub(crate) model_path: String,
+    pub(crate) fields: Vec<String>,
+    /// The number of observed queries the index serves.
+    pub(crate) hits: u64,
+}
+
+/// Print the indexes which would serve the observed queries but aren't
+/// declared in the schema.
+pub(crate) fn advise() -> Result<()> {
+    let advices = advices()?;
+    for advice in &advices {
+        println!("{} {}: @@index([{}]) serves {} observed queries", "Advice".yellow().bold(), advice.model_path, advice.fields.join(", "), advice.hits);
+    }
+    if advices.is_empty() {
+        info_message("the declared indexes serve all observed queries");
+    }
+    Ok(())
+}
+
+/// The indexes which would serve the observed queries but aren't declared in
+/// the schema, the ones serving the most queries of a model first. Shapes
+/// served by the same index, like `[a]` and `[a, b]`, share an advice.
+pub(crate) fn advices() -> Result<Vec<Advice>> {
+    let shapes = load_shapes().ok_or_else(|| Error::new(format!("no query shapes are recorded, run the server in development and send some queries first, they are written to `{}`", SHAPES_FILE)))?;
+    let mut result = vec![];
+    for (model_path, model_shapes) in &shapes {
+        let path: Vec<&str> = model_path.split('.').collect();
+        let Some(model) = Ctx::main_namespace().model_at_path(&path) else { continue };
+        let declared = declared_indexes(model);
+        let mut suggested: Vec<(Vec<String>, u64)> = vec![];
+        for (shape, hits) in model_shapes.values() {
+            let fields = shape.suggested_index();
+            if declared.iter().any(|index| index.starts_with(&fields)) {
+                continue
+            }
+            match suggested.iter_mut().find(|(s, _)| s.starts_with(&fields) || fields.starts_with(s)) {
+                Some((existing, existing_hits)) => {
+                    if fields.len() > existing.len() {
+                        *existing = fields;
+                    }
+                    *existing_hits += hits;
+                }
+                None => suggested.push((fields, *hits)),
+            }
+        }
+        suggested.sort_by(|a, b| b.1.cmp(&a.1));
+        result.extend(suggested.into_iter().map(|(fields, hits)| Advice { model_path: model_path.clone(), fields, hits }));
+    }
+    Ok(result)
+}
+
+fn declared_indexes(model: &Model) -> Vec<Vec<String>> {
+    model.indexes.values().map(|index| index.items.iter().map(|item| item.field.clone()).collect()).collect()
+}
@@ -1,4 +1,4 @@
-use std::process::exit;
+use std::env;
 use std::env::current_dir;
 use teo_result::{Error, Result};
 use teo_runtime::namespace::Namespace;
@@ -12,8 +12,41 @@ use teo_runtime::schema::load::load_schema::load_schema;
 use crate::cli::run::run;
 use dotenvy::dotenv;
 use teo_runtime::connection::transaction;
+use teo_runtime::pipeline::item;
+use std::sync::Arc;
+#[cfg(feature = "wasm")]
+use crate::wasm::{WasmLimits, WasmPlugin};
+#[cfg(feature = "js")]
+use crate::js::JsScript;
 use crate::app::callbacks::callback::AsyncCallbackArgument;
+use crate::app::db_function::define_db_function_handler;
+#[cfg(any(feature = "wasm", feature = "js"))]
+use crate::app::plugin::define_plugin_pipeline_item;
+use crate::app::plugin::define_plugin;
+use crate::app::naming::{apply_naming, NamingConvention};
+use crate::app::database::connector::ConnectorBuilder;
+use crate::app::scalar::ScalarCodec;
+use crate::app::secrets::SecretProvider;
+use crate::events::consumer::{Consumer, EventSource};
+use crate::events::EventSink;
+use crate::lint::check_schema_arguments;
+use crate::migrate::backfill::{Backfill, BackfillCallback, DEFAULT_BACKFILL_BATCH_SIZE};
 use crate::prelude::{Entrance, RuntimeVersion};
+use crate::utils::db_functions::extract_db_function_handlers;
+use crate::utils::plugins::extract_plugin_declarations;
+use crate::utils::transitions::rewrite_transitions;
+use crate::utils::delimiters::print_schema_errors;
+use crate::utils::environments::apply_environment_overlays;
+use crate::utils::named_queries::extract_named_queries;
+use crate::stdlib::{load as load_crate_std};
+use crate::server::client_ip::Cidr;
+use crate::server::credentials::CredentialVerifier;
+use crate::server::envelope::Envelope;
+use crate::server::error::ErrorFormat;
+use crate::server::i18n::MessageCatalogs;
+use crate::server::lockout::{CaptchaVerifier, SignInLockout};
+use crate::server::magic_link::{MagicLinks, MagicLinkSender};
+use crate::server::signature::NonceStore;
 
 #[derive(Debug)]
 pub struct App { }
@@ -38,18 +71,43 @@ impl App {
         }
         Ctx::set_argv(argv);
         let cli = cli_parse(Ctx::get().runtime_version.clone(), Ctx::get().entrance, Ctx::argv());
+        if cli.command.ignores_schema() {
+            Ctx::set_cli(cli);
+            return Ok(Self { });
+        }
         let current_dir = match current_dir() {
             Ok(current_dir) => current_dir,
             Err(e) => Err(Error::new(format!("{}", e)))?,
         };
         let main_schema_file = find_main_schema_file(cli.schema.as_ref().map(AsRef::as_ref), &current_dir)?;
-        let (schema, diagnostics) = schema_parse(main_schema_file.as_path().to_str().unwrap(), None, None);
-        print_diagnostics(&diagnostics, true);
+        let schema_dir = main_schema_file.parent().unwrap_or(current_dir.as_path());
+        let mut overlays = apply_environment_overlays(schema_dir, env::var("TEO_ENV").ok().as_deref());
+        let named_queries = extract_named_queries(schema_dir, &mut overlays)?;
+        let db_function_handlers = extract_db_function_handlers(schema_dir, &mut overlays)?;
+        let plugins = extract_plugin_declarations(schema_dir, &mut overlays)?;
+        rewrite_transitions(schema_dir, &mut overlays)?;
+        let (schema, diagnostics) = schema_parse(main_schema_file.as_path().to_str().unwrap(), None, if overlays.is_empty() { None } else { Some(overlays.clone()) });
         if diagnostics.has_errors() {
-            exit(1);
+            print_schema_errors(schema_dir, &main_schema_file, &overlays, &diagnostics);
+            Err(Error::new("the schema has errors"))?
         }
+        print_diagnostics(&diagnostics, true);
+        check_schema_arguments(&schema)?;
         load_std(Ctx::main_namespace_mut());
+        load_crate_std(Ctx::main_namespace_mut());
+        for handler in db_function_handlers {
+            define_db_function_handler(Ctx::main_namespace_mut(), handler);
+        }
+        for plugin in plugins {
+            define_plugin(Ctx::main_namespace_mut(), plugin)?;
+        }
         Ctx::set_schema(schema);
+        for query in &named_queries {
+            if Ctx::main_namespace().model_at_path(&query.model.iter().map(|s| s.as_str()).collect()).is_none() {
+                Err(Error::new(format!("query `{}`: model `{}` is not found", query.name, query.model.join("."))))?
+            }
+        }
+        Ctx::set_named_queries(named_queries);
         Ctx::set_cli(cli);
         Ok(Self { })
     }
@@ -68,6 +126,285 @@ impl App {
         });
     }
 
+    /// Load the message catalogs in `dir` to localize error messages by the
+    /// request's `Accept-Language` header. See `MessageCatalogs` for the format.
+    pub fn message_catalogs(&self, dir: impl AsRef<std::path::Path>) -> Result<()> {
+        Ctx::set_message_catalogs(MessageCatalogs::load(dir)?);
+        Ok(())
+    }
+
+    /// Set the shape of error responses. `ErrorFormat::Legacy`, a single
+    /// message string for each field, is the default. Regenerate clients
+    /// after switching to `ErrorFormat::Structured`.
+    pub fn error_format(&self, format: ErrorFormat) {
+        Ctx::set_error_format(format);
+    }
+
+    /// Retry connecting to unreachable databases at startup with exponential
+    /// backoff, starting at 500ms. By default the app exits on the first
+    /// failure.
+    pub fn connect_retries(&self, retries: u32) {
+        Ctx::set_connect_retries(retries);
+    }
+
+    /// Set the size limit of JSON request bodies in bytes. Defaults to 256KB.
+    pub fn default_body_limit(&self, limit: usize) {
+        Ctx::body_limits_mut().set_default(limit);
+    }
+
+    /// Override the body size limit of an action, e.g. `"Product.importCsv"`,
+    /// or of an action of every model, e.g. `"createMany"`. Requests over the
+    /// limit are rejected with 413.
+    pub fn body_limit(&self, action: &str, limit: usize) {
+        Ctx::body_limits_mut().insert(action, limit);
+    }
+
+    /// Set the shape of json responses, e.g. bare data or other key names
+    /// for frontends with an existing contract. Generated clients follow it.
+    pub fn envelope(&self, envelope: Envelope) {
+        Ctx::envelopes_mut().set_default(envelope);
+    }
+
+    /// Override the response shape of an action, e.g. `"User.findMany"`, or
+    /// of an action of every model, e.g. `"findMany"`.
+    pub fn action_envelope(&self, action: &str, envelope: Envelope) {
+        Ctx::envelopes_mut().insert(action, envelope);
+    }
+
+    /// How long the responses of create and upsert requests with an
+    /// `Idempotency-Key` header are kept for replaying. Defaults to 24 hours.
+    pub fn idempotency_window(&self, window: std::time::Duration) {
+        Ctx::set_idempotency_window(window);
+    }
+
+    /// Expose the model actions through a single JSON-RPC 2.0 endpoint at
+    /// `/_rpc`, where the method of a call is like `blog.Post.findMany`.
+    /// Batches and notifications are supported.
+    pub fn json_rpc(&self, enabled: bool) {
+        Ctx::set_json_rpc(enabled);
+    }
+
+    /// Append a comment with the model, action and request id to the raw SQL
+    /// statements run for a request, like
+    /// `/*action='findMany',model='blog.Post',request_id='...'*/`, so that
+    /// database load can be attributed to endpoints. This covers sequences,
+    /// slug history, tree filters, sessions and database functions; the
+    /// statements the connectors build for model actions aren't tagged.
+    pub fn query_tags(&self, enabled: bool) {
+        Ctx::set_query_tags(enabled);
+    }
+
+    /// Trust the `Forwarded` and `X-Forwarded-For` headers of requests from
+    /// these proxies, given as CIDRs like `"10.0.0.0/8"` or as addresses, to
+    /// find the client IP address used by sign in lockouts, sessions, logs
+    /// and `$requestIp`. Without trusted proxies the headers are ignored.
+    pub fn trusted_proxies(&self, proxies: &[&str]) -> Result<()> {
+        Ctx::set_trusted_proxies(proxies.iter().map(|proxy| Cidr::parse(proxy)).collect::<Result<_>>()?);
+        Ok(())
+    }
+
+    /// Accept requests signed with the API key `id` and its HMAC `secret`.
+    /// Signed requests carry the `X-Teo-Key`, `X-Teo-Timestamp`,
+    /// `X-Teo-Nonce` and `X-Teo-Signature` headers; replayed nonces and
+    /// stale timestamps are rejected with 401.
+    pub fn signing_key(&self, id: &str, secret: &str) {
+        Ctx::insert_signing_key(id, secret);
+    }
+
+    /// Remember the nonces of signed requests in `store` instead of in
+    /// process, so that replays are rejected across the instances of an app.
+    pub fn nonce_store<S>(&self, store: S) where S: NonceStore + 'static {
+        Ctx::set_nonce_store(store);
+    }
+
+    /// Reject requests which aren't signed with a signing key.
+    pub fn require_signed_requests(&self, required: bool) {
+        Ctx::set_signature_required(required);
+    }
+
+    /// Let identities with the `role` act as another identity of the same
+    /// model by sending its unique where input in the `X-Teo-Act-As` header,
+    /// e.g. `{"id": 2}`. Handlers, pipelines and permissions see the identity
+    /// acted as, and each impersonated request is logged and recorded with
+    /// both identities in the `_teo_impersonations` table, which migrations
+    /// create.
+    pub fn impersonation_role(&self, role: &str) {
+        Ctx::set_impersonation_role(role);
+    }
+
+    /// Record the tokens issued by `signIn` as sessions with their device, IP
+    /// address and expiry, and reject tokens whose session is revoked. The
+    /// `/_sessions/list`, `/_sessions/revoke` and `/_sessions/revokeAll`
+    /// endpoints manage the sessions of the identity of the bearer token.
+    /// Tokens issued before sessions are enabled are rejected.
+    pub fn sessions(&self, enabled: bool) {
+        Ctx::set_sessions(enabled);
+    }
+
+    /// Limit failed `signIn` attempts per account and per IP address. Locked
+    /// accounts are rejected with 423 and locked IP addresses with 429.
+    pub fn sign_in_lockout(&self, lockout: SignInLockout) {
+        Ctx::set_sign_in_lockout(lockout);
+    }
+
+    /// Verify the CAPTCHA tokens required by `SignInLockout::captcha_after`.
+    pub fn captcha_verifier<V>(&self, verifier: V) where V: CaptchaVerifier + 'static {
+        Ctx::set_captcha_verifier(verifier);
+    }
+
+    /// Enable password-less sign in with magic links for the identity models.
+    /// `requestMagicLink` issues a single-use token and hands the link to
+    /// `sender` for delivery, `verifyMagicLink` uses the token and responds
+    /// like `signIn`.
+    pub fn magic_links<S>(&self, config: MagicLinks, sender: S) where S: MagicLinkSender + 'static {
+        Ctx::set_magic_links(config, sender);
+    }
+
+    /// Verify the proofs of `provider` credentials linked with the `link`
+    /// action of a `@@credential` model, e.g. `google` ID tokens.
+    pub fn credential_verifier<V>(&self, provider: &str, verifier: V) where V: CredentialVerifier + 'static {
+        Ctx::insert_credential_verifier(provider, verifier);
+    }
+
+    /// The secret URLs are signed with by `sign_url` and `$signedUrl`.
+    pub fn url_signing_secret(&self, secret: &str) {
+        Ctx::set_url_signing_secret(secret);
+    }
+
+    /// Enable the `/_maintenance` endpoint, which turns the maintenance mode
+    /// on and off for requests with this secret in `X-Teo-Maintenance-Secret`.
+    /// While it's on, writes are rejected with 503 and `Retry-After`. On Unix,
+    /// `SIGUSR1` toggles it too.
+    pub fn maintenance_secret(&self, secret: &str) {
+        Ctx::set_maintenance_secret(secret);
+    }
+
+    /// Mark a custom handler which only reads, e.g. `"Report.stats"`, so that
+    /// it stays available while the maintenance mode allows reads. Handlers
+    /// declared with `GET` and the read actions of models are reads already.
+    pub fn read_handler(&self, action: &str) {
+        Ctx::insert_read_handler(action);
+    }
+
+    /// Derive the table and column names which aren't set in the schema with
+    /// a convention, e.g. `NamingConvention::SnakeCase` stores `BlogPost` as
+    /// `blog_post`. When the convention changes, the next migration renames
+    /// the existing tables and columns.
+    pub fn naming_convention(&self, convention: NamingConvention) {
+        Ctx::naming_mut().convention = convention;
+    }
+
+    /// Prefix the table names which aren't set in the schema, e.g. `"app_"`.
+    pub fn table_prefix(&self, prefix: &str) {
+        Ctx::naming_mut().table_prefix = Some(prefix.to_owned());
+    }
+
+    /// Derive the table names which aren't set in the schema from the model
+    /// path. This replaces the naming convention for tables, the table prefix
+    /// still applies.
+    pub fn table_name_hook<F>(&self, f: F) where F: Fn(&Vec<&str>) -> String + Send + Sync + 'static {
+        Ctx::naming_mut().table_name_hook = Some(Arc::new(f));
+    }
+
+    /// Derive the column names which aren't set in the schema from the model
+    /// path and the field name. This replaces the naming convention for
+    /// columns.
+    pub fn column_name_hook<F>(&self, f: F) where F: Fn(&Vec<&str>, &str) -> String + Send + Sync + 'static {
+        Ctx::naming_mut().column_name_hook = Some(Arc::new(f));
+    }
+
+    /// Populate `field` of the existing records of the model at `model`, e.g.
+    /// `"blog.Post"`, after migration, for example to fill a newly added
+    /// `slug` from `title`. Records whose field is null are loaded in batches
+    /// of `batch_size` and saved with the value returned by `f`; returning
+    /// null leaves a record untouched.
+    pub fn backfill<F>(&self, model: &str, field: &str, batch_size: Option<usize>, f: F) where F: BackfillCallback + 'static {
+        Ctx::push_backfill(Backfill {
+            model: model.split('.').map(|s| s.to_owned()).collect(),
+            field: field.to_owned(),
+            batch_size: batch_size.unwrap_or(DEFAULT_BACKFILL_BATCH_SIZE),
+            callback: Arc::new(f),
+        });
+    }
+
+    /// Register a secret provider. Connector urls can reference its secrets
+    /// like `${name:key}`, e.g. `"postgres://app:${vault:db-password}@db/app"`.
+    /// `env` and `file` providers are builtin.
+    pub fn secret_provider<P>(&self, name: &str, provider: P) where P: SecretProvider + 'static {
+        Ctx::insert_secret_provider(name, provider);
+    }
+
+    /// Register a connector builder for connector urls with `scheme`, e.g.
+    /// `"dynamodb"` for `url "dynamodb://local"`. This lets connectors
+    /// implemented out of tree be used like the builtin ones. `dynamodb` is
+    /// builtin with the `dynamodb` feature.
+    pub fn connector<B>(&self, scheme: &str, builder: B) where B: ConnectorBuilder + 'static {
+        Ctx::insert_connector_builder(scheme, builder);
+    }
+
+    /// Register the codec of a custom scalar type. Fields declared with
+    /// `@scalar(name)` are parsed and serialized by it.
+    pub fn scalar<C>(&self, name: &str, codec: C) where C: ScalarCodec + 'static {
+        Ctx::insert_scalar(name, codec);
+    }
+
+    /// Mirror the models marked with `@@searchIndex` into the OpenSearch or
+    /// Elasticsearch server at `url`, e.g. `"http://localhost:9200"`.
+    pub fn search_engine(&self, url: &str) {
+        Ctx::set_search_engine(url);
+    }
+
+    /// Register an event sink. Models marked with `@@publish(sink)` publish
+    /// their change events through it.
+    pub fn event_sink<S>(&self, name: &str, sink: S) where S: EventSink + 'static {
+        Ctx::insert_event_sink(name, sink);
+    }
+
+    /// Register an event source for consumers.
+    pub fn event_source<S>(&self, name: &str, source: S) where S: EventSource + 'static {
+        Ctx::insert_event_source(name, source);
+    }
+
+    /// Route the messages of `topic` from the event source `source` into the
+    /// handler at `handler`, e.g. `"orders.OrderEvents.created"`. Messages are
+    /// JSON validated against the handler input and pass through the
+    /// middleware stack like HTTP requests.
+    pub fn consumer(&self, source: &str, topic: &str, handler: &str) {
+        Ctx::push_consumer(Consumer {
+            source: source.to_owned(),
+            topic: topic.to_owned(),
+            handler: handler.split('.').map(|s| s.to_owned()).collect(),
+        });
+    }
+
+    /// Define a pipeline item in the main namespace. The item can be referenced
+    /// from the schema by name like builtin ones, e.g. `$slugify`.
+    pub fn pipeline_item<T>(&self, name: &str, call: T) where T: item::Call + 'static {
+        Ctx::main_namespace_mut().define_pipeline_item(name, call);
+    }
+
+    /// Define a pipeline item backed by a function exported from a WASM module.
+    /// The pipeline value is passed to the function as JSON and replaced with
+    /// the returned JSON.
+    #[cfg(feature = "wasm")]
+    pub fn wasm_pipeline_item(&self, name: &str, path: impl AsRef<std::path::Path>, export: &str, limits: WasmLimits) -> Result<()> {
+        let plugin = WasmPlugin::load(path, limits)?;
+        let export = export.to_owned();
+        define_plugin_pipeline_item(Ctx::main_namespace_mut(), name, Arc::new(move |input: &serde_json::Value| plugin.call(&export, input)));
+        Ok(())
+    }
+
+    /// Define a pipeline item backed by a function of a JavaScript file. The
+    /// pipeline value is passed to the function and replaced with its return
+    /// value.
+    #[cfg(feature = "js")]
+    pub fn js_pipeline_item(&self, name: &str, path: impl AsRef<std::path::Path>, function: &str) -> Result<()> {
+        let script = JsScript::load(path)?;
+        let function = function.to_owned();
+        define_plugin_pipeline_item(Ctx::main_namespace_mut(), name, Arc::new(move |input: &serde_json::Value| script.call(&function, input)));
+        Ok(())
+    }
+
     pub fn main_namespace(&self) -> &'static Namespace {
         Ctx::main_namespace()
     }
@@ -82,7 +419,12 @@ impl App {
     }
 
     pub async fn prepare_for_run(&self) -> Result<()> {
-        load_schema(Ctx::main_namespace_mut(), Ctx::schema(), Ctx::cli().command.ignores_loading()).await
+        if Ctx::cli().command.ignores_schema() {
+            return Ok(());
+        }
+        load_schema(Ctx::main_namespace_mut(), Ctx::schema(), Ctx::cli().command.ignores_loading()).await?;
+        apply_naming(Ctx::main_namespace_mut(), Ctx::naming());
+        Ok(())
     }
 
     pub async fn run_without_prepare(&self) -> Result<()> {
@@ -1,7 +1,8 @@
 use educe::Educe;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use maplit::btreemap;
 use once_cell::sync::OnceCell;
 use teo_parser::ast::schema::Schema;
@@ -9,9 +10,27 @@ use teo_result::Result;
 use teo_runtime::connection;
 use teo_runtime::namespace::Namespace;
 use crate::app::callbacks::callback::AsyncCallback;
+use crate::app::naming::Naming;
+use crate::app::database::connector::{builtin_connector_builders, ConnectorBuilder};
+use crate::app::scalar::{builtin_scalars, ScalarCodec};
+use crate::app::secrets::{builtin_secret_providers, SecretProvider};
 use crate::cli::command::CLI;
+use crate::events::consumer::{Consumer, EventSource};
+use crate::events::EventSink;
+use crate::migrate::backfill::Backfill;
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
+use crate::server::body_limit::BodyLimits;
+use crate::server::client_ip::Cidr;
+use crate::server::envelope::Envelopes;
+use crate::server::error::ErrorFormat;
+use crate::server::credentials::CredentialVerifier;
+use crate::server::idempotency::DEFAULT_IDEMPOTENCY_WINDOW;
+use crate::server::i18n::MessageCatalogs;
+use crate::server::lockout::{CaptchaVerifier, SignInLockout};
+use crate::server::magic_link::{MagicLinks, MagicLinkSender};
+use crate::server::signature::{MemoryNonceStore, NonceStore};
+use crate::utils::named_queries::NamedQuery;
 
 
 #[derive(Educe)]
@@ -40,6 +59,49 @@ pub struct Ctx {
     pub(crate) programs: BTreeMap<String, Program>,
     #[educe(Debug(ignore))]
     pub(crate) conn_ctx: Option<connection::Ctx>,
+    pub(crate) message_catalogs: Option<MessageCatalogs>,
+    pub(crate) error_format: ErrorFormat,
+    pub(crate) connect_retries: u32,
+    pub(crate) body_limits: BodyLimits,
+    pub(crate) idempotency_window: Duration,
+    pub(crate) json_rpc: bool,
+    pub(crate) query_tags: bool,
+    pub(crate) trusted_proxies: Vec<Cidr>,
+    #[educe(Debug(ignore))]
+    pub(crate) signing_keys: BTreeMap<String, String>,
+    pub(crate) signature_required: bool,
+    #[educe(Debug(ignore))]
+    pub(crate) nonce_store: Arc<dyn NonceStore>,
+    pub(crate) impersonation_role: Option<String>,
+    pub(crate) sessions: bool,
+    pub(crate) sign_in_lockout: Option<SignInLockout>,
+    #[educe(Debug(ignore))]
+    pub(crate) captcha_verifier: Option<Arc<dyn CaptchaVerifier>>,
+    #[educe(Debug(ignore))]
+    pub(crate) url_signing_secret: Option<String>,
+    #[educe(Debug(ignore))]
+    pub(crate) magic_links: Option<(MagicLinks, Arc<dyn MagicLinkSender>)>,
+    #[educe(Debug(ignore))]
+    pub(crate) credential_verifiers: BTreeMap<String, Arc<dyn CredentialVerifier>>,
+    #[educe(Debug(ignore))]
+    pub(crate) maintenance_secret: Option<String>,
+    pub(crate) read_handlers: BTreeSet<String>,
+    #[educe(Debug(ignore))]
+    pub(crate) secret_providers: BTreeMap<String, Arc<dyn SecretProvider>>,
+    #[educe(Debug(ignore))]
+    pub(crate) connector_builders: BTreeMap<String, Arc<dyn ConnectorBuilder>>,
+    #[educe(Debug(ignore))]
+    pub(crate) scalars: BTreeMap<String, Arc<dyn ScalarCodec>>,
+    pub(crate) search_engine: Option<String>,
+    #[educe(Debug(ignore))]
+    pub(crate) event_sinks: BTreeMap<String, Arc<dyn EventSink>>,
+    #[educe(Debug(ignore))]
+    pub(crate) event_sources: BTreeMap<String, Arc<dyn EventSource>>,
+    pub(crate) consumers: Vec<Consumer>,
+    pub(crate) naming: Naming,
+    pub(crate) backfills: Vec<Backfill>,
+    pub(crate) named_queries: Vec<NamedQuery>,
+    pub(crate) envelopes: Envelopes,
 }
 
 impl Ctx {
@@ -56,6 +118,37 @@ impl Ctx {
             setup: None,
             programs: btreemap!{},
             conn_ctx: None,
+            message_catalogs: None,
+            error_format: ErrorFormat::default(),
+            connect_retries: 0,
+            body_limits: BodyLimits::default(),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            json_rpc: false,
+            query_tags: false,
+            trusted_proxies: vec![],
+            signing_keys: BTreeMap::new(),
+            signature_required: false,
+            nonce_store: Arc::new(MemoryNonceStore),
+            impersonation_role: None,
+            sessions: false,
+            sign_in_lockout: None,
+            captcha_verifier: None,
+            url_signing_secret: None,
+            magic_links: None,
+            credential_verifiers: BTreeMap::new(),
+            maintenance_secret: None,
+            read_handlers: BTreeSet::new(),
+            secret_providers: builtin_secret_providers(),
+            connector_builders: builtin_connector_builders(),
+            scalars: builtin_scalars(),
+            search_engine: None,
+            event_sinks: BTreeMap::new(),
+            event_sources: BTreeMap::new(),
+            consumers: vec![],
+            naming: Naming::default(),
+            backfills: vec![],
+            named_queries: vec![],
+            envelopes: Envelopes::default(),
         }
     }
 
@@ -150,6 +243,234 @@ impl Ctx {
         Ctx::get().conn_ctx.as_ref().unwrap()
     }
 
+    pub fn message_catalogs() -> Option<&'static MessageCatalogs> {
+        Ctx::get().message_catalogs.as_ref()
+    }
+
+    pub fn set_message_catalogs(catalogs: MessageCatalogs) {
+        Ctx::get_mut().message_catalogs = Some(catalogs);
+    }
+
+    pub fn error_format() -> ErrorFormat {
+        Ctx::get().error_format
+    }
+
+    pub fn set_error_format(format: ErrorFormat) {
+        Ctx::get_mut().error_format = format;
+    }
+
+    pub fn connect_retries() -> u32 {
+        Ctx::get().connect_retries
+    }
+
+    pub fn set_connect_retries(retries: u32) {
+        Ctx::get_mut().connect_retries = retries;
+    }
+
+    pub fn body_limits() -> &'static BodyLimits {
+        &Ctx::get().body_limits
+    }
+
+    pub fn body_limits_mut() -> &'static mut BodyLimits {
+        &mut Ctx::get_mut().body_limits
+    }
+
+    pub fn envelopes() -> &'static Envelopes {
+        &Ctx::get().envelopes
+    }
+
+    pub fn envelopes_mut() -> &'static mut Envelopes {
+        &mut Ctx::get_mut().envelopes
+    }
+
+    pub fn idempotency_window() -> Duration {
+        Ctx::get().idempotency_window
+    }
+
+    pub fn set_idempotency_window(window: Duration) {
+        Ctx::get_mut().idempotency_window = window;
+    }
+
+    pub fn json_rpc() -> bool {
+        Ctx::get().json_rpc
+    }
+
+    pub fn set_json_rpc(enabled: bool) {
+        Ctx::get_mut().json_rpc = enabled;
+    }
+
+    pub fn query_tags() -> bool {
+        Ctx::get().query_tags
+    }
+
+    pub fn set_query_tags(enabled: bool) {
+        Ctx::get_mut().query_tags = enabled;
+    }
+
+    pub(crate) fn trusted_proxies() -> &'static Vec<Cidr> {
+        &Ctx::get().trusted_proxies
+    }
+
+    pub(crate) fn set_trusted_proxies(proxies: Vec<Cidr>) {
+        Ctx::get_mut().trusted_proxies = proxies;
+    }
+
+    pub fn signing_keys() -> &'static BTreeMap<String, String> {
+        &Ctx::get().signing_keys
+    }
+
+    pub fn insert_signing_key(id: &str, secret: &str) {
+        Ctx::get_mut().signing_keys.insert(id.to_owned(), secret.to_owned());
+    }
+
+    pub fn signature_required() -> bool {
+        Ctx::get().signature_required
+    }
+
+    pub fn set_signature_required(required: bool) {
+        Ctx::get_mut().signature_required = required;
+    }
+
+    pub(crate) fn nonce_store() -> &'static Arc<dyn NonceStore> {
+        &Ctx::get().nonce_store
+    }
+
+    pub fn set_nonce_store<S>(store: S) where S: NonceStore + 'static {
+        Ctx::get_mut().nonce_store = Arc::new(store);
+    }
+
+    pub fn impersonation_role() -> Option<&'static str> {
+        Ctx::get().impersonation_role.as_deref()
+    }
+
+    pub fn set_impersonation_role(role: &str) {
+        Ctx::get_mut().impersonation_role = Some(role.to_owned());
+    }
+
+    pub fn sessions() -> bool {
+        Ctx::get().sessions
+    }
+
+    pub fn set_sessions(enabled: bool) {
+        Ctx::get_mut().sessions = enabled;
+    }
+
+    pub fn sign_in_lockout() -> Option<&'static SignInLockout> {
+        Ctx::get().sign_in_lockout.as_ref()
+    }
+
+    pub fn set_sign_in_lockout(lockout: SignInLockout) {
+        Ctx::get_mut().sign_in_lockout = Some(lockout);
+    }
+
+    pub fn captcha_verifier() -> Option<&'static Arc<dyn CaptchaVerifier>> {
+        Ctx::get().captcha_verifier.as_ref()
+    }
+
+    pub fn set_captcha_verifier<V>(verifier: V) where V: CaptchaVerifier + 'static {
+        Ctx::get_mut().captcha_verifier = Some(Arc::new(verifier));
+    }
+
+    pub fn url_signing_secret() -> Option<&'static str> {
+        Ctx::get().url_signing_secret.as_deref()
+    }
+
+    pub fn set_url_signing_secret(secret: &str) {
+        Ctx::get_mut().url_signing_secret = Some(secret.to_owned());
+    }
+
+    pub fn magic_links() -> Option<&'static (MagicLinks, Arc<dyn MagicLinkSender>)> {
+        Ctx::get().magic_links.as_ref()
+    }
+
+    pub fn set_magic_links<S>(config: MagicLinks, sender: S) where S: MagicLinkSender + 'static {
+        Ctx::get_mut().magic_links = Some((config, Arc::new(sender)));
+    }
+
+    pub fn credential_verifier(provider: &str) -> Option<&'static Arc<dyn CredentialVerifier>> {
+        Ctx::get().credential_verifiers.get(provider)
+    }
+
+    pub fn insert_credential_verifier<V>(provider: &str, verifier: V) where V: CredentialVerifier + 'static {
+        Ctx::get_mut().credential_verifiers.insert(provider.to_owned(), Arc::new(verifier));
+    }
+
+    pub fn maintenance_secret() -> Option<&'static str> {
+        Ctx::get().maintenance_secret.as_deref()
+    }
+
+    pub fn set_maintenance_secret(secret: &str) {
+        Ctx::get_mut().maintenance_secret = Some(secret.to_owned());
+    }
+
+    pub fn read_handlers() -> &'static BTreeSet<String> {
+        &Ctx::get().read_handlers
+    }
+
+    pub fn insert_read_handler(action: &str) {
+        Ctx::get_mut().read_handlers.insert(action.to_owned());
+    }
+
+    pub fn insert_secret_provider<P>(name: &str, provider: P) where P: SecretProvider + 'static {
+        Ctx::get_mut().secret_providers.insert(name.to_owned(), Arc::new(provider));
+    }
+
+    pub fn insert_connector_builder<B>(scheme: &str, builder: B) where B: ConnectorBuilder + 'static {
+        Ctx::get_mut().connector_builders.insert(scheme.to_owned(), Arc::new(builder));
+    }
+
+    pub fn scalar(name: &str) -> Option<&'static Arc<dyn ScalarCodec>> {
+        Ctx::get().scalars.get(name)
+    }
+
+    pub fn insert_scalar<C>(name: &str, codec: C) where C: ScalarCodec + 'static {
+        Ctx::get_mut().scalars.insert(name.to_owned(), Arc::new(codec));
+    }
+
+    pub fn search_engine() -> Option<&'static str> {
+        Ctx::get().search_engine.as_deref()
+    }
+
+    pub fn set_search_engine(url: &str) {
+        Ctx::get_mut().search_engine = Some(url.trim_end_matches('/').to_owned());
+    }
+
+    pub fn insert_event_sink<S>(name: &str, sink: S) where S: EventSink + 'static {
+        Ctx::get_mut().event_sinks.insert(name.to_owned(), Arc::new(sink));
+    }
+
+    pub fn insert_event_source<S>(name: &str, source: S) where S: EventSource + 'static {
+        Ctx::get_mut().event_sources.insert(name.to_owned(), Arc::new(source));
+    }
+
+    pub(crate) fn push_consumer(consumer: Consumer) {
+        Ctx::get_mut().consumers.push(consumer);
+    }
+
+    pub fn naming() -> &'static Naming {
+        &Ctx::get().naming
+    }
+
+    pub fn naming_mut() -> &'static mut Naming {
+        &mut Ctx::get_mut().naming
+    }
+
+    pub(crate) fn backfills() -> &'static Vec<Backfill> {
+        &Ctx::get().backfills
+    }
+
+    pub(crate) fn push_backfill(backfill: Backfill) {
+        Ctx::get_mut().backfills.push(backfill);
+    }
+
+    pub(crate) fn named_queries() -> &'static Vec<NamedQuery> {
+        &Ctx::get().named_queries
+    }
+
+    pub(crate) fn set_named_queries(queries: Vec<NamedQuery>) {
+        Ctx::get_mut().named_queries = queries;
+    }
+
     pub fn setup() -> Option<&'static Arc<dyn AsyncCallback>> {
         Ctx::get().setup.as_ref()
     }
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use teo_result::Result;
+use teo_runtime::connection::connection::Connection;
+
+/// Builds connections for connector urls of one scheme, e.g. `dynamodb`.
+///
+/// This is the extension point for connectors implemented out of tree. The
+/// returned connection implements the runtime `Connection` and `Transaction`
+/// traits, which cover saving, finding and aggregating objects, the migration
+/// hook and the mapping of field types.
+pub trait ConnectorBuilder: Send + Sync {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<Arc<dyn Connection>>>;
+}
+
+impl<F, Fut> ConnectorBuilder for F where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Arc<dyn Connection>>> + Send + 'static {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<Arc<dyn Connection>>> {
+        Box::pin(self(url))
+    }
+}
+
+/// The scheme of a connector url, e.g. `dynamodb` of `dynamodb://local`.
+pub(crate) fn url_scheme(url: &str) -> Option<&str> {
+    url.split_once("://").map(|(scheme, _)| scheme)
+}
+
+/// The builtin builders. `memory` connects the memory connector, `dynamodb`
+/// connects DynamoDB when the `dynamodb` feature is enabled.
+pub(crate) fn builtin_connector_builders() -> BTreeMap<String, Arc<dyn ConnectorBuilder>> {
+    let mut builders: BTreeMap<String, Arc<dyn ConnectorBuilder>> = BTreeMap::new();
+    builders.insert("memory".to_owned(), Arc::new(crate::app::database::memory::connect));
+    #[cfg(feature = "dynamodb")]
+    builders.insert("dynamodb".to_owned(), Arc::new(crate::app::database::dynamodb::connect));
+    builders
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use aws_sdk_dynamodb::Client;
+use aws_sdk_dynamodb::types::{AttributeDefinition, BillingMode, CreateGlobalSecondaryIndexAction, GlobalSecondaryIndex, GlobalSecondaryIndexUpdate, IndexStatus, KeySchemaElement, KeyType, Projection, ProjectionType, TableStatus};
+use teo_result::{Error, Result};
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::Model;
+use tokio::time::sleep;
+use crate::app::database::dynamodb::query::{index_keys, table_key, Key};
+use crate::app::database::dynamodb::value::key_attribute_type;
+use crate::message::info_message;
+
+/// Provision the tables of the models: a table with on-demand billing for
+/// each model, with a global secondary index for each of its other indexes.
+/// Indexes added to the schema are added to existing tables, one at a time
+/// as DynamoDB requires. Indexes and tables aren't dropped, except that
+/// `reset_database` deletes and recreates the tables.
+pub(super) async fn migrate_tables(client: &Client, models: Vec<&Model>, dry_run: bool, reset_database: bool, silent: bool) -> Result<()> {
+    let mut existing = existing_tables(client).await?;
+    for model in models {
+        let table = model.table_name.as_str();
+        let key = table_key(model)?;
+        let indexes = index_keys(model)?;
+        if reset_database && existing.contains(table) {
+            if !dry_run {
+                client.delete_table().table_name(table).send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+                wait_until_deleted(client, table).await?;
+            }
+            existing.remove(table);
+        }
+        if !existing.contains(table) {
+            if !silent {
+                info_message(format!("create DynamoDB table `{}`", table));
+            }
+            if !dry_run {
+                let mut request = client.create_table()
+                    .table_name(table)
+                    .billing_mode(BillingMode::PayPerRequest)
+                    .set_key_schema(Some(key_schema(&key)?))
+                    .set_attribute_definitions(Some(attribute_definitions(model, [&key].into_iter().chain(indexes.iter()))?));
+                for index in &indexes {
+                    request = request.global_secondary_indexes(GlobalSecondaryIndex::builder()
+                        .index_name(index.index.clone().unwrap())
+                        .set_key_schema(Some(key_schema(index)?))
+                        .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                        .build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?);
+                }
+                request.send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+                wait_until_active(client, table).await?;
+            }
+            continue;
+        }
+        let description = client.describe_table().table_name(table).send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+        let created: HashSet<String> = description.table.and_then(|t| t.global_secondary_indexes).unwrap_or_default()
+            .into_iter().filter_map(|index| index.index_name).collect();
+        for index in indexes.iter().filter(|index| !created.contains(index.index.as_ref().unwrap())) {
+            let name = index.index.clone().unwrap();
+            if !silent {
+                info_message(format!("create DynamoDB index `{}` of `{}`", name, table));
+            }
+            if dry_run { continue }
+            let action = CreateGlobalSecondaryIndexAction::builder()
+                .index_name(name)
+                .set_key_schema(Some(key_schema(index)?))
+                .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                .build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+            client.update_table()
+                .table_name(table)
+                .set_attribute_definitions(Some(attribute_definitions(model, [&key, index].into_iter())?))
+                .global_secondary_index_updates(GlobalSecondaryIndexUpdate::builder().create(action).build())
+                .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+            wait_until_active(client, table).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn existing_tables(client: &Client) -> Result<HashSet<String>> {
+    let mut tables = HashSet::new();
+    let mut start = None;
+    loop {
+        let response = client.list_tables().set_exclusive_start_table_name(start).send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+        tables.extend(response.table_names.unwrap_or_default());
+        start = response.last_evaluated_table_name;
+        if start.is_none() { break }
+    }
+    Ok(tables)
+}
+
+fn key_schema(key: &Key) -> Result<Vec<KeySchemaElement>> {
+    let mut schema = vec![KeySchemaElement::builder().attribute_name(&key.partition).key_type(KeyType::Hash).build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?];
+    if let Some(sort) = &key.sort {
+        schema.push(KeySchemaElement::builder().attribute_name(sort).key_type(KeyType::Range).build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?);
+    }
+    Ok(schema)
+}
+
+/// The definitions of the key attributes of the keys, each once.
+fn attribute_definitions<'a>(model: &Model, keys: impl Iterator<Item = &'a Key>) -> Result<Vec<AttributeDefinition>> {
+    let mut columns = vec![];
+    for key in keys {
+        for column in [Some(&key.partition), key.sort.as_ref()].into_iter().flatten() {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+    }
+    columns.iter().map(|column| {
+        let field = model.fields.values().find(|f| &f.column_name == column).unwrap();
+        AttributeDefinition::builder()
+            .attribute_name(column)
+            .attribute_type(key_attribute_type(field.r#type())?)
+            .build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))
+    }).collect()
+}
+
+/// Wait until a table and its indexes are active, which they must be to be
+/// read, written or updated again.
+async fn wait_until_active(client: &Client, table: &str) -> Result<()> {
+    loop {
+        let description = client.describe_table().table_name(table).send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+        if let Some(table) = description.table {
+            let indexes_active = table.global_secondary_indexes.unwrap_or_default().iter().all(|index| index.index_status == Some(IndexStatus::Active));
+            if table.table_status == Some(TableStatus::Active) && indexes_active {
+                return Ok(());
+            }
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn wait_until_deleted(client: &Client, table: &str) -> Result<()> {
+    while existing_tables(client).await?.contains(table) {
+        sleep(Duration::from_secs(1)).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! The DynamoDB connector, behind the `dynamodb` feature.
+//!
+//! It's selected by a connector url of the `dynamodb` scheme, with the
+//! region as host and an optional endpoint for DynamoDB Local:
+//!
+//! ```teo
+//! connector {
+//!   provider: .sqlite
+//!   url: "dynamodb://us-east-1?endpoint=http://localhost:8000"
+//! }
+//! ```
+//!
+//! The schema requires a provider, which is not read: the connector is the
+//! one of the url scheme. Like the memory connector, it's a connector of a
+//! builder, so the features which run SQL or MongoDB commands, like count
+//! estimates, query tags, sessions and the outbox, are off for it.
+//! Credentials are read from the environment like with the AWS CLI.
+//!
+//! A model is stored in a table named after it. Its primary key of one or
+//! two fields is the partition key and sort key of the table, and each of
+//! its other indexes of one or two fields is a global secondary index.
+//! `findUnique` gets an item by its key, `findMany` queries the table or the
+//! index whose partition key is compared for equality, and `cursor` is the
+//! pagination token. Updates are conditional on the `@version` field of the
+//! model if it has one. Transactions are committed with `TransactWriteItems`.
+//!
+//! Models with relations or `@@expires`, and keys which don't fit a table
+//! or an index, are rejected when the connector connects.
+
+mod migrate;
+mod query;
+mod transaction;
+mod value;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_dynamodb::Client;
+use teo_result::{Error, Result};
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::namespace::Namespace;
+use url::Url;
+use crate::app::database::dynamodb::query::{index_keys, table_key};
+use crate::app::database::dynamodb::transaction::DynamoDBTransaction;
+use crate::stdlib::decorators::expires::model_expiry;
+
+#[derive(Debug, Clone)]
+pub struct DynamoDBConnection {
+    client: Client,
+}
+
+impl DynamoDBConnection {
+
+    /// Connect to DynamoDB with a `dynamodb://<region>?endpoint=<url>` url.
+    pub async fn new(url: &str) -> Result<Self> {
+        let url = Url::parse(url).map_err(|_| Error::new(format!("invalid DynamoDB url \"{}\"", url)))?;
+        let region = url.host_str().filter(|host| !host.is_empty()).ok_or_else(|| Error::new("DynamoDB url has no region"))?;
+        let config = aws_config::defaults(BehaviorVersion::latest()).region(Region::new(region.to_owned())).load().await;
+        let mut builder = aws_sdk_dynamodb::config::Builder::from(&config);
+        if let Some((_, endpoint)) = url.query_pairs().find(|(key, _)| key == "endpoint") {
+            builder = builder.endpoint_url(endpoint.to_string());
+        }
+        let client = Client::from_conf(builder.build());
+        client.list_tables().limit(1).send().await.map_err(|err| Error::new(format!("cannot connect to DynamoDB: {}", err)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Connection for DynamoDBConnection {
+
+    async fn transaction(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(DynamoDBTransaction::new(self.client.clone(), true)))
+    }
+
+    async fn no_transaction(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(DynamoDBTransaction::new(self.client.clone(), false)))
+    }
+}
+
+/// Connect a `dynamodb://` url, the builtin connector builder of the scheme.
+pub(crate) async fn connect(url: String) -> Result<Arc<dyn Connection>> {
+    Ok(Arc::new(DynamoDBConnection::new(&url).await?))
+}
+
+/// Check that the models of a namespace connected to DynamoDB, and of its
+/// namespaces without connectors of their own, are supported.
+pub(crate) fn check_models(namespace: &Namespace) -> Result<()> {
+    for model in namespace.models.values() {
+        let path = model.path().join(".");
+        table_key(model)?;
+        index_keys(model)?;
+        if !model.relations.is_empty() {
+            Err(Error::new(format!("DynamoDB: `{}` has relations, which are not supported", path)))?
+        }
+        if model_expiry(model).is_some() {
+            Err(Error::new(format!("DynamoDB: `{}` has `@@expires`, which is not supported", path)))?
+        }
+    }
+    for child in namespace.namespaces.values().filter(|child| child.connector.is_none()) {
+        check_models(child)?;
+    }
+    Ok(())
+}
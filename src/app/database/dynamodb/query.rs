@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use aws_sdk_dynamodb::types::AttributeValue;
+use indexmap::IndexMap;
+use teo_result::{Error, Result};
+use teo_runtime::model::index::Type as IndexType;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::database::dynamodb::value::attribute;
+
+/// The placeholders of an expression and the names and values they stand
+/// for.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Expression {
+    pub(super) names: HashMap<String, String>,
+    pub(super) values: HashMap<String, AttributeValue>,
+}
+
+impl Expression {
+
+    pub(super) fn name(&mut self, column: &str) -> String {
+        let placeholder = format!("#n{}", self.names.len());
+        self.names.insert(placeholder.clone(), column.to_owned());
+        placeholder
+    }
+
+    pub(super) fn value(&mut self, value: &Value) -> Result<String> {
+        let placeholder = format!(":v{}", self.values.len());
+        self.values.insert(placeholder.clone(), attribute(value)?);
+        Ok(placeholder)
+    }
+
+    pub(super) fn names(&self) -> Option<HashMap<String, String>> {
+        (!self.names.is_empty()).then(|| self.names.clone())
+    }
+
+    pub(super) fn values(&self) -> Option<HashMap<String, AttributeValue>> {
+        (!self.values.is_empty()).then(|| self.values.clone())
+    }
+}
+
+/// A key of a table or of a global secondary index: the partition key
+/// field, and the sort key field if any.
+#[derive(Debug, Clone)]
+pub(super) struct Key {
+    /// The name of the global secondary index, `None` for the table key.
+    pub(super) index: Option<String>,
+    pub(super) partition: String,
+    pub(super) sort: Option<String>,
+}
+
+/// The table key of a model, from its primary index of one or two fields.
+pub(super) fn table_key(model: &Model) -> Result<Key> {
+    let index = model.primary_index().ok_or_else(|| Error::new(format!("DynamoDB: `{}` has no primary key", model.path().join("."))))?;
+    key(model, None, index.items.iter().map(|item| item.field.as_str()).collect())
+}
+
+/// The keys of the global secondary indexes of a model, one for each of its
+/// other indexes of one or two fields.
+pub(super) fn index_keys(model: &Model) -> Result<Vec<Key>> {
+    model.indexes.iter()
+        .filter(|(_, index)| index.r#type() != IndexType::Primary)
+        .map(|(name, index)| key(model, Some(name.clone()), index.items.iter().map(|item| item.field.as_str()).collect()))
+        .collect()
+}
+
+fn key(model: &Model, index: Option<String>, fields: Vec<&str>) -> Result<Key> {
+    let column = |field: &str| model.field(field).map(|f| f.column_name.clone()).ok_or_else(|| Error::new(format!("DynamoDB: field `{}` is not found", field)));
+    match fields.as_slice() {
+        [partition] => Ok(Key { index, partition: column(partition)?, sort: None }),
+        [partition, sort] => Ok(Key { index, partition: column(partition)?, sort: Some(column(sort)?) }),
+        _ => Err(Error::new(format!("DynamoDB: the keys of `{}` must have one or two fields", model.path().join(".")))),
+    }
+}
+
+/// A `Query` request made from a `where` input: the key condition on the
+/// first table or index key whose partition key is compared for equality,
+/// and a filter of the other conditions.
+#[derive(Debug)]
+pub(super) struct Query {
+    pub(super) key: Key,
+    pub(super) key_condition: String,
+    pub(super) filter: Option<String>,
+    pub(super) expression: Expression,
+}
+
+impl Query {
+
+    /// Make the query of a `where` input. Fails if no key can be queried,
+    /// since a full table scan isn't done behind the back of a request.
+    pub(super) fn new(model: &Model, r#where: &Value) -> Result<Self> {
+        let conditions = r#where.as_dictionary().cloned().unwrap_or_default();
+        let conditions = columns(model, conditions)?;
+        let mut keys = vec![table_key(model)?];
+        keys.extend(index_keys(model)?);
+        let key = keys.into_iter().find(|key| conditions.get(&key.partition).and_then(equals).is_some()).ok_or_else(|| {
+            Error::invalid_request_message(format!("DynamoDB: a query of `{}` must compare the partition key of the table or of an index for equality", model.path().join(".")))
+        })?;
+        let mut expression = Expression::default();
+        let mut rest = conditions.clone();
+        let partition = rest.shift_remove(&key.partition).and_then(|c| equals(&c).cloned()).unwrap();
+        let mut key_condition = format!("{} = {}", expression.name(&key.partition), expression.value(&partition)?);
+        if let Some(sort) = &key.sort {
+            if let Some(condition) = rest.get(sort).and_then(|c| sort_condition(sort, c, &mut expression).transpose()) {
+                key_condition = format!("{} AND {}", key_condition, condition?);
+                rest.shift_remove(sort);
+            }
+        }
+        let filter = filter(&rest, &mut expression)?;
+        Ok(Self { key, key_condition, filter, expression })
+    }
+}
+
+/// Key the conditions of fields by column, leaving `AND`, `OR` and `NOT`.
+fn columns(model: &Model, conditions: IndexMap<String, Value>) -> Result<IndexMap<String, Value>> {
+    conditions.into_iter().map(|(key, condition)| match key.as_str() {
+        "AND" | "OR" => Ok((key, Value::Array(condition.as_array().cloned().unwrap_or_default().into_iter()
+            .map(|c| Ok(Value::Dictionary(columns(model, c.as_dictionary().cloned().unwrap_or_default())?)))
+            .collect::<Result<Vec<_>>>()?))),
+        "NOT" => Ok((key, Value::Dictionary(columns(model, condition.as_dictionary().cloned().unwrap_or_default())?))),
+        field => {
+            let field = model.field(field).ok_or_else(|| Error::invalid_request_message(format!("DynamoDB: field `{}` is not found", field)))?;
+            Ok((field.column_name.clone(), condition))
+        }
+    }).collect()
+}
+
+/// The value a condition compares for equality, if it's an equality.
+fn equals(condition: &Value) -> Option<&Value> {
+    match condition {
+        Value::Dictionary(ops) if ops.len() == 1 => ops.get("equals").filter(|v| !v.is_null()),
+        Value::Dictionary(_) | Value::Null => None,
+        value => Some(value),
+    }
+}
+
+/// The key condition of a sort key, or `None` if the condition can't be one.
+fn sort_condition(column: &str, condition: &Value, expression: &mut Expression) -> Result<Option<String>> {
+    if let Some(value) = equals(condition) {
+        return Ok(Some(format!("{} = {}", expression.name(column), expression.value(value)?)));
+    }
+    let Some(ops) = condition.as_dictionary() else { return Ok(None) };
+    let name = expression.name(column);
+    Ok(match (ops.get("gte"), ops.get("lte"), ops.len()) {
+        (Some(low), Some(high), 2) => Some(format!("{} BETWEEN {} AND {}", name, expression.value(low)?, expression.value(high)?)),
+        _ if ops.len() == 1 => {
+            let (op, value) = ops.first().unwrap();
+            match op.as_str() {
+                "gt" => Some(format!("{} > {}", name, expression.value(value)?)),
+                "gte" => Some(format!("{} >= {}", name, expression.value(value)?)),
+                "lt" => Some(format!("{} < {}", name, expression.value(value)?)),
+                "lte" => Some(format!("{} <= {}", name, expression.value(value)?)),
+                "startsWith" => Some(format!("begins_with({}, {})", name, expression.value(value)?)),
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+fn filter(conditions: &IndexMap<String, Value>, expression: &mut Expression) -> Result<Option<String>> {
+    let mut parts = vec![];
+    for (key, condition) in conditions {
+        match key.as_str() {
+            "AND" | "OR" => {
+                let inner = condition.as_array().cloned().unwrap_or_default().iter()
+                    .map(|c| filter(&c.as_dictionary().cloned().unwrap_or_default(), expression))
+                    .collect::<Result<Vec<_>>>()?.into_iter().flatten().map(|c| format!("({})", c)).collect::<Vec<_>>();
+                if !inner.is_empty() {
+                    parts.push(format!("({})", inner.join(if key == "AND" { " AND " } else { " OR " })));
+                }
+            }
+            "NOT" => if let Some(inner) = filter(&condition.as_dictionary().cloned().unwrap_or_default(), expression)? {
+                parts.push(format!("NOT ({})", inner));
+            },
+            column => parts.push(field_filter(column, condition, expression)?),
+        }
+    }
+    Ok((!parts.is_empty()).then(|| parts.join(" AND ")))
+}
+
+fn field_filter(column: &str, condition: &Value, expression: &mut Expression) -> Result<String> {
+    let name = expression.name(column);
+    let ops = match condition {
+        Value::Dictionary(ops) => ops.clone(),
+        value => IndexMap::from([("equals".to_owned(), value.clone())]),
+    };
+    let mut parts = vec![];
+    for (op, value) in &ops {
+        parts.push(match (op.as_str(), value) {
+            ("equals", Value::Null) => format!("(attribute_not_exists({}) OR attribute_type({}, {}))", name, name, expression.value(&Value::String("NULL".to_owned()))?),
+            ("not", Value::Null) => format!("(attribute_exists({}) AND NOT attribute_type({}, {}))", name, name, expression.value(&Value::String("NULL".to_owned()))?),
+            ("equals", value) => format!("{} = {}", name, expression.value(value)?),
+            ("not", value) => format!("{} <> {}", name, expression.value(value)?),
+            ("gt", value) => format!("{} > {}", name, expression.value(value)?),
+            ("gte", value) => format!("{} >= {}", name, expression.value(value)?),
+            ("lt", value) => format!("{} < {}", name, expression.value(value)?),
+            ("lte", value) => format!("{} <= {}", name, expression.value(value)?),
+            ("in" | "notIn", Value::Array(values)) => {
+                let values = values.iter().map(|v| expression.value(v)).collect::<Result<Vec<_>>>()?;
+                let condition = if values.is_empty() { format!("attribute_type({}, {})", name, expression.value(&Value::String("_".to_owned()))?) } else { format!("{} IN ({})", name, values.join(", ")) };
+                if op == "in" { condition } else { format!("NOT ({})", condition) }
+            }
+            ("contains", value) => format!("contains({}, {})", name, expression.value(value)?),
+            ("startsWith", value) => format!("begins_with({}, {})", name, expression.value(value)?),
+            ("has", value) => format!("contains({}, {})", name, expression.value(value)?),
+            (op, _) => Err(Error::invalid_request_message(format!("DynamoDB: `{}` filters are not supported", op)))?,
+        });
+    }
+    Ok(parts.join(" AND "))
+}
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use aws_sdk_dynamodb::Client;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{Delete, Put, Select, TransactWriteItem};
+use key_path::{path, KeyPath};
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::error_ext::unique_value_duplicated;
+use teo_parser::r#type::Type;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::request;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::app::database::dynamodb::migrate::migrate_tables;
+use crate::app::database::dynamodb::query::{table_key, Expression, Key, Query};
+use crate::app::database::dynamodb::value::{attribute, record, value, Item};
+use crate::stdlib::decorators::version::is_version_field;
+
+/// The most writes a `TransactWriteItems` request takes.
+const MAX_TRANSACTION_WRITES: usize = 100;
+
+/// What a write failing its condition means.
+#[derive(Debug, Clone)]
+enum Conflict {
+    /// A record with the key of the created one exists.
+    Duplicated(String),
+    /// The updated record was changed or deleted by another request.
+    Changed(String),
+}
+
+impl Conflict {
+
+    fn error(&self) -> Error {
+        match self {
+            Conflict::Duplicated(field) => unique_value_duplicated(path![], field.clone()),
+            Conflict::Changed(model) => {
+                let mut error = Error::new(format!("DynamoDB: the `{}` record was changed or deleted by another request", model));
+                error.code = 409;
+                error
+            }
+        }
+    }
+}
+
+/// A write of an item: a put, or a delete if `item` is `None`, conditional
+/// on `condition` if it has one.
+#[derive(Debug, Clone)]
+struct Write {
+    table: String,
+    key: Item,
+    item: Option<Item>,
+    condition: Option<(String, Expression)>,
+    conflict: Option<Conflict>,
+    /// Whether the item is created by the transaction writing it.
+    created: bool,
+}
+
+impl Write {
+
+    fn transact_item(&self) -> Result<TransactWriteItem> {
+        let (condition, names, values) = match &self.condition {
+            Some((condition, expression)) => (Some(condition.clone()), expression.names(), expression.values()),
+            None => (None, None, None),
+        };
+        let item = match &self.item {
+            Some(item) => TransactWriteItem::builder().put(Put::builder()
+                .table_name(&self.table)
+                .set_item(Some(item.clone()))
+                .set_condition_expression(condition)
+                .set_expression_attribute_names(names)
+                .set_expression_attribute_values(values)
+                .build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?),
+            None => TransactWriteItem::builder().delete(Delete::builder()
+                .table_name(&self.table)
+                .set_key(Some(self.key.clone()))
+                .set_condition_expression(condition)
+                .set_expression_attribute_names(names)
+                .set_expression_attribute_values(values)
+                .build().map_err(|err| Error::new(format!("DynamoDB: {}", err)))?),
+        };
+        Ok(item.build())
+    }
+}
+
+/// A transaction of the DynamoDB connector.
+///
+/// A transaction buffers its writes and commits them in one
+/// `TransactWriteItems` request, so other requests never see them before
+/// they are committed, and they are all or none written. Conditions are
+/// checked when the transaction is committed. A transaction reads its own
+/// writes of the items it reads, so `findUnique` by key sees the items it
+/// creates, but queries don't. It takes up to 100 writes of distinct items.
+/// Without a transaction, writes go to the table directly.
+#[derive(Debug, Clone)]
+pub(super) struct DynamoDBTransaction {
+    client: Client,
+    writes: Option<Arc<Mutex<Vec<Write>>>>,
+    committed: Arc<AtomicBool>,
+}
+
+impl DynamoDBTransaction {
+
+    pub(super) fn new(client: Client, transaction: bool) -> Self {
+        Self { client, writes: transaction.then(|| Arc::new(Mutex::new(vec![]))), committed: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Write an item, or buffer the write in a transaction. A write of an
+    /// item written already by the transaction replaces the buffered one,
+    /// keeping its condition, which is the one checked against the table.
+    async fn write(&self, write: Write) -> Result<()> {
+        let Some(writes) = &self.writes else {
+            return self.write_through(write).await;
+        };
+        let mut writes = writes.lock().unwrap();
+        match writes.iter().position(|w| w.table == write.table && w.key == write.key) {
+            Some(index) if write.created && writes[index].item.is_some() => {
+                Err(write.conflict.as_ref().map_or_else(|| Error::new("DynamoDB: the item is written already"), Conflict::error))?
+            }
+            Some(index) if writes[index].created && write.item.is_none() => {
+                writes.remove(index);
+            }
+            Some(index) => writes[index].item = write.item,
+            None => {
+                if writes.len() == MAX_TRANSACTION_WRITES {
+                    Err(Error::new(format!("DynamoDB: a transaction takes at most {} writes", MAX_TRANSACTION_WRITES)))?
+                }
+                writes.push(write);
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_through(&self, write: Write) -> Result<()> {
+        let (condition, names, values) = match &write.condition {
+            Some((condition, expression)) => (Some(condition.clone()), expression.names(), expression.values()),
+            None => (None, None, None),
+        };
+        let result = match write.item {
+            Some(item) => self.client.put_item()
+                .table_name(&write.table)
+                .set_item(Some(item))
+                .set_condition_expression(condition)
+                .set_expression_attribute_names(names)
+                .set_expression_attribute_values(values)
+                .send().await.map(|_| ()).map_err(|err| (err.as_service_error().map_or(false, |e| e.is_conditional_check_failed_exception()), err.to_string())),
+            None => self.client.delete_item()
+                .table_name(&write.table)
+                .set_key(Some(write.key))
+                .set_condition_expression(condition)
+                .set_expression_attribute_names(names)
+                .set_expression_attribute_values(values)
+                .send().await.map(|_| ()).map_err(|err| (err.as_service_error().map_or(false, |e| e.is_conditional_check_failed_exception()), err.to_string())),
+        };
+        match (result, &write.conflict) {
+            (Ok(()), _) => Ok(()),
+            (Err((true, _)), Some(conflict)) => Err(conflict.error()),
+            (Err((_, message)), _) => Err(Error::new(format!("DynamoDB: {}", message))),
+        }
+    }
+
+    /// The buffered write of an item, `Some(None)` if it's deleted.
+    fn buffered(&self, table: &str, key: &Item) -> Option<Option<Item>> {
+        let writes = self.writes.as_ref()?.lock().unwrap();
+        writes.iter().find(|w| w.table == table && &w.key == key).map(|w| w.item.clone())
+    }
+
+    /// Items read from the table with the buffered writes of the transaction
+    /// applied.
+    fn with_buffered(&self, model: &Model, items: Vec<Item>) -> Result<Vec<Item>> {
+        if self.writes.is_none() {
+            return Ok(items);
+        }
+        let key = table_key(model)?;
+        Ok(items.into_iter().filter_map(|item| match self.buffered(&model.table_name, &key_attributes(&key, &item)) {
+            Some(buffered) => buffered,
+            None => Some(item),
+        }).collect())
+    }
+
+    /// The items of a `findMany` finder. `take` limits and `cursor` starts
+    /// the query after the record it identifies, like a pagination token.
+    /// `skip` is only accepted as `1` with a cursor, which skips the cursor.
+    async fn items(&self, model: &Model, finder: &Value) -> Result<Vec<Item>> {
+        let query = Query::new(model, arg(finder, "where").unwrap_or(&Value::Null))?;
+        let take = arg(finder, "take").and_then(|t| t.as_int64());
+        let cursor = arg(finder, "cursor");
+        match arg(finder, "skip").and_then(|s| s.as_int64()).unwrap_or(0) {
+            0 => (),
+            1 if cursor.is_some() => (),
+            _ => Err(Error::invalid_request_message("DynamoDB: `skip` is not supported, paginate with `cursor`"))?,
+        }
+        let mut forward = order_forward(model, &query.key, arg(finder, "orderBy"))?;
+        if take.map_or(false, |t| t < 0) {
+            forward = !forward;
+        }
+        let limit = take.map(|t| t.unsigned_abs() as usize);
+        let mut start = match cursor {
+            Some(cursor) => Some(self.start_key(model, &query.key, cursor).await?),
+            None => None,
+        };
+        let mut items = vec![];
+        loop {
+            let response = self.client.query()
+                .table_name(&model.table_name)
+                .set_index_name(query.key.index.clone())
+                .key_condition_expression(&query.key_condition)
+                .set_filter_expression(query.filter.clone())
+                .set_expression_attribute_names(query.expression.names())
+                .set_expression_attribute_values(query.expression.values())
+                .scan_index_forward(forward)
+                .set_limit(limit.filter(|_| query.filter.is_none()).map(|l| (l - items.len()) as i32))
+                .set_exclusive_start_key(start)
+                .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+            items.extend(response.items.unwrap_or_default());
+            start = response.last_evaluated_key;
+            if let Some(limit) = limit {
+                if items.len() >= limit {
+                    items.truncate(limit);
+                    break;
+                }
+            }
+            if start.is_none() {
+                break;
+            }
+        }
+        if take.map_or(false, |t| t < 0) {
+            items.reverse();
+        }
+        self.with_buffered(model, items)
+    }
+
+    /// The exclusive start key of a cursor: the attributes of the table key,
+    /// and of the index key when querying an index, of the cursor's item.
+    async fn start_key(&self, model: &Model, key: &Key, cursor: &Value) -> Result<Item> {
+        let item = self.get_item(model, &key_of(model, cursor)?).await?.ok_or_else(|| Error::invalid_request_message("DynamoDB: the cursor is not found"))?;
+        let table_key = table_key(model)?;
+        let columns = [Some(&table_key.partition), table_key.sort.as_ref(), Some(&key.partition), key.sort.as_ref()];
+        Ok(columns.into_iter().flatten().filter_map(|column| item.get(column).map(|a| (column.clone(), a.clone()))).collect())
+    }
+
+    async fn get_item(&self, model: &Model, key: &Item) -> Result<Option<Item>> {
+        if let Some(buffered) = self.buffered(&model.table_name, key) {
+            return Ok(buffered);
+        }
+        let response = self.client.get_item()
+            .table_name(&model.table_name)
+            .set_key(Some(key.clone()))
+            .consistent_read(true)
+            .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+        Ok(response.item)
+    }
+
+    async fn objects(&self, model: &'static Model, items: Vec<Item>, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>) -> Result<Vec<Object>> {
+        // models with relations are rejected when the connector connects
+        let (select, include) = if ignore_select_and_include { (None, None) } else { (arg(finder, "select"), arg(finder, "include")) };
+        let mut objects = vec![];
+        for item in items {
+            let object = transaction_ctx.new_object(model, action, req_ctx.clone())?;
+            object.set_from_database_result_value(&record(model, &item)?, select, include);
+            objects.push(object);
+        }
+        Ok(objects)
+    }
+
+    /// Put an object. Creating is conditional on the key not being stored.
+    /// Updating is conditional on the key being stored, and on the version
+    /// of the record if the model has a `@version` field: the stored version
+    /// must be the one of the object, which is the value read or the value
+    /// of the update input, and it's increased by one.
+    async fn put_object(&self, object: &Object) -> Result<()> {
+        let model = object.model();
+        let key = table_key(model)?;
+        let mut expression = Expression::default();
+        let partition = expression.name(&key.partition);
+        let (mut condition, conflict) = if object.is_new() {
+            let field = model.primary_index().and_then(|index| index.items.first()).map(|item| item.field.clone()).unwrap_or_default();
+            (format!("attribute_not_exists({})", partition), Conflict::Duplicated(field))
+        } else {
+            if let Some(field) = model.primary_index().into_iter().flat_map(|index| index.items.iter()).find(|item| object.get_previous_value(&item.field).ok() != object.get_value(&item.field).ok()) {
+                Err(Error::invalid_request_message(format!("DynamoDB: the key field `{}` cannot be updated", field.field)))?
+            }
+            (format!("attribute_exists({})", partition), Conflict::Changed(model.path().join(".")))
+        };
+        if !object.is_new() {
+            if let Some(field) = model.fields.values().find(|field| is_version_field(field)) {
+                let version = object.get_value(field.name())?;
+                let next = match &version {
+                    Value::Int(v) => Value::Int(v + 1),
+                    Value::Int64(v) => Value::Int64(v + 1),
+                    _ => Err(Error::invalid_request_message(format!("DynamoDB: the version `{}` is not set", field.name())))?,
+                };
+                condition = format!("{} AND {} = {}", condition, expression.name(&field.column_name), expression.value(&version)?);
+                object.set_value(field.name(), next)?;
+            }
+        }
+        let item = item_of(object)?;
+        self.write(Write {
+            table: model.table_name.clone(),
+            key: key_attributes(&key, &item),
+            item: Some(item),
+            condition: Some((condition, expression)),
+            conflict: Some(conflict),
+            created: object.is_new(),
+        }).await
+    }
+}
+
+#[async_trait]
+impl Transaction for DynamoDBTransaction {
+
+    async fn migrate(&self, models: Vec<&Model>, dry_run: bool, reset_database: bool, silent: bool) -> Result<()> {
+        migrate_tables(&self.client, models, dry_run, reset_database, silent).await
+    }
+
+    async fn purge(&self, models: Vec<&Model>) -> Result<()> {
+        for model in models {
+            let key = table_key(model)?;
+            let mut start = None;
+            loop {
+                let response = self.client.scan()
+                    .table_name(&model.table_name)
+                    .set_exclusive_start_key(start)
+                    .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+                for item in response.items.unwrap_or_default() {
+                    self.client.delete_item()
+                        .table_name(&model.table_name)
+                        .set_key(Some(key_attributes(&key, &item)))
+                        .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+                }
+                start = response.last_evaluated_key;
+                if start.is_none() { break }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a PartiQL statement. Returns the items it reads.
+    async fn query_raw(&self, query: &Value) -> Result<Value> {
+        let statement = query.as_str().ok_or_else(|| Error::new("DynamoDB: a raw query is a PartiQL statement"))?;
+        let response = self.client.execute_statement()
+            .statement(statement)
+            .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+        let items = response.items.unwrap_or_default().iter().map(|item| {
+            Ok(Value::Dictionary(item.iter().map(|(k, v)| Ok((k.clone(), value(v, &Type::Any)?))).collect::<Result<_>>()?))
+        }).collect::<Result<Vec<_>>>()?;
+        Ok(Value::Array(items))
+    }
+
+    async fn save_object(&self, object: &Object) -> Result<()> {
+        self.put_object(object).await
+    }
+
+    async fn delete_object(&self, object: &Object) -> Result<()> {
+        let model = object.model();
+        let key = key_of(model, &object.identifier())?;
+        self.write(Write { table: model.table_name.clone(), key, item: None, condition: None, conflict: None, created: false }).await
+    }
+
+    /// Get the item by its key if the finder has the whole table key, query
+    /// for it otherwise.
+    async fn find_unique(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, _path: KeyPath) -> Result<Option<Object>> {
+        let r#where = arg(finder, "where").cloned().unwrap_or(Value::Null);
+        let item = match key_of(model, &r#where) {
+            Ok(key) if key.len() == r#where.as_dictionary().map_or(0, |w| w.len()) => self.get_item(model, &key).await?,
+            _ => self.items(model, &Value::Dictionary(IndexMap::from([("where".to_owned(), r#where), ("take".to_owned(), Value::Int64(1))]))).await?.pop(),
+        };
+        Ok(self.objects(model, item.into_iter().collect(), finder, ignore_select_and_include, action, transaction_ctx, req_ctx).await?.pop())
+    }
+
+    async fn find_many(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, _path: KeyPath) -> Result<Vec<Object>> {
+        let items = self.items(model, finder).await?;
+        self.objects(model, items, finder, ignore_select_and_include, action, transaction_ctx, req_ctx).await
+    }
+
+    async fn count(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Value> {
+        Ok(Value::Int64(self.count_objects(model, finder, transaction_ctx).await? as i64))
+    }
+
+    async fn count_objects(&self, model: &'static Model, finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<usize> {
+        let query = Query::new(model, arg(finder, "where").unwrap_or(&Value::Null))?;
+        let mut count = 0;
+        let mut start = None;
+        loop {
+            let response = self.client.query()
+                .table_name(&model.table_name)
+                .set_index_name(query.key.index.clone())
+                .key_condition_expression(&query.key_condition)
+                .set_filter_expression(query.filter.clone())
+                .set_expression_attribute_names(query.expression.names())
+                .set_expression_attribute_values(query.expression.values())
+                .select(Select::Count)
+                .set_exclusive_start_key(start)
+                .send().await.map_err(|err| Error::new(format!("DynamoDB: {}", err)))?;
+            count += response.count as usize;
+            start = response.last_evaluated_key;
+            if start.is_none() { break }
+        }
+        Ok(count)
+    }
+
+    async fn count_fields(&self, _model: &'static Model, _finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Value> {
+        Err(Error::invalid_request_message("DynamoDB: counting fields is not supported"))
+    }
+
+    async fn aggregate(&self, _model: &'static Model, _finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Value> {
+        Err(Error::invalid_request_message("DynamoDB: `aggregate` is not supported"))
+    }
+
+    async fn group_by(&self, _model: &'static Model, _finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        Err(Error::invalid_request_message("DynamoDB: `groupBy` is not supported"))
+    }
+
+    async fn sql(&self, _model: &'static Model, _sql: &str, _transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        Err(Error::new("DynamoDB: SQL is not supported"))
+    }
+
+    fn is_committed(&self) -> bool {
+        self.committed.load(Ordering::SeqCst)
+    }
+
+    fn is_transaction(&self) -> bool {
+        self.writes.is_some()
+    }
+
+    /// Write the buffered writes in one `TransactWriteItems` request. If a
+    /// condition fails, nothing is written and the error is the one of the
+    /// first write whose condition failed.
+    async fn commit(&self) -> Result<()> {
+        let writes = match &self.writes {
+            Some(writes) => std::mem::take(&mut *writes.lock().unwrap()),
+            None => vec![],
+        };
+        if !writes.is_empty() {
+            let items = writes.iter().map(Write::transact_item).collect::<Result<Vec<_>>>()?;
+            let result = self.client.transact_write_items().set_transact_items(Some(items)).send().await;
+            if let Err(err) = result {
+                let reasons = match err.as_service_error() {
+                    Some(TransactWriteItemsError::TransactionCanceledException(e)) => e.cancellation_reasons().to_vec(),
+                    _ => vec![],
+                };
+                let failed = reasons.iter().position(|reason| reason.code() == Some("ConditionalCheckFailed"));
+                return Err(match failed.and_then(|index| writes[index].conflict.as_ref()) {
+                    Some(conflict) => conflict.error(),
+                    None => Error::new(format!("DynamoDB: {}", err)),
+                });
+            }
+        }
+        self.committed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        if let Some(writes) = &self.writes {
+            writes.lock().unwrap().clear();
+        }
+        Ok(())
+    }
+
+    async fn spawn(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(self.clone()))
+    }
+}
+
+fn arg<'a>(finder: &'a Value, name: &str) -> Option<&'a Value> {
+    finder.as_dictionary().and_then(|finder| finder.get(name)).filter(|v| !v.is_null())
+}
+
+/// Whether the query reads in ascending order. Only the sort key of the
+/// queried key can be ordered by.
+fn order_forward(model: &Model, key: &Key, order_by: Option<&Value>) -> Result<bool> {
+    let orders: Vec<(String, Value)> = match order_by {
+        None => return Ok(true),
+        Some(Value::Array(orders)) => orders.iter().flat_map(|o| o.as_dictionary().cloned().unwrap_or_default()).collect(),
+        Some(order) => order.as_dictionary().cloned().unwrap_or_default().into_iter().collect(),
+    };
+    match orders.as_slice() {
+        [] => Ok(true),
+        [(field, direction)] if model.field(field).map(|f| Some(&f.column_name) == key.sort.as_ref()).unwrap_or(false) => Ok(direction.as_str() != Some("desc")),
+        _ => Err(Error::invalid_request_message("DynamoDB: records can only be ordered by the sort key of the queried table or index")),
+    }
+}
+
+/// The item of an object, without its null values, which keys of global
+/// secondary indexes can't be.
+fn item_of(object: &Object) -> Result<Item> {
+    let mut item = HashMap::new();
+    for field in object.model().fields.values() {
+        match object.get_value(field.name())? {
+            Value::Null => (),
+            value => { item.insert(field.column_name.clone(), attribute(&value)?); }
+        }
+    }
+    Ok(item)
+}
+
+/// The table key of a `where` input or an identifier, keyed by field name.
+fn key_of(model: &Model, r#where: &Value) -> Result<Item> {
+    let key = table_key(model)?;
+    let fields = r#where.as_dictionary().ok_or_else(|| Error::invalid_request_message("DynamoDB: the record key is not found"))?;
+    let mut item = HashMap::new();
+    for column in [Some(&key.partition), key.sort.as_ref()].into_iter().flatten() {
+        let field = model.fields.values().find(|f| &f.column_name == column).unwrap();
+        let value = fields.get(field.name()).ok_or_else(|| Error::invalid_request_message(format!("DynamoDB: the key field `{}` is not found", field.name())))?;
+        item.insert(column.clone(), attribute(value)?);
+    }
+    Ok(item)
+}
+
+fn key_attributes(key: &Key, item: &Item) -> Item {
+    [Some(&key.partition), key.sort.as_ref()].into_iter().flatten().filter_map(|column| item.get(column).map(|a| (column.clone(), a.clone()))).collect()
+}
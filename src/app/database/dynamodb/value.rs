@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use aws_sdk_dynamodb::types::{AttributeValue, ScalarAttributeType};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+use indexmap::IndexMap;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::Model;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+
+/// An item of a table, keyed by column name.
+pub(super) type Item = HashMap<String, AttributeValue>;
+
+/// The attribute of a value. Numbers are stored as `N`, dates as ISO 8601
+/// strings, which sort like the dates.
+pub(super) fn attribute(value: &Value) -> Result<AttributeValue> {
+    Ok(match value {
+        Value::Null => AttributeValue::Null(true),
+        Value::Bool(b) => AttributeValue::Bool(*b),
+        Value::Int(i) => AttributeValue::N(i.to_string()),
+        Value::Int64(i) => AttributeValue::N(i.to_string()),
+        Value::Float32(f) => AttributeValue::N(f.to_string()),
+        Value::Float(f) => AttributeValue::N(f.to_string()),
+        Value::Decimal(d) => AttributeValue::N(d.to_string()),
+        Value::String(s) => AttributeValue::S(s.clone()),
+        Value::Date(d) => AttributeValue::S(d.format("%Y-%m-%d").to_string()),
+        Value::DateTime(d) => AttributeValue::S(d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        Value::Array(values) => AttributeValue::L(values.iter().map(attribute).collect::<Result<Vec<_>>>()?),
+        Value::Dictionary(map) => AttributeValue::M(map.iter().map(|(k, v)| Ok((k.clone(), attribute(v)?))).collect::<Result<HashMap<_, _>>>()?),
+        value => Err(Error::new(format!("DynamoDB cannot store {:?}", value)))?,
+    })
+}
+
+/// The value of an attribute read for a field of `type`.
+pub(super) fn value(attribute: &AttributeValue, r#type: &Type) -> Result<Value> {
+    let r#type = match r#type {
+        Type::Optional(inner) => inner.as_ref(),
+        r#type => r#type,
+    };
+    let invalid = || Error::new(format!("DynamoDB attribute {:?} is not a valid {:?}", attribute, r#type));
+    Ok(match (attribute, r#type) {
+        (AttributeValue::Null(_), _) => Value::Null,
+        (AttributeValue::Bool(b), _) => Value::Bool(*b),
+        (AttributeValue::N(n), Type::Int) => Value::Int(n.parse().map_err(|_| invalid())?),
+        (AttributeValue::N(n), Type::Int64) => Value::Int64(n.parse().map_err(|_| invalid())?),
+        (AttributeValue::N(n), Type::Float32) => Value::Float32(n.parse().map_err(|_| invalid())?),
+        (AttributeValue::N(n), Type::Decimal) => Value::Decimal(n.parse::<BigDecimal>().map_err(|_| invalid())?),
+        (AttributeValue::N(n), _) => Value::Float(n.parse().map_err(|_| invalid())?),
+        (AttributeValue::S(s), Type::Date) => Value::Date(NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| invalid())?),
+        (AttributeValue::S(s), Type::DateTime) => Value::DateTime(DateTime::parse_from_rfc3339(s).map_err(|_| invalid())?.with_timezone(&Utc)),
+        (AttributeValue::S(s), _) => Value::String(s.clone()),
+        (AttributeValue::L(values), Type::Array(inner)) => Value::Array(values.iter().map(|v| value(v, inner)).collect::<Result<Vec<_>>>()?),
+        (AttributeValue::L(values), _) => Value::Array(values.iter().map(|v| value(v, &Type::Any)).collect::<Result<Vec<_>>>()?),
+        (AttributeValue::M(map), Type::Dictionary(inner)) => Value::Dictionary(map.iter().map(|(k, v)| Ok((k.clone(), value(v, inner)?))).collect::<Result<IndexMap<_, _>>>()?),
+        (AttributeValue::M(map), _) => Value::Dictionary(map.iter().map(|(k, v)| Ok((k.clone(), value(v, &Type::Any)?))).collect::<Result<IndexMap<_, _>>>()?),
+        _ => Err(invalid())?,
+    })
+}
+
+/// The attribute type of a key field in attribute definitions.
+pub(super) fn key_attribute_type(r#type: &Type) -> Result<ScalarAttributeType> {
+    Ok(match r#type {
+        Type::Int | Type::Int64 | Type::Float32 | Type::Float | Type::Decimal => ScalarAttributeType::N,
+        Type::String | Type::Date | Type::DateTime => ScalarAttributeType::S,
+        r#type => Err(Error::new(format!("DynamoDB keys cannot be {:?}", r#type)))?,
+    })
+}
+
+/// The record of an item, keyed by field name, as a dictionary the runtime
+/// builds objects from.
+pub(super) fn record(model: &Model, item: &Item) -> Result<Value> {
+    let mut record = IndexMap::new();
+    for field in model.fields.values() {
+        if let Some(attribute) = item.get(&field.column_name) {
+            record.insert(field.name().to_owned(), value(attribute, field.r#type())?);
+        }
+    }
+    Ok(Value::Dictionary(record))
+}
@@ -0,0 +1,297 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use regex::Regex;
+use teo_result::{Error, Result};
+use teo_runtime::model::relation::Relation;
+use teo_runtime::model::Model;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::memory::store::{Record, Tables};
+
+/// The operators of a field filter. A dictionary with other keys is a value
+/// compared for equality, e.g. of a JSON field.
+const OPERATORS: [&str; 18] = ["equals", "not", "gt", "gte", "lt", "lte", "in", "notIn", "contains", "startsWith", "endsWith", "matches", "mode", "has", "hasSome", "hasEvery", "isEmpty", "length"];
+
+/// Whether a record matches a `where` input.
+pub(super) fn matches(tables: &Tables, model: &Model, record: &Record, r#where: Option<&Value>) -> Result<bool> {
+    let Some(conditions) = r#where.and_then(Value::as_dictionary) else { return Ok(true) };
+    for (key, condition) in conditions {
+        let matched = match key.as_str() {
+            "AND" => {
+                let mut all = true;
+                for condition in list(condition) {
+                    all = all && matches(tables, model, record, Some(condition))?;
+                }
+                all
+            }
+            "OR" => {
+                let mut any = false;
+                for condition in list(condition) {
+                    any = any || matches(tables, model, record, Some(condition))?;
+                }
+                any
+            }
+            "NOT" => {
+                let mut none = true;
+                for condition in list(condition) {
+                    none = none && !matches(tables, model, record, Some(condition))?;
+                }
+                none
+            }
+            name => if model.field(name).is_some() {
+                field_matches(record.get(name).unwrap_or(&Value::Null), condition)?
+            } else if let Some(relation) = relation(model, name) {
+                relation_matches(tables, relation, record, condition)?
+            } else {
+                Err(Error::invalid_request_message(format!("memory connector: field `{}` of `{}` is not found", name, model.path().join("."))))?
+            },
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Whether a value matches the condition of a field: a value to compare it
+/// with for equality or a dictionary of operators.
+pub(super) fn field_matches(value: &Value, condition: &Value) -> Result<bool> {
+    let ops = match condition {
+        Value::Dictionary(ops) if !ops.is_empty() && ops.keys().all(|op| OPERATORS.contains(&op.as_str())) => ops,
+        condition => return Ok(values_equal(value, condition)),
+    };
+    let insensitive = ops.get("mode").and_then(Value::as_str) == Some("caseInsensitive");
+    for (op, operand) in ops {
+        let matched = match op.as_str() {
+            "mode" => true,
+            "equals" => text_equal(value, operand, insensitive),
+            "not" => match operand {
+                Value::Dictionary(_) => !field_matches(value, operand)?,
+                operand => !text_equal(value, operand, insensitive),
+            },
+            "gt" => compare(value, operand) == Some(Ordering::Greater),
+            "gte" => matches!(compare(value, operand), Some(Ordering::Greater | Ordering::Equal)),
+            "lt" => compare(value, operand) == Some(Ordering::Less),
+            "lte" => matches!(compare(value, operand), Some(Ordering::Less | Ordering::Equal)),
+            "in" => array(op, operand)?.iter().any(|operand| text_equal(value, operand, insensitive)),
+            "notIn" => !value.is_null() && !array(op, operand)?.iter().any(|operand| text_equal(value, operand, insensitive)),
+            "contains" => match value {
+                Value::Array(items) => items.iter().any(|item| values_equal(item, operand)),
+                value => text_op(value, operand, insensitive, |value, operand| value.contains(operand)),
+            },
+            "startsWith" => text_op(value, operand, insensitive, |value, operand| value.starts_with(operand)),
+            "endsWith" => text_op(value, operand, insensitive, |value, operand| value.ends_with(operand)),
+            "matches" => match (value, operand.as_str()) {
+                (Value::String(value), Some(pattern)) => Regex::new(pattern).map_err(|_| Error::invalid_request_message(format!("memory connector: invalid regex \"{}\"", pattern)))?.is_match(value),
+                _ => false,
+            },
+            "has" => value.as_array().map_or(false, |items| items.iter().any(|item| values_equal(item, operand))),
+            "hasSome" => value.as_array().map_or(false, |items| array(op, operand).map_or(false, |operands| operands.iter().any(|operand| items.iter().any(|item| values_equal(item, operand))))),
+            "hasEvery" => value.as_array().map_or(false, |items| array(op, operand).map_or(false, |operands| operands.iter().all(|operand| items.iter().any(|item| values_equal(item, operand))))),
+            "isEmpty" => value.as_array().map_or(false, |items| Some(items.is_empty()) == operand.as_bool()),
+            "length" => value.as_array().map_or(false, |items| integer(operand) == Some(items.len() as i64)),
+            _ => unreachable!(),
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Whether the related records of a record match the condition of a
+/// relation: `some`, `every` or `none` for a relation of many, `is` or
+/// `isNot` for a relation of one, which a `where` input is a shorthand of.
+fn relation_matches(tables: &Tables, relation: &Relation, record: &Record, condition: &Value) -> Result<bool> {
+    let (model, related) = related_records(tables, relation, record)?;
+    let mut count = 0;
+    let ops = condition.as_dictionary().cloned().unwrap_or_default();
+    let (op, inner) = match ops.first() {
+        Some((op, inner)) if ops.len() == 1 && ["some", "every", "none", "is", "isNot"].contains(&op.as_str()) => (op.as_str(), inner),
+        _ => ("is", condition),
+    };
+    for related in &related {
+        if matches(tables, model, related, Some(inner))? {
+            count += 1;
+        }
+    }
+    Ok(match op {
+        "some" => count > 0,
+        "every" => count == related.len(),
+        "none" => count == 0,
+        "is" if inner.is_null() => related.is_empty(),
+        "is" => count > 0,
+        _ if inner.is_null() => !related.is_empty(),
+        _ => !related.is_empty() && count == 0,
+    })
+}
+
+/// The relation of a model named `name`.
+pub(super) fn relation<'a>(model: &'a Model, name: &str) -> Option<&'a Relation> {
+    model.relations().into_iter().find(|relation| relation.name() == name)
+}
+
+/// The related model of a relation and the records related to a record, in
+/// the order they were created. Many-to-many relations are followed through
+/// the records of their join table.
+pub(super) fn related_records<'a>(tables: &'a Tables, relation: &Relation, record: &Record) -> Result<(&'static Model, Vec<&'a Record>)> {
+    let namespace = Ctx::main_namespace();
+    let model = namespace.model_at_path(&relation.model_path()).ok_or_else(|| Error::not_found())?;
+    let records = tables.get(&model.table_name).map_or(&[][..], Vec::as_slice);
+    if relation.has_join_table() {
+        let (through_model, through_relation) = namespace.through_relation(relation);
+        let (_, opposite_relation) = namespace.through_opposite_relation(relation);
+        let joins: Vec<&Record> = tables.get(&through_model.table_name).map_or(&[][..], Vec::as_slice).iter()
+            .filter(|join| through_relation.iter().all(|(local, foreign)| linked(join.get(local), record.get(foreign))))
+            .collect();
+        Ok((model, records.iter().filter(|related| joins.iter().any(|join| opposite_relation.iter().all(|(local, foreign)| linked(join.get(local), related.get(foreign))))).collect()))
+    } else {
+        Ok((model, records.iter().filter(|related| relation.iter().all(|(local, foreign)| linked(record.get(local), related.get(foreign)))).collect()))
+    }
+}
+
+/// Whether the values of a link are set and equal, null keys link nothing.
+fn linked(local: Option<&Value>, foreign: Option<&Value>) -> bool {
+    match (local, foreign) {
+        (Some(local), Some(foreign)) if !local.is_null() => values_equal(local, foreign),
+        _ => false,
+    }
+}
+
+/// The orders of an `orderBy` input, a dictionary or a list of them: the
+/// key path of each ordered value, e.g. `["_sum", "amount"]` for groups, and
+/// whether it's descending.
+pub(super) fn orders(order_by: Option<&Value>) -> Result<Vec<(Vec<String>, bool)>> {
+    fn collect(path: Vec<String>, value: &Value, orders: &mut Vec<(Vec<String>, bool)>) -> Result<()> {
+        match value {
+            Value::Dictionary(entries) => for (key, value) in entries {
+                let mut path = path.clone();
+                path.push(key.clone());
+                collect(path, value, orders)?;
+            },
+            direction => match direction.as_str() {
+                Some("asc") => orders.push((path, false)),
+                Some("desc") => orders.push((path, true)),
+                _ => Err(Error::invalid_request_message(format!("memory connector: invalid order of `{}`", path.join("."))))?,
+            },
+        }
+        Ok(())
+    }
+    let mut result = vec![];
+    if let Some(order_by) = order_by {
+        for order in list(order_by) {
+            collect(vec![], order, &mut result)?;
+        }
+    }
+    Ok(result)
+}
+
+/// The orders of an `orderBy` input of records, which can only be ordered by
+/// their own fields.
+pub(super) fn record_orders(model: &Model, order_by: Option<&Value>) -> Result<Vec<(Vec<String>, bool)>> {
+    let orders = orders(order_by)?;
+    if let Some((path, _)) = orders.iter().find(|(path, _)| path.len() != 1 || model.field(&path[0]).is_none()) {
+        Err(Error::invalid_request_message(format!("memory connector: records of `{}` cannot be ordered by `{}`", model.path().join("."), path.join("."))))?
+    }
+    Ok(orders)
+}
+
+/// Sort records by their orders, nulls first. The sort is stable, records
+/// which are ordered the same stay in the order they were created.
+pub(super) fn sort<R: Borrow<Record>>(records: &mut [R], orders: &[(Vec<String>, bool)]) {
+    records.sort_by(|a, b| {
+        for (path, descending) in orders {
+            let ordering = match (lookup(a.borrow(), path), lookup(b.borrow(), path)) {
+                (Value::Null, Value::Null) => Ordering::Equal,
+                (Value::Null, _) => Ordering::Less,
+                (_, Value::Null) => Ordering::Greater,
+                (a, b) => compare(a, b).unwrap_or(Ordering::Equal),
+            };
+            let ordering = if *descending { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+}
+
+fn lookup<'a>(record: &'a Record, path: &[String]) -> &'a Value {
+    let mut value = record.get(&path[0]);
+    for key in &path[1..] {
+        value = value.and_then(Value::as_dictionary).and_then(|entries| entries.get(key));
+    }
+    value.unwrap_or(&Value::Null)
+}
+
+/// Compare values of the same kind. Numbers of different types compare by
+/// value.
+pub(super) fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+        (Value::DateTime(a), Value::DateTime(b)) => Some(a.cmp(b)),
+        (Value::Decimal(a), Value::Decimal(b)) => Some(a.cmp(b)),
+        (Value::ObjectId(a), Value::ObjectId(b)) => Some(a.to_hex().cmp(&b.to_hex())),
+        (a, b) => match (integer(a), integer(b)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => number(a)?.partial_cmp(&number(b)?),
+        },
+    }
+}
+
+/// Whether values are equal, numbers of different types by value.
+pub(super) fn values_equal(a: &Value, b: &Value) -> bool {
+    match compare(a, b) {
+        Some(ordering) => ordering == Ordering::Equal,
+        None => a == b,
+    }
+}
+
+fn text_equal(value: &Value, operand: &Value, insensitive: bool) -> bool {
+    match (value, operand) {
+        (Value::String(value), Value::String(operand)) if insensitive => value.to_lowercase() == operand.to_lowercase(),
+        (value, operand) => values_equal(value, operand),
+    }
+}
+
+fn text_op(value: &Value, operand: &Value, insensitive: bool, op: impl Fn(&str, &str) -> bool) -> bool {
+    match (value, operand) {
+        (Value::String(value), Value::String(operand)) if insensitive => op(&value.to_lowercase(), &operand.to_lowercase()),
+        (Value::String(value), Value::String(operand)) => op(value, operand),
+        _ => false,
+    }
+}
+
+pub(super) fn integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(i) => Some(*i as i64),
+        Value::Int64(i) => Some(*i),
+        _ => None,
+    }
+}
+
+pub(super) fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Int64(i) => Some(*i as f64),
+        Value::Float32(f) => Some(*f as f64),
+        Value::Float(f) => Some(*f),
+        Value::Decimal(d) => d.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+fn array<'a>(op: &str, operand: &'a Value) -> Result<&'a Vec<Value>> {
+    operand.as_array().ok_or_else(|| Error::invalid_request_message(format!("memory connector: expect the operand of `{}` to be an array", op)))
+}
+
+/// The items of a value which is a list or a single item.
+fn list(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        value => vec![value],
+    }
+}
@@ -0,0 +1,65 @@
+//! The memory connector, which keeps the records in the memory of the app.
+//!
+//! It's selected by a connector url of the `memory` scheme:
+//!
+//! ```teo
+//! connector {
+//!   provider: .sqlite
+//!   url: "memory://"
+//! }
+//! ```
+//!
+//! Nothing is sent to a database, so it's handy for unit tests and demos.
+//! The records are gone when the app exits, and connecting again starts
+//! from an empty store. The provider is used by the schema only. Features
+//! which run SQL or MongoDB commands of their own, like sessions or the
+//! outbox, fail with an error.
+//!
+//! Unique indexes are enforced, `@autoIncrement` fields are numbered from
+//! one, and finders support `where` with relation filters, `orderBy`,
+//! `cursor`, `skip`, `take`, `distinct`, `select` and `include`, as well as
+//! `count`, `aggregate` and `groupBy`. A transaction reads and writes a copy
+//! of the store, which its writes are replayed on when it's committed.
+
+mod filter;
+mod query;
+mod store;
+mod transaction;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use teo_result::Result;
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::Transaction;
+use crate::app::database::memory::store::Store;
+use crate::app::database::memory::transaction::MemoryTransaction;
+
+#[derive(Debug, Default)]
+pub struct MemoryConnection {
+    store: Arc<Store>,
+}
+
+impl MemoryConnection {
+
+    /// Create a connection with an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Connection for MemoryConnection {
+
+    async fn transaction(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(MemoryTransaction::new(self.store.clone(), true)))
+    }
+
+    async fn no_transaction(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(MemoryTransaction::new(self.store.clone(), false)))
+    }
+}
+
+/// Connect a `memory://` url, the builtin connector builder of the scheme.
+pub(crate) async fn connect(_url: String) -> Result<Arc<dyn Connection>> {
+    Ok(Arc::new(MemoryConnection::new()))
+}
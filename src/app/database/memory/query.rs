@@ -0,0 +1,221 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use bigdecimal::BigDecimal;
+use indexmap::IndexMap;
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::database::memory::filter::{compare, field_matches, integer, matches, number, orders, record_orders, related_records, relation, sort, values_equal};
+use crate::app::database::memory::store::{has_key, Record, Tables};
+
+/// The records of a finder as values, with the related records it includes.
+pub(super) fn find(tables: &Tables, model: &Model, finder: &Value) -> Result<Vec<Value>> {
+    records(tables, model, finder)?.into_iter().map(|record| with_includes(tables, model, record, arg(finder, "include"))).collect()
+}
+
+/// The records of a finder: filtered by `where`, ordered by `orderBy`,
+/// deduplicated by `distinct` and paginated.
+pub(super) fn records<'a>(tables: &'a Tables, model: &Model, finder: &Value) -> Result<Vec<&'a Record>> {
+    let mut records = filter(tables, model, tables.get(&model.table_name).map_or(&[][..], Vec::as_slice).iter().collect(), arg(finder, "where"))?;
+    sort(&mut records, &record_orders(model, arg(finder, "orderBy"))?);
+    if let Some(distinct) = arg(finder, "distinct").and_then(Value::as_array) {
+        let fields: Vec<&str> = distinct.iter().filter_map(Value::as_str).collect();
+        let mut seen: Vec<&Record> = vec![];
+        records.retain(|record| {
+            let duplicated = seen.iter().any(|other| fields.iter().all(|field| values_equal(record.get(*field).unwrap_or(&Value::Null), other.get(*field).unwrap_or(&Value::Null))));
+            if !duplicated {
+                seen.push(*record);
+            }
+            !duplicated
+        });
+    }
+    paginate(records, finder)
+}
+
+fn filter<'a>(tables: &Tables, model: &Model, records: Vec<&'a Record>, r#where: Option<&Value>) -> Result<Vec<&'a Record>> {
+    let mut result = vec![];
+    for record in records {
+        if matches(tables, model, record, r#where)? {
+            result.push(record);
+        }
+    }
+    Ok(result)
+}
+
+/// Apply `cursor`, `skip` and `take`, or `pageSize` and `pageNumber`. The
+/// cursor record is the first one, and a negative `take` takes the records
+/// before it.
+fn paginate<R: Borrow<Record>>(mut records: Vec<R>, finder: &Value) -> Result<Vec<R>> {
+    let mut skip = arg(finder, "skip").and_then(integer).unwrap_or(0);
+    let mut take = arg(finder, "take").and_then(integer);
+    if let Some(page_size) = arg(finder, "pageSize").and_then(integer) {
+        skip = (arg(finder, "pageNumber").and_then(integer).unwrap_or(1) - 1) * page_size;
+        take = Some(page_size);
+    }
+    let backwards = take.map_or(false, |take| take < 0);
+    if backwards {
+        records.reverse();
+    }
+    if let Some(cursor) = arg(finder, "cursor") {
+        let cursor = cursor.as_dictionary().ok_or_else(|| Error::invalid_request_message("memory connector: expect `cursor` to be an object"))?;
+        records = match records.iter().position(|record| has_key(record.borrow(), cursor)) {
+            Some(position) => records.split_off(position),
+            None => vec![],
+        };
+    }
+    let mut records: Vec<R> = records.into_iter()
+        .skip(skip.max(0) as usize)
+        .take(take.map_or(usize::MAX, |take| take.unsigned_abs() as usize))
+        .collect();
+    if backwards {
+        records.reverse();
+    }
+    Ok(records)
+}
+
+/// The value of a record with its included relations, which are filtered,
+/// ordered and paginated by their own finders.
+fn with_includes(tables: &Tables, model: &Model, record: &Record, include: Option<&Value>) -> Result<Value> {
+    let mut value = record.clone();
+    for (name, finder) in include.and_then(Value::as_dictionary).into_iter().flatten() {
+        if matches!(finder, Value::Null | Value::Bool(false)) {
+            continue
+        }
+        let relation = relation(model, name).ok_or_else(|| Error::invalid_request_message(format!("memory connector: relation `{}` of `{}` is not found", name, model.path().join("."))))?;
+        let (related_model, related) = related_records(tables, relation, record)?;
+        let mut related = filter(tables, related_model, related, arg(finder, "where"))?;
+        sort(&mut related, &record_orders(related_model, arg(finder, "orderBy"))?);
+        let related = paginate(related, finder)?.into_iter()
+            .map(|related| with_includes(tables, related_model, related, arg(finder, "include")))
+            .collect::<Result<Vec<_>>>()?;
+        value.insert(name.clone(), if relation.is_vec { Value::Array(related) } else { related.into_iter().next().unwrap_or(Value::Null) });
+    }
+    Ok(Value::Dictionary(value))
+}
+
+/// The number of records of each field of `select`, `_all` for all records.
+pub(super) fn count_fields(tables: &Tables, model: &Model, finder: &Value) -> Result<Value> {
+    let records = records(tables, model, finder)?;
+    let mut counts = IndexMap::new();
+    for (field, selected) in arg(finder, "select").and_then(Value::as_dictionary).into_iter().flatten() {
+        if selected.as_bool() != Some(true) { continue }
+        let count = if field == "_all" { records.len() } else { records.iter().filter(|record| record.get(field).map_or(false, |v| !v.is_null())).count() };
+        counts.insert(field.clone(), Value::Int64(count as i64));
+    }
+    Ok(Value::Dictionary(counts))
+}
+
+/// The `_count`, `_sum`, `_avg`, `_min` and `_max` of the records of a
+/// finder.
+pub(super) fn aggregate(tables: &Tables, model: &Model, finder: &Value) -> Result<Value> {
+    Ok(Value::Dictionary(aggregates(&records(tables, model, finder)?, finder)?))
+}
+
+/// Group the records of `where` by the fields of `by`. Each group has the
+/// values of `by` and its aggregates, groups are filtered by `having`, then
+/// ordered and paginated.
+pub(super) fn group_by(tables: &Tables, model: &Model, finder: &Value) -> Result<Vec<Value>> {
+    let by: Vec<String> = match arg(finder, "by") {
+        Some(Value::Array(fields)) => fields.iter().map(|field| field.as_str().map(ToOwned::to_owned)).collect::<Option<_>>(),
+        Some(Value::String(field)) => Some(vec![field.clone()]),
+        _ => None,
+    }.ok_or_else(|| Error::invalid_request_message("memory connector: expect `by` to be a list of fields"))?;
+    let records = filter(tables, model, tables.get(&model.table_name).map_or(&[][..], Vec::as_slice).iter().collect(), arg(finder, "where"))?;
+    let mut groups: Vec<(Record, Vec<&Record>)> = vec![];
+    for record in records {
+        let values: Record = by.iter().map(|field| (field.clone(), record.get(field).cloned().unwrap_or(Value::Null))).collect();
+        match groups.iter_mut().find(|(group, _)| group.iter().all(|(field, value)| values_equal(value, &values[field]))) {
+            Some((_, members)) => members.push(record),
+            None => groups.push((values, vec![record])),
+        }
+    }
+    let mut rows = vec![];
+    for (mut row, members) in groups {
+        row.extend(aggregates(&members, finder)?);
+        if having_matches(&row, arg(finder, "having"))? {
+            rows.push(row);
+        }
+    }
+    sort(&mut rows, &orders(arg(finder, "orderBy"))?);
+    Ok(paginate(rows, finder)?.into_iter().map(Value::Dictionary).collect())
+}
+
+/// Whether a group matches `having`, whose conditions are on the values of
+/// `by` or on aggregates, e.g. `{ amount: { _sum: { gt: 100 } } }`.
+fn having_matches(row: &Record, having: Option<&Value>) -> Result<bool> {
+    let Some(conditions) = having.and_then(Value::as_dictionary) else { return Ok(true) };
+    for (field, condition) in conditions {
+        let matched = match (field.as_str(), condition) {
+            ("AND", condition) => condition.as_array().map_or(Ok(true), |c| c.iter().try_fold(true, |all, c| Ok::<_, Error>(all && having_matches(row, Some(c))?)))?,
+            ("OR", condition) => condition.as_array().map_or(Ok(true), |c| c.iter().try_fold(false, |any, c| Ok::<_, Error>(any || having_matches(row, Some(c))?)))?,
+            ("NOT", condition) => !having_matches(row, Some(condition))?,
+            (field, Value::Dictionary(entries)) if entries.keys().any(|key| key.starts_with('_')) => {
+                let mut all = true;
+                for (function, condition) in entries {
+                    let value = row.get(function).and_then(Value::as_dictionary).and_then(|values| values.get(field)).unwrap_or(&Value::Null);
+                    all = all && field_matches(value, condition)?;
+                }
+                all
+            }
+            (field, condition) => field_matches(row.get(field).unwrap_or(&Value::Null), condition)?,
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn aggregates(records: &[&Record], finder: &Value) -> Result<Record> {
+    let mut result = IndexMap::new();
+    for function in ["_count", "_sum", "_avg", "_min", "_max"] {
+        let Some(fields) = arg(finder, function).and_then(Value::as_dictionary) else { continue };
+        let mut values = IndexMap::new();
+        for (field, selected) in fields {
+            if selected.as_bool() != Some(true) { continue }
+            let column: Vec<&Value> = records.iter().filter_map(|record| record.get(field)).filter(|value| !value.is_null()).collect();
+            values.insert(field.clone(), match function {
+                "_count" if field == "_all" => Value::Int64(records.len() as i64),
+                "_count" => Value::Int64(column.len() as i64),
+                "_sum" => sum(field, &column)?,
+                "_avg" => average(field, &column)?,
+                "_min" => column.into_iter().min_by(|a, b| compare(a, b).unwrap_or(Ordering::Equal)).cloned().unwrap_or(Value::Null),
+                _ => column.into_iter().max_by(|a, b| compare(a, b).unwrap_or(Ordering::Equal)).cloned().unwrap_or(Value::Null),
+            });
+        }
+        result.insert(function.to_owned(), Value::Dictionary(values));
+    }
+    Ok(result)
+}
+
+/// The sum of numbers, an `Int64` of integers and a `Decimal` of decimals.
+fn sum(field: &str, values: &[&Value]) -> Result<Value> {
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    if values.iter().all(|value| integer(value).is_some()) {
+        return Ok(Value::Int64(values.iter().filter_map(|value| integer(value)).sum()));
+    }
+    if values.iter().all(|value| matches!(value, Value::Decimal(_))) {
+        return Ok(Value::Decimal(values.iter().filter_map(|value| if let Value::Decimal(d) = value { Some(d.clone()) } else { None }).fold(BigDecimal::from(0), |sum, d| sum + d)));
+    }
+    Ok(Value::Float(numbers(field, values)?.into_iter().sum()))
+}
+
+fn average(field: &str, values: &[&Value]) -> Result<Value> {
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    if let Value::Decimal(sum) = sum(field, values)? {
+        return Ok(Value::Decimal(sum / BigDecimal::from(values.len() as i64)));
+    }
+    Ok(Value::Float(numbers(field, values)?.into_iter().sum::<f64>() / values.len() as f64))
+}
+
+fn numbers(field: &str, values: &[&Value]) -> Result<Vec<f64>> {
+    values.iter().map(|value| number(value).ok_or_else(|| Error::invalid_request_message(format!("memory connector: `{}` is not a number", field)))).collect()
+}
+
+pub(super) fn arg<'a>(finder: &'a Value, name: &str) -> Option<&'a Value> {
+    finder.as_dictionary().and_then(|finder| finder.get(name)).filter(|v| !v.is_null())
+}
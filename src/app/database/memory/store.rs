@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use indexmap::IndexMap;
+use key_path::path;
+use teo_result::Result;
+use teo_runtime::error_ext::unique_value_duplicated;
+use teo_runtime::model::index::Type as IndexType;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::database::memory::filter::values_equal;
+
+/// A stored record, keyed by field name.
+pub(super) type Record = IndexMap<String, Value>;
+
+/// The records of each table, in the order they were created.
+pub(super) type Tables = BTreeMap<String, Vec<Record>>;
+
+/// The store of a connection.
+#[derive(Debug, Default)]
+pub(super) struct Store {
+    pub(super) tables: Mutex<Tables>,
+    /// The last number given to each `@autoIncrement` field, by table and
+    /// field. Numbers aren't given again when a transaction is aborted, like
+    /// with the sequences of databases.
+    sequences: Mutex<HashMap<(String, String), i64>>,
+}
+
+impl Store {
+
+    /// The next number of an `@autoIncrement` field, after the largest one
+    /// stored in `records`.
+    pub(super) fn next_number(&self, table: &str, field: &str, records: &[Record]) -> i64 {
+        let stored = records.iter().filter_map(|record| match record.get(field) {
+            Some(Value::Int(i)) => Some(*i as i64),
+            Some(Value::Int64(i)) => Some(*i),
+            _ => None,
+        }).max().unwrap_or(0);
+        let mut sequences = self.sequences.lock().unwrap();
+        let last = sequences.entry((table.to_owned(), field.to_owned())).or_insert(0);
+        *last = (*last).max(stored) + 1;
+        *last
+    }
+}
+
+/// A write of a transaction, replayed on the store when it's committed.
+#[derive(Debug, Clone)]
+pub(super) struct Write {
+    pub(super) table: String,
+    /// The primary key of the record before the write, `None` if it's
+    /// created.
+    pub(super) key: Option<Record>,
+    /// The record after the write, `None` if it's deleted.
+    pub(super) record: Option<Record>,
+    /// The fields of the unique indexes of the table.
+    pub(super) uniques: Vec<Vec<String>>,
+}
+
+impl Write {
+
+    /// Apply the write to `tables`.
+    pub(super) fn apply(&self, tables: &mut Tables) -> Result<()> {
+        let records = tables.entry(self.table.clone()).or_default();
+        let position = self.key.as_ref().and_then(|key| records.iter().position(|record| has_key(record, key)));
+        match &self.record {
+            Some(record) => {
+                for fields in &self.uniques {
+                    // null values are distinct from each other, like in SQL
+                    if fields.iter().any(|field| record.get(field).map_or(true, Value::is_null)) {
+                        continue
+                    }
+                    let duplicated = records.iter().enumerate()
+                        .filter(|(index, _)| Some(*index) != position)
+                        .any(|(_, other)| fields.iter().all(|field| other.get(field).map_or(false, |value| values_equal(value, &record[field]))));
+                    if duplicated {
+                        return Err(unique_value_duplicated(path![], fields[0].clone()));
+                    }
+                }
+                match position {
+                    Some(position) => records[position] = record.clone(),
+                    None => records.push(record.clone()),
+                }
+            }
+            None => if let Some(position) = position {
+                records.remove(position);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The fields of the primary and unique indexes of a model.
+pub(super) fn unique_fields(model: &Model) -> Vec<Vec<String>> {
+    model.indexes.values()
+        .filter(|index| matches!(index.r#type(), IndexType::Primary | IndexType::Unique))
+        .map(|index| index.items.iter().map(|item| item.field.clone()).collect())
+        .collect()
+}
+
+/// Whether a record has the values of a primary key.
+pub(super) fn has_key(record: &Record, key: &Record) -> bool {
+    key.iter().all(|(field, value)| record.get(field).map_or(false, |v| values_equal(v, value)))
+}
@@ -0,0 +1,217 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+use key_path::KeyPath;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::request;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::app::database::memory::query::{self, arg};
+use crate::app::database::memory::store::{unique_fields, Record, Store, Tables, Write};
+
+/// The tables of a transaction and the writes to replay when it's committed.
+#[derive(Debug)]
+struct Staged {
+    tables: Tables,
+    writes: Vec<Write>,
+}
+
+/// A transaction of the memory connector.
+///
+/// A transaction reads and writes a copy of the store made when it begins.
+/// Committing replays its writes on the store, all or none of them: a write
+/// which breaks a unique index fails the commit. Of concurrent transactions
+/// writing a record, the one committed last wins. Without a transaction,
+/// writes go to the store directly.
+#[derive(Debug, Clone)]
+pub(super) struct MemoryTransaction {
+    store: Arc<Store>,
+    staged: Option<Arc<Mutex<Staged>>>,
+    committed: Arc<AtomicBool>,
+}
+
+impl MemoryTransaction {
+
+    pub(super) fn new(store: Arc<Store>, transaction: bool) -> Self {
+        let staged = transaction.then(|| Arc::new(Mutex::new(Staged { tables: store.tables.lock().unwrap().clone(), writes: vec![] })));
+        Self { store, staged, committed: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Run `f` with the tables the transaction reads.
+    fn read<T>(&self, f: impl FnOnce(&Tables) -> T) -> T {
+        match &self.staged {
+            Some(staged) => f(&staged.lock().unwrap().tables),
+            None => f(&self.store.tables.lock().unwrap()),
+        }
+    }
+
+    fn write(&self, write: Write) -> Result<()> {
+        match &self.staged {
+            Some(staged) => {
+                let mut staged = staged.lock().unwrap();
+                write.apply(&mut staged.tables)?;
+                staged.writes.push(write);
+                Ok(())
+            }
+            None => write.apply(&mut self.store.tables.lock().unwrap()),
+        }
+    }
+
+    fn objects(&self, model: &'static Model, values: Vec<Value>, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>) -> Result<Vec<Object>> {
+        let (select, include) = if ignore_select_and_include { (None, None) } else { (arg(finder, "select"), arg(finder, "include")) };
+        let mut objects = vec![];
+        for value in values {
+            let object = transaction_ctx.new_object(model, action, req_ctx.clone())?;
+            object.set_from_database_result_value(&value, select, include);
+            objects.push(object);
+        }
+        Ok(objects)
+    }
+}
+
+#[async_trait]
+impl Transaction for MemoryTransaction {
+
+    /// Create the tables which don't exist, `reset_database` empties them.
+    async fn migrate(&self, models: Vec<&Model>, dry_run: bool, reset_database: bool, _silent: bool) -> Result<()> {
+        if dry_run {
+            return Ok(());
+        }
+        let mut tables = self.store.tables.lock().unwrap();
+        for model in models {
+            let records = tables.entry(model.table_name.clone()).or_default();
+            if reset_database {
+                records.clear();
+            }
+        }
+        Ok(())
+    }
+
+    async fn purge(&self, models: Vec<&Model>) -> Result<()> {
+        let mut tables = self.store.tables.lock().unwrap();
+        for model in models {
+            tables.remove(&model.table_name);
+        }
+        Ok(())
+    }
+
+    async fn query_raw(&self, _query: &Value) -> Result<Value> {
+        Err(Error::new("memory connector: raw queries are not supported"))
+    }
+
+    /// Save an object, numbering its `@autoIncrement` fields if it's new.
+    async fn save_object(&self, object: &Object) -> Result<()> {
+        let model = object.model();
+        let key = if object.is_new() {
+            for field in model.fields.values().filter(|field| field.auto_increment) {
+                if object.get_value(field.name())?.is_null() {
+                    let number = self.read(|tables| self.store.next_number(&model.table_name, field.name(), tables.get(&model.table_name).map_or(&[][..], Vec::as_slice)));
+                    object.set_value(field.name(), match field.r#type().unwrap_optional() {
+                        Type::Int64 => Value::Int64(number),
+                        _ => Value::Int(number as i32),
+                    })?;
+                }
+            }
+            None
+        } else {
+            let mut key = Record::new();
+            for item in model.primary_index().into_iter().flat_map(|index| index.items.iter()) {
+                let previous = object.get_previous_value(&item.field).ok().filter(|value| !value.is_null());
+                key.insert(item.field.clone(), match previous {
+                    Some(previous) => previous,
+                    None => object.get_value(&item.field)?,
+                });
+            }
+            Some(key)
+        };
+        let mut record = Record::new();
+        for field in model.fields.values().filter(|field| !field.r#virtual) {
+            record.insert(field.name().to_owned(), object.get_value(field.name())?);
+        }
+        self.write(Write { table: model.table_name.clone(), key, record: Some(record), uniques: unique_fields(model) })
+    }
+
+    async fn delete_object(&self, object: &Object) -> Result<()> {
+        let model = object.model();
+        let key = object.identifier().as_dictionary().cloned().ok_or_else(|| Error::new("memory connector: the record key is not found"))?;
+        self.write(Write { table: model.table_name.clone(), key: Some(key), record: None, uniques: vec![] })
+    }
+
+    async fn find_unique(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, _path: KeyPath) -> Result<Option<Object>> {
+        let values = self.read(|tables| query::find(tables, model, finder))?;
+        Ok(self.objects(model, values.into_iter().take(1).collect(), finder, ignore_select_and_include, action, transaction_ctx, req_ctx)?.pop())
+    }
+
+    async fn find_many(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, _path: KeyPath) -> Result<Vec<Object>> {
+        let values = self.read(|tables| query::find(tables, model, finder))?;
+        self.objects(model, values, finder, ignore_select_and_include, action, transaction_ctx, req_ctx)
+    }
+
+    async fn count(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Value> {
+        if arg(finder, "select").is_some() {
+            self.count_fields(model, finder, transaction_ctx).await
+        } else {
+            Ok(Value::Int64(self.count_objects(model, finder, transaction_ctx).await? as i64))
+        }
+    }
+
+    async fn count_objects(&self, model: &'static Model, finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<usize> {
+        self.read(|tables| query::records(tables, model, finder).map(|records| records.len()))
+    }
+
+    async fn count_fields(&self, model: &'static Model, finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Value> {
+        self.read(|tables| query::count_fields(tables, model, finder))
+    }
+
+    async fn aggregate(&self, model: &'static Model, finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Value> {
+        self.read(|tables| query::aggregate(tables, model, finder))
+    }
+
+    async fn group_by(&self, model: &'static Model, finder: &Value, _transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        self.read(|tables| query::group_by(tables, model, finder))
+    }
+
+    async fn sql(&self, _model: &'static Model, _sql: &str, _transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        Err(Error::new("memory connector: SQL is not supported"))
+    }
+
+    fn is_committed(&self) -> bool {
+        self.committed.load(Ordering::SeqCst)
+    }
+
+    fn is_transaction(&self) -> bool {
+        self.staged.is_some()
+    }
+
+    /// Replay the writes on a copy of the store, which replaces the store if
+    /// they all apply.
+    async fn commit(&self) -> Result<()> {
+        if let Some(staged) = &self.staged {
+            let writes = std::mem::take(&mut staged.lock().unwrap().writes);
+            let mut tables = self.store.tables.lock().unwrap();
+            let mut committed = tables.clone();
+            for write in &writes {
+                write.apply(&mut committed)?;
+            }
+            *tables = committed;
+        }
+        self.committed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        if let Some(staged) = &self.staged {
+            staged.lock().unwrap().writes.clear();
+        }
+        Ok(())
+    }
+
+    async fn spawn(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(self.clone()))
+    }
+}
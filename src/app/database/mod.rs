@@ -1,17 +1,28 @@
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use url::Url;
 use array_tool::vec::Join;
-use teo_result::{Result};
+use teo_result::{Error, Result};
 use teo_runtime::config::connector::Connector;
 use teo_runtime::connection::connection::Connection;
 use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
 use teo_runtime::namespace::Namespace;
 use teo_sql_connector::connector::SQLConnection;
 use teo_sql_connector::schema::dialect::SQLDialect;
 use teo_mongodb_connector::connector::MongoDBConnection;
 use crate::app::ctx::Ctx;
+use crate::app::database::connector::url_scheme;
+use crate::app::secrets::resolve_secrets;
 use teo_runtime::connection::Ctx as ConnCtx;
 use crate::message::info_message;
 
+pub mod connector;
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+pub mod memory;
+
 pub async fn connect_databases(namespace: &mut Namespace, silent: bool) -> Result<()> {
     may_connect_database(namespace, silent).await?;
     for namespace in namespace.namespaces.values_mut() {
@@ -24,28 +35,129 @@ pub async fn connect_databases(namespace: &mut Namespace, silent: bool) -> Resul
 
 pub async fn may_connect_database(namespace: &mut Namespace, silent: bool) -> Result<()> {
     if namespace.connector.is_none() { return Ok(()) }
+    let name = if namespace.path.is_empty() { "main".to_string() } else { namespace.path().join(".") };
+    let connector = namespace.connector.as_mut().unwrap();
+    connector.url = resolve_secrets(&connector.url, &Ctx::get().secret_providers).await.map_err(|err| {
+        Error::new(format!("cannot resolve the url of `{}`: {}", name, err.message))
+    })?;
     let connector = namespace.connector.as_ref().unwrap();
-    let connection = connection_for_connector(connector).await;
+    #[cfg(feature = "dynamodb")]
+    if url_scheme(&connector.url) == Some("dynamodb") {
+        dynamodb::check_models(namespace)?;
+    }
+    let retries = Ctx::connect_retries();
+    let mut attempt = 0;
+    let connection = loop {
+        match connection_for_connector(connector).await {
+            Ok(connection) => break connection,
+            Err(err) if attempt < retries => {
+                let delay = Duration::from_millis(500 * 2u64.pow(attempt.min(6)));
+                if !silent {
+                    info_message(format!("cannot connect to `{}` at \"{}\": {}, retrying in {}ms", name, redacted_url(&connector.url), err.message, delay.as_millis()));
+                }
+                sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) => Err(Error::new(format!("cannot connect to `{}` at \"{}\": {}", name, redacted_url(&connector.url), err.message)))?,
+        }
+    };
     if !silent {
-        info_message(format!("{} connector connected for `{}` at \"{}\"", connector.provider.lowercase_desc(), if namespace.path.is_empty() { "main".to_string() } else { namespace.path().join(".") }, connector.url));
+        let connector_desc = match url_scheme(&connector.url).filter(|_| !is_provider_connector(connector)) {
+            Some(scheme) => scheme,
+            None => connector.provider.lowercase_desc(),
+        };
+        info_message(format!("{} connector connected for `{}` at \"{}\"", connector_desc, name, redacted_url(&connector.url)));
     }
     namespace.connection = Some(connection);
     Ok(())
 }
 
-async fn connection_for_connector(connector: &Connector) -> Arc<dyn Connection> {
-    if connector.provider.is_mongo() {
-        Arc::new(MongoDBConnection::new(connector.url.as_str()).await)
-    } else {
-        Arc::new(SQLConnection::new(
-            match connector.provider {
-                Database::MongoDB => unreachable!(),
-                Database::MySQL => SQLDialect::MySQL,
-                Database::PostgreSQL => SQLDialect::PostgreSQL,
-                Database::SQLite => SQLDialect::SQLite,
-            },
-            connector.url.as_str(),
-            false,
-        ).await)
+/// Hide the password of a database url for printing.
+pub(crate) fn redacted_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        }
+        _ => url.to_owned(),
+    }
+}
+
+/// Whether a connector is connected by the connector of its provider, which
+/// runs SQL or MongoDB commands, rather than by a connector builder like the
+/// memory connector. Features which run commands of their own need it.
+pub(crate) fn is_provider_connector(connector: &Connector) -> bool {
+    url_scheme(&connector.url).map_or(true, |scheme| !Ctx::get().connector_builders.contains_key(scheme))
+}
+
+/// Connect with the connector's settings. Urls whose scheme has a registered
+/// connector builder are connected by it. The connectors panic when the
+/// database is unreachable, so the connection is made on a separate task and
+/// a panic is turned into an error.
+pub(crate) async fn connection_for_connector(connector: &Connector) -> Result<Arc<dyn Connection>> {
+    if let Some(builder) = url_scheme(&connector.url).and_then(|scheme| Ctx::get().connector_builders.get(scheme)) {
+        return builder.connect(connector.url.clone()).await;
+    }
+    let provider = connector.provider.clone();
+    let url = connector.url.clone();
+    let result = tokio::spawn(async move {
+        if provider.is_mongo() {
+            Arc::new(MongoDBConnection::new(url.as_str()).await) as Arc<dyn Connection>
+        } else {
+            Arc::new(SQLConnection::new(
+                match provider {
+                    Database::MongoDB => unreachable!(),
+                    Database::MySQL => SQLDialect::MySQL,
+                    Database::PostgreSQL => SQLDialect::PostgreSQL,
+                    Database::SQLite => SQLDialect::SQLite,
+                },
+                url.as_str(),
+                false,
+            ).await)
+        }
+    }).await;
+    result.map_err(|err| {
+        let message = if err.is_panic() {
+            let payload = err.into_panic();
+            payload.downcast_ref::<String>().cloned().or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string())).unwrap_or_else(|| "connector panicked".to_owned())
+        } else {
+            err.to_string()
+        };
+        Error::new(message)
+    })
+}
+/// The connection serving the namespace at `namespace_path`, the one of the
+/// closest enclosing namespace with a connector, and its database.
+pub(crate) fn namespace_connection(namespace_path: &[String]) -> Result<(Arc<dyn Connection>, Database)> {
+    let conn_ctx = Ctx::conn_ctx();
+    let (connection_path, connection) = conn_ctx.connections_iter()
+        .filter(|(connection_path, _)| namespace_path.starts_with(connection_path))
+        .max_by_key(|(connection_path, _)| connection_path.len())
+        .ok_or_else(|| Error::new(format!("no connection is found for `{}`", namespace_path.join("."))))?;
+    connection_with_database(&connection_path, connection.clone())
+}
+
+/// The connection of a model and its database.
+pub(crate) fn model_connection(model: &Model) -> Result<(Arc<dyn Connection>, Database)> {
+    let path = model.path();
+    let namespace_path: Vec<String> = path[..path.len() - 1].iter().map(|s| s.to_string()).collect();
+    namespace_connection(&namespace_path)
+}
+
+/// The connection of the main namespace, which tables of the server like
+/// sessions and magic link tokens are stored with.
+pub(crate) fn main_connection() -> Result<(Arc<dyn Connection>, Database)> {
+    let (connection_path, connection) = Ctx::conn_ctx().connections_iter()
+        .min_by_key(|(connection_path, _)| connection_path.len())
+        .ok_or_else(|| Error::new("no connection is found for the main namespace"))?;
+    connection_with_database(&connection_path, connection.clone())
+}
+
+fn connection_with_database(connection_path: &Vec<String>, connection: Arc<dyn Connection>) -> Result<(Arc<dyn Connection>, Database)> {
+    let namespace = Ctx::conn_ctx().namespace().namespace_at_path(&connection_path.iter().map(AsRef::as_ref).collect()).ok_or_else(|| Error::not_found())?;
+    let connector = namespace.connector.as_ref().ok_or_else(|| Error::new(format!("no connector is found for `{}`", connection_path.join("."))))?;
+    if !is_provider_connector(connector) {
+        Err(Error::new(format!("the {} connector of `{}` doesn't run SQL or MongoDB commands", url_scheme(&connector.url).unwrap_or_default(), if connection_path.is_empty() { "main".to_owned() } else { connection_path.join(".") })))?
     }
-}
\ No newline at end of file
+    Ok((connection, connector.provider.clone()))
+}
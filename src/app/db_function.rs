@@ -0,0 +1,78 @@
+use teo_result::{Error, Result};
+use teo_runtime::database::database::Database;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::server::query_tag::tagged;
+use crate::utils::db_functions::DbFunctionHandler;
+use crate::utils::sql::{identifier, sql_literal};
+
+/// Implement a handler declared with `from dbFunction(...)` in the schema.
+pub(crate) fn define_db_function_handler(main_namespace: &mut Namespace, handler: DbFunctionHandler) {
+    let DbFunctionHandler { namespace_path, name, function, many } = handler;
+    let namespace = main_namespace.namespace_mut_or_create_at_path(&namespace_path.iter().map(AsRef::as_ref).collect());
+    namespace.define_handler(name.as_str(), move |ctx: request::Ctx| {
+        let namespace_path = namespace_path.clone();
+        let function = function.clone();
+        async move { call_db_function(&namespace_path, &function, ctx.body(), many).await }
+    });
+}
+
+/// Call the database function `function` with the handler input and respond
+/// with the rows it returns, or with the first row unless `many`.
+///
+/// The input fields are passed as arguments in their declared order. On
+/// PostgreSQL they are passed by name, so the parameters of the function must
+/// be named like the input fields. MySQL calls the stored procedure with
+/// `CALL`. Raw statements can't bind parameters through the connectors, so
+/// the names are quoted as identifiers and the values as literals of the
+/// dialect with `sql_literal`.
+pub(crate) async fn call_db_function(namespace_path: &Vec<String>, function: &str, input: &Value, many: bool) -> Result<Response> {
+    if !function.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+        Err(Error::new(format!("invalid database function name `{}`", function)))?
+    }
+    let conn_ctx = Ctx::conn_ctx();
+    let Some((connection_path, connection)) = conn_ctx.connections_iter()
+        .filter(|(path, _)| namespace_path.starts_with(path))
+        .max_by_key(|(path, _)| path.len()) else {
+        Err(Error::new(format!("no connection is found for database function `{}`", function)))?
+    };
+    let namespace = conn_ctx.namespace().namespace_at_path(&connection_path.iter().map(AsRef::as_ref).collect()).ok_or_else(|| Error::not_found())?;
+    let statement = db_function_statement(namespace.connector.as_ref().map(|connector| &connector.provider), function, input)?;
+    let transaction = connection.no_transaction().await?;
+    let rows = transaction.query_raw(&Value::String(tagged(statement))).await.map_err(|e| {
+        Error::new(format!("database function `{}` failed: {}", function, e.message))
+    })?;
+    let rows = match rows {
+        Value::Array(rows) => rows,
+        Value::Null => vec![],
+        row => vec![row],
+    };
+    if many {
+        Ok(Response::data(Value::Array(rows)))
+    } else {
+        Ok(Response::data(rows.into_iter().next().unwrap_or(Value::Null)))
+    }
+}
+
+/// The statement which calls the database function with the input.
+pub(crate) fn db_function_statement(database: Option<&Database>, function: &str, input: &Value) -> Result<String> {
+    let arguments: Vec<(&String, &Value)> = match input {
+        Value::Dictionary(map) => map.iter().collect(),
+        Value::Null => vec![],
+        _ => Err(Error::invalid_request_message("expect the input of a database function handler to be an object"))?,
+    };
+    match database {
+        Some(database @ Database::PostgreSQL) => {
+            let arguments: Vec<String> = arguments.iter().map(|(name, value)| Ok(format!("{} => {}", identifier(name, database), sql_literal(value, database)?))).collect::<Result<_>>()?;
+            Ok(format!("SELECT * FROM {}({})", function, arguments.join(", ")))
+        }
+        Some(database @ Database::MySQL) => {
+            let arguments: Vec<String> = arguments.iter().map(|(_, value)| sql_literal(value, database)).collect::<Result<_>>()?;
+            Ok(format!("CALL {}({})", function, arguments.join(", ")))
+        }
+        _ => Err(Error::new("database function handlers are only supported by PostgreSQL and MySQL")),
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+use chrono::Utc;
+use key_path::path;
+use teo_result::Result;
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::stdlib::decorators::expires::model_expiry;
+
+/// How often expired records are swept.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many expired records are loaded and deleted at a time.
+const SWEEP_BATCH_SIZE: i64 = 500;
+
+/// Start deleting the expired records of models with `@@expires` in the
+/// background. Does nothing if no model expires records.
+pub(crate) fn start_expiry_sweeper(silent: bool) {
+    if expiring_models().is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            sweep_expired(silent).await;
+        }
+    });
+}
+
+/// Delete the expired records of models with `@@expires` once.
+pub(crate) async fn sweep_expired(silent: bool) {
+    for (model, after, field) in expiring_models() {
+        match sweep(model, after, &field).await {
+            Ok(0) => (),
+            Ok(count) => if !silent {
+                info_message(format!("deleted {} expired {} records", count, model.path().join(".")));
+            },
+            Err(err) => info_message(format!("cannot delete expired {} records: {}", model.path().join("."), err.message)),
+        }
+    }
+}
+
+fn expiring_models() -> Vec<(&'static Model, i64, String)> {
+    let mut models = vec![];
+    collect_expiring_models(Ctx::main_namespace(), &mut models);
+    models
+}
+
+fn collect_expiring_models(namespace: &'static Namespace, result: &mut Vec<(&'static Model, i64, String)>) {
+    for model in namespace.models.values() {
+        if let Some((after, field)) = model_expiry(model) {
+            result.push((model, after, field));
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_expiring_models(child, result);
+    }
+}
+
+async fn sweep(model: &'static Model, after: i64, field: &str) -> Result<usize> {
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let cutoff = Utc::now() - chrono::Duration::seconds(after);
+    let mut count = 0;
+    loop {
+        let objects: Vec<Object> = ctx.find_many(model, &teon!({
+            "where": { field: { "lt": Value::DateTime(cutoff) } },
+            "take": SWEEP_BATCH_SIZE,
+        }), None, path![]).await?;
+        if objects.is_empty() {
+            break;
+        }
+        for object in objects {
+            object.delete().await?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -2,6 +2,13 @@ pub mod app;
 pub mod ctx;
 pub mod callbacks;
 pub mod database;
+pub(crate) mod db_function;
+pub(crate) mod expiry;
+pub(crate) mod money;
+pub mod naming;
+pub(crate) mod plugin;
+pub mod scalar;
+pub mod secrets;
 
 pub use app::App;
 pub use ctx::Ctx;
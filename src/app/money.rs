@@ -0,0 +1,149 @@
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::app::scalar::ScalarCodec;
+
+/// The name of the builtin money scalar type.
+pub(crate) const MONEY_SCALAR: &str = "Money";
+
+/// An amount of money in the minor units of its currency, e.g. cents.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Money {
+    pub(crate) minor: i64,
+    pub(crate) currency: String,
+}
+
+impl Money {
+
+    /// Read a money value in the stored form `{ "minor": 1234, "currency":
+    /// "USD" }` or in the client form `{ "amount": "12.34", "currency": "USD" }`.
+    pub(crate) fn from_json(value: &JsonValue) -> Result<Self> {
+        let currency = value.get("currency").and_then(|c| c.as_str()).ok_or_else(|| Error::new("expect a money value to have a `currency`"))?;
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+            Err(Error::new(format!("invalid currency code `{}`, expect an ISO 4217 code like `USD`", currency)))?
+        }
+        let minor = match (value.get("minor"), value.get("amount")) {
+            (Some(minor), _) => minor.as_i64().ok_or_else(|| Error::new("expect `minor` to be an integer"))?,
+            (None, Some(JsonValue::String(amount))) => parse_amount(amount, currency)?,
+            (None, Some(JsonValue::Number(amount))) => parse_amount(&amount.to_string(), currency)?,
+            _ => Err(Error::new("expect a money value to have an `amount`"))?,
+        };
+        Ok(Self { minor, currency: currency.to_owned() })
+    }
+
+    /// The stored form.
+    pub(crate) fn to_stored_json(&self) -> JsonValue {
+        json!({ "minor": self.minor, "currency": self.currency })
+    }
+
+    /// The client form, the amount is a decimal string so that it doesn't lose
+    /// precision in JavaScript.
+    pub(crate) fn to_json(&self) -> JsonValue {
+        json!({ "amount": self.amount(), "currency": self.currency })
+    }
+
+    /// The amount as a decimal string, e.g. `"-12.34"`.
+    pub(crate) fn amount(&self) -> String {
+        let exponent = currency_exponent(&self.currency);
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let digits = self.minor.unsigned_abs().to_string();
+        if exponent == 0 {
+            return format!("{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = exponent + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - exponent);
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+
+    /// The amount for display, e.g. `"$1,234.50"` or `"1,234.50 CHF"`.
+    pub(crate) fn format(&self) -> String {
+        let amount = self.amount();
+        let (sign, amount) = amount.strip_prefix('-').map_or(("", amount.as_str()), |a| ("-", a));
+        let (integer, fraction) = amount.split_once('.').map_or((amount, None), |(i, f)| (i, Some(f)));
+        let mut grouped = String::new();
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        if let Some(fraction) = fraction {
+            grouped = format!("{}.{}", grouped, fraction);
+        }
+        match currency_symbol(&self.currency) {
+            Some(symbol) => format!("{}{}{}", sign, symbol, grouped),
+            None => format!("{}{} {}", sign, grouped, self.currency),
+        }
+    }
+}
+
+/// Sum amounts of the same currency. Amounts of different currencies cannot
+/// be added without exchange rates, so they are rejected.
+pub(crate) fn sum_money<'a>(values: impl Iterator<Item = &'a Money>) -> Result<Option<Money>> {
+    let mut result: Option<Money> = None;
+    for value in values {
+        match result.as_mut() {
+            None => result = Some(value.clone()),
+            Some(sum) if sum.currency != value.currency => {
+                Err(Error::invalid_request_message(format!("cannot sum amounts in {} and {}, filter the records by currency", sum.currency, value.currency)))?
+            }
+            Some(sum) => sum.minor = sum.minor.checked_add(value.minor).ok_or_else(|| Error::new("money sum overflows"))?,
+        }
+    }
+    Ok(result)
+}
+
+/// The codec of the builtin `Money` scalar type. Declare money fields as
+/// `Json` fields with `@money`.
+pub(crate) struct MoneyCodec;
+
+impl ScalarCodec for MoneyCodec {
+
+    fn parse(&self, value: &JsonValue) -> Result<JsonValue> {
+        Ok(Money::from_json(value)?.to_stored_json())
+    }
+
+    fn serialize(&self, value: &JsonValue) -> JsonValue {
+        Money::from_json(value).map(|money| money.to_json()).unwrap_or_else(|_| value.clone())
+    }
+
+    fn typescript_type(&self) -> String {
+        "{ amount: string, currency: string }".to_owned()
+    }
+}
+
+fn parse_amount(amount: &str, currency: &str) -> Result<i64> {
+    let exponent = currency_exponent(currency);
+    let (negative, unsigned) = amount.strip_prefix('-').map_or((false, amount), |a| (true, a));
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty() || !integer.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        Err(Error::new(format!("invalid amount `{}`", amount)))?
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > exponent {
+        Err(Error::new(format!("amount `{}` has more than {} decimal places, which {} doesn't allow", amount, exponent, currency)))?
+    }
+    let digits = format!("{}{:0<width$}", integer, fraction, width = exponent);
+    let minor: i64 = digits.parse().map_err(|_| Error::new(format!("amount `{}` is too large", amount)))?;
+    Ok(if negative { -minor } else { minor })
+}
+
+/// The number of decimal places of a currency by ISO 4217.
+fn currency_exponent(currency: &str) -> usize {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+fn currency_symbol(currency: &str) -> Option<&'static str> {
+    match currency {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" | "CNY" => Some("¥"),
+        "INR" => Some("₹"),
+        "KRW" => Some("₩"),
+        _ => None,
+    }
+}
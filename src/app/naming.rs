@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::traits::named::Named;
+use crate::stdlib::decorators::map::{is_field_mapped, is_model_mapped};
+
+/// How default table and column names are derived from model and field
+/// names. Names set explicitly in the schema are kept.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum NamingConvention {
+    /// Use the names of the schema, the default.
+    #[default]
+    AsIs,
+    /// `BlogPost` is stored as `blog_post`.
+    SnakeCase,
+    /// `BlogPost` is stored as `blogPost`.
+    CamelCase,
+}
+
+impl NamingConvention {
+
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            NamingConvention::AsIs => name.to_owned(),
+            NamingConvention::SnakeCase => words(name).join("_"),
+            NamingConvention::CamelCase => words(name).iter().enumerate().map(|(i, word)| if i == 0 { word.clone() } else { upper_first(word) }).collect(),
+        }
+    }
+}
+
+/// Derives a table name from a model path.
+pub type TableNameHook = Arc<dyn Fn(&Vec<&str>) -> String + Send + Sync>;
+
+/// Derives a column name from a model path and a field name.
+pub type ColumnNameHook = Arc<dyn Fn(&Vec<&str>, &str) -> String + Send + Sync>;
+
+/// The naming settings of an app.
+#[derive(Clone, Default)]
+pub struct Naming {
+    pub(crate) convention: NamingConvention,
+    pub(crate) table_prefix: Option<String>,
+    pub(crate) table_name_hook: Option<TableNameHook>,
+    pub(crate) column_name_hook: Option<ColumnNameHook>,
+}
+
+impl std::fmt::Debug for Naming {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Naming")
+            .field("convention", &self.convention)
+            .field("table_prefix", &self.table_prefix)
+            .field("table_name_hook", &self.table_name_hook.is_some())
+            .field("column_name_hook", &self.column_name_hook.is_some())
+            .finish()
+    }
+}
+
+impl Naming {
+
+    fn is_default(&self) -> bool {
+        self.convention == NamingConvention::AsIs && self.table_prefix.is_none() && self.table_name_hook.is_none() && self.column_name_hook.is_none()
+    }
+
+    fn table_name(&self, path: &Vec<&str>) -> String {
+        let name = match &self.table_name_hook {
+            Some(hook) => hook(path),
+            None => self.convention.apply(path.last().copied().unwrap_or_default()),
+        };
+        match &self.table_prefix {
+            Some(prefix) => format!("{}{}", prefix, name),
+            None => name,
+        }
+    }
+
+    fn column_name(&self, path: &Vec<&str>, field_name: &str) -> String {
+        match &self.column_name_hook {
+            Some(hook) => hook(path, field_name),
+            None => self.convention.apply(field_name),
+        }
+    }
+}
+
+/// Rename the tables and columns of a namespace whose names are not set with
+/// `@map` in the schema.
+pub(crate) fn apply_naming(namespace: &mut Namespace, naming: &Naming) {
+    if naming.is_default() {
+        return;
+    }
+    for model in namespace.models.values_mut() {
+        let path: Vec<String> = model.path().iter().map(|s| s.to_string()).collect();
+        let path: Vec<&str> = path.iter().map(|s| s.as_str()).collect();
+        if !is_model_mapped(model) {
+            model.table_name = naming.table_name(&path);
+        }
+        for field in model.fields.values_mut() {
+            if !is_field_mapped(field) {
+                field.column_name = naming.column_name(&path, field.name());
+            }
+        }
+    }
+    for child in namespace.namespaces.values_mut() {
+        apply_naming(child, naming);
+    }
+}
+
+/// Split a name into lowercase words at underscores and case changes, e.g.
+/// `HTTPRequest_id` into `http`, `request` and `id`.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = vec![];
+    let mut current = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '_' || *c == '-' || c.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue
+        }
+        let boundary = c.is_uppercase() && i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric() || chars.get(i + 1).map_or(false, |n| n.is_lowercase()) && chars[i - 1].is_uppercase());
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::utils::plugins::{PluginDeclaration, PluginRuntime, PluginTarget};
+#[cfg(feature = "wasm")]
+use crate::wasm::{WasmLimits, WasmPlugin};
+#[cfg(feature = "js")]
+use crate::js::JsScript;
+
+/// A call of a plugin function, from a JSON input to a JSON output. It blocks
+/// while the plugin runs.
+pub(crate) type PluginCall = Arc<dyn Fn(&JsonValue) -> Result<JsonValue> + Send + Sync>;
+
+/// Implement a handler or pipeline item declared with a module in the
+/// schema. WASM modules run with the default `WasmLimits`.
+pub(crate) fn define_plugin(main_namespace: &mut Namespace, declaration: PluginDeclaration) -> Result<()> {
+    let call = match declaration.runtime {
+        PluginRuntime::Wasm => wasm_call(&declaration)?,
+        PluginRuntime::Js => js_call(&declaration)?,
+    };
+    let namespace = main_namespace.namespace_mut_or_create_at_path(&declaration.namespace_path.iter().map(AsRef::as_ref).collect());
+    match declaration.target {
+        PluginTarget::Handler => define_plugin_handler(namespace, &declaration.name, call),
+        PluginTarget::PipelineItem => define_plugin_pipeline_item(namespace, &declaration.name, call),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "wasm")]
+fn wasm_call(declaration: &PluginDeclaration) -> Result<PluginCall> {
+    let plugin = WasmPlugin::load(&declaration.path, WasmLimits::default())?;
+    let export = declaration.export.clone();
+    Ok(Arc::new(move |input: &JsonValue| plugin.call(&export, input)))
+}
+
+#[cfg(not(feature = "wasm"))]
+fn wasm_call(declaration: &PluginDeclaration) -> Result<PluginCall> {
+    Err(Error::new(format!("`{}`: WASM modules require the `wasm` feature", declaration.name)))
+}
+
+#[cfg(feature = "js")]
+fn js_call(declaration: &PluginDeclaration) -> Result<PluginCall> {
+    let script = JsScript::load(&declaration.path)?;
+    let function = declaration.export.clone();
+    Ok(Arc::new(move |input: &JsonValue| script.call(&function, input)))
+}
+
+#[cfg(not(feature = "js"))]
+fn js_call(declaration: &PluginDeclaration) -> Result<PluginCall> {
+    Err(Error::new(format!("`{}`: JavaScript modules require the `js` feature", declaration.name)))
+}
+
+/// Define a pipeline item which replaces the pipeline value with the output
+/// of the plugin, which gets the value as JSON.
+pub(crate) fn define_plugin_pipeline_item(namespace: &mut Namespace, name: &str, call: PluginCall) {
+    namespace.define_pipeline_item(name, move |_args: Arguments, ctx: pipeline::Ctx| {
+        let call = call.clone();
+        async move {
+            let input = JsonValue::try_from(ctx.value())?;
+            let output = tokio::task::spawn_blocking(move || call(&input)).await.map_err(|e| Error::new(format!("{}", e)))??;
+            Ok(Value::from(output))
+        }
+    });
+}
+
+/// Define a handler which responds with the output of the plugin, which gets
+/// the validated input as JSON.
+pub(crate) fn define_plugin_handler(namespace: &mut Namespace, name: &str, call: PluginCall) {
+    namespace.define_handler(name, move |ctx: request::Ctx| {
+        let call = call.clone();
+        async move {
+            let input = JsonValue::try_from(ctx.body())?;
+            let output = tokio::task::spawn_blocking(move || call(&input)).await.map_err(|e| Error::new(format!("{}", e)))??;
+            Ok(Response::data(Value::from(output)))
+        }
+    });
+}
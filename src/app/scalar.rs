@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use serde_json::{Value as JsonValue};
+use teo_result::Result;
+use teo_runtime::database::database::Database;
+use crate::app::money::{MoneyCodec, MONEY_SCALAR};
+
+/// A codec backing a custom scalar type like `Money` or `PhoneNumber`. Fields
+/// are declared with the type through `@scalar("PhoneNumber")` on a `String`
+/// or `Json` field.
+///
+/// Inputs are parsed before validation, so codecs can reject bad values and
+/// normalize good ones in one place. Records are serialized before they are
+/// responded with.
+pub trait ScalarCodec: Send + Sync {
+
+    /// Validate and normalize an input value. Return an error with a message
+    /// for the client to reject it.
+    fn parse(&self, value: &JsonValue) -> Result<JsonValue>;
+
+    /// The value to respond with for a stored value.
+    fn serialize(&self, value: &JsonValue) -> JsonValue {
+        value.clone()
+    }
+
+    /// The column type of the field on `database`, e.g. `"NUMERIC(19, 4)"`.
+    /// The type of the declared field is used if it's `None`.
+    fn database_type(&self, _database: &Database) -> Option<String> {
+        None
+    }
+
+    /// The TypeScript type of the field in generated clients.
+    fn typescript_type(&self) -> String {
+        "string".to_owned()
+    }
+}
+
+/// The builtin scalar types. `Money` stores an amount with its currency.
+pub(crate) fn builtin_scalars() -> BTreeMap<String, Arc<dyn ScalarCodec>> {
+    let mut scalars: BTreeMap<String, Arc<dyn ScalarCodec>> = BTreeMap::new();
+    scalars.insert(MONEY_SCALAR.to_owned(), Arc::new(MoneyCodec));
+    scalars
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use regex::Regex;
+use teo_result::{Error, Result};
+
+/// Resolves secret references of one kind, e.g. a secret manager. Returns
+/// `None` when the secret doesn't exist.
+pub trait SecretProvider: Send + Sync {
+    fn resolve(&self, key: String) -> BoxFuture<'static, Result<Option<String>>>;
+}
+
+impl<F, Fut> SecretProvider for F where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Option<String>>> + Send + 'static {
+    fn resolve(&self, key: String) -> BoxFuture<'static, Result<Option<String>>> {
+        Box::pin(self(key))
+    }
+}
+
+/// The builtin providers. `env` reads an environment variable, `file` reads a
+/// file like a mounted Docker or Kubernetes secret.
+pub(crate) fn builtin_secret_providers() -> BTreeMap<String, Arc<dyn SecretProvider>> {
+    let mut providers: BTreeMap<String, Arc<dyn SecretProvider>> = BTreeMap::new();
+    providers.insert("env".to_owned(), Arc::new(|key: String| async move {
+        Ok(std::env::var(&key).ok())
+    }));
+    providers.insert("file".to_owned(), Arc::new(|key: String| async move {
+        Ok(tokio::fs::read_to_string(&key).await.ok().map(|s| s.trim_end().to_owned()))
+    }));
+    providers
+}
+
+/// Replace the `${provider:key}` references in `text` with resolved secrets.
+/// All missing secrets are reported together.
+pub(crate) async fn resolve_secrets(text: &str, providers: &BTreeMap<String, Arc<dyn SecretProvider>>) -> Result<String> {
+    let regex = Regex::new(r"\$\{([A-Za-z][A-Za-z0-9_-]*):([^}]+)\}").unwrap();
+    let mut result = String::new();
+    let mut last = 0;
+    let mut problems = vec![];
+    for captures in regex.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        result.push_str(&text[last..whole.start()]);
+        last = whole.end();
+        let (provider_name, key) = (&captures[1], captures[2].trim());
+        let Some(provider) = providers.get(provider_name) else {
+            problems.push(format!("unknown secret provider `{}`", provider_name));
+            continue
+        };
+        match provider.resolve(key.to_owned()).await? {
+            Some(value) => result.push_str(&value),
+            None => problems.push(format!("`{}:{}` is not set", provider_name, key)),
+        }
+    }
+    result.push_str(&text[last..]);
+    if problems.is_empty() {
+        Ok(result)
+    } else {
+        Err(Error::new(problems.join(", ")))
+    }
+}
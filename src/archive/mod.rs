@@ -0,0 +1,194 @@
+pub(crate) mod s3;
+
+use std::path::PathBuf;
+use std::time::Duration;
+use chrono::Utc;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::archive::s3::S3Bucket;
+use crate::events::object_json;
+use crate::message::info_message;
+use crate::stdlib::decorators::archive::{model_archive, ArchivePolicy};
+
+/// How often old records are archived.
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How many records are written to an archive file.
+const ARCHIVE_BATCH_SIZE: i64 = 1000;
+
+/// Where archive files are stored.
+enum Store {
+    File(PathBuf),
+    S3 { bucket: S3Bucket, prefix: String },
+}
+
+impl Store {
+
+    fn from_url(url: &str) -> Result<Self> {
+        if let Some(path) = url.strip_prefix("file://") {
+            Ok(Store::File(PathBuf::from(path)))
+        } else if let Some(rest) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            Ok(Store::S3 { bucket: S3Bucket::new(bucket), prefix: prefix.trim_matches('/').to_owned() })
+        } else {
+            Err(Error::new(format!("unsupported archive destination `{}`", url)))
+        }
+    }
+
+    async fn put(&self, key: &str, content: Vec<u8>) -> Result<()> {
+        match self {
+            Store::File(base) => {
+                let path = base.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await.map_err(|e| Error::new(format!("{}", e)))?;
+                }
+                tokio::fs::write(path, content).await.map_err(|e| Error::new(format!("{}", e)))
+            }
+            Store::S3 { bucket, prefix } => bucket.put(&prefixed(prefix, key), content).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match self {
+            Store::File(base) => tokio::fs::read(base.join(key)).await.map_err(|e| Error::new(format!("{}", e))),
+            Store::S3 { bucket, prefix } => bucket.get(&prefixed(prefix, key)).await,
+        }
+    }
+
+    /// The keys of the files in `dir`, relative to the store.
+    async fn list(&self, dir: &str) -> Result<Vec<String>> {
+        let mut keys = match self {
+            Store::File(base) => {
+                let mut keys = vec![];
+                let Ok(mut entries) = tokio::fs::read_dir(base.join(dir)).await else { return Ok(keys) };
+                while let Some(entry) = entries.next_entry().await.map_err(|e| Error::new(format!("{}", e)))? {
+                    keys.push(format!("{}/{}", dir, entry.file_name().to_string_lossy()));
+                }
+                keys
+            }
+            Store::S3 { bucket, prefix } => {
+                let full_prefix = prefixed(prefix, &format!("{}/", dir));
+                let strip = prefixed(prefix, "");
+                bucket.list(&full_prefix).await?.into_iter().map(|key| key.strip_prefix(&strip).unwrap_or(&key).to_owned()).collect()
+            }
+        };
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+fn prefixed(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_owned() } else { format!("{}/{}", prefix, key) }
+}
+
+/// Start archiving the old records of models with `@@archive` in the
+/// background. Does nothing if no model archives records.
+pub(crate) fn start_archiver(silent: bool) {
+    let mut models = vec![];
+    collect_archived_models(Ctx::main_namespace(), &mut models);
+    if models.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ARCHIVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            for (model, policy) in &models {
+                match archive(model, policy, silent).await {
+                    Ok(0) => (),
+                    Ok(count) => if !silent {
+                        info_message(format!("archived {} {} records", count, model.path().join(".")));
+                    },
+                    Err(err) => info_message(format!("cannot archive {} records: {}", model.path().join("."), err.message)),
+                }
+            }
+        }
+    });
+}
+
+fn collect_archived_models(namespace: &'static Namespace, result: &mut Vec<(&'static Model, ArchivePolicy)>) {
+    for model in namespace.models.values() {
+        if let Some(policy) = model_archive(model) {
+            result.push((model, policy));
+        }
+    }
+    for child in namespace.namespaces.values() {
+        collect_archived_models(child, result);
+    }
+}
+
+/// Write the records older than the policy allows to JSONL files, one file
+/// per batch, and delete them once their file is stored. A record is deleted
+/// only after it's archived, so a failure can archive a record twice but
+/// never lose it.
+pub(crate) async fn archive(model: &'static Model, policy: &ArchivePolicy, silent: bool) -> Result<usize> {
+    let store = Store::from_url(&policy.to)?;
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let cutoff = Utc::now() - chrono::Duration::seconds(policy.after);
+    let dir = model.path().join(".");
+    let mut count = 0;
+    loop {
+        let objects: Vec<Object> = ctx.find_many(model, &teon!({
+            "where": { policy.field.as_str(): { "lt": Value::DateTime(cutoff) } },
+            "orderBy": { policy.field.as_str(): "asc" },
+            "take": ARCHIVE_BATCH_SIZE,
+        }), None, path![]).await?;
+        if objects.is_empty() {
+            break;
+        }
+        let mut content = String::new();
+        for object in &objects {
+            content.push_str(&object_json(object)?.to_string());
+            content.push('\n');
+        }
+        let key = format!("{}/{}-{}.jsonl", dir, Utc::now().format("%Y%m%dT%H%M%S"), uuid::Uuid::new_v4());
+        store.put(&key, content.into_bytes()).await?;
+        if !silent {
+            info_message(format!("archived {} {} records to {}", objects.len(), dir, key));
+        }
+        for object in objects {
+            object.delete().await?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Restore archived records of a model. `keys` are the archive files to
+/// restore, like `blog.Post/20240101T000000-<uuid>.jsonl`; all files of the
+/// model are restored if it's empty. Files are kept, restoring a file twice
+/// fails on the unique constraints of the records.
+pub(crate) async fn restore(model: &'static Model, keys: &Vec<String>, silent: bool) -> Result<usize> {
+    let policy = model_archive(model).ok_or_else(|| Error::new(format!("`{}` is not archived", model.path().join("."))))?;
+    let store = Store::from_url(&policy.to)?;
+    let keys = if keys.is_empty() { store.list(&model.path().join(".")).await? } else { keys.clone() };
+    let create = builtin_action_handler_from_name("create").ok_or_else(|| Error::not_found())?;
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let mut count = 0;
+    for key in keys {
+        let content = store.get(&key).await?;
+        let content = String::from_utf8(content).map_err(|_| Error::new(format!("archive file `{}` isn't UTF-8", key)))?;
+        let mut restored = 0;
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let record: JsonValue = serde_json::from_str(line).map_err(|e| Error::new(format!("invalid archive file `{}`: {}", key, e)))?;
+            let input = validate_and_transform_json_input_for_builtin_action(model, create, &json!({ "create": record }), Ctx::main_namespace())?;
+            let object = ctx.create_object(model, input.get("create").unwrap_or(&Value::Null), None).await?;
+            object.save().await.map_err(|e| Error::new(format!("cannot restore a record of `{}`: {}", key, e.message)))?;
+            restored += 1;
+        }
+        if !silent {
+            info_message(format!("restored {} records from {}", restored, key));
+        }
+        count += restored;
+    }
+    Ok(count)
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+use chrono::Utc;
+use regex::Regex;
+use ring::digest::{digest, SHA256};
+use ring::hmac;
+use teo_result::{Error, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A bucket of S3 or an S3 compatible storage. Credentials are read from
+/// `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`, the region from
+/// `AWS_REGION`, and `AWS_ENDPOINT_URL` points to a compatible storage like
+/// MinIO. Requests are signed with AWS Signature Version 4 and use path style
+/// urls.
+#[derive(Debug, Clone)]
+pub(crate) struct S3Bucket {
+    bucket: String,
+}
+
+impl S3Bucket {
+
+    pub(crate) fn new(bucket: &str) -> Self {
+        Self { bucket: bucket.to_owned() }
+    }
+
+    pub(crate) async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        self.request("PUT", key, vec![], body).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.request("GET", key, vec![], vec![]).await
+    }
+
+    /// The keys starting with `prefix`.
+    pub(crate) async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let key_regex = Regex::new(r"<Key>([^<]*)</Key>").unwrap();
+        let token_regex = Regex::new(r"<NextContinuationToken>([^<]*)</NextContinuationToken>").unwrap();
+        let mut keys = vec![];
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type".to_owned(), "2".to_owned()), ("prefix".to_owned(), prefix.to_owned())];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token".to_owned(), token.clone()));
+            }
+            let body = self.request("GET", "", query, vec![]).await?;
+            let body = String::from_utf8_lossy(&body).to_string();
+            keys.extend(key_regex.captures_iter(&body).map(|c| unescape_xml(&c[1])));
+            match token_regex.captures(&body) {
+                Some(captures) => continuation_token = Some(unescape_xml(&captures[1])),
+                None => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    async fn request(&self, method: &str, key: &str, mut query: Vec<(String, String)>, body: Vec<u8>) -> Result<Vec<u8>> {
+        let access_key = std::env::var("AWS_ACCESS_KEY_ID").map_err(|_| Error::new("s3: AWS_ACCESS_KEY_ID is not set"))?;
+        let secret_key = std::env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| Error::new("s3: AWS_SECRET_ACCESS_KEY is not set"))?;
+        let region = std::env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_owned());
+        let endpoint = std::env::var("AWS_ENDPOINT_URL").unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = endpoint.trim_end_matches('/');
+        let host = endpoint.split("://").nth(1).unwrap_or(endpoint).to_owned();
+        let path = if key.is_empty() {
+            format!("/{}", uri_encode(&self.bucket, false))
+        } else {
+            format!("/{}/{}", uri_encode(&self.bucket, false), uri_encode(key, false))
+        };
+        query.sort();
+        let query_string = query.iter().map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true))).collect::<Vec<String>>().join("&");
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(digest(&SHA256, &body).as_ref());
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, query_string, host, payload_hash, amz_date, payload_hash,
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, region);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(digest(&SHA256, canonical_request.as_bytes()).as_ref()));
+        let mut signing_key = hmac_sign(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+        for part in [region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sign(&signing_key, part.as_bytes());
+        }
+        let signature = hex(&hmac_sign(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}", access_key, scope, signature);
+        let url = if query_string.is_empty() { format!("{}{}", endpoint, path) } else { format!("{}{}?{}", endpoint, path, query_string) };
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build().map_err(|e| Error::new(format!("s3: {}", e)))?;
+        let method = reqwest::Method::from_bytes(method.as_bytes()).unwrap();
+        let response = client.request(method, &url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization)
+            .body(body)
+            .send().await.map_err(|e| Error::new(format!("s3: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let mut error = Error::new(format!("s3: {} responds with {}", url, status));
+            error.code = status.as_u16();
+            Err(error)?
+        }
+        Ok(response.bytes().await.map_err(|e| Error::new(format!("s3: {}", e)))?.to_vec())
+    }
+}
+
+fn hmac_sign(key: &[u8], message: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), message).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent encode like AWS expects, `/` is kept in paths.
+fn uri_encode(string: &str, encode_slash: bool) -> String {
+    string.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        b'/' if !encode_slash => "/".to_owned(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+fn unescape_xml(string: &str) -> String {
+    string.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
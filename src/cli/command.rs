@@ -1,7 +1,13 @@
+use std::collections::BTreeMap;
+
 #[derive(Debug)]
 pub(crate) struct ServeCommand {
     pub(crate) no_migration: bool,
     pub(crate) no_autoseed: bool,
+    pub(crate) watch: bool,
+    pub(crate) verify_schema: bool,
+    pub(crate) grpc: Option<u16>,
+    pub(crate) grpc_package: String,
     pub(crate) env: Option<String>,
 }
 
@@ -10,26 +16,47 @@ pub(crate) enum GenerateCommand {
     GenerateClientCommand(GenerateClientCommand),
     GenerateEntityCommand(GenerateEntityCommand),
     GenerateAdminCommand(GenerateAdminCommand),
+    GenerateMobileCommand(GenerateMobileCommand),
+    GenerateProtoCommand(GenerateProtoCommand),
 }
 
 #[derive(Debug)]
 pub(crate) struct GenerateClientCommand {
     pub(crate) all: bool,
+    pub(crate) full: bool,
+    pub(crate) hooks: Option<String>,
     pub(crate) names: Option<Vec<String>>,
 }
 
 #[derive(Debug)]
 pub(crate) struct GenerateEntityCommand {
     pub(crate) all: bool,
+    pub(crate) full: bool,
     pub(crate) names: Option<Vec<String>>,
 }
 
 #[derive(Debug)]
 pub(crate) struct GenerateAdminCommand { }
 
+#[derive(Debug)]
+pub(crate) struct GenerateMobileCommand {
+    pub(crate) language: String,
+    pub(crate) dest: String,
+    pub(crate) package: String,
+    pub(crate) host: String,
+    pub(crate) full: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct GenerateProtoCommand {
+    pub(crate) dest: String,
+    pub(crate) package: String,
+}
+
 #[derive(Debug)]
 pub(crate) struct MigrateCommand {
     pub(crate) dry: bool,
+    pub(crate) check: bool,
 }
 
 #[derive(Debug)]
@@ -37,6 +64,14 @@ pub(crate) struct SeedCommand {
     pub(crate) action: SeedCommandAction,
     pub(crate) all: bool,
     pub(crate) names: Option<Vec<String>>,
+    pub(crate) fake: Option<FakeOptions>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FakeOptions {
+    pub(crate) count: usize,
+    pub(crate) counts: BTreeMap<String, usize>,
+    pub(crate) seed: Option<u64>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -49,9 +84,64 @@ pub(crate) enum SeedCommandAction {
 #[derive(Debug)]
 pub(crate) struct PurgeCommand { }
 
+#[derive(Debug)]
+pub(crate) enum DbCommand {
+    DbCheckCommand(DbCheckCommand),
+}
+
+#[derive(Debug)]
+pub(crate) struct DbCheckCommand {
+    pub(crate) fix: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct RefreshCommand {
+    pub(crate) names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RestoreCommand {
+    pub(crate) name: String,
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct SequenceCommand {
+    pub(crate) name: String,
+    pub(crate) order_by: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ExportCommand {
+    pub(crate) name: String,
+    pub(crate) format: String,
+    pub(crate) out: String,
+    pub(crate) r#where: Option<String>,
+    pub(crate) after: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ConsoleCommand { }
+
+#[derive(Debug)]
+pub(crate) struct RoutesCommand {
+    pub(crate) json: bool,
+}
+
 #[derive(Debug)]
 pub(crate) struct LintCommand { }
 
+#[derive(Debug)]
+pub(crate) struct AdviseCommand { }
+
+#[derive(Debug)]
+pub(crate) struct FmtCommand {
+    pub(crate) check: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct LspCommand { }
+
 #[derive(Debug)]
 pub(crate) struct RunCommand {
     pub(crate) list: bool,
@@ -78,7 +168,17 @@ pub(crate) enum CLICommand {
     Migrate(MigrateCommand),
     Seed(SeedCommand),
     Purge(PurgeCommand),
+    Db(DbCommand),
+    Refresh(RefreshCommand),
+    Restore(RestoreCommand),
+    Sequence(SequenceCommand),
+    Export(ExportCommand),
+    Console(ConsoleCommand),
+    Routes(RoutesCommand),
     Lint(LintCommand),
+    Advise(AdviseCommand),
+    Fmt(FmtCommand),
+    Lsp(LspCommand),
     Run(RunCommand),
 }
 
@@ -88,6 +188,16 @@ impl CLICommand {
         match self {
             CLICommand::Generate(_) => true,
             CLICommand::Lint(_) => true,
+            CLICommand::Advise(_) => true,
+            CLICommand::Fmt(_) => true,
+            CLICommand::Lsp(_) => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn ignores_schema(&self) -> bool {
+        match self {
+            CLICommand::Lsp(_) => true,
             _ => false,
         }
     }
@@ -2,7 +2,7 @@ use std::env;
 use clap::{Arg, ArgAction, Command as ClapCommand};
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
-use crate::cli::command::{CLI, CLICommand, GenerateAdminCommand, GenerateClientCommand, GenerateCommand, GenerateEntityCommand, LintCommand, MigrateCommand, PurgeCommand, RunCommand, SeedCommand, SeedCommandAction, ServeCommand};
+use crate::cli::command::{AdviseCommand, CLI, CLICommand, GenerateAdminCommand, GenerateClientCommand, GenerateCommand, GenerateEntityCommand, GenerateMobileCommand, GenerateProtoCommand, ConsoleCommand, DbCheckCommand, DbCommand, ExportCommand, FakeOptions, FmtCommand, LintCommand, LspCommand, MigrateCommand, PurgeCommand, RefreshCommand, RestoreCommand, RoutesCommand, RunCommand, SeedCommand, SeedCommandAction, SequenceCommand, ServeCommand};
 
 pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: Option<Vec<String>>) -> CLI {
     let argv = argv.unwrap_or(env::args_os().map(|s| s.to_str().unwrap().to_owned()).collect());
@@ -55,7 +55,28 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                 .short('S')
                 .long("no-autoseed")
                 .help("Start server without auto seeding autoseed dataset")
-                .action(ArgAction::SetTrue)))
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("watch")
+                .short('w')
+                .long("watch")
+                .help("Restart the server when schema files change")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("verify-schema")
+                .long("verify-schema")
+                .help("Check that the databases match the schema before starting the server")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("grpc")
+                .long("grpc")
+                .help("Serve the services of `teo generate proto` on this port, requires the grpc feature")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u16))
+                .num_args(1))
+            .arg(Arg::new("grpc-package")
+                .long("grpc-package")
+                .help("The protobuf package name of the gRPC services")
+                .action(ArgAction::Set)
+                .default_value("teo")
+                .num_args(1)))
         .subcommand(ClapCommand::new("generate")
             .about("Generate code")
             .arg_required_else_help(true)
@@ -67,6 +88,16 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                     .help("Generate all clients")
                     .action(ArgAction::SetTrue)
                     .conflicts_with("NAME"))
+                .arg(Arg::new("full")
+                    .short('f')
+                    .long("full")
+                    .help("Rewrite all generated files instead of only changed ones")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("hooks")
+                    .long("hooks")
+                    .help("Generate React hooks for TypeScript clients, react-query or swr")
+                    .action(ArgAction::Set)
+                    .num_args(1))
                 .arg(Arg::new("NAME")
                     .action(ArgAction::Append)
                     .conflicts_with("all")
@@ -81,6 +112,11 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                     .help("Generate all entities")
                     .action(ArgAction::SetTrue)
                     .conflicts_with("NAME"))
+                .arg(Arg::new("full")
+                    .short('f')
+                    .long("full")
+                    .help("Rewrite all generated files instead of only changed ones")
+                    .action(ArgAction::SetTrue))
                 .arg(Arg::new("NAME")
                     .action(ArgAction::Append)
                     .conflicts_with("all")
@@ -88,14 +124,63 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                     .num_args(1..)))
             .subcommand(ClapCommand::new("admin")
                 .about("Generate admin dashboard")
-                .arg_required_else_help(false)))
+                .arg_required_else_help(false))
+            .subcommand(ClapCommand::new("mobile")
+                .about("Generate Kotlin or Swift client")
+                .arg(Arg::new("LANGUAGE")
+                    .help("The client language, kotlin or swift")
+                    .required(true)
+                    .num_args(1))
+                .arg(Arg::new("dest")
+                    .short('d')
+                    .long("dest")
+                    .help("The destination directory")
+                    .action(ArgAction::Set)
+                    .required(true)
+                    .num_args(1))
+                .arg(Arg::new("package")
+                    .short('p')
+                    .long("package")
+                    .help("The Kotlin package name")
+                    .action(ArgAction::Set)
+                    .default_value("teo.client")
+                    .num_args(1))
+                .arg(Arg::new("host")
+                    .long("host")
+                    .help("The default server host of the client")
+                    .action(ArgAction::Set)
+                    .default_value("http://localhost:5050")
+                    .num_args(1))
+                .arg(Arg::new("full")
+                    .short('f')
+                    .long("full")
+                    .help("Rewrite all generated files instead of only changed ones")
+                    .action(ArgAction::SetTrue)))
+            .subcommand(ClapCommand::new("proto")
+                .about("Generate a .proto file for gRPC clients")
+                .arg(Arg::new("dest")
+                    .short('d')
+                    .long("dest")
+                    .help("The destination directory")
+                    .action(ArgAction::Set)
+                    .required(true)
+                    .num_args(1))
+                .arg(Arg::new("package")
+                    .short('p')
+                    .long("package")
+                    .help("The protobuf package name")
+                    .action(ArgAction::Set)
+                    .default_value("teo")
+                    .num_args(1))))
         .subcommand(ClapCommand::new("migrate")
             .about("Run migration")
             .arg(Arg::new("dry")
                 .short('d')
                 .long("dry")
                 .help("Dry run")
-                .action(ArgAction::SetTrue)))
+                .action(ArgAction::SetTrue))
+            .subcommand(ClapCommand::new("check")
+                .about("Classify pending schema changes as compatible or breaking, fail on breaking ones")))
         .subcommand(ClapCommand::new("seed")
             .about("Seed data")
             .arg(Arg::new("unseed")
@@ -118,11 +203,121 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                 .action(ArgAction::Append)
                 .conflicts_with("all")
                 .help("Data set names to process")
-                .num_args(1..)))
+                .num_args(1..))
+            .arg(Arg::new("fake")
+                .long("fake")
+                .help("Insert this many random records into each model")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with_all(["NAME", "all", "unseed", "reseed"])
+                .num_args(1))
+            .arg(Arg::new("count")
+                .long("count")
+                .help("Override the random record count of a model, e.g. User=50")
+                .action(ArgAction::Append)
+                .requires("fake")
+                .num_args(1))
+            .arg(Arg::new("fake-seed")
+                .long("fake-seed")
+                .help("Seed the random generator to insert the same records each time")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .requires("fake")
+                .num_args(1)))
         .subcommand(ClapCommand::new("purge")
             .about("Purge and clear the database without dropping tables."))
+        .subcommand(ClapCommand::new("db")
+            .about("Inspect the data of the databases")
+            .arg_required_else_help(true)
+            .subcommand(ClapCommand::new("check")
+                .about("Report orphaned references and duplicate unique values, fail if any is found")
+                .arg(Arg::new("fix")
+                    .long("fix")
+                    .help("Fix orphaned references by the onDelete rules of their relations")
+                    .action(ArgAction::SetTrue))))
+        .subcommand(ClapCommand::new("refresh")
+            .about("Refresh materialized view models")
+            .arg(Arg::new("NAME")
+                .action(ArgAction::Append)
+                .required(true)
+                .help("Model names to refresh, e.g. blog.Stats")
+                .num_args(1..)))
+        .subcommand(ClapCommand::new("restore")
+            .about("Restore archived records of a model")
+            .arg(Arg::new("NAME")
+                .required(true)
+                .help("The model name, e.g. blog.Post")
+                .num_args(1))
+            .arg(Arg::new("KEY")
+                .action(ArgAction::Append)
+                .help("Archive files to restore, all files of the model if omitted")
+                .num_args(0..)))
+        .subcommand(ClapCommand::new("sequence")
+            .about("Number the existing records of a sequence field")
+            .arg(Arg::new("NAME")
+                .required(true)
+                .help("The field name, e.g. billing.Invoice.number")
+                .num_args(1))
+            .arg(Arg::new("order-by")
+                .long("order-by")
+                .help("The field to number records in the order of, e.g. createdAt")
+                .action(ArgAction::Set)
+                .num_args(1)))
+        .subcommand(ClapCommand::new("export")
+            .about("Export the records of a model as Apache Arrow or Parquet")
+            .arg(Arg::new("NAME")
+                .required(true)
+                .help("The model name, e.g. blog.Post")
+                .num_args(1))
+            .arg(Arg::new("format")
+                .short('f')
+                .long("format")
+                .help("The format, arrow or parquet")
+                .action(ArgAction::Set)
+                .value_parser(["arrow", "parquet"])
+                .default_value("parquet")
+                .num_args(1))
+            .arg(Arg::new("out")
+                .short('o')
+                .long("out")
+                .help("The file to write")
+                .action(ArgAction::Set)
+                .required(true)
+                .num_args(1))
+            .arg(Arg::new("where")
+                .short('w')
+                .long("where")
+                .help("A JSON where filter of the records to export")
+                .action(ArgAction::Set)
+                .num_args(1))
+            .arg(Arg::new("after")
+                .short('a')
+                .long("after")
+                .help("Resume an export after the cursor token reported by a failed one")
+                .action(ArgAction::Set)
+                .num_args(1)))
+        .subcommand(ClapCommand::new("console")
+            .about("Run model actions and pipelines interactively"))
+        .subcommand(ClapCommand::new("routes")
+            .about("List the endpoints of the server")
+            .arg(Arg::new("json")
+                .short('j')
+                .long("json")
+                .help("Print the endpoints as JSON")
+                .action(ArgAction::SetTrue)))
         .subcommand(ClapCommand::new("lint")
             .about("Lint the schema files"))
+        .subcommand(ClapCommand::new("advise")
+            .about("Suggest indexes for the queries observed in development"))
+        .subcommand(ClapCommand::new("fmt")
+            .about("Format the schema files")
+            .arg(Arg::new("check")
+                .short('c')
+                .long("check")
+                .help("Check whether the schema files are formatted without writing them")
+                .action(ArgAction::SetTrue)))
+        .subcommand(ClapCommand::new("lsp")
+            .about("Start the language server over stdio"))
         .subcommand(ClapCommand::new("run")
             .about("Run a defined program")
             .arg(Arg::new("list")
@@ -150,26 +345,41 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
     let command = match matches.subcommand() {
         Some(("serve", submatches)) => {
             let env: Option<&String> = submatches.get_one("ENV");
-            CLICommand::Serve(ServeCommand { no_migration: submatches.get_flag("no-migration"), no_autoseed: submatches.get_flag("no-autoseed"), env: env.cloned() })
+            CLICommand::Serve(ServeCommand { no_migration: submatches.get_flag("no-migration"), no_autoseed: submatches.get_flag("no-autoseed"), watch: submatches.get_flag("watch"), verify_schema: submatches.get_flag("verify-schema"), grpc: submatches.get_one::<u16>("grpc").copied(), grpc_package: submatches.get_one::<String>("grpc-package").unwrap().clone(), env: env.cloned() })
         }
         Some(("generate", submatches)) => {
             match submatches.subcommand() {
                 Some(("client", submatches)) => {
                     let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
-                    CLICommand::Generate(GenerateCommand::GenerateClientCommand(GenerateClientCommand { all: submatches.get_flag("all"), names }))
+                    CLICommand::Generate(GenerateCommand::GenerateClientCommand(GenerateClientCommand { all: submatches.get_flag("all"), full: submatches.get_flag("full"), hooks: submatches.get_one::<String>("hooks").cloned(), names }))
                 }
                 Some(("entity", submatches)) => {
                     let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
-                    CLICommand::Generate(GenerateCommand::GenerateEntityCommand(GenerateEntityCommand { all: submatches.get_flag("all"), names }))
+                    CLICommand::Generate(GenerateCommand::GenerateEntityCommand(GenerateEntityCommand { all: submatches.get_flag("all"), full: submatches.get_flag("full"), names }))
                 }
                 Some(("admin", _)) => {
                     CLICommand::Generate(GenerateCommand::GenerateAdminCommand(GenerateAdminCommand {}))
                 }
+                Some(("mobile", submatches)) => {
+                    CLICommand::Generate(GenerateCommand::GenerateMobileCommand(GenerateMobileCommand {
+                        language: submatches.get_one::<String>("LANGUAGE").unwrap().clone(),
+                        dest: submatches.get_one::<String>("dest").unwrap().clone(),
+                        package: submatches.get_one::<String>("package").unwrap().clone(),
+                        host: submatches.get_one::<String>("host").unwrap().clone(),
+                        full: submatches.get_flag("full"),
+                    }))
+                }
+                Some(("proto", submatches)) => {
+                    CLICommand::Generate(GenerateCommand::GenerateProtoCommand(GenerateProtoCommand {
+                        dest: submatches.get_one::<String>("dest").unwrap().clone(),
+                        package: submatches.get_one::<String>("package").unwrap().clone(),
+                    }))
+                }
                 _ => unreachable!()
             }
         }
         Some(("migrate", submatches)) => {
-            CLICommand::Migrate(MigrateCommand { dry: submatches.get_flag("dry") })
+            CLICommand::Migrate(MigrateCommand { dry: submatches.get_flag("dry"), check: submatches.subcommand_matches("check").is_some() })
         }
         Some(("seed", submatches)) => {
             let action = if submatches.get_flag("reseed") {
@@ -180,18 +390,72 @@ pub(crate) fn parse(runtime_version: RuntimeVersion, entrance: Entrance, argv: O
                 SeedCommandAction::Seed
             };
             let names: Option<Vec<String>> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect::<Vec<String>>());
+            let fake = submatches.get_one::<usize>("fake").map(|count| FakeOptions {
+                count: *count,
+                counts: submatches.get_many::<String>("count").map(|counts| counts.filter_map(|c| {
+                    let (model, count) = c.split_once('=')?;
+                    Some((model.to_owned(), count.parse().ok()?))
+                }).collect()).unwrap_or_default(),
+                seed: submatches.get_one::<u64>("fake-seed").copied(),
+            });
             CLICommand::Seed(SeedCommand {
                 action,
                 all: submatches.get_flag("all"),
                 names,
+                fake,
             })
         }
         Some(("purge", _submatches)) => {
             CLICommand::Purge(PurgeCommand { })
         }
+        Some(("db", submatches)) => {
+            match submatches.subcommand() {
+                Some(("check", submatches)) => {
+                    CLICommand::Db(DbCommand::DbCheckCommand(DbCheckCommand { fix: submatches.get_flag("fix") }))
+                }
+                _ => unreachable!()
+            }
+        }
+        Some(("refresh", submatches)) => {
+            let names: Vec<String> = submatches.get_many::<String>("NAME").map(|s| s.map(|v| v.to_string()).collect()).unwrap_or_default();
+            CLICommand::Refresh(RefreshCommand { names })
+        }
+        Some(("restore", submatches)) => {
+            let name = submatches.get_one::<String>("NAME").unwrap().to_string();
+            let keys: Vec<String> = submatches.get_many::<String>("KEY").map(|s| s.map(|v| v.to_string()).collect()).unwrap_or_default();
+            CLICommand::Restore(RestoreCommand { name, keys })
+        }
+        Some(("sequence", submatches)) => {
+            let name = submatches.get_one::<String>("NAME").unwrap().to_string();
+            let order_by = submatches.get_one::<String>("order-by").map(|s| s.to_string());
+            CLICommand::Sequence(SequenceCommand { name, order_by })
+        }
+        Some(("export", submatches)) => {
+            let name = submatches.get_one::<String>("NAME").unwrap().to_string();
+            let format = submatches.get_one::<String>("format").unwrap().to_string();
+            let out = submatches.get_one::<String>("out").unwrap().to_string();
+            let r#where = submatches.get_one::<String>("where").map(|s| s.to_string());
+            let after = submatches.get_one::<String>("after").map(|s| s.to_string());
+            CLICommand::Export(ExportCommand { name, format, out, r#where, after })
+        }
+        Some(("console", _submatches)) => {
+            CLICommand::Console(ConsoleCommand { })
+        }
+        Some(("routes", submatches)) => {
+            CLICommand::Routes(RoutesCommand { json: submatches.get_flag("json") })
+        }
         Some(("lint", _submatches)) => {
             CLICommand::Lint(LintCommand { })
         }
+        Some(("advise", _submatches)) => {
+            CLICommand::Advise(AdviseCommand { })
+        }
+        Some(("fmt", submatches)) => {
+            CLICommand::Fmt(FmtCommand { check: submatches.get_flag("check") })
+        }
+        Some(("lsp", _submatches)) => {
+            CLICommand::Lsp(LspCommand { })
+        }
         Some(("run", submatches)) => {
             let name: Option<String> = submatches.get_one::<String>("NAME").map(|s| s.clone());
             CLICommand::Run(RunCommand {
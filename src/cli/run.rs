@@ -1,24 +1,64 @@
 use teo_parser::diagnostics::diagnostics::Diagnostics;
+use std::path::PathBuf;
 use teo_result::{Error, Result};
+use teo_runtime::config::client::{Client, ClientLanguage};
+use teo_runtime::config::entity::Entity;
 use crate::app::ctx::Ctx;
 use crate::app::database::connect_databases;
-use crate::cli::command::{CLI, CLICommand, GenerateCommand, SeedCommandAction};
+use crate::app::expiry::start_expiry_sweeper;
+use crate::archive::{restore, start_archiver};
+use crate::console::console;
+use crate::server::maintenance::start_maintenance_signal_listener;
+use crate::events::consumer::start_consumers;
+use crate::events::outbox::start_outbox_relay;
+use crate::cli::command::{CLI, CLICommand, DbCommand, ExportCommand, GenerateCommand, SeedCommandAction};
 use crate::server::make::serve;
+use crate::server::routes::{print_routes, routes};
+use crate::server::sequence::backfill_sequence;
 use teo_runtime::connection::transaction;
 use teo_runtime::schema::load::load_data_sets::load_data_sets;
 use crate::migrate::migrate;
+use crate::migrate::check::check_migration;
+use crate::migrate::verify::verify_schema;
+use crate::migrate::views::refresh_view;
+use crate::message::info_message;
 use crate::purge::purge;
+use crate::seeder::fake::fake;
 use crate::seeder::seed::seed;
+use crate::generate::generate_incrementally;
+use crate::integrity::check_integrity;
+use crate::generate::hooks::{generate_hooks, HooksLibrary};
+use crate::generate::mobile::{generate_mobile_client, MobileLanguage};
+use crate::generate::permissions::generate_permissions;
+use crate::generate::scalars::generate_scalars;
+use crate::generate::queries::generate_queries;
+use crate::generate::proto::generate_proto;
+use crate::generate::transport::generate_transport;
+use crate::generate::errors::generate_errors;
+use crate::watch::{exit_with_watching_process, is_watched, watch};
+use crate::fmt::fmt;
+use crate::lsp::lsp;
+use crate::lint::lint;
+use crate::advise::advise;
 
 pub async fn run(cli: &CLI) -> Result<()> {
     match &cli.command {
         CLICommand::Serve(serve_command) => {
+            if serve_command.watch {
+                if !is_watched() {
+                    return watch(cli).await;
+                }
+                exit_with_watching_process();
+            }
             connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
             let conn_ctx = Ctx::conn_ctx();
             // migrate
             if !serve_command.no_migration {
                 migrate(false, false, cli.silent).await?;
             }
+            if serve_command.verify_schema {
+                verify_schema(cli.silent).await?;
+            }
             // seed auto seed data sets
             if !serve_command.no_autoseed {
                 if Ctx::main_namespace().database.is_some() {
@@ -33,12 +73,24 @@ pub async fn run(cli: &CLI) -> Result<()> {
                 let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
                 setup.call(transaction_ctx).await?;
             }
+            start_expiry_sweeper(cli.silent);
+            start_archiver(cli.silent);
+            start_outbox_relay(cli.silent)?;
+            start_consumers(cli.silent).await?;
+            if let Some(port) = serve_command.grpc {
+                #[cfg(feature = "grpc")]
+                crate::grpc::start_grpc_server(port, &serve_command.grpc_package, cli.silent)?;
+                #[cfg(not(feature = "grpc"))]
+                Err(Error::new(format!("cannot serve the gRPC package `{}` on port {}, teo is built without the grpc feature", serve_command.grpc_package, port)))?;
+            }
+            start_maintenance_signal_listener()?;
             // start server
             serve(conn_ctx.namespace(), conn_ctx.namespace().server.as_ref().unwrap(), &Ctx::get().runtime_version, &Ctx::get().entrance, cli.silent).await
         }
         CLICommand::Generate(generate_command) => {
             match generate_command {
                 GenerateCommand::GenerateClientCommand(command) => {
+                    let hooks = command.hooks.as_deref().map(HooksLibrary::from_name).transpose()?;
                     let names = if let Some(names) = command.names.as_ref() {
                         names.clone()
                     } else if command.all {
@@ -46,13 +98,13 @@ pub async fn run(cli: &CLI) -> Result<()> {
                     } else {
                         match Ctx::main_namespace().clients.len() {
                             0 => Err(Error::new("no clients found"))?,
-                            1 => return teo_generator::client::generate(Ctx::main_namespace(), Ctx::main_namespace().clients.first_key_value().unwrap().1).await,
+                            1 => return generate_client(Ctx::main_namespace().clients.first_key_value().unwrap().1, command.full, hooks).await,
                             _ => Err(Error::new("requires client name"))?,
                         }
                     };
                     for name in names {
                         if let Some(client) = Ctx::main_namespace().clients.get(&name) {
-                            teo_generator::client::generate(Ctx::main_namespace(), client).await?;
+                            generate_client(client, command.full, hooks).await?;
                         } else {
                             Err(Error::new("client not found"))?
                         }
@@ -67,19 +119,29 @@ pub async fn run(cli: &CLI) -> Result<()> {
                     } else {
                         match Ctx::main_namespace().entities.len() {
                             0 => Err(Error::new("no entities found"))?,
-                            1 => return teo_generator::entity::generate(Ctx::main_namespace(), Ctx::main_namespace().entities.first_key_value().unwrap().1).await,
+                            1 => return generate_entity(Ctx::main_namespace().entities.first_key_value().unwrap().1, command.full).await,
                             _ => Err(Error::new("requires entity name"))?,
                         }
                     };
                     for name in names {
                         if let Some(entity) = Ctx::main_namespace().entities.get(&name) {
-                            teo_generator::entity::generate(Ctx::main_namespace(), entity).await?;
+                            generate_entity(entity, command.full).await?;
                         } else {
                             Err(Error::new("entity not found"))?
                         }
                     }
                     Ok(())
                 }
+                GenerateCommand::GenerateMobileCommand(command) => {
+                    let language = MobileLanguage::from_name(&command.language)?;
+                    let dest = PathBuf::from(&command.dest);
+                    generate_incrementally(&dest, command.full, |staging| async move {
+                        generate_mobile_client(Ctx::main_namespace(), language, &PathBuf::from(staging), &command.package, &command.host)
+                    }).await
+                }
+                GenerateCommand::GenerateProtoCommand(command) => {
+                    generate_proto(Ctx::main_namespace(), &PathBuf::from(&command.dest), &command.package)
+                }
                 GenerateCommand::GenerateAdminCommand(_) => {
                     if let Some(admin) = &Ctx::main_namespace().admin {
                         teo_generator::admin::generate(Ctx::main_namespace(), admin, Ctx::main_namespace().server.as_ref().unwrap()).await?;
@@ -90,23 +152,82 @@ pub async fn run(cli: &CLI) -> Result<()> {
         }
         CLICommand::Migrate(migrate_command) => {
             connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            if migrate_command.check {
+                return check_migration().await;
+            }
             migrate(migrate_command.dry, false, cli.silent).await?;
             Ok(())
         }
         CLICommand::Seed(seed_command) => {
             connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            if let Some(fake_options) = &seed_command.fake {
+                let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+                return fake(fake_options.count, &fake_options.counts, fake_options.seed, transaction_ctx).await;
+            }
             let mut diagnostics = Diagnostics::new();
             let data_sets = load_data_sets(Ctx::main_namespace(), seed_command.names.as_ref(), seed_command.all, Ctx::schema(), &mut diagnostics)?;
             let transaction_ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
             seed(seed_command.action, data_sets, transaction_ctx, true).await?;
             Ok(())
         }
+        CLICommand::Refresh(refresh_command) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            for name in &refresh_command.names {
+                let path: Vec<&str> = name.split('.').collect();
+                let model = Ctx::main_namespace().model_at_path(&path).ok_or_else(|| Error::new(format!("model `{}` is not found", name)))?;
+                refresh_view(model).await?;
+                if !cli.silent {
+                    info_message(format!("refreshed `{}`", name));
+                }
+            }
+            Ok(())
+        }
+        CLICommand::Restore(restore_command) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            let path: Vec<&str> = restore_command.name.split('.').collect();
+            let model = Ctx::main_namespace().model_at_path(&path).ok_or_else(|| Error::new(format!("model `{}` is not found", restore_command.name)))?;
+            let count = restore(model, &restore_command.keys, cli.silent).await?;
+            if !cli.silent {
+                info_message(format!("restored {} `{}` records", count, restore_command.name));
+            }
+            Ok(())
+        }
+        CLICommand::Sequence(sequence_command) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            let (model_name, field_name) = sequence_command.name.rsplit_once('.').ok_or_else(|| Error::new(format!("expect a field name like `Invoice.number`, found `{}`", sequence_command.name)))?;
+            let path: Vec<&str> = model_name.split('.').collect();
+            let model = Ctx::main_namespace().model_at_path(&path).ok_or_else(|| Error::new(format!("model `{}` is not found", model_name)))?;
+            let count = backfill_sequence(model, field_name, sequence_command.order_by.as_deref(), cli.silent).await?;
+            if !cli.silent {
+                info_message(format!("numbered {} `{}` records", count, model_name));
+            }
+            Ok(())
+        }
+        CLICommand::Export(export_command) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            export_records(export_command, cli.silent).await
+        }
+        CLICommand::Console(_) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            console().await
+        }
+        CLICommand::Routes(routes_command) => {
+            print_routes(&routes(Ctx::main_namespace()), routes_command.json);
+            Ok(())
+        }
         CLICommand::Purge(purge_command) => {
             connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
             purge().await?;
             Ok(())
         }
-        CLICommand::Lint(lint_command) => Ok(()),
+        CLICommand::Db(DbCommand::DbCheckCommand(db_check_command)) => {
+            connect_databases(Ctx::main_namespace_mut(), cli.silent).await?;
+            check_integrity(db_check_command.fix).await
+        }
+        CLICommand::Lint(_) => lint(cli),
+        CLICommand::Advise(_) => advise(),
+        CLICommand::Fmt(fmt_command) => fmt(cli, fmt_command.check),
+        CLICommand::Lsp(_) => lsp(),
         CLICommand::Run(run_command) => {
             if run_command.list {
                 println!("+-{:<32}-+-{:<64}-+", "--------------------------------", "----------------------------------------------------------------");
@@ -138,4 +259,91 @@ pub async fn run(cli: &CLI) -> Result<()> {
             Ok(())
         },
     }
-}
\ No newline at end of file
+}
+
+async fn generate_client(client: &'static Client, full: bool, hooks: Option<HooksLibrary>) -> Result<()> {
+    generate_incrementally(&PathBuf::from(&client.dest), full, |staging| async move {
+        let mut client = client.clone();
+        client.dest = staging;
+        teo_generator::client::generate(Ctx::main_namespace(), &client).await?;
+        generate_client_extras(&client, &client.dest, hooks)
+    }).await
+}
+
+fn generate_client_extras(client: &Client, dest: &str, hooks: Option<HooksLibrary>) -> Result<()> {
+    let is_typescript = matches!(client.provider, ClientLanguage::TypeScript(_));
+    if hooks.is_some() && !is_typescript {
+        return Err(Error::new("hooks can only be generated for TypeScript clients"));
+    }
+    if !is_typescript {
+        return Ok(());
+    }
+    let dir = if client.package { PathBuf::from(dest).join("src") } else { PathBuf::from(dest) };
+    generate_transport(&dir)?;
+    generate_errors(&dir)?;
+    generate_permissions(Ctx::main_namespace(), &dir)?;
+    generate_scalars(Ctx::main_namespace(), &dir)?;
+    generate_queries(Ctx::main_namespace(), &dir)?;
+    match hooks {
+        Some(hooks) => generate_hooks(Ctx::main_namespace(), &dir, hooks),
+        None => Ok(()),
+    }
+}
+
+async fn generate_entity(entity: &'static Entity, full: bool) -> Result<()> {
+    generate_incrementally(&PathBuf::from(&entity.dest), full, |staging| async move {
+        let mut entity = entity.clone();
+        entity.dest = staging;
+        teo_generator::entity::generate(Ctx::main_namespace(), &entity).await
+    }).await
+}
+
+#[cfg(feature = "arrow")]
+async fn export_records(command: &ExportCommand, silent: bool) -> Result<()> {
+    use crate::events::object_json;
+    use crate::export::{encode, ExportFormat};
+    use crate::server::export_cursor::{begin_snapshot, export_page, DEFAULT_PAGE_SIZE};
+    let path: Vec<&str> = command.name.split('.').collect();
+    let model = Ctx::main_namespace().model_at_path(&path).ok_or_else(|| Error::new(format!("model `{}` is not found", command.name)))?;
+    let format = ExportFormat::from_name(&command.format).ok_or_else(|| Error::new(format!("unknown export format `{}`", command.format)))?;
+    let finder: serde_json::Value = match &command.r#where {
+        Some(r#where) => serde_json::from_str(r#where).map_err(|e| Error::new(format!("invalid where filter: {}", e)))?,
+        None => serde_json::json!({}),
+    };
+    let args = serde_json::json!({ "where": finder });
+    let after = command.after.clone();
+    // the pages are read in one snapshot, a failed export reports the
+    // cursor to resume from
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let records = ctx.run_transaction(move |ctx: transaction::Ctx| {
+        let (args, mut after) = (args.clone(), after.clone());
+        async move {
+            begin_snapshot(model, &ctx).await?;
+            let mut records = vec![];
+            loop {
+                let page = export_page(model, &args, after.as_deref(), DEFAULT_PAGE_SIZE, Ctx::main_namespace(), &ctx).await.map_err(|e| match &after {
+                    Some(after) => Error::new(format!("{}, resume with `--after {}`", e.message, after)),
+                    None => e,
+                })?;
+                for object in &page.objects {
+                    records.push(object_json(object)?);
+                }
+                match page.cursor {
+                    Some(cursor) => after = Some(cursor),
+                    None => break,
+                }
+            }
+            Ok(records)
+        }
+    }).await?;
+    std::fs::write(&command.out, encode(&records, format)?).map_err(|e| Error::new(format!("cannot write \"{}\": {}", command.out, e)))?;
+    if !silent {
+        info_message(format!("exported {} `{}` records to {}", records.len(), command.name, command.out));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "arrow"))]
+async fn export_records(_command: &ExportCommand, _silent: bool) -> Result<()> {
+    Err(Error::new("exporting records requires teo to be built with the `arrow` feature"))
+}
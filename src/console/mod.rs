@@ -0,0 +1,207 @@
+use std::io::Write;
+use colored::Colorize;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::pipeline;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use crate::app::ctx::Ctx;
+use crate::events::object_json;
+use crate::generate::mobile::collect_models;
+use crate::utils::literal::parse_literal;
+
+const HELP: &str = "\
+Expressions:
+  Model.action(args)        run a model action, args are the request body
+                            e.g. blog.Post.findMany({ where: { published: true }, take: 5 })
+  Model.field.pipeline(v)   run the onSet, onSave or onOutput pipeline of a field
+                            e.g. User.email.onSet(\" Ada@Example.com \")
+Actions:
+  findMany findFirst findUnique count create update updateMany delete deleteMany
+Commands:
+  .help     show this message
+  .models   list the models
+  .exit     leave the console";
+
+const ACTIONS: [&str; 9] = ["findMany", "findFirst", "findUnique", "count", "create", "update", "updateMany", "delete", "deleteMany"];
+
+const PIPELINES: [&str; 3] = ["onSet", "onSave", "onOutput"];
+
+/// An interactive console which evaluates model actions and field pipelines
+/// against the connected databases and pretty prints the results.
+///
+/// Actions run directly on the connection, without middlewares and
+/// permission checks. An expression continues on the next line while its
+/// brackets are open.
+pub(crate) async fn console() -> Result<()> {
+    println!("{} type {} for help, {} to leave", "teo console".bold(), ".help".cyan(), ".exit".cyan());
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut buffer = String::new();
+    loop {
+        prompt(if buffer.is_empty() { "teo> " } else { "...> " });
+        let Some(line) = lines.next_line().await.map_err(|e| Error::new(format!("cannot read stdin: {}", e)))? else {
+            println!();
+            return Ok(());
+        };
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+        if depth(&buffer) > 0 {
+            continue
+        }
+        let input = std::mem::take(&mut buffer);
+        let input = input.trim();
+        match input {
+            "" => (),
+            ".exit" | ".quit" => return Ok(()),
+            ".help" => println!("{}", HELP),
+            ".models" => {
+                let mut models = vec![];
+                collect_models(Ctx::main_namespace(), &mut models);
+                for model in models {
+                    println!("{}", model.path().join("."));
+                }
+            }
+            _ => match evaluate(input).await {
+                Ok(result) => println!("{}", serde_json::to_string_pretty(&result).unwrap()),
+                Err(error) => println!("{} {}", "error:".red().bold(), error.message),
+            },
+        }
+    }
+}
+
+fn prompt(prompt: &str) {
+    print!("{}", prompt.green());
+    let _ = std::io::stdout().flush();
+}
+
+/// How many brackets are left open, strings are skipped.
+fn depth(source: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in source.chars() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            _ => (),
+        }
+    }
+    depth
+}
+
+/// Evaluate `path.method(args)`.
+async fn evaluate(input: &str) -> Result<JsonValue> {
+    let open = input.find('(').ok_or_else(|| Error::new("expect an expression like `User.findMany({ ... })`, type .help for help"))?;
+    if !input.ends_with(')') {
+        Err(Error::new("expect the expression to end with `)`"))?
+    }
+    let mut path: Vec<&str> = input[..open].trim().split('.').collect();
+    let method = path.pop().unwrap();
+    let source = input[open + 1..input.len() - 1].trim();
+    let args = if source.is_empty() { json!({}) } else { parse_literal(source)? };
+    if PIPELINES.contains(&method) {
+        let Some((field_name, model_path)) = path.split_last() else { Err(Error::new(format!("expect a field before `{}`", method)))? };
+        let model = Ctx::main_namespace().model_at_path(model_path).ok_or_else(|| Error::new(format!("model `{}` is not found", model_path.join("."))))?;
+        return run_field_pipeline(model, field_name, method, args).await;
+    }
+    if !ACTIONS.contains(&method) {
+        Err(Error::new(format!("unknown action `{}`, expect one of {}", method, ACTIONS.join(", "))))?
+    }
+    let model = Ctx::main_namespace().model_at_path(&path).ok_or_else(|| Error::new(format!("model `{}` is not found", path.join("."))))?;
+    run_action(model, method, args).await
+}
+
+async fn run_action(model: &'static Model, action: &str, mut args: JsonValue) -> Result<JsonValue> {
+    if action == "findFirst" {
+        if let Some(args) = args.as_object_mut() {
+            args.entry("take").or_insert(json!(1));
+        }
+    }
+    let handler = builtin_action_handler_from_name(action).ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, handler, &args, Ctx::main_namespace())?;
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let finder = teon!({ "where": input.get("where").cloned().unwrap_or(teon!({})) });
+    match action {
+        "findMany" => {
+            let objects: Vec<Object> = ctx.find_many(model, &input, None, path![]).await?;
+            Ok(JsonValue::Array(objects.iter().map(object_json).collect::<Result<Vec<JsonValue>>>()?))
+        }
+        "findFirst" | "findUnique" => {
+            let objects: Vec<Object> = ctx.find_many(model, &input, None, path![]).await?;
+            objects.first().map_or(Ok(JsonValue::Null), object_json)
+        }
+        "count" => {
+            let count = ctx.count_objects(model, &finder, path![]).await?;
+            Ok(json!(count))
+        }
+        "create" => {
+            let object = ctx.create_object(model, input.get("create").unwrap_or(&Value::Null), None).await?;
+            object.save().await?;
+            object_json(&object)
+        }
+        "update" | "updateMany" => {
+            let objects: Vec<Object> = ctx.find_many(model, &finder, None, path![]).await?;
+            if action == "update" && objects.is_empty() {
+                Err(Error::not_found())?
+            }
+            let mut updated = vec![];
+            for object in objects.iter().take(if action == "update" { 1 } else { objects.len() }) {
+                if let Some(Value::Dictionary(update)) = input.get("update") {
+                    for (key, value) in update {
+                        object.set(key.as_str(), value.clone())?;
+                    }
+                }
+                object.save().await?;
+                updated.push(object_json(object)?);
+            }
+            Ok(if action == "update" { updated.remove(0) } else { JsonValue::Array(updated) })
+        }
+        "delete" | "deleteMany" => {
+            let objects: Vec<Object> = ctx.find_many(model, &finder, None, path![]).await?;
+            if action == "delete" && objects.is_empty() {
+                Err(Error::not_found())?
+            }
+            let mut deleted = vec![];
+            for object in objects.iter().take(if action == "delete" { 1 } else { objects.len() }) {
+                deleted.push(object_json(object)?);
+                object.delete().await?;
+            }
+            Ok(if action == "delete" { deleted.remove(0) } else { json!({ "count": deleted.len() }) })
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Run a field pipeline on `value` in the context of a new, unsaved record.
+async fn run_field_pipeline(model: &'static Model, field_name: &str, pipeline_name: &str, value: JsonValue) -> Result<JsonValue> {
+    let field = model.field(field_name).ok_or_else(|| Error::new(format!("field `{}` is not found on `{}`", field_name, model.path().join("."))))?;
+    let pipeline = match pipeline_name {
+        "onSet" => &field.on_set,
+        "onSave" => &field.on_save,
+        _ => &field.on_output,
+    };
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let object = ctx.create_object(model, &teon!({}), None).await?;
+    let pipeline_ctx = pipeline::Ctx::new(Value::from(value), object.clone(), path![field_name], object.action(), ctx, None);
+    let output = pipeline_ctx.run_pipeline(pipeline).await?;
+    Ok(JsonValue::try_from(&output)?)
+}
@@ -0,0 +1,102 @@
+use std::future::Future;
+use std::sync::Arc;
+use actix_web::test::TestRequest;
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::{connection, request};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::Handler;
+use teo_runtime::handler::handler::Method;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_handler;
+use teo_runtime::namespace::Namespace;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::server::request::RequestImpl;
+
+/// Subscribes to the topics of a message broker, e.g. Kafka or NATS. The
+/// stream yields the payloads of the received messages.
+pub trait EventSource: Send + Sync {
+    fn subscribe(&self, topic: String) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>>;
+}
+
+impl<F, Fut> EventSource for F where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<BoxStream<'static, Result<String>>>> + Send + 'static {
+    fn subscribe(&self, topic: String) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        Box::pin(self(topic))
+    }
+}
+
+/// Routes the messages of a topic into a handler.
+#[derive(Debug, Clone)]
+pub(crate) struct Consumer {
+    pub(crate) source: String,
+    pub(crate) topic: String,
+    pub(crate) handler: Vec<String>,
+}
+
+/// Subscribe to the topics of the registered consumers in the background.
+/// Each message is parsed as JSON, validated against the input of the handler
+/// and passed through the middleware stack like an HTTP request. Messages
+/// which fail are reported and skipped.
+pub(crate) async fn start_consumers(silent: bool) -> Result<()> {
+    for consumer in Ctx::get().consumers.iter() {
+        let source = Ctx::get().event_sources.get(&consumer.source).ok_or_else(|| Error::new(format!("event source `{}` is not registered", consumer.source)))?;
+        let (namespace, handler) = resolve_handler(&consumer.handler).ok_or_else(|| Error::new(format!("handler `{}` is not found", consumer.handler.join("."))))?;
+        let mut messages = source.subscribe(consumer.topic.clone()).await?;
+        if !silent {
+            info_message(format!("consuming `{}` of `{}` with `{}`", consumer.topic, consumer.source, consumer.handler.join(".")));
+        }
+        let consumer = consumer.clone();
+        tokio::spawn(async move {
+            while let Some(message) = messages.next().await {
+                let result = match message {
+                    Ok(payload) => consume(namespace, handler, &consumer, &payload).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    info_message(format!("cannot consume a message of `{}`: {}", consumer.topic, err.message));
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn consume(namespace: &'static Namespace, handler: &'static Handler, consumer: &Consumer, payload: &str) -> Result<()> {
+    let main_namespace = Ctx::main_namespace();
+    let json_body: JsonValue = serde_json::from_str(payload).map_err(|e| Error::invalid_request_message(format!("invalid json message: {}", e)))?;
+    let body = validate_and_transform_json_input_for_handler(handler, &json_body, main_namespace)?;
+    let path = format!("/{}", consumer.handler.join("/"));
+    let match_result = main_namespace.handler_map.default_match(Method::Post, &path).ok_or_else(|| Error::not_found())?;
+    let http_request = TestRequest::post().uri(&path).to_http_request();
+    let ctx = request::Ctx::new(
+        request::Request::new(Arc::new(RequestImpl::new(http_request))),
+        Arc::new(body),
+        transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
+        match_result,
+    );
+    namespace.middleware_stack.call(ctx, handler.call).await?;
+    Ok(())
+}
+
+/// Find a handler by its path, e.g. `["orders", "OrderEvents", "created"]`
+/// for a handler of a group, or `["orders", "created"]` for a namespace
+/// handler.
+fn resolve_handler(path: &Vec<String>) -> Option<(&'static Namespace, &'static Handler)> {
+    let main_namespace = Ctx::main_namespace();
+    let (name, rest) = path.split_last()?;
+    if let Some((group_name, namespace_path)) = rest.split_last() {
+        if let Some(namespace) = main_namespace.namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) {
+            let group = namespace.handler_groups.get(group_name).or_else(|| namespace.model_handler_groups.get(group_name));
+            if let Some(handler) = group.and_then(|g| g.handlers.get(name)) {
+                return Some((namespace, handler));
+            }
+        }
+    }
+    let namespace = main_namespace.namespace_at_path(&rest.iter().map(AsRef::as_ref).collect())?;
+    Some((namespace, namespace.handlers.get(name)?))
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+use futures_util::future::BoxFuture;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use teo_result::{Error, Result};
+use crate::events::EventSink;
+
+/// How long a publish waits for the producer queue when it's full.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An event sink publishing to Kafka topics named like the topics.
+pub struct KafkaSink {
+    producer: FutureProducer,
+}
+
+impl KafkaSink {
+
+    /// Connect to the Kafka brokers of `bootstrap_servers`, e.g.
+    /// `"localhost:9092"`.
+    pub fn connect(bootstrap_servers: &str) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap_servers)
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(|e| Error::new(format!("cannot connect to Kafka at \"{}\": {}", bootstrap_servers, e)))?;
+        Ok(Self { producer })
+    }
+}
+
+impl EventSink for KafkaSink {
+
+    fn publish(&self, topic: String, payload: String) -> BoxFuture<'static, Result<()>> {
+        let producer = self.producer.clone();
+        Box::pin(async move {
+            producer.send(FutureRecord::<(), _>::to(&topic).payload(&payload), QUEUE_TIMEOUT).await
+                .map(|_| ())
+                .map_err(|(e, _)| Error::new(format!("{}", e)))
+        })
+    }
+}
@@ -0,0 +1,100 @@
+pub mod consumer;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub(crate) mod outbox;
+#[cfg(feature = "rabbitmq")]
+pub mod rabbitmq;
+
+use std::future::Future;
+use std::time::Duration;
+use chrono::{SecondsFormat, Utc};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::Object;
+use tokio::time::sleep;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+/// The version of the change event format. It's bumped on incompatible
+/// changes so that consumers can tell the formats apart.
+pub const CHANGE_EVENT_VERSION: u32 = 1;
+
+const PUBLISH_ATTEMPTS: u32 = 5;
+
+/// Publishes change events to a message broker. `payload` is the JSON
+/// encoded event. Sinks of Kafka, NATS and RabbitMQ are provided behind the
+/// `kafka`, `nats` and `rabbitmq` features.
+pub trait EventSink: Send + Sync {
+    fn publish(&self, topic: String, payload: String) -> BoxFuture<'static, Result<()>>;
+}
+
+impl<F, Fut> EventSink for F where
+    F: Fn(String, String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send + 'static {
+    fn publish(&self, topic: String, payload: String) -> BoxFuture<'static, Result<()>> {
+        Box::pin(self(topic, payload))
+    }
+}
+
+/// The field values of an object as JSON.
+pub(crate) fn object_json(object: &Object) -> Result<JsonValue> {
+    let mut record = Map::new();
+    for field in object.model().fields.values() {
+        record.insert(field.name().to_owned(), JsonValue::try_from(&object.get_value(field.name())?)?);
+    }
+    Ok(JsonValue::Object(record))
+}
+
+/// The change event of a saved or deleted object. The data is the record
+/// as responses serialize it, without `@writeonly` fields, and without
+/// virtual fields, which aren't stored.
+pub(crate) async fn change_event(object: &Object, action: &str) -> Result<JsonValue> {
+    let model = object.model();
+    let mut data = JsonValue::try_from(&object.to_teon().await?)?;
+    if let Some(data) = data.as_object_mut() {
+        for field in model.fields.values().filter(|field| field.r#virtual) {
+            data.remove(field.name());
+        }
+    }
+    Ok(json!({
+        "version": CHANGE_EVENT_VERSION,
+        "id": uuid::Uuid::new_v4().to_string(),
+        "model": model.path().join("."),
+        "action": action,
+        "occurredAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "data": data,
+    }))
+}
+
+/// Publish an event with the sink registered as `sink`. Failed publishes are
+/// retried with exponential backoff, so a consumer may receive an event more
+/// than once.
+pub(crate) async fn publish(sink: &str, topic: &str, event: &JsonValue) -> Result<()> {
+    let sink_impl = Ctx::get().event_sinks.get(sink).ok_or_else(|| Error::new(format!("event sink `{}` is not registered", sink)))?;
+    let payload = event.to_string();
+    let mut attempt = 0;
+    loop {
+        match sink_impl.publish(topic.to_owned(), payload.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(_) if attempt + 1 < PUBLISH_ATTEMPTS => {
+                sleep(Duration::from_millis(200 * 2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            Err(err) => Err(Error::new(format!("cannot publish to `{}` of event sink `{}`: {}", topic, sink, err.message)))?,
+        }
+    }
+}
+
+/// Publish an event in the background, so that the request of the change
+/// doesn't wait for the retries. An event which can't be published is
+/// logged and dropped, publish through the outbox not to lose events.
+pub(crate) fn publish_in_background(sink: String, topic: String, event: JsonValue) {
+    tokio::spawn(async move {
+        if let Err(err) = publish(&sink, &topic, &event).await {
+            info_message(err.message);
+        }
+    });
+}
@@ -0,0 +1,56 @@
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use teo_result::{Error, Result};
+use crate::events::consumer::EventSource;
+use crate::events::EventSink;
+
+/// An event sink publishing to NATS subjects named like the topics.
+pub struct NatsSink {
+    client: async_nats::Client,
+}
+
+impl NatsSink {
+
+    /// Connect to the NATS server at `url`, e.g. `"nats://localhost:4222"`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = async_nats::connect(url).await.map_err(|e| Error::new(format!("cannot connect to NATS at \"{}\": {}", url, e)))?;
+        Ok(Self { client })
+    }
+}
+
+impl EventSink for NatsSink {
+
+    fn publish(&self, topic: String, payload: String) -> BoxFuture<'static, Result<()>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            client.publish(topic, payload.into()).await.map_err(|e| Error::new(format!("{}", e)))?;
+            client.flush().await.map_err(|e| Error::new(format!("{}", e)))
+        })
+    }
+}
+
+/// An event source subscribing to NATS subjects named like the topics.
+pub struct NatsSource {
+    client: async_nats::Client,
+}
+
+impl NatsSource {
+
+    /// Connect to the NATS server at `url`, e.g. `"nats://localhost:4222"`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = async_nats::connect(url).await.map_err(|e| Error::new(format!("cannot connect to NATS at \"{}\": {}", url, e)))?;
+        Ok(Self { client })
+    }
+}
+
+impl EventSource for NatsSource {
+
+    fn subscribe(&self, topic: String) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let subscriber = client.subscribe(topic).await.map_err(|e| Error::new(format!("{}", e)))?;
+            Ok(subscriber.map(|message| String::from_utf8(message.payload.to_vec()).map_err(|e| Error::new(format!("{}", e)))).boxed())
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::database::database::Database;
+use teo_runtime::pipeline;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::app::database::model_connection;
+use crate::events::publish;
+use crate::message::info_message;
+use crate::server::estimate::int;
+use crate::stdlib::decorators::publish::uses_outbox;
+use crate::utils::sql::quote;
+
+/// The table outbox events are written to.
+pub(crate) const OUTBOX_TABLE: &str = "_teo_outbox";
+
+/// How often the relay looks for unpublished events.
+const RELAY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many events are published at a time.
+const RELAY_BATCH_SIZE: usize = 100;
+
+/// How many times the relay tries to publish an event before it gives up on
+/// it. The event stays in the table with its last error and `dead_at` set.
+const RELAY_ATTEMPTS: i64 = 10;
+
+/// Write an event to the outbox table with the transaction of the object, so
+/// the event is stored if and only if the change is committed.
+pub(crate) async fn write_outbox(ctx: &pipeline::Ctx, sink: &str, topic: &str, event: &JsonValue) -> Result<()> {
+    let transaction = ctx.transaction_ctx().transaction_for_model(ctx.object().model()).await?;
+    let (_, database) = model_connection(ctx.object().model())?;
+    let id = event.get("id").and_then(|i| i.as_str()).unwrap_or_default().to_owned();
+    let statement = format!(
+        "INSERT INTO {} (id, sink, topic, payload, created_at) VALUES ({}, {}, {}, {}, {})",
+        OUTBOX_TABLE, quote(&id, &database)?, quote(sink, &database)?, quote(topic, &database)?, quote(&event.to_string(), &database)?, Utc::now().timestamp_millis(),
+    );
+    transaction.query_raw(&Value::String(statement)).await?;
+    Ok(())
+}
+
+/// Create the outbox table of connections whose models publish through the
+/// outbox.
+pub(crate) async fn create_outbox_tables() -> Result<()> {
+    for (connection, _) in outbox_connections()? {
+        let transaction = connection.no_transaction().await?;
+        transaction.query_raw(&Value::String(format!(
+            "CREATE TABLE IF NOT EXISTS {} (id VARCHAR(36) PRIMARY KEY, sink VARCHAR(255) NOT NULL, topic VARCHAR(255) NOT NULL, payload TEXT NOT NULL, created_at BIGINT NOT NULL, published_at BIGINT NULL, attempts INT NOT NULL DEFAULT 0, last_error TEXT NULL, dead_at BIGINT NULL)",
+            OUTBOX_TABLE,
+        ))).await?;
+    }
+    Ok(())
+}
+
+/// Start publishing the outbox events in the background. Events are marked
+/// as published after their sink accepts them, so an event is published at
+/// least once. An event which fails is tried again on the next rounds, and
+/// dead-lettered after `RELAY_ATTEMPTS` failures, while later events are
+/// still published. Does nothing if no model publishes through the outbox.
+pub(crate) fn start_outbox_relay(silent: bool) -> Result<()> {
+    let connections = outbox_connections()?;
+    if connections.is_empty() {
+        return Ok(());
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RELAY_INTERVAL);
+        loop {
+            interval.tick().await;
+            for (connection, database) in &connections {
+                match relay(connection.clone(), database).await {
+                    Ok(0) => (),
+                    Ok(count) => if !silent {
+                        info_message(format!("published {} outbox events", count));
+                    },
+                    Err(err) => info_message(format!("cannot publish outbox events: {}", err.message)),
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Publish a batch of the unpublished events of a connection, returns how
+/// many are published.
+pub(crate) async fn relay(connection: Arc<dyn Connection>, database: &Database) -> Result<usize> {
+    let transaction = connection.no_transaction().await?;
+    let rows = transaction.query_raw(&Value::String(format!(
+        "SELECT id, sink, topic, payload, attempts FROM {} WHERE published_at IS NULL AND dead_at IS NULL ORDER BY created_at LIMIT {}",
+        OUTBOX_TABLE, RELAY_BATCH_SIZE,
+    ))).await?;
+    let Value::Array(rows) = rows else { return Ok(0) };
+    let mut count = 0;
+    for row in rows {
+        let Some(id) = row.get("id").and_then(|v| v.as_str()).map(|s| s.to_owned()) else { continue };
+        let statement = match relay_row(&row).await {
+            Ok(()) => {
+                count += 1;
+                format!("UPDATE {} SET published_at = {} WHERE id = {}", OUTBOX_TABLE, Utc::now().timestamp_millis(), quote(&id, database)?)
+            }
+            Err(err) => {
+                let attempts = row.get("attempts").and_then(int).unwrap_or(0) + 1;
+                let dead_at = if attempts >= RELAY_ATTEMPTS { Utc::now().timestamp_millis().to_string() } else { "NULL".to_owned() };
+                info_message(format!("cannot publish outbox event {} (attempt {} of {}): {}", id, attempts, RELAY_ATTEMPTS, err.message));
+                format!(
+                    "UPDATE {} SET attempts = {}, last_error = {}, dead_at = {} WHERE id = {}",
+                    OUTBOX_TABLE, attempts, quote(&err.message, database)?, dead_at, quote(&id, database)?,
+                )
+            }
+        };
+        transaction.query_raw(&Value::String(statement)).await?;
+    }
+    Ok(count)
+}
+
+/// Publish the event of an outbox row.
+async fn relay_row(row: &Value) -> Result<()> {
+    let column = |name: &str| row.get(name).and_then(|v| v.as_str()).map(|s| s.to_owned()).ok_or_else(|| Error::new(format!("invalid outbox row, missing `{}`", name)));
+    let (sink, topic) = (column("sink")?, column("topic")?);
+    let event: JsonValue = serde_json::from_str(&column("payload")?).map_err(|e| Error::new(format!("invalid outbox payload: {}", e)))?;
+    publish(&sink, &topic, &event).await
+}
+
+/// The connections of namespaces which have models publishing through the
+/// outbox and their databases.
+pub(crate) fn outbox_connections() -> Result<Vec<(Arc<dyn Connection>, Database)>> {
+    let conn_ctx = Ctx::conn_ctx();
+    let mut result = vec![];
+    for (namespace_path, connection) in conn_ctx.connections_iter() {
+        let Some(namespace) = conn_ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else { continue };
+        if !namespace.models_under_connector().iter().any(|model| uses_outbox(model)) {
+            continue
+        }
+        let Some(connector) = namespace.connector.as_ref() else { continue };
+        let database = connector.provider.clone();
+        if database.is_mongo() || !is_provider_connector(connector) {
+            Err(Error::new("the outbox is only supported by SQL databases"))?
+        }
+        result.push((connection.clone(), database));
+    }
+    Ok(result)
+}
@@ -0,0 +1,48 @@
+use futures_util::future::BoxFuture;
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions};
+use lapin::publisher_confirm::Confirmation;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties};
+use teo_result::{Error, Result};
+use crate::events::EventSink;
+
+/// An event sink publishing to a RabbitMQ exchange with the topics as
+/// routing keys. Publishes wait for the broker to confirm them.
+pub struct RabbitMqSink {
+    _connection: Connection,
+    channel: Channel,
+    exchange: String,
+}
+
+impl RabbitMqSink {
+
+    /// Connect to the RabbitMQ server at `url`, e.g.
+    /// `"amqp://localhost:5672"`, publishing to `exchange`. The exchange is
+    /// declared by the broker's configuration, `""` is the default exchange
+    /// which routes to the queue named like the topic.
+    pub async fn connect(url: &str, exchange: &str) -> Result<Self> {
+        let error = |e: lapin::Error| Error::new(format!("cannot connect to RabbitMQ at \"{}\": {}", url, e));
+        let connection = Connection::connect(url, ConnectionProperties::default()).await.map_err(error)?;
+        let channel = connection.create_channel().await.map_err(error)?;
+        channel.confirm_select(ConfirmSelectOptions::default()).await.map_err(error)?;
+        Ok(Self { _connection: connection, channel, exchange: exchange.to_owned() })
+    }
+}
+
+impl EventSink for RabbitMqSink {
+
+    fn publish(&self, topic: String, payload: String) -> BoxFuture<'static, Result<()>> {
+        let channel = self.channel.clone();
+        let exchange = self.exchange.clone();
+        Box::pin(async move {
+            let properties = BasicProperties::default()
+                .with_content_type("application/json".into())
+                .with_delivery_mode(2);
+            let confirm = channel.basic_publish(&exchange, &topic, BasicPublishOptions::default(), payload.as_bytes(), properties).await
+                .map_err(|e| Error::new(format!("{}", e)))?;
+            match confirm.await.map_err(|e| Error::new(format!("{}", e)))? {
+                Confirmation::Nack(_) => Err(Error::new("the broker rejected the event")),
+                _ => Ok(()),
+            }
+        })
+    }
+}
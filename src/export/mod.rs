@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::StreamWriter;
+use arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+
+/// How many rows are decoded into a record batch.
+const BATCH_SIZE: usize = 1024;
+
+/// A columnar format records can be exported in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ExportFormat {
+    /// Apache Arrow IPC stream.
+    Arrow,
+    Parquet,
+}
+
+impl ExportFormat {
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arrow" => Some(ExportFormat::Arrow),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    /// The format requested by an `Accept` header.
+    pub(crate) fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').map(|t| t.split(';').next().unwrap_or("").trim()).find_map(|t| match t {
+            "application/vnd.apache.arrow.stream" => Some(ExportFormat::Arrow),
+            "application/vnd.apache.parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Arrow => "application/vnd.apache.arrow.stream",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// Encode JSON records in a columnar format. The schema is inferred from the
+/// records, dates are kept as ISO 8601 strings.
+pub(crate) fn encode(records: &[JsonValue], format: ExportFormat) -> Result<Vec<u8>> {
+    let schema = Arc::new(infer_json_schema_from_iterator(records.iter().map(|r| Ok(r.clone()))).map_err(arrow_error)?);
+    let batches = record_batches(records, schema.clone())?;
+    let mut buffer = vec![];
+    match format {
+        ExportFormat::Arrow => {
+            let mut writer = StreamWriter::try_new(&mut buffer, &schema).map_err(arrow_error)?;
+            for batch in &batches {
+                writer.write(batch).map_err(arrow_error)?;
+            }
+            writer.finish().map_err(arrow_error)?;
+        }
+        ExportFormat::Parquet => {
+            let mut writer = ArrowWriter::try_new(&mut buffer, schema, None).map_err(|e| Error::new(format!("parquet: {}", e)))?;
+            for batch in &batches {
+                writer.write(batch).map_err(|e| Error::new(format!("parquet: {}", e)))?;
+            }
+            writer.close().map_err(|e| Error::new(format!("parquet: {}", e)))?;
+        }
+    }
+    Ok(buffer)
+}
+
+/// Replace the records of a `findMany` or `groupBy` response with their
+/// columnar encoding.
+pub(crate) async fn export_response(response: HttpResponse, format: ExportFormat) -> HttpResponse {
+    if !response.status().is_success() {
+        return response;
+    }
+    let (head, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body).await else {
+        return HttpResponse::InternalServerError().finish();
+    };
+    let json_value: JsonValue = match serde_json::from_slice(&bytes) {
+        Ok(json_value) => json_value,
+        Err(_) => return head.set_body(bytes).map_into_boxed_body(),
+    };
+    let records = json_value.get("data").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+    match encode(&records, format) {
+        Ok(encoded) => HttpResponse::Ok().content_type(format.content_type()).body(encoded),
+        Err(err) => HttpResponse::InternalServerError().body(err.message),
+    }
+}
+
+fn record_batches(records: &[JsonValue], schema: Arc<Schema>) -> Result<Vec<RecordBatch>> {
+    let mut batches = vec![];
+    for chunk in records.chunks(BATCH_SIZE) {
+        let mut decoder = ReaderBuilder::new(schema.clone()).with_batch_size(BATCH_SIZE).build_decoder().map_err(arrow_error)?;
+        decoder.serialize(chunk).map_err(arrow_error)?;
+        if let Some(batch) = decoder.flush().map_err(arrow_error)? {
+            batches.push(batch);
+        }
+    }
+    Ok(batches)
+}
+
+fn arrow_error(err: arrow::error::ArrowError) -> Error {
+    Error::new(format!("arrow: {}", err))
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::env::current_dir;
+use std::fs;
+use std::path::Path;
+use teo_parser::ast::node::Node;
+use teo_parser::parse as schema_parse;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_result::{Error, Result};
+use teo_runtime::utils::find_main_schema_file;
+use crate::cli::command::CLI;
+use crate::message::info_message;
+use crate::utils::find_schema_files;
+
+const INDENT: &str = "  ";
+
+/// Decorators which are sorted into this order. They only set properties of
+/// what they decorate, so their order doesn't matter to the schema. Other
+/// decorators keep their places, as their order may matter.
+const DECORATOR_ORDER: [&str; 6] = ["id", "autoIncrement", "unique", "index", "default", "map"];
+
+/// Format all schema files next to the main schema file. In check mode, files
+/// are not written and an error is returned if any file is not formatted.
+pub(crate) fn fmt(cli: &CLI, check: bool) -> Result<()> {
+    let current_dir = current_dir().map_err(|e| Error::new(format!("{}", e)))?;
+    let main_schema_file = find_main_schema_file(cli.main(), &current_dir)?;
+    let dir = main_schema_file.parent().unwrap_or(current_dir.as_path()).to_owned();
+    let mut unformatted = vec![];
+    for path in find_schema_files(&dir) {
+        let source = fs::read_to_string(&path).map_err(|e| Error::new(format!("{}", e)))?;
+        let formatted = format_schema(path.to_str().unwrap(), &source)?;
+        if formatted == source {
+            continue
+        }
+        if check {
+            unformatted.push(path.display().to_string());
+        } else {
+            fs::write(&path, formatted).map_err(|e| Error::new(format!("{}", e)))?;
+            if !cli.silent {
+                info_message(format!("formatted \"{}\"", path.display()));
+            }
+        }
+    }
+    if unformatted.is_empty() {
+        Ok(())
+    } else {
+        for path in &unformatted {
+            info_message(format!("\"{}\" is not formatted", path));
+        }
+        Err(Error::new(format!("{} schema file(s) are not formatted", unformatted.len())))
+    }
+}
+
+/// Format the schema file at `path` whose content is `source`.
+///
+/// The file is parsed and printed from the tokens of its syntax tree.
+/// Indentation is two spaces per open bracket, blank lines are collapsed,
+/// spacing around punctuation is normalized and the decorators of
+/// `DECORATOR_ORDER` are sorted. Line breaks are kept where they are, and
+/// comments and literals are kept as is. Formatting a formatted file doesn't
+/// change it. A file with syntax errors isn't formatted.
+pub(crate) fn format_schema(path: &str, source: &str) -> Result<String> {
+    let (schema, diagnostics) = schema_parse(path, None, Some(HashMap::from([(path.to_owned(), source.to_owned())])));
+    if diagnostics.errors().iter().any(|error| same_file(error.source_path(), path)) {
+        Err(Error::new(format!("cannot format \"{}\", it has errors", path)))?
+    }
+    let parsed = schema.sources().into_iter().find(|s| same_file(&s.file_path, path)).ok_or_else(|| Error::new(format!("cannot parse \"{}\"", path)))?;
+    let mut printer = Printer { source, tokens: vec![], breaks: 0, spaced: false };
+    printer.nodes(0, source.len(), sorted(parsed.children.values()));
+    let formatted = layout(&printer.tokens);
+    if content(source) != content(&formatted) {
+        Err(Error::new(format!("cannot format \"{}\", its syntax tree doesn't cover its content", path)))?
+    }
+    Ok(formatted)
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+/// The characters of a source other than whitespace, sorted. Formatting
+/// moves and reorders them, but never adds or drops any.
+fn content(source: &str) -> Vec<char> {
+    let mut chars: Vec<char> = source.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.sort_unstable();
+    chars
+}
+
+/// A token of the source.
+#[derive(Debug)]
+struct Token<'a> {
+    text: &'a str,
+    /// The line breaks before the token in the source.
+    breaks: usize,
+    /// Whether whitespace is before the token in the source.
+    spaced: bool,
+}
+
+/// Collects the tokens of a syntax tree, the text of its leaves and the text
+/// between them, which is comments and punctuation the tree doesn't keep.
+struct Printer<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    breaks: usize,
+    spaced: bool,
+}
+
+impl<'a> Printer<'a> {
+
+    /// Collect the tokens of the nodes between `start` and `end`, sorting the
+    /// decorators among the places they take.
+    fn nodes(&mut self, start: usize, end: usize, children: Vec<&'a Node>) {
+        let mut places: Vec<usize> = (0..children.len()).filter(|i| decorator_rank(children[*i]).is_some()).collect();
+        let mut decorators: Vec<&'a Node> = places.iter().map(|i| children[*i]).collect();
+        decorators.sort_by_key(|decorator| decorator_rank(decorator));
+        let mut replacements: Vec<Option<&'a Node>> = vec![None; children.len()];
+        for (place, decorator) in places.drain(..).zip(decorators) {
+            replacements[place] = Some(decorator);
+        }
+        let mut offset = start;
+        for (child, replacement) in children.into_iter().zip(replacements) {
+            let span = child.span();
+            if span.start > offset {
+                self.gap(offset, span.start);
+            }
+            self.node(replacement.unwrap_or(child));
+            offset = offset.max(span.end);
+        }
+        if end > offset {
+            self.gap(offset, end);
+        }
+    }
+
+    fn node(&mut self, node: &'a Node) {
+        let span = node.span();
+        match node.children().filter(|children| !children.is_empty()) {
+            Some(children) => self.nodes(span.start, span.end, sorted(children.values())),
+            None => self.leaf(self.source.get(span.start..span.end).unwrap_or("")),
+        }
+    }
+
+    /// Collect the text of a leaf, whose span may take whitespace around it,
+    /// like the line break after a comment.
+    fn leaf(&mut self, text: &'a str) {
+        let trimmed = text.trim_start();
+        self.whitespace(&text[..text.len() - trimmed.len()]);
+        let token = trimmed.trim_end();
+        self.push(token);
+        self.whitespace(&trimmed[token.len()..]);
+    }
+
+    fn whitespace(&mut self, whitespace: &str) {
+        self.breaks += whitespace.matches('\n').count();
+        self.spaced |= !whitespace.is_empty();
+    }
+
+    /// Collect the tokens between `start` and `end`, which aren't nodes.
+    /// Comments are tokens of their own.
+    fn gap(&mut self, start: usize, end: usize) {
+        let mut rest = self.source.get(start..end).unwrap_or("");
+        loop {
+            let trimmed = rest.trim_start();
+            self.whitespace(&rest[..rest.len() - trimmed.len()]);
+            if trimmed.is_empty() {
+                return
+            }
+            let len = token_len(trimmed);
+            self.push(&trimmed[..len]);
+            rest = &trimmed[len..];
+        }
+    }
+
+    fn push(&mut self, text: &'a str) {
+        if text.is_empty() {
+            return
+        }
+        self.tokens.push(Token { text, breaks: self.breaks, spaced: self.spaced });
+        self.breaks = 0;
+        self.spaced = false;
+    }
+}
+
+/// Nodes in the order they are in the source.
+fn sorted<'a>(nodes: impl Iterator<Item = &'a Node>) -> Vec<&'a Node> {
+    let mut nodes: Vec<&Node> = nodes.collect();
+    nodes.sort_by_key(|node| node.span().start);
+    nodes
+}
+
+fn decorator_rank(node: &Node) -> Option<usize> {
+    let Node::Decorator(decorator) = node else { return None };
+    let name = decorator.identifier_path().names().join(".");
+    DECORATOR_ORDER.iter().position(|n| *n == name)
+}
+
+/// The length of the token `text` starts with.
+fn token_len(text: &str) -> usize {
+    if text.starts_with("//") {
+        return text.find('\n').map_or(text.len(), |i| text[..i].trim_end().len());
+    }
+    if text.starts_with("/*") {
+        return text.find("*/").map_or(text.len(), |i| i + 2);
+    }
+    if text.starts_with('"') {
+        let mut escaped = false;
+        for (i, c) in text.char_indices().skip(1) {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => return i + 1,
+                _ => escaped = false,
+            }
+        }
+        return text.len();
+    }
+    if text.starts_with(|c: char| "{}()[],:".contains(c)) {
+        return 1;
+    }
+    text.char_indices().skip(1)
+        .find(|(i, c)| c.is_whitespace() || "{}()[],:\"".contains(*c) || text[*i..].starts_with("//"))
+        .map_or(text.len(), |(i, _)| i)
+}
+
+fn is_comment(text: &str) -> bool {
+    text.starts_with("//") || text.starts_with("/*")
+}
+
+fn is_opener(text: &str) -> bool {
+    matches!(text, "{" | "(" | "[")
+}
+
+fn is_closer(text: &str) -> bool {
+    matches!(text, "}" | ")" | "]")
+}
+
+fn ends_word(text: &str) -> bool {
+    text.ends_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '?' | ')' | ']'))
+}
+
+/// Whether a space separates two tokens on a line.
+fn space_between(previous: &Token, token: &Token) -> bool {
+    let (p, t) = (previous.text, token.text);
+    if is_comment(t) {
+        return true;
+    }
+    if matches!(p, "(" | "[" | "@" | "@@" | "$" | ".") {
+        return false;
+    }
+    if matches!(t, ")" | "]" | "," | ":" | "?") {
+        return false;
+    }
+    if matches!(p, "," | ":") {
+        return true;
+    }
+    if t == "(" && ends_word(p) {
+        return false;
+    }
+    if t == "{" {
+        return true;
+    }
+    token.spaced
+}
+
+/// Lay out tokens on the lines they are on in the source.
+fn layout(tokens: &[Token]) -> String {
+    let mut lines: Vec<(bool, Vec<&Token>)> = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        let breaks = match index.checked_sub(1).map(|i| &tokens[i]) {
+            None => 1,
+            Some(previous) if previous.text.starts_with("//") => token.breaks.max(1),
+            Some(_) => token.breaks,
+        };
+        if breaks > 0 {
+            lines.push((token.breaks > 1, vec![token]));
+        } else {
+            lines.last_mut().unwrap().1.push(token);
+        }
+    }
+    let mut result = String::new();
+    let mut open: Vec<usize> = vec![];
+    let mut previous_line: Option<&Vec<&Token>> = None;
+    for (index, (blank, line)) in lines.iter().enumerate() {
+        let leading_closers = line.iter().take_while(|token| is_closer(token.text)).count();
+        for _ in 0..leading_closers {
+            open.pop();
+        }
+        let mut depth = open.clone();
+        depth.dedup();
+        let after_opener = previous_line.map_or(true, |previous| is_opener(previous.last().unwrap().text));
+        if *blank && !after_opener && leading_closers == 0 {
+            result.push('\n');
+        }
+        result.push_str(&INDENT.repeat(depth.len()));
+        for (position, token) in line.iter().enumerate() {
+            if position > 0 && space_between(line[position - 1], token) {
+                result.push(' ');
+            }
+            result.push_str(token.text);
+            if position >= leading_closers {
+                if is_opener(token.text) {
+                    open.push(index);
+                } else if is_closer(token.text) {
+                    open.pop();
+                }
+            }
+        }
+        result.push('\n');
+        previous_line = Some(line);
+    }
+    result
+}
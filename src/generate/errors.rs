@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::Path;
+use teo_result::Result;
+use crate::app::ctx::Ctx;
+use crate::generate::mobile::io_error;
+use crate::server::error::ErrorFormat;
+
+/// The file name of the generated error types.
+pub(crate) const ERRORS_FILE_NAME: &str = "errors.ts";
+
+/// Write the type of error responses next to a generated TypeScript client,
+/// in the shape of the configured `ErrorFormat`.
+pub(crate) fn generate_errors(dest: &Path) -> Result<()> {
+    let field_errors = match Ctx::error_format() {
+        ErrorFormat::Legacy => "string",
+        ErrorFormat::Structured => "FieldError[]",
+    };
+    let content = ERRORS_SOURCE.replace("{{FIELD_ERRORS}}", field_errors);
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join(ERRORS_FILE_NAME), content).map_err(io_error)
+}
+
+const ERRORS_SOURCE: &str = r#"// This file is generated by Teo, do not edit it.
+export interface FieldError {
+    // The machine-readable code of the error, if it has one.
+    code?: string
+    message: string
+    // The key path of the field split into keys and indices.
+    path: (string | number)[]
+}
+
+// The `error` of an error response.
+export interface ResponseError {
+    type: string
+    message: string
+    // The machine-readable code of a `UserError`.
+    code?: string
+    requestId?: string
+    // The errors of the input by key path, e.g. `posts.0.title`.
+    errors?: { [keyPath: string]: {{FIELD_ERRORS}} }
+}
+
+export function isResponseError(value: unknown): value is ResponseError {
+    return typeof value === "object" && value !== null && typeof (value as ResponseError).message === "string" && typeof (value as ResponseError).type === "string"
+}
+"#;
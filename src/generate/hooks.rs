@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+
+/// The data fetching library the generated hooks are built on.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum HooksLibrary {
+    ReactQuery,
+    Swr,
+}
+
+impl HooksLibrary {
+
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "react-query" => Ok(HooksLibrary::ReactQuery),
+            "swr" => Ok(HooksLibrary::Swr),
+            _ => Err(Error::new(format!("unknown hooks library `{}`, expect react-query or swr", name))),
+        }
+    }
+}
+
+/// The file name of the generated hooks module.
+pub(crate) const HOOKS_FILE_NAME: &str = "hooks.ts";
+
+const QUERY_ACTIONS: [&str; 6] = ["findUnique", "findFirst", "findMany", "count", "aggregate", "groupBy"];
+const MUTATION_ACTIONS: [&str; 9] = ["create", "update", "upsert", "delete", "createMany", "updateMany", "deleteMany", "copy", "copyMany"];
+
+/// Write a React hooks module next to a generated TypeScript client. Every
+/// model action gets a hook named after the model path and the action, e.g.
+/// `useUserFindMany` and `useUserCreate`. Queries are keyed by the model path,
+/// the action and the input, and mutations invalidate the queries of their
+/// model.
+pub(crate) fn generate_hooks(namespace: &Namespace, dest: &Path, library: HooksLibrary) -> Result<()> {
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    let mut content = header(library);
+    for model in models {
+        let path = model.path();
+        let mut accessor: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+        if let Some(last) = accessor.last_mut() {
+            *last = lower_first(last);
+        }
+        let accessor = accessor.join(".");
+        let prefix: String = path.iter().map(|s| upper_first(s)).collect();
+        let key = path.join(".");
+        for action in QUERY_ACTIONS {
+            content.push_str(&query_hook(library, &prefix, &accessor, &key, action));
+        }
+        for action in MUTATION_ACTIONS {
+            content.push_str(&mutation_hook(library, &prefix, &accessor, &key, action));
+        }
+    }
+    fs::create_dir_all(dest).map_err(|e| Error::new(format!("{}", e)))?;
+    fs::write(dest.join(HOOKS_FILE_NAME), content).map_err(|e| Error::new(format!("{}", e)))
+}
+
+fn collect_models<'a>(namespace: &'a Namespace, result: &mut Vec<&'a Model>) {
+    result.extend(namespace.models.values());
+    for child in namespace.namespaces.values() {
+        collect_models(child, result);
+    }
+}
+
+fn header(library: HooksLibrary) -> String {
+    let imports = match library {
+        HooksLibrary::ReactQuery => "import { useMutation, useQuery, useQueryClient } from \"@tanstack/react-query\"\n",
+        HooksLibrary::Swr => "import useSWR, { useSWRConfig } from \"swr\"\nimport useSWRMutation from \"swr/mutation\"\n",
+    };
+    format!(r#"// This file is generated by Teo, do not edit it.
+import {{ createContext, useContext }} from "react"
+{imports}import type {{ Teo }} from "./index"
+
+const TeoContext = createContext<Teo | undefined>(undefined)
+
+// Provide the client used by the hooks.
+export const TeoProvider = TeoContext.Provider
+
+function useTeo(): Teo {{
+    const teo = useContext(TeoContext)
+    if (!teo) {{
+        throw new Error("wrap the component tree in a TeoProvider")
+    }}
+    return teo
+}}
+
+function stable(value: any): any {{
+    if (Array.isArray(value)) {{
+        return value.map(stable)
+    }}
+    if (value && typeof value === "object" && !(value instanceof Date)) {{
+        return Object.keys(value).sort().reduce((result: any, key) => {{
+            result[key] = stable(value[key])
+            return result
+        }}, {{}})
+    }}
+    return value
+}}
+
+// The cache key of a query, stable regardless of the key order of the input.
+export function teoQueryKey(model: string, action: string, input?: any): any[] {{
+    return input === undefined ? [model, action] : [model, action, stable(input)]
+}}
+"#)
+}
+
+fn query_hook(library: HooksLibrary, prefix: &str, accessor: &str, key: &str, action: &str) -> String {
+    let name = format!("use{}{}", prefix, upper_first(action));
+    let input = format!("Parameters<Teo[\"{}\"][\"{}\"]>[0]", accessor.replace('.', "\"][\""), action);
+    match library {
+        HooksLibrary::ReactQuery => format!(r#"
+export function {name}(input: {input}) {{
+    const teo = useTeo()
+    return useQuery({{ queryKey: teoQueryKey("{key}", "{action}", input), queryFn: () => teo.{accessor}.{action}(input) }})
+}}
+"#),
+        HooksLibrary::Swr => format!(r#"
+export function {name}(input: {input} | null) {{
+    const teo = useTeo()
+    return useSWR(input === null ? null : teoQueryKey("{key}", "{action}", input), () => teo.{accessor}.{action}(input!))
+}}
+"#),
+    }
+}
+
+fn mutation_hook(library: HooksLibrary, prefix: &str, accessor: &str, key: &str, action: &str) -> String {
+    let name = format!("use{}{}", prefix, upper_first(action));
+    let input = format!("Parameters<Teo[\"{}\"][\"{}\"]>[0]", accessor.replace('.', "\"][\""), action);
+    match library {
+        HooksLibrary::ReactQuery => format!(r#"
+export function {name}() {{
+    const teo = useTeo()
+    const queryClient = useQueryClient()
+    return useMutation({{
+        mutationFn: (input: {input}) => teo.{accessor}.{action}(input),
+        onSuccess: () => queryClient.invalidateQueries({{ queryKey: ["{key}"] }}),
+    }})
+}}
+"#),
+        HooksLibrary::Swr => format!(r#"
+export function {name}() {{
+    const teo = useTeo()
+    const {{ mutate }} = useSWRConfig()
+    return useSWRMutation(teoQueryKey("{key}", "{action}"), async (_key: any, {{ arg }}: {{ arg: {input} }}) => {{
+        const result = await teo.{accessor}.{action}(arg)
+        await mutate((cacheKey: any) => Array.isArray(cacheKey) && cacheKey[0] === "{key}")
+        return result
+    }})
+}}
+"#),
+    }
+}
+
+fn lower_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+use teo_parser::r#type::Type;
+use teo_result::Result;
+use teo_runtime::model::Model;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use crate::generate::mobile::{accessor_name, action_path, envelopes, io_error, type_name, Output, ACTIONS};
+
+/// Generate a Kotlin client built on kotlinx.serialization and OkHttp. The
+/// client is written into a single `Teo.kt` file.
+pub(crate) fn generate(models: &Vec<&Model>, dest: &Path, package: &str, host: &str) -> Result<()> {
+    let envelope_entries: Vec<String> = envelopes().into_iter().map(|(key, data, meta)| {
+        format!("    {:?} to Envelope({}, {:?}),", key, data.map_or("null".to_owned(), |d| format!("{:?}", d)), meta)
+    }).collect();
+    let envelope_entries = envelope_entries.join("\n");
+    let mut content = format!(r#"// This file is generated by Teo, do not edit it.
+package {package}
+
+import kotlinx.serialization.KSerializer
+import kotlinx.serialization.Serializable
+import kotlinx.serialization.builtins.ListSerializer
+import kotlinx.serialization.builtins.nullable
+import kotlinx.serialization.builtins.serializer
+import kotlinx.serialization.json.Json
+import kotlinx.serialization.json.JsonElement
+import kotlinx.serialization.json.JsonNull
+import kotlinx.serialization.json.JsonObject
+import kotlinx.serialization.json.buildJsonObject
+import kotlinx.serialization.json.jsonObject
+import okhttp3.MediaType.Companion.toMediaType
+import okhttp3.OkHttpClient
+import okhttp3.Request
+import okhttp3.RequestBody.Companion.toRequestBody
+
+@Serializable
+data class Meta(val count: Int? = null)
+
+@Serializable
+data class Response<T>(val data: T, val meta: Meta? = null)
+
+class TeoException(val status: Int, message: String) : Exception(message)
+
+// The response envelopes of the server, `data` is null for bare responses.
+internal data class Envelope(val data: String?, val meta: String)
+
+internal val envelopes: Map<String, Envelope> = mapOf(
+{envelope_entries})
+
+class Teo(private val host: String = "{host}", private val http: OkHttpClient = OkHttpClient(), private val headers: Map<String, String> = mapOf()) {{
+
+    internal val json = Json {{ ignoreUnknownKeys = true }}
+
+    internal fun <T> request(path: String, input: JsonObject, serializer: KSerializer<T>): Response<T> {{
+        val builder = Request.Builder()
+            .url(host.trimEnd('/') + path)
+            .post(input.toString().toRequestBody("application/json".toMediaType()))
+        headers.forEach {{ (name, value) -> builder.header(name, value) }}
+        http.newCall(builder.build()).execute().use {{ response ->
+            val body = response.body?.string() ?: ""
+            if (!response.isSuccessful) {{
+                throw TeoException(response.code, body)
+            }}
+            val key = path.trim('/').replace('/', '.')
+            val envelope = envelopes[key] ?: envelopes[key.substringAfterLast('.')] ?: envelopes.getValue("")
+            val element = json.parseToJsonElement(body)
+            val normalized = buildJsonObject {{
+                if (envelope.data == null) {{
+                    put("data", element)
+                    response.header("x-teo-meta")?.let {{ put("meta", json.parseToJsonElement(it)) }}
+                }} else {{
+                    put("data", element.jsonObject[envelope.data] ?: JsonNull)
+                    element.jsonObject[envelope.meta]?.let {{ put("meta", it) }}
+                }}
+            }}
+            return json.decodeFromJsonElement(Response.serializer(serializer), normalized)
+        }}
+    }}
+"#);
+    for model in models {
+        content.push_str(&format!("\n    val {}: {}Delegate by lazy {{ {}Delegate(this) }}\n", accessor_name(model), type_name(model), type_name(model)));
+    }
+    content.push_str("}\n");
+    for model in models {
+        let name = type_name(model);
+        content.push_str(&format!("\n@Serializable\ndata class {}(\n", name));
+        for field in model.fields.values() {
+            let mut kotlin_type = kotlin_type(field.r#type());
+            if field.is_optional() && !kotlin_type.ends_with('?') {
+                kotlin_type.push('?');
+            }
+            let default = if kotlin_type.ends_with('?') { " = null" } else { "" };
+            content.push_str(&format!("    val {}: {}{},\n", field.name(), kotlin_type, default));
+        }
+        content.push_str(")\n");
+        content.push_str(&format!("\nclass {}Delegate internal constructor(private val teo: Teo) {{\n", name));
+        for (action, output) in ACTIONS {
+            let (return_type, serializer) = match output {
+                Output::Record => (format!("{}?", name), format!("{}.serializer().nullable", name)),
+                Output::Records => (format!("List<{}>", name), format!("ListSerializer({}.serializer())", name)),
+                Output::OptionalRecords => (format!("List<{}?>", name), format!("ListSerializer({}.serializer().nullable)", name)),
+                Output::Count => ("Int".to_owned(), "Int.serializer()".to_owned()),
+                Output::Json => ("JsonElement".to_owned(), "JsonElement.serializer()".to_owned()),
+            };
+            content.push_str(&format!(
+                "\n    fun {}(input: JsonObject = buildJsonObject {{}}): Response<{}> = teo.request(\"{}\", input, {})\n",
+                action, return_type, action_path(model, action), serializer,
+            ));
+        }
+        content.push_str("}\n");
+    }
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join("Teo.kt"), content).map_err(io_error)
+}
+
+fn kotlin_type(t: &Type) -> String {
+    match t {
+        Type::Bool => "Boolean".to_owned(),
+        Type::Int => "Int".to_owned(),
+        Type::Int64 => "Long".to_owned(),
+        Type::Float32 => "Float".to_owned(),
+        Type::Float => "Double".to_owned(),
+        // decimals, object ids and dates are transferred as strings
+        Type::Decimal | Type::String | Type::ObjectId | Type::Date | Type::DateTime => "String".to_owned(),
+        Type::Array(inner) => format!("List<{}>", kotlin_type(inner)),
+        Type::Dictionary(inner) => format!("Map<String, {}>", kotlin_type(inner)),
+        Type::Optional(inner) => format!("{}?", kotlin_type(inner).trim_end_matches('?')),
+        Type::EnumVariant(_) => "String".to_owned(),
+        _ => "JsonElement".to_owned(),
+    }
+}
@@ -0,0 +1,125 @@
+pub(crate) mod kotlin;
+pub(crate) mod swift;
+
+use std::path::Path;
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use crate::app::ctx::Ctx;
+
+/// The language of a mobile client.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum MobileLanguage {
+    Kotlin,
+    Swift,
+}
+
+impl MobileLanguage {
+
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "kotlin" => Ok(MobileLanguage::Kotlin),
+            "swift" => Ok(MobileLanguage::Swift),
+            _ => Err(Error::new(format!("unknown mobile client language `{}`, expect kotlin or swift", name))),
+        }
+    }
+}
+
+/// How the `data` of an action response is decoded.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Output {
+    /// A record, or null if nothing is found.
+    Record,
+    /// A list of records.
+    Records,
+    /// A list of records with null for the missing ones.
+    OptionalRecords,
+    Count,
+    /// Aggregation results, decoded as arbitrary JSON.
+    Json,
+}
+
+/// The model actions and the decoding of their outputs, the same as the ones
+/// of the TypeScript client, and `findByIds`.
+pub(crate) const ACTIONS: [(&str, Output); 16] = [
+    ("findUnique", Output::Record),
+    ("findFirst", Output::Record),
+    ("findMany", Output::Records),
+    ("findByIds", Output::OptionalRecords),
+    ("create", Output::Record),
+    ("update", Output::Record),
+    ("upsert", Output::Record),
+    ("delete", Output::Record),
+    ("copy", Output::Record),
+    ("createMany", Output::Records),
+    ("updateMany", Output::Records),
+    ("deleteMany", Output::Records),
+    ("copyMany", Output::Records),
+    ("count", Output::Count),
+    ("aggregate", Output::Json),
+    ("groupBy", Output::Json),
+];
+
+/// Generate a mobile client of the models of `namespace` into `dest`.
+pub(crate) fn generate_mobile_client(namespace: &Namespace, language: MobileLanguage, dest: &Path, package: &str, host: &str) -> Result<()> {
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    match language {
+        MobileLanguage::Kotlin => kotlin::generate(&models, dest, package, host),
+        MobileLanguage::Swift => swift::generate(&models, dest, host),
+    }
+}
+
+pub(crate) fn collect_models<'a>(namespace: &'a Namespace, result: &mut Vec<&'a Model>) {
+    result.extend(namespace.models.values());
+    for child in namespace.namespaces.values() {
+        collect_models(child, result);
+    }
+}
+
+/// The type name of a model, namespace path included, e.g. `BlogPost` for
+/// `blog.Post`.
+pub(crate) fn type_name(model: &Model) -> String {
+    model.path().iter().map(|s| upper_first(s)).collect()
+}
+
+/// The accessor of a model on the client, e.g. `blogPost` for `blog.Post`.
+pub(crate) fn accessor_name(model: &Model) -> String {
+    lower_first(&type_name(model))
+}
+
+/// The URL path of a model action.
+pub(crate) fn action_path(model: &Model, action: &str) -> String {
+    format!("/{}/{}", model.path().join("/"), action)
+}
+
+pub(crate) fn lower_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_lowercase().chain(chars).collect()).unwrap_or_default()
+}
+
+pub(crate) fn upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// The response envelopes as `(key, data, meta)`, keyed like the overrides
+/// of the server. The default envelope is keyed by an empty string.
+pub(crate) fn envelopes() -> Vec<(String, Option<String>, String)> {
+    let json = Ctx::envelopes().to_json();
+    let entry = |key: &str, envelope: &JsonValue| (
+        key.to_owned(),
+        envelope["data"].as_str().map(|s| s.to_owned()),
+        envelope["meta"].as_str().unwrap_or("meta").to_owned(),
+    );
+    let mut result = vec![entry("", &json["default"])];
+    if let Some(overrides) = json["overrides"].as_object() {
+        result.extend(overrides.iter().map(|(key, envelope)| entry(key, envelope)));
+    }
+    result
+}
+
+pub(crate) fn io_error(err: std::io::Error) -> Error {
+    Error::new(format!("{}", err))
+}
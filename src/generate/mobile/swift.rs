@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+use teo_parser::r#type::Type;
+use teo_result::Result;
+use teo_runtime::model::Model;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use crate::generate::mobile::{accessor_name, action_path, envelopes, io_error, type_name, Output, ACTIONS};
+
+/// Generate a Swift client built on Codable and URLSession. The client is
+/// written into a single `Teo.swift` file.
+pub(crate) fn generate(models: &Vec<&Model>, dest: &Path, host: &str) -> Result<()> {
+    let envelope_entries: Vec<String> = envelopes().into_iter().map(|(key, data, meta)| {
+        format!("    {:?}: Envelope(data: {}, meta: {:?}),", key, data.map_or("nil".to_owned(), |d| format!("{:?}", d)), meta)
+    }).collect();
+    let envelope_entries = envelope_entries.join("\n");
+    let mut content = format!(r#"// This file is generated by Teo, do not edit it.
+import Foundation
+
+public enum JSONValue: Codable, Equatable {{
+    case null
+    case bool(Bool)
+    case number(Double)
+    case string(String)
+    case array([JSONValue])
+    case object([String: JSONValue])
+
+    public init(from decoder: Decoder) throws {{
+        let container = try decoder.singleValueContainer()
+        if container.decodeNil() {{ self = .null }}
+        else if let value = try? container.decode(Bool.self) {{ self = .bool(value) }}
+        else if let value = try? container.decode(Double.self) {{ self = .number(value) }}
+        else if let value = try? container.decode(String.self) {{ self = .string(value) }}
+        else if let value = try? container.decode([JSONValue].self) {{ self = .array(value) }}
+        else {{ self = .object(try container.decode([String: JSONValue].self)) }}
+    }}
+
+    public func encode(to encoder: Encoder) throws {{
+        var container = encoder.singleValueContainer()
+        switch self {{
+        case .null: try container.encodeNil()
+        case .bool(let value): try container.encode(value)
+        case .number(let value): try container.encode(value)
+        case .string(let value): try container.encode(value)
+        case .array(let value): try container.encode(value)
+        case .object(let value): try container.encode(value)
+        }}
+    }}
+}}
+
+public struct Meta: Codable {{
+    public let count: Int?
+}}
+
+public struct Response<T: Decodable>: Decodable {{
+    public let data: T
+    public let meta: Meta?
+}}
+
+// The response envelopes of the server, `data` is nil for bare responses.
+struct Envelope {{
+    let data: String?
+    let meta: String
+}}
+
+let envelopes: [String: Envelope] = [
+{envelope_entries}
+]
+
+public struct TeoError: Error {{
+    public let status: Int
+    public let body: String
+}}
+
+public final class Teo {{
+    let host: URL
+    let session: URLSession
+    let headers: [String: String]
+
+    public init(host: URL = URL(string: "{host}")!, session: URLSession = .shared, headers: [String: String] = [:]) {{
+        self.host = host
+        self.session = session
+        self.headers = headers
+    }}
+
+    func request<T: Decodable>(_ path: String, _ input: [String: JSONValue]) async throws -> Response<T> {{
+        var request = URLRequest(url: host.appendingPathComponent(path))
+        request.httpMethod = "POST"
+        request.setValue("application/json", forHTTPHeaderField: "Content-Type")
+        headers.forEach {{ request.setValue($0.value, forHTTPHeaderField: $0.key) }}
+        request.httpBody = try JSONEncoder().encode(input)
+        let (data, response) = try await session.data(for: request)
+        let status = (response as? HTTPURLResponse)?.statusCode ?? 0
+        guard (200..<300).contains(status) else {{
+            throw TeoError(status: status, body: String(decoding: data, as: UTF8.self))
+        }}
+        let key = path.split(separator: "/").joined(separator: ".")
+        let envelope = envelopes[key] ?? envelopes[String(key.split(separator: ".").last ?? "")] ?? envelopes[""]!
+        let body = try JSONSerialization.jsonObject(with: data, options: [.fragmentsAllowed])
+        var normalized: [String: Any] = [:]
+        if let dataKey = envelope.data {{
+            let object = body as? [String: Any] ?? [:]
+            normalized["data"] = object[dataKey] ?? NSNull()
+            normalized["meta"] = object[envelope.meta]
+        }} else {{
+            normalized["data"] = body
+            if let meta = (response as? HTTPURLResponse)?.value(forHTTPHeaderField: "x-teo-meta") {{
+                normalized["meta"] = try JSONSerialization.jsonObject(with: Data(meta.utf8))
+            }}
+        }}
+        return try JSONDecoder().decode(Response<T>.self, from: JSONSerialization.data(withJSONObject: normalized))
+    }}
+"#);
+    for model in models {
+        content.push_str(&format!("\n    public lazy var {}: {}Delegate = {}Delegate(teo: self)\n", accessor_name(model), type_name(model), type_name(model)));
+    }
+    content.push_str("}\n");
+    for model in models {
+        let name = type_name(model);
+        content.push_str(&format!("\npublic struct {}: Codable {{\n", name));
+        for field in model.fields.values() {
+            let mut swift_type = swift_type(field.r#type());
+            if field.is_optional() && !swift_type.ends_with('?') {
+                swift_type.push('?');
+            }
+            content.push_str(&format!("    public let {}: {}\n", field.name(), swift_type));
+        }
+        content.push_str("}\n");
+        content.push_str(&format!("\npublic final class {}Delegate {{\n    unowned let teo: Teo\n\n    init(teo: Teo) {{\n        self.teo = teo\n    }}\n", name));
+        for (action, output) in ACTIONS {
+            let return_type = match output {
+                Output::Record => format!("{}?", name),
+                Output::Records => format!("[{}]", name),
+                Output::OptionalRecords => format!("[{}?]", name),
+                Output::Count => "Int".to_owned(),
+                Output::Json => "JSONValue".to_owned(),
+            };
+            content.push_str(&format!(
+                "\n    public func {}(_ input: [String: JSONValue] = [:]) async throws -> Response<{}> {{\n        try await teo.request(\"{}\", input)\n    }}\n",
+                action, return_type, action_path(model, action),
+            ));
+        }
+        content.push_str("}\n");
+    }
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join("Teo.swift"), content).map_err(io_error)
+}
+
+fn swift_type(t: &Type) -> String {
+    match t {
+        Type::Bool => "Bool".to_owned(),
+        Type::Int => "Int32".to_owned(),
+        Type::Int64 => "Int64".to_owned(),
+        Type::Float32 => "Float".to_owned(),
+        Type::Float => "Double".to_owned(),
+        // decimals, object ids and dates are transferred as strings
+        Type::Decimal | Type::String | Type::ObjectId | Type::Date | Type::DateTime => "String".to_owned(),
+        Type::Array(inner) => format!("[{}]", swift_type(inner)),
+        Type::Dictionary(inner) => format!("[String: {}]", swift_type(inner)),
+        Type::Optional(inner) => format!("{}?", swift_type(inner).trim_end_matches('?')),
+        Type::EnumVariant(_) => "String".to_owned(),
+        _ => "JSONValue".to_owned(),
+    }
+}
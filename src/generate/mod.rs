@@ -0,0 +1,108 @@
+pub(crate) mod errors;
+pub(crate) mod hooks;
+pub(crate) mod mobile;
+pub(crate) mod permissions;
+pub(crate) mod proto;
+pub(crate) mod queries;
+pub(crate) mod scalars;
+pub(crate) mod transport;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use ring::digest::{digest, SHA256};
+use teo_result::{Error, Result};
+
+/// The manifest file written into the generation destination. It records the
+/// hash of each generated file, so that unchanged files are not rewritten and
+/// files which are no longer generated can be removed.
+pub(crate) const MANIFEST_FILE_NAME: &str = ".teo-manifest.json";
+
+/// Generate into a staging directory, then copy only the changed files into
+/// `dest`, or all of them with `full`. Files recorded in the previous manifest
+/// but not generated this time are deleted.
+pub(crate) async fn generate_incrementally<F, Fut>(dest: &Path, full: bool, f: F) -> Result<()> where F: FnOnce(String) -> Fut, Fut: Future<Output = Result<()>> {
+    let staging = std::env::temp_dir().join(format!("teo-generate-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging).map_err(io_error)?;
+    let result = match f(staging.to_str().unwrap().to_owned()).await {
+        Ok(()) => sync_staging_into_dest(&staging, dest, full),
+        Err(err) => Err(err),
+    };
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn sync_staging_into_dest(staging: &Path, dest: &Path, full: bool) -> Result<()> {
+    let old_manifest = read_manifest(dest);
+    let mut new_manifest = BTreeMap::new();
+    for relative in list_files(staging)? {
+        let content = fs::read(staging.join(&relative)).map_err(io_error)?;
+        let hash = hash_content(&content);
+        let target = dest.join(&relative);
+        let unchanged = !full && target.is_file() && fs::read(&target).map(|c| hash_content(&c) == hash).unwrap_or(false);
+        if !unchanged {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(io_error)?;
+            }
+            fs::write(&target, &content).map_err(io_error)?;
+        }
+        new_manifest.insert(relative_key(&relative), hash);
+    }
+    for stale in old_manifest.keys().filter(|k| !new_manifest.contains_key(*k)) {
+        let target = dest.join(stale);
+        if target.is_file() {
+            fs::remove_file(&target).map_err(io_error)?;
+        }
+    }
+    write_manifest(dest, &new_manifest)
+}
+
+fn list_files(base: &Path) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    list_files_internal(base, &PathBuf::new(), &mut result)?;
+    result.sort();
+    Ok(result)
+}
+
+fn list_files_internal(base: &Path, relative: &Path, result: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(base.join(relative)).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let entry_relative = relative.join(entry.file_name());
+        if entry.path().is_dir() {
+            list_files_internal(base, &entry_relative, result)?;
+        } else {
+            result.push(entry_relative);
+        }
+    }
+    Ok(())
+}
+
+fn hash_content(content: &[u8]) -> String {
+    digest(&SHA256, content).as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn relative_key(relative: &Path) -> String {
+    relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect::<Vec<String>>().join("/")
+}
+
+fn read_manifest(dest: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(dest.join(MANIFEST_FILE_NAME)).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(dest: &Path, manifest: &BTreeMap<String, String>) -> Result<()> {
+    fs::create_dir_all(dest).map_err(io_error)?;
+    let content = serde_json::to_string_pretty(manifest).unwrap();
+    let path = dest.join(MANIFEST_FILE_NAME);
+    let unchanged = fs::read_to_string(&path).map(|c| c == content).unwrap_or(false);
+    if !unchanged {
+        fs::write(path, content).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+fn io_error(err: std::io::Error) -> Error {
+    Error::new(format!("{}", err))
+}
@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::Path;
+use serde_json::{Map, Value as JsonValue};
+use teo_result::Result;
+use teo_runtime::namespace::Namespace;
+use crate::generate::mobile::{collect_models, io_error};
+use crate::stdlib::decorators::permissions::{model_guard, PERMISSION_ACTIONS};
+
+/// The file name of the generated capability map.
+pub(crate) const PERMISSIONS_FILE_NAME: &str = "permissions.ts";
+
+/// Write the `@@permissions` guards of the models next to a generated
+/// TypeScript client, with a `can` helper to hide what the identity isn't
+/// allowed to do. The server checks the guards regardless.
+pub(crate) fn generate_permissions(namespace: &Namespace, dest: &Path) -> Result<()> {
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    let mut map = Map::new();
+    for model in models {
+        let mut guards = Map::new();
+        for (_, actions) in PERMISSION_ACTIONS {
+            for action in actions.iter() {
+                if let Some(guard) = model_guard(model, action) {
+                    guards.insert(action.to_string(), JsonValue::String(guard.desc()));
+                }
+            }
+        }
+        if !guards.is_empty() {
+            map.insert(model.path().join("."), JsonValue::Object(guards));
+        }
+    }
+    let content = PERMISSIONS_SOURCE.replace("{{PERMISSIONS}}", &serde_json::to_string_pretty(&JsonValue::Object(map)).unwrap());
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join(PERMISSIONS_FILE_NAME), content).map_err(io_error)
+}
+
+const PERMISSIONS_SOURCE: &str = r#"// This file is generated by Teo, do not edit it.
+export type Guard = "everyone" | "identity" | "nobody" | `role:${string}`
+
+// The guards of model actions keyed by the model path. Actions which are not
+// listed are not restricted.
+export const permissions: { [model: string]: { [action: string]: Guard } } = {{PERMISSIONS}}
+
+export interface Identity {
+    role?: string | string[] | null
+}
+
+// Whether an identity, or a guest if it's null, may run an action of a model.
+export function can(identity: Identity | null | undefined, model: string, action: string): boolean {
+    const guard = permissions[model]?.[action]
+    if (guard === undefined || guard === "everyone") {
+        return true
+    }
+    if (guard === "nobody" || !identity) {
+        return false
+    }
+    if (guard === "identity") {
+        return true
+    }
+    const role = guard.slice("role:".length)
+    return Array.isArray(identity.role) ? identity.role.includes(role) : identity.role === role
+}
+"#;
@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::Path;
+use teo_parser::r#type::Type;
+use teo_result::Result;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::namespace::Namespace;
+use crate::generate::mobile::{collect_models, io_error, type_name, upper_first, Output, ACTIONS};
+
+/// Generate a `teo.proto` describing the models of `namespace` for gRPC
+/// clients: a message per model and a service per model with an rpc per
+/// action. Action inputs are the same JSON arguments as the HTTP API, passed
+/// as `google.protobuf.Struct`. `teo serve --grpc PORT` serves the services.
+pub(crate) fn generate_proto(namespace: &Namespace, dest: &Path, package: &str) -> Result<()> {
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    let mut content = format!(r#"// This file is generated by Teo, do not edit it.
+syntax = "proto3";
+
+package {package};
+
+import "google/protobuf/struct.proto";
+
+message Meta {{
+  optional int64 count = 1;
+}}
+
+message CountResponse {{
+  int64 data = 1;
+}}
+
+message JsonResponse {{
+  google.protobuf.Value data = 1;
+}}
+"#);
+    for model in &models {
+        let name = type_name(model);
+        content.push_str(&format!("\nmessage {} {{\n", name));
+        for (index, field) in model.fields.values().enumerate() {
+            let proto_type = proto_type(field.r#type());
+            let label = if proto_type.starts_with("repeated ") || proto_type.starts_with("map<") {
+                ""
+            } else if field.is_optional() {
+                "optional "
+            } else {
+                ""
+            };
+            content.push_str(&format!("  {}{} {} = {};\n", label, proto_type, field.name(), index + 1));
+        }
+        content.push_str("}\n");
+        content.push_str(&format!("\nmessage {name}Response {{\n  optional {name} data = 1;\n}}\n"));
+        content.push_str(&format!("\nmessage {name}ListResponse {{\n  repeated {name} data = 1;\n  Meta meta = 2;\n}}\n"));
+        content.push_str(&format!("\nservice {}Service {{\n", name));
+        for (action, output) in ACTIONS {
+            let response = match output {
+                Output::Record => format!("{}Response", name),
+                Output::Records => format!("{}ListResponse", name),
+                Output::Count => "CountResponse".to_owned(),
+                // repeated messages can't hold nulls
+                Output::OptionalRecords | Output::Json => "JsonResponse".to_owned(),
+            };
+            content.push_str(&format!("  rpc {}(google.protobuf.Struct) returns ({});\n", upper_first(action), response));
+        }
+        content.push_str("}\n");
+    }
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join("teo.proto"), content).map_err(io_error)
+}
+
+/// The protobuf type of a field type. The gRPC server encodes records by
+/// it, so it must stay in step with the generated messages.
+pub(crate) fn proto_type(t: &Type) -> String {
+    match t {
+        Type::Bool => "bool".to_owned(),
+        Type::Int => "int32".to_owned(),
+        Type::Int64 => "int64".to_owned(),
+        Type::Float32 => "float".to_owned(),
+        Type::Float => "double".to_owned(),
+        // decimals, object ids and dates are transferred as strings
+        Type::Decimal | Type::String | Type::ObjectId | Type::Date | Type::DateTime | Type::EnumVariant(_) => "string".to_owned(),
+        Type::Optional(inner) => proto_type(inner),
+        Type::Array(inner) => match proto_type(inner) {
+            inner if inner.starts_with("repeated ") || inner.starts_with("map<") => "google.protobuf.ListValue".to_owned(),
+            inner => format!("repeated {}", inner),
+        },
+        Type::Dictionary(inner) => match proto_type(inner) {
+            inner if inner.starts_with("repeated ") || inner.starts_with("map<") => "google.protobuf.Struct".to_owned(),
+            inner => format!("map<string, {}>", inner),
+        },
+        _ => "google.protobuf.Value".to_owned(),
+    }
+}
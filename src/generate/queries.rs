@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde_json::Value as JsonValue;
+use teo_parser::r#type::Type;
+use teo_result::Result;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use crate::app::ctx::Ctx;
+use crate::generate::mobile::{accessor_name, action_path, io_error, upper_first};
+
+/// The file name of the generated named queries.
+pub(crate) const QUERIES_FILE_NAME: &str = "queries.ts";
+
+/// Write a function for each named query next to a generated TypeScript
+/// client, named after the model and the query, e.g. `userActiveUsers`. The
+/// parameters are typed by the fields they're compared with. Nothing is
+/// written if the schema doesn't declare queries.
+pub(crate) fn generate_queries(namespace: &Namespace, dest: &Path) -> Result<()> {
+    if Ctx::named_queries().is_empty() {
+        return Ok(());
+    }
+    let mut content = r#"// This file is generated by Teo, do not edit it.
+export interface QueryOptions {
+    host: string
+    headers?: Record<string, string>
+}
+
+export interface QueryPage {
+    skip?: number
+    take?: number
+}
+
+async function runQuery<T>(options: QueryOptions, path: string, body: object): Promise<T[]> {
+    const response = await fetch(options.host + path, {
+        method: "POST",
+        headers: { "Content-Type": "application/json", ...options.headers },
+        body: JSON.stringify(body),
+    })
+    const json = await response.json()
+    if (!response.ok) {
+        throw json.error
+    }
+    return json.data
+}
+"#.to_owned();
+    for query in Ctx::named_queries() {
+        let Some(model) = namespace.model_at_path(&query.model.iter().map(|s| s.as_str()).collect()) else { continue };
+        let mut types = BTreeMap::new();
+        if let Some(filter) = query.arguments.get("where") {
+            parameter_types(model, filter, None, &mut types);
+        }
+        let params: Vec<String> = query.parameters.iter().map(|name| format!("{}: {}", name, types.get(name).map_or("unknown", |t| t.as_str()))).collect();
+        let (params_argument, params_body) = if params.is_empty() {
+            ("".to_owned(), "")
+        } else {
+            (format!("params: {{ {} }}, ", params.join(", ")), "params, ")
+        };
+        content.push_str(&format!(
+            "\nexport function {}{}<T = any>(options: QueryOptions, {}page?: QueryPage): Promise<T[]> {{\n    return runQuery<T>(options, \"{}\", {{ {}...page }})\n}}\n",
+            accessor_name(model), upper_first(&query.name), params_argument, action_path(model, &query.name), params_body,
+        ));
+    }
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join(QUERIES_FILE_NAME), content).map_err(io_error)
+}
+
+/// Record the TypeScript type of the parameters in a where input by the
+/// field they're compared with.
+fn parameter_types(model: &Model, value: &JsonValue, field: Option<&Type>, types: &mut BTreeMap<String, String>) {
+    let Some(object) = value.as_object() else {
+        if let Some(list) = value.as_array() {
+            list.iter().for_each(|v| parameter_types(model, v, field, types));
+        }
+        return
+    };
+    if let (1, Some(JsonValue::String(name))) = (object.len(), object.get("$param")) {
+        types.insert(name.clone(), field.map_or("unknown".to_owned(), typescript_type));
+        return
+    }
+    for (key, value) in object {
+        match key.as_str() {
+            "in" | "notIn" => if let (Some(JsonValue::String(name)), Some(field)) = (value.get("$param"), field) {
+                types.insert(name.clone(), format!("{}[]", typescript_type(field)));
+            } else {
+                parameter_types(model, value, field, types);
+            },
+            "AND" | "OR" | "NOT" => parameter_types(model, value, None, types),
+            key => match model.field(key) {
+                Some(f) => parameter_types(model, value, Some(f.r#type()), types),
+                None => parameter_types(model, value, field, types),
+            },
+        }
+    }
+}
+
+fn typescript_type(t: &Type) -> String {
+    match t {
+        Type::Optional(inner) => typescript_type(inner),
+        Type::String | Type::ObjectId | Type::Date | Type::DateTime | Type::Decimal | Type::EnumVariant(_) => "string".to_owned(),
+        Type::Int | Type::Int64 | Type::Float32 | Type::Float => "number".to_owned(),
+        Type::Bool => "boolean".to_owned(),
+        _ => "unknown".to_owned(),
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use teo_result::Result;
+use teo_runtime::namespace::Namespace;
+use crate::app::ctx::Ctx;
+use crate::generate::mobile::{collect_models, io_error};
+use crate::stdlib::decorators::scalar::field_scalar;
+
+/// The file name of the generated custom scalar types.
+pub(crate) const SCALARS_FILE_NAME: &str = "scalars.ts";
+
+/// Write the TypeScript types of the custom scalar types used by the models
+/// next to a generated TypeScript client, with the scalar type of each field
+/// keyed by the model path. Nothing is written if no field uses one.
+pub(crate) fn generate_scalars(namespace: &Namespace, dest: &Path) -> Result<()> {
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    let mut types = BTreeMap::new();
+    let mut fields: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for model in models {
+        for field in model.fields.values() {
+            let Some(name) = field_scalar(field) else { continue };
+            let Some(codec) = Ctx::scalar(name) else { continue };
+            types.insert(name.to_owned(), codec.typescript_type());
+            fields.entry(model.path().join(".")).or_default().insert(field.name().to_owned(), name.to_owned());
+        }
+    }
+    if types.is_empty() {
+        return Ok(());
+    }
+    let mut content = "// This file is generated by Teo, do not edit it.\n".to_owned();
+    for (name, typescript_type) in &types {
+        content.push_str(&format!("export type {} = {}\n", name, typescript_type));
+    }
+    content.push_str(&format!("\n// The custom scalar types of model fields keyed by the model path.\nexport const scalarFields: {{ [model: string]: {{ [field: string]: string }} }} = {}\n", serde_json::to_string_pretty(&fields).unwrap()));
+    fs::create_dir_all(dest).map_err(io_error)?;
+    fs::write(dest.join(SCALARS_FILE_NAME), content).map_err(io_error)
+}
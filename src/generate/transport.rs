@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::Path;
+use teo_result::{Error, Result};
+use crate::app::ctx::Ctx;
+
+/// The file name of the generated transport module.
+pub(crate) const TRANSPORT_FILE_NAME: &str = "transport.ts";
+
+/// Write the transport module next to a generated TypeScript client.
+///
+/// `installTransport` wraps `fetch` for requests to the client host with
+/// timeouts, retries of reads, interceptors and a token refresh hook. Actions
+/// are all sent with `POST`, so reads are recognized by the action name.
+/// Responses in a custom envelope are reshaped back to `{ data, meta }` and
+/// `{ error }`, which the client expects.
+pub(crate) fn generate_transport(dest: &Path) -> Result<()> {
+    let source = TRANSPORT_SOURCE.replace("__ENVELOPES__", &Ctx::envelopes().to_json().to_string());
+    fs::create_dir_all(dest).map_err(|e| Error::new(format!("{}", e)))?;
+    fs::write(dest.join(TRANSPORT_FILE_NAME), source).map_err(|e| Error::new(format!("{}", e)))
+}
+
+const TRANSPORT_SOURCE: &str = r#"// This file is generated by Teo, do not edit it.
+export type RequestInterceptor = (request: Request) => Request | Promise<Request>
+export type ResponseInterceptor = (response: Response, request: Request) => Response | Promise<Response>
+
+export interface TransportOptions {
+    // Only requests whose URL starts with the host are handled.
+    host: string
+    // Abort a request after this many milliseconds.
+    timeout?: number
+    // How many times a failed read is retried.
+    retries?: number
+    // The delay before the first retry in milliseconds, doubled for each retry.
+    retryDelay?: number
+    requestInterceptors?: RequestInterceptor[]
+    responseInterceptors?: ResponseInterceptor[]
+    // Called once when a request is rejected with 401. Return a new access
+    // token to retry the request with it, or undefined to give up.
+    refreshToken?: () => Promise<string | undefined>
+}
+
+const READ_ACTIONS = ["findUnique", "findFirst", "findMany", "count", "aggregate", "groupBy"]
+
+function isRead(request: Request): boolean {
+    if (request.method === "GET" || request.method === "HEAD") {
+        return true
+    }
+    const action = new URL(request.url).pathname.split("/").pop() ?? ""
+    return READ_ACTIONS.includes(action)
+}
+
+interface Envelope {
+    data: string | null
+    meta: string
+    error: string
+}
+
+// The response envelopes of the server.
+const ENVELOPES: { default: Envelope, overrides: { [action: string]: Envelope } } = __ENVELOPES__
+
+function envelopeFor(host: string, request: Request): Envelope {
+    const parts = request.url.slice(host.length).split("?")[0].split("/").filter((part) => part !== "")
+    const key = parts.join(".")
+    return ENVELOPES.overrides[key] ?? ENVELOPES.overrides[parts[parts.length - 1] ?? ""] ?? ENVELOPES.default
+}
+
+// Reshape a response in a custom envelope to `{ data, meta }` or `{ error }`.
+async function normalize(response: Response, envelope: Envelope): Promise<Response> {
+    if (envelope.data === "data" && envelope.meta === "meta" && envelope.error === "error") {
+        return response
+    }
+    if (!(response.headers.get("Content-Type") ?? "").includes("application/json")) {
+        return response
+    }
+    let body: any
+    try {
+        body = await response.clone().json()
+    } catch {
+        return response
+    }
+    let normalized: any
+    if (!response.ok) {
+        if (body === null || typeof body !== "object" || !(envelope.error in body)) {
+            return response
+        }
+        normalized = { error: body[envelope.error] }
+    } else if (envelope.data === null) {
+        const meta = response.headers.get("x-teo-meta")
+        normalized = meta === null ? { data: body } : { data: body, meta: JSON.parse(meta) }
+    } else {
+        normalized = { data: body?.[envelope.data] ?? null }
+        if (body !== null && typeof body === "object" && envelope.meta in body) {
+            normalized.meta = body[envelope.meta]
+        }
+    }
+    return new Response(JSON.stringify(normalized), { status: response.status, statusText: response.statusText, headers: response.headers })
+}
+
+function sleep(milliseconds: number): Promise<void> {
+    return new Promise((resolve) => setTimeout(resolve, milliseconds))
+}
+
+async function send(originalFetch: typeof fetch, request: Request, timeout?: number): Promise<Response> {
+    if (timeout === undefined) {
+        return originalFetch(request)
+    }
+    const controller = new AbortController()
+    const timer = setTimeout(() => controller.abort(), timeout)
+    try {
+        return await originalFetch(new Request(request, { signal: controller.signal }))
+    } finally {
+        clearTimeout(timer)
+    }
+}
+
+// Install the transport. Returns a function restoring the original fetch.
+export function installTransport(options: TransportOptions): () => void {
+    const originalFetch = globalThis.fetch
+    globalThis.fetch = async (input: RequestInfo | URL, init?: RequestInit): Promise<Response> => {
+        let request = new Request(input, init)
+        if (!request.url.startsWith(options.host)) {
+            return originalFetch(request)
+        }
+        for (const interceptor of options.requestInterceptors ?? []) {
+            request = await interceptor(request)
+        }
+        const retries = isRead(request) ? (options.retries ?? 0) : 0
+        let response: Response | undefined = undefined
+        for (let attempt = 0; attempt <= retries; attempt++) {
+            if (attempt > 0) {
+                await sleep((options.retryDelay ?? 200) * 2 ** (attempt - 1))
+            }
+            try {
+                response = await send(originalFetch, request.clone(), options.timeout)
+            } catch (error) {
+                if (attempt === retries) {
+                    throw error
+                }
+                continue
+            }
+            if (response.status < 500) {
+                break
+            }
+        }
+        if (response!.status === 401 && options.refreshToken) {
+            const token = await options.refreshToken()
+            if (token !== undefined) {
+                const retried = new Request(request.clone())
+                retried.headers.set("Authorization", `Bearer ${token}`)
+                response = await send(originalFetch, retried, options.timeout)
+            }
+        }
+        response = await normalize(response!, envelopeFor(options.host, request))
+        for (const interceptor of options.responseInterceptors ?? []) {
+            response = await interceptor(response!, request)
+        }
+        return response!
+    }
+    return () => {
+        globalThis.fetch = originalFetch
+    }
+}
+"#;
@@ -0,0 +1,159 @@
+use prost::bytes::{Buf, BufMut};
+use prost::encoding::{self, encode_key, encode_varint, DecodeContext, WireType};
+use prost::DecodeError;
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+use serde_json::{Map, Number, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::Model;
+use crate::generate::mobile::Output;
+use crate::generate::proto::proto_type;
+
+/// A message which is encoded already. Responses are encoded from the JSON
+/// responses of the server by the layout of the generated `.proto`, as the
+/// messages are only known at runtime.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Encoded(pub(super) Vec<u8>);
+
+impl prost::Message for Encoded {
+
+    fn encode_raw<B>(&self, buf: &mut B) where B: BufMut, Self: Sized {
+        buf.put_slice(&self.0);
+    }
+
+    fn merge_field<B>(&mut self, tag: u32, wire_type: WireType, buf: &mut B, ctx: DecodeContext) -> std::result::Result<(), DecodeError> where B: Buf, Self: Sized {
+        encoding::skip_field(wire_type, tag, buf, ctx)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Encode the `data` and `meta` of a response as the response message of
+/// the action.
+pub(super) fn encode_response(model: &Model, output: Output, data: &JsonValue, meta: Option<&JsonValue>) -> Result<Encoded> {
+    let mut buf = vec![];
+    match output {
+        Output::Record => if data.is_object() {
+            encode_bytes(1, &encode_record(model, data)?, &mut buf);
+        },
+        Output::Records => {
+            for record in data.as_array().into_iter().flatten() {
+                encode_bytes(1, &encode_record(model, record)?, &mut buf);
+            }
+            let mut meta_buf = vec![];
+            if let Some(count) = meta.and_then(|meta| meta.get("count")).and_then(JsonValue::as_i64) {
+                encoding::int64::encode(1, &count, &mut meta_buf);
+            }
+            encode_bytes(2, &meta_buf, &mut buf);
+        }
+        Output::Count => encoding::int64::encode(1, &integer(data)?, &mut buf),
+        Output::OptionalRecords | Output::Json => encoding::message::encode(1, &value(data), &mut buf),
+    }
+    Ok(Encoded(buf))
+}
+
+/// Encode a record as the message of its model. The fields are numbered
+/// in the order they are declared, like the generated messages.
+fn encode_record(model: &Model, record: &JsonValue) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+    for (index, field) in model.fields.values().enumerate() {
+        let Some(value) = record.get(field.name()).filter(|value| !value.is_null()) else { continue };
+        encode_field(index as u32 + 1, &proto_type(field.r#type()), value, &mut buf)
+            .map_err(|e| Error::new(format!("cannot encode `{}.{}`: {}", model.path().join("."), field.name(), e.message)))?;
+    }
+    Ok(buf)
+}
+
+fn encode_field(tag: u32, proto_type: &str, value: &JsonValue, buf: &mut Vec<u8>) -> Result<()> {
+    if let Some(item_type) = proto_type.strip_prefix("repeated ") {
+        // proto3 parsers accept unpacked repeated scalars
+        for item in value.as_array().ok_or_else(|| Error::new("expect a list"))? {
+            encode_scalar(tag, item_type, item, buf)?;
+        }
+        return Ok(());
+    }
+    if let Some(value_type) = proto_type.strip_prefix("map<string, ").and_then(|t| t.strip_suffix('>')) {
+        for (key, item) in value.as_object().ok_or_else(|| Error::new("expect an object"))? {
+            let mut entry = vec![];
+            encoding::string::encode(1, key, &mut entry);
+            encode_scalar(2, value_type, item, &mut entry)?;
+            encode_bytes(tag, &entry, buf);
+        }
+        return Ok(());
+    }
+    encode_scalar(tag, proto_type, value, buf)
+}
+
+fn encode_scalar(tag: u32, proto_type: &str, value: &JsonValue, buf: &mut Vec<u8>) -> Result<()> {
+    match proto_type {
+        "bool" => encoding::bool::encode(tag, &value.as_bool().ok_or_else(|| Error::new("expect a bool"))?, buf),
+        "int32" => encoding::int32::encode(tag, &(integer(value)? as i32), buf),
+        "int64" => encoding::int64::encode(tag, &integer(value)?, buf),
+        "float" => encoding::float::encode(tag, &(number(value)? as f32), buf),
+        "double" => encoding::double::encode(tag, &number(value)?, buf),
+        "string" => encoding::string::encode(tag, &value.as_str().map(ToOwned::to_owned).unwrap_or_else(|| value.to_string()), buf),
+        "google.protobuf.ListValue" => encoding::message::encode(tag, &ListValue { values: value.as_array().ok_or_else(|| Error::new("expect a list"))?.iter().map(self::value).collect() }, buf),
+        "google.protobuf.Struct" => encoding::message::encode(tag, &structure(value.as_object().ok_or_else(|| Error::new("expect an object"))?), buf),
+        _ => encoding::message::encode(tag, &self::value(value), buf),
+    }
+    Ok(())
+}
+
+fn encode_bytes(tag: u32, bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_key(tag, WireType::LengthDelimited, buf);
+    encode_varint(bytes.len() as u64, buf);
+    buf.put_slice(bytes);
+}
+
+fn integer(value: &JsonValue) -> Result<i64> {
+    match value {
+        JsonValue::Number(number) => number.as_i64(),
+        JsonValue::String(string) => string.parse().ok(),
+        _ => None,
+    }.ok_or_else(|| Error::new("expect an integer"))
+}
+
+fn number(value: &JsonValue) -> Result<f64> {
+    value.as_f64().ok_or_else(|| Error::new("expect a number"))
+}
+
+fn value(json: &JsonValue) -> Value {
+    let kind = match json {
+        JsonValue::Null => Kind::NullValue(0),
+        JsonValue::Bool(b) => Kind::BoolValue(*b),
+        JsonValue::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        JsonValue::String(s) => Kind::StringValue(s.clone()),
+        JsonValue::Array(items) => Kind::ListValue(ListValue { values: items.iter().map(value).collect() }),
+        JsonValue::Object(object) => Kind::StructValue(structure(object)),
+    };
+    Value { kind: Some(kind) }
+}
+
+fn structure(object: &Map<String, JsonValue>) -> Struct {
+    Struct { fields: object.iter().map(|(key, item)| (key.clone(), value(item))).collect() }
+}
+
+/// The JSON arguments of a `google.protobuf.Struct` input. Protobuf numbers
+/// are doubles, whole ones are turned into integers for integer fields.
+pub(super) fn struct_json(input: &Struct) -> JsonValue {
+    JsonValue::Object(input.fields.iter().map(|(key, item)| (key.clone(), value_json(item))).collect())
+}
+
+fn value_json(value: &Value) -> JsonValue {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => JsonValue::Null,
+        Some(Kind::BoolValue(b)) => JsonValue::Bool(*b),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < 9007199254740992.0 => JsonValue::Number(Number::from(*n as i64)),
+        Some(Kind::NumberValue(n)) => Number::from_f64(*n).map_or(JsonValue::Null, JsonValue::Number),
+        Some(Kind::StringValue(s)) => JsonValue::String(s.clone()),
+        Some(Kind::ListValue(list)) => JsonValue::Array(list.values.iter().map(value_json).collect()),
+        Some(Kind::StructValue(object)) => struct_json(object),
+    }
+}
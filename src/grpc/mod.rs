@@ -0,0 +1,167 @@
+//! The gRPC server, started by `teo serve --grpc PORT`.
+//!
+//! It serves the services of the `.proto` generated by `teo generate proto`
+//! for internal service-to-service traffic. Each call is forwarded to the
+//! HTTP action of the same model and name on the loopback interface, with
+//! the ascii metadata of the call as headers. So calls pass the same
+//! middlewares, identity and guards as HTTP requests, and an `authorization`
+//! metadata entry signs a call in like the header does. Errors are returned
+//! as gRPC statuses, with the JSON error of the server as their details.
+
+mod encode;
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use tonic::codec::ProstCodec;
+use tonic::codegen::Bytes;
+use tonic::metadata::KeyAndValueRef;
+use tonic::server::{Grpc, UnaryService};
+use tonic::{Code, Status};
+use crate::app::ctx::Ctx;
+use crate::generate::mobile::{action_path, collect_models, type_name, upper_first, Output, ACTIONS};
+use crate::grpc::encode::{encode_response, struct_json, Encoded};
+use crate::message::info_message;
+use crate::server::envelope::META_HEADER;
+
+/// Metadata which belongs to the gRPC call and isn't forwarded.
+const CALL_METADATA: [&str; 4] = ["content-type", "te", "user-agent", "content-length"];
+
+/// An rpc of a model service.
+#[derive(Clone, Copy)]
+struct Method {
+    model: &'static Model,
+    action: &'static str,
+    output: Output,
+}
+
+/// Forwards the calls of an rpc to the HTTP server.
+#[derive(Clone)]
+struct Forward {
+    method: Method,
+    /// The URL of the HTTP server, path prefix included.
+    base: Arc<String>,
+    client: reqwest::Client,
+}
+
+/// Start the gRPC server on `port`, serving the services of the protobuf
+/// package `package`.
+pub(crate) fn start_grpc_server(port: u16, package: &str, silent: bool) -> Result<()> {
+    let conf = Ctx::main_namespace().server.as_ref().ok_or_else(|| Error::new("the gRPC server requires a server config"))?;
+    let host = match conf.bind.0.as_str() {
+        "0.0.0.0" => "127.0.0.1".to_owned(),
+        "::" => "[::1]".to_owned(),
+        host => host.to_owned(),
+    };
+    let prefix = conf.path_prefix.as_deref().unwrap_or("").trim_end_matches('/');
+    let base = Arc::new(format!("http://{}:{}{}", host, conf.bind.1, prefix));
+    let methods = Arc::new(methods(package));
+    let client = reqwest::Client::new();
+    let address: SocketAddr = format!("{}:{}", conf.bind.0, port).parse().map_err(|e| Error::new(format!("invalid gRPC address: {}", e)))?;
+    let make_service = make_service_fn(move |_| {
+        let (methods, base, client) = (methods.clone(), base.clone(), client.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                let method = methods.get(request.uri().path()).copied();
+                let forward = method.map(|method| Forward { method, base: base.clone(), client: client.clone() });
+                async move {
+                    Ok::<_, Infallible>(match forward {
+                        Some(forward) => Grpc::new(ProstCodec::<Encoded, prost_types::Struct>::default()).unary(forward, request).await,
+                        None => Status::unimplemented(format!("unknown method {}", request.uri().path())).to_http(),
+                    })
+                }
+            }))
+        }
+    });
+    let server = Server::try_bind(&address).map_err(|e| Error::new(format!("cannot bind {}: {}", address, e)))?
+        .http2_only(true)
+        .serve(make_service);
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            info_message(format!("the gRPC server stopped: {}", err));
+        }
+    });
+    if !silent {
+        info_message(format!("gRPC listening on {}", address));
+    }
+    Ok(())
+}
+
+/// The rpcs by their paths, e.g. `/teo.BlogPostService/FindMany`.
+fn methods(package: &str) -> HashMap<String, Method> {
+    let mut models = vec![];
+    collect_models(Ctx::main_namespace(), &mut models);
+    let mut methods = HashMap::new();
+    for model in models {
+        for (action, output) in ACTIONS {
+            methods.insert(format!("/{}.{}Service/{}", package, type_name(model), upper_first(action)), Method { model, action, output });
+        }
+    }
+    methods
+}
+
+impl UnaryService<prost_types::Struct> for Forward {
+    type Response = Encoded;
+    type Future = BoxFuture<'static, std::result::Result<tonic::Response<Encoded>, Status>>;
+
+    fn call(&mut self, request: tonic::Request<prost_types::Struct>) -> Self::Future {
+        let forward = self.clone();
+        Box::pin(async move { forward.forward(request).await })
+    }
+}
+
+impl Forward {
+
+    async fn forward(&self, request: tonic::Request<prost_types::Struct>) -> std::result::Result<tonic::Response<Encoded>, Status> {
+        let Method { model, action, output } = self.method;
+        let (metadata, _, input) = request.into_parts();
+        let mut http_request = self.client.post(format!("{}{}", self.base, action_path(model, action))).json(&struct_json(&input));
+        for entry in metadata.iter() {
+            let KeyAndValueRef::Ascii(key, value) = entry else { continue };
+            if key.as_str().starts_with("grpc-") || CALL_METADATA.contains(&key.as_str()) {
+                continue
+            }
+            if let Ok(value) = value.to_str() {
+                http_request = http_request.header(key.as_str(), value);
+            }
+        }
+        let response = http_request.send().await.map_err(|e| Status::unavailable(format!("{}", e)))?;
+        let status = response.status().as_u16();
+        let meta = response.headers().get(META_HEADER).and_then(|value| value.to_str().ok()).and_then(|value| serde_json::from_str(value).ok());
+        let body: JsonValue = response.json().await.map_err(|e| Status::internal(format!("invalid response of the server: {}", e)))?;
+        let envelope = Ctx::envelopes().envelope_for(&model.path().join("."), action);
+        let (data, meta, error) = envelope.open(body, meta, status >= 400);
+        if let Some(error) = error {
+            return Err(error_status(status, &error));
+        }
+        let encoded = encode_response(model, output, &data, meta.as_ref()).map_err(|e| Status::internal(e.message))?;
+        Ok(tonic::Response::new(encoded))
+    }
+}
+
+/// The status of an HTTP error response.
+fn error_status(status: u16, error: &JsonValue) -> Status {
+    let code = match status {
+        400 | 422 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        409 => Code::AlreadyExists,
+        412 => Code::FailedPrecondition,
+        413 => Code::OutOfRange,
+        423 | 429 => Code::ResourceExhausted,
+        501 => Code::Unimplemented,
+        503 => Code::Unavailable,
+        504 => Code::DeadlineExceeded,
+        _ => Code::Internal,
+    };
+    let message = error.get("message").and_then(JsonValue::as_str).unwrap_or("request failed").to_owned();
+    Status::with_details(code, message, Bytes::from(error.to_string()))
+}
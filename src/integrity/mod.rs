@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashSet};
+use colored::Colorize;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::index::Type as IndexType;
+use teo_runtime::model::relation::delete::Delete;
+use teo_runtime::model::relation::Relation;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::generate::mobile::collect_models;
+use crate::message::info_message;
+use crate::server::export_cursor::{export_page, DEFAULT_PAGE_SIZE};
+use crate::stdlib::decorators::view::model_view;
+
+/// How many keys are looked up in one query.
+const BATCH_SIZE: usize = 500;
+
+/// How many offending values of an issue are printed.
+const SAMPLE_SIZE: usize = 5;
+
+/// Distinct key values of the records of a model and how many records hold
+/// each, keyed by their JSON.
+type Tally = BTreeMap<String, (Vec<Value>, usize)>;
+
+/// How orphaned references are fixed, taken from the `onDelete` rule of
+/// their relation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Fix {
+    Delete,
+    Nullify,
+}
+
+impl Fix {
+
+    fn of(relation: &Relation) -> Option<Self> {
+        match relation.delete {
+            Delete::Cascade => Some(Fix::Delete),
+            Delete::Nullify if relation.is_optional() => Some(Fix::Nullify),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Fix::Delete => "delete the records (onDelete: .cascade)",
+            Fix::Nullify => "set the references to null (onDelete: .nullify)",
+        }
+    }
+}
+
+enum Issue {
+    /// Records whose foreign keys reference missing records.
+    Orphans {
+        model: &'static Model,
+        relation: &'static Relation,
+        keys: Vec<(Vec<Value>, usize)>,
+        fix: Option<Fix>,
+    },
+    /// Records sharing the values of a unique index, written before the
+    /// index was created or while it wasn't enforced.
+    Duplicates {
+        model: &'static Model,
+        fields: Vec<String>,
+        keys: Vec<(Vec<Value>, usize)>,
+    },
+}
+
+impl Issue {
+
+    fn records(&self) -> usize {
+        match self {
+            Issue::Orphans { keys, .. } | Issue::Duplicates { keys, .. } => keys.iter().map(|(_, count)| count).sum(),
+        }
+    }
+
+    fn print(&self) -> Result<()> {
+        match self {
+            Issue::Orphans { model, relation, keys, fix } => {
+                let fields: Vec<&str> = relation.iter().map(|(field, _)| field).collect();
+                println!("{} {}.{}: {} records reference {} missing `{}` records by {}", "Orphaned".red().bold(), model.path().join("."), relation.name(), self.records(), keys.len(), relation.model_path().join("."), fields.join(", "));
+                print_samples(keys)?;
+                match fix {
+                    Some(fix) => println!("    fix: {}", fix.describe()),
+                    None => println!("    fix: none, the relation isn't .cascade or optional .nullify on delete, fix the records by hand"),
+                }
+            }
+            Issue::Duplicates { model, fields, keys } => {
+                println!("{} {}: {} records share {} values of @@unique([{}])", "Duplicated".red().bold(), model.path().join("."), self.records(), keys.len(), fields.join(", "));
+                print_samples(keys)?;
+                println!("    fix: none, merge or change the records by hand");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_samples(keys: &Vec<(Vec<Value>, usize)>) -> Result<()> {
+    for (values, count) in keys.iter().take(SAMPLE_SIZE) {
+        let values = values.iter().map(JsonValue::try_from).collect::<Result<Vec<_>>>()?;
+        println!("    {} ({} records)", JsonValue::Array(values), count);
+    }
+    if keys.len() > SAMPLE_SIZE {
+        println!("    and {} more", keys.len() - SAMPLE_SIZE);
+    }
+    Ok(())
+}
+
+/// `teo db check`. Scan the records of every model for foreign keys
+/// referencing missing records, join records of many-to-many relations
+/// included, and for records sharing the values of a unique index, e.g.
+/// written before Teo managed the database. Prints a report of the issues
+/// and how each would be fixed, and fails if any is left. With `fix`,
+/// orphaned references are fixed by the `onDelete` rules of their
+/// relations: `.cascade` deletes the records and `.nullify` sets optional
+/// references to null. Other orphans and duplicates are left to be fixed by
+/// hand.
+pub(crate) async fn check_integrity(fix: bool) -> Result<()> {
+    let mut models = vec![];
+    collect_models(Ctx::main_namespace(), &mut models);
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let mut issues = vec![];
+    for model in models.into_iter().filter(|m| model_view(m).is_none()) {
+        issues.extend(check_model(model, &ctx).await?);
+    }
+    let mut left = 0;
+    for issue in &issues {
+        issue.print()?;
+        match issue {
+            Issue::Orphans { model, relation, keys, fix: Some(strategy) } if fix => {
+                let fixed = fix_orphans(*model, *relation, keys, *strategy, &ctx).await?;
+                println!("    {} {} records", "Fixed".green().bold(), fixed);
+            }
+            _ => left += 1,
+        }
+    }
+    if issues.is_empty() {
+        info_message("no orphaned references or duplicate unique values are found");
+    }
+    if left > 0 {
+        Err(Error::new(format!("{} integrity issue{} found{}", left, if left == 1 { " is" } else { "s are" }, if fix { "" } else { ", fixable ones are fixed with `--fix`" })))?
+    }
+    Ok(())
+}
+
+async fn check_model(model: &'static Model, ctx: &transaction::Ctx) -> Result<Vec<Issue>> {
+    let relations: Vec<&'static Relation> = model.relations().into_iter().filter(|r| r.has_foreign_key).collect();
+    let uniques: Vec<Vec<String>> = model.indexes.values().filter(|index| index.r#type() == IndexType::Unique)
+        .map(|index| index.items.iter().map(|item| item.field.clone()).collect()).collect();
+    if relations.is_empty() && uniques.is_empty() {
+        return Ok(vec![]);
+    }
+    let references: Vec<Vec<String>> = relations.iter().map(|r| r.iter().map(|(field, _)| field.to_owned()).collect()).collect();
+    let mut reference_tallies = vec![Tally::new(); relations.len()];
+    let mut unique_tallies = vec![Tally::new(); uniques.len()];
+    let mut after = None;
+    loop {
+        let page = export_page(model, &json!({}), after.as_deref(), DEFAULT_PAGE_SIZE, Ctx::main_namespace(), ctx).await?;
+        for object in &page.objects {
+            for (fields, tally) in references.iter().zip(reference_tallies.iter_mut()).chain(uniques.iter().zip(unique_tallies.iter_mut())) {
+                count(object, fields, tally)?;
+            }
+        }
+        match page.cursor {
+            Some(cursor) => after = Some(cursor),
+            None => break,
+        }
+    }
+    let mut issues = vec![];
+    for (relation, tally) in relations.into_iter().zip(reference_tallies) {
+        let keys = missing(relation, &tally, ctx).await?;
+        if !keys.is_empty() {
+            issues.push(Issue::Orphans { model, relation, keys, fix: Fix::of(relation) });
+        }
+    }
+    for (fields, tally) in uniques.into_iter().zip(unique_tallies) {
+        let keys: Vec<(Vec<Value>, usize)> = tally.into_values().filter(|(_, count)| *count > 1).collect();
+        if !keys.is_empty() {
+            issues.push(Issue::Duplicates { model, fields, keys });
+        }
+    }
+    Ok(issues)
+}
+
+/// Count the values of `fields` of a record. Records with a null among them
+/// reference nothing and never conflict.
+fn count(object: &Object, fields: &Vec<String>, tally: &mut Tally) -> Result<()> {
+    let values = fields.iter().map(|field| object.get_value(field)).collect::<Result<Vec<Value>>>()?;
+    if values.iter().any(Value::is_null) {
+        return Ok(());
+    }
+    let key = key(&values)?;
+    tally.entry(key).or_insert((values, 0)).1 += 1;
+    Ok(())
+}
+
+/// The referenced keys of a relation which no record holds.
+async fn missing(relation: &Relation, tally: &Tally, ctx: &transaction::Ctx) -> Result<Vec<(Vec<Value>, usize)>> {
+    let referenced_model = Ctx::main_namespace().model_at_path(&relation.model_path()).ok_or_else(|| Error::not_found())?;
+    let references: Vec<&str> = relation.iter().map(|(_, reference)| reference).collect();
+    let entries: Vec<(&String, &(Vec<Value>, usize))> = tally.iter().collect();
+    let mut missing = vec![];
+    for batch in entries.chunks(BATCH_SIZE) {
+        let finders: Vec<Value> = batch.iter().map(|(_, (values, _))| finder(&references, values)).collect();
+        let found: Vec<Object> = ctx.find_many(referenced_model, &teon!({ "where": { "OR": Value::Array(finders) } }), None, path![]).await?;
+        let mut existing = HashSet::new();
+        for object in found {
+            existing.insert(key(&references.iter().map(|reference| object.get_value(reference)).collect::<Result<Vec<Value>>>()?)?);
+        }
+        for (key, (values, count)) in batch {
+            if !existing.contains(*key) {
+                missing.push((values.clone(), *count));
+            }
+        }
+    }
+    Ok(missing)
+}
+
+/// Delete or unlink the records of a model referencing the missing keys.
+/// Returns the number of records fixed.
+async fn fix_orphans(model: &'static Model, relation: &'static Relation, keys: &Vec<(Vec<Value>, usize)>, fix: Fix, ctx: &transaction::Ctx) -> Result<usize> {
+    let fields: Vec<&'static str> = relation.iter().map(|(field, _)| field).collect();
+    let batches: Vec<Vec<Vec<Value>>> = keys.chunks(BATCH_SIZE).map(|batch| batch.iter().map(|(values, _)| values.clone()).collect()).collect();
+    ctx.run_transaction(move |ctx: transaction::Ctx| {
+        let (fields, batches) = (fields.clone(), batches.clone());
+        async move {
+            let mut fixed = 0;
+            for batch in batches {
+                let finders: Vec<Value> = batch.iter().map(|values| finder(&fields, values)).collect();
+                let records: Vec<Object> = ctx.find_many(model, &teon!({ "where": { "OR": Value::Array(finders) } }), None, path![]).await?;
+                for record in records {
+                    match fix {
+                        Fix::Delete => record.delete().await?,
+                        Fix::Nullify => {
+                            for field in &fields {
+                                record.set(field, Value::Null)?;
+                            }
+                            record.save().await?;
+                        }
+                    }
+                    fixed += 1;
+                }
+            }
+            Ok(fixed)
+        }
+    }).await
+}
+
+fn finder(fields: &Vec<&str>, values: &Vec<Value>) -> Value {
+    let mut finder = teon!({});
+    for (field, value) in fields.iter().zip(values) {
+        finder.as_dictionary_mut().unwrap().insert((*field).to_owned(), value.clone());
+    }
+    finder
+}
+
+/// The values of a key as JSON, so that keys read from different models
+/// compare equal.
+fn key(values: &Vec<Value>) -> Result<String> {
+    Ok(JsonValue::Array(values.iter().map(JsonValue::try_from).collect::<Result<Vec<_>>>()?).to_string())
+}
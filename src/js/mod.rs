@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::Path;
+use boa_engine::{Context, Source};
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+
+/// A JavaScript file whose top level functions can be called from pipelines.
+///
+/// Functions receive the pipeline value as a JSON compatible argument and
+/// return the new value. Each call runs in a fresh context, so scripts cannot
+/// keep state between calls. TypeScript sources must be compiled to
+/// JavaScript first.
+#[derive(Debug, Clone)]
+pub struct JsScript {
+    source: String,
+    loop_iteration_limit: u64,
+}
+
+impl JsScript {
+
+    /// Load the script at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.extension().map_or(false, |e| e == "ts" || e == "mts" || e == "cts") {
+            Err(Error::new(format!("cannot load \"{}\", compile TypeScript sources to JavaScript first", path.display())))?
+        }
+        let source = fs::read_to_string(path).map_err(|e| Error::new(format!("cannot load \"{}\": {}", path.display(), e)))?;
+        Ok(Self { source, loop_iteration_limit: 1_000_000 })
+    }
+
+    /// Limit the loop iterations of a call to guard against infinite loops.
+    pub fn with_loop_iteration_limit(mut self, limit: u64) -> Self {
+        self.loop_iteration_limit = limit;
+        self
+    }
+
+    /// Call a top level function with a JSON input.
+    pub fn call(&self, function: &str, input: &JsonValue) -> Result<JsonValue> {
+        let mut context = Context::default();
+        context.runtime_limits_mut().set_loop_iteration_limit(self.loop_iteration_limit);
+        let input_literal = serde_json::to_string(&input.to_string()).unwrap();
+        let script = format!("{}\n;JSON.stringify({}(JSON.parse({})))", self.source, function, input_literal);
+        let result = context.eval(Source::from_bytes(&script)).map_err(|e| Error::new(format!("javascript error: {}", e)))?;
+        let output = match result.as_string() {
+            Some(output) => output.to_std_string_escaped(),
+            None => return Ok(JsonValue::Null),
+        };
+        serde_json::from_str(&output).map_err(|e| Error::new(format!("javascript function returns invalid json: {}", e)))
+    }
+}
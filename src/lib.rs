@@ -3,15 +3,51 @@ pub mod app;
 pub mod server;
 pub mod migrate;
 pub mod purge;
+mod advise;
+mod archive;
+mod console;
+mod generate;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod integrity;
+mod fmt;
+mod lsp;
+mod lint;
+pub mod events;
+mod search;
+mod stdlib;
 pub mod seeder;
+mod watch;
+pub mod test;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "js")]
+pub mod js;
+#[cfg(feature = "arrow")]
+mod export;
 mod message;
+mod utils;
 
 pub mod prelude {
     pub use crate::app::App;
     pub use crate::app;
+    pub use crate::app::naming::NamingConvention;
+    pub use crate::app::database::connector::ConnectorBuilder;
+    pub use crate::app::scalar::ScalarCodec;
+    pub use crate::events::EventSink;
+    pub use crate::events::consumer::EventSource;
+    pub use teo_runtime::connection::connection::Connection;
+    pub use teo_runtime::connection::transaction::Transaction;
     pub use crate::cli::entrance::Entrance;
     pub use crate::cli::runtime_version::RuntimeVersion;
     pub use crate::server::static_files::serve_static_files;
+    pub use crate::server::signed_url::sign_url;
+    pub use crate::server::signature::NonceStore;
+    pub use crate::server::credentials::{CredentialVerifier, VerifiedCredential};
+    pub use crate::server::lockout::{CaptchaVerifier, SignInLockout};
+    pub use crate::server::magic_link::{MagicLink, MagicLinks, MagicLinkSender};
+    pub use crate::server::error::{UserError, FieldError, ErrorFormat};
+    pub use crate::server::envelope::Envelope;
     pub use teo_runtime::namespace::Namespace;
     pub extern crate teo_result;
     pub use teo_result::{Error, Result, ResultExt};
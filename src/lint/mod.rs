@@ -0,0 +1,172 @@
+use std::env::current_dir;
+use colored::Colorize;
+use teo_parser::ast::argument_list::ArgumentList;
+use teo_parser::ast::argument_list_declaration::ArgumentListDeclaration;
+use teo_parser::ast::expression::ExpressionKind;
+use teo_parser::ast::node::Node;
+use teo_parser::ast::schema::Schema;
+use teo_parser::ast::span::Span;
+use teo_parser::ast::unit::Unit;
+use teo_parser::diagnostics::printer::print_diagnostics;
+use teo_parser::r#type::Type;
+use teo_parser::traits::named_identifiable::NamedIdentifiable;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_parser::traits::resolved::Resolve;
+use teo_parser::parse as schema_parse;
+use teo_result::{Error, Result};
+use teo_runtime::utils::find_main_schema_file;
+use crate::cli::command::CLI;
+
+/// A problem found by the argument checks. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LintError {
+    pub(crate) path: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) message: String,
+}
+
+/// Lint the schema and print the problems found.
+pub(crate) fn lint(cli: &CLI) -> Result<()> {
+    let current_dir = current_dir().map_err(|e| Error::new(format!("{}", e)))?;
+    let main_schema_file = find_main_schema_file(cli.main(), &current_dir)?;
+    let (schema, diagnostics) = schema_parse(main_schema_file.as_path().to_str().unwrap(), None, None);
+    print_diagnostics(&diagnostics, true);
+    if diagnostics.has_errors() {
+        Err(Error::new("the schema has errors"))?
+    }
+    check_schema_arguments(&schema)
+}
+
+/// Check the arguments of the decorators and pipeline items of a schema,
+/// printing the problems found. The app checks them when it loads the
+/// schema, so wrong arguments fail before the server starts.
+pub(crate) fn check_schema_arguments(schema: &Schema) -> Result<()> {
+    let errors = check_arguments(schema);
+    for error in &errors {
+        println!("{} {}:{}:{} {}", "Error".red().bold(), error.path, error.line, error.column, error.message);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(format!("found {} problem(s) in schema files", errors.len())))
+    }
+}
+
+/// Check the arguments of decorator and pipeline item calls against the
+/// declarations of the decorators and pipeline items, the builtin ones as
+/// well as the ones declared in the schema. Arguments whose types aren't
+/// known without running the schema, like references, aren't checked.
+pub(crate) fn check_arguments(schema: &Schema) -> Vec<LintError> {
+    let mut errors = vec![];
+    for source in schema.sources() {
+        if source.builtin {
+            continue
+        }
+        for node in source.children.values() {
+            check_node(schema, &source.file_path, node, &mut errors);
+        }
+    }
+    errors
+}
+
+fn check_node(schema: &Schema, path: &str, node: &Node, errors: &mut Vec<LintError>) {
+    match node {
+        Node::Decorator(decorator) => if decorator.is_resolved() {
+            if let Some(declaration) = schema.find_top_by_path(decorator.resolved().reference.path()).and_then(Node::as_decorator_declaration) {
+                let name = format!("@{}", decorator.identifier_path().names().join("."));
+                let declarations: Vec<Option<&ArgumentListDeclaration>> = if declaration.has_variants() {
+                    declaration.variants().map(|variant| variant.argument_list_declaration()).collect()
+                } else {
+                    vec![declaration.argument_list_declaration()]
+                };
+                check_call(path, &name, decorator.argument_list(), &declarations, errors);
+            }
+        },
+        Node::Unit(unit) => check_unit(schema, path, unit, errors),
+        _ => (),
+    }
+    if let Some(children) = node.children() {
+        for child in children.values() {
+            check_node(schema, path, child, errors);
+        }
+    }
+}
+
+/// Check the pipeline item calls of a unit, e.g. `$minLength(2).trim`.
+fn check_unit(schema: &Schema, path: &str, unit: &Unit, errors: &mut Vec<LintError>) {
+    let expressions: Vec<_> = unit.expressions().collect();
+    for (index, expression) in expressions.iter().enumerate() {
+        let ExpressionKind::Identifier(identifier) = &expression.kind else { continue };
+        let Some(declaration) = schema.pipeline_item_declarations().into_iter().find(|d| d.identifier().name() == identifier.name()) else { continue };
+        let argument_list = expressions.get(index + 1).and_then(|next| match &next.kind {
+            ExpressionKind::ArgumentList(argument_list) => Some(argument_list),
+            _ => None,
+        });
+        let declarations: Vec<Option<&ArgumentListDeclaration>> = if declaration.has_variants() {
+            declaration.variants().map(|variant| variant.argument_list_declaration()).collect()
+        } else {
+            vec![declaration.argument_list_declaration()]
+        };
+        check_call(path, &format!("${}", identifier.name()), argument_list, &declarations, errors);
+    }
+}
+
+/// Check a call against the argument lists it may be declared with, it
+/// passes if it matches any of them.
+fn check_call(path: &str, name: &str, argument_list: Option<&ArgumentList>, declarations: &Vec<Option<&ArgumentListDeclaration>>, errors: &mut Vec<LintError>) {
+    let mut first_problem = None;
+    for declaration in declarations {
+        match check_call_with(name, argument_list, *declaration) {
+            None => return,
+            Some(problem) => if first_problem.is_none() {
+                first_problem = Some(problem);
+            },
+        }
+    }
+    if let Some((span, message)) = first_problem {
+        errors.push(LintError { path: path.to_owned(), line: span.start_position.0, column: span.start_position.1, message });
+    }
+}
+
+fn check_call_with(name: &str, argument_list: Option<&ArgumentList>, declaration: Option<&ArgumentListDeclaration>) -> Option<(Span, String)> {
+    let Some(argument_list) = argument_list else { return None };
+    let parameters: Vec<_> = declaration.map(|d| d.argument_declarations().collect()).unwrap_or_default();
+    let arguments: Vec<_> = argument_list.arguments().collect();
+    if arguments.len() > parameters.len() {
+        return Some((argument_list.span(), format!("`{}` expects at most {} argument(s), found {}", name, parameters.len(), arguments.len())));
+    }
+    for (index, argument) in arguments.iter().enumerate() {
+        let parameter = match argument.name() {
+            Some(label) => match parameters.iter().find(|p| p.name().name() == label.name()) {
+                Some(parameter) => *parameter,
+                None => return Some((argument.span(), format!("`{}` has no argument named `{}`", name, label.name()))),
+            },
+            None => match parameters.get(index) {
+                Some(parameter) => *parameter,
+                None => continue,
+            },
+        };
+        let expected = parameter.type_expr().resolved();
+        let actual = argument.value().resolved().r#type();
+        if !checkable(expected) || !checkable(actual) {
+            continue
+        }
+        if !expected.test(actual) {
+            return Some((argument.span(), format!("argument `{}` of `{}` expects {}, found {}", parameter.name().name(), name, expected, actual)));
+        }
+    }
+    let positional = arguments.iter().filter(|argument| argument.name().is_none()).count();
+    for (index, parameter) in parameters.iter().enumerate() {
+        let given = index < positional || arguments.iter().any(|argument| argument.name().map_or(false, |label| label.name() == parameter.name().name()));
+        if !given && !parameter.type_expr().resolved().is_optional() {
+            return Some((argument_list.span(), format!("`{}` is missing argument `{}`", name, parameter.name().name())));
+        }
+    }
+    None
+}
+
+/// Whether a type is known without running the schema.
+fn checkable(r#type: &Type) -> bool {
+    !r#type.is_any() && !r#type.is_undetermined() && !r#type.contains_generics() && !r#type.contains_keywords()
+}
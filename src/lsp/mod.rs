@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value as JsonValue};
+use teo_parser::{auto_complete_items, jump_to_definition, parse as schema_parse};
+use teo_parser::ast::node::Node;
+use teo_parser::ast::schema::Schema;
+use teo_parser::ast::span::Span;
+use teo_parser::diagnostics::diagnostics::DiagnosticsLog;
+use teo_parser::traits::documentable::Documentable;
+use teo_parser::traits::node_trait::NodeTrait;
+use teo_result::{Error, Result};
+use teo_runtime::utils::find_main_schema_file;
+use url::Url;
+use crate::utils::delimiters::{check_delimiters, recover_delimiters};
+
+/// The open documents of the language server. Completion, definitions and
+/// hovers come from the parser and resolver, on the schema the document
+/// belongs to.
+pub(crate) struct Server {
+    root: Option<PathBuf>,
+    documents: BTreeMap<String, String>,
+}
+
+/// Run the language server over stdio until the client sends `exit`.
+pub(crate) fn lsp() -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut reader = BufReader::new(stdin.lock());
+    let mut server = Server::new(None);
+    while let Some(message) = read_message(&mut reader)? {
+        let method = message.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(JsonValue::Null);
+        match method {
+            "initialize" => {
+                server.root = params.get("rootUri").and_then(|u| u.as_str()).and_then(uri_to_path);
+                respond(id, json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "completionProvider": { "triggerCharacters": ["@", "$", "."] },
+                        "definitionProvider": true,
+                        "hoverProvider": true,
+                    },
+                    "serverInfo": { "name": "teo", "version": env!("CARGO_PKG_VERSION") },
+                }))?;
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_owned();
+                server.open(&uri, params["textDocument"]["text"].as_str().unwrap_or(""));
+                server.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_owned();
+                if let Some(change) = params["contentChanges"].as_array().and_then(|c| c.last()) {
+                    server.documents.insert(uri.clone(), change["text"].as_str().unwrap_or("").to_owned());
+                }
+                server.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_owned();
+                server.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_owned();
+                server.documents.remove(&uri);
+            }
+            "textDocument/completion" => respond(id, server.completion(&params))?,
+            "textDocument/definition" => respond(id, server.definition(&params))?,
+            "textDocument/hover" => respond(id, server.hover(&params))?,
+            "shutdown" => respond(id, JsonValue::Null)?,
+            "exit" => return Ok(()),
+            _ => if id.is_some() {
+                write_message(&json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": format!("method not found: {}", method) },
+                }))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Server {
+
+    pub(crate) fn new(root: Option<PathBuf>) -> Self {
+        Self { root, documents: BTreeMap::new() }
+    }
+
+    pub(crate) fn open(&mut self, uri: &str, text: &str) {
+        self.documents.insert(uri.to_owned(), text.to_owned());
+    }
+
+    /// Publish the diagnostics of a document. Unbalanced delimiters are
+    /// reported with the closers expected, and the document is parsed with
+    /// them recovered, so the errors cascading from them are left out.
+    fn publish_diagnostics(&self, uri: &str) -> Result<()> {
+        let Some(path) = uri_to_path(uri) else { return Ok(()) };
+        let mut unsaved: HashMap<String, String> = self.documents.iter().filter_map(|(uri, text)| {
+            uri_to_path(uri).map(|p| (p.to_string_lossy().to_string(), text.clone()))
+        }).collect();
+        let mut items = vec![];
+        if let Some(text) = self.documents.get(uri) {
+            let delimiter_errors = check_delimiters(text);
+            for error in &delimiter_errors {
+                let expected: Vec<String> = error.expected.iter().map(|c| format!("`{}`", c)).collect();
+                items.push(json!({
+                    "range": {
+                        "start": { "line": error.line - 1, "character": error.column - 1 },
+                        "end": { "line": error.line - 1, "character": error.column },
+                    },
+                    "severity": 1,
+                    "source": "teo",
+                    "message": if expected.is_empty() { error.message.clone() } else { format!("{}, expected {}", error.message, expected.join(" or ")) },
+                }));
+            }
+            if !delimiter_errors.is_empty() {
+                unsaved.insert(path.to_string_lossy().to_string(), recover_delimiters(text, &delimiter_errors));
+            }
+        }
+        let (_, diagnostics) = schema_parse(path.to_str().unwrap(), None, Some(unsaved));
+        for error in diagnostics.errors() {
+            if Path::new(error.source_path()) == path.as_path() {
+                items.push(diagnostic_item(error, 1));
+            }
+        }
+        for warning in diagnostics.warnings() {
+            if Path::new(warning.source_path()) == path.as_path() {
+                items.push(diagnostic_item(warning, 2));
+            }
+        }
+        write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": items },
+        }))
+    }
+
+    /// The schema a document belongs to: the schema of the main schema file
+    /// if it imports the document, or else the document's own. Open documents
+    /// take precedence over files on disk.
+    fn schema(&self, path: &Path) -> Schema {
+        let unsaved: HashMap<String, String> = self.documents.iter().filter_map(|(uri, text)| {
+            uri_to_path(uri).map(|p| (p.to_string_lossy().to_string(), text.clone()))
+        }).collect();
+        if let Some(main) = self.root.as_ref().and_then(|root| find_main_schema_file(None, root).ok()) {
+            let (schema, _) = schema_parse(main.to_str().unwrap(), None, Some(unsaved.clone()));
+            if schema.sources().iter().any(|source| Path::new(&source.file_path) == path) {
+                return schema;
+            }
+        }
+        schema_parse(path.to_str().unwrap(), None, Some(unsaved)).0
+    }
+
+    /// The completion items of the parser at the position, which depend on
+    /// what is being written there, e.g. decorators after `@` and types after
+    /// a field name.
+    pub(crate) fn completion(&self, params: &JsonValue) -> JsonValue {
+        let Some((path, line_col)) = position(params) else { return JsonValue::Array(vec![]) };
+        let schema = self.schema(&path);
+        JsonValue::Array(auto_complete_items(&schema, path.to_str().unwrap(), line_col).into_iter().map(|item| {
+            let mut result = json!({ "label": item.label });
+            if let Some(detail) = item.detail {
+                result["detail"] = json!(detail);
+            }
+            if let Some(documentation) = item.documentation {
+                result["documentation"] = json!({ "kind": "markdown", "value": documentation });
+            }
+            result
+        }).collect())
+    }
+
+    /// The declaration of the name at the position, resolved through the
+    /// imports of the schema.
+    pub(crate) fn definition(&self, params: &JsonValue) -> JsonValue {
+        let Some((path, line_col)) = position(params) else { return JsonValue::Null };
+        let schema = self.schema(&path);
+        JsonValue::Array(jump_to_definition(&schema, path.to_str().unwrap(), line_col).into_iter().filter_map(|definition| {
+            let uri = Url::from_file_path(&definition.path).ok()?;
+            Some(json!({ "uri": uri.to_string(), "range": range(&definition.identifier_span) }))
+        }).collect())
+    }
+
+    /// The documentation of the declaration of the name at the position.
+    pub(crate) fn hover(&self, params: &JsonValue) -> JsonValue {
+        let Some((path, line_col)) = position(params) else { return JsonValue::Null };
+        let schema = self.schema(&path);
+        let Some(definition) = jump_to_definition(&schema, path.to_str().unwrap(), line_col).into_iter().next() else { return JsonValue::Null };
+        let Some(source) = schema.sources().into_iter().find(|source| source.file_path == definition.path) else { return JsonValue::Null };
+        let Some(documentable) = find_node(source.children.values(), &definition.target_span).and_then(documentable) else { return JsonValue::Null };
+        let mut contents = format!("```teo\n{} {}\n```", documentable.kind(), documentable.title());
+        if let Some(comment) = documentable.comment() {
+            for paragraph in [&comment.name, &comment.desc].into_iter().flatten() {
+                contents.push_str("\n\n");
+                contents.push_str(paragraph);
+            }
+        }
+        json!({ "contents": { "kind": "markdown", "value": contents }, "range": range(&definition.selection_span) })
+    }
+}
+
+/// The document path and the 1-based line and column of a position.
+fn position(params: &JsonValue) -> Option<(PathBuf, (usize, usize))> {
+    let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+    let line = params["position"]["line"].as_u64()? as usize;
+    let character = params["position"]["character"].as_u64()? as usize;
+    Some((path, (line + 1, character + 1)))
+}
+
+fn range(span: &Span) -> JsonValue {
+    json!({
+        "start": { "line": span.start_position.0.saturating_sub(1), "character": span.start_position.1.saturating_sub(1) },
+        "end": { "line": span.end_position.0.saturating_sub(1), "character": span.end_position.1.saturating_sub(1) },
+    })
+}
+
+/// The node spanning `span`.
+fn find_node<'a>(nodes: impl Iterator<Item = &'a Node>, span: &Span) -> Option<&'a Node> {
+    for node in nodes {
+        let node_span = node.span();
+        if node_span.start == span.start && node_span.end == span.end {
+            return Some(node);
+        }
+        if node_span.start <= span.start && span.end <= node_span.end {
+            if let Some(found) = node.children().and_then(|children| find_node(children.values(), span)) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+fn documentable(node: &Node) -> Option<&dyn Documentable> {
+    let documentable: &dyn Documentable = match node {
+        Node::Model(model) => model,
+        Node::Field(field) => field,
+        Node::Enum(r#enum) => r#enum,
+        Node::EnumMember(member) => member,
+        Node::InterfaceDeclaration(interface) => interface,
+        Node::ConfigDeclaration(config) => config,
+        Node::DecoratorDeclaration(decorator) => decorator,
+        Node::PipelineItemDeclaration(pipeline_item) => pipeline_item,
+        Node::HandlerDeclaration(handler) => handler,
+        _ => return None,
+    };
+    Some(documentable)
+}
+
+fn diagnostic_item(log: &impl DiagnosticsLog, severity: u8) -> JsonValue {
+    let span = log.span();
+    json!({
+        "range": {
+            "start": { "line": span.start_position.0.saturating_sub(1), "character": span.start_position.1.saturating_sub(1) },
+            "end": { "line": span.end_position.0.saturating_sub(1), "character": span.end_position.1.saturating_sub(1) },
+        },
+        "severity": severity,
+        "source": "teo",
+        "message": log.message(),
+    })
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+fn respond(id: Option<JsonValue>, result: JsonValue) -> Result<()> {
+    write_message(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn read_message(reader: &mut impl BufRead) -> Result<Option<JsonValue>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).map_err(|e| Error::new(format!("{}", e)))? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse().ok();
+        }
+    }
+    let Some(content_length) = content_length else {
+        return Err(Error::new("missing Content-Length header"));
+    };
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content).map_err(|e| Error::new(format!("{}", e)))?;
+    serde_json::from_slice(&content).map(Some).map_err(|e| Error::new(format!("{}", e)))
+}
+
+fn write_message(message: &JsonValue) -> Result<()> {
+    let content = message.to_string();
+    let mut stdout = std::io::stdout().lock();
+    write!(stdout, "Content-Length: {}\r\n\r\n{}", content.len(), content).map_err(|e| Error::new(format!("{}", e)))?;
+    stdout.flush().map_err(|e| Error::new(format!("{}", e)))
+}
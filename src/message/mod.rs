@@ -20,12 +20,14 @@ pub fn request_message(
     handler_group_path: &Vec<String>,
     action: &str,
     code: u16,
+    client_ip: &str,
+    request_id: &str,
 ) {
     let handler_str: String = handler_group_path.join(".") + ".";
     let code_string = format_code_into_string(code);
     let ms = time_elapsed.as_millis();
     let ms_str = format!("{ms}ms").normal().clear();
-    println!("{} {} {} => {}{} {} {}", timestamp(), method.bright_blue().bold(), path.bright_yellow(), handler_str.magenta(), action.purple(), code_string, ms_str)
+    println!("{} {} {} => {}{} {} {} {} {}", timestamp(), method.bright_blue().bold(), path.bright_yellow(), handler_str.magenta(), action.purple(), code_string, ms_str, client_ip.dimmed(), request_id.dimmed())
 }
 
 pub fn unhandled_request_message(
@@ -33,11 +35,21 @@ pub fn unhandled_request_message(
     method: &str,
     path: &str,
     code: u16,
+    client_ip: &str,
+    request_id: &str,
 ) {
     let code_string = format_code_into_string(code);
     let ms = time_elapsed.as_millis();
     let ms_str = format!("{ms}ms").normal().clear();
-    println!("{} {} {} {} {}", timestamp(), method.bright_blue().bold(), path.bright_yellow(), code_string, ms_str)
+    println!("{} {} {} {} {} {} {}", timestamp(), method.bright_blue().bold(), path.bright_yellow(), code_string, ms_str, client_ip.dimmed(), request_id.dimmed())
+}
+
+pub fn impersonation_message(principal: &str, identity: &str, request_id: &str) {
+    println!("{} {} {} {} {}", timestamp(), principal.bright_yellow(), "acts as".purple(), identity.bright_yellow(), request_id.dimmed())
+}
+
+pub fn merge_message(source: &str, target: &str, relinked: usize, request_id: &str) {
+    println!("{} {} {} {} {} {}", timestamp(), source.bright_yellow(), "merged into".purple(), target.bright_yellow(), format!("{} relinked", relinked).normal().clear(), request_id.dimmed())
 }
 
 fn format_code_into_string(code: u16) -> ColoredString {
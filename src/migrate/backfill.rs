@@ -0,0 +1,91 @@
+use std::future::Future;
+use std::sync::Arc;
+use futures_util::future::BoxFuture;
+use key_path::path;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::Object;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+
+/// The number of records a backfill loads and saves at a time by default.
+pub(crate) const DEFAULT_BACKFILL_BATCH_SIZE: usize = 500;
+
+/// Computes the value of a backfilled field from a record.
+pub trait BackfillCallback: Send + Sync {
+    fn call(&self, object: Object) -> BoxFuture<'static, Result<Value>>;
+}
+
+impl<F, Fut> BackfillCallback for F where
+    F: Fn(Object) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Value>> + Send + 'static {
+    fn call(&self, object: Object) -> BoxFuture<'static, Result<Value>> {
+        Box::pin(self(object))
+    }
+}
+
+/// Populates a field of existing records after migration.
+#[derive(Clone)]
+pub(crate) struct Backfill {
+    pub(crate) model: Vec<String>,
+    pub(crate) field: String,
+    pub(crate) batch_size: usize,
+    pub(crate) callback: Arc<dyn BackfillCallback>,
+}
+
+impl std::fmt::Debug for Backfill {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Backfill").field("model", &self.model).field("field", &self.field).field("batch_size", &self.batch_size).finish()
+    }
+}
+
+/// Run the registered backfills. Only records whose field is null are
+/// touched, so a backfill does nothing once it has completed, and an
+/// interrupted backfill continues where it stopped.
+pub(crate) async fn run_backfills(silent: bool) -> Result<()> {
+    for backfill in Ctx::backfills() {
+        run_backfill(backfill, silent).await?;
+    }
+    Ok(())
+}
+
+async fn run_backfill(backfill: &Backfill, silent: bool) -> Result<()> {
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let model_path: Vec<&str> = backfill.model.iter().map(|s| s.as_str()).collect();
+    let model = ctx.namespace().model_at_path(&model_path).ok_or_else(|| Error::new(format!("backfill: model `{}` is not found", model_path.join("."))))?;
+    let name = format!("{}.{}", model_path.join("."), backfill.field);
+    // records for which the callback returns null are skipped in later batches
+    let mut skip = 0;
+    let mut done = 0;
+    loop {
+        let objects: Vec<Object> = ctx.find_many(model, &teon!({
+            "where": { backfill.field.as_str(): Value::Null },
+            "skip": skip as i64,
+            "take": backfill.batch_size as i64,
+        }), None, path![]).await?;
+        if objects.is_empty() {
+            break;
+        }
+        let count = objects.len();
+        for object in objects {
+            let value = backfill.callback.call(object.clone()).await?;
+            if value.is_null() {
+                skip += 1;
+                continue
+            }
+            object.set(backfill.field.as_str(), value)?;
+            object.save().await.map_err(|e| Error::new(format!("backfill {}: {}", name, e.message)))?;
+            done += 1;
+        }
+        if !silent {
+            info_message(format!("backfill {}: {} records updated", name, done));
+        }
+        if count < backfill.batch_size {
+            break;
+        }
+    }
+    Ok(())
+}
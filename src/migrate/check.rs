@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use chrono::Utc;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::message::info_message;
+use crate::stdlib::decorators::constraints::{field_constraints, Constraint};
+use crate::stdlib::decorators::generated::field_generated;
+use crate::utils::sql::quote;
+
+/// The table the schema of the last migration is recorded in, one row for
+/// each connection.
+pub(crate) const SCHEMA_SNAPSHOTS_TABLE: &str = "_teo_schema_snapshots";
+
+/// A difference between the migrated schema and the schema in code.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Change {
+    pub(crate) description: String,
+    pub(crate) breaking: bool,
+}
+
+/// Record the schema of the models of a connection after it's migrated, so
+/// that `teo migrate check` can compare against it.
+pub(crate) async fn record_schema_snapshot(transaction: Arc<dyn Transaction>, namespace_path: &str, models: &Vec<&Model>, database: &Database) -> Result<()> {
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (namespace VARCHAR(255) PRIMARY KEY, snapshot TEXT NOT NULL, migrated_at BIGINT NOT NULL)",
+        SCHEMA_SNAPSHOTS_TABLE,
+    ))).await?;
+    transaction.query_raw(&Value::String(format!(
+        "DELETE FROM {} WHERE namespace = {}",
+        SCHEMA_SNAPSHOTS_TABLE, quote(namespace_path, database)?,
+    ))).await?;
+    transaction.query_raw(&Value::String(format!(
+        "INSERT INTO {} (namespace, snapshot, migrated_at) VALUES ({}, {}, {})",
+        SCHEMA_SNAPSHOTS_TABLE, quote(namespace_path, database)?, quote(&snapshot(models).to_string(), database)?, Utc::now().timestamp_millis(),
+    ))).await?;
+    Ok(())
+}
+
+/// Compare the schema recorded by the last migration of each connection with
+/// the schema in code and print the pending changes. Additions are backward
+/// compatible, so the running version keeps working after the migration;
+/// removals, renames, type changes and new required columns are breaking.
+/// Fails if any change is breaking, so that deploys can be gated on it.
+pub(crate) async fn check_migration() -> Result<()> {
+    let ctx = Ctx::conn_ctx();
+    let mut breaking = 0;
+    for (namespace_path, connection) in ctx.connections_iter() {
+        let namespace = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()).unwrap();
+        let name = if namespace_path.is_empty() { "main".to_owned() } else { namespace_path.join(".") };
+        let Some(connector) = namespace.connector.as_ref() else { continue };
+        let database = &connector.provider;
+        if database.is_mongo() || !is_provider_connector(connector) {
+            info_message(format!("{}: schema checks are only supported by SQL databases", name));
+            continue
+        }
+        let transaction = connection.no_transaction().await?;
+        let Some(recorded) = recorded_snapshot(transaction, &namespace_path.join("."), database).await else {
+            info_message(format!("{}: no migration is recorded, run `teo migrate` first", name));
+            continue
+        };
+        let changes = diff(&recorded, &snapshot(&namespace.models_under_connector()));
+        if changes.is_empty() {
+            info_message(format!("{}: the schema is up to date", name));
+        }
+        for change in changes {
+            info_message(format!("{}: {} {}", name, if change.breaking { "breaking:  " } else { "compatible:" }, change.description));
+            if change.breaking {
+                breaking += 1;
+            }
+        }
+    }
+    if breaking > 0 {
+        Err(Error::new(format!("{} breaking schema change{} pending", breaking, if breaking == 1 { " is" } else { "s are" })))?
+    }
+    Ok(())
+}
+
+fn snapshot(models: &Vec<&Model>) -> JsonValue {
+    let mut result = Map::new();
+    for model in models {
+        let mut fields = Map::new();
+        for field in model.fields.values() {
+            fields.insert(field.name().to_owned(), field_snapshot(field));
+        }
+        result.insert(model.path().join("."), json!({ "table": model.table_name, "fields": fields }));
+    }
+    JsonValue::Object(result)
+}
+
+/// The recorded schema of a field.
+pub(super) fn field_snapshot(field: &Field) -> JsonValue {
+    json!({
+        "column": field.column_name,
+        "type": format!("{:?}", field.r#type()),
+        "optional": field.is_optional(),
+        "default": field.default.is_some(),
+        "generated": field_generated(field).map(|g| format!("{} {}", g.expression, if g.stored { "stored" } else { "virtual" })),
+        "constraints": field_constraints(field).iter().map(Constraint::describe).collect::<Vec<_>>(),
+    })
+}
+
+pub(super) async fn recorded_snapshot(transaction: Arc<dyn Transaction>, namespace_path: &str, database: &Database) -> Option<JsonValue> {
+    let rows = transaction.query_raw(&Value::String(format!(
+        "SELECT snapshot FROM {} WHERE namespace = {}",
+        SCHEMA_SNAPSHOTS_TABLE, quote(namespace_path, database).ok()?,
+    ))).await.ok()?;
+    let Value::Array(rows) = rows else { return None };
+    let snapshot = rows.into_iter().next()?.get("snapshot")?.as_str()?.to_owned();
+    serde_json::from_str(&snapshot).ok()
+}
+
+/// The changes from the `recorded` snapshot to the `current` one.
+pub(crate) fn diff(recorded: &JsonValue, current: &JsonValue) -> Vec<Change> {
+    let empty = Map::new();
+    let recorded = recorded.as_object().unwrap_or(&empty);
+    let current = current.as_object().unwrap_or(&empty);
+    let mut changes = vec![];
+    let mut change = |description: String, breaking: bool| changes.push(Change { description, breaking });
+    for (model, old) in recorded {
+        let Some(new) = current.get(model) else {
+            change(format!("model `{}` is removed", model), true);
+            continue
+        };
+        if old.get("table") != new.get("table") {
+            change(format!("table of `{}` is renamed from {} to {}", model, old["table"], new["table"]), true);
+        }
+        let old_fields = old.get("fields").and_then(|f| f.as_object()).unwrap_or(&empty);
+        let new_fields = new.get("fields").and_then(|f| f.as_object()).unwrap_or(&empty);
+        for (field, old_field) in old_fields {
+            let Some(new_field) = new_fields.get(field) else {
+                change(format!("field `{}.{}` is removed", model, field), true);
+                continue
+            };
+            if old_field.get("column") != new_field.get("column") {
+                change(format!("column of `{}.{}` is renamed from {} to {}", model, field, old_field["column"], new_field["column"]), true);
+            }
+            if old_field.get("type") != new_field.get("type") {
+                change(format!("type of `{}.{}` changes from {} to {}", model, field, old_field["type"], new_field["type"]), true);
+            }
+            let generation = |field: &JsonValue| field.get("generated").and_then(|g| g.as_str()).map(|g| g.to_owned());
+            if generation(old_field) != generation(new_field) {
+                // the column is recomputed, the values stay consistent
+                change(format!("generation of `{}.{}` changes from {} to {}", model, field, generation(old_field).unwrap_or("none".to_owned()), generation(new_field).unwrap_or("none".to_owned())), false);
+            }
+            let constraints = |field: &JsonValue| field.get("constraints").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+            for constraint in constraints(new_field).iter().filter(|c| !constraints(old_field).contains(c)) {
+                // existing rows may violate it
+                change(format!("constraint {} is added to `{}.{}`", constraint, model, field), true);
+            }
+            for constraint in constraints(old_field).iter().filter(|c| !constraints(new_field).contains(c)) {
+                change(format!("constraint {} is removed from `{}.{}`", constraint, model, field), false);
+            }
+            match (old_field.get("optional").and_then(|o| o.as_bool()), new_field.get("optional").and_then(|o| o.as_bool())) {
+                (Some(true), Some(false)) => change(format!("field `{}.{}` becomes required", model, field), true),
+                (Some(false), Some(true)) => change(format!("field `{}.{}` becomes optional", model, field), false),
+                _ => (),
+            }
+        }
+        for (field, new_field) in new_fields {
+            if old_fields.contains_key(field) {
+                continue
+            }
+            let required = new_field.get("optional").and_then(|o| o.as_bool()) == Some(false) && new_field.get("default").and_then(|d| d.as_bool()) != Some(true);
+            if required {
+                change(format!("required field `{}.{}` without a default is added", model, field), true);
+            } else {
+                change(format!("field `{}.{}` is added", model, field), false);
+            }
+        }
+    }
+    for model in current.keys().filter(|m| !recorded.contains_key(*m)) {
+        change(format!("model `{}` is added", model), false);
+    }
+    changes
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::stdlib::decorators::collation::{field_collation, CASE_INSENSITIVE_COLLATION};
+use crate::utils::sql::{identifier, quote};
+
+/// The collation of MySQL columns of `@caseInsensitive` fields, which
+/// ignores case but not accents.
+const MYSQL_CASE_INSENSITIVE_COLLATION: &str = "utf8mb4_0900_as_ci";
+
+/// A column as the database reports it.
+struct LiveColumn {
+    r#type: String,
+    collation: Option<String>,
+}
+
+/// Change the columns of fields with collations to use them. The connector
+/// creates the columns with the default collation first. Columns which
+/// already have the collation are left alone.
+pub(crate) async fn alter_collated_columns(transaction: Arc<dyn Transaction>, models: &Vec<&Model>, database: &Database) -> Result<()> {
+    for model in models {
+        for field in model.fields.values() {
+            let Some(collation) = field_collation(field) else { continue };
+            let field_path = format!("{}.{}", model.path().join("."), field.name());
+            if !database.is_pg() && !database.is_mysql() {
+                Err(Error::new(format!("`{}`: collations are only supported by PostgreSQL and MySQL", field_path)))?
+            }
+            let Some(column) = live_column(transaction.clone(), model, field, database).await? else { continue };
+            let Some(statements) = statements(model, field, &column, collation, database) else { continue };
+            for statement in statements {
+                transaction.query_raw(&Value::String(statement)).await.map_err(|e| {
+                    Error::new(format!("cannot change the collation of `{}`: {}", field_path, e.message))
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn live_column(transaction: Arc<dyn Transaction>, model: &Model, field: &Field, database: &Database) -> Result<Option<LiveColumn>> {
+    let sql = match database {
+        Database::PostgreSQL => format!(
+            "SELECT format_type(a.atttypid, a.atttypmod) AS type, co.collname AS collation FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid LEFT JOIN pg_collation co ON co.oid = a.attcollation WHERE c.relname = {} AND a.attname = {} AND pg_table_is_visible(c.oid)",
+            quote(&model.table_name, database)?, quote(&field.column_name, database)?,
+        ),
+        _ => format!(
+            "SELECT column_type AS type, collation_name AS collation FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = {} AND column_name = {}",
+            quote(&model.table_name, database)?, quote(&field.column_name, database)?,
+        ),
+    };
+    let rows = transaction.query_raw(&Value::String(sql)).await?;
+    let Some(row) = (match rows { Value::Array(rows) => rows.into_iter().next(), _ => None }) else { return Ok(None) };
+    let r#type = row.get("type").and_then(|t| t.as_str()).ok_or_else(|| Error::new(format!("cannot read the column type of `{}.{}`", model.path().join("."), field.name())))?.to_owned();
+    let collation = row.get("collation").and_then(|c| c.as_str()).map(ToOwned::to_owned);
+    Ok(Some(LiveColumn { r#type, collation }))
+}
+
+/// The statements which give a column its collation, `None` if it has it.
+fn statements(model: &Model, field: &Field, column: &LiveColumn, collation: &str, database: &Database) -> Option<Vec<String>> {
+    let table = identifier(&model.table_name, database);
+    let name = identifier(&field.column_name, database);
+    match database {
+        Database::PostgreSQL if collation == CASE_INSENSITIVE_COLLATION => (column.r#type != "citext").then(|| vec![
+            "CREATE EXTENSION IF NOT EXISTS citext".to_owned(),
+            format!("ALTER TABLE {} ALTER COLUMN {} TYPE citext", table, name),
+        ]),
+        Database::PostgreSQL => {
+            // a citext column of a field which had `@caseInsensitive` is
+            // turned back into text
+            let r#type = if column.r#type == "citext" { "text" } else { column.r#type.as_str() };
+            (column.r#type == "citext" || column.collation.as_deref() != Some(collation)).then(|| vec![
+                format!("ALTER TABLE {} ALTER COLUMN {} TYPE {} COLLATE {}", table, name, r#type, identifier(collation, database)),
+            ])
+        }
+        _ => {
+            let collation = if collation == CASE_INSENSITIVE_COLLATION { MYSQL_CASE_INSENSITIVE_COLLATION } else { collation };
+            (column.collation.as_deref() != Some(collation)).then(|| vec![
+                format!("ALTER TABLE {} MODIFY COLUMN {} {} COLLATE {} {}", table, name, column.r#type, collation, if field.is_optional() { "NULL" } else { "NOT NULL" }),
+            ])
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use ring::digest::{digest, SHA256};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::stdlib::decorators::constraints::{field_constraints, Constraint};
+use crate::utils::sql::{identifier, quote};
+
+/// The prefix of the names of the check constraints managed here.
+const CONSTRAINT_PREFIX: &str = "teo_ck_";
+
+/// The longest identifier PostgreSQL keeps.
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+/// Add the `CHECK` constraints of the constrained fields of the models and
+/// drop the ones of removed rules. A constraint is named after its table,
+/// column, rule and a hash of its expression, so unchanged constraints are
+/// left alone and changed ones are replaced. Rules the database can't check
+/// are skipped, they are still checked by the app.
+pub(crate) async fn sync_check_constraints(transaction: Arc<dyn Transaction>, models: &Vec<&Model>, database: &Database) -> Result<()> {
+    if !matches!(database, Database::PostgreSQL | Database::MySQL) {
+        return Ok(());
+    }
+    for model in models {
+        let mut declared = BTreeMap::new();
+        for field in model.fields.values() {
+            for constraint in field_constraints(field) {
+                let Some(expression) = expression(&field.column_name, &constraint, database) else { continue };
+                declared.insert(constraint_name(&model.table_name, &field.column_name, constraint.rule(), &expression), expression);
+            }
+        }
+        let existing = existing_constraints(transaction.clone(), &model.table_name, database).await?;
+        let table = identifier(&model.table_name, database);
+        for name in existing.iter().filter(|name| !declared.contains_key(*name)) {
+            let drop = if database.is_mysql() { "DROP CHECK" } else { "DROP CONSTRAINT" };
+            transaction.query_raw(&Value::String(format!("ALTER TABLE {} {} {}", table, drop, identifier(name, database)))).await?;
+        }
+        for (name, expression) in declared.iter().filter(|(name, _)| !existing.contains(*name)) {
+            transaction.query_raw(&Value::String(format!("ALTER TABLE {} ADD CONSTRAINT {} CHECK ({})", table, identifier(name, database), expression))).await.map_err(|e| {
+                Error::new(format!("cannot add the check constraint `{}` of `{}`, fix the rows violating it first: {}", name, model.path().join("."), e.message))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// The check expression of a rule, `None` if the database can't check it.
+pub(crate) fn expression(column: &str, constraint: &Constraint, database: &Database) -> Option<String> {
+    let column = identifier(column, database);
+    Some(match constraint {
+        Constraint::MinLength(n) => format!("char_length({}) >= {}", column, n),
+        Constraint::MaxLength(n) => format!("char_length({}) <= {}", column, n),
+        Constraint::Min(n) => format!("{} >= {}", column, n),
+        Constraint::Max(n) => format!("{} <= {}", column, n),
+        Constraint::Regex(pattern) if database.is_pg() => format!("{} ~ {}", column, quote(pattern, database).ok()?),
+        Constraint::Regex(_) => None?,
+    })
+}
+
+pub(crate) fn constraint_name(table: &str, column: &str, rule: &str, expression: &str) -> String {
+    let hash: String = digest(&SHA256, expression.as_bytes()).as_ref().iter().take(4).map(|b| format!("{:02x}", b)).collect();
+    let mut name = format!("{}{}_{}_{}", CONSTRAINT_PREFIX, table, column, rule.to_lowercase());
+    // keep the hash when the name is too long, character boundaries apart
+    let mut length = MAX_IDENTIFIER_LENGTH - hash.len() - 1;
+    while name.len() > length && !name.is_char_boundary(length) {
+        length -= 1;
+    }
+    name.truncate(length);
+    format!("{}_{}", name, hash)
+}
+
+async fn existing_constraints(transaction: Arc<dyn Transaction>, table: &str, database: &Database) -> Result<Vec<String>> {
+    let sql = match database {
+        Database::PostgreSQL => format!(
+            "SELECT c.conname AS name FROM pg_constraint c JOIN pg_class t ON t.oid = c.conrelid WHERE t.relname = {} AND c.contype = 'c' AND pg_table_is_visible(t.oid) AND starts_with(c.conname, {})",
+            quote(table, database)?, quote(CONSTRAINT_PREFIX, database)?,
+        ),
+        _ => format!(
+            "SELECT constraint_name AS name FROM information_schema.table_constraints WHERE table_schema = DATABASE() AND table_name = {} AND constraint_type = 'CHECK' AND LEFT(constraint_name, {}) = {}",
+            quote(table, database)?, CONSTRAINT_PREFIX.len(), quote(CONSTRAINT_PREFIX, database)?,
+        ),
+    };
+    Ok(match transaction.query_raw(&Value::String(sql)).await? {
+        Value::Array(rows) => rows.iter().filter_map(|row| row.get("name").or_else(|| row.get("NAME")).and_then(|n| n.as_str()).map(|n| n.to_owned())).collect(),
+        _ => vec![],
+    })
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::message::info_message;
+use crate::stdlib::decorators::fuzzy_index::has_fuzzy_index;
+use crate::utils::sql::{identifier, quote};
+
+/// Sync the `pg_trgm` GIN indexes of `@fuzzyIndex` fields on PostgreSQL.
+/// Missing indexes are created, and the ones of fields which lost the
+/// decorator are dropped. Other databases have no trigram indexes, `similar`
+/// filters compare the values on the server there.
+pub(crate) async fn sync_fuzzy_indexes(transaction: Arc<dyn Transaction>, models: &Vec<&Model>, database: &Database, silent: bool) -> Result<()> {
+    if !database.is_pg() {
+        return Ok(());
+    }
+    for model in models {
+        let wanted: Vec<(String, &str)> = model.fields.values()
+            .filter(|field| has_fuzzy_index(field))
+            .map(|field| (index_name(model, &field.column_name), field.column_name.as_str()))
+            .collect();
+        let existing = existing_indexes(transaction.clone(), model, database).await?;
+        for index in existing.iter().filter(|index| !wanted.iter().any(|(name, _)| name == *index)) {
+            transaction.query_raw(&Value::String(format!("DROP INDEX IF EXISTS {}", identifier(index, database)))).await?;
+            if !silent {
+                info_message(format!("dropped trigram index {}", index));
+            }
+        }
+        for (index, column) in wanted.iter().filter(|(name, _)| !existing.contains(name)) {
+            for statement in [
+                "CREATE EXTENSION IF NOT EXISTS pg_trgm".to_owned(),
+                format!("CREATE INDEX {} ON {} USING gin ({} gin_trgm_ops)", identifier(index, database), identifier(&model.table_name, database), identifier(column, database)),
+            ] {
+                transaction.query_raw(&Value::String(statement)).await.map_err(|e| {
+                    Error::new(format!("cannot create the trigram index of `{}.{}`: {}", model.path().join("."), column, e.message))
+                })?;
+            }
+            if !silent {
+                info_message(format!("created trigram index {}", index));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn index_name(model: &Model, column: &str) -> String {
+    format!("{}_{}_trgm_idx", model.table_name, column)
+}
+
+/// The trigram indexes of the table of a model, by the names migrations
+/// give them.
+async fn existing_indexes(transaction: Arc<dyn Transaction>, model: &Model, database: &Database) -> Result<Vec<String>> {
+    let rows = transaction.query_raw(&Value::String(format!(
+        "SELECT indexname FROM pg_indexes WHERE schemaname = current_schema() AND tablename = {}",
+        quote(&model.table_name, database)?,
+    ))).await?;
+    let Value::Array(rows) = rows else { return Ok(vec![]) };
+    let prefix = format!("{}_", model.table_name);
+    Ok(rows.iter()
+        .filter_map(|row| row.get("indexname").and_then(|name| name.as_str()))
+        .filter(|name| name.starts_with(&prefix) && name.ends_with("_trgm_idx"))
+        .map(ToOwned::to_owned)
+        .collect())
+}
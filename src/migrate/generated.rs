@@ -0,0 +1,113 @@
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::stdlib::decorators::generated::{field_generated, Generated};
+use crate::utils::sql::quote;
+
+/// Casts, quotes and grouping the databases add when they store an
+/// expression.
+static EXPRESSION_NOISE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"::[a-z ]+(\([0-9, ]*\))?|[\s()`"]"#).unwrap());
+
+/// A column as the database reports it.
+struct LiveColumn {
+    r#type: String,
+    /// The generation, `None` for a plain column.
+    generated: Option<Generated>,
+}
+
+/// Turn the columns of `@generated` fields into generated columns. The
+/// connector creates them as plain columns first; they are dropped and added
+/// again with `GENERATED ALWAYS AS`, keeping their types. Columns already
+/// generated with the same expression and storage are left alone.
+pub(crate) async fn alter_generated_columns(transaction: Arc<dyn Transaction>, models: &Vec<&Model>, database: &Database) -> Result<()> {
+    for model in models {
+        for field in model.fields.values() {
+            let Some(generated) = field_generated(field) else { continue };
+            let field_path = format!("{}.{}", model.path().join("."), field.name());
+            match database {
+                Database::PostgreSQL if !generated.stored => Err(Error::new(format!("`{}`: PostgreSQL only supports stored generated columns", field_path)))?,
+                Database::SQLite if generated.stored => Err(Error::new(format!("`{}`: SQLite cannot add stored generated columns to existing tables, use `stored: false`", field_path)))?,
+                Database::MongoDB => Err(Error::new(format!("`{}`: generated columns are only supported by SQL databases", field_path)))?,
+                _ => (),
+            }
+            let Some(column) = live_column(transaction.clone(), model, field, database).await? else { continue };
+            if column.generated.as_ref().map_or(false, |live| same(live, &generated, database)) {
+                continue
+            }
+            for statement in statements(model, field, &column.r#type, &generated, database) {
+                transaction.query_raw(&Value::String(statement)).await.map_err(|e| {
+                    Error::new(format!("cannot generate the column of `{}`: {}", field_path, e.message))
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn live_column(transaction: Arc<dyn Transaction>, model: &Model, field: &Field, database: &Database) -> Result<Option<LiveColumn>> {
+    let sql = match database {
+        Database::PostgreSQL => format!(
+            "SELECT format_type(a.atttypid, a.atttypmod) AS type, a.attgenerated = 's' AS generated, pg_get_expr(d.adbin, d.adrelid) AS expression FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum WHERE c.relname = {} AND a.attname = {} AND pg_table_is_visible(c.oid)",
+            quote(&model.table_name, database)?, quote(&field.column_name, database)?,
+        ),
+        Database::MySQL => format!(
+            "SELECT column_type AS type, extra LIKE '%GENERATED%' AS generated, extra LIKE 'STORED%' AS stored, generation_expression AS expression FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = {} AND column_name = {}",
+            quote(&model.table_name, database)?, quote(&field.column_name, database)?,
+        ),
+        // SQLite doesn't report the expression, `hidden` is 2 for virtual
+        // and 3 for stored generated columns
+        _ => format!(
+            "SELECT type AS type, hidden IN (2, 3) AS generated, hidden = 3 AS stored FROM pragma_table_xinfo({}) WHERE name = {}",
+            quote(&model.table_name, database)?, quote(&field.column_name, database)?,
+        ),
+    };
+    let rows = transaction.query_raw(&Value::String(sql)).await?;
+    let Some(row) = (match rows { Value::Array(rows) => rows.into_iter().next(), _ => None }) else { return Ok(None) };
+    let r#type = row.get("type").and_then(|t| t.as_str()).ok_or_else(|| Error::new(format!("cannot read the column type of `{}.{}`", model.path().join("."), field.name())))?.to_owned();
+    let generated = truthy(row.get("generated")).then(|| Generated {
+        expression: row.get("expression").and_then(|e| e.as_str()).unwrap_or_default().to_owned(),
+        stored: database.is_pg() || truthy(row.get("stored")),
+    });
+    Ok(Some(LiveColumn { r#type, generated }))
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Int(i)) => *i != 0,
+        Some(Value::Int64(i)) => *i != 0,
+        _ => false,
+    }
+}
+
+/// Whether a live generation matches the declared one. Expressions are
+/// compared without the casts, quotes, parentheses and whitespace the
+/// database adds; SQLite expressions aren't compared.
+fn same(live: &Generated, declared: &Generated, database: &Database) -> bool {
+    let normalize = |expression: &str| EXPRESSION_NOISE.replace_all(&expression.to_lowercase(), "").to_string();
+    live.stored == declared.stored && (matches!(database, Database::SQLite) || normalize(&live.expression) == normalize(&declared.expression))
+}
+
+fn statements(model: &Model, field: &Field, column_type: &str, generated: &Generated, database: &Database) -> Vec<String> {
+    let storage = if generated.stored { "STORED" } else { "VIRTUAL" };
+    let delimiter = if database.is_mysql() { '`' } else { '"' };
+    let table = format!("{0}{1}{0}", delimiter, model.table_name);
+    let column = format!("{0}{1}{0}", delimiter, field.column_name);
+    let nullability = if field.is_optional() { "NULL" } else { "NOT NULL" };
+    let add = format!("ADD COLUMN {} {} GENERATED ALWAYS AS ({}) {}", column, column_type, generated.expression, storage);
+    match database {
+        // SQLite alters one column at a time
+        Database::SQLite => vec![
+            format!("ALTER TABLE {} DROP COLUMN {}", table, column),
+            format!("ALTER TABLE {} {}", table, add),
+        ],
+        _ => vec![format!("ALTER TABLE {} DROP COLUMN {}, {} {}", table, column, add, nullability)],
+    }
+}
@@ -1,12 +1,150 @@
+pub mod backfill;
+pub(crate) mod check;
+pub(crate) mod collation;
+pub(crate) mod constraints;
+pub(crate) mod fuzzy;
+pub(crate) mod generated;
+pub(crate) mod online;
+pub(crate) mod rebuild;
+pub(crate) mod rename;
+pub(crate) mod scalars;
+pub(crate) mod verify;
+pub(crate) mod views;
+
 use teo_result::{Error, Result};
+use teo_runtime::database::database::Database;
 use crate::app::ctx::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::events::outbox::create_outbox_tables;
+use crate::migrate::backfill::run_backfills;
+use crate::migrate::check::record_schema_snapshot;
+use crate::migrate::collation::alter_collated_columns;
+use crate::migrate::constraints::sync_check_constraints;
+use crate::migrate::fuzzy::sync_fuzzy_indexes;
+use crate::migrate::generated::alter_generated_columns;
+use crate::migrate::online::alter_columns_online;
+use crate::migrate::rebuild::{begin_rebuilds, disable_foreign_keys, end_rebuilds, tables_to_rebuild};
+use crate::migrate::rename::rename_tables_and_columns;
+use crate::migrate::scalars::alter_scalar_columns;
+use crate::migrate::views::create_view;
+use crate::search::sync_search_mappings;
+use crate::server::sequence::create_sequences_table;
+use crate::server::magic_link::create_magic_links_table;
+use crate::server::impersonation::create_impersonations_table;
+use crate::server::sessions::create_sessions_table;
+use crate::server::slug::create_slug_history_table;
+use crate::stdlib::decorators::collation::has_collated_fields;
+use crate::stdlib::decorators::constraints::has_constrained_fields;
+use crate::stdlib::decorators::generated::has_generated_fields;
+use crate::stdlib::decorators::scalar::has_scalar_fields;
+use crate::stdlib::decorators::view::{is_materialized_view, model_view};
 
 pub async fn migrate(dry_run: bool, reset: bool, silent: bool) -> Result<()> {
     let ctx = Ctx::conn_ctx();
     for (namespace_path, connection) in ctx.connections_iter() {
         let namespace = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()).unwrap();
-        let transaction = connection.no_transaction().await?;
-        transaction.migrate(namespace.models_under_connector(), dry_run, reset, silent).await?;
+        let (views, models): (Vec<_>, Vec<_>) = namespace.models_under_connector().into_iter().partition(|model| model_view(model).is_some());
+        // the steps running SQL of their own are skipped with connectors like the memory connector
+        let provider = namespace.connector.as_ref().filter(|connector| is_provider_connector(connector)).map(|connector| &connector.provider);
+        // SQLite can't alter columns, the tables are rebuilt in one transaction
+        let rebuilds = match provider {
+            Some(Database::SQLite) if !dry_run && !reset => tables_to_rebuild(connection.no_transaction().await?, &namespace_path.join("."), &models).await,
+            _ => vec![],
+        };
+        if !rebuilds.is_empty() {
+            disable_foreign_keys(connection.no_transaction().await?).await?;
+        }
+        let transaction = if rebuilds.is_empty() { connection.no_transaction().await? } else { connection.transaction().await? };
+        let rebuild_transaction = transaction.clone();
+        let table_models = models.clone();
+        let snapshot_models = models.clone();
+        let scalar_models: Vec<_> = models.iter().filter(|model| has_scalar_fields(model)).copied().collect();
+        let generated_models: Vec<_> = models.iter().filter(|model| has_generated_fields(model)).copied().collect();
+        let collated_models: Vec<_> = models.iter().filter(|model| has_collated_fields(model)).copied().collect();
+        let constrained_models: Vec<_> = models.iter().filter(|model| has_constrained_fields(model)).copied().collect();
+        // errors are passed to the end of the rebuilds, which turns foreign keys on again
+        let prepared = async {
+            if let Some(database) = provider.filter(|database| !reset && !database.is_mongo()) {
+                rename_tables_and_columns(transaction.clone(), &namespace_path.join("."), &models, database, dry_run, silent).await?;
+            }
+            if let Some(database) = provider.filter(|_| !dry_run && !reset) {
+                alter_columns_online(connection.clone(), &namespace_path.join("."), &models, database, silent).await?;
+            }
+            if !rebuilds.is_empty() {
+                begin_rebuilds(transaction.clone(), &rebuilds, silent).await?;
+            }
+            Ok::<(), Error>(())
+        }.await;
+        let migrated = match prepared {
+            Ok(()) => {
+                // connectors panic on unsupported changes, report them as errors
+                let result = tokio::spawn(async move {
+                    transaction.migrate(models, dry_run, reset, silent).await
+                }).await;
+                match result {
+                    Ok(result) => result,
+                    Err(err) if err.is_panic() => {
+                        let payload = err.into_panic();
+                        let message = payload.downcast_ref::<String>().cloned().or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string())).unwrap_or_else(|| "migration panicked".to_owned());
+                        Err(Error::new(format!("migration failed: {}", message)))
+                    }
+                    Err(err) => Err(Error::new(format!("migration failed: {}", err))),
+                }
+            }
+            Err(error) => Err(error),
+        };
+        if rebuilds.is_empty() {
+            migrated?;
+        } else {
+            end_rebuilds(rebuild_transaction.clone(), connection.no_transaction().await?, &rebuilds, migrated).await?;
+        }
+        let view_transaction = if rebuilds.is_empty() { rebuild_transaction } else { connection.no_transaction().await? };
+        if let Some(database) = provider.filter(|database| !dry_run && !database.is_mongo()) {
+            create_slug_history_table(view_transaction.clone(), &table_models).await?;
+            create_sequences_table(view_transaction.clone(), &table_models).await?;
+            record_schema_snapshot(view_transaction.clone(), &namespace_path.join("."), &snapshot_models, database).await?;
+            sync_fuzzy_indexes(view_transaction.clone(), &snapshot_models, database, silent).await?;
+        }
+        if !dry_run && !scalar_models.is_empty() {
+            if let Some(database) = provider {
+                alter_scalar_columns(view_transaction.clone(), &scalar_models, database).await?;
+            }
+        }
+        if !dry_run && !collated_models.is_empty() {
+            if let Some(database) = provider {
+                alter_collated_columns(view_transaction.clone(), &collated_models, database).await?;
+            }
+        }
+        if !dry_run && !generated_models.is_empty() {
+            if let Some(database) = provider {
+                alter_generated_columns(view_transaction.clone(), &generated_models, database).await?;
+            }
+        }
+        if !dry_run && !constrained_models.is_empty() {
+            if let Some(database) = provider {
+                sync_check_constraints(view_transaction.clone(), &constrained_models, database).await?;
+            }
+        }
+        if !dry_run && !views.is_empty() {
+            let Some(database) = provider.filter(|database| !database.is_mongo()) else {
+                Err(Error::new("view models are only supported by SQL databases"))?
+            };
+            for view in views {
+                if is_materialized_view(view) && !database.is_pg() {
+                    Err(Error::new(format!("materialized view `{}` is only supported by PostgreSQL", view.path().join("."))))?
+                }
+                create_view(view_transaction.clone(), view, database).await?;
+            }
+        }
+    }
+    if !dry_run {
+        create_outbox_tables().await?;
+        create_sessions_table().await?;
+        create_impersonations_table().await?;
+        create_magic_links_table().await?;
+        sync_search_mappings(Ctx::main_namespace()).await?;
+        run_backfills(silent).await?;
     }
     Ok(())
 }
+
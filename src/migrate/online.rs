@@ -0,0 +1,401 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::message::info_message;
+use crate::migrate::check::{field_snapshot, recorded_snapshot};
+use crate::server::estimate::int;
+use crate::stdlib::decorators::online_alter::model_online_alter;
+use crate::utils::sql::{column_type, identifier, quote, sql_literal};
+
+/// The table the progress of online column alterations is recorded in, so
+/// that an interrupted migration resumes where it stopped: the phase of the
+/// alteration and, while copying, the last copied key.
+pub(crate) const ONLINE_ALTERATIONS_TABLE: &str = "_teo_online_alterations";
+
+/// The suffix of the column holding the new values until the swap.
+const NEW_COLUMN_SUFFIX: &str = "__teo_new";
+
+/// The suffix the replaced column is renamed with before it's dropped.
+const OLD_COLUMN_SUFFIX: &str = "__teo_old";
+
+/// Alter the columns of `@@onlineAlter` models whose type or optionality
+/// changed since the last migration, on MySQL and PostgreSQL, before the
+/// connector migrates the models. After the swap the columns match the
+/// schema, so the connector doesn't alter them with a blocking statement.
+pub(super) async fn alter_columns_online(connection: Arc<dyn Connection>, namespace_path: &str, models: &Vec<&Model>, database: &Database, silent: bool) -> Result<()> {
+    let models: Vec<(&Model, usize)> = models.iter().filter_map(|model| model_online_alter(model).map(|batch_size| (*model, batch_size))).collect();
+    if models.is_empty() || !(database.is_mysql() || database.is_pg()) {
+        return Ok(());
+    }
+    let transaction = connection.no_transaction().await?;
+    let Some(recorded) = recorded_snapshot(transaction.clone(), namespace_path, database).await else {
+        return Ok(());
+    };
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (table_name VARCHAR(191) NOT NULL, column_name VARCHAR(191) NOT NULL, phase VARCHAR(16) NOT NULL, last_key TEXT, PRIMARY KEY (table_name, column_name))",
+        ONLINE_ALTERATIONS_TABLE,
+    ))).await?;
+    for (model, batch_size) in models {
+        let Some(old_fields) = recorded.get(model.path().join(".")).and_then(|m| m.get("fields")) else { continue };
+        for field in model.fields.values() {
+            let Some(old_field) = old_fields.get(field.name()) else { continue };
+            if ["type", "optional"].iter().all(|key| old_field.get(key) == field_snapshot(field).get(key)) {
+                continue
+            }
+            let alteration = OnlineAlteration::new(model, field, database.clone(), batch_size, silent)?;
+            alteration.run(connection.clone(), transaction.clone()).await.map_err(|e| {
+                Error::new(format!("cannot alter the column of `{}.{}` online: {}", model.path().join("."), field.name(), e.message))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// The phase of an alteration in progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// The rows are being copied to the new column.
+    Copying,
+    /// The columns are being swapped, MySQL only.
+    Swapping,
+    /// The columns are swapped, the old one may be left.
+    Swapped,
+}
+
+impl Phase {
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Copying => "copying",
+            Phase::Swapping => "swapping",
+            Phase::Swapped => "swapped",
+        }
+    }
+
+    fn parse(phase: &str) -> Option<Self> {
+        match phase {
+            "copying" => Some(Phase::Copying),
+            "swapping" => Some(Phase::Swapping),
+            "swapped" => Some(Phase::Swapped),
+            _ => None,
+        }
+    }
+}
+
+/// The alteration of one column: a new column is added and kept in sync with
+/// the old one by triggers, the existing rows are copied in batches in the
+/// order of the primary key, and the columns are swapped.
+///
+/// An interrupted alteration is resumed by the next migration from the
+/// phase it recorded. On PostgreSQL the swap is one transaction, which
+/// deletes the progress too. MySQL commits implicitly on `LOCK TABLES` and
+/// `ALTER TABLE`, so its swap isn't atomic: it records `swapping` before
+/// dropping the triggers and `swapped` after renaming the columns. A swap
+/// stopped before the rename copies the rows again, as writes may have
+/// been made without the triggers, and a swap stopped after it only drops
+/// the old column.
+struct OnlineAlteration {
+    database: Database,
+    table: String,
+    column: String,
+    key: String,
+    column_type: String,
+    required: bool,
+    batch_size: usize,
+    silent: bool,
+}
+
+impl OnlineAlteration {
+
+    fn new(model: &Model, field: &Field, database: Database, batch_size: usize, silent: bool) -> Result<Self> {
+        let keys: Vec<String> = model.primary_index().ok_or_else(|| Error::new("the model has no primary key"))?
+            .items.iter().map(|item| item.field.clone()).collect();
+        let [key] = keys.as_slice() else {
+            Err(Error::new("the model must have a primary key of one field"))?
+        };
+        let key = model.field(key).ok_or_else(|| Error::not_found())?.column_name.clone();
+        Ok(Self {
+            table: model.table_name.clone(),
+            column: field.column_name.clone(),
+            key,
+            column_type: column_type(field.r#type(), &database)?,
+            required: !field.is_optional(),
+            batch_size,
+            silent,
+            database,
+        })
+    }
+
+    async fn run(&self, connection: Arc<dyn Connection>, transaction: Arc<dyn Transaction>) -> Result<()> {
+        match self.recorded_progress(transaction.clone()).await? {
+            None => {
+                self.message(format!("altering {}.{} online to {}", self.table, self.column, self.column_type));
+                self.add_column(transaction.clone()).await?;
+                self.create_triggers(transaction.clone()).await?;
+                self.record_progress(transaction.clone(), Phase::Copying, None).await?;
+                self.copy_and_swap(connection, transaction, None).await?;
+            }
+            Some((Phase::Copying, last_key)) => {
+                self.message(format!("resuming the alteration of {}.{}", self.table, self.column));
+                self.create_triggers(transaction.clone()).await?;
+                self.copy_and_swap(connection, transaction, last_key).await?;
+            }
+            Some((Phase::Swapping, _)) if self.column_exists(transaction.clone(), &format!("{}{}", self.column, NEW_COLUMN_SUFFIX)).await? => {
+                self.message(format!("resuming the alteration of {}.{}, copying the rows again", self.table, self.column));
+                self.create_triggers(transaction.clone()).await?;
+                self.record_progress(transaction.clone(), Phase::Copying, None).await?;
+                self.copy_and_swap(connection, transaction, None).await?;
+            }
+            Some((Phase::Swapping | Phase::Swapped, _)) => {
+                self.message(format!("finishing the alteration of {}.{}", self.table, self.column));
+                self.finish(transaction).await?;
+            }
+        }
+        self.message(format!("altered {}.{}", self.table, self.column));
+        Ok(())
+    }
+
+    async fn copy_and_swap(&self, connection: Arc<dyn Connection>, transaction: Arc<dyn Transaction>, last_key: Option<String>) -> Result<()> {
+        self.copy(transaction.clone(), last_key).await?;
+        if self.required && self.database.is_pg() {
+            // validating the check takes no lock which blocks writes, and lets
+            // SET NOT NULL skip its scan
+            self.execute(transaction.clone(), format!("ALTER TABLE {} DROP CONSTRAINT IF EXISTS {}", self.table(), self.check_name())).await?;
+            self.execute(transaction.clone(), format!("ALTER TABLE {} ADD CONSTRAINT {} CHECK ({} IS NOT NULL) NOT VALID", self.table(), self.check_name(), self.new_column())).await?;
+            self.execute(transaction.clone(), format!("ALTER TABLE {} VALIDATE CONSTRAINT {}", self.table(), self.check_name())).await?;
+        }
+        match self.database {
+            Database::PostgreSQL => self.swap_pg(connection).await,
+            _ => {
+                self.swap_mysql(transaction.clone()).await?;
+                self.finish(transaction).await
+            }
+        }
+    }
+
+    /// The phase and the last copied key of an alteration in progress.
+    async fn recorded_progress(&self, transaction: Arc<dyn Transaction>) -> Result<Option<(Phase, Option<String>)>> {
+        let rows = transaction.query_raw(&Value::String(format!(
+            "SELECT phase, last_key FROM {} WHERE table_name = {} AND column_name = {}",
+            ONLINE_ALTERATIONS_TABLE, quote(&self.table, &self.database)?, quote(&self.column, &self.database)?,
+        ))).await?;
+        let Value::Array(rows) = rows else { return Ok(None) };
+        let Some(row) = rows.first() else { return Ok(None) };
+        let phase = row.get("phase").and_then(|p| p.as_str()).unwrap_or_default();
+        let phase = Phase::parse(phase).ok_or_else(|| Error::new(format!("unknown phase `{}` of the alteration in progress", phase)))?;
+        Ok(Some((phase, row.get("last_key").and_then(|k| k.as_str()).map(|k| k.to_owned()))))
+    }
+
+    async fn record_progress(&self, transaction: Arc<dyn Transaction>, phase: Phase, last_key: Option<&str>) -> Result<()> {
+        let last_key = match last_key {
+            Some(last_key) => quote(last_key, &self.database)?,
+            None => "NULL".to_owned(),
+        };
+        self.delete_progress(transaction.clone()).await?;
+        self.execute(transaction, format!(
+            "INSERT INTO {} (table_name, column_name, phase, last_key) VALUES ({}, {}, {}, {})",
+            ONLINE_ALTERATIONS_TABLE, quote(&self.table, &self.database)?, quote(&self.column, &self.database)?, quote(phase.as_str(), &self.database)?, last_key,
+        )).await
+    }
+
+    async fn delete_progress(&self, transaction: Arc<dyn Transaction>) -> Result<()> {
+        self.execute(transaction, format!(
+            "DELETE FROM {} WHERE table_name = {} AND column_name = {}",
+            ONLINE_ALTERATIONS_TABLE, quote(&self.table, &self.database)?, quote(&self.column, &self.database)?,
+        )).await
+    }
+
+    async fn column_exists(&self, transaction: Arc<dyn Transaction>, column: &str) -> Result<bool> {
+        let exists = match self.database {
+            Database::PostgreSQL => "SELECT column_name AS name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = {} AND column_name = {}",
+            _ => "SELECT column_name AS name FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = {} AND column_name = {}",
+        };
+        let exists = exists.replacen("{}", &quote(&self.table, &self.database)?, 1).replacen("{}", &quote(column, &self.database)?, 1);
+        Ok(matches!(transaction.query_raw(&Value::String(exists)).await?, Value::Array(rows) if !rows.is_empty()))
+    }
+
+    /// Add the new column, nullable until every row is copied. A column left
+    /// by an alteration which stopped before recording its progress is
+    /// dropped first.
+    async fn add_column(&self, transaction: Arc<dyn Transaction>) -> Result<()> {
+        if self.column_exists(transaction.clone(), &format!("{}{}", self.column, NEW_COLUMN_SUFFIX)).await? {
+            self.execute(transaction.clone(), format!("ALTER TABLE {} DROP COLUMN {}", self.table(), self.new_column())).await?;
+        }
+        self.execute(transaction, format!("ALTER TABLE {} ADD COLUMN {} {} NULL", self.table(), self.new_column(), self.column_type)).await
+    }
+
+    /// Copy every write of the old column to the new one.
+    async fn create_triggers(&self, transaction: Arc<dyn Transaction>) -> Result<()> {
+        let trigger = self.trigger_name("");
+        match self.database {
+            Database::PostgreSQL => {
+                self.execute(transaction.clone(), format!(
+                    "CREATE OR REPLACE FUNCTION {}() RETURNS trigger AS $teo$ BEGIN NEW.{} := {}; RETURN NEW; END $teo$ LANGUAGE plpgsql",
+                    trigger, self.new_column(), self.converted(&format!("NEW.{}", self.column())),
+                )).await?;
+                self.execute(transaction.clone(), format!("DROP TRIGGER IF EXISTS {} ON {}", trigger, self.table())).await?;
+                self.execute(transaction, format!("CREATE TRIGGER {} BEFORE INSERT OR UPDATE ON {} FOR EACH ROW EXECUTE FUNCTION {}()", trigger, self.table(), trigger)).await
+            }
+            _ => {
+                for event in ["INSERT", "UPDATE"] {
+                    let trigger = self.trigger_name(&format!("_{}", event.to_lowercase()));
+                    self.execute(transaction.clone(), format!("DROP TRIGGER IF EXISTS {}", trigger)).await?;
+                    self.execute(transaction.clone(), format!(
+                        "CREATE TRIGGER {} BEFORE {} ON {} FOR EACH ROW SET NEW.{} = NEW.{}",
+                        trigger, event, self.table(), self.new_column(), self.column(),
+                    )).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Copy the rows after `last_key` in batches, recording the progress
+    /// after each one.
+    async fn copy(&self, transaction: Arc<dyn Transaction>, mut last_key: Option<String>) -> Result<()> {
+        let total = match transaction.query_raw(&Value::String(format!("SELECT COUNT(*) AS count FROM {}", self.table()))).await? {
+            Value::Array(rows) => rows.first().and_then(|row| row.get("count")).and_then(int).unwrap_or(0),
+            _ => 0,
+        };
+        let mut copied = 0;
+        loop {
+            let after = last_key.as_ref().map(|k| format!(" WHERE {} > {}", self.key(), k)).unwrap_or_default();
+            let rows = transaction.query_raw(&Value::String(format!(
+                "SELECT {} AS k FROM {}{} ORDER BY {} LIMIT {}",
+                self.key(), self.table(), after, self.key(), self.batch_size,
+            ))).await?;
+            let Value::Array(rows) = rows else { break };
+            let Some(last) = rows.last().and_then(|row| row.get("k")) else { break };
+            let last = sql_literal(last, &self.database)?;
+            let lower = last_key.as_ref().map(|k| format!("{} > {} AND ", self.key(), k)).unwrap_or_default();
+            self.execute(transaction.clone(), format!(
+                "UPDATE {} SET {} = {} WHERE {}{} <= {}",
+                self.table(), self.new_column(), self.converted(&self.column()), lower, self.key(), last,
+            )).await?;
+            self.record_progress(transaction.clone(), Phase::Copying, Some(&last)).await?;
+            copied += rows.len();
+            self.message(format!("{}.{}: copied {} of about {} rows", self.table, self.column, copied, total));
+            last_key = Some(last);
+            if rows.len() < self.batch_size {
+                break
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the triggers, swap the columns, drop the old one and the
+    /// progress in one transaction.
+    async fn swap_pg(&self, connection: Arc<dyn Connection>) -> Result<()> {
+        let transaction = connection.transaction().await?;
+        let result = async {
+            self.execute(transaction.clone(), format!("DROP TRIGGER IF EXISTS {} ON {}", self.trigger_name(""), self.table())).await?;
+            self.execute(transaction.clone(), format!("DROP FUNCTION IF EXISTS {}()", self.trigger_name(""))).await?;
+            self.execute(transaction.clone(), format!("ALTER TABLE {} RENAME COLUMN {} TO {}", self.table(), self.column(), self.old_column())).await?;
+            self.execute(transaction.clone(), format!("ALTER TABLE {} RENAME COLUMN {} TO {}", self.table(), self.new_column(), self.column())).await?;
+            if self.required {
+                self.execute(transaction.clone(), format!("ALTER TABLE {} ALTER COLUMN {} SET NOT NULL", self.table(), self.column())).await?;
+                self.execute(transaction.clone(), format!("ALTER TABLE {} DROP CONSTRAINT {}", self.table(), self.check_name())).await?;
+            }
+            self.execute(transaction.clone(), format!("ALTER TABLE {} DROP COLUMN {}", self.table(), self.old_column())).await?;
+            self.delete_progress(transaction.clone()).await
+        }.await;
+        match result {
+            Ok(()) => transaction.commit().await,
+            Err(error) => {
+                transaction.abort().await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Drop the triggers and swap the columns with the table locked, so that
+    /// no write is lost between them. Making a MySQL column required rebuilds
+    /// the table under the lock, which is the only blocking step. Each
+    /// statement commits, the phases are recorded around them.
+    async fn swap_mysql(&self, transaction: Arc<dyn Transaction>) -> Result<()> {
+        self.record_progress(transaction.clone(), Phase::Swapping, None).await?;
+        let result = async {
+            self.execute(transaction.clone(), format!("LOCK TABLES {} WRITE", self.table())).await?;
+            for event in ["insert", "update"] {
+                self.execute(transaction.clone(), format!("DROP TRIGGER IF EXISTS {}", self.trigger_name(&format!("_{}", event)))).await?;
+            }
+            let required = if self.required { format!(", MODIFY COLUMN {} {} NOT NULL", self.new_column(), self.column_type) } else { String::new() };
+            self.execute(transaction.clone(), format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}, RENAME COLUMN {} TO {}{}",
+                self.table(), self.column(), self.old_column(), self.new_column(), self.column(), required,
+            )).await
+        }.await;
+        transaction.query_raw(&Value::String("UNLOCK TABLES".to_owned())).await?;
+        result?;
+        self.record_progress(transaction, Phase::Swapped, None).await
+    }
+
+    /// Drop the old column if it's left, and the progress.
+    async fn finish(&self, transaction: Arc<dyn Transaction>) -> Result<()> {
+        if self.column_exists(transaction.clone(), &format!("{}{}", self.column, OLD_COLUMN_SUFFIX)).await? {
+            self.execute(transaction.clone(), format!("ALTER TABLE {} DROP COLUMN {}", self.table(), self.old_column())).await?;
+        }
+        self.delete_progress(transaction).await
+    }
+
+    /// The value of the old column converted to the new type.
+    fn converted(&self, column: &str) -> String {
+        match self.database {
+            Database::PostgreSQL => format!("CAST({} AS {})", column, self.column_type),
+            // MySQL converts values on assignment
+            _ => column.to_owned(),
+        }
+    }
+
+    fn table(&self) -> String {
+        identifier(&self.table, &self.database)
+    }
+
+    fn column(&self) -> String {
+        identifier(&self.column, &self.database)
+    }
+
+    fn new_column(&self) -> String {
+        identifier(&format!("{}{}", self.column, NEW_COLUMN_SUFFIX), &self.database)
+    }
+
+    fn old_column(&self) -> String {
+        identifier(&format!("{}{}", self.column, OLD_COLUMN_SUFFIX), &self.database)
+    }
+
+    fn key(&self) -> String {
+        identifier(&self.key, &self.database)
+    }
+
+    fn trigger_name(&self, suffix: &str) -> String {
+        identifier(&limited(format!("_teo_sync_{}_{}{}", self.table, self.column, suffix)), &self.database)
+    }
+
+    fn check_name(&self) -> String {
+        identifier(&limited(format!("_teo_required_{}_{}", self.table, self.column)), &self.database)
+    }
+
+    async fn execute(&self, transaction: Arc<dyn Transaction>, statement: String) -> Result<()> {
+        transaction.query_raw(&Value::String(statement)).await?;
+        Ok(())
+    }
+
+    fn message(&self, message: String) {
+        if !self.silent {
+            info_message(message);
+        }
+    }
+}
+
+/// Cut a name to the 63 characters PostgreSQL keeps of identifiers.
+fn limited(name: String) -> String {
+    name.chars().take(63).collect()
+}
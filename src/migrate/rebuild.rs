@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::message::info_message;
+use crate::migrate::check::{field_snapshot, recorded_snapshot};
+use crate::utils::sql::{identifier, quote};
+
+/// The prefix of the temporary tables holding the records of rebuilt tables.
+const REBUILD_PREFIX: &str = "_teo_rebuild_";
+
+/// The tables of the models whose columns change their type, optionality or
+/// default since the last migration. SQLite can't alter columns, so these
+/// are rebuilt.
+pub(super) async fn tables_to_rebuild(transaction: Arc<dyn Transaction>, namespace_path: &str, models: &Vec<&Model>) -> Vec<String> {
+    let Some(recorded) = recorded_snapshot(transaction, namespace_path, &Database::SQLite).await else {
+        return vec![];
+    };
+    models.iter().filter(|model| {
+        let Some(old_fields) = recorded.get(model.path().join(".")).and_then(|m| m.get("fields")) else { return false };
+        model.fields.values().any(|field| {
+            let Some(old_field) = old_fields.get(field.name()) else { return false };
+            ["type", "optional", "default"].iter().any(|key| old_field.get(key) != field_snapshot(field).get(key))
+        })
+    }).map(|model| model.table_name.clone()).collect()
+}
+
+/// Turn foreign keys off for the rebuild. Dropping a table would otherwise
+/// delete the records referencing it through cascading foreign keys. SQLite
+/// ignores this inside a transaction, so it's run before the migration
+/// transaction begins.
+pub(super) async fn disable_foreign_keys(transaction: Arc<dyn Transaction>) -> Result<()> {
+    transaction.query_raw(&Value::String("PRAGMA foreign_keys = OFF".to_owned())).await?;
+    Ok(())
+}
+
+/// Move the records of the tables to rebuild into temporary tables and drop
+/// the tables, so that the connector creates them again with the new columns
+/// and indexes. Runs in the transaction of the migration, with foreign keys
+/// off. Records referencing the tables keep referencing them by name.
+pub(super) async fn begin_rebuilds(transaction: Arc<dyn Transaction>, tables: &Vec<String>, silent: bool) -> Result<()> {
+    for table in tables {
+        if !silent {
+            info_message(format!("rebuilding table {}", table));
+        }
+        let backup = identifier(&format!("{}{}", REBUILD_PREFIX, table), &Database::SQLite);
+        transaction.query_raw(&Value::String(format!("DROP TABLE IF EXISTS temp.{}", backup))).await?;
+        transaction.query_raw(&Value::String(format!("CREATE TEMP TABLE {} AS SELECT * FROM {}", backup, identifier(table, &Database::SQLite)))).await?;
+        transaction.query_raw(&Value::String(format!("DROP TABLE {}", identifier(table, &Database::SQLite)))).await?;
+    }
+    Ok(())
+}
+
+/// Copy the records back into the rebuilt tables, column by column, check
+/// the foreign keys and commit the migration. Columns removed from a table
+/// are left out and new ones get their defaults. If the migration failed or
+/// a foreign key is broken, it's rolled back and the tables are kept as they
+/// were. Foreign keys are turned on again with `connection` either way.
+pub(super) async fn end_rebuilds(transaction: Arc<dyn Transaction>, connection: Arc<dyn Transaction>, tables: &Vec<String>, migrated: Result<()>) -> Result<()> {
+    let result = match migrated {
+        Ok(()) => match copy_back(transaction.clone(), tables).await {
+            Ok(()) => check_foreign_keys(transaction.clone()).await,
+            Err(error) => Err(error),
+        },
+        Err(error) => Err(error),
+    };
+    let result = match result {
+        Ok(()) => transaction.commit().await,
+        Err(error) => transaction.abort().await.and(Err(error)),
+    };
+    connection.query_raw(&Value::String("PRAGMA foreign_keys = ON".to_owned())).await?;
+    result
+}
+
+/// Fail if a record references a record which doesn't exist, e.g. when a
+/// rebuilt column can't hold the values it's referenced by.
+async fn check_foreign_keys(transaction: Arc<dyn Transaction>) -> Result<()> {
+    let rows = transaction.query_raw(&Value::String("PRAGMA foreign_key_check".to_owned())).await?;
+    let Value::Array(rows) = rows else { return Ok(()) };
+    let broken: Vec<String> = rows.iter().filter_map(|row| {
+        let table = row.get("table").and_then(|t| t.as_str())?;
+        let parent = row.get("parent").and_then(|p| p.as_str())?;
+        Some(format!("{} -> {}", table, parent))
+    }).collect();
+    if broken.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(format!("the rebuilt tables break foreign keys: {}", broken.join(", "))))
+    }
+}
+
+async fn copy_back(transaction: Arc<dyn Transaction>, tables: &Vec<String>) -> Result<()> {
+    for table in tables {
+        let backup = format!("{}{}", REBUILD_PREFIX, table);
+        let new_columns = columns(transaction.clone(), "main", table).await?;
+        let columns: Vec<String> = columns(transaction.clone(), "temp", &backup).await?.into_iter()
+            .filter(|column| new_columns.contains(column))
+            .map(|column| identifier(&column, &Database::SQLite))
+            .collect();
+        let backup = identifier(&backup, &Database::SQLite);
+        transaction.query_raw(&Value::String(format!("INSERT INTO {} ({}) SELECT {} FROM temp.{}", identifier(table, &Database::SQLite), columns.join(", "), columns.join(", "), backup))).await.map_err(|e| {
+            Error::new(format!("cannot copy the records of {} into the rebuilt table: {}", table, e.message))
+        })?;
+        transaction.query_raw(&Value::String(format!("DROP TABLE temp.{}", backup))).await?;
+    }
+    Ok(())
+}
+
+/// The column names of a table of a schema.
+async fn columns(transaction: Arc<dyn Transaction>, schema: &str, table: &str) -> Result<Vec<String>> {
+    let rows = transaction.query_raw(&Value::String(format!("SELECT name FROM pragma_table_info({}, {})", quote(table, &Database::SQLite)?, quote(schema, &Database::SQLite)?))).await?;
+    let Value::Array(rows) = rows else { return Ok(vec![]) };
+    Ok(rows.iter().filter_map(|row| row.get("name").and_then(|n| n.as_str()).map(|n| n.to_owned())).collect())
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use teo_result::Result;
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+use crate::message::info_message;
+use crate::migrate::check::recorded_snapshot;
+use crate::utils::sql::identifier;
+
+/// Rename the tables and columns whose names changed since the last
+/// migration, e.g. by `@map` or a new naming convention of the app, before
+/// the connector migrates the models. The connector would drop the old
+/// tables and columns and create empty ones instead.
+///
+/// The old names are read from the schema snapshot of the last migration.
+/// Nothing is renamed before the first one. The renames are printed unless
+/// `silent`, and only printed with `dry_run`.
+pub(super) async fn rename_tables_and_columns(transaction: Arc<dyn Transaction>, namespace_path: &str, models: &Vec<&Model>, database: &Database, dry_run: bool, silent: bool) -> Result<()> {
+    let Some(recorded) = recorded_snapshot(transaction.clone(), namespace_path, database).await else {
+        return Ok(());
+    };
+    for model in models {
+        let Some(old) = recorded.get(model.path().join(".")) else { continue };
+        let table = identifier(&model.table_name, database);
+        if let Some(old_table) = old.get("table").and_then(|t| t.as_str()).filter(|t| *t != model.table_name) {
+            run(transaction.clone(), format!("ALTER TABLE {} RENAME TO {}", identifier(old_table, database), table), dry_run, silent).await?;
+        }
+        for field in model.fields.values() {
+            let Some(old_column) = old.get("fields").and_then(|f| f.get(field.name())).and_then(|f| f.get("column")).and_then(|c| c.as_str()) else { continue };
+            if old_column != field.column_name {
+                run(transaction.clone(), format!("ALTER TABLE {} RENAME COLUMN {} TO {}", table, identifier(old_column, database), identifier(&field.column_name, database)), dry_run, silent).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run(transaction: Arc<dyn Transaction>, statement: String, dry_run: bool, silent: bool) -> Result<()> {
+    if !silent {
+        info_message(&statement);
+    }
+    if !dry_run {
+        transaction.query_raw(&Value::String(statement)).await?;
+    }
+    Ok(())
+}
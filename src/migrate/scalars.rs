@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::stdlib::decorators::scalar::field_scalar;
+
+/// Change the columns of custom scalar fields to the types their codecs map
+/// them to. The connector creates the columns with the declared field types
+/// first.
+pub(crate) async fn alter_scalar_columns(transaction: Arc<dyn Transaction>, models: &Vec<&Model>, database: &Database) -> Result<()> {
+    for model in models {
+        for field in model.fields.values() {
+            let Some(name) = field_scalar(field) else { continue };
+            let Some(codec) = Ctx::scalar(name) else { continue };
+            let Some(column_type) = codec.database_type(database) else { continue };
+            let statement = match database {
+                Database::PostgreSQL => format!("ALTER TABLE \"{}\" ALTER COLUMN \"{}\" TYPE {} USING \"{}\"::{}", model.table_name, field.column_name, column_type, field.column_name, column_type),
+                Database::MySQL => format!("ALTER TABLE `{}` MODIFY COLUMN `{}` {} {}", model.table_name, field.column_name, column_type, if field.is_optional() { "NULL" } else { "NOT NULL" }),
+                _ => continue,
+            };
+            transaction.query_raw(&Value::String(statement)).await.map_err(|e| {
+                Error::new(format!("cannot change the column of `{}.{}` to {}: {}", model.path().join("."), field.name(), column_type, e.message))
+            })?;
+        }
+    }
+    Ok(())
+}
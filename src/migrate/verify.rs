@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::message::info_message;
+use crate::stdlib::decorators::view::model_view;
+use crate::utils::sql::quote;
+
+/// A column of a live table.
+#[derive(Debug)]
+struct Column {
+    r#type: String,
+    nullable: bool,
+}
+
+/// Compare the tables of the live databases with the models before the
+/// server starts: missing tables and columns, column types, nullability and
+/// declared indexes. Fails with a report of every difference, so that a
+/// missed migration stops the server instead of surfacing as decoding errors
+/// on the first queries.
+pub(crate) async fn verify_schema(silent: bool) -> Result<()> {
+    let ctx = Ctx::conn_ctx();
+    let mut differences = vec![];
+    for (namespace_path, connection) in ctx.connections_iter() {
+        let namespace = ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()).unwrap();
+        let name = if namespace_path.is_empty() { "main".to_owned() } else { namespace_path.join(".") };
+        let Some(connector) = namespace.connector.as_ref() else { continue };
+        let database = connector.provider.clone();
+        if database.is_mongo() || !is_provider_connector(connector) {
+            if !silent {
+                info_message(format!("{}: schema verification is only supported by SQL databases", name));
+            }
+            continue
+        }
+        let transaction = connection.no_transaction().await?;
+        for model in namespace.models_under_connector() {
+            for difference in verify_model(model, &database, transaction.clone()).await? {
+                differences.push(format!("{}: {}", name, difference));
+            }
+        }
+    }
+    if differences.is_empty() {
+        if !silent {
+            info_message("the databases match the schema");
+        }
+        return Ok(());
+    }
+    Err(Error::new(format!(
+        "the databases don't match the schema, run `teo migrate` or fix the schema:\n  {}",
+        differences.join("\n  "),
+    )))
+}
+
+async fn verify_model(model: &Model, database: &Database, transaction: Arc<dyn Transaction>) -> Result<Vec<String>> {
+    let model_path = model.path().join(".");
+    let columns = columns(&model.table_name, database, transaction.clone()).await?;
+    if columns.is_empty() {
+        return Ok(vec![format!("table `{}` of `{}` is missing", model.table_name, model_path)]);
+    }
+    let mut differences = vec![];
+    for field in model.fields.values().filter(|f| !f.r#virtual) {
+        let Some(column) = columns.get(&field.column_name) else {
+            differences.push(format!("column `{}` of `{}.{}` is missing", field.column_name, model_path, field.name()));
+            continue
+        };
+        if let (Some(expected), Some(found)) = (field_family(field.r#type()), column_family(&column.r#type)) {
+            // SQLite columns accept any type
+            if expected != found && !matches!(database, Database::SQLite) {
+                differences.push(format!("column `{}` of `{}.{}` is {}, expect {}", field.column_name, model_path, field.name(), column.r#type, expected));
+            }
+        }
+        if field.is_optional() && !column.nullable {
+            differences.push(format!("column `{}` of `{}.{}` is NOT NULL, but the field is optional", field.column_name, model_path, field.name()));
+        }
+        // SQLite reports primary keys as nullable
+        if !field.is_optional() && column.nullable && !matches!(database, Database::SQLite) {
+            differences.push(format!("column `{}` of `{}.{}` is nullable, but the field is required", field.column_name, model_path, field.name()));
+        }
+    }
+    if model_view(model).is_none() {
+        let indexes = indexes(&model.table_name, database, transaction).await?;
+        for index in model.indexes.values() {
+            let expected: BTreeSet<String> = index.items.iter()
+                .map(|item| model.field(&item.field).map_or(item.field.clone(), |f| f.column_name.clone()))
+                .collect();
+            if !indexes.values().any(|found| *found == expected) {
+                differences.push(format!("index on ({}) of `{}` is missing", expected.into_iter().collect::<Vec<_>>().join(", "), model_path));
+            }
+        }
+    }
+    Ok(differences)
+}
+
+/// The columns of a table by name, empty if the table doesn't exist.
+async fn columns(table: &str, database: &Database, transaction: Arc<dyn Transaction>) -> Result<BTreeMap<String, Column>> {
+    let sql = match database {
+        Database::PostgreSQL => format!(
+            "SELECT column_name AS name, data_type AS type, is_nullable AS nullable FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = {}",
+            quote(table, database)?,
+        ),
+        Database::MySQL => format!(
+            "SELECT column_name AS name, column_type AS type, is_nullable AS nullable FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = {}",
+            quote(table, database)?,
+        ),
+        _ => format!(
+            "SELECT name AS name, type AS type, CASE WHEN \"notnull\" = 0 THEN 'YES' ELSE 'NO' END AS nullable FROM pragma_table_info({})",
+            quote(table, database)?,
+        ),
+    };
+    let mut result = BTreeMap::new();
+    for row in rows(transaction.query_raw(&Value::String(sql)).await?) {
+        let (Some(name), Some(r#type), Some(nullable)) = (string(&row, "name"), string(&row, "type"), string(&row, "nullable")) else { continue };
+        result.insert(name, Column { r#type, nullable: nullable.eq_ignore_ascii_case("YES") });
+    }
+    Ok(result)
+}
+
+/// The column sets of the indexes of a table by index name.
+async fn indexes(table: &str, database: &Database, transaction: Arc<dyn Transaction>) -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let sql = match database {
+        Database::PostgreSQL => format!(
+            "SELECT i.relname AS name, a.attname AS \"column\" FROM pg_index x JOIN pg_class t ON t.oid = x.indrelid JOIN pg_class i ON i.oid = x.indexrelid JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = ANY(x.indkey) WHERE t.relname = {} AND pg_table_is_visible(t.oid)",
+            quote(table, database)?,
+        ),
+        Database::MySQL => format!(
+            "SELECT index_name AS name, column_name AS `column` FROM information_schema.statistics WHERE table_schema = DATABASE() AND table_name = {}",
+            quote(table, database)?,
+        ),
+        // an integer primary key is kept in the table itself, not in an index
+        _ => format!(
+            "SELECT l.name AS name, i.name AS \"column\" FROM pragma_index_list({table}) AS l, pragma_index_info(l.name) AS i UNION ALL SELECT 'primary' AS name, name AS \"column\" FROM pragma_table_info({table}) WHERE pk > 0",
+            table = quote(table, database)?,
+        ),
+    };
+    let mut result: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for row in rows(transaction.query_raw(&Value::String(sql)).await?) {
+        let (Some(name), Some(column)) = (string(&row, "name"), string(&row, "column")) else { continue };
+        result.entry(name).or_default().insert(column);
+    }
+    Ok(result)
+}
+
+fn rows(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(rows) => rows,
+        _ => vec![],
+    }
+}
+
+fn string(row: &Value, key: &str) -> Option<String> {
+    row.get(key).or_else(|| row.get(&key.to_uppercase())).and_then(|v| v.as_str()).map(|s| s.to_owned())
+}
+
+/// The family of column types a field is stored in.
+fn field_family(r#type: &Type) -> Option<&'static str> {
+    match r#type {
+        Type::Optional(inner) => field_family(inner),
+        Type::String => Some("text"),
+        Type::Bool => Some("bool"),
+        Type::Int | Type::Int64 => Some("int"),
+        Type::Float32 | Type::Float | Type::Decimal => Some("number"),
+        Type::Date => Some("date"),
+        Type::DateTime => Some("datetime"),
+        _ => None,
+    }
+}
+
+/// The family of a column type as reported by the database.
+fn column_family(column_type: &str) -> Option<&'static str> {
+    let column_type = column_type.to_lowercase();
+    if column_type.starts_with("tinyint(1)") || column_type.starts_with("bool") {
+        Some("bool")
+    } else if column_type.contains("int") || column_type == "serial" || column_type == "bigserial" {
+        Some("int")
+    } else if ["char", "text", "clob", "enum"].iter().any(|t| column_type.contains(t)) {
+        Some("text")
+    } else if ["real", "double", "float", "numeric", "decimal"].iter().any(|t| column_type.contains(t)) {
+        Some("number")
+    } else if column_type.contains("timestamp") || column_type.contains("datetime") {
+        Some("datetime")
+    } else if column_type == "date" {
+        Some("date")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::stdlib::decorators::view::{is_materialized_view, model_view};
+use crate::utils::sql::identifier;
+
+/// Create or replace the view of a view model.
+pub(crate) async fn create_view(transaction: Arc<dyn Transaction>, model: &Model, database: &Database) -> Result<()> {
+    let query = model_view(model).unwrap_or_default();
+    let name = identifier(&model.table_name, database);
+    let statements = if is_materialized_view(model) {
+        [format!("DROP MATERIALIZED VIEW IF EXISTS {}", name), format!("CREATE MATERIALIZED VIEW {} AS {}", name, query)]
+    } else {
+        [format!("DROP VIEW IF EXISTS {}", name), format!("CREATE VIEW {} AS {}", name, query)]
+    };
+    for statement in statements {
+        transaction.query_raw(&Value::String(statement)).await.map_err(|e| {
+            Error::new(format!("cannot create the view of `{}`: {}", model.path().join("."), e.message))
+        })?;
+    }
+    Ok(())
+}
+
+/// Refresh the materialized view of a model.
+pub(crate) async fn refresh_view(model: &Model) -> Result<()> {
+    if !is_materialized_view(model) {
+        Err(Error::invalid_request_message(format!("`{}` is not a materialized view", model.path().join("."))))?
+    }
+    let conn_ctx = Ctx::conn_ctx();
+    for (namespace_path, connection) in conn_ctx.connections_iter() {
+        let Some(namespace) = conn_ctx.namespace().namespace_at_path(&namespace_path.iter().map(AsRef::as_ref).collect()) else { continue };
+        if !namespace.models_under_connector().iter().any(|m| std::ptr::eq(*m, model)) {
+            continue
+        }
+        let transaction = connection.no_transaction().await?;
+        transaction.query_raw(&Value::String(format!("REFRESH MATERIALIZED VIEW {}", identifier(&model.table_name, &Database::PostgreSQL)))).await.map_err(|e| {
+            Error::new(format!("cannot refresh the view of `{}`: {}", model.path().join("."), e.message))
+        })?;
+        return Ok(());
+    }
+    Err(Error::new(format!("no connection is found for `{}`", model.path().join("."))))
+}
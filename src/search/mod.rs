@@ -0,0 +1,138 @@
+use std::time::Duration;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::server::find_by_ids::records_by_ids;
+use crate::stdlib::decorators::search_index::model_search_index;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Index a saved object into the search index of its model.
+pub(crate) async fn index_object(object: &Object) -> Result<()> {
+    let Some(base) = Ctx::search_engine() else { return Ok(()) };
+    let model = object.model();
+    let Some(index) = model_search_index(model) else { return Ok(()) };
+    let mut document = Map::new();
+    for field in model.fields.values() {
+        if mapping_type(field.r#type()).is_none() {
+            continue
+        }
+        document.insert(field.name().to_owned(), JsonValue::try_from(&object.get_value(field.name())?)?);
+    }
+    let url = format!("{}/{}/_doc/{}", base, index, document_id(object)?);
+    request(reqwest::Method::PUT, &url, Some(JsonValue::Object(document))).await?;
+    Ok(())
+}
+
+/// Remove a deleted object from the search index of its model.
+pub(crate) async fn remove_object(object: &Object) -> Result<()> {
+    let Some(base) = Ctx::search_engine() else { return Ok(()) };
+    let Some(index) = model_search_index(object.model()) else { return Ok(()) };
+    let url = format!("{}/{}/_doc/{}", base, index, document_id(object)?);
+    match request(reqwest::Method::DELETE, &url, None).await {
+        Err(err) if err.code == 404 => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
+
+/// Run a search request against the index of a model and respond with the
+/// matching records and the total count. The hits are read back from the
+/// database by their primary keys, so records are serialized and guarded
+/// like `findMany` does and hits of deleted records are left out.
+pub(crate) async fn search(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let base = Ctx::search_engine().ok_or_else(|| Error::new("search engine is not configured"))?;
+    let index = model_search_index(model).ok_or_else(|| Error::not_found())?;
+    let response = request(reqwest::Method::POST, &format!("{}/{}/_search", base, index), Some(body.clone())).await?;
+    let hits = response.get("hits");
+    let documents: Vec<JsonValue> = hits.and_then(|h| h.get("hits")).and_then(|h| h.as_array()).map(|hits| {
+        hits.iter().filter_map(|hit| hit.get("_source").cloned()).collect()
+    }).unwrap_or_default();
+    let total = hits.and_then(|h| h.get("total")).and_then(|t| t.get("value").or(Some(t))).and_then(|t| t.as_u64()).unwrap_or(documents.len() as u64);
+    let keys: Vec<String> = model.primary_index().ok_or_else(|| Error::new(format!("`{}` has no primary key", model.path().join("."))))?
+        .items.iter().map(|item| item.field.clone()).collect();
+    let ids: Vec<JsonValue> = documents.iter().map(|document| {
+        JsonValue::Object(keys.iter().map(|key| (key.clone(), document.get(key).cloned().unwrap_or(JsonValue::Null))).collect())
+    }).collect();
+    let records = records_by_ids(model, &ids, &json!({}), main_namespace, ctx).await?;
+    let records: Vec<Value> = records.into_iter().filter(|record| !record.is_null()).collect();
+    Ok(Response::data_meta(Value::Array(records), teon!({ "count": total as i64 })))
+}
+
+/// Create the indexes of searchable models, or update their mappings if they
+/// exist.
+pub(crate) async fn sync_search_mappings(namespace: &Namespace) -> Result<()> {
+    let Some(base) = Ctx::search_engine() else { return Ok(()) };
+    for model in namespace.models.values() {
+        let Some(index) = model_search_index(model) else { continue };
+        let properties = mapping(model);
+        let url = format!("{}/{}", base, index);
+        if request(reqwest::Method::HEAD, &url, None).await.is_ok() {
+            request(reqwest::Method::PUT, &format!("{}/_mapping", url), Some(json!({ "properties": properties }))).await?;
+        } else {
+            request(reqwest::Method::PUT, &url, Some(json!({ "mappings": { "properties": properties } }))).await?;
+        }
+    }
+    for namespace in namespace.namespaces.values() {
+        Box::pin(sync_search_mappings(namespace)).await?;
+    }
+    Ok(())
+}
+
+/// The index mapping of a model generated from its field types.
+pub(crate) fn mapping(model: &Model) -> JsonValue {
+    let mut properties = Map::new();
+    for field in model.fields.values() {
+        if let Some(r#type) = mapping_type(field.r#type()) {
+            properties.insert(field.name().to_owned(), json!({ "type": r#type }));
+        }
+    }
+    JsonValue::Object(properties)
+}
+
+fn mapping_type(r#type: &Type) -> Option<&'static str> {
+    match r#type {
+        Type::Bool => Some("boolean"),
+        Type::Int => Some("integer"),
+        Type::Int64 => Some("long"),
+        Type::Float32 => Some("float"),
+        Type::Float | Type::Decimal => Some("double"),
+        Type::String => Some("text"),
+        Type::ObjectId | Type::EnumVariant(_) => Some("keyword"),
+        Type::Date | Type::DateTime => Some("date"),
+        Type::Array(inner) | Type::Optional(inner) => mapping_type(inner),
+        _ => None,
+    }
+}
+
+fn document_id(object: &Object) -> Result<String> {
+    let identifier = JsonValue::try_from(&object.identifier())?;
+    let values: Vec<String> = match identifier {
+        JsonValue::Object(map) => map.values().map(|v| v.as_str().map(|s| s.to_owned()).unwrap_or_else(|| v.to_string())).collect(),
+        value => vec![value.to_string()],
+    };
+    Ok(url::form_urlencoded::byte_serialize(values.join("-").as_bytes()).collect())
+}
+
+async fn request(method: reqwest::Method, url: &str, body: Option<JsonValue>) -> Result<JsonValue> {
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build().map_err(|e| Error::new(format!("search engine: {}", e)))?;
+    let mut request = client.request(method, url);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request.send().await.map_err(|e| Error::new(format!("search engine: {}", e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        let mut error = Error::new(format!("search engine: {} responds with {}", url, status));
+        error.code = status.as_u16();
+        Err(error)?
+    }
+    Ok(response.json().await.unwrap_or(JsonValue::Null))
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use chrono::{Duration, Utc};
+use itertools::Itertools;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::Field;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::traits::named::Named;
+use teo_runtime::teon;
+use crate::prelude::Value;
+use crate::stdlib::decorators::dimensions::field_dimensions;
+
+const FIRST_NAMES: [&str; 12] = ["Alice", "Bob", "Carol", "David", "Emma", "Frank", "Grace", "Henry", "Iris", "Jack", "Kate", "Leo"];
+const LAST_NAMES: [&str; 10] = ["Smith", "Johnson", "Brown", "Taylor", "Miller", "Wilson", "Moore", "Clark", "Lewis", "Walker"];
+const WORDS: [&str; 16] = ["lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do", "eiusmod", "tempor", "incididunt", "ut", "labore", "magna"];
+const DOMAINS: [&str; 4] = ["example.com", "example.org", "example.net", "test.dev"];
+
+/// Insert random records into every model. `counts` overrides `count` for
+/// some models, keyed by model path. The same `seed` generates the same
+/// records.
+pub(crate) async fn fake(count: usize, counts: &BTreeMap<String, usize>, seed: Option<u64>, ctx: transaction::Ctx) -> Result<()> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut models = vec![];
+    collect_models(ctx.namespace(), &mut models);
+    let mut created: BTreeMap<String, Vec<Object>> = BTreeMap::new();
+    for model in ordered_models(models)? {
+        let key = model.path().join(".");
+        let model_count = counts.get(&key).copied().unwrap_or(count);
+        let mut objects = vec![];
+        for _ in 0..model_count {
+            let mut input = teon!({});
+            for field in model.fields.values() {
+                if field.auto || field.auto_increment || field.foreign_key || field.default.is_some() {
+                    continue
+                }
+                if field.is_optional() && rng.gen_bool(0.2) {
+                    continue
+                }
+                input.as_dictionary_mut().unwrap().insert(field.name().to_owned(), fake_value(field, field.r#type(), ctx.namespace(), &mut rng));
+            }
+            for relation in model.relations().into_iter().filter(|r| r.has_foreign_key) {
+                let related = created.get(&relation.model_path().join(".")).and_then(|objects| objects.choose(&mut rng));
+                let Some(related) = related else {
+                    if relation.is_required() {
+                        Err(Error::new(format!("fake: no records of `{}` to relate to", relation.model_path().join("."))))?
+                    }
+                    continue
+                };
+                for (field, reference) in relation.iter() {
+                    input.as_dictionary_mut().unwrap().insert(field.to_owned(), related.get_value(reference)?);
+                }
+            }
+            let object = ctx.create_object(model, &input, None).await?;
+            object.save().await.map_err(|e| Error::new(format!("fake: cannot create `{}`: {}", key, e.message)))?;
+            objects.push(object);
+        }
+        created.insert(key, objects);
+    }
+    Ok(())
+}
+
+fn collect_models<'a>(namespace: &'a Namespace, result: &mut Vec<&'a Model>) {
+    result.extend(namespace.models.values());
+    for child in namespace.namespaces.values() {
+        collect_models(child, result);
+    }
+}
+
+/// Order models so that the targets of required relations come first.
+fn ordered_models(models: Vec<&Model>) -> Result<Vec<&Model>> {
+    let mut pending = models;
+    let mut result: Vec<&Model> = vec![];
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<&Model>, Vec<&Model>) = pending.into_iter().partition(|model| {
+            model.relations().iter().filter(|r| r.has_foreign_key && r.is_required()).all(|r| {
+                r.model_path() == model.path() || result.iter().any(|m| m.path() == r.model_path())
+            })
+        });
+        if ready.is_empty() {
+            Err(Error::new(format!("fake: circular required relationship between these models: `{}`", rest.iter().map(|m| m.path().join(".")).join(","))))?
+        }
+        result.extend(ready);
+        pending = rest;
+    }
+    Ok(result)
+}
+
+/// The length limits of a string field declared with `$minLength`,
+/// `$maxLength` and `$length`.
+fn length_limits(field: &Field) -> (usize, usize) {
+    let mut min = 0;
+    let mut max = usize::MAX;
+    for item in &field.on_set.items {
+        match item.path.last().map(|s| s.as_str()) {
+            Some("minLength") => if let Ok(len) = item.arguments.get::<usize>("len") { min = len },
+            Some("maxLength") => if let Ok(len) = item.arguments.get::<usize>("len") { max = len },
+            Some("length") => if let Ok(len) = item.arguments.get::<usize>("len") { min = len; max = len },
+            _ => (),
+        }
+    }
+    (min, max)
+}
+
+fn has_validator(field: &Field, name: &str) -> bool {
+    field.on_set.items.iter().any(|item| item.path.last().map(|s| s.as_str()) == Some(name))
+}
+
+/// A random value of type `t` for a field.
+pub(crate) fn fake_value(field: &Field, t: &Type, namespace: &Namespace, rng: &mut StdRng) -> Value {
+    match t {
+        Type::Optional(inner) => fake_value(field, inner, namespace, rng),
+        Type::Bool => Value::Bool(rng.gen()),
+        Type::Int => Value::Int(rng.gen_range(0..1000)),
+        Type::Int64 => Value::Int64(rng.gen_range(0..100_000)),
+        Type::Float32 => Value::Float32(rng.gen_range(0.0..1000.0)),
+        Type::Float => Value::Float((rng.gen_range(0.0..1000.0f64) * 100.0).round() / 100.0),
+        Type::Date => Value::Date((Utc::now() - Duration::days(rng.gen_range(0..365))).date_naive()),
+        Type::DateTime => Value::DateTime(Utc::now() - Duration::seconds(rng.gen_range(0..31_536_000))),
+        Type::String => Value::String(fake_string(field, rng)),
+        Type::Array(inner) => {
+            // vectors have the length of their dimensions
+            let len = field_dimensions(field).map_or_else(|| rng.gen_range(1..4), |dimensions| dimensions as usize);
+            Value::Array((0..len).map(|_| fake_value(field, inner, namespace, rng)).collect())
+        }
+        Type::EnumVariant(reference) => {
+            let members: Vec<String> = namespace.enum_at_path(&reference.str_path()).map(|e| e.members().iter().map(|m| m.name().to_owned()).collect()).unwrap_or_default();
+            members.choose(rng).map(|m| Value::String(m.clone())).unwrap_or(Value::Null)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn fake_string(field: &Field, rng: &mut StdRng) -> String {
+    let name = field.name().to_lowercase();
+    let first = *FIRST_NAMES.choose(rng).unwrap();
+    let last = *LAST_NAMES.choose(rng).unwrap();
+    let value = if has_validator(field, "isEmail") || name.contains("email") {
+        // a number keeps emails unique
+        format!("{}.{}{}@{}", first.to_lowercase(), last.to_lowercase(), rng.gen_range(1..100_000), DOMAINS.choose(rng).unwrap())
+    } else if has_validator(field, "isURL") || name.contains("url") || name.contains("website") {
+        format!("https://{}/{}", DOMAINS.choose(rng).unwrap(), WORDS.choose(rng).unwrap())
+    } else if name.contains("name") {
+        format!("{} {}", first, last)
+    } else {
+        (0..rng.gen_range(2..8)).map(|_| *WORDS.choose(rng).unwrap()).join(" ")
+    };
+    let (min, max) = length_limits(field);
+    let mut value = value;
+    while value.chars().count() < min {
+        value.push_str(WORDS.choose(rng).unwrap());
+    }
+    value.chars().take(max).collect()
+}
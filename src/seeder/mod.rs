@@ -1,2 +1,3 @@
+pub(crate) mod fake;
 pub(crate) mod seed;
 pub(crate) mod models;
\ No newline at end of file
@@ -9,7 +9,7 @@ use crate::seeder::models::data_set_record::DataSetRecord;
 use crate::seeder::models::data_set_relation::DataSetRelation;
 use teo_runtime::teon;
 use crate::cli::command::SeedCommandAction;
-use teo_result::Result;
+use teo_result::{Error, Result};
 use teo_runtime::connection::transaction;
 use teo_runtime::data_set::{DataSet, Group, Record};
 use teo_runtime::model::field::is_optional::IsOptional;
@@ -21,12 +21,12 @@ pub(crate) async fn seed(action: SeedCommandAction, datasets: Vec<DataSet>, ctx:
     // seed for user
     for dataset in &datasets {
         match action {
-            SeedCommandAction::Seed => seed_dataset(dataset, ctx.clone()).await,
-            SeedCommandAction::Reseed => reseed_dataset(dataset, ctx.clone()).await,
-            SeedCommandAction::Unseed => unseed_dataset(dataset, ctx.clone()).await,
+            SeedCommandAction::Seed => seed_dataset(dataset, ctx.clone()).await?,
+            SeedCommandAction::Reseed => reseed_dataset(dataset, ctx.clone()).await?,
+            SeedCommandAction::Unseed => unseed_dataset(dataset, ctx.clone()).await?,
         }
     }
-    remove_user_deleted_dataset_records_and_relations(&datasets, ctx).await;
+    remove_user_deleted_dataset_records_and_relations(&datasets, ctx).await?;
     if exit {
         std::process::exit(0);
     } else {
@@ -34,24 +34,24 @@ pub(crate) async fn seed(action: SeedCommandAction, datasets: Vec<DataSet>, ctx:
     }
 }
 
-pub(crate) async fn seed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
-    let ordered_groups = ordered_group(&dataset.groups, ctx.clone());
+pub(crate) async fn seed_dataset(dataset: &DataSet, ctx: transaction::Ctx) -> Result<()> {
+    let ordered_groups = ordered_group(&dataset.groups, ctx.clone())?;
     // newly added records, we only update reference and relationships for these records.
     let mut added_records: IndexMap<String, Vec<String>> = indexmap!{};
     // First, insert into database with required foreign key relations
     for group in &ordered_groups {
-        let group_model = ctx.namespace().model_at_path(&group.model_path()).unwrap();
+        let group_model = model_at_path(&ctx, &group.model_path())?;
         let mut added_names = vec![];
         let seed_records = DataSetRecord::find_many(teon!({
             "where": {
                 "group": group.name.join(".").as_str(),
                 "dataSet": dataset.name.join(".").as_str(),
             }
-        }), ctx.clone()).await.unwrap();
+        }), ctx.clone()).await?;
         for record in group.records.iter() {
             let existing = seed_records.iter().find(|r| &r.name() == &record.name).is_some();
             if !existing {
-                perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await;
+                perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await?;
                 added_names.push(record.name.clone());
             }
         }
@@ -60,17 +60,18 @@ pub(crate) async fn seed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
         for seed_record in seed_records.iter() {
             let existing = group.records.iter().find(|r| &r.name == &seed_record.name()).is_some();
             if !existing {
-                perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await;
+                perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await?;
             }
         }
     }
     // Second, setup optional relations and array relations
-    setup_new_relations(dataset, &ordered_groups, Some(&added_records), ctx.clone()).await;
+    setup_new_relations(dataset, &ordered_groups, Some(&added_records), ctx.clone()).await?;
     // Last, remove records for user removed groups
-    remove_records_for_user_removed_groups(dataset, &ordered_groups, ctx.clone()).await;
+    remove_records_for_user_removed_groups(dataset, &ordered_groups, ctx.clone()).await?;
+    Ok(())
 }
 
-async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: transaction::Ctx) {
+async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: transaction::Ctx) -> Result<()> {
     let user_removed_seed_records_for_group = DataSetRecord::find_many(teon!({
         "where": {
             "dataSet": dataset.name.join(".").as_str(),
@@ -78,14 +79,14 @@ async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_group
                 "notIn": Value::Array(ordered_groups.iter().map(|g| Value::String(g.name.join("."))).collect()),
             },
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     for record in user_removed_seed_records_for_group {
         let model = ctx.namespace().model_at_path(&record.group().iter().map(AsRef::as_ref).collect());
-        if model.is_some() {
-            perform_remove_from_database(dataset, &record, model.unwrap(), ctx.clone()).await;
+        if let Some(model) = model {
+            perform_remove_from_database(dataset, &record, model, ctx.clone()).await?;
         } else {
             // this table is already dropped
-            record.delete().await.unwrap();
+            record.delete().await?;
         }
     }
     let user_removed_seed_relations_for_group = DataSetRelation::find_many(teon!({
@@ -105,7 +106,7 @@ async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_group
                 }
             ]
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     for relation in user_removed_seed_relations_for_group {
         let group_a_string = relation.group_a();
         let group_b_string = relation.group_b();
@@ -114,46 +115,48 @@ async fn remove_records_for_user_removed_groups(dataset: &DataSet, ordered_group
         let model_a = ctx.namespace().model_at_path(&group_a);
         let model_b = ctx.namespace().model_at_path(&group_b);
         if model_a.is_none() || model_b.is_none() {
-            relation.delete().await.unwrap();
+            relation.delete().await?;
         }
     }
+    Ok(())
 }
 
-pub(crate) async fn reseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
-    let ordered_groups = ordered_group(&dataset.groups, ctx.clone());
+pub(crate) async fn reseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) -> Result<()> {
+    let ordered_groups = ordered_group(&dataset.groups, ctx.clone())?;
     for group in &ordered_groups {
-        let group_model = ctx.namespace().model_at_path(&group.model_path()).unwrap();
+        let group_model = model_at_path(&ctx, &group.model_path())?;
         let seed_records = DataSetRecord::find_many(teon!({
             "where": {
                 "group": group.name.join(".").as_str(),
                 "dataSet": dataset.name.join(".").as_str(),
             }
-        }), ctx.clone()).await.unwrap();
+        }), ctx.clone()).await?;
         for record in group.records.iter() {
             if let Some(seed_record) = seed_records.iter().find(|r| &r.name() == &record.name) {
                 // recreate or update
-                perform_recreate_or_update_an_record(dataset, group, record, group_model, seed_record, ctx.clone()).await;
+                perform_recreate_or_update_an_record(dataset, group, record, group_model, seed_record, ctx.clone()).await?;
             } else {
                 // create
-                perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await;
+                perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await?;
             }
         }
         // delete records which are not recorded in user dataset
         for seed_record in seed_records.iter() {
             let existing = group.records.iter().find(|r| &r.name == &seed_record.name()).is_some();
             if !existing {
-                perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await;
+                perform_remove_from_database(dataset, seed_record, group_model, ctx.clone()).await?;
             }
         }
     }
     // Second, setup optional relations and array relations
-    sync_relations(dataset, &ordered_groups, ctx.clone()).await;
+    sync_relations(dataset, &ordered_groups, ctx.clone()).await?;
     // Last, remove records for user removed groups
-    remove_records_for_user_removed_groups(dataset, &ordered_groups, ctx.clone()).await;
+    remove_records_for_user_removed_groups(dataset, &ordered_groups, ctx.clone()).await?;
+    Ok(())
 }
 
-pub(crate) async fn unseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
-    let mut ordered_groups = ordered_group(&dataset.groups, ctx.clone());
+pub(crate) async fn unseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) -> Result<()> {
+    let mut ordered_groups = ordered_group(&dataset.groups, ctx.clone())?;
     ordered_groups.reverse();
     for group in ordered_groups {
         let seed_records = DataSetRecord::find_many(teon!({
@@ -161,18 +164,19 @@ pub(crate) async fn unseed_dataset(dataset: &DataSet, ctx: transaction::Ctx) {
                 "group": group.name.join(".").as_str(),
                 "dataSet": dataset.name.join(".").as_str(),
             }
-        }), ctx.clone()).await.unwrap();
+        }), ctx.clone()).await?;
         // delete records
         for seed_record in seed_records.iter() {
-            let model = ctx.namespace().model_at_path(&seed_record.group().iter().map(AsRef::as_ref).collect()).unwrap();
-            perform_remove_from_database(dataset, seed_record, model, ctx.clone()).await;
+            let model = model_at_path(&ctx, &seed_record.group().iter().map(AsRef::as_ref).collect())?;
+            perform_remove_from_database(dataset, seed_record, model, ctx.clone()).await?;
         }
     }
+    Ok(())
 }
 
-async fn sync_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: transaction::Ctx) {
+async fn sync_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: transaction::Ctx) -> Result<()> {
     for group in ordered_groups {
-        let group_model = ctx.namespace().model_at_path(&group.model_path()).unwrap();
+        let group_model = model_at_path(&ctx, &group.model_path())?;
         let should_process = group_model.relations().iter().find(|r| !(r.has_foreign_key && r.is_required())).is_some();
         if !should_process { continue }
         let seed_records = DataSetRecord::find_many(teon!({
@@ -180,12 +184,12 @@ async fn sync_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: tr
                 "group": group.name.join(".").as_str(),
                 "dataSet": dataset.name.join(".").as_str(),
             }
-        }), ctx.clone()).await.unwrap();
+        }), ctx.clone()).await?;
         for record in group.records.iter() {
-            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).unwrap();
+            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).ok_or_else(|| record_not_found(&group.name.join("."), &record.name))?;
             let object: Object = ctx.find_unique(group_model, &teon!({
-                "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)
-            }), None, path![]).await.unwrap().unwrap();
+                "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)?
+            }), None, path![]).await?.ok_or_else(|| record_not_found(&group.name.join("."), &record.name))?;
             for relation in group_model.relations() {
                 // find relations
                 let relation_records = DataSetRelation::find_many(teon!({
@@ -207,30 +211,31 @@ async fn sync_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, ctx: tr
                             }
                         ]
                     }
-                }), ctx.clone()).await.unwrap();
+                }), ctx.clone()).await?;
                 let mut relation_record_refs: Vec<&DataSetRelation> = relation_records.iter().collect();
-                if let Some(reference) = record.value.as_dictionary().unwrap().get(relation.name()) {
+                if let Some(reference) = record_value(record)?.get(relation.name()) {
                     if let Some(references) = reference.as_array() {
                         for reference in references {
-                            sync_relation_internal(record, reference, relation, dataset, &object, &relation_records, &mut relation_record_refs, ctx.clone()).await;
+                            sync_relation_internal(record, reference, relation, dataset, &object, &relation_records, &mut relation_record_refs, ctx.clone()).await?;
                         }
                     } else {
-                        sync_relation_internal(record, reference, relation, dataset, &object, &relation_records, &mut relation_record_refs, ctx.clone()).await;
+                        sync_relation_internal(record, reference, relation, dataset, &object, &relation_records, &mut relation_record_refs, ctx.clone()).await?;
                     }
                 } else {
                     // find relations and cut
                     for relation_record in relation_record_refs {
-                        cut_relation(relation_record, seed_record, group_model, dataset, &object, ctx.clone()).await;
+                        cut_relation(relation_record, seed_record, group_model, dataset, &object, ctx.clone()).await?;
                     }
                 }
             }
         }
     }
+    Ok(())
 }
 
-async fn setup_new_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, limit: Option<&IndexMap<String, Vec<String>>>, ctx: transaction::Ctx) {
+async fn setup_new_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, limit: Option<&IndexMap<String, Vec<String>>>, ctx: transaction::Ctx) -> Result<()> {
     for group in ordered_groups {
-        let group_model = ctx.namespace().model_at_path(&group.model_path()).unwrap();
+        let group_model = model_at_path(&ctx, &group.model_path())?;
         let should_process = group_model.relations().iter().find(|r| !(r.has_foreign_key && r.is_required())).is_some();
         if !should_process { continue }
         let seed_records = DataSetRecord::find_many(teon!({
@@ -238,80 +243,86 @@ async fn setup_new_relations(dataset: &DataSet, ordered_groups: &Vec<&Group>, li
                 "group": group.name.join(".").as_str(),
                 "dataSet": dataset.name.join(".").as_str(),
             }
-        }), ctx.clone()).await.unwrap();
+        }), ctx.clone()).await?;
         for record in group.records.iter() {
-            if !(limit.is_none() || limit.unwrap().get(&group.name.join(".")).unwrap().contains(&record.name)) { continue }
-            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).unwrap();
+            if let Some(limit) = limit {
+                if !limit.get(&group.name.join(".")).map_or(false, |names| names.contains(&record.name)) { continue }
+            }
+            let seed_record = seed_records.iter().find(|o| o.name().as_str() == &record.name).ok_or_else(|| record_not_found(&group.name.join("."), &record.name))?;
             let object: Object = ctx.find_unique(group_model, &teon!({
-                "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)
-            }), None, path![]).await.unwrap().unwrap();
+                "where": record_json_string_to_where_unique(seed_record.record().as_str(), group_model)?
+            }), None, path![]).await?.ok_or_else(|| record_not_found(&group.name.join("."), &record.name))?;
             for relation in group_model.relations() {
-                if let Some(reference) = record.value.as_dictionary().unwrap().get(relation.name()) {
+                if let Some(reference) = record_value(record)?.get(relation.name()) {
                     if let Some(references) = reference.as_array() {
                         for reference in references {
-                            setup_relations_internal(record, reference, relation, dataset, &object, ctx.clone()).await;
+                            setup_relations_internal(record, reference, relation, dataset, &object, ctx.clone()).await?;
                         }
                     } else {
-                        setup_relations_internal(record, reference, relation, dataset, &object, ctx.clone()).await;
+                        setup_relations_internal(record, reference, relation, dataset, &object, ctx.clone()).await?;
                     }
                 }
             }
         }
     }
+    Ok(())
 }
 
-async fn sync_relation_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, relation_records: &'a Vec<DataSetRelation>, relation_record_refs: &mut Vec<&'a DataSetRelation>, ctx: transaction::Ctx) {
-    let that_name = reference.as_str().unwrap().to_string();
+async fn sync_relation_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, relation_records: &'a Vec<DataSetRelation>, relation_record_refs: &mut Vec<&'a DataSetRelation>, ctx: transaction::Ctx) -> Result<()> {
+    let that_name = reference_name(reference)?;
     if let Some(existing_relation_record) = relation_records.iter().find(|r| {
         (&r.name_a() == record.name.as_str() && r.name_b() == that_name) ||
             (&r.name_b() == record.name.as_str() && r.name_a() == that_name)
     }) {
-        let index = relation_record_refs.iter().position(|r| *r == existing_relation_record).unwrap();
-        relation_record_refs.remove(index);
+        if let Some(index) = relation_record_refs.iter().position(|r| *r == existing_relation_record) {
+            relation_record_refs.remove(index);
+        }
     }
-    setup_relations_internal(record, reference, relation, dataset, object, ctx.clone()).await;
+    setup_relations_internal(record, reference, relation, dataset, object, ctx.clone()).await
 }
 
-async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, ctx: transaction::Ctx) {
-    let that_name = reference.as_str().unwrap().to_string();
+async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, relation: &'static Relation, dataset: &DataSet, object: &'a Object, ctx: transaction::Ctx) -> Result<()> {
+    let that_name = reference_name(reference)?;
+    let that_group = relation.model_path().join(".");
     let that_seed_record = DataSetRecord::find_first(teon!({
         "where": {
-            "group": relation.model_path().join("."),
+            "group": that_group.as_str(),
             "dataSet": dataset.name.join(".").as_str(),
             "name": that_name.clone(),
         }
-    }), ctx.clone()).await.unwrap().unwrap();
-    let that_object: Object = ctx.find_unique(ctx.namespace().model_at_path(&relation.model_path()).unwrap(), &teon!({
-        "where": record_json_string_to_where_unique(that_seed_record.record(), ctx.namespace().model_at_path(&relation.model_path()).unwrap())
-    }), None, path![]).await.unwrap().unwrap();
+    }), ctx.clone()).await?.ok_or_else(|| record_not_found(&that_group, &that_name))?;
+    let that_model = model_at_path(&ctx, &relation.model_path())?;
+    let that_object: Object = ctx.find_unique(that_model, &teon!({
+        "where": record_json_string_to_where_unique(that_seed_record.record(), that_model)?
+    }), None, path![]).await?.ok_or_else(|| record_not_found(&that_group, &that_name))?;
     if relation.is_optional() && relation.has_foreign_key {
         // update this record
         for (local, foreign) in relation.iter() {
-            object.set_value(local, that_object.get_value(foreign).unwrap()).unwrap();
+            object.set_value(local, that_object.get_value(foreign)?)?;
         }
-        object.save_for_seed_without_required_relation().await.unwrap();
+        object.save_for_seed_without_required_relation().await?;
     } else if !relation.has_join_table() {
         // update that record
         for (local, foreign) in relation.iter() {
-            that_object.set_value(foreign, object.get_value(local).unwrap()).unwrap();
+            that_object.set_value(foreign, object.get_value(local)?)?;
         }
-        that_object.save_for_seed_without_required_relation().await.unwrap();
+        that_object.save_for_seed_without_required_relation().await?;
     } else {
         let (through_model, through_relation) = ctx.namespace().through_relation(relation);
         let (_, through_that_relation) = ctx.namespace().through_opposite_relation(relation);
         let mut where_unique: IndexMap<String, Value> = IndexMap::new();
         for (local, foreign) in through_relation.iter() {
-            where_unique.insert(local.to_string(), object.get_value(foreign).unwrap());
+            where_unique.insert(local.to_string(), object.get_value(foreign)?);
         }
         for (local, foreign) in through_that_relation.iter() {
-            where_unique.insert(local.to_string(), that_object.get_value(foreign).unwrap());
+            where_unique.insert(local.to_string(), that_object.get_value(foreign)?);
         }
         let link_record: Option<Object> = ctx.find_first(through_model, &teon!({
             "where": Value::Dictionary(where_unique.clone())
-        }), None, path![]).await.unwrap();
+        }), None, path![]).await?;
         if link_record.is_none() {
-            let link_object = ctx.create_object(through_model, Value::Dictionary(where_unique), None).await.unwrap();
-            link_object.save_for_seed_without_required_relation().await.unwrap();
+            let link_object = ctx.create_object(through_model, Value::Dictionary(where_unique), None).await?;
+            link_object.save_for_seed_without_required_relation().await?;
         }
     }
     // update relation record
@@ -336,7 +347,7 @@ async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, rel
                 }
             ]
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     if exist_relation_record.is_none() {
         // not exist, create
         let that_relation = ctx.namespace().opposite_relation(relation).1;
@@ -348,24 +359,24 @@ async fn setup_relations_internal<'a>(record: &Record, reference: &'a Value, rel
             "groupB": that_object.model().path().join("."),
             "relationB": if that_relation.is_some() { Value::String(that_relation.unwrap().name().to_owned()) } else { Value::Null },
             "nameB": that_name.clone(),
-        }), ctx.clone()).await.unwrap();
-        new_relation_record.save().await.unwrap();
+        }), ctx.clone()).await?;
+        new_relation_record.save().await?;
     }
+    Ok(())
 }
 
 /// This perform, deletes an object from the database.
-async fn perform_remove_from_database<'a>(dataset: &DataSet, record: &'a DataSetRecord, group_model: &'static Model, ctx: transaction::Ctx) {
+async fn perform_remove_from_database<'a>(dataset: &DataSet, record: &'a DataSetRecord, group_model: &'static Model, ctx: transaction::Ctx) -> Result<()> {
     let json_identifier = record.record();
     let exist: Option<Object> = ctx.find_unique(group_model, &teon!({
-        "where": record_json_string_to_where_unique(json_identifier, group_model)
-    }), None, path![]).await.unwrap();
-    if exist.is_none() {
+        "where": record_json_string_to_where_unique(json_identifier, group_model)?
+    }), None, path![]).await?;
+    let Some(exist) = exist else {
         // This record doesn't exist, cannot delete it or cut its relationships
-        record.delete().await.unwrap();
-        return
-    }
+        record.delete().await?;
+        return Ok(());
+    };
     // First, cut relations
-    let exist = exist.unwrap();
     let relations = DataSetRelation::find_many(teon!({
         "where": {
             "OR": [
@@ -381,27 +392,29 @@ async fn perform_remove_from_database<'a>(dataset: &DataSet, record: &'a DataSet
                 }
             ]
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     for relation in relations {
-        cut_relation(&relation, record, group_model, dataset, &exist, ctx.clone()).await;
+        cut_relation(&relation, record, group_model, dataset, &exist, ctx.clone()).await?;
     }
     // Second, delete it and the seed record
-    exist.delete().await.unwrap();
-    record.delete().await.unwrap();
+    exist.delete().await?;
+    record.delete().await
 }
 
-async fn cut_relation<'a>(relation: &'a DataSetRelation, record: &'a DataSetRecord, group_model: &'static Model, dataset: &DataSet, exist: &'a Object, ctx: transaction::Ctx) {
+async fn cut_relation<'a>(relation: &'a DataSetRelation, record: &'a DataSetRecord, group_model: &'static Model, dataset: &DataSet, exist: &'a Object, ctx: transaction::Ctx) -> Result<()> {
     let rel_name = if record.group().join(".").as_str() == relation.group_a() { relation.relation_a() } else { relation.relation_b() };
-    let model_relation = group_model.relation(&rel_name).unwrap();
+    let Some(model_relation) = group_model.relation(&rel_name) else {
+        // This relation is removed from the model
+        return relation.delete().await;
+    };
     if model_relation.has_foreign_key {
         // If has foreign keys, this relation is already cut
-        relation.delete().await.unwrap();
-        return
+        return relation.delete().await;
     }
     // get that record
     let that_model_name = if record.group().join(".").as_str() == relation.group_a() { relation.group_b() } else { relation.group_a() };
     let that_model_path: Vec<String> = that_model_name.split(".").map(|s| s.to_string()).collect();
-    let that_model = ctx.namespace().model_at_path(&that_model_path.iter().map(|s| s.as_str()).collect()).unwrap();
+    let that_model = model_at_path(&ctx, &that_model_path.iter().map(|s| s.as_str()).collect())?;
     let that_name = if record.group().join(".").as_str() == relation.group_a() { relation.name_b() } else { relation.name_a() };
     let that_record_record = DataSetRecord::find_first(teon!({
         "where": {
@@ -409,99 +422,94 @@ async fn cut_relation<'a>(relation: &'a DataSetRelation, record: &'a DataSetReco
             "group": that_model_name.as_str(),
             "name": that_name.as_str()
         }
-    }), ctx.clone()).await.unwrap().unwrap();
+    }), ctx.clone()).await?.ok_or_else(|| record_not_found(&that_model_name, &that_name))?;
     let identifier = that_record_record.record();
-    let that_record_where_unique = record_json_string_to_where_unique(&identifier, that_model);
+    let that_record_where_unique = record_json_string_to_where_unique(&identifier, that_model)?;
     let that_record: Option<Object> = ctx.find_unique(that_model, &teon!({
             "where": that_record_where_unique
-        }), None, path![]).await.unwrap();
-    if that_record.is_none() {
-        relation.delete().await.unwrap();
-        return
-    }
-    let that_record = that_record.unwrap();
+        }), None, path![]).await?;
+    let Some(that_record) = that_record else {
+        return relation.delete().await;
+    };
     if model_relation.has_join_table() {
         let (through_model, through_relation) = ctx.namespace().through_relation(model_relation);
         let (_, through_that_relation) = ctx.namespace().through_opposite_relation(model_relation);
         let mut where_unique: IndexMap<String, Value> = IndexMap::new();
         for (local, foreign) in through_relation.iter() {
-            where_unique.insert(local.to_string(), exist.get_value(foreign).unwrap());
+            where_unique.insert(local.to_string(), exist.get_value(foreign)?);
         }
         for (local, foreign) in through_that_relation.iter() {
-            where_unique.insert(local.to_string(), that_record.get_value(foreign).unwrap());
+            where_unique.insert(local.to_string(), that_record.get_value(foreign)?);
         }
         let link_record: Option<Object> = ctx.find_first(through_model, &teon!({
             "where": Value::Dictionary(where_unique)
-        }), None, path![]).await.unwrap();
-        if link_record.is_none() {
+        }), None, path![]).await?;
+        let Some(link_record) = link_record else {
             // Maybe this record is deleted already
-            relation.delete().await.unwrap();
-            return
-        }
-        let link_record = link_record.unwrap();
-        link_record.delete().await.unwrap();
+            return relation.delete().await;
+        };
+        link_record.delete().await?;
     } else {
         let mut link_to_self = true;
         for (local, foreign) in model_relation.iter() {
-            if that_record.get_value(foreign).unwrap() != exist.get_value(local).unwrap() {
+            if that_record.get_value(foreign)? != exist.get_value(local)? {
                 link_to_self = false;
             }
         }
         if link_to_self {
             // nullify
             for (_local, foreign) in model_relation.iter() {
-                that_record.set_value(foreign, Value::Null).unwrap();
+                that_record.set_value(foreign, Value::Null)?;
             }
-            that_record.save_for_seed_without_required_relation().await.unwrap();
+            that_record.save_for_seed_without_required_relation().await?;
         }
     }
-    relation.delete().await.unwrap();
+    relation.delete().await
 }
 
-async fn perform_recreate_or_update_an_record<'a>(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, seed_record: &'a DataSetRecord, ctx: transaction::Ctx) {
+async fn perform_recreate_or_update_an_record<'a>(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, seed_record: &'a DataSetRecord, ctx: transaction::Ctx) -> Result<()> {
     let object: Option<Object> = ctx.find_unique(group_model, &teon!({
-        "where": record_json_string_to_where_unique(seed_record.record(), group_model)
-    }), None, path![]).await.unwrap();
-    if object.is_none() {
-        seed_record.delete().await.unwrap();
-        perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await;
-        return
-    }
-    let object = object.unwrap();
-    let input = insert_or_update_input(dataset, group, record, group_model, ctx.clone()).await;
-    object.set_teon(&input).await.unwrap();
-    object.save_for_seed_without_required_relation().await.unwrap();
+        "where": record_json_string_to_where_unique(seed_record.record(), group_model)?
+    }), None, path![]).await?;
+    let Some(object) = object else {
+        seed_record.delete().await?;
+        return perform_insert_into_database(dataset, group, record, group_model, ctx.clone()).await;
+    };
+    let input = insert_or_update_input(dataset, group, record, group_model, ctx.clone()).await?;
+    object.set_teon(&input).await?;
+    object.save_for_seed_without_required_relation().await?;
     seed_record.set_record(object_identifier_in_json(&object));
-    seed_record.save().await.unwrap();
+    seed_record.save().await
 }
 
-async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, ctx: transaction::Ctx) -> Value {
+async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, ctx: transaction::Ctx) -> Result<Value> {
     let mut input = teon!({});
     // nullify exist relations and reset
     for field in group_model.fields.values().filter(|f| f.foreign_key) {
         input.as_dictionary_mut().unwrap().insert(field.name().to_owned(), Value::Null);
     }
-    for (k, v) in record.value.as_dictionary().unwrap() {
+    for (k, v) in record_value(record)? {
         if group_model.field(k).is_some() {
             input.as_dictionary_mut().unwrap().insert(k.to_owned(), v.clone());
         } else if let Some(relation) = group_model.relation(k) {
             if relation.is_required() && relation.has_foreign_key {
                 // setup required relationship
-                let that_record_name = v.as_str().unwrap().to_string();
+                let that_record_name = reference_name(v)?;
+                let that_group = relation.model_path().join(".");
                 let that_record_data = DataSetRecord::find_first(teon!({
                     "where": {
-                        "group": relation.model_path().join("."),
+                        "group": that_group.as_str(),
                         "dataSet": dataset.name.join(".").as_str(),
-                        "name": that_record_name,
+                        "name": that_record_name.as_str(),
                     }
-                }), ctx.clone()).await.unwrap().unwrap();
+                }), ctx.clone()).await?.ok_or_else(|| record_not_found(&that_group, &that_record_name))?;
                 let that_record_identifier_json = that_record_data.record();
-                let relation_model = ctx.namespace().model_at_path(&relation.model_path()).unwrap();
+                let relation_model = model_at_path(&ctx, &relation.model_path())?;
                 let that_record: Object = ctx.find_unique(relation_model, &teon!({
-                    "where": record_json_string_to_where_unique(&that_record_identifier_json, ctx.namespace().model_at_path(&relation.model_path()).unwrap())
-                }), None, path![]).await.unwrap().unwrap();
+                    "where": record_json_string_to_where_unique(&that_record_identifier_json, relation_model)?
+                }), None, path![]).await?.ok_or_else(|| record_not_found(&that_group, &that_record_name))?;
                 for (field, reference) in relation.iter() {
-                    input.as_dictionary_mut().unwrap().insert(field.to_owned(), that_record.get_value(reference).unwrap());
+                    input.as_dictionary_mut().unwrap().insert(field.to_owned(), that_record.get_value(reference)?);
                 }
                 // update relation record
                 let (_, opposite_relation) = ctx.namespace().opposite_relation(relation);
@@ -514,7 +522,7 @@ async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Recor
                                 "relationA": relation.name(),
                                 "nameA": record.name.as_str(),
                                 "groupB": that_record.model().path().join("."),
-                                "nameB": v.as_str().unwrap().to_string(),
+                                "nameB": that_record_name.as_str(),
                             },
                             {
                                 "dataSet": dataset.name.join(".").as_str(),
@@ -522,11 +530,11 @@ async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Recor
                                 "relationB": relation.name(),
                                 "nameB": record.name.as_str(),
                                 "groupA": that_record.model().path().join("."),
-                                "nameA": v.as_str().unwrap().to_string()
+                                "nameA": that_record_name.as_str()
                             }
                         ]
                     }
-                }), ctx.clone()).await.unwrap();
+                }), ctx.clone()).await?;
                 if exist_relation_record.is_none() {
                     let relation_record = DataSetRelation::new(teon!({
                     "dataSet": dataset.name.join(".").as_str(),
@@ -535,45 +543,48 @@ async fn insert_or_update_input(dataset: &DataSet, group: &Group, record: &Recor
                     "nameA": record.name.as_str(),
                     "groupB": that_record.model().path().join("."),
                     "relationB": if opposite_relation.is_some() { Value::String(opposite_relation.unwrap().name().to_owned()) } else { Value::Null },
-                    "nameB": v.as_str().unwrap().to_string()
-                }), ctx.clone()).await.unwrap();
-                    relation_record.save().await.unwrap();
+                    "nameB": that_record_name.as_str()
+                }), ctx.clone()).await?;
+                    relation_record.save().await?;
                 }
             }
         }
     }
-    input
+    Ok(input)
 }
 
 /// This perform, saves an object into the database. It doesn't setup relationships without
 /// required foreign keys.
-async fn perform_insert_into_database(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, ctx: transaction::Ctx) {
-    let input = insert_or_update_input(dataset, group, record, group_model, ctx.clone()).await;
-    let object = ctx.create_object(group_model, &input, None).await.unwrap();
-    object.save_for_seed_without_required_relation().await.unwrap();
+async fn perform_insert_into_database(dataset: &DataSet, group: &Group, record: &Record, group_model: &'static Model, ctx: transaction::Ctx) -> Result<()> {
+    let input = insert_or_update_input(dataset, group, record, group_model, ctx.clone()).await?;
+    let object = ctx.create_object(group_model, &input, None).await?;
+    object.save_for_seed_without_required_relation().await?;
     let record_object = DataSetRecord::new(teon!({
         "group": group.name.join(".").as_str(),
         "dataSet": dataset.name.join(".").as_str(),
         "name": record.name.as_str(),
         "record": object_identifier_in_json(&object),
-    }), ctx.clone()).await.unwrap();
-    record_object.save().await.unwrap();
+    }), ctx.clone()).await?;
+    record_object.save().await
 }
 
-fn record_json_string_to_where_unique(json_str: impl AsRef<str>, model: &'static Model) -> Value {
-    let json_value: serde_json::Value = serde_json::from_str(json_str.as_ref()).unwrap();
-    let json_object = json_value.as_object().unwrap();
+fn record_json_string_to_where_unique(json_str: impl AsRef<str>, model: &'static Model) -> Result<Value> {
+    let invalid = || Error::new(format!("seed: the stored identifier `{}` of `{}` is invalid", json_str.as_ref(), model.path().join(".")));
+    let json_value: serde_json::Value = serde_json::from_str(json_str.as_ref()).map_err(|_| invalid())?;
+    let json_object = json_value.as_object().ok_or_else(invalid)?;
     let mut result_teon_value = teon!({});
     for (k, v) in json_object {
-        match model.field(k).unwrap().r#type() {
-            Type::String => result_teon_value.as_dictionary_mut().unwrap().insert(k.to_owned(), Value::String(v.as_str().unwrap().to_string())),
-            Type::ObjectId => result_teon_value.as_dictionary_mut().unwrap().insert(k.to_owned(), Value::ObjectId(ObjectId::parse_str(v.as_str().unwrap()).unwrap())),
-            Type::Int => result_teon_value.as_dictionary_mut().unwrap().insert(k.to_owned(), Value::Int(v.as_i64().unwrap() as i32)),
-            Type::Int64 => result_teon_value.as_dictionary_mut().unwrap().insert(k.to_owned(), Value::Int64(v.as_i64().unwrap())),
-            _ => unreachable!()
-        };
+        let field = model.field(k).ok_or_else(invalid)?;
+        let value = match field.r#type() {
+            Type::String => v.as_str().map(|s| Value::String(s.to_string())),
+            Type::ObjectId => v.as_str().and_then(|s| ObjectId::parse_str(s).ok()).map(Value::ObjectId),
+            Type::Int => v.as_i64().map(|i| Value::Int(i as i32)),
+            Type::Int64 => v.as_i64().map(Value::Int64),
+            _ => None,
+        }.ok_or_else(invalid)?;
+        result_teon_value.as_dictionary_mut().unwrap().insert(k.to_owned(), value);
     }
-    result_teon_value
+    Ok(result_teon_value)
 }
 
 fn object_identifier_in_json(object: &Object) -> String {
@@ -591,11 +602,28 @@ fn object_identifier_in_json(object: &Object) -> String {
     result.to_string()
 }
 
-fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Vec<&Group> {
+fn model_at_path(ctx: &transaction::Ctx, path: &Vec<&str>) -> Result<&'static Model> {
+    ctx.namespace().model_at_path(path).ok_or_else(|| Error::new(format!("seed: model `{}` is not found", path.join("."))))
+}
+
+fn record_not_found(group: &str, name: &str) -> Error {
+    Error::new(format!("seed: record `{}` of `{}` is not found", name, group))
+}
+
+fn record_value(record: &Record) -> Result<&IndexMap<String, Value>> {
+    record.value.as_dictionary().ok_or_else(|| Error::new(format!("seed: record `{}` is not a dictionary", record.name)))
+}
+
+/// The name of the record a relation of a record refers to.
+fn reference_name(reference: &Value) -> Result<String> {
+    reference.as_str().map(ToOwned::to_owned).ok_or_else(|| Error::new(format!("seed: expect a record name, found `{}`", reference)))
+}
+
+fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Result<Vec<&Group>> {
     let mut deps: IndexMap<String, Vec<String>> = IndexMap::new();
     for group in groups {
         let model_name = &group.name.join(".");
-        let model = ctx.namespace().model_at_path(&group.name.iter().map(|s| s.as_str()).collect()).unwrap();
+        let model = model_at_path(&ctx, &group.name.iter().map(|s| s.as_str()).collect())?;
         let mut model_deps = vec![];
         for relation in model.relations() {
             if relation.has_foreign_key && relation.is_required() {
@@ -617,7 +645,7 @@ fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Vec<&Group> {
             }
         }
         if !has_some {
-            panic!("Circular required relationship between these models: `{}'.", deps.keys().join(","))
+            Err(Error::new(format!("circular required relationship between these models: `{}`", deps.keys().join(","))))?
         }
         for group in &result {
             let group_name = group.name.join(".");
@@ -632,10 +660,10 @@ fn ordered_group(groups: &Vec<Group>, ctx: transaction::Ctx) -> Vec<&Group> {
             }
         }
     }
-    result
+    Ok(result)
 }
 
-async fn remove_user_deleted_dataset_records_and_relations(datasets: &Vec<DataSet>, ctx: transaction::Ctx) {
+async fn remove_user_deleted_dataset_records_and_relations(datasets: &Vec<DataSet>, ctx: transaction::Ctx) -> Result<()> {
     // remove seed data set records if user removed some seed data set
     let names = Value::Array(datasets.iter().map(|d| Value::String(d.name.join(".").clone())).collect::<Vec<Value>>());
     let records_to_remove = DataSetRecord::find_many(teon!({
@@ -644,9 +672,9 @@ async fn remove_user_deleted_dataset_records_and_relations(datasets: &Vec<DataSe
                 "notIn": &names,
             }
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     for record in records_to_remove {
-        record.delete().await.unwrap();
+        record.delete().await?;
     }
     let relations_to_remove = DataSetRelation::find_many(teon!({
         "where": {
@@ -654,8 +682,9 @@ async fn remove_user_deleted_dataset_records_and_relations(datasets: &Vec<DataSe
                 "notIn": names,
             }
         }
-    }), ctx.clone()).await.unwrap();
+    }), ctx.clone()).await?;
     for relation in relations_to_remove {
-        relation.delete().await.unwrap();
+        relation.delete().await?;
     }
+    Ok(())
 }
\ No newline at end of file
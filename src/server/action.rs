@@ -0,0 +1,229 @@
+use std::sync::Arc;
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::action::Action;
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::default::{create, update, upsert, copy, create_many, update_many, copy_many, delete_many, count, aggregate, group_by, delete};
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::server::credentials;
+use crate::server::estimate;
+use crate::server::etag::check_if_match;
+use crate::server::money::MoneySum;
+use crate::server::nearest::NearestTo;
+use crate::server::nested_write;
+use crate::server::output;
+use crate::server::partial_update;
+use crate::server::permissions::check_permission;
+use crate::server::position;
+use crate::server::projection::Projection;
+use crate::server::scalar;
+use crate::server::sequence;
+use crate::server::similar::SimilarFilter;
+use crate::server::slug;
+use crate::server::tags;
+use crate::server::tree::TreeFilter;
+use crate::stdlib::decorators::credential::model_credential;
+use crate::stdlib::decorators::position::model_position_fields;
+use crate::stdlib::decorators::scalar::has_scalar_fields;
+use crate::stdlib::decorators::sequence::model_sequence_fields;
+use crate::stdlib::decorators::slug::model_slug_fields;
+use crate::stdlib::decorators::taggable::model_taggable;
+use crate::stdlib::decorators::tree::model_tree;
+use crate::stdlib::decorators::view::{model_view, WRITE_ACTIONS};
+
+/// A builtin action of a model, with the options of the request which the
+/// query engine doesn't understand taken out of its body.
+///
+/// Requests, batch actions and JSON-RPC calls all run builtin actions
+/// through `run`, so their inputs are completed, checked and validated, and
+/// their responses rewritten, the same way.
+#[derive(Debug, Clone)]
+pub(super) struct BuiltinAction {
+    model: &'static Model,
+    name: String,
+    action: Action,
+    if_match: Option<String>,
+    projection: Projection,
+    nearest_to: Option<NearestTo>,
+    similar: SimilarFilter,
+    money_sum: MoneySum,
+    estimate: bool,
+    tree_filter: Option<TreeFilter>,
+}
+
+impl BuiltinAction {
+
+    /// Take the options of an action out of its body. `if_match` is the
+    /// `If-Match` precondition of an update or delete.
+    pub(super) fn take(model: &'static Model, name: &str, action: Action, body: &mut JsonValue, if_match: Option<String>) -> Result<Self> {
+        if model_view(model).is_some() && WRITE_ACTIONS.contains(&name) {
+            let mut error = Error::new(format!("`{}` is a view, writes are not allowed", model.path().join(".")));
+            error.code = 405;
+            Err(error)?
+        }
+        Ok(Self {
+            model,
+            name: name.to_owned(),
+            action,
+            if_match: if_match.filter(|_| matches!(name, "update" | "delete")),
+            projection: Projection::take(body),
+            nearest_to: if name == "findMany" { NearestTo::take(body)? } else { None },
+            similar: if name == "findMany" { SimilarFilter::take(body) } else { SimilarFilter::default() },
+            money_sum: if name == "aggregate" { MoneySum::take(model, body)? } else { MoneySum::default() },
+            estimate: if name == "count" { estimate::take_estimate(body)? } else { false },
+            tree_filter: if model_tree(model).is_some() { TreeFilter::take(body)? } else { None },
+        })
+    }
+
+    /// Whether the action writes records.
+    pub(super) fn is_write(&self) -> bool {
+        WRITE_ACTIONS.contains(&self.name.as_str())
+    }
+
+    /// Run the action with the transaction of `ctx`. The permission is
+    /// checked first. The body is then completed with tree filters, slugs,
+    /// sequence numbers, tags and positions, resolved and checked against the
+    /// stored records, validated and handed to the handler.
+    pub(super) async fn run(&self, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+        let (model, name) = (self.model, self.name.as_str());
+        check_permission(model, name, ctx).await?;
+        let mut body = body.clone();
+        if let (Some(tree), Some(tree_filter)) = (model_tree(model), &self.tree_filter) {
+            tree_filter.apply(model, &tree, &mut body, main_namespace).await?;
+        }
+        if matches!(name, "create" | "createMany" | "upsert") && !model_slug_fields(model).is_empty() {
+            slug::fill_slugs(model, &mut body, main_namespace, &ctx.transaction_ctx()).await?;
+        }
+        if matches!(name, "create" | "createMany" | "upsert") && !model_sequence_fields(model).is_empty() {
+            sequence::fill_sequences(model, &mut body, &ctx.transaction_ctx()).await?;
+        }
+        if let Some(taggable) = model_taggable(model) {
+            if matches!(name, "create" | "createMany" | "update" | "upsert") {
+                tags::rewrite_tag_inputs(&taggable, &mut body)?;
+            }
+        }
+        if !model_position_fields(model).is_empty() {
+            if matches!(name, "create" | "createMany" | "upsert") {
+                position::fill_positions(model, &mut body, main_namespace, &ctx.transaction_ctx()).await?;
+            } else if name == "update" {
+                position::apply_moves(model, &mut body, main_namespace, &ctx.transaction_ctx()).await?;
+            }
+        }
+        if matches!(name, "update" | "updateMany" | "upsert") {
+            partial_update::resolve_set_if_missing(model, name, &mut body, main_namespace, &ctx.transaction_ctx()).await?;
+        }
+        if has_scalar_fields(model) {
+            scalar::parse_input(model, &mut body)?;
+        }
+        let input = validate_and_transform_json_input_for_builtin_action(model, self.action, &body, main_namespace)?;
+        if let Some(credential) = model_credential(model) {
+            if matches!(name, "update" | "updateMany" | "delete" | "deleteMany") {
+                credentials::check_keeps_credentials(model, &credential, name, &body, main_namespace).await?;
+            }
+        }
+        if let Some(if_match) = &self.if_match {
+            check_if_match(if_match, model, &body, main_namespace, ctx).await?;
+        }
+        if name == "update" && !model_slug_fields(model).is_empty() {
+            slug::record_slug_changes(model, &body, main_namespace, &ctx.transaction_ctx()).await?;
+        }
+        let write_graph = matches!(name, "create" | "update") && nested_write::needs_write_graph(model, &body, main_namespace);
+        let ctx = with_body(ctx, input, ctx.transaction_ctx());
+        match name {
+            "findMany" | "findFirst" | "findUnique" => output::find(model, name, &ctx).await,
+            "create" | "update" if write_graph => nested_write::write(model, name, &body, main_namespace, &ctx).await,
+            "create" => create(&ctx).await,
+            "update" => update(&ctx).await,
+            "upsert" => upsert(&ctx).await,
+            "copy" => copy(&ctx).await,
+            "delete" => delete(&ctx).await,
+            "createMany" => create_many(&ctx).await,
+            "updateMany" => update_many(&ctx).await,
+            "copyMany" => copy_many(&ctx).await,
+            "deleteMany" => delete_many(&ctx).await,
+            "count" if self.estimate => estimate::count(model, &ctx).await,
+            "count" => count(&ctx).await,
+            "aggregate" => {
+                let response = aggregate(&ctx).await?;
+                if self.money_sum.is_effective() { self.money_sum.apply(model, &ctx, response).await } else { Ok(response) }
+            }
+            "groupBy" => group_by(&ctx).await,
+            _ => Err(Error::not_found()),
+        }
+    }
+
+    /// Run the action like `run`, writes in a transaction of their own. A
+    /// write graph which hits a unique violation is run again in a new
+    /// transaction.
+    pub(super) async fn run_in_transaction(&self, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+        if !self.is_write() {
+            return self.run(body, main_namespace, ctx).await;
+        }
+        let retries = nested_write::unique_violation_retries(self.model, &self.name, body, main_namespace);
+        let mut attempt = 0;
+        loop {
+            let (action, body, ctx) = (self.clone(), body.clone(), ctx.clone());
+            let result = ctx.transaction_ctx().run_transaction(move |transaction_ctx: transaction::Ctx| {
+                let (action, body) = (action.clone(), body.clone());
+                let ctx = with_body(&ctx, ctx.body().clone(), transaction_ctx);
+                async move { action.run(&body, main_namespace, &ctx).await }
+            }).await;
+            match result {
+                Err(error) if attempt < retries && nested_write::is_unique_violation(&error) => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Rewrite the json body of a successful response with the options:
+    /// `similar` and `nearestTo` filter, rank and paginate the records,
+    /// custom scalars are serialized and the projection is applied.
+    pub(super) fn finish(&self, json_value: &mut JsonValue) {
+        if self.similar.is_effective() {
+            self.similar.apply(json_value);
+        }
+        if let Some(nearest_to) = &self.nearest_to {
+            nearest_to.apply(json_value);
+        }
+        if has_scalar_fields(self.model) {
+            if let Some(data) = json_value.get_mut("data") {
+                scalar::serialize_records(self.model, data);
+            }
+        }
+        if self.projection.is_effective() {
+            if let Some(data) = json_value.get_mut("data") {
+                self.projection.apply(data);
+            }
+        }
+    }
+
+    /// Rewrite a successful json response with `finish`.
+    pub(super) async fn finish_response(&self, response: HttpResponse) -> HttpResponse {
+        let rewrites = self.similar.is_effective() || self.nearest_to.is_some() || has_scalar_fields(self.model) || self.projection.is_effective();
+        if !rewrites || !response.status().is_success() {
+            return response;
+        }
+        let (head, body) = response.into_parts();
+        let Ok(bytes) = to_bytes(body).await else {
+            return HttpResponse::InternalServerError().finish();
+        };
+        let mut json_value: JsonValue = match serde_json::from_slice(&bytes) {
+            Ok(json_value) => json_value,
+            Err(_) => return head.set_body(bytes).map_into_boxed_body(),
+        };
+        self.finish(&mut json_value);
+        head.set_body(json_value.to_string()).map_into_boxed_body()
+    }
+}
+
+/// The request context of `ctx` with another body and transaction.
+pub(super) fn with_body(ctx: &request::Ctx, body: Value, transaction_ctx: transaction::Ctx) -> request::Ctx {
+    request::Ctx::new(ctx.request().clone(), Arc::new(body), transaction_ctx, ctx.handler_match().clone())
+}
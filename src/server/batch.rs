@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use actix_web::HttpRequest;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::{connection, request};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::handler::Method;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::body::BodyInner;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::server::action::BuiltinAction;
+use crate::server::impersonation::call_middlewares;
+use crate::server::request::RequestImpl;
+
+/// The path of the batch endpoint.
+pub(super) const BATCH_PATH: &str = "/_batch";
+
+/// Run the actions of a batch request in order within a single transaction.
+///
+/// The request body is `{ "actions": [{ "model": "User", "action": "create",
+/// "body": { ... } }, ...] }` and the response is `{ "data": [...] }` with the
+/// response of each action. If an action fails, the whole batch is rolled back
+/// and the error names the index of the failed action.
+///
+/// An action body can reference the responses of earlier actions with
+/// `{ "$ref": "0.data.id" }`, where the first key is the index of the action.
+/// An update or delete can carry the `If-Match` precondition of its record
+/// as `ifMatch`.
+pub(super) async fn batch(http_request: &HttpRequest, json_body: &JsonValue, main_namespace: &'static Namespace) -> Result<Response> {
+    let Some(actions) = json_body.get("actions").and_then(|a| a.as_array()) else {
+        return Err(Error::invalid_request_message("expect `actions` to be an array"));
+    };
+    let actions = actions.clone();
+    let http_request = http_request.clone();
+    let transaction_ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let results = transaction_ctx.run_transaction(move |transaction_ctx: transaction::Ctx| {
+        let actions = actions.clone();
+        let http_request = http_request.clone();
+        async move {
+            let mut results: Vec<JsonValue> = vec![];
+            for (index, item) in actions.iter().enumerate() {
+                let result = async {
+                    let item = resolve_refs(item, &results)?;
+                    run_action(&http_request, &item, main_namespace, transaction_ctx.clone()).await
+                }.await.map_err(|error| {
+                    let mut batch_error = Error::new(format!("batch action {} failed: {}", index, error.message));
+                    batch_error.code = error.code;
+                    batch_error.errors = error.errors;
+                    batch_error
+                })?;
+                results.push(result);
+            }
+            Ok(results)
+        }
+    }).await?;
+    Ok(Response::data(Value::from(JsonValue::Array(results))))
+}
+
+/// Run a builtin action of a batch or a JSON-RPC call with `transaction_ctx`.
+pub(super) async fn run_action(http_request: &HttpRequest, item: &JsonValue, main_namespace: &'static Namespace, transaction_ctx: transaction::Ctx) -> Result<JsonValue> {
+    let model_name = item.get("model").and_then(|m| m.as_str()).ok_or_else(|| Error::invalid_request_message("expect `model` to be a string"))?;
+    let action_name = item.get("action").and_then(|a| a.as_str()).ok_or_else(|| Error::invalid_request_message("expect `action` to be a string"))?;
+    let mut body = item.get("body").cloned().unwrap_or(json!({}));
+    let model_path: Vec<&str> = model_name.split('.').collect();
+    let model = main_namespace.model_at_path(&model_path).ok_or_else(|| Error::invalid_request_message(format!("model `{}` is not found", model_name)))?;
+    let action = builtin_action_handler_from_name(action_name).ok_or_else(|| Error::invalid_request_message(format!("action `{}` is not found", action_name)))?;
+    let dest_namespace = main_namespace.namespace_at_path(&model_path[..model_path.len() - 1].to_vec()).unwrap_or(main_namespace);
+    let handler_path = format!("/{}/{}", model_path.join("/"), action_name);
+    let match_result = main_namespace.handler_map.default_match(Method::Post, &handler_path).ok_or_else(|| Error::not_found())?;
+    let if_match = item.get("ifMatch").and_then(|i| i.as_str()).map(|s| s.to_owned());
+    let builtin = BuiltinAction::take(model, action_name, action, &mut body, if_match)?;
+    let ctx = request::Ctx::new(
+        request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+        Arc::new(Value::from(body.clone())),
+        transaction_ctx,
+        match_result,
+    );
+    let response = call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| {
+        let (builtin, body) = (builtin.clone(), body.clone());
+        async move { builtin.run(&body, main_namespace, &ctx).await }
+    }).await?;
+    let mut result = match response.body().inner.as_ref() {
+        BodyInner::Teon(value) => JsonValue::try_from(value)?,
+        _ => JsonValue::Null,
+    };
+    builtin.finish(&mut result);
+    Ok(result)
+}
+
+/// Replace `{ "$ref": "index.path" }` objects with the referenced values.
+fn resolve_refs(value: &JsonValue, results: &Vec<JsonValue>) -> Result<JsonValue> {
+    Ok(match value {
+        JsonValue::Object(map) if map.len() == 1 && map.contains_key("$ref") => {
+            let reference = map.get("$ref").and_then(|r| r.as_str()).ok_or_else(|| Error::invalid_request_message("expect `$ref` to be a string"))?;
+            let mut keys = reference.split('.');
+            let index: usize = keys.next().and_then(|i| i.parse().ok()).ok_or_else(|| Error::invalid_request_message(format!("invalid reference `{}`", reference)))?;
+            let Some(mut current) = results.get(index) else {
+                return Err(Error::invalid_request_message(format!("reference `{}` points to an action which is not run yet", reference)));
+            };
+            for key in keys {
+                current = match current {
+                    JsonValue::Object(map) => map.get(key),
+                    JsonValue::Array(array) => key.parse::<usize>().ok().and_then(|i| array.get(i)),
+                    _ => None,
+                }.ok_or_else(|| Error::invalid_request_message(format!("reference `{}` is not found", reference)))?;
+            }
+            current.clone()
+        }
+        JsonValue::Object(map) => JsonValue::Object(map.iter().map(|(k, v)| Ok((k.clone(), resolve_refs(v, results)?))).collect::<Result<_>>()?),
+        JsonValue::Array(array) => JsonValue::Array(array.iter().map(|v| resolve_refs(v, results)).collect::<Result<_>>()?),
+        _ => value.clone(),
+    })
+}
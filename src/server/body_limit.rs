@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+/// The default size limit of JSON request bodies in bytes.
+pub const DEFAULT_BODY_LIMIT: usize = 262_144;
+
+/// Size limits of JSON request bodies. Overrides are keyed by
+/// `"Model.action"` for a single action, or by `"action"` for an action of
+/// every model or handler group. Namespaced models are written like
+/// `"shop.Product.importCsv"`.
+#[derive(Debug, Clone)]
+pub struct BodyLimits {
+    default: usize,
+    overrides: BTreeMap<String, usize>,
+}
+
+impl Default for BodyLimits {
+
+    fn default() -> Self {
+        Self { default: DEFAULT_BODY_LIMIT, overrides: BTreeMap::new() }
+    }
+}
+
+impl BodyLimits {
+
+    pub(crate) fn set_default(&mut self, limit: usize) {
+        self.default = limit;
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, limit: usize) {
+        self.overrides.insert(key.to_owned(), limit);
+    }
+
+    /// The limit of a handler, the most specific override wins.
+    pub(crate) fn limit_for(&self, group_path: &str, handler_name: &str) -> usize {
+        let full = if group_path.is_empty() { handler_name.to_owned() } else { format!("{}.{}", group_path, handler_name) };
+        self.overrides.get(&full)
+            .or_else(|| self.overrides.get(handler_name))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, SecondsFormat, TimeZone, Utc};
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::response::body::BodyInner;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+
+const AGGREGATES: [&str; 5] = ["_count", "_min", "_max", "_sum", "_avg"];
+
+/// The unit date buckets are truncated to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+}
+
+/// The `findMany` arguments fetching the records of a bucketed `groupBy`.
+pub(super) fn find_many_arguments(json_body: &JsonValue) -> JsonValue {
+    json!({ "where": json_body.get("where").cloned().unwrap_or(json!({})) })
+}
+
+/// Group the records of a `findMany` response for a `groupBy` with a
+/// `bucket: { field, unit }` argument. Records are grouped by the date field
+/// truncated to the unit, weeks start on Monday, and by the `by` fields. The
+/// aggregates are computed on the server, so `_min` and `_max` work for dates
+/// too.
+pub(super) fn group_by_bucket(response: &Response, json_body: &JsonValue, bucket: &JsonValue) -> Result<Response> {
+    let field = bucket.get("field").and_then(|f| f.as_str()).ok_or_else(|| Error::invalid_request_message("expect `bucket.field` to be a string"))?;
+    let unit = match bucket.get("unit").and_then(|u| u.as_str()) {
+        Some("day") => Unit::Day,
+        Some("week") => Unit::Week,
+        Some("month") => Unit::Month,
+        _ => Err(Error::invalid_request_message("expect `bucket.unit` to be one of day, week and month"))?,
+    };
+    let by: Vec<String> = json_body.get("by").and_then(|b| b.as_array()).map(|b| b.iter().filter_map(|f| f.as_str().map(|s| s.to_owned())).collect()).unwrap_or_default();
+    let BodyInner::Teon(value) = response.body().inner.as_ref() else { return Ok(Response::data(Value::Array(vec![]))) };
+    let json_value = JsonValue::try_from(value)?;
+    let records = json_value.get("data").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+    let mut groups: BTreeMap<String, (Map<String, JsonValue>, Vec<JsonValue>)> = BTreeMap::new();
+    for record in records {
+        let Some(date) = record.get(field).and_then(parse_date) else { continue };
+        let bucket_start = truncate(date, unit).to_rfc3339_opts(SecondsFormat::Millis, true);
+        let mut key = Map::new();
+        key.insert("bucket".to_owned(), json!(bucket_start));
+        for by_field in &by {
+            key.insert(by_field.clone(), record.get(by_field).cloned().unwrap_or(JsonValue::Null));
+        }
+        groups.entry(JsonValue::Object(key.clone()).to_string()).or_insert_with(|| (key, vec![])).1.push(record);
+    }
+    let mut result = vec![];
+    for (_, (mut key, records)) in groups {
+        for aggregate in AGGREGATES {
+            let Some(JsonValue::Object(fields)) = json_body.get(aggregate) else { continue };
+            let mut output = Map::new();
+            for (name, enabled) in fields {
+                if enabled.as_bool() != Some(true) { continue }
+                output.insert(name.clone(), compute(aggregate, name, &records));
+            }
+            key.insert(aggregate.to_owned(), JsonValue::Object(output));
+        }
+        result.push(JsonValue::Object(key));
+    }
+    Ok(Response::data(Value::from(JsonValue::Array(result))))
+}
+
+fn compute(aggregate: &str, name: &str, records: &Vec<JsonValue>) -> JsonValue {
+    if aggregate == "_count" {
+        return json!(if name == "_all" { records.len() } else { records.iter().filter(|r| !r.get(name).map_or(true, |v| v.is_null())).count() });
+    }
+    let values: Vec<&JsonValue> = records.iter().filter_map(|r| r.get(name)).filter(|v| !v.is_null()).collect();
+    match aggregate {
+        "_min" | "_max" => {
+            let compare = |a: &&JsonValue, b: &&JsonValue| match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                // dates and strings, ISO 8601 dates compare lexicographically
+                _ => a.to_string().cmp(&b.to_string()),
+            };
+            let found = if aggregate == "_min" { values.iter().min_by(compare) } else { values.iter().max_by(compare) };
+            found.map(|v| (*v).clone()).unwrap_or(JsonValue::Null)
+        }
+        _ => {
+            let numbers: Vec<f64> = values.iter().filter_map(|v| v.as_f64()).collect();
+            if numbers.is_empty() {
+                return JsonValue::Null;
+            }
+            let sum: f64 = numbers.iter().sum();
+            if aggregate == "_sum" { json!(sum) } else { json!(sum / numbers.len() as f64) }
+        }
+    }
+}
+
+fn parse_date(value: &JsonValue) -> Option<DateTime<Utc>> {
+    let string = value.as_str().or_else(|| value.get("$date").and_then(|d| d.as_str()))?;
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(string) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(string, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)).map(|d| Utc.from_utc_datetime(&d))
+}
+
+fn truncate(date: DateTime<Utc>, unit: Unit) -> DateTime<Utc> {
+    let day = date.date_naive();
+    let start = match unit {
+        Unit::Day => day,
+        Unit::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        Unit::Month => day.with_day(1).unwrap_or(day),
+    };
+    Utc.from_utc_datetime(&start.and_hms_opt(0, 0, 0).unwrap())
+}
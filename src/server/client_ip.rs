@@ -0,0 +1,113 @@
+use std::net::IpAddr;
+use actix_http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::dev::ServiceRequest;
+use teo_result::{Error, Result};
+use crate::app::ctx::Ctx;
+
+/// The header which carries the client IP address to handlers and pipelines.
+/// It's always set by the server, a value sent by the client is replaced.
+pub const CLIENT_IP_HEADER: &str = "x-teo-client-ip";
+
+/// A range of IP addresses like `10.0.0.0/8` or `2001:db8::/32`. A single
+/// address is a range of one.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+
+    pub(crate) fn parse(cidr: &str) -> Result<Self> {
+        let (address, prefix) = cidr.trim().split_once('/').map_or((cidr.trim(), None), |(a, p)| (a, Some(p)));
+        let network: IpAddr = address.parse().map_err(|_| Error::new(format!("invalid proxy address `{}`", cidr)))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|p| *p <= max).ok_or_else(|| Error::new(format!("invalid prefix length in `{}`", cidr)))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+
+    pub(crate) fn contains(&self, address: &IpAddr) -> bool {
+        match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(*address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(*address) & mask
+            }
+            (IpAddr::V6(_), IpAddr::V4(address)) => self.contains(&IpAddr::V6(address.to_ipv6_mapped())),
+            (IpAddr::V4(_), IpAddr::V6(address)) => address.to_ipv4_mapped().map_or(false, |address| self.contains(&IpAddr::V4(address))),
+        }
+    }
+}
+
+/// The IP address of the client of a request.
+///
+/// Forwarding headers are only read when the peer is a trusted proxy. Then
+/// the addresses of `Forwarded`, or of `X-Forwarded-For` without it, are
+/// walked from the nearest hop, and the first address which isn't a trusted
+/// proxy is the client. Without trusted proxies, the peer is the client.
+pub(crate) fn client_ip_for(req: &ServiceRequest) -> String {
+    let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else { return String::new() };
+    let trusted_proxies = Ctx::trusted_proxies();
+    let is_trusted = |address: &IpAddr| trusted_proxies.iter().any(|cidr| cidr.contains(address));
+    if !is_trusted(&peer) {
+        return peer.to_string();
+    }
+    let hops = forwarded_hops(req.headers());
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        // an obfuscated or unknown hop cannot be checked, stop at it
+        let Some(address) = hop else { break };
+        client = *address;
+        if !is_trusted(address) {
+            break
+        }
+    }
+    client.to_string()
+}
+
+/// Set the client IP header, so that handlers and pipelines can read the
+/// address from the request headers.
+pub(crate) fn insert_client_ip(headers: &mut HeaderMap, client_ip: &str) {
+    let name = HeaderName::from_static(CLIENT_IP_HEADER);
+    headers.remove(&name);
+    if let Ok(value) = HeaderValue::from_str(client_ip) {
+        headers.insert(name, value);
+    }
+}
+
+/// The addresses of the forwarding headers from the client to the nearest
+/// proxy. `None` is a hop whose address is unknown or obfuscated.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded: Vec<&str> = headers.get_all("forwarded").filter_map(|v| v.to_str().ok()).collect();
+    if !forwarded.is_empty() {
+        return forwarded.iter().flat_map(|value| value.split(',')).map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                if key.trim().eq_ignore_ascii_case("for") { Some(parse_node(value)) } else { None }
+            }).flatten()
+        }).collect();
+    }
+    headers.get_all("x-forwarded-for").filter_map(|v| v.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+/// Parse a node like `192.0.2.43`, `"192.0.2.43:4711"` or `"[2001:db8::1]:4711"`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+    if let Ok(address) = node.parse() {
+        return Some(address);
+    }
+    // IPv4 with a port
+    node.rsplit_once(':').and_then(|(address, _)| address.parse().ok())
+}
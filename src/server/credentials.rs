@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use futures_util::future::BoxFuture;
+use key_path::path;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection;
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::relation::Relation;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::Ctx;
+use crate::server::error::UserError;
+use crate::server::impersonation::effective_identity;
+use crate::stdlib::decorators::credential::CredentialFields;
+
+/// A credential proven by a credential verifier.
+#[derive(Debug, Clone)]
+pub struct VerifiedCredential {
+    /// The account at the provider, e.g. the email address or the OIDC
+    /// subject.
+    pub subject: String,
+    /// Other fields of the credential record, e.g. the password hash.
+    pub fields: Map<String, JsonValue>,
+}
+
+/// Verifies the proof of a credential before it's linked, e.g. the ID token
+/// of an OIDC provider, and responds with the verified credential, or `None`
+/// if the proof is invalid.
+pub trait CredentialVerifier: Send + Sync {
+    fn verify(&self, proof: JsonValue) -> BoxFuture<'static, Result<Option<VerifiedCredential>>>;
+}
+
+impl<F, Fut> CredentialVerifier for F where
+    F: Fn(JsonValue) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Option<VerifiedCredential>>> + Send + 'static {
+    fn verify(&self, proof: JsonValue) -> BoxFuture<'static, Result<Option<VerifiedCredential>>> {
+        Box::pin(self(proof))
+    }
+}
+
+/// The `link` action of a credential model. The body is
+/// `{ provider, proof }`. The proof is checked by the verifier of the
+/// provider, then the credential is linked to the identity of the request.
+/// Linking a credential the identity already has responds with it, a
+/// credential of another identity is rejected with 409.
+pub(super) async fn link(model: &'static Model, fields: &CredentialFields, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let relation = owner_relation(model, fields)?;
+    let identity = request_identity(relation, ctx).await?;
+    let provider = body.get("provider").and_then(|p| p.as_str()).ok_or_else(|| Error::invalid_request_message("expect `provider` to be a string"))?;
+    let verifier = Ctx::credential_verifier(provider).ok_or_else(|| Error::invalid_request_message(format!("provider `{}` is not supported", provider)))?;
+    let Some(verified) = verifier.verify(body.get("proof").cloned().unwrap_or(JsonValue::Null)).await? else {
+        Err(UserError::new("CREDENTIAL_INVALID", format!("the `{}` credential is invalid", provider)).with_status(401))?
+    };
+    let owner = owner_finder(relation, &identity)?;
+    let credential = match find_credentials(model, json!({ fields.provider.as_str(): provider, fields.subject.as_str(): verified.subject }), main_namespace, ctx.transaction_ctx()).await?.into_iter().next() {
+        Some(credential) => {
+            if !owned_by(relation, &credential, &owner)? {
+                Err(UserError::new("CREDENTIAL_LINKED", format!("the `{}` credential is linked to another identity", provider)).with_status(409))?
+            }
+            credential
+        }
+        None => {
+            let mut create = verified.fields.clone();
+            create.insert(fields.provider.clone(), json!(provider));
+            create.insert(fields.subject.clone(), json!(verified.subject));
+            if let JsonValue::Object(owner) = &owner {
+                create.extend(owner.clone());
+            }
+            let action = builtin_action_handler_from_name("create").ok_or_else(|| Error::not_found())?;
+            let input = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "create": create }), main_namespace)?;
+            let credential = ctx.transaction_ctx().create_object(model, input.get("create").unwrap_or(&Value::Null), None).await?;
+            credential.save().await?;
+            credential
+        }
+    };
+    Ok(Response::data(credential.to_teon().await?))
+}
+
+/// The `unlink` action of a credential model. The body is `{ where }`, a
+/// unique where input of a credential of the identity of the request. The
+/// credential is deleted and the remaining ones counted in one transaction,
+/// which is rolled back if none remains.
+pub(super) async fn unlink(model: &'static Model, fields: &CredentialFields, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let relation = owner_relation(model, fields)?;
+    let identity = request_identity(relation, ctx).await?;
+    let owner = owner_finder(relation, &identity)?;
+    let finder = body.get("where").cloned().ok_or_else(|| Error::invalid_request_message("expect `where` to be an object"))?;
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), main_namespace)?;
+    let unique = input.get("where").cloned().unwrap_or(Value::Null);
+    let owner_where = where_input(model, owner.clone(), main_namespace)?;
+    let result = ctx.transaction_ctx().run_transaction(move |ctx: transaction::Ctx| {
+        let (unique, owner, owner_where) = (unique.clone(), owner.clone(), owner_where.clone());
+        async move {
+            let found: Vec<Object> = ctx.find_many(model, &teon!({ "where": unique, "take": 1 }), None, path![]).await?;
+            let credential = match found.into_iter().next() {
+                Some(credential) if owned_by(relation, &credential, &owner)? => credential,
+                _ => Err(UserError::new("CREDENTIAL_NOT_FOUND", "the credential is not found").with_status(404))?,
+            };
+            let value = credential.to_teon().await?;
+            credential.delete().await?;
+            if ctx.count_objects(model, &teon!({ "where": owner_where }), path![]).await? == 0 {
+                Err(UserError::new("LAST_CREDENTIAL", "the last credential of an identity cannot be unlinked").with_status(409))?
+            }
+            Ok(value)
+        }
+    }).await?;
+    Ok(Response::data(result))
+}
+
+/// Reject builtin writes of a credential model which would leave an identity
+/// without credentials: deletes, and updates moving credentials to another
+/// identity. Unlike `unlink`, the check runs before the write.
+pub(crate) async fn check_keeps_credentials(model: &'static Model, fields: &CredentialFields, action: &str, body: &JsonValue, main_namespace: &'static Namespace) -> Result<()> {
+    let relation = owner_relation(model, fields)?;
+    if matches!(action, "update" | "updateMany") {
+        let Some(update) = body.get("update").and_then(|u| u.as_object()) else { return Ok(()) };
+        if !update.contains_key(relation.name()) && !relation.iter().any(|(field, _)| update.contains_key(field)) {
+            return Ok(());
+        }
+    }
+    let finder = body.get("where").cloned().unwrap_or(json!({}));
+    let ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let mut affected: BTreeMap<String, (JsonValue, usize)> = BTreeMap::new();
+    for credential in find_credentials(model, finder, main_namespace, ctx.clone()).await? {
+        let mut owner = Map::new();
+        for (field, _) in relation.iter() {
+            owner.insert(field.to_owned(), JsonValue::try_from(&credential.get_value(field)?)?);
+        }
+        let owner = JsonValue::Object(owner);
+        affected.entry(owner.to_string()).or_insert((owner, 0)).1 += 1;
+    }
+    for (owner, count) in affected.into_values() {
+        if ctx.count_objects(model, &teon!({ "where": where_input(model, owner, main_namespace)? }), path![]).await? <= count {
+            Err(UserError::new("LAST_CREDENTIAL", "the last credential of an identity cannot be removed").with_status(409))?
+        }
+    }
+    Ok(())
+}
+
+fn owner_relation<'a>(model: &'a Model, fields: &CredentialFields) -> Result<&'a Relation> {
+    model.relations().into_iter().find(|r| r.name() == fields.owner.as_str())
+        .filter(|r| r.has_foreign_key)
+        .ok_or_else(|| Error::new(format!("@@credential: `{}` is not a relation with foreign keys of `{}`", fields.owner, model.path().join("."))))
+}
+
+/// The identity of the request, which must be of the owner model.
+async fn request_identity(relation: &Relation, ctx: &request::Ctx) -> Result<Object> {
+    let Some(identity) = effective_identity(ctx).await? else {
+        Err(UserError::new("IDENTITY_REQUIRED", "identity is required").with_status(401))?
+    };
+    if identity.model().path() != relation.model_path() {
+        Err(UserError::new("CREDENTIAL_MODEL_MISMATCH", format!("credentials belong to `{}` identities", relation.model_path().join("."))).with_status(403))?
+    }
+    Ok(identity)
+}
+
+/// The foreign keys of the credentials of an identity.
+fn owner_finder(relation: &Relation, identity: &Object) -> Result<JsonValue> {
+    let mut finder = Map::new();
+    for (field, reference) in relation.iter() {
+        finder.insert(field.to_owned(), JsonValue::try_from(&identity.get_value(reference)?)?);
+    }
+    Ok(JsonValue::Object(finder))
+}
+
+fn owned_by(relation: &Relation, credential: &Object, owner: &JsonValue) -> Result<bool> {
+    for (field, _) in relation.iter() {
+        if owner.get(field) != Some(&JsonValue::try_from(&credential.get_value(field)?)?) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn find_credentials(model: &'static Model, finder: JsonValue, main_namespace: &'static Namespace, ctx: transaction::Ctx) -> Result<Vec<Object>> {
+    ctx.find_many(model, &teon!({ "where": where_input(model, finder, main_namespace)? }), None, path![]).await
+}
+
+fn where_input(model: &'static Model, finder: JsonValue, main_namespace: &'static Namespace) -> Result<Value> {
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &json!({ "where": finder }), main_namespace)?;
+    Ok(input.get("where").cloned().unwrap_or(Value::Null))
+}
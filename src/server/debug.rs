@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use serde_json::{json, Value as JsonValue};
+use crate::utils::environments::is_development;
+
+/// The timings of the stages of a request sent with `"_debug": true`. They
+/// are reported in `meta.debug` of the response.
+///
+/// The flag is only honored in development, elsewhere it's silently removed.
+#[derive(Debug, Clone)]
+pub(super) struct DebugTimings {
+    last: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl DebugTimings {
+
+    /// Take the `_debug` flag out of request arguments.
+    pub(super) fn take(args: &mut JsonValue) -> Option<Self> {
+        let flag = args.as_object_mut()?.remove("_debug")?;
+        if flag.as_bool() == Some(true) && is_development() {
+            Some(Self { last: Instant::now(), stages: vec![] })
+        } else {
+            None
+        }
+    }
+
+    /// Record the time elapsed since the previous stage.
+    pub(super) fn stage(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.stages.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// Add the timings to the `meta` of a json response.
+    pub(super) async fn apply_to_response(&self, response: HttpResponse) -> HttpResponse {
+        let (head, body) = response.into_parts();
+        let Ok(bytes) = to_bytes(body).await else {
+            return HttpResponse::InternalServerError().finish();
+        };
+        let mut json_value: JsonValue = match serde_json::from_slice(&bytes) {
+            Ok(JsonValue::Object(object)) => JsonValue::Object(object),
+            _ => return head.set_body(bytes).map_into_boxed_body(),
+        };
+        let stages: Vec<JsonValue> = self.stages.iter().map(|(name, duration)| json!({
+            "name": name,
+            "ms": duration.as_secs_f64() * 1000.0,
+        })).collect();
+        let total: f64 = self.stages.iter().map(|(_, d)| d.as_secs_f64() * 1000.0).sum();
+        let object = json_value.as_object_mut().unwrap();
+        let meta = object.entry("meta").or_insert_with(|| json!({}));
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert("debug".to_owned(), json!({ "stages": stages, "totalMs": total }));
+        }
+        head.set_body(json_value.to_string()).map_into_boxed_body()
+    }
+}
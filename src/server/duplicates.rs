@@ -0,0 +1,125 @@
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::events::object_json;
+use crate::server::similar::similarity;
+use crate::stdlib::decorators::dedupe::model_dedupe_fields;
+
+/// The score two records must reach to be reported as duplicates when the
+/// request doesn't specify one.
+const DEFAULT_THRESHOLD: f64 = 0.85;
+
+/// The `findDuplicates` action of a model with `@dedupe` fields. The body is
+/// `{ where?, fields?, threshold? }`.
+///
+/// Every pair of records matched by `where` is scored by the mean similarity
+/// of their normalized `@dedupe` fields, `fields` narrows them. Fields which
+/// are empty in either record don't count. Pairs reaching the threshold are
+/// joined into clusters, which are returned with the mean score of their
+/// pairs, best first. Scoring is quadratic in the number of records, so large
+/// tables should be narrowed with `where`.
+pub(super) async fn find_duplicates(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let threshold = body.get("threshold").and_then(|t| t.as_f64()).unwrap_or(DEFAULT_THRESHOLD);
+    let requested: Option<Vec<&str>> = body.get("fields").and_then(|f| f.as_array()).map(|f| f.iter().filter_map(|f| f.as_str()).collect());
+    let fields: Vec<_> = model_dedupe_fields(model).into_iter()
+        .filter(|(field, _)| requested.as_ref().map_or(true, |r| r.contains(&field.name())))
+        .collect();
+    if fields.is_empty() {
+        Err(Error::invalid_request_message("expect `fields` to contain @dedupe fields"))?
+    }
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &json!({ "where": body.get("where").cloned().unwrap_or(json!({})) }), main_namespace)?;
+    let objects: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null) }), Some(ctx.clone()), path![]).await?;
+    // the normalized values of each record, in the order of `fields`
+    let mut normalized: Vec<Vec<Option<String>>> = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        let mut values = vec![];
+        for (field, pipeline) in &fields {
+            let mut value = object.get_value(field.name())?;
+            if let Some(pipeline) = pipeline {
+                if !value.is_null() {
+                    let pipeline_ctx = pipeline::Ctx::new(value, object.clone(), path![index, field.name()], object.action(), ctx.transaction_ctx(), Some(ctx.clone()));
+                    value = pipeline_ctx.run_pipeline(pipeline).await?;
+                }
+            }
+            values.push(comparable(&value)?);
+        }
+        normalized.push(values);
+    }
+    let mut parents: Vec<usize> = (0..objects.len()).collect();
+    let mut pairs: Vec<(usize, usize, f64)> = vec![];
+    for a in 0..objects.len() {
+        for b in (a + 1)..objects.len() {
+            let Some(score) = score(&normalized[a], &normalized[b]) else { continue };
+            if score >= threshold {
+                let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                parents[root_b] = root_a;
+                pairs.push((a, b, score));
+            }
+        }
+    }
+    let mut clusters: Vec<(usize, Vec<usize>, Vec<f64>)> = vec![];
+    for (a, b, score) in pairs {
+        let cluster_root = root(&mut parents, a);
+        let index = match clusters.iter().position(|(r, _, _)| *r == cluster_root) {
+            Some(index) => index,
+            None => {
+                clusters.push((cluster_root, vec![], vec![]));
+                clusters.len() - 1
+            }
+        };
+        let (_, members, scores) = &mut clusters[index];
+        for member in [a, b] {
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+        scores.push(score);
+    }
+    let mut result = vec![];
+    for (_, mut members, scores) in clusters {
+        members.sort();
+        let records = members.iter().map(|m| object_json(&objects[*m])).collect::<Result<Vec<JsonValue>>>()?;
+        result.push(json!({ "score": scores.iter().sum::<f64>() / scores.len() as f64, "records": records }));
+    }
+    result.sort_by(|a, b| b["score"].as_f64().unwrap_or(0.0).total_cmp(&a["score"].as_f64().unwrap_or(0.0)));
+    Ok(Response::data(Value::from(JsonValue::Array(result))))
+}
+
+/// The mean similarity of the fields both records have values for.
+fn score(a: &Vec<Option<String>>, b: &Vec<Option<String>>) -> Option<f64> {
+    let scores: Vec<f64> = a.iter().zip(b.iter()).filter_map(|(a, b)| match (a, b) {
+        (Some(a), Some(b)) => Some(similarity(a, b)),
+        _ => None,
+    }).collect();
+    if scores.is_empty() {
+        return None;
+    }
+    Some(scores.iter().sum::<f64>() / scores.len() as f64)
+}
+
+fn comparable(value: &Value) -> Result<Option<String>> {
+    Ok(match JsonValue::try_from(value)? {
+        JsonValue::Null => None,
+        JsonValue::String(string) if string.is_empty() => None,
+        JsonValue::String(string) => Some(string),
+        value => Some(value.to_string()),
+    })
+}
+
+fn root(parents: &mut Vec<usize>, mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use actix_http::header::{HeaderName, HeaderValue};
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use serde_json::{json, Map, Value as JsonValue};
+
+/// Carries the meta of bare responses, which have no room for it in the body.
+pub const META_HEADER: &str = "x-teo-meta";
+
+/// The shape of json responses.
+///
+/// ```ignore
+/// // { "result": [...], "pagination": { "count": 2 } } and { "errors": { ... } }
+/// app.envelope(Envelope::default().data_key("result").meta_key("pagination").error_key("errors"));
+/// // the data itself, the meta goes to the `x-teo-meta` header
+/// app.action_envelope("User.findMany", Envelope::bare());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    data: Option<String>,
+    meta: String,
+    error: String,
+}
+
+impl Default for Envelope {
+
+    fn default() -> Self {
+        Self { data: Some("data".to_owned()), meta: "meta".to_owned(), error: "error".to_owned() }
+    }
+}
+
+impl Envelope {
+
+    /// Respond with the data itself instead of an object wrapping it.
+    pub fn bare() -> Self {
+        Self { data: None, ..Self::default() }
+    }
+
+    pub fn data_key(self, key: impl Into<String>) -> Self {
+        Self { data: Some(key.into()), ..self }
+    }
+
+    pub fn meta_key(self, key: impl Into<String>) -> Self {
+        Self { meta: key.into(), ..self }
+    }
+
+    pub fn error_key(self, key: impl Into<String>) -> Self {
+        Self { error: key.into(), ..self }
+    }
+
+    /// Read a response of the envelope as `(data, meta, error)`. `meta` is
+    /// the `x-teo-meta` header, which carries the meta of bare responses, and
+    /// `failed` whether the status is an error status.
+    pub(crate) fn open(&self, body: JsonValue, meta: Option<JsonValue>, failed: bool) -> (JsonValue, Option<JsonValue>, Option<JsonValue>) {
+        let mut object = match body {
+            JsonValue::Object(object) if failed || self.data.is_some() => object,
+            body => return (body, meta, None),
+        };
+        if failed {
+            return (JsonValue::Null, None, Some(object.remove(&self.error).unwrap_or(JsonValue::Null)));
+        }
+        let data = self.data.as_ref().and_then(|key| object.remove(key)).unwrap_or(JsonValue::Null);
+        (data, object.remove(&self.meta), None)
+    }
+
+    /// The envelope as `{ data, meta, error }` for the generated clients,
+    /// `data` is null for bare responses.
+    pub(crate) fn to_json(&self) -> JsonValue {
+        json!({ "data": self.data, "meta": self.meta, "error": self.error })
+    }
+}
+
+/// The response envelopes. Overrides are keyed like body limits, by
+/// `"Model.action"` for a single action, or by `"action"` for an action of
+/// every model or handler group.
+#[derive(Debug, Clone, Default)]
+pub struct Envelopes {
+    default: Envelope,
+    overrides: BTreeMap<String, Envelope>,
+}
+
+impl Envelopes {
+
+    pub(crate) fn set_default(&mut self, envelope: Envelope) {
+        self.default = envelope;
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, envelope: Envelope) {
+        self.overrides.insert(key.to_owned(), envelope);
+    }
+
+    /// Whether every response keeps the standard shape.
+    pub(crate) fn is_default(&self) -> bool {
+        self.default == Envelope::default() && self.overrides.values().all(|e| *e == Envelope::default())
+    }
+
+    /// The envelope of a handler, the most specific override wins.
+    pub(crate) fn envelope_for(&self, group_path: &str, handler_name: &str) -> &Envelope {
+        let full = if group_path.is_empty() { handler_name.to_owned() } else { format!("{}.{}", group_path, handler_name) };
+        self.overrides.get(&full)
+            .or_else(|| self.overrides.get(handler_name))
+            .unwrap_or(&self.default)
+    }
+
+    /// The envelopes as `{ default, overrides }` for the generated clients.
+    pub(crate) fn to_json(&self) -> JsonValue {
+        let overrides: Map<String, JsonValue> = self.overrides.iter().map(|(k, e)| (k.clone(), e.to_json())).collect();
+        json!({ "default": self.default.to_json(), "overrides": overrides })
+    }
+}
+
+/// Reshape a standard `{ data, meta }` or `{ error }` json response. Other
+/// responses, like files and JSON-RPC responses, are left as they are.
+pub(super) async fn apply_envelope(envelope: &Envelope, response: HttpResponse) -> HttpResponse {
+    if *envelope == Envelope::default() {
+        return response;
+    }
+    let (mut head, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body).await else {
+        return HttpResponse::InternalServerError().finish();
+    };
+    let mut object = match serde_json::from_slice(&bytes) {
+        Ok(JsonValue::Object(object)) if !object.is_empty() && object.keys().all(|k| matches!(k.as_str(), "data" | "meta" | "error")) => object,
+        _ => return head.set_body(bytes).map_into_boxed_body(),
+    };
+    let mut result = Map::new();
+    if let Some(error) = object.remove("error") {
+        result.insert(envelope.error.clone(), error);
+        return head.set_body(JsonValue::Object(result).to_string()).map_into_boxed_body();
+    }
+    let data = object.remove("data").unwrap_or(JsonValue::Null);
+    let meta = object.remove("meta");
+    let body = match &envelope.data {
+        Some(key) => {
+            result.insert(key.clone(), data);
+            if let Some(meta) = meta {
+                result.insert(envelope.meta.clone(), meta);
+            }
+            JsonValue::Object(result)
+        }
+        None => {
+            if let Some(value) = meta.and_then(|meta| HeaderValue::from_str(&meta.to_string()).ok()) {
+                head.headers_mut().insert(HeaderName::from_static(META_HEADER), value);
+            }
+            data
+        }
+    };
+    head.set_body(body.to_string()).map_into_boxed_body()
+}
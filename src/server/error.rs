@@ -2,38 +2,205 @@ use std::fmt::{Display, Formatter};
 use actix_http::body::BoxBody;
 use actix_http::StatusCode;
 use actix_web::{HttpResponse, ResponseError};
-use serde_json::json;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value as JsonValue};
 use teo_runtime::Value;
 use teo_result::Error;
+use crate::app::Ctx;
+use crate::server::i18n::MessageCatalog;
+
+/// An error with a machine-readable code, which is returned to the client as
+/// `code` beside the message. Message catalogs look up translations by code.
+/// The `UserError` is kept as the platform native object of the `Error` it's
+/// turned into, so the field errors of the `Error` are only field errors.
+///
+/// Errors of fields are added by their key paths, a field may have several.
+///
+/// ```ignore
+/// Err(UserError::new("PHONE_OR_EMAIL_REQUIRED", "phone or email is required")
+///     .with_field_error("phone", "REQUIRED", "phone is required")
+///     .with_field_error("email", "REQUIRED", "email is required"))?
+/// ```
+#[derive(Debug, Clone)]
+pub struct UserError {
+    pub code: String,
+    pub message: String,
+    pub status: u16,
+    pub fields: IndexMap<String, Vec<FieldError>>,
+}
+
+impl UserError {
+
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { code: code.into(), message: message.into(), status: 400, fields: IndexMap::new() }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add an error of the field at `key_path`, e.g. `posts.0.title`.
+    pub fn with_field_error(mut self, key_path: impl Into<String>, code: impl Into<String>, message: impl Into<String>) -> Self {
+        self.fields.entry(key_path.into()).or_default().push(FieldError { code: Some(code.into()), message: message.into() });
+        self
+    }
+}
+
+/// An error of a field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl From<UserError> for Error {
+
+    fn from(value: UserError) -> Self {
+        let mut error = Error::new(value.message.clone());
+        error.code = value.status;
+        // the messages of each field, for the ones reading `errors`
+        for (key, field_errors) in &value.fields {
+            let messages: Vec<&str> = field_errors.iter().map(|e| e.message.as_str()).collect();
+            error.errors.get_or_insert_with(Default::default).insert(key.clone(), messages.join(", "));
+        }
+        error.assign_platform_native_object(value);
+        error
+    }
+}
+
+/// The code attached to an error by `UserError`.
+pub(crate) fn error_code(error: &Error) -> Option<&str> {
+    error.platform_native_object::<UserError>().map(|user_error| user_error.code.as_str())
+}
+
+/// The errors of the fields of an error by key path. The field errors of a
+/// `UserError` keep their codes, the ones of other errors have none.
+pub(crate) fn field_errors(error: &Error) -> IndexMap<String, Vec<FieldError>> {
+    let user_fields = error.platform_native_object::<UserError>().map(|user_error| &user_error.fields);
+    let mut result = IndexMap::new();
+    for (key, message) in error.errors.iter().flatten() {
+        let errors = match user_fields.and_then(|fields| fields.get(key)) {
+            Some(errors) => errors.clone(),
+            None => vec![FieldError { code: None, message: message.clone() }],
+        };
+        result.insert(key.clone(), errors);
+    }
+    result
+}
+
+/// The `errors` of an error response in `format`, with the messages
+/// localized by `localize`.
+pub(crate) fn field_errors_json(errors: IndexMap<String, Vec<FieldError>>, format: ErrorFormat, localize: impl Fn(Option<&str>, &str) -> String) -> Map<String, JsonValue> {
+    errors.into_iter().map(|(key, errors)| {
+        let value = match format {
+            ErrorFormat::Legacy => json!(errors.iter().map(|e| localize(e.code.as_deref(), &e.message)).collect::<Vec<String>>().join(", ")),
+            ErrorFormat::Structured => JsonValue::Array(errors.iter().map(|e| {
+                let mut item = json!({ "message": localize(e.code.as_deref(), &e.message), "path": key_path(&key) });
+                if let Some(code) = &e.code {
+                    item["code"] = json!(code);
+                }
+                item
+            }).collect()),
+        };
+        (key, value)
+    }).collect()
+}
+
+/// The shape of error responses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Each key path of `errors` maps to a list of `{ code, message, path }`
+    /// objects, one for each error of the field. `code` is only there for
+    /// errors with codes, `path` is the key path split into keys and indices.
+    Structured,
+    /// Each key path of `errors` maps to a single message string, the shape
+    /// existing clients expect.
+    #[default]
+    Legacy,
+}
 
 #[derive(Debug)]
-pub(super) struct WrapError(Error);
+pub(super) struct WrapError {
+    error: Error,
+    catalog: Option<&'static MessageCatalog>,
+    request_id: Option<String>,
+}
+
+impl WrapError {
+
+    pub(super) fn localized(self, catalog: Option<&'static MessageCatalog>) -> Self {
+        Self { catalog, ..self }
+    }
+
+    pub(super) fn with_request_id(self, request_id: Option<String>) -> Self {
+        Self { request_id, ..self }
+    }
+
+    /// The error object of an error response.
+    pub(super) fn error_json(&self) -> JsonValue {
+        let value: Value = (&self.error).into();
+        let mut json_value: serde_json::Value = value.try_into().unwrap_or_else(|_| json!({ "type": "InternalServerError" }));
+        let code = error_code(&self.error);
+        let message = self.localize(code, &self.error.message);
+        if let Some(object) = json_value.as_object_mut() {
+            object.insert("message".to_owned(), json!(message));
+            if let Some(code) = code {
+                object.insert("code".to_owned(), json!(code));
+            }
+            object.remove("errors");
+            let errors = field_errors(&self.error);
+            if !errors.is_empty() {
+                let localize = |code: Option<&str>, message: &str| self.localize(code, message).to_owned();
+                object.insert("errors".to_owned(), JsonValue::Object(field_errors_json(errors, Ctx::error_format(), localize)));
+            }
+            if let Some(request_id) = &self.request_id {
+                object.insert("requestId".to_owned(), json!(request_id));
+            }
+        }
+        json_value
+    }
+
+    pub(super) fn status(&self) -> u16 {
+        self.error.code
+    }
+
+    fn localize<'a>(&'a self, code: Option<&str>, message: &'a str) -> &'a str {
+        self.catalog.and_then(|c| c.translate(code, message)).unwrap_or(message)
+    }
+}
+
+/// Split a key path like `posts.0.title` into `["posts", 0, "title"]`.
+fn key_path(key: &str) -> JsonValue {
+    JsonValue::Array(key.split('.').filter(|k| !k.is_empty()).map(|k| match k.parse::<u64>() {
+        Ok(index) => json!(index),
+        Err(_) => json!(k),
+    }).collect())
+}
 
 impl Display for WrapError {
 
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self.0, f)
+        Display::fmt(&self.error, f)
     }
 }
 
 impl From<Error> for WrapError {
 
     fn from(value: Error) -> Self {
-        Self(value)
+        Self { error: value, catalog: None, request_id: None }
     }
 }
 
 impl ResponseError for WrapError {
 
     fn status_code(&self) -> StatusCode {
-        StatusCode::from_u16(self.0.code).unwrap()
+        StatusCode::from_u16(self.error.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        let value: Value = (&self.0).into();
-        let json_value: serde_json::Value = value.try_into().unwrap();
         HttpResponse::Ok().status(self.status_code()).json(json!({
-            "error": json_value
+            "error": self.error_json()
         }))
     }
 }
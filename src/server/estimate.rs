@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use key_path::path;
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::Transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::model::Model;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::server::query_tag::tagged;
+use crate::utils::sql::quote;
+
+/// Below this many estimated records, records are counted exactly.
+const ESTIMATE_THRESHOLD: i64 = 100_000;
+
+/// Take `estimate` out of the arguments of a `count`.
+pub(super) fn take_estimate(args: &mut JsonValue) -> Result<bool> {
+    let Some(object) = args.as_object_mut() else { return Ok(false) };
+    match object.remove("estimate") {
+        None | Some(JsonValue::Bool(false)) => Ok(false),
+        Some(JsonValue::Bool(true)) if object.contains_key("select") => Err(Error::invalid_request_message("`estimate` can't be used with `select`")),
+        Some(JsonValue::Bool(true)) => Ok(true),
+        Some(_) => Err(Error::invalid_request_message("expect `estimate` to be a bool")),
+    }
+}
+
+/// `count(estimate: true)`. Counts all records of a model from the table
+/// statistics of the database, `pg_class.reltuples` on PostgreSQL and
+/// `information_schema.tables` on MySQL, and responds with
+/// `meta.estimated`. Counts with a `where` filter, small tables, tables
+/// without statistics and other databases are counted exactly.
+pub(super) async fn count(model: &'static Model, ctx: &request::Ctx) -> Result<Response> {
+    let r#where = ctx.body().get("where").cloned().unwrap_or(teon!({}));
+    let filtered = match &r#where {
+        Value::Dictionary(map) => !map.is_empty(),
+        Value::Null => false,
+        _ => true,
+    };
+    if !filtered {
+        if let Some(estimate) = estimate(model).await? {
+            if estimate >= ESTIMATE_THRESHOLD {
+                return Ok(Response::data_meta(Value::Int64(estimate), teon!({ "estimated": true })));
+            }
+        }
+    }
+    let count = ctx.transaction_ctx().count_objects(model, &teon!({ "where": r#where }), path![]).await?;
+    Ok(Response::data_meta(Value::Int64(count as i64), teon!({ "estimated": false })))
+}
+
+/// The estimated number of records of a model, `None` if the database has no
+/// statistics for its table.
+async fn estimate(model: &Model) -> Result<Option<i64>> {
+    let Some((transaction, database)) = connection_for(model).await? else { return Ok(None) };
+    let sql = match database {
+        // reltuples is -1 until the table is analyzed
+        Database::PostgreSQL => format!("SELECT reltuples::bigint AS estimate FROM pg_class WHERE oid = to_regclass({})", quote(&format!("\"{}\"", model.table_name.replace('"', "\"\"")), &database)?),
+        Database::MySQL => format!("SELECT table_rows AS estimate FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = {}", quote(&model.table_name, &database)?),
+        _ => return Ok(None),
+    };
+    let rows = transaction.query_raw(&Value::String(tagged(sql))).await?;
+    let estimate = match rows {
+        Value::Array(rows) => rows.into_iter().next().and_then(|row| row.get("estimate").or_else(|| row.get("ESTIMATE")).and_then(int)),
+        _ => None,
+    };
+    Ok(estimate.filter(|e| *e >= 0))
+}
+
+/// An integer read from a raw query row, whatever type the database returns.
+pub(crate) fn int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(i) => Some(*i as i64),
+        Value::Int64(i) => Some(*i),
+        Value::Float(f) => Some(*f as i64),
+        Value::Decimal(d) => d.to_string().parse().ok(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The connection of a model and its database, `None` if the connector
+/// doesn't run SQL or MongoDB commands, like the memory connector.
+async fn connection_for(model: &Model) -> Result<Option<(Arc<dyn Transaction>, Database)>> {
+    let path = model.path();
+    let namespace_path: Vec<String> = path[..path.len() - 1].iter().map(|s| s.to_string()).collect();
+    let conn_ctx = Ctx::conn_ctx();
+    let (connection_path, connection) = conn_ctx.connections_iter()
+        .filter(|(connection_path, _)| namespace_path.starts_with(connection_path))
+        .max_by_key(|(connection_path, _)| connection_path.len())
+        .ok_or_else(|| Error::new("no connection is found for count estimates"))?;
+    let namespace = conn_ctx.namespace().namespace_at_path(&connection_path.iter().map(AsRef::as_ref).collect()).ok_or_else(|| Error::not_found())?;
+    let connector = namespace.connector.as_ref().ok_or_else(|| Error::new("no connector is found for count estimates"))?;
+    if !is_provider_connector(connector) {
+        return Ok(None);
+    }
+    Ok(Some((connection.no_transaction().await?, connector.provider.clone())))
+}
@@ -0,0 +1,51 @@
+use ring::digest::{digest, SHA256};
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::request;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::body::BodyInner;
+use teo_runtime::response::Response;
+use crate::server::action::with_body;
+use crate::server::output;
+
+/// A weak ETag of the record in a `findUnique` response. The tag is derived
+/// from the returned record, so it changes whenever a returned field changes,
+/// including `updatedAt` and version fields.
+pub(super) fn response_etag(response: &Response) -> Option<String> {
+    let BodyInner::Teon(value) = response.body().inner.as_ref() else { return None };
+    let json_value = JsonValue::try_from(value).ok()?;
+    let data = json_value.get("data")?;
+    if data.is_null() {
+        return None;
+    }
+    let hash: String = digest(&SHA256, data.to_string().as_bytes()).as_ref().iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    Some(format!("W/\"{}\"", hash))
+}
+
+/// Whether an `If-None-Match` or `If-Match` header value matches `etag`. Tags
+/// are compared weakly.
+pub(super) fn etag_matches(header: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    header.split(',').any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// Check the `If-Match` precondition of an update or delete against the
+/// current record, read with the transaction of `ctx`. Fails with 412 if the
+/// record has been changed since the client fetched it.
+pub(super) async fn check_if_match(if_match: &str, model: &'static Model, json_body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<()> {
+    let action = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::internal_server_error_message("findUnique is not found"))?;
+    let body = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "where": json_body.get("where").cloned().unwrap_or(JsonValue::Null) }), main_namespace)?;
+    let ctx = with_body(ctx, body, ctx.transaction_ctx());
+    let current = output::find(model, "findUnique", &ctx).await.ok().and_then(|response| response_etag(&response));
+    match current {
+        Some(current) if etag_matches(if_match, &current) => Ok(()),
+        _ => {
+            let mut error = Error::new("precondition failed: the record has been modified");
+            error.code = 412;
+            Err(error)
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use key_path::path;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::database::database::Database;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::Ctx;
+
+/// How many records a page holds unless `take` says otherwise.
+pub(crate) const DEFAULT_PAGE_SIZE: usize = 1000;
+
+/// The most records a page holds.
+const MAX_PAGE_SIZE: usize = 10000;
+
+/// The arguments of `findMany` which conflict with keyset iteration.
+const CONFLICTING_ARGUMENTS: [&str; 6] = ["orderBy", "skip", "cursor", "distinct", "pageSize", "pageNumber"];
+
+/// `exportCursor` of a `findMany`, for scanning a whole table page by page.
+/// `true` starts a scan and a cursor token of a previous page resumes it.
+#[derive(Debug, Clone)]
+pub(super) struct ExportCursor {
+    after: Option<String>,
+    take: usize,
+}
+
+impl ExportCursor {
+
+    /// Take `exportCursor` out of the arguments of a `findMany`.
+    pub(super) fn take(args: &mut JsonValue) -> Result<Option<Self>> {
+        let Some(object) = args.as_object_mut() else { return Ok(None) };
+        let after = match object.remove("exportCursor") {
+            None | Some(JsonValue::Bool(false)) => return Ok(None),
+            Some(JsonValue::Bool(true)) => None,
+            Some(JsonValue::String(token)) => Some(token),
+            Some(_) => Err(Error::invalid_request_message("expect `exportCursor` to be true or a cursor token"))?,
+        };
+        if let Some(argument) = CONFLICTING_ARGUMENTS.iter().find(|a| object.contains_key(**a)) {
+            Err(Error::invalid_request_message(format!("`{}` can't be used with `exportCursor`, records are ordered by the primary key", argument)))?
+        }
+        let take = match object.remove("take") {
+            Some(take) => take.as_u64().filter(|t| *t > 0 && *t as usize <= MAX_PAGE_SIZE).ok_or_else(|| {
+                Error::invalid_request_message(format!("expect `take` to be between 1 and {} with `exportCursor`", MAX_PAGE_SIZE))
+            })? as usize,
+            None => DEFAULT_PAGE_SIZE,
+        };
+        Ok(Some(Self { after, take }))
+    }
+
+    /// Respond with a page of records and the cursor of the next page in
+    /// `meta.cursor`, null after the last page. The page is read in one
+    /// snapshot.
+    pub(super) async fn respond(&self, model: &'static Model, args: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+        let (args, after, take) = (args.clone(), self.after.clone(), self.take);
+        let (records, cursor) = ctx.transaction_ctx().run_transaction(move |ctx: transaction::Ctx| {
+            let (args, after) = (args.clone(), after.clone());
+            async move {
+                begin_snapshot(model, &ctx).await?;
+                let page = export_page(model, &args, after.as_deref(), take, main_namespace, &ctx).await?;
+                let mut records = vec![];
+                for object in &page.objects {
+                    records.push(object.to_teon().await?);
+                }
+                Ok((records, page.cursor))
+            }
+        }).await?;
+        Ok(Response::data_meta(Value::Array(records), teon!({ "cursor": cursor.map_or(Value::Null, Value::String) })))
+    }
+}
+
+/// A page of a scan.
+pub(crate) struct ExportPage {
+    pub(crate) objects: Vec<Object>,
+    /// The token resuming the scan after this page, `None` after the last
+    /// page.
+    pub(crate) cursor: Option<String>,
+}
+
+/// Read the page of a scan after the cursor token `after`, or the first
+/// page. Records are ordered by the primary key and each page starts after
+/// the key of the last record of the previous one, so records which aren't
+/// changed during the scan are neither skipped nor repeated, and a scan can
+/// be resumed from any token.
+pub(crate) async fn export_page(model: &'static Model, args: &JsonValue, after: Option<&str>, take: usize, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<ExportPage> {
+    let keys: Vec<String> = model.primary_index().ok_or_else(|| Error::new(format!("`{}` has no primary key", model.path().join("."))))?
+        .items.iter().map(|item| item.field.clone()).collect();
+    let mut args = args.as_object().cloned().unwrap_or_default();
+    let finder = args.remove("where").unwrap_or(json!({}));
+    args.insert("where".to_owned(), match after {
+        Some(token) => json!({ "AND": [finder, after_key(&keys, &decode(model, token)?)] }),
+        None => finder,
+    });
+    args.insert("orderBy".to_owned(), JsonValue::Array(keys.iter().map(|key| json!({ key.as_str(): "asc" })).collect()));
+    args.insert("take".to_owned(), json!(take + 1));
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &JsonValue::Object(args), main_namespace)?;
+    let mut objects: Vec<Object> = ctx.find_many(model, &input, None, path![]).await?;
+    let cursor = if objects.len() > take {
+        objects.truncate(take);
+        Some(encode(model, objects.last().unwrap())?)
+    } else {
+        None
+    };
+    Ok(ExportPage { objects, cursor })
+}
+
+/// Read the rest of a transaction in one snapshot where the database needs
+/// to be told: PostgreSQL reads each statement in its own snapshot by
+/// default. MySQL transactions are repeatable reads and SQLite transactions
+/// serializable already.
+pub(crate) async fn begin_snapshot(model: &'static Model, ctx: &transaction::Ctx) -> Result<()> {
+    if model_database(model).map_or(false, |database| database.is_pg()) {
+        let transaction = ctx.transaction_for_model(model).await?;
+        transaction.query_raw(&Value::String("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY".to_owned())).await?;
+    }
+    Ok(())
+}
+
+/// The records after a primary key, in primary key order.
+fn after_key(keys: &Vec<String>, last: &Map<String, JsonValue>) -> JsonValue {
+    let branches: Vec<JsonValue> = (0..keys.len()).map(|index| {
+        let mut branch = Map::new();
+        for key in &keys[..index] {
+            branch.insert(key.clone(), last.get(key).cloned().unwrap_or(JsonValue::Null));
+        }
+        branch.insert(keys[index].clone(), json!({ "gt": last.get(&keys[index]).cloned().unwrap_or(JsonValue::Null) }));
+        JsonValue::Object(branch)
+    }).collect();
+    json!({ "OR": branches })
+}
+
+fn encode(model: &Model, object: &Object) -> Result<String> {
+    let token = json!({ "model": model.path().join("."), "after": JsonValue::try_from(&object.identifier())? });
+    Ok(URL_SAFE_NO_PAD.encode(token.to_string()))
+}
+
+fn decode(model: &Model, token: &str) -> Result<Map<String, JsonValue>> {
+    let invalid = || Error::invalid_request_message("the export cursor is invalid");
+    let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+    let token: JsonValue = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if token.get("model").and_then(|m| m.as_str()) != Some(model.path().join(".").as_str()) {
+        Err(Error::invalid_request_message(format!("the export cursor isn't a cursor of `{}`", model.path().join("."))))?
+    }
+    token.get("after").and_then(|a| a.as_object()).cloned().ok_or_else(invalid)
+}
+
+/// The database of the connector of a model, the closest one up the
+/// namespaces.
+fn model_database(model: &Model) -> Option<Database> {
+    let path = model.path();
+    let namespace_path: Vec<&str> = path[..path.len() - 1].iter().map(AsRef::as_ref).collect();
+    (0..=namespace_path.len()).rev().find_map(|length| {
+        Ctx::main_namespace().namespace_at_path(&namespace_path[..length].to_vec())?.connector.as_ref().map(|c| c.provider.clone())
+    })
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::server::output::output_record;
+
+/// The most ids a `findByIds` request takes.
+const MAX_IDS: usize = 1000;
+
+/// The `findByIds` action of a model. The body is `{ ids, select?, include? }`,
+/// `ids` are primary key values, or objects of them for compound primary
+/// keys. The records are fetched in one query and responded in the order of
+/// `ids`, with null for the missing ones, e.g. to hydrate search hits. A
+/// `findByIds` handler declared by the model replaces it.
+pub(super) async fn find_by_ids(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let ids = body.get("ids").and_then(|i| i.as_array()).ok_or_else(|| Error::invalid_request_message("expect `ids` to be an array"))?;
+    if ids.len() > MAX_IDS {
+        Err(Error::invalid_request_message(format!("expect at most {} ids", MAX_IDS)))?
+    }
+    Ok(Response::data(Value::Array(records_by_ids(model, ids, body, main_namespace, ctx).await?)))
+}
+
+/// Fetch the records of `ids` in one query with the `select` and `include`
+/// of `body` and serialize them like find actions do, in the order of `ids`
+/// with null for the missing ones.
+pub(crate) async fn records_by_ids(model: &'static Model, ids: &Vec<JsonValue>, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Vec<Value>> {
+    let keys: Vec<String> = model.primary_index().ok_or_else(|| Error::new(format!("`{}` has no primary key", model.path().join("."))))?
+        .items.iter().map(|item| item.field.clone()).collect();
+    let finders = ids.iter().map(|id| match (id, keys.as_slice()) {
+        (JsonValue::Object(_), _) => Ok(id.clone()),
+        (_, [key]) => Ok(json!({ key.as_str(): id })),
+        _ => Err(Error::invalid_request_message(format!("expect each id of `{}` to be an object of {}", model.path().join("."), keys.join(", ")))),
+    }).collect::<Result<Vec<JsonValue>>>()?;
+    let mut finder = json!({ "where": { "OR": finders.clone() } });
+    for key in ["select", "include"] {
+        if let Some(value) = body.get(key) {
+            finder[key] = value.clone();
+        }
+    }
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &finder, main_namespace)?;
+    let objects: Vec<Object> = if ids.is_empty() { vec![] } else { ctx.transaction_ctx().find_many(model, &input, Some(ctx.clone()), path![]).await? };
+    let mut found = HashMap::new();
+    for object in objects {
+        found.insert(id_key(&keys, &JsonValue::try_from(&object.identifier())?), object);
+    }
+    let mut records = vec![];
+    for (index, finder) in finders.iter().enumerate() {
+        let Some(object) = found.get(&id_key(&keys, finder)) else {
+            records.push(Value::Null);
+            continue
+        };
+        records.push(output_record(model, object, index, ctx).await?);
+    }
+    Ok(records)
+}
+
+/// The primary key values of a record or an id, so that `5` and `"5"`
+/// match.
+fn id_key(keys: &Vec<String>, finder: &JsonValue) -> String {
+    keys.iter().map(|key| match finder.get(key) {
+        Some(JsonValue::String(string)) => string.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    }).collect::<Vec<_>>().join("\u{0}")
+}
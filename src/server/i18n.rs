@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use teo_result::{Error, Result};
+
+/// Localized error messages, one catalog per locale.
+///
+/// Catalogs are loaded from `<locale>.json` files in a directory. Each file is
+/// a flat object which maps an error code or an original message to the
+/// localized message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageCatalogs {
+    catalogs: HashMap<String, MessageCatalog>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageCatalog {
+    messages: HashMap<String, String>,
+}
+
+impl MessageCatalogs {
+
+    /// Load all catalogs in `dir`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir).map_err(|e| Error::new(format!("cannot read message catalogs at \"{}\": {}", dir.display(), e)))?;
+        let mut catalogs = HashMap::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map_or(true, |e| e != "json") {
+                continue
+            }
+            let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            let content = fs::read_to_string(&path).map_err(|e| Error::new(format!("cannot read \"{}\": {}", path.display(), e)))?;
+            let messages: HashMap<String, String> = serde_json::from_str(&content).map_err(|e| Error::new(format!("invalid message catalog \"{}\": {}", path.display(), e)))?;
+            catalogs.insert(locale.to_lowercase(), MessageCatalog { messages });
+        }
+        Ok(Self { catalogs })
+    }
+
+    /// Select the catalog for an `Accept-Language` header value. Languages are
+    /// tried by quality, a regional locale like `zh-TW` falls back to `zh`.
+    pub fn negotiate(&self, accept_language: Option<&str>) -> Option<&MessageCatalog> {
+        accept_languages(accept_language?).iter().find_map(|tag| {
+            self.catalogs.get(tag).or_else(|| tag.split('-').next().and_then(|primary| self.catalogs.get(primary)))
+        })
+    }
+}
+
+/// The lowercased language tags of an `Accept-Language` header value, by
+/// quality.
+pub(crate) fn accept_languages(accept_language: &str) -> Vec<String> {
+    let mut languages: Vec<(String, f32)> = accept_language.split(',').filter_map(|part| {
+        let mut segments = part.trim().split(';');
+        let tag = segments.next()?.trim().to_lowercase();
+        let quality = segments.find_map(|s| s.trim().strip_prefix("q=").and_then(|q| q.parse().ok())).unwrap_or(1.0);
+        if tag.is_empty() || tag == "*" { None } else { Some((tag, quality)) }
+    }).collect();
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
+
+impl MessageCatalog {
+
+    /// Translate a message, looking up the error code first.
+    pub fn translate(&self, code: Option<&str>, message: &str) -> Option<&str> {
+        code.and_then(|code| self.messages.get(code)).or_else(|| self.messages.get(message)).map(|s| s.as_str())
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use actix_http::StatusCode;
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use actix_web::web::Bytes;
+use once_cell::sync::Lazy;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::utils::hex::sha256_hex;
+
+/// The default time a response is kept for replaying.
+pub const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone)]
+enum Entry {
+    InFlight { fingerprint: String },
+    Done { fingerprint: String, status: StatusCode, content_type: Option<String>, body: Bytes, expires: Instant },
+}
+
+/// Responses of requests with an `Idempotency-Key` header. The store is kept in
+/// process, so apps running several instances should route requests of a
+/// client to the same instance.
+static STORE: Lazy<Mutex<HashMap<String, Entry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// What to do with an incoming request.
+pub(super) enum Idempotency {
+    /// The key is new, run the handler and `finish` afterwards.
+    Proceed(String),
+    /// The key is known, return the stored response.
+    Replay(HttpResponse),
+}
+
+/// Look up an idempotency key. `scope` separates keys of different handlers
+/// and `credential`, the `Authorization` header of the request, keys of
+/// different clients, so that a response is only replayed to the client it
+/// was made for. `body` is the request body as it was sent.
+pub(super) fn begin(scope: &str, credential: Option<&str>, key: &str, body: &JsonValue) -> Result<Idempotency> {
+    let store_key = format!("{}:{}:{}", scope, credential.map(|c| sha256_hex(c.as_bytes())).unwrap_or_default(), key);
+    let fingerprint = fingerprint(body);
+    let mut store = STORE.lock().unwrap();
+    let now = Instant::now();
+    store.retain(|_, entry| match entry {
+        Entry::Done { expires, .. } => *expires > now,
+        Entry::InFlight { .. } => true,
+    });
+    match store.get(&store_key) {
+        None => {
+            store.insert(store_key.clone(), Entry::InFlight { fingerprint });
+            Ok(Idempotency::Proceed(store_key))
+        }
+        Some(Entry::InFlight { fingerprint: f } | Entry::Done { fingerprint: f, .. }) if *f != fingerprint => {
+            Err(error_with_code("idempotency key is reused with a different request body", 422))
+        }
+        Some(Entry::InFlight { .. }) => Err(error_with_code("a request with this idempotency key is in progress", 409)),
+        Some(Entry::Done { status, content_type, body, .. }) => {
+            let mut builder = HttpResponse::build(*status);
+            if let Some(content_type) = content_type {
+                builder.content_type(content_type.as_str());
+            }
+            builder.insert_header(("Idempotent-Replayed", "true"));
+            Ok(Idempotency::Replay(builder.body(body.clone())))
+        }
+    }
+}
+
+/// Store the response of a request which began with `Idempotency::Proceed`.
+/// Failed requests are not stored, so that they can be retried.
+pub(super) async fn finish(store_key: String, response: HttpResponse, window: Duration) -> HttpResponse {
+    let status = response.status();
+    if !status.is_success() {
+        STORE.lock().unwrap().remove(&store_key);
+        return response;
+    }
+    let content_type = response.headers().get("Content-Type").and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+    let (head, body) = response.into_parts();
+    let body = match to_bytes(body).await {
+        Ok(body) => body,
+        Err(_) => {
+            STORE.lock().unwrap().remove(&store_key);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let mut store = STORE.lock().unwrap();
+    if let Some(Entry::InFlight { fingerprint }) = store.remove(&store_key) {
+        store.insert(store_key, Entry::Done { fingerprint, status, content_type, body: body.clone(), expires: Instant::now() + window });
+    }
+    head.set_body(body).map_into_boxed_body()
+}
+
+/// Forget an in flight key after the handler failed.
+pub(super) fn abort(store_key: &str) {
+    STORE.lock().unwrap().remove(store_key);
+}
+
+fn fingerprint(body: &JsonValue) -> String {
+    sha256_hex(body.to_string().as_bytes())
+}
+
+fn error_with_code(message: &str, code: u16) -> Error {
+    let mut error = Error::new(message);
+    error.code = code;
+    error
+}
@@ -0,0 +1,117 @@
+use chrono::Utc;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::middleware::next::Next;
+use teo_runtime::model::Object;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::main_connection;
+use crate::message::impersonation_message;
+use crate::server::error::UserError;
+use crate::server::permissions::has_role;
+use crate::server::query_tag::tagged;
+use crate::server::request_id::REQUEST_ID_HEADER;
+use crate::utils::sql::quote;
+
+/// The header carrying the unique where input of the identity to act as.
+pub(crate) const ACT_AS_HEADER: &str = "x-teo-act-as";
+
+/// The table impersonated requests are recorded in.
+pub(crate) const IMPERSONATIONS_TABLE: &str = "_teo_impersonations";
+
+/// Run `next` through the middleware stack of a namespace. Right after the
+/// middlewares resolve the identity of the request, it's replaced with the
+/// identity the request acts as, so handlers, pipelines and permission
+/// checks all see the same identity.
+pub(super) async fn call_middlewares(namespace: &'static Namespace, ctx: request::Ctx, next: &dyn Next) -> Result<Response> {
+    namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| {
+        let response = next.call(ctx.clone());
+        async move {
+            effective_identity(&ctx).await?;
+            response.await
+        }
+    }).await
+}
+
+/// The identity a request acts with. Without an `X-Teo-Act-As` header, this
+/// is the signed in identity. With it, the signed in identity must have the
+/// impersonation role, and the identity found with the header on the same
+/// model replaces it; the signed in identity is kept as `principal` and the
+/// request is recorded in the audit table. `call_middlewares` applies this
+/// to every request, later calls return the identity already in place.
+pub(crate) async fn effective_identity(ctx: &request::Ctx) -> Result<Option<Object>> {
+    let local_objects = ctx.request().local_objects();
+    let identity = local_objects.get::<Object>("identity").cloned();
+    if local_objects.get::<Object>("principal").is_some() {
+        return Ok(identity);
+    }
+    let Some(act_as) = ctx.request().headers().get(ACT_AS_HEADER).map(|h| h.to_owned()) else { return Ok(identity) };
+    let Some(principal) = identity else {
+        Err(UserError::new("IDENTITY_REQUIRED", "identity is required to act as another identity").with_status(401))?
+    };
+    let allowed = match Ctx::impersonation_role() {
+        Some(role) => has_role(&principal, role)?,
+        None => false,
+    };
+    if !allowed {
+        Err(UserError::new("IMPERSONATION_NOT_ALLOWED", "identity is not allowed to act as another identity").with_status(403))?
+    }
+    let finder: JsonValue = serde_json::from_str(&act_as).map_err(|_| Error::invalid_request_message("expect `X-Teo-Act-As` to be a json object"))?;
+    let model = principal.model();
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), Ctx::main_namespace())?;
+    let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+    let Some(target) = found.into_iter().next() else {
+        Err(Error::invalid_request_message("the identity to act as is not found"))?
+    };
+    let request_id = ctx.request().headers().get(REQUEST_ID_HEADER).unwrap_or("").to_owned();
+    record_impersonation(&describe(&principal)?, &describe(&target)?, &request_id, ctx).await?;
+    local_objects.insert("principal", principal);
+    local_objects.insert("identity", target.clone());
+    Ok(Some(target))
+}
+
+/// Record an impersonated request in the audit table, outside the
+/// transaction of the request, so the record is kept if the request fails.
+async fn record_impersonation(principal: &str, identity: &str, request_id: &str, ctx: &request::Ctx) -> Result<()> {
+    impersonation_message(principal, identity, request_id);
+    let (connection, database) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(tagged(format!(
+        "INSERT INTO {} (id, principal, identity, request_id, method, path, created_at) VALUES ({}, {}, {}, {}, {}, {}, {})",
+        IMPERSONATIONS_TABLE,
+        quote(&uuid::Uuid::new_v4().to_string(), &database)?,
+        quote(principal, &database)?,
+        quote(identity, &database)?,
+        quote(request_id, &database)?,
+        quote(&ctx.request().method().to_string(), &database)?,
+        quote(ctx.request().path(), &database)?,
+        Utc::now().timestamp_millis(),
+    )))).await?;
+    Ok(())
+}
+
+/// Create the impersonation audit table if impersonation is enabled.
+pub(crate) async fn create_impersonations_table() -> Result<()> {
+    if Ctx::impersonation_role().is_none() {
+        return Ok(());
+    }
+    let (connection, _) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (id VARCHAR(64) PRIMARY KEY, principal VARCHAR(255) NOT NULL, identity VARCHAR(255) NOT NULL, request_id VARCHAR(255) NOT NULL, method VARCHAR(16) NOT NULL, path VARCHAR(2048) NOT NULL, created_at BIGINT NOT NULL)",
+        IMPERSONATIONS_TABLE,
+    ))).await?;
+    Ok(())
+}
+
+fn describe(object: &Object) -> Result<String> {
+    Ok(format!("{}{}", object.model().path().join("."), JsonValue::try_from(&object.identifier())?))
+}
@@ -0,0 +1,97 @@
+use actix_web::HttpRequest;
+use serde_json::{json, Value as JsonValue};
+use teo_result::Error;
+use teo_runtime::connection;
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::namespace::Namespace;
+use crate::server::batch::run_action;
+use crate::server::error::WrapError;
+use crate::server::i18n::MessageCatalog;
+
+/// The path of the JSON-RPC endpoint.
+pub(super) const JSON_RPC_PATH: &str = "/_rpc";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Handle a JSON-RPC 2.0 request or batch. The method of a call is the model
+/// path and the action, e.g. `blog.Post.findMany`, and the params are the
+/// action arguments. The result is the body of the action response. Errors
+/// carry the usual error object as `data`. Returns `None` if the request
+/// only contains notifications.
+pub(super) async fn json_rpc(http_request: &HttpRequest, body: Result<JsonValue, Error>, main_namespace: &'static Namespace, catalog: Option<&'static MessageCatalog>) -> Option<JsonValue> {
+    let body = match body {
+        Ok(body) => body,
+        Err(err) => return Some(error_response(JsonValue::Null, PARSE_ERROR, &err.message, None)),
+    };
+    match body {
+        JsonValue::Array(calls) if calls.is_empty() => Some(error_response(JsonValue::Null, INVALID_REQUEST, "empty batch", None)),
+        JsonValue::Array(calls) => {
+            let mut responses = vec![];
+            for call_body in calls {
+                if let Some(response) = call(http_request, call_body, main_namespace, catalog).await {
+                    responses.push(response);
+                }
+            }
+            if responses.is_empty() { None } else { Some(JsonValue::Array(responses)) }
+        }
+        body => call(http_request, body, main_namespace, catalog).await,
+    }
+}
+
+async fn call(http_request: &HttpRequest, body: JsonValue, main_namespace: &'static Namespace, catalog: Option<&'static MessageCatalog>) -> Option<JsonValue> {
+    let id = body.get("id").cloned();
+    let response_id = id.clone().unwrap_or(JsonValue::Null);
+    if body.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+        return Some(error_response(response_id, INVALID_REQUEST, "expect `jsonrpc` to be \"2.0\"", None));
+    }
+    let Some(method) = body.get("method").and_then(|m| m.as_str()) else {
+        return Some(error_response(response_id, INVALID_REQUEST, "expect `method` to be a string", None));
+    };
+    let found = method.rsplit_once('.').filter(|(model, action)| {
+        main_namespace.model_at_path(&model.split('.').collect()).is_some() && builtin_action_handler_from_name(action).is_some()
+    });
+    let Some((model, action)) = found else {
+        return Some(error_response(response_id, METHOD_NOT_FOUND, &format!("method `{}` is not found", method), None));
+    };
+    let params = body.get("params").cloned().unwrap_or(json!({}));
+    if !params.is_object() {
+        return Some(error_response(response_id, INVALID_PARAMS, "expect `params` to be an object", None));
+    }
+    let item = json!({ "model": model, "action": action, "body": params });
+    // a call runs in a transaction of its own, like a batch of one action
+    let transaction_ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+    let http_request = http_request.clone();
+    let result = transaction_ctx.run_transaction(move |transaction_ctx: transaction::Ctx| {
+        let (http_request, item) = (http_request.clone(), item.clone());
+        async move { run_action(&http_request, &item, main_namespace, transaction_ctx).await }
+    }).await;
+    // notifications are run, but never answered
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(err) => {
+            let error = WrapError::from(err).localized(catalog);
+            let code = match error.status() {
+                400 => INVALID_PARAMS,
+                404 => METHOD_NOT_FOUND,
+                _ => SERVER_ERROR,
+            };
+            let data = error.error_json();
+            let message = data.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_owned();
+            error_response(id, code, &message, Some(data))
+        }
+    })
+}
+
+fn error_response(id: JsonValue, code: i64, message: &str, data: Option<JsonValue>) -> JsonValue {
+    let mut error = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "error": error, "id": id })
+}
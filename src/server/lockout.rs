@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use actix_web::HttpRequest;
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::app::Ctx;
+use crate::server::client_ip::CLIENT_IP_HEADER;
+use crate::server::error::UserError;
+
+/// The header carrying the CAPTCHA token of a sign in.
+pub const CAPTCHA_HEADER: &str = "X-Teo-Captcha";
+
+/// Limits on failed sign ins.
+///
+/// An account is locked for `cooldown` after `max_attempts_per_account`
+/// failures within `window`, and an IP address after
+/// `max_attempts_per_ip`. With `captcha_after`, sign ins to an account with
+/// that many recent failures must carry a CAPTCHA token in the
+/// `X-Teo-Captcha` header, which is checked by the CAPTCHA verifier.
+#[derive(Debug, Clone)]
+pub struct SignInLockout {
+    pub max_attempts_per_account: u32,
+    pub max_attempts_per_ip: u32,
+    pub window: Duration,
+    pub cooldown: Duration,
+    pub captcha_after: Option<u32>,
+    /// The credentials which don't identify the account, e.g. the password.
+    pub secret_fields: Vec<String>,
+}
+
+impl Default for SignInLockout {
+
+    fn default() -> Self {
+        Self {
+            max_attempts_per_account: 5,
+            max_attempts_per_ip: 20,
+            window: Duration::from_secs(15 * 60),
+            cooldown: Duration::from_secs(15 * 60),
+            captcha_after: None,
+            secret_fields: vec!["password".to_owned()],
+        }
+    }
+}
+
+/// Verifies the CAPTCHA token of a sign in, given the token and the IP
+/// address of the request.
+pub trait CaptchaVerifier: Send + Sync {
+    fn verify(&self, token: String, ip: String) -> BoxFuture<'static, Result<bool>>;
+}
+
+impl<F, Fut> CaptchaVerifier for F where
+    F: Fn(String, String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<bool>> + Send + 'static {
+    fn verify(&self, token: String, ip: String) -> BoxFuture<'static, Result<bool>> {
+        Box::pin(self(token, ip))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    failures: Vec<Instant>,
+    locked_until: Option<Instant>,
+}
+
+/// Failed sign ins by account and by IP address. The counters are kept in
+/// process, like idempotency keys.
+static COUNTERS: Lazy<Mutex<HashMap<String, Counter>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A sign in which passed the lockout checks. Report the outcome with
+/// `succeed` or `fail`.
+pub(super) struct SignInAttempt {
+    account_key: String,
+    ip_key: String,
+}
+
+/// Check the lockouts of the account and the IP address of a sign in.
+/// Fails with 429 if the IP address is locked or a CAPTCHA is missing, and
+/// with 423 if the account is locked.
+pub(super) async fn begin_sign_in(http_request: &HttpRequest, model_path: &str, json_body: &JsonValue) -> Result<Option<SignInAttempt>> {
+    let Some(policy) = Ctx::sign_in_lockout() else { return Ok(None) };
+    let ip = http_request.headers().get(CLIENT_IP_HEADER).and_then(|v| v.to_str().ok()).unwrap_or("").to_owned();
+    let mut account = Map::new();
+    if let Some(credentials) = json_body.get("credentials").and_then(|c| c.as_object()) {
+        for (key, value) in credentials {
+            if !policy.secret_fields.contains(key) {
+                account.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    let attempt = SignInAttempt {
+        account_key: format!("account:{}:{}", model_path, JsonValue::Object(account)),
+        ip_key: format!("ip:{}", ip),
+    };
+    let account_failures = {
+        let mut counters = COUNTERS.lock().unwrap();
+        let now = Instant::now();
+        if let Some(retry_after) = locked_for(&mut counters, &attempt.ip_key, now) {
+            Err(UserError::new("TOO_MANY_SIGN_IN_ATTEMPTS", format!("too many sign in attempts, retry in {} seconds", retry_after.as_secs() + 1)).with_status(429))?
+        }
+        if let Some(retry_after) = locked_for(&mut counters, &attempt.account_key, now) {
+            Err(UserError::new("ACCOUNT_LOCKED", format!("account is locked, retry in {} seconds", retry_after.as_secs() + 1)).with_status(423))?
+        }
+        counters.get_mut(&attempt.account_key).map_or(0, |counter| {
+            counter.failures.retain(|failure| now.duration_since(*failure) < policy.window);
+            counter.failures.len() as u32
+        })
+    };
+    if policy.captcha_after.map_or(false, |after| account_failures >= after) {
+        let token = http_request.headers().get(CAPTCHA_HEADER).and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+        let verifier = Ctx::captcha_verifier().ok_or_else(|| Error::new("captcha verifier is not configured"))?;
+        let passed = match token {
+            Some(token) => verifier.verify(token, ip).await?,
+            None => false,
+        };
+        if !passed {
+            Err(UserError::new("CAPTCHA_REQUIRED", "a valid captcha is required to sign in").with_status(429))?
+        }
+    }
+    Ok(Some(attempt))
+}
+
+impl SignInAttempt {
+
+    /// Reset the failures of the account.
+    pub(super) fn succeed(self) {
+        COUNTERS.lock().unwrap().remove(&self.account_key);
+    }
+
+    /// Count a failure for both the account and the IP address.
+    pub(super) fn fail(self) {
+        let Some(policy) = Ctx::sign_in_lockout() else { return };
+        let mut counters = COUNTERS.lock().unwrap();
+        let now = Instant::now();
+        counters.retain(|_, counter| counter.locked_until.map_or(false, |until| until > now) || counter.failures.iter().any(|f| now.duration_since(*f) < policy.window));
+        for (key, max_attempts) in [(&self.account_key, policy.max_attempts_per_account), (&self.ip_key, policy.max_attempts_per_ip)] {
+            let counter = counters.entry(key.clone()).or_default();
+            counter.failures.retain(|failure| now.duration_since(*failure) < policy.window);
+            counter.failures.push(now);
+            if counter.failures.len() as u32 >= max_attempts {
+                counter.failures.clear();
+                counter.locked_until = Some(now + policy.cooldown);
+            }
+        }
+    }
+}
+
+fn locked_for(counters: &mut HashMap<String, Counter>, key: &str, now: Instant) -> Option<Duration> {
+    let counter = counters.get_mut(key)?;
+    match counter.locked_until {
+        Some(until) if until > now => Some(until - now),
+        Some(_) => {
+            counter.locked_until = None;
+            None
+        }
+        None => None,
+    }
+}
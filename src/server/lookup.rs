@@ -0,0 +1,29 @@
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// Find the records of a `findMany` json input with a transaction. With a
+/// request ctx, the read permissions of the records are checked against the
+/// identity of the request.
+pub(super) async fn find(model: &'static Model, args: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx, req_ctx: Option<&request::Ctx>) -> Result<Vec<Object>> {
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, args, main_namespace)?;
+    ctx.find_many(model, &input, req_ctx.cloned(), path![]).await
+}
+
+/// Find the record of a unique where json input with a transaction, like
+/// `find`.
+pub(super) async fn find_unique(model: &'static Model, finder: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx, req_ctx: Option<&request::Ctx>) -> Result<Option<Object>> {
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), main_namespace)?;
+    let found: Vec<Object> = ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), req_ctx.cloned(), path![]).await?;
+    Ok(found.into_iter().next())
+}
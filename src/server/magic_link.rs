@@ -0,0 +1,181 @@
+use std::future::Future;
+use std::time::Duration;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use key_path::path;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::{self, Pipeline};
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use uuid::Uuid;
+use crate::app::Ctx;
+use crate::app::database::main_connection;
+use crate::events::object_json;
+use crate::server::error::UserError;
+use crate::server::query_tag::tagged;
+use crate::utils::hex::sha256_hex;
+use crate::utils::sql::quote;
+
+/// The table issued magic link tokens are recorded in.
+pub(crate) const MAGIC_LINKS_TABLE: &str = "_teo_magic_links";
+
+/// The key `@identity.tokenIssuer` records the token pipeline of an identity
+/// model under.
+const TOKEN_ISSUER_KEY: &str = "identity:tokenIssuer";
+
+/// Settings of password-less sign in with magic links.
+///
+/// `requestMagicLink` of an identity model takes `{ <field>: ... }`, issues
+/// a single-use token for the identity with that value and hands the link to
+/// the sender. `verifyMagicLink` takes `{ token }` and responds like
+/// `signIn`, with the identity and a session token in `meta.token`.
+#[derive(Debug, Clone)]
+pub struct MagicLinks {
+    /// The link sent to the identity, `{token}` is replaced with the token,
+    /// e.g. `https://app.example.com/sign-in?token={token}`.
+    pub url: String,
+    /// The unique field identities are looked up by.
+    pub field: String,
+    /// How long a token can be used.
+    pub ttl: Duration,
+}
+
+impl Default for MagicLinks {
+
+    fn default() -> Self {
+        Self {
+            url: "{token}".to_owned(),
+            field: "email".to_owned(),
+            ttl: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// A magic link to deliver.
+#[derive(Debug, Clone)]
+pub struct MagicLink {
+    /// The path of the identity model, e.g. `User`.
+    pub model: String,
+    /// The value of the lookup field, e.g. the email address.
+    pub recipient: String,
+    pub token: String,
+    pub url: String,
+    /// Milliseconds since the epoch.
+    pub expires_at: i64,
+}
+
+/// Delivers magic links, e.g. by email.
+pub trait MagicLinkSender: Send + Sync {
+    fn send(&self, link: MagicLink) -> BoxFuture<'static, Result<()>>;
+}
+
+impl<F, Fut> MagicLinkSender for F where
+    F: Fn(MagicLink) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send + 'static {
+    fn send(&self, link: MagicLink) -> BoxFuture<'static, Result<()>> {
+        Box::pin(self(link))
+    }
+}
+
+/// The token pipeline of an identity model, `None` for other models.
+pub(crate) fn model_token_issuer(model: &Model) -> Option<&Pipeline> {
+    match model.data.get(TOKEN_ISSUER_KEY)?.as_teon()? {
+        Value::Pipeline(pipeline) => Some(pipeline),
+        _ => None,
+    }
+}
+
+/// Issue a token for the identity with the lookup field of the body and send
+/// it. The response doesn't tell whether the identity exists.
+pub(super) async fn request_magic_link(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let (config, sender) = Ctx::magic_links().ok_or_else(|| Error::not_found())?;
+    let recipient = body.get(&config.field).and_then(|v| v.as_str()).ok_or_else(|| Error::invalid_request_message(format!("expect `{}` to be a string", config.field)))?;
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let mut finder = Map::new();
+    finder.insert(config.field.clone(), json!(recipient));
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), main_namespace)?;
+    let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+    let Some(identity) = found.into_iter().next() else { return Ok(Response::empty()) };
+    let token = new_token()?;
+    let expires_at = Utc::now().timestamp_millis() + config.ttl.as_millis() as i64;
+    let model_path = model.path().join(".");
+    let (connection, database) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(tagged(format!(
+        "INSERT INTO {} (id, model, identifier, expires_at, used_at, used_by) VALUES ({}, {}, {}, {}, NULL, NULL)",
+        MAGIC_LINKS_TABLE, quote(&sha256_hex(token.as_bytes()), &database)?, quote(&model_path, &database)?, quote(&JsonValue::try_from(&identity.identifier())?.to_string(), &database)?, expires_at,
+    )))).await?;
+    sender.send(MagicLink {
+        model: model_path,
+        recipient: recipient.to_owned(),
+        url: config.url.replace("{token}", &token),
+        token,
+        expires_at,
+    }).await?;
+    Ok(Response::empty())
+}
+
+/// Use a token once and sign its identity in. A token is marked used with a
+/// nonce before the identity is read, so concurrent uses can't both pass.
+pub(super) async fn verify_magic_link(model: &'static Model, body: &JsonValue, ctx: &request::Ctx) -> Result<Response> {
+    let token = body.get("token").and_then(|v| v.as_str()).ok_or_else(|| Error::invalid_request_message("expect `token` to be a string"))?;
+    let issuer = model_token_issuer(model).ok_or_else(|| Error::not_found())?;
+    let now = Utc::now().timestamp_millis();
+    let nonce = Uuid::new_v4().to_string();
+    let (connection, database) = main_connection()?;
+    let id = quote(&sha256_hex(token.as_bytes()), &database)?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(tagged(format!(
+        "UPDATE {} SET used_at = {}, used_by = {} WHERE id = {} AND model = {} AND used_at IS NULL AND expires_at > {}",
+        MAGIC_LINKS_TABLE, now, quote(&nonce, &database)?, id, quote(&model.path().join("."), &database)?, now,
+    )))).await?;
+    let rows = transaction.query_raw(&Value::String(tagged(format!(
+        "SELECT identifier FROM {} WHERE id = {} AND used_by = {}",
+        MAGIC_LINKS_TABLE, id, quote(&nonce, &database)?,
+    )))).await?;
+    let identifier = match rows {
+        Value::Array(rows) => rows.into_iter().next().and_then(|row| row.get("identifier").and_then(|i| i.as_str()).map(|i| i.to_owned())),
+        _ => None,
+    };
+    let Some(identifier) = identifier else {
+        Err(UserError::new("MAGIC_LINK_INVALID", "the magic link is invalid, used or expired").with_status(401))?
+    };
+    let finder: JsonValue = serde_json::from_str(&identifier).map_err(|_| Error::new("invalid magic link row, bad `identifier`"))?;
+    let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": Value::from(finder), "take": 1 }), None, path![]).await?;
+    let Some(identity) = found.into_iter().next() else {
+        Err(UserError::new("MAGIC_LINK_IDENTITY_NOT_FOUND", "the identity of the magic link is not found").with_status(401))?
+    };
+    let pipeline_ctx = pipeline::Ctx::new(Value::ModelObject(identity.clone()), identity.clone(), path![], identity.action(), ctx.transaction_ctx(), Some(ctx.clone()));
+    let token = pipeline_ctx.run_pipeline(issuer).await?;
+    Ok(Response::data_meta(Value::from(object_json(&identity)?), teon!({ "token": token })))
+}
+
+/// Create the magic links table if magic links are enabled.
+pub(crate) async fn create_magic_links_table() -> Result<()> {
+    if Ctx::magic_links().is_none() {
+        return Ok(());
+    }
+    let (connection, _) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (id VARCHAR(64) PRIMARY KEY, model VARCHAR(255) NOT NULL, identifier TEXT NOT NULL, expires_at BIGINT NOT NULL, used_at BIGINT NULL, used_by VARCHAR(64) NULL)",
+        MAGIC_LINKS_TABLE,
+    ))).await?;
+    Ok(())
+}
+
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| Error::new("cannot generate a magic link token"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
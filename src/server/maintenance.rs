@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+use actix_http::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use actix_web::{HttpMessage, HttpRequest};
+use once_cell::sync::Lazy;
+use ring::digest::{digest, SHA256};
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::handler::Method;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::message::info_message;
+use crate::server::error::UserError;
+use crate::server::named_query::named_query_for;
+
+/// The path of the maintenance endpoint.
+pub(super) const MAINTENANCE_PATH: &str = "/_maintenance";
+
+/// The header carrying the maintenance secret.
+pub(crate) const MAINTENANCE_SECRET_HEADER: &str = "x-teo-maintenance-secret";
+
+/// The `Retry-After` of maintenance responses when none is given, in seconds.
+const DEFAULT_RETRY_AFTER: u64 = 60;
+
+/// The builtin and crate actions of models which only read records.
+const READ_ACTIONS: [&str; 14] = ["findMany", "findFirst", "findUnique", "findByIds", "count", "aggregate", "groupBy", "search", "pull", "resolveSlug", "findDuplicates", "suggestTags", "export", "validate"];
+
+/// The maintenance mode while it's on.
+#[derive(Debug, Clone)]
+struct Maintenance {
+    allow_reads: bool,
+    retry_after: u64,
+}
+
+static MAINTENANCE: Lazy<Mutex<Option<Maintenance>>> = Lazy::new(|| Mutex::new(None));
+
+/// Recorded in the extensions of a request rejected by the maintenance mode,
+/// so that its response gets a `Retry-After`.
+#[derive(Debug, Copy, Clone)]
+pub(super) struct MaintenanceRejection {
+    retry_after: u64,
+}
+
+impl MaintenanceRejection {
+
+    /// Set `Retry-After` on the response of the rejected request.
+    pub(super) fn insert_retry_after(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.retry_after.to_string()) {
+            headers.insert(RETRY_AFTER, value);
+        }
+    }
+}
+
+/// Turn the maintenance mode on or off. While it's on, writes are rejected
+/// with 503, and so are reads unless `allow_reads`.
+pub(crate) fn set_maintenance(enabled: bool, allow_reads: bool, retry_after: Option<u64>) {
+    *MAINTENANCE.lock().unwrap() = enabled.then(|| Maintenance { allow_reads, retry_after: retry_after.unwrap_or(DEFAULT_RETRY_AFTER) });
+}
+
+/// Reject a request while the maintenance mode is on, unless it's a read and
+/// reads are allowed. Requests are classified by the handler they are routed
+/// to, see `is_read`.
+pub(super) fn check_maintenance(http_request: &HttpRequest, main_namespace: &'static Namespace, method: Method, path: &str) -> Result<()> {
+    let Some(maintenance) = MAINTENANCE.lock().unwrap().clone() else { return Ok(()) };
+    if maintenance.allow_reads && is_read(main_namespace, method, path) {
+        return Ok(());
+    }
+    http_request.extensions_mut().insert(MaintenanceRejection { retry_after: maintenance.retry_after });
+    Err(UserError::new("MAINTENANCE", "the service is under maintenance, try again later").with_status(503))?
+}
+
+/// Whether the handler of a request only reads. The read actions of models
+/// and named queries are reads. Custom handlers are reads when they're
+/// declared with `GET` or marked with `App::read_handler`. Batches, JSON-RPC
+/// calls and everything else count as writes.
+fn is_read(main_namespace: &'static Namespace, method: Method, path: &str) -> bool {
+    let Some(match_result) = main_namespace.handler_map.r#match(method, path).or_else(|| main_namespace.handler_map.default_match(method, path)) else {
+        return false;
+    };
+    let name = match_result.handler_name();
+    let group = main_namespace.namespace_at_path(&match_result.path_without_last());
+    let custom_handler = match main_namespace.namespace_at_path(&match_result.path()) {
+        Some(namespace) => namespace.handlers.get(name),
+        None => group.and_then(|namespace| namespace.model_handler_groups.get(match_result.group_name()).or_else(|| namespace.handler_groups.get(match_result.group_name()))).and_then(|group| group.handlers.get(name)),
+    };
+    if let Some(handler) = custom_handler {
+        // handlers of the main namespace have no path
+        let path = match_result.path().join(".");
+        let action = if path.is_empty() { name.to_owned() } else { format!("{}.{}", path, name) };
+        return handler.method == Method::Get || Ctx::read_handlers().contains(&action);
+    }
+    READ_ACTIONS.contains(&name) || group
+        .and_then(|namespace| namespace.models.get(match_result.group_name()))
+        .map_or(false, |model| named_query_for(&model.path().join("."), name).is_some())
+}
+
+/// Handle a request to the maintenance endpoint. `GET` responds with the
+/// current mode, `POST` with `{ enabled, allowReads?, retryAfter? }` changes
+/// it. Both require the maintenance secret in `X-Teo-Maintenance-Secret`.
+pub(super) fn handle_maintenance(http_request: &HttpRequest, method: Method, json_body: Option<&JsonValue>) -> Result<Response> {
+    let secret = Ctx::maintenance_secret().ok_or_else(|| Error::not_found())?;
+    let provided = http_request.headers().get(MAINTENANCE_SECRET_HEADER).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if digest(&SHA256, provided.as_bytes()).as_ref() != digest(&SHA256, secret.as_bytes()).as_ref() {
+        let mut error = Error::new("invalid maintenance secret");
+        error.code = 401;
+        Err(error)?
+    }
+    if let (Method::Post, Some(json_body)) = (method, json_body) {
+        let enabled = json_body.get("enabled").and_then(|e| e.as_bool()).ok_or_else(|| Error::invalid_request_message("expect `enabled` to be a bool"))?;
+        let allow_reads = json_body.get("allowReads").and_then(|a| a.as_bool()).unwrap_or(false);
+        let retry_after = json_body.get("retryAfter").and_then(|r| r.as_u64());
+        set_maintenance(enabled, allow_reads, retry_after);
+        info_message(if enabled { "maintenance mode is on" } else { "maintenance mode is off" });
+    }
+    let maintenance = MAINTENANCE.lock().unwrap().clone();
+    Ok(Response::data(Value::from(json!({
+        "enabled": maintenance.is_some(),
+        "allowReads": maintenance.as_ref().map_or(false, |m| m.allow_reads),
+        "retryAfter": maintenance.as_ref().map(|m| m.retry_after),
+    }))))
+}
+
+/// Toggle the maintenance mode with `SIGUSR1`, keeping reads allowed. Use
+/// the endpoint for other settings.
+#[cfg(unix)]
+pub(crate) fn start_maintenance_signal_listener() -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut signals = signal(SignalKind::user_defined1()).map_err(|e| Error::new(format!("cannot listen to SIGUSR1: {}", e)))?;
+    tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            let enabled = MAINTENANCE.lock().unwrap().is_none();
+            set_maintenance(enabled, true, None);
+            info_message(if enabled { "maintenance mode is on" } else { "maintenance mode is off" });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn start_maintenance_signal_listener() -> Result<()> {
+    Ok(())
+}
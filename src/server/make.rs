@@ -1,3 +1,4 @@
+use std::future::Future;
 use std::sync::Arc;
 use std::time::SystemTime;
 use actix_web::dev::Service;
@@ -11,7 +12,8 @@ use teo_runtime::config::server::Server;
 use teo_runtime::namespace::Namespace;
 use actix_http::body::MessageBody;
 use actix_http::{HttpMessage, Method as HttpMethod};
-use actix_web::{App, FromRequest, HttpRequest, HttpResponse, HttpServer, web};
+use actix_http::header::{HeaderValue, ETAG};
+use actix_web::{App, FromRequest, HttpRequest, HttpResponse, HttpServer, ResponseError, web};
 use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
 use actix_web::middleware::DefaultHeaders;
 use teo_parser::ast::handler::HandlerInputFormat;
@@ -21,15 +23,21 @@ use teo_runtime::handler::Handler;
 use teo_runtime::handler::handler::Method;
 use teo_runtime::{connection, request};
 use teo_runtime::connection::transaction;
-use teo_runtime::handler::default::{create, find_first, find_many, find_unique, update, upsert, copy, create_many, update_many, copy_many, delete_many, count, aggregate, group_by, delete};
+use teo_runtime::handler::default::find_many;
 use teo_runtime::model::Model;
 use teo_runtime::response::Response;
 use teo_runtime::Value;
+use crate::advise;
+use crate::server::action::BuiltinAction;
+#[cfg(feature = "arrow")]
+use crate::export::{export_response, ExportFormat};
 use crate::cli::entrance::Entrance;
 use crate::cli::runtime_version::RuntimeVersion;
 use crate::purge;
 use crate::seeder::seed::seed;
-use crate::server::parse::{parse_form_body, parse_json_body};
+use crate::server::output;
+use crate::server::parse::{parse_form_body, parse_json_body, read_body};
+use crate::server::permissions::check_permission;
 use teo_runtime::handler::input::{validate_and_transform_json_input_for_handler, validate_and_transform_json_input_for_builtin_action};
 use teo_runtime::handler::r#match::HandlerMatch;
 use teo_runtime::schema::load::load_data_sets::load_data_sets;
@@ -37,11 +45,52 @@ use crate::app::Ctx;
 use crate::app::database::connect_databases;
 use crate::cli::command::SeedCommandAction;
 use crate::message::{info_message, request_message, unhandled_request_message};
+use crate::server::batch::{batch, BATCH_PATH};
+use crate::server::bucket;
+use crate::server::client_ip::{client_ip_for, insert_client_ip};
+use crate::server::debug::DebugTimings;
+use crate::server::duplicates;
+use crate::server::merge;
+use crate::server::named_query::{named_query_arguments, named_query_for};
+use crate::server::envelope::apply_envelope;
+use crate::server::credentials;
 use crate::server::error::WrapError;
+use crate::server::idempotency::{self, Idempotency};
+use crate::server::impersonation::call_middlewares;
+use crate::server::json_rpc::{json_rpc, JSON_RPC_PATH};
+use crate::server::lockout::begin_sign_in;
+use crate::server::magic_link::{self, model_token_issuer};
+use crate::server::maintenance::{check_maintenance, handle_maintenance, MaintenanceRejection, MAINTENANCE_PATH};
+use crate::server::etag::{etag_matches, response_etag};
+use crate::server::export_cursor::ExportCursor;
+use crate::server::find_by_ids;
+use crate::server::validate;
+use crate::server::query_tag::{set_query_target, with_query_tag};
 use crate::server::request::RequestImpl;
+use crate::server::sessions::{check_session, handle_sessions, record_session, SESSIONS_PATH};
+use crate::server::signature::verify_signature;
+use crate::server::signed_url::verify_signed_url;
+use crate::server::slug;
+use crate::server::pii;
+use crate::server::sync;
+use crate::server::tags;
+use crate::server::request_id::{insert_request_id, request_id_for, REQUEST_ID_HEADER};
 use crate::server::responder::IntoHttpResponse;
+use crate::migrate::views::refresh_view;
+use crate::search;
+use crate::stdlib::decorators::dedupe::model_dedupe_fields;
+use crate::stdlib::decorators::credential::model_credential;
+use crate::stdlib::decorators::merge::model_merge;
+use crate::stdlib::decorators::pii_strategy::has_pii_fields;
+use crate::stdlib::decorators::search_index::model_search_index;
+use crate::stdlib::decorators::slug::model_slug_fields;
+use crate::stdlib::decorators::sync::model_sync;
+use crate::stdlib::decorators::taggable::model_taggable;
+use crate::stdlib::decorators::validate_action::has_validate_action;
+use crate::stdlib::decorators::view::{is_materialized_view, model_view};
+use crate::utils::environments::is_development;
 
-fn make_server_app(
+pub(crate) fn make_server_app(
     main_namespace: &'static Namespace,
     conf: &'static Server,
 ) -> App<impl ServiceFactory<
@@ -57,205 +106,534 @@ fn make_server_app(
             .add(("Access-Control-Allow-Methods", "OPTIONS, POST, GET"))
             .add(("Access-Control-Allow-Headers", "*"))
             .add(("Access-Control-Max-Age", "86400")))
-        .wrap_fn(|req, srv| {
+        .wrap_fn(|mut req, srv| {
             let start = SystemTime::now();
+            let request_id = request_id_for(req.headers());
+            insert_request_id(req.headers_mut(), &request_id);
+            let client_ip = client_ip_for(&req);
+            insert_client_ip(req.headers_mut(), &client_ip);
             let fut = srv.call(req);
             async move {
-                let res = fut.await?;
+                let mut res = fut.await?;
+                insert_request_id(res.headers_mut(), &request_id);
+                let rejection = res.request().extensions().get::<MaintenanceRejection>().copied();
+                if let Some(rejection) = rejection {
+                    rejection.insert_retry_after(res.headers_mut());
+                }
                 {
                     let binding = res.request().extensions();
                     let handler_found_info = binding.get::<HandlerMatch>().clone();
-                    let time_elapsed = SystemTime::now().duration_since(start).unwrap();
+                    let time_elapsed = SystemTime::now().duration_since(start).unwrap_or_default();
                     let path = res.request().path();
                     let method = res.request().method().as_str();
                     if let Some(handler_found_info) = handler_found_info {
-                        request_message(time_elapsed, method, path, &handler_found_info.path, handler_found_info.name.as_str(), res.response().status().as_u16());
+                        request_message(time_elapsed, method, path, &handler_found_info.path, handler_found_info.name.as_str(), res.response().status().as_u16(), &client_ip, &request_id);
                     } else {
-                        unhandled_request_message(time_elapsed, method, path, res.response().status().as_u16());
+                        unhandled_request_message(time_elapsed, method, path, res.response().status().as_u16(), &client_ip, &request_id);
                     }
                 }
                 Ok(res)
             }
         })
         .default_service(web::route().to(move |http_request: HttpRequest, payload: web::Payload| async move {
-            // validate path
-            let path = main_namespace.handler_map.remove_path_prefix(http_request.path(), conf.path_prefix.as_ref().map(|s| s.as_str()));
-            let method = method_from(http_request.method())?;
-            let match_result = if let Some(m_result) = main_namespace.handler_map.r#match(method, path) {
-                m_result
-            } else if let Some(m_result) = main_namespace.handler_map.default_match(method, path) {
-                m_result
-            } else {
-                Err(Error::not_found())?
+            let catalog = Ctx::message_catalogs().and_then(|catalogs| catalogs.negotiate(http_request.headers().get("Accept-Language").and_then(|v| v.to_str().ok())));
+            let request_id = http_request.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+            if Ctx::envelopes().is_default() {
+                return with_query_tag(request_id.clone().unwrap_or_default(), handle_request(main_namespace, conf, http_request, payload)).await.map_err(|error| error.localized(catalog).with_request_id(request_id));
+            }
+            let response = match with_query_tag(request_id.clone().unwrap_or_default(), handle_request(main_namespace, conf, http_request.clone(), payload)).await {
+                Ok(response) => response,
+                Err(error) => error.localized(catalog).with_request_id(request_id).error_response(),
             };
+            let envelope = match http_request.extensions().get::<HandlerMatch>() {
+                Some(handler_match) => Ctx::envelopes().envelope_for(&handler_match.path.join("."), handler_match.handler_name()),
+                None => Ctx::envelopes().envelope_for("", ""),
+            };
+            Ok(apply_envelope(envelope, response).await)
+        }));
+    app
+}
 
-            // High-risk operations for testing
-            #[cfg(feature="dangerous_operation")]
-            if match_result.path()[0] == "danger" {
-                return Ok::<HttpResponse, WrapError>(
-                    dangerous_operation(match_result.handler_name())
-                        .await?
-                        .into_http_response(http_request.clone()),
-                );
-            }
+async fn handle_request(
+    main_namespace: &'static Namespace,
+    conf: &'static Server,
+    http_request: HttpRequest,
+    payload: web::Payload,
+) -> std::result::Result<HttpResponse, WrapError> {
+    // validate path
+    let path = main_namespace.handler_map.remove_path_prefix(http_request.path(), conf.path_prefix.as_ref().map(|s| s.as_str()));
+    let method = method_from(http_request.method())?;
+    let payload = verify_signature(&http_request, payload, body_limit_for(main_namespace, method, path)).await?;
+    // signed action urls are opened with GET and run as the POST action
+    let signed_body = verify_signed_url(&http_request)?;
+    let method = if signed_body.is_some() && method == Method::Get && main_namespace.handler_map.r#match(method, path).is_none() { Method::Post } else { method };
+    if path == MAINTENANCE_PATH {
+        let json_body = if method == Method::Post { Some(parse_json_body(payload, Ctx::body_limits().limit_for("", "_maintenance")).await?) } else { None };
+        return Ok(handle_maintenance(&http_request, method, json_body.as_ref())?.into_http_response(http_request.clone()));
+    }
+    check_maintenance(&http_request, main_namespace, method, path)?;
+    if Ctx::sessions() {
+        check_session(&http_request, path).await?;
+        if let Some(action) = path.strip_prefix(SESSIONS_PATH).filter(|_| method == Method::Post) {
+            let json_body = parse_json_body(payload, Ctx::body_limits().limit_for("", "_sessions")).await?;
+            return Ok(handle_sessions(&http_request, action, &json_body).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if path == BATCH_PATH && method == Method::Post {
+        let json_body = parse_json_body(payload, Ctx::body_limits().limit_for("", "_batch")).await?;
+        return Ok(batch(&http_request, &json_body, main_namespace).await?.into_http_response(http_request.clone()));
+    }
+    if path == JSON_RPC_PATH && method == Method::Post && Ctx::json_rpc() {
+        let body = read_body(payload, Ctx::body_limits().limit_for("", "_rpc")).await?;
+        let json_body = serde_json::from_slice(&body).map_err(|_| Error::invalid_request_message("incorrect json format"));
+        let catalog = Ctx::message_catalogs().and_then(|catalogs| catalogs.negotiate(http_request.headers().get("Accept-Language").and_then(|v| v.to_str().ok())));
+        return Ok(match json_rpc(&http_request, json_body, main_namespace, catalog).await {
+            Some(response) => HttpResponse::Ok().json(response),
+            None => HttpResponse::NoContent().finish(),
+        });
+    }
+    let match_result = if let Some(m_result) = main_namespace.handler_map.r#match(method, path) {
+        m_result
+    } else if let Some(m_result) = main_namespace.handler_map.default_match(method, path) {
+        m_result
+    } else {
+        Err(Error::not_found())?
+    };
+    set_query_target(match_result.path.join("."), match_result.handler_name().to_owned());
+
+    // High-risk operations for testing
+    #[cfg(feature="dangerous_operation")]
+    if match_result.path()[0] == "danger" {
+        return Ok::<HttpResponse, WrapError>(
+            dangerous_operation(match_result.handler_name())
+                .await?
+                .into_http_response(http_request.clone()),
+        );
+    }
 
-            // Normal handling
-            let mut group = false;
-            let dest_namespace = if let Some(d) = main_namespace.namespace_at_path(&match_result.path()) {
-                d
-            } else if match_result.path().len() > 0 {
-                if let Some(d) = main_namespace.namespace_at_path(&match_result.path_without_last()) {
-                    group = true;
-                    d
+    // Normal handling
+    let mut group = false;
+    let dest_namespace = if let Some(d) = main_namespace.namespace_at_path(&match_result.path()) {
+        d
+    } else if match_result.path().len() > 0 {
+        if let Some(d) = main_namespace.namespace_at_path(&match_result.path_without_last()) {
+            group = true;
+            d
+        } else {
+            Err(Error::not_found())?
+        }
+    } else {
+        Err(Error::not_found())?
+    };
+    if group && match_result.handler_name() == "refresh" && method == Method::Post {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| is_materialized_view(m)) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["refresh"], |_ctx, _body| async move {
+                refresh_view(model).await?;
+                Ok(Response::data(Value::Null))
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "search" && method == Method::Post {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| model_search_index(m).is_some()) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["findMany"], |ctx, body| async move {
+                search::search(model, &body, main_namespace, &ctx).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && matches!(match_result.handler_name(), "pull" | "push") && method == Method::Post {
+        if let Some((model, fields)) = dest_namespace.models.get(match_result.group_name()).and_then(|m| model_sync(m).map(|f| (m, f))) {
+            let is_push = match_result.handler_name() == "push";
+            let permissions: &'static [&'static str] = if is_push { &["update"] } else { &["findMany"] };
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, permissions, |ctx, body| {
+                let fields = fields.clone();
+                async move {
+                    if is_push {
+                        sync::push(model, &fields, &body, main_namespace, &ctx).await
+                    } else {
+                        sync::pull(model, &fields, &body, &ctx).await
+                    }
+                }
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && matches!(match_result.handler_name(), "anonymize" | "export") && method == Method::Post {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| has_pii_fields(m)) {
+            let is_anonymize = match_result.handler_name() == "anonymize";
+            let permissions: &'static [&'static str] = if is_anonymize { &["anonymize"] } else { &["export"] };
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, permissions, |ctx, body| async move {
+                if is_anonymize {
+                    pii::anonymize(model, &body, main_namespace, ctx.transaction_ctx()).await
                 } else {
-                    Err(Error::not_found())?
+                    pii::export(model, &body, main_namespace, ctx.transaction_ctx()).await
                 }
-            } else {
-                Err(Error::not_found())?
-            };
-            let handler_resolved = if group {
-                if let Some(model) = dest_namespace.models.get(match_result.group_name()) {
-                    if let Some(group) = dest_namespace.model_handler_groups.get(match_result.group_name()) {
-                        if let Some(handler) = group.handlers.get(match_result.handler_name()) {
-                            (dest_namespace, HandlerResolved::Custom(handler))
-                        } else {
-                            if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
-                                (dest_namespace, HandlerResolved::Builtin(model, action))
-                            } else {
-                                Err(Error::not_found())?
-                            }
-                        }
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "resolveSlug" && method == Method::Post {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| model_slug_fields(m).iter().any(|(_, policy)| policy.history)) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["findUnique"], |ctx, body| async move {
+                slug::resolve_slug(model, &body, main_namespace, ctx.transaction_ctx()).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "findDuplicates" && method == Method::Post {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| !model_dedupe_fields(m).is_empty()) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["findMany"], |ctx, body| async move {
+                duplicates::find_duplicates(model, &body, main_namespace, &ctx).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "merge" && method == Method::Post {
+        if let Some((model, fields)) = dest_namespace.models.get(match_result.group_name()).and_then(|m| model_merge(m).map(|f| (m, f))) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["update", "delete"], |ctx, body| {
+                let fields = fields.clone();
+                async move { merge::merge(model, &fields, &body, main_namespace, &ctx).await }
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "suggestTags" && method == Method::Post {
+        if let Some((model, taggable)) = dest_namespace.models.get(match_result.group_name()).and_then(|m| model_taggable(m).map(|t| (m, t))) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["findMany"], |ctx, body| {
+                let taggable = taggable.clone();
+                async move { tags::suggest_tags(model, &taggable, &body, main_namespace, ctx.transaction_ctx()).await }
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && match_result.handler_name() == "findByIds" && method == Method::Post && !has_model_handler(dest_namespace, &match_result) {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()) {
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &["findMany"], |ctx, body| async move {
+                find_by_ids::find_by_ids(model, &body, main_namespace, &ctx).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    // validate is guarded like the update or create it checks
+    if group && match_result.handler_name() == "validate" && method == Method::Post && !has_model_handler(dest_namespace, &match_result) {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| has_validate_action(m) && model_view(m).is_none()) {
+            let permissions = |body: &JsonValue| -> &'static [&'static str] { if body.get("update").is_some() { &["update"] } else { &["create"] } };
+            return Ok(custom_action_by_body(&http_request, payload, main_namespace, dest_namespace, match_result, model, permissions, |ctx, body| async move {
+                validate::validate(model, &body, main_namespace, &ctx).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    // credentials are linked to and unlinked from the signed in identity only
+    if group && matches!(match_result.handler_name(), "link" | "unlink") && method == Method::Post {
+        if let Some((model, fields)) = dest_namespace.models.get(match_result.group_name()).and_then(|m| model_credential(m).map(|f| (m, f))) {
+            let is_link = match_result.handler_name() == "link";
+            return Ok(custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &[], |ctx, body| {
+                let fields = fields.clone();
+                async move {
+                    if is_link {
+                        credentials::link(model, &fields, &body, main_namespace, &ctx).await
                     } else {
-                        if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
-                            (dest_namespace, HandlerResolved::Builtin(model, action))
-                        } else {
-                            Err(Error::not_found())?
-                        }
+                        credentials::unlink(model, &fields, &body, main_namespace, &ctx).await
                     }
-                } else if let Some(group) = dest_namespace.handler_groups.get(match_result.group_name()) {
-                    if let Some(handler) = group.handlers.get(match_result.handler_name()) {
-                        (dest_namespace, HandlerResolved::Custom(handler))
+                }
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    if group && matches!(match_result.handler_name(), "requestMagicLink" | "verifyMagicLink") && method == Method::Post && Ctx::magic_links().is_some() {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()).filter(|m| model_token_issuer(m).is_some()) {
+            let is_verify = match_result.handler_name() == "verifyMagicLink";
+            let response = custom_action(&http_request, payload, main_namespace, dest_namespace, match_result, model, &[], |ctx, body| async move {
+                if is_verify {
+                    magic_link::verify_magic_link(model, &body, &ctx).await
+                } else {
+                    magic_link::request_magic_link(model, &body, main_namespace, &ctx).await
+                }
+            }).await?;
+            if is_verify && Ctx::sessions() {
+                record_session(&http_request, &response).await?;
+            }
+            return Ok(response.into_http_response(http_request.clone()));
+        }
+    }
+    if group && method == Method::Post {
+        if let Some((model, query)) = dest_namespace.models.get(match_result.group_name()).and_then(|m| named_query_for(&m.path().join("."), match_result.handler_name()).map(|q| (m, q))) {
+            http_request.extensions_mut().insert(match_result.clone());
+            let json_body = parse_json_body(payload, Ctx::body_limits().limit_for(&match_result.path.join("."), match_result.handler_name())).await?;
+            let find_many_action = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+            let body = validate_and_transform_json_input_for_builtin_action(model, find_many_action, &named_query_arguments(query, &json_body)?, main_namespace)?;
+            let ctx = request::Ctx::new(
+                request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+                Arc::new(body),
+                transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
+                match_result,
+            );
+            return Ok(call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| async move {
+                check_permission(model, "findMany", &ctx).await?;
+                output::find(model, "findMany", &ctx).await
+            }).await?.into_http_response(http_request.clone()));
+        }
+    }
+    let handler_resolved = if group {
+        if let Some(model) = dest_namespace.models.get(match_result.group_name()) {
+            if let Some(group) = dest_namespace.model_handler_groups.get(match_result.group_name()) {
+                if let Some(handler) = group.handlers.get(match_result.handler_name()) {
+                    (dest_namespace, HandlerResolved::Custom(handler))
+                } else {
+                    if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
+                        (dest_namespace, HandlerResolved::Builtin(model, action))
                     } else {
                         Err(Error::not_found())?
                     }
-                } else {
-                    Err(Error::not_found())?
                 }
             } else {
-                if let Some(handler) = dest_namespace.handlers.get(match_result.handler_name()) {
-                    (dest_namespace, HandlerResolved::Custom(handler))
+                if let Some(action) = builtin_action_handler_from_name(match_result.handler_name()) {
+                    (dest_namespace, HandlerResolved::Builtin(model, action))
                 } else {
                     Err(Error::not_found())?
                 }
-            };
-            let dest_namespace = handler_resolved.0;
-            let handler_resolved = handler_resolved.1;
-            if method == Method::Options {
-                // special handle for options
-                let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                let transaction_ctx = transaction::Ctx::new(conn_ctx);
+            }
+        } else if let Some(group) = dest_namespace.handler_groups.get(match_result.group_name()) {
+            if let Some(handler) = group.handlers.get(match_result.handler_name()) {
+                (dest_namespace, HandlerResolved::Custom(handler))
+            } else {
+                Err(Error::not_found())?
+            }
+        } else {
+            Err(Error::not_found())?
+        }
+    } else {
+        if let Some(handler) = dest_namespace.handlers.get(match_result.handler_name()) {
+            (dest_namespace, HandlerResolved::Custom(handler))
+        } else {
+            Err(Error::not_found())?
+        }
+    };
+    let dest_namespace = handler_resolved.0;
+    let handler_resolved = handler_resolved.1;
+    if method == Method::Options {
+        // special handle for options
+        let conn_ctx = connection::Ctx::from_namespace(main_namespace);
+        let transaction_ctx = transaction::Ctx::new(conn_ctx);
+        let ctx = request::Ctx::new(
+            request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+            Arc::new(Value::Null),
+            transaction_ctx,
+            match_result
+        );
+        return Ok::<HttpResponse, WrapError>(call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| async {
+            Ok(Response::empty())
+        }).await?.into_http_response(http_request.clone()));
+    }
+    http_request.extensions_mut().insert(match_result.clone());
+    // parse body
+    let mut format = HandlerInputFormat::Json;
+    match handler_resolved {
+        HandlerResolved::Custom(handler) => {
+            format = handler.format;
+        }
+        _ => (),
+    }
+    let mut json_body = match format {
+        HandlerInputFormat::Json => if let Some(signed_body) = signed_body {
+            signed_body
+        } else if method == Method::Get || method == Method::Delete {
+            JsonValue::Null
+        } else {
+            parse_json_body(payload, Ctx::body_limits().limit_for(&match_result.path.join("."), match_result.handler_name())).await?
+        },
+        HandlerInputFormat::Form => parse_form_body(http_request.clone(), payload).await?,
+    };
+    return match handler_resolved {
+        HandlerResolved::Builtin(model, action) => {
+            // idempotent requests are told apart by the body as it was sent
+            let sent_body = json_body.clone();
+            #[cfg(feature = "arrow")]
+            let export_format = http_request.headers().get("Accept").and_then(|v| v.to_str().ok()).and_then(ExportFormat::from_accept).filter(|_| matches!(match_result.handler_name(), "findMany" | "groupBy"));
+            if let Some(bucket) = json_body.as_object_mut().filter(|_| match_result.handler_name() == "groupBy").and_then(|o| o.remove("bucket")) {
+                let find_many_action = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+                let body = validate_and_transform_json_input_for_builtin_action(model, find_many_action, &bucket::find_many_arguments(&json_body), main_namespace)?;
                 let ctx = request::Ctx::new(
                     request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                    Arc::new(Value::Null),
-                    transaction_ctx,
-                    match_result
+                    Arc::new(body),
+                    transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
+                    match_result.clone(),
                 );
-                return Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async {
-                    Ok(Response::empty())
-                }).await?.into_http_response(http_request.clone()));
-            }
-            http_request.extensions_mut().insert(match_result.clone());
-            // parse body
-            let mut format = HandlerInputFormat::Json;
-            match handler_resolved {
-                HandlerResolved::Custom(handler) => {
-                    format = handler.format;
+                let response = call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| async move {
+                    check_permission(model, "groupBy", &ctx).await?;
+                    find_many(&ctx).await
+                }).await?;
+                let response = bucket::group_by_bucket(&response, &json_body, &bucket)?.into_http_response(http_request.clone());
+                #[cfg(feature = "arrow")]
+                if let Some(format) = export_format {
+                    return Ok(export_response(response, format).await);
                 }
-                _ => (),
+                return Ok(response);
             }
-            let json_body = match format {
-                HandlerInputFormat::Json => if method == Method::Get || method == Method::Delete {
-                    JsonValue::Null
-                } else {
-                    parse_json_body(payload).await?
-                },
-                HandlerInputFormat::Form => parse_form_body(http_request.clone(), payload).await?,
-            };
-            return match handler_resolved {
-                HandlerResolved::Builtin(model, action) => {
-                    let body = validate_and_transform_json_input_for_builtin_action(model, action, &json_body, main_namespace)?;
-                    let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                    let transaction_ctx = transaction::Ctx::new(conn_ctx);
+            if match_result.handler_name() == "findMany" {
+                if let Some(export_cursor) = ExportCursor::take(&mut json_body)? {
                     let ctx = request::Ctx::new(
                         request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                        Arc::new(body),
-                        transaction_ctx,
+                        Arc::new(Value::from(json_body.clone())),
+                        transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
                         match_result.clone(),
                     );
-                    match match_result.handler_name() {
-                        "findMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "findFirst" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_first(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "findUnique" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            find_unique(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "create" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            create(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "delete" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            delete(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "update" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            update(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "upsert" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            upsert(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "copy" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            copy(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "createMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            create_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "updateMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            update_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "copyMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            copy_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "deleteMany" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            delete_many(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "count" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            count(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "aggregate" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            aggregate(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        "groupBy" => Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, &|ctx: request::Ctx| async move {
-                            group_by(&ctx).await
-                        }).await?.into_http_response(http_request.clone())),
-                        _ => Err(Error::not_found())?,
+                    return Ok(call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| {
+                        let (export_cursor, json_body) = (export_cursor.clone(), json_body.clone());
+                        async move {
+                            check_permission(model, "findMany", &ctx).await?;
+                            export_cursor.respond(model, &json_body, main_namespace, &ctx).await
+                        }
+                    }).await?.into_http_response(http_request.clone()));
+                }
+            }
+            let mut debug = DebugTimings::take(&mut json_body);
+            if matches!(match_result.handler_name(), "findMany" | "findFirst" | "findUnique" | "count") && is_development() {
+                advise::record(model, &json_body);
+            }
+            let if_match = http_request.headers().get("If-Match").and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+            let builtin = BuiltinAction::take(model, match_result.handler_name(), action, &mut json_body, if_match)?;
+            let conn_ctx = connection::Ctx::from_namespace(main_namespace);
+            let transaction_ctx = transaction::Ctx::new(conn_ctx);
+            let ctx = request::Ctx::new(
+                request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+                Arc::new(Value::from(json_body.clone())),
+                transaction_ctx,
+                match_result.clone(),
+            );
+            let idempotency_key = http_request.headers().get("Idempotency-Key").and_then(|v| v.to_str().ok()).filter(|_| matches!(match_result.handler_name(), "create" | "upsert"));
+            let credential = http_request.headers().get("Authorization").and_then(|v| v.to_str().ok());
+            let store_key = match idempotency_key {
+                Some(key) => match idempotency::begin(&format!("{}.{}", match_result.path.join("."), match_result.handler_name()), credential, key, &sent_body)? {
+                    Idempotency::Replay(response) => return Ok(response),
+                    Idempotency::Proceed(store_key) => Some(store_key),
+                },
+                None => None,
+            };
+            let result = call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| {
+                let (builtin, json_body) = (builtin.clone(), json_body.clone());
+                async move { builtin.run_in_transaction(&json_body, main_namespace, &ctx).await }
+            }).await.map_err(WrapError::from).map(|response| {
+                if match_result.handler_name() != "findUnique" {
+                    return response.into_http_response(http_request.clone());
+                }
+                let Some(etag) = response_etag(&response) else {
+                    return response.into_http_response(http_request.clone());
+                };
+                let if_none_match = http_request.headers().get("If-None-Match").and_then(|v| v.to_str().ok());
+                if if_none_match.map_or(false, |h| etag_matches(h, &etag)) {
+                    return HttpResponse::NotModified().insert_header(("ETag", etag)).finish();
+                }
+                let mut http_response = response.into_http_response(http_request.clone());
+                if let Ok(value) = HeaderValue::from_str(&etag) {
+                    http_response.headers_mut().insert(ETAG, value);
+                }
+                http_response
+            });
+            if let Some(debug) = debug.as_mut() {
+                debug.stage("action");
+            }
+            let result = match result {
+                Ok(response) => Ok(builtin.finish_response(response).await),
+                result => result,
+            };
+            #[cfg(feature = "arrow")]
+            let result = match (result, export_format) {
+                (Ok(response), Some(format)) => Ok(export_response(response, format).await),
+                (result, _) => result,
+            };
+            let result = match store_key {
+                Some(store_key) => match result {
+                    Ok(response) => Ok(idempotency::finish(store_key, response, Ctx::idempotency_window()).await),
+                    Err(error) => {
+                        idempotency::abort(&store_key);
+                        Err(error)
                     }
                 },
-                HandlerResolved::Custom(handler) => {
-                    let body = validate_and_transform_json_input_for_handler(handler, &json_body, main_namespace)?;
-                    let conn_ctx = connection::Ctx::from_namespace(main_namespace);
-                    let transaction_ctx = transaction::Ctx::new(conn_ctx);
-                    let ctx = request::Ctx::new(
-                        request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
-                        Arc::new(body),
-                        transaction_ctx,
-                        match_result
-                    );
-                    Ok::<HttpResponse, WrapError>(dest_namespace.middleware_stack.call(ctx, handler.call).await?.into_http_response(http_request.clone()))
+                None => result,
+            };
+            match (result, debug) {
+                (Ok(response), Some(mut debug)) => {
+                    debug.stage("postprocess");
+                    Ok(debug.apply_to_response(response).await)
                 }
+                (result, _) => result,
             }
-        }));
-    app
+        },
+        HandlerResolved::Custom(handler) => {
+            let body = validate_and_transform_json_input_for_handler(handler, &json_body, main_namespace)?;
+            let is_sign_in = match_result.handler_name() == "signIn";
+            let sign_in_attempt = if is_sign_in { begin_sign_in(&http_request, &match_result.path.join("."), &json_body).await? } else { None };
+            let conn_ctx = connection::Ctx::from_namespace(main_namespace);
+            let transaction_ctx = transaction::Ctx::new(conn_ctx);
+            let ctx = request::Ctx::new(
+                request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+                Arc::new(body),
+                transaction_ctx,
+                match_result
+            );
+            let response = match (call_middlewares(dest_namespace, ctx, handler.call).await, sign_in_attempt) {
+                (Ok(response), Some(attempt)) => {
+                    attempt.succeed();
+                    response
+                }
+                (Err(error), Some(attempt)) if (400..500).contains(&error.code) => {
+                    attempt.fail();
+                    Err(error)?
+                }
+                (result, _) => result?,
+            };
+            if is_sign_in && Ctx::sessions() {
+                record_session(&http_request, &response).await?;
+            }
+            Ok::<HttpResponse, WrapError>(response.into_http_response(http_request.clone()))
+        }
+    }
+}
+
+/// Whether the model of a match declares a handler of the matched name,
+/// which takes precedence over the actions of the same name added by Teo.
+fn has_model_handler(dest_namespace: &Namespace, match_result: &HandlerMatch) -> bool {
+    dest_namespace.model_handler_groups.get(match_result.group_name()).map_or(false, |group| group.handlers.contains_key(match_result.handler_name()))
+}
+
+/// Run a custom action of a model through the middleware stack of its
+/// namespace. The body is parsed as a JSON object under the action's body
+/// limit and the action runs only if the request passes the guards of every
+/// action in `permissions`.
+async fn custom_action<F, Fut>(
+    http_request: &HttpRequest,
+    payload: web::Payload,
+    main_namespace: &'static Namespace,
+    dest_namespace: &'static Namespace,
+    match_result: HandlerMatch,
+    model: &'static Model,
+    permissions: &'static [&'static str],
+    action: F,
+) -> Result<Response> where F: Fn(request::Ctx, JsonValue) -> Fut + Send + Sync, Fut: Future<Output = Result<Response>> + Send + 'static {
+    custom_action_by_body(http_request, payload, main_namespace, dest_namespace, match_result, model, move |_: &JsonValue| permissions, action).await
+}
+
+/// Like `custom_action`, for actions whose guards depend on the body.
+async fn custom_action_by_body<P, F, Fut>(
+    http_request: &HttpRequest,
+    payload: web::Payload,
+    main_namespace: &'static Namespace,
+    dest_namespace: &'static Namespace,
+    match_result: HandlerMatch,
+    model: &'static Model,
+    permissions: P,
+    action: F,
+) -> Result<Response> where P: Fn(&JsonValue) -> &'static [&'static str] + Send + Sync, F: Fn(request::Ctx, JsonValue) -> Fut + Send + Sync, Fut: Future<Output = Result<Response>> + Send + 'static {
+    http_request.extensions_mut().insert(match_result.clone());
+    let json_body = parse_json_body(payload, Ctx::body_limits().limit_for(&match_result.path.join("."), match_result.handler_name())).await?;
+    let ctx = request::Ctx::new(
+        request::Request::new(Arc::new(RequestImpl::new(http_request.clone()))),
+        Arc::new(Value::from(json_body.clone())),
+        transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace)),
+        match_result,
+    );
+    call_middlewares(dest_namespace, ctx, &|ctx: request::Ctx| {
+        let action = action(ctx.clone(), json_body.clone());
+        let permissions = permissions(&json_body);
+        async move {
+            for permission in permissions {
+                check_permission(model, permission, &ctx).await?;
+            }
+            action.await
+        }
+    }).await
 }
 
 pub(crate) async fn serve(
@@ -270,8 +648,8 @@ pub(crate) async fn serve(
     let server = HttpServer::new(move || {
         make_server_app(namespace, conf)
     })
-        .bind((bind.0, bind.1 as u16))
-        .unwrap()
+        .bind((bind.0.as_str(), bind.1 as u16))
+        .map_err(|e| Error::new(format!("cannot bind {}:{}: {}", bind.0, bind.1, e)))?
         .run();
     let result = future::join(server, server_start_message(port as u16, runtime_version, entrance, silent)).await;
     result.1
@@ -289,6 +667,22 @@ async fn server_start_message(port: u16, runtime_version: &'static RuntimeVersio
     Ok(())
 }
 
+/// The body size limit of the handler a request is routed to, so a signed
+/// request body is read with the same limit as an unsigned one.
+fn body_limit_for(main_namespace: &'static Namespace, method: Method, path: &str) -> usize {
+    let limits = Ctx::body_limits();
+    match path {
+        MAINTENANCE_PATH => limits.limit_for("", "_maintenance"),
+        BATCH_PATH => limits.limit_for("", "_batch"),
+        JSON_RPC_PATH => limits.limit_for("", "_rpc"),
+        _ if path.starts_with(SESSIONS_PATH) => limits.limit_for("", "_sessions"),
+        _ => match main_namespace.handler_map.r#match(method, path).or_else(|| main_namespace.handler_map.default_match(method, path)) {
+            Some(match_result) => limits.limit_for(&match_result.path.join("."), match_result.handler_name()),
+            None => limits.limit_for("", ""),
+        },
+    }
+}
+
 fn method_from(m: &HttpMethod) -> Result<Method> {
     Ok(match m.as_str() {
         "GET" => Method::Get,
@@ -0,0 +1,152 @@
+use chrono::Utc;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::events::object_json;
+use crate::generate::mobile::collect_models;
+use crate::message::merge_message;
+use crate::server::request_id::REQUEST_ID_HEADER;
+use crate::stdlib::decorators::merge::MergeFields;
+
+/// How the value of a field is picked when two records are merged. A null
+/// value is always replaced with the value of the other record.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Strategy {
+    KeepTarget,
+    KeepSource,
+    Newest,
+}
+
+impl Strategy {
+
+    fn parse(value: &JsonValue) -> Result<Self> {
+        match value.as_str() {
+            Some("keepTarget") => Ok(Strategy::KeepTarget),
+            Some("keepSource") => Ok(Strategy::KeepSource),
+            Some("newest") => Ok(Strategy::Newest),
+            _ => Err(Error::invalid_request_message("expect a merge strategy to be one of keepTarget, keepSource and newest")),
+        }
+    }
+}
+
+/// The `merge` action of a model with `@@merge`. The body is
+/// `{ source, target, strategy?, fields? }`, `source` and `target` are unique
+/// where inputs. `strategy` picks the field values of the merged record,
+/// `keepTarget` by default, and `fields` overrides it per field, e.g.
+/// `{ email: "keepSource" }`.
+///
+/// The target is updated first, then the records of all models referencing
+/// the source are re-pointed to the target, then the source is marked
+/// deleted. The steps run one after another, a merge which failed half way
+/// is completed by running it again. Unique fields of the source stay taken
+/// by the deleted source, so they should be kept with `keepTarget`.
+pub(super) async fn merge(model: &'static Model, fields: &MergeFields, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let source = find_record(model, body, "source", main_namespace, ctx).await?;
+    let target = find_record(model, body, "target", main_namespace, ctx).await?;
+    let identifier = target.identifier();
+    if source.identifier() == identifier {
+        Err(Error::invalid_request_message("expect `source` and `target` to be different records"))?
+    }
+    if !matches!(source.get_value(&fields.deleted)?, Value::Null | Value::Bool(false)) {
+        Err(Error::invalid_request_message("the source record is already deleted"))?
+    }
+    let default_strategy = body.get("strategy").map(Strategy::parse).transpose()?.unwrap_or(Strategy::KeepTarget);
+    let overrides = body.get("fields").and_then(|f| f.as_object()).cloned().unwrap_or_default();
+    let source_is_newer = match fields.updated_at.as_ref() {
+        Some(updated_at) => match (source.get_value(updated_at)?, target.get_value(updated_at)?) {
+            (Value::DateTime(source), Value::DateTime(target)) => source > target,
+            (Value::DateTime(_), _) => true,
+            _ => false,
+        },
+        None => false,
+    };
+    for field in model.fields.values() {
+        let name = field.name();
+        if field.auto || field.auto_increment || identifier.get(name).is_some() || name == fields.deleted || Some(name) == fields.updated_at.as_deref() {
+            continue
+        }
+        let strategy = match overrides.get(name) {
+            Some(strategy) => Strategy::parse(strategy)?,
+            None => default_strategy,
+        };
+        if strategy == Strategy::Newest && fields.updated_at.is_none() {
+            Err(Error::invalid_request_message("the newest strategy requires `updatedAt` in @@merge"))?
+        }
+        let (source_value, target_value) = (source.get_value(name)?, target.get_value(name)?);
+        let keep_source = match strategy {
+            Strategy::KeepTarget => target_value.is_null(),
+            Strategy::KeepSource => !source_value.is_null(),
+            Strategy::Newest => if source_is_newer { !source_value.is_null() } else { target_value.is_null() },
+        };
+        if keep_source && source_value != target_value {
+            target.set(name, source_value)?;
+        }
+    }
+    target.save().await?;
+    let relinked = relink(model, &source, &target, main_namespace, ctx).await?;
+    source.set(&fields.deleted, deleted_value(model, &fields.deleted)?)?;
+    source.save().await?;
+    let request_id = ctx.request().headers().get(REQUEST_ID_HEADER).unwrap_or("").to_owned();
+    merge_message(&describe(&source)?, &describe(&target)?, relinked, &request_id);
+    Ok(Response::data_meta(Value::from(object_json(&target)?), teon!({ "relinked": relinked as i64 })))
+}
+
+/// Point the records referencing `source` to `target`. Returns the number of
+/// records changed.
+async fn relink(model: &'static Model, source: &Object, target: &Object, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<usize> {
+    let mut models = vec![];
+    collect_models(main_namespace, &mut models);
+    let mut relinked = 0;
+    for other in models {
+        for relation in other.relations().into_iter().filter(|r| r.has_foreign_key && r.model_path() == model.path()) {
+            let mut finder = teon!({});
+            for (field, reference) in relation.iter() {
+                finder.as_dictionary_mut().unwrap().insert(field.to_owned(), source.get_value(reference)?);
+            }
+            let records: Vec<Object> = ctx.transaction_ctx().find_many(other, &teon!({ "where": finder }), None, path![]).await?;
+            for record in records {
+                for (field, reference) in relation.iter() {
+                    record.set(field, target.get_value(reference)?)?;
+                }
+                record.save().await?;
+                relinked += 1;
+            }
+        }
+    }
+    Ok(relinked)
+}
+
+fn deleted_value(model: &Model, name: &str) -> Result<Value> {
+    let field = model.field(name).ok_or_else(|| Error::new(format!("field `{}` is not found", name)))?;
+    let r#type = match field.r#type() {
+        Type::Optional(inner) => inner.as_ref(),
+        r#type => r#type,
+    };
+    match r#type {
+        Type::Bool => Ok(Value::Bool(true)),
+        Type::DateTime => Ok(Value::DateTime(Utc::now())),
+        _ => Err(Error::new(format!("@@merge: `{}.{}` must be a Bool or DateTime field", model.path().join("."), name))),
+    }
+}
+
+async fn find_record(model: &'static Model, body: &JsonValue, key: &str, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Object> {
+    let finder = body.get(key).cloned().ok_or_else(|| Error::invalid_request_message(format!("expect `{}` to be an object", key)))?;
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), main_namespace)?;
+    let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+    found.into_iter().next().ok_or_else(|| Error::invalid_request_message(format!("the {} record is not found", key)))
+}
+
+fn describe(object: &Object) -> Result<String> {
+    Ok(format!("{}{}", object.model().path().join("."), JsonValue::try_from(&object.identifier())?))
+}
@@ -1,6 +1,52 @@
 pub mod make;
 pub mod parse;
+pub mod projection;
 pub mod request;
 pub mod responder;
+pub mod action;
+pub mod batch;
+pub mod body_limit;
+pub mod bucket;
+pub mod client_ip;
+pub mod credentials;
+pub mod debug;
+pub mod duplicates;
+pub mod envelope;
 pub mod error;
+pub mod estimate;
+pub mod etag;
+pub mod export_cursor;
+pub mod find_by_ids;
+pub mod i18n;
+pub mod idempotency;
+pub mod impersonation;
+pub mod json_rpc;
+pub mod lockout;
+pub mod lookup;
+pub mod magic_link;
+pub mod maintenance;
+pub mod merge;
+pub mod money;
+pub mod named_query;
+pub mod nearest;
+pub mod nested_write;
+pub mod output;
+pub mod partial_update;
+pub mod permissions;
+pub mod position;
+pub mod pii;
+pub mod query_tag;
+pub mod request_id;
+pub mod routes;
+pub mod scalar;
+pub mod sequence;
+pub mod sessions;
+pub mod signature;
+pub mod signed_url;
+pub mod similar;
+pub mod slug;
 pub mod static_files;
+pub mod sync;
+pub mod tags;
+pub mod tree;
+pub mod validate;
@@ -0,0 +1,75 @@
+use key_path::path;
+use serde_json::{Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::{Model, Object};
+use teo_runtime::request;
+use teo_runtime::response::body::BodyInner;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::money::{sum_money, Money, MONEY_SCALAR};
+use crate::stdlib::decorators::scalar::field_scalar;
+
+/// The money fields in the `_sum` input of an `aggregate`.
+///
+/// Money is stored as JSON which the query engine cannot sum, so these fields
+/// are removed from the arguments and summed on the server. Other aggregates
+/// of money fields are rejected.
+#[derive(Debug, Clone, Default)]
+pub(super) struct MoneySum {
+    fields: Vec<String>,
+}
+
+impl MoneySum {
+
+    /// Take the money fields out of aggregate arguments.
+    pub(super) fn take(model: &Model, args: &mut JsonValue) -> Result<Self> {
+        let money_fields: Vec<&str> = model.fields.values().filter(|f| field_scalar(f) == Some(MONEY_SCALAR)).map(|f| f.name()).collect();
+        let Some(object) = args.as_object_mut() else { return Ok(Self::default()) };
+        for aggregate in ["_avg", "_min", "_max"] {
+            if let Some(field) = object.get(aggregate).and_then(|a| a.as_object()).and_then(|a| a.keys().find(|k| money_fields.contains(&k.as_str()))) {
+                Err(Error::invalid_request_message(format!("`{}` of money field `{}` is not supported, use `_sum`", aggregate, field)))?
+            }
+        }
+        let Some(JsonValue::Object(sum)) = object.get_mut("_sum") else { return Ok(Self::default()) };
+        let fields: Vec<String> = money_fields.iter().filter(|f| sum.remove(**f).and_then(|v| v.as_bool()) == Some(true)).map(|f| f.to_string()).collect();
+        if sum.is_empty() {
+            object.remove("_sum");
+        }
+        Ok(Self { fields })
+    }
+
+    /// Whether any money field is summed.
+    pub(super) fn is_effective(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    /// Sum the money fields of the records matching the `where` of an
+    /// aggregate and add them to its `_sum` output.
+    pub(super) async fn apply(&self, model: &'static Model, ctx: &request::Ctx, response: Response) -> Result<Response> {
+        let finder = teon!({ "where": ctx.body().get("where").cloned().unwrap_or(teon!({})) });
+        let objects: Vec<Object> = ctx.transaction_ctx().find_many(model, &finder, None, path![]).await?;
+        let mut sums = Map::new();
+        for field in &self.fields {
+            let mut values = vec![];
+            for object in &objects {
+                let value = JsonValue::try_from(&object.get_value(field)?)?;
+                if !value.is_null() {
+                    values.push(Money::from_json(&value)?);
+                }
+            }
+            let sum = sum_money(values.iter()).map_err(|e| Error::invalid_request_message(format!("`{}`: {}", field, e.message)))?;
+            sums.insert(field.clone(), sum.map_or(JsonValue::Null, |sum| sum.to_json()));
+        }
+        let mut json_value = match response.body().inner.as_ref() {
+            BodyInner::Teon(value) => JsonValue::try_from(value)?,
+            _ => JsonValue::Null,
+        };
+        let Some(data) = json_value.get_mut("data").and_then(|d| d.as_object_mut()) else { return Ok(response) };
+        match data.get_mut("_sum").and_then(|s| s.as_object_mut()) {
+            Some(existing) => existing.extend(sums),
+            None => { data.insert("_sum".to_owned(), JsonValue::Object(sums)); }
+        }
+        Ok(Response::teon(Value::from(json_value)))
+    }
+}
@@ -0,0 +1,32 @@
+use serde_json::Value as JsonValue;
+use teo_result::{Error, Result};
+use crate::app::Ctx;
+use crate::utils::named_queries::NamedQuery;
+
+/// The named query served at the path of a model action, if any.
+pub(super) fn named_query_for(model_path: &str, name: &str) -> Option<&'static NamedQuery> {
+    Ctx::named_queries().iter().find(|query| query.model.join(".") == model_path && query.name == name)
+}
+
+/// The `findMany` arguments of a named query request. The body is
+/// `{ params?, skip?, take? }`, the parameters are substituted into the
+/// stored arguments and `skip` and `take` replace the stored ones.
+pub(super) fn named_query_arguments(query: &NamedQuery, body: &JsonValue) -> Result<JsonValue> {
+    let params = body.get("params").cloned().unwrap_or(JsonValue::Object(Default::default()));
+    if !params.is_object() {
+        Err(Error::invalid_request_message("expect `params` to be an object"))?
+    }
+    let mut arguments = query.substitute(&params)?;
+    let Some(object) = arguments.as_object_mut() else {
+        return Err(Error::new(format!("query `{}` is not an object", query.name)));
+    };
+    for key in ["skip", "take"] {
+        if let Some(value) = body.get(key) {
+            if !value.is_i64() {
+                Err(Error::invalid_request_message(format!("expect `{}` to be an integer", key)))?
+            }
+            object.insert(key.to_owned(), value.clone());
+        }
+    }
+    Ok(arguments)
+}
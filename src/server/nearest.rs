@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+
+/// The distance metric of a `nearestTo` query.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Metric {
+    Cosine,
+    Euclidean,
+    DotProduct,
+}
+
+/// A `nearestTo: { field: { vector, metric, take } }` input of a `findMany`.
+///
+/// Vectors are stored as `Float[]` fields. The query engine doesn't understand
+/// the input, so it's removed from the arguments, and the records are ranked
+/// by their distance to `vector` on the server. Pagination is applied after
+/// ranking.
+#[derive(Debug, Clone)]
+pub(super) struct NearestTo {
+    field: String,
+    vector: Vec<f64>,
+    metric: Metric,
+    skip: Option<usize>,
+    take: Option<usize>,
+}
+
+impl NearestTo {
+
+    /// Take the `nearestTo` input out of query arguments.
+    pub(super) fn take(args: &mut JsonValue) -> Result<Option<Self>> {
+        let Some(object) = args.as_object_mut() else { return Ok(None) };
+        let Some(nearest_to) = object.remove("nearestTo") else { return Ok(None) };
+        let Some((field, input)) = nearest_to.as_object().and_then(|o| o.iter().next()) else {
+            Err(Error::invalid_request_message("expect `nearestTo` to have a vector field"))?
+        };
+        let vector: Vec<f64> = input.get("vector").and_then(|v| v.as_array()).map(|v| v.iter().filter_map(|n| n.as_f64()).collect()).unwrap_or_default();
+        if vector.is_empty() {
+            Err(Error::invalid_request_message("expect `nearestTo.vector` to be a list of numbers"))?
+        }
+        let metric = match input.get("metric").and_then(|m| m.as_str()).unwrap_or("cosine") {
+            "cosine" => Metric::Cosine,
+            "euclidean" => Metric::Euclidean,
+            "dotProduct" => Metric::DotProduct,
+            metric => Err(Error::invalid_request_message(format!("unknown metric `{}`, expect cosine, euclidean or dotProduct", metric)))?,
+        };
+        let skip = object.remove("skip").and_then(|s| s.as_u64()).map(|s| s as usize);
+        let take = object.remove("take").and_then(|t| t.as_u64()).map(|t| t as usize);
+        let take = match (take, input.get("take").and_then(|t| t.as_u64()).map(|t| t as usize)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Ok(Some(Self { field: field.clone(), vector, metric, skip, take }))
+    }
+
+    /// The distance of a record to the vector, smaller is closer.
+    fn distance(&self, record: &JsonValue) -> Option<f64> {
+        let other: Vec<f64> = record.get(&self.field)?.as_array()?.iter().filter_map(|n| n.as_f64()).collect();
+        if other.len() != self.vector.len() {
+            return None;
+        }
+        let dot: f64 = self.vector.iter().zip(&other).map(|(a, b)| a * b).sum();
+        Some(match self.metric {
+            Metric::Cosine => {
+                let norms = norm(&self.vector) * norm(&other);
+                if norms == 0.0 { 1.0 } else { 1.0 - dot / norms }
+            }
+            Metric::Euclidean => self.vector.iter().zip(&other).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt(),
+            Metric::DotProduct => -dot,
+        })
+    }
+
+    /// Rank and paginate the `data` of a response.
+    pub(super) fn apply(&self, json_value: &mut JsonValue) {
+        if let Some(JsonValue::Array(records)) = json_value.get_mut("data") {
+            // records without a vector of the right dimensions are left out
+            let mut ranked: Vec<(f64, JsonValue)> = records.drain(..).filter_map(|record| self.distance(&record).map(|d| (d, record))).collect();
+            ranked.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let count = ranked.len();
+            *records = ranked.into_iter().skip(self.skip.unwrap_or(0)).take(self.take.unwrap_or(usize::MAX)).map(|(_, record)| record).collect();
+            if let Some(meta) = json_value.get_mut("meta").and_then(|m| m.as_object_mut()) {
+                meta.insert("count".to_owned(), json!(count));
+            }
+        }
+    }
+}
+
+fn norm(vector: &[f64]) -> f64 {
+    vector.iter().map(|n| n * n).sum::<f64>().sqrt()
+}
@@ -0,0 +1,666 @@
+use std::collections::VecDeque;
+use key_path::path;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::error_ext::unique_value_duplicated;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::relation::Relation;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::server::lookup::{find, find_unique};
+use crate::server::output::output_record;
+
+/// Nested writes up to this many relation levels are left to the builtin
+/// handlers, deeper ones are planned as a write graph.
+const BUILTIN_DEPTH: usize = 2;
+
+/// The number of relation levels of the nested writes in the `create` or
+/// `update` argument of an action.
+pub(super) fn nested_depth(model: &Model, args: &JsonValue, main_namespace: &'static Namespace) -> usize {
+    ["create", "update"].iter().filter_map(|key| args.get(*key)).map(|record| record_depth(model, record, main_namespace)).max().unwrap_or(0)
+}
+
+/// The nested relation inputs which only the write graph supports. Nested
+/// updates are written by the graph too, which only writes the fields sent,
+/// so absent optional fields of the related records are never set to null.
+const GRAPH_OPERATIONS: [&str; 3] = ["connectOrCreate", "upsert", "update"];
+
+/// How many times a write graph is run again after it hits a unique
+/// violation, which happens when a concurrent request creates a record that
+/// `connectOrCreate` or `upsert` was about to create.
+const UNIQUE_VIOLATION_RETRIES: usize = 2;
+
+/// The nested list relation inputs which take where filters, written like
+/// `disconnect: [{ where }]`, `set: [{ where }]` and `deleteMany: { where }`.
+/// Only the write graph supports the filter form.
+const FILTER_OPERATIONS: [&str; 3] = ["disconnect", "set", "deleteMany"];
+
+/// Whether an action has to be run as a write graph, because its nested
+/// writes are deeper than the builtin handlers support, or because they use
+/// `connectOrCreate`, `upsert`, `update` or where filters.
+pub(super) fn needs_write_graph(model: &Model, args: &JsonValue, main_namespace: &'static Namespace) -> bool {
+    nested_depth(model, args, main_namespace) > BUILTIN_DEPTH
+        || ["create", "update"].iter().filter_map(|key| args.get(*key)).any(|record| uses_graph_operations(model, record, main_namespace))
+}
+
+fn uses_graph_operations(model: &Model, record: &JsonValue, main_namespace: &'static Namespace) -> bool {
+    let Some(record) = record.as_object() else { return false };
+    record.iter().any(|(key, nested)| {
+        let Some(relation) = model.relations().into_iter().find(|r| r.name() == key.as_str()) else { return false };
+        let Some(related_model) = main_namespace.model_at_path(&relation.model_path()) else { return false };
+        nested.as_object().map_or(false, |n| GRAPH_OPERATIONS.iter().any(|o| n.contains_key(*o)) || uses_filters(n))
+            || nested_records(nested).into_iter().any(|r| uses_graph_operations(related_model, r, main_namespace))
+    })
+}
+
+fn uses_filters(nested: &Map<String, JsonValue>) -> bool {
+    FILTER_OPERATIONS.iter().filter_map(|o| nested.get(*o)).any(|value| match value {
+        JsonValue::Array(items) => items.iter().any(|item| item.get("where").is_some()),
+        item => item.get("where").is_some(),
+    })
+}
+
+fn record_depth(model: &Model, record: &JsonValue, main_namespace: &'static Namespace) -> usize {
+    let Some(record) = record.as_object() else { return 0 };
+    record.iter().filter_map(|(key, nested)| {
+        let relation = model.relations().into_iter().find(|r| r.name() == key.as_str())?;
+        let related_model = main_namespace.model_at_path(&relation.model_path())?;
+        Some(1 + nested_records(nested).into_iter().map(|r| record_depth(related_model, r, main_namespace)).max().unwrap_or(0))
+    }).max().unwrap_or(0)
+}
+
+/// The records written by a nested relation input, the `create` and `update`
+/// of `{ where, create, update }` items included.
+fn nested_records(nested: &JsonValue) -> Vec<&JsonValue> {
+    let Some(nested) = nested.as_object() else { return vec![] };
+    let mut result = vec![];
+    for value in nested.values() {
+        let items: Vec<&JsonValue> = match value {
+            JsonValue::Array(items) => items.iter().collect(),
+            item => vec![item],
+        };
+        for item in items {
+            match item.as_object() {
+                Some(object) if object.contains_key("where") => result.extend(["create", "update"].iter().filter_map(|key| object.get(*key))),
+                Some(_) => result.push(item),
+                None => (),
+            }
+        }
+    }
+    result
+}
+
+/// Run a `create` or `update` action as a write graph with the transaction
+/// and the identity of `ctx`, then respond with the record like the builtin
+/// handler does, `include` and `select` honored.
+pub(super) async fn write(model: &'static Model, action: &str, args: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let graph = WriteGraph::plan(model, action, args, main_namespace)?;
+    let order = graph.order()?;
+    let object = graph.execute(&order, main_namespace, ctx).await?;
+    let mut finder = json!({ "where": JsonValue::try_from(&object.identifier())? });
+    for key in ["include", "select"] {
+        if let Some(value) = args.get(key) {
+            finder[key] = value.clone();
+        }
+    }
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &finder, main_namespace)?;
+    let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &input, Some(ctx.clone()), path![]).await?;
+    let object = found.into_iter().next().ok_or_else(|| Error::not_found())?;
+    Ok(Response::data(output_record(model, &object, 0, ctx).await?))
+}
+
+#[derive(Debug, Clone)]
+enum Operation {
+    /// Create a record with the scalar fields.
+    Create(Map<String, JsonValue>),
+    /// Find an existing record by a unique where input.
+    Connect(JsonValue),
+    /// Find an existing record by a unique where input and update its scalar
+    /// fields.
+    Update(JsonValue, Map<String, JsonValue>),
+    /// Find an existing record by a unique where input, or create one with
+    /// the scalar fields.
+    ConnectOrCreate(JsonValue, Map<String, JsonValue>),
+    /// Find a record linked to the record of the parent node through a
+    /// relation and update its scalar fields. A list relation finds it by a
+    /// where input.
+    NestedUpdate {
+        finder: Option<JsonValue>,
+        update: Map<String, JsonValue>,
+        relation: &'static Relation,
+        parent: usize,
+    },
+    /// Find the related record and update it, or create it. Without a where
+    /// input, the record is found through the relation to its parent.
+    Upsert {
+        finder: Option<JsonValue>,
+        create: Map<String, JsonValue>,
+        update: Map<String, JsonValue>,
+        parent: Option<Parent>,
+    },
+}
+
+/// The parent of a to-one upsert, with pairs of a field of the parent and a
+/// field of the related record.
+#[derive(Debug, Clone)]
+struct Parent {
+    index: usize,
+    fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    model: &'static Model,
+    operation: Operation,
+}
+
+/// The foreign key of `holder` points to `referenced`, as pairs of a field
+/// of the holder and a field of the referenced record.
+#[derive(Debug, Clone)]
+struct Reference {
+    holder: usize,
+    referenced: usize,
+    fields: Vec<(String, String)>,
+}
+
+/// A join record links two records of a many-to-many relation, with pairs of
+/// a field of the join record and a field of the linked record.
+#[derive(Debug, Clone)]
+struct Link {
+    through_model: &'static Model,
+    records: [(usize, Vec<(String, String)>); 2],
+}
+
+/// How a list relation of a record is pruned.
+#[derive(Debug, Clone)]
+enum PruneKind {
+    /// Unlink the related records matching any of the filters.
+    Disconnect(Vec<JsonValue>),
+    /// Link the records matching any of the filters, and unlink the others.
+    Set(Vec<JsonValue>),
+    /// Delete the related records matching the filter.
+    DeleteMany(JsonValue),
+}
+
+/// The pruning of a list relation of the record of a node. It's done right
+/// after the record is written, so records linked by the same write are not
+/// pruned.
+#[derive(Debug, Clone)]
+struct Prune {
+    index: usize,
+    relation: &'static Relation,
+    kind: PruneKind,
+}
+
+/// The records of a nested write as nodes, and the foreign keys and join
+/// records between them as edges. Nodes are written after the nodes their
+/// foreign keys point to, join records are written last.
+#[derive(Debug, Clone)]
+struct WriteGraph {
+    nodes: Vec<Node>,
+    references: Vec<Reference>,
+    links: Vec<Link>,
+    prunes: Vec<Prune>,
+}
+
+impl WriteGraph {
+
+    fn plan(model: &'static Model, action: &str, args: &JsonValue, main_namespace: &'static Namespace) -> Result<Self> {
+        let mut graph = Self { nodes: vec![], references: vec![], links: vec![], prunes: vec![] };
+        match action {
+            "create" => {
+                let record = args.get("create").cloned().unwrap_or(json!({}));
+                let (scalars, relations) = split(model, &record)?;
+                let root = graph.add(model, Operation::Create(scalars));
+                graph.add_relations(model, root, relations, main_namespace)?;
+            }
+            "update" => {
+                let finder = args.get("where").cloned().unwrap_or(json!({}));
+                let record = args.get("update").cloned().unwrap_or(json!({}));
+                let (scalars, relations) = split(model, &record)?;
+                let root = graph.add(model, Operation::Update(finder, scalars));
+                graph.add_relations(model, root, relations, main_namespace)?;
+            }
+            _ => Err(Error::new(format!("`{}` doesn't support deep nested writes", action)))?,
+        }
+        Ok(graph)
+    }
+
+    fn add(&mut self, model: &'static Model, operation: Operation) -> usize {
+        self.nodes.push(Node { model, operation });
+        self.nodes.len() - 1
+    }
+
+    fn add_relations(&mut self, model: &'static Model, index: usize, relations: Vec<(&'static Relation, JsonValue)>, main_namespace: &'static Namespace) -> Result<()> {
+        for (relation, nested) in relations {
+            let related_model = main_namespace.model_at_path(&relation.model_path()).ok_or_else(|| Error::not_found())?;
+            let Some(nested) = nested.as_object() else {
+                Err(Error::invalid_request_message(format!("expect `{}` to be an object", relation.name())))?
+            };
+            for (operation, value) in nested {
+                if FILTER_OPERATIONS.contains(&operation.as_str()) {
+                    if !relation.is_vec {
+                        Err(Error::invalid_request_message(format!("`{}` of `{}.{}` is only supported by list relations", operation, model.path().join("."), relation.name())))?
+                    }
+                    // a filter is `{ where }`, or a unique where input
+                    let filter = |item: &JsonValue| item.get("where").cloned().unwrap_or_else(|| item.clone());
+                    let filters: Vec<JsonValue> = match value {
+                        JsonValue::Array(items) => items.iter().map(filter).collect(),
+                        item => vec![filter(item)],
+                    };
+                    let kind = match operation.as_str() {
+                        "disconnect" => PruneKind::Disconnect(filters),
+                        "set" => PruneKind::Set(filters),
+                        _ => PruneKind::DeleteMany(json!({ "OR": filters })),
+                    };
+                    self.prunes.push(Prune { index, relation, kind });
+                    continue
+                }
+                let items: Vec<&JsonValue> = match value {
+                    JsonValue::Array(items) => items.iter().collect(),
+                    item => vec![item],
+                };
+                for item in items {
+                    let child = match operation.as_str() {
+                        "create" => {
+                            let (scalars, relations) = split(related_model, item)?;
+                            let child = self.add(related_model, Operation::Create(scalars));
+                            self.add_relations(related_model, child, relations, main_namespace)?;
+                            child
+                        }
+                        "connect" => self.add(related_model, Operation::Connect(item.clone())),
+                        "update" => {
+                            let (finder, record) = if relation.is_vec {
+                                let finder = item.get("where").cloned().ok_or_else(|| Error::invalid_request_message(format!("expect `where` in `update` of `{}`", relation.name())))?;
+                                (Some(finder), item.get("update").cloned().unwrap_or(json!({})))
+                            } else {
+                                (None, item.clone())
+                            };
+                            let (update, relations) = split(related_model, &record)?;
+                            let child = self.add(related_model, Operation::NestedUpdate { finder, update, relation, parent: index });
+                            // the record is linked already, it's only written after its parent
+                            self.references.push(Reference { holder: child, referenced: index, fields: vec![] });
+                            self.add_relations(related_model, child, relations, main_namespace)?;
+                            continue
+                        }
+                        "connectOrCreate" => {
+                            let finder = item.get("where").cloned().ok_or_else(|| Error::invalid_request_message(format!("expect `where` in `connectOrCreate` of `{}`", relation.name())))?;
+                            let (scalars, relations) = split(related_model, item.get("create").unwrap_or(&json!({})))?;
+                            if !relations.is_empty() {
+                                Err(Error::invalid_request_message(format!("nested relation writes are not supported inside `connectOrCreate` of `{}`", relation.name())))?
+                            }
+                            self.add(related_model, Operation::ConnectOrCreate(finder, scalars))
+                        }
+                        "upsert" => {
+                            let (create, create_relations) = split(related_model, item.get("create").unwrap_or(&json!({})))?;
+                            let (update, update_relations) = split(related_model, item.get("update").unwrap_or(&json!({})))?;
+                            if !create_relations.is_empty() || !update_relations.is_empty() {
+                                Err(Error::invalid_request_message(format!("nested relation writes are not supported inside `upsert` of `{}`", relation.name())))?
+                            }
+                            let finder = item.get("where").cloned();
+                            if finder.is_none() && relation.is_vec {
+                                Err(Error::invalid_request_message(format!("expect `where` in `upsert` of `{}`", relation.name())))?
+                            }
+                            let parent = if finder.is_none() && !relation.has_join_table() {
+                                Some(Parent { index, fields: pairs(relation.iter()) })
+                            } else {
+                                None
+                            };
+                            self.add(related_model, Operation::Upsert { finder, create, update, parent })
+                        }
+                        _ => Err(Error::invalid_request_message(format!("`{}` of `{}.{}` is not supported in nested writes deeper than {} relations", operation, model.path().join("."), relation.name(), BUILTIN_DEPTH)))?,
+                    };
+                    self.relate(index, child, relation, main_namespace);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the edge of a relation from the node at `index` to `child`.
+    fn relate(&mut self, index: usize, child: usize, relation: &'static Relation, main_namespace: &'static Namespace) {
+        if relation.has_join_table() {
+            let (through_model, through_relation) = main_namespace.through_relation(relation);
+            let (_, through_opposite_relation) = main_namespace.through_opposite_relation(relation);
+            self.links.push(Link {
+                through_model,
+                records: [(index, pairs(through_relation.iter())), (child, pairs(through_opposite_relation.iter()))],
+            });
+        } else if relation.has_foreign_key {
+            self.references.push(Reference { holder: index, referenced: child, fields: pairs(relation.iter()) });
+        } else {
+            self.references.push(Reference { holder: child, referenced: index, fields: pairs(relation.iter().map(|(local, foreign)| (foreign, local))) });
+        }
+    }
+
+    /// Whether a node looks up a record before it creates one, so a unique
+    /// violation may be a lost race worth retrying.
+    fn finds_before_create(&self) -> bool {
+        self.nodes.iter().any(|node| matches!(node.operation, Operation::ConnectOrCreate(..) | Operation::Upsert { .. }))
+    }
+
+    /// The nodes in write order.
+    fn order(&self) -> Result<Vec<usize>> {
+        let mut pending: Vec<usize> = vec![0; self.nodes.len()];
+        for reference in &self.references {
+            pending[reference.holder] += 1;
+        }
+        let mut ready: VecDeque<usize> = (0..self.nodes.len()).filter(|i| pending[*i] == 0).collect();
+        let mut order = vec![];
+        while let Some(index) = ready.pop_front() {
+            order.push(index);
+            for reference in self.references.iter().filter(|r| r.referenced == index) {
+                pending[reference.holder] -= 1;
+                if pending[reference.holder] == 0 {
+                    ready.push_back(reference.holder);
+                }
+            }
+        }
+        if order.len() < self.nodes.len() {
+            Err(Error::invalid_request_message("the nested writes reference each other in a cycle"))?
+        }
+        Ok(order)
+    }
+
+    /// Write the nodes in order, then the join records. Returns the root
+    /// record.
+    async fn execute(&self, order: &Vec<usize>, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Object> {
+        let mut objects: Vec<Option<Object>> = vec![None; self.nodes.len()];
+        for index in order {
+            let node = &self.nodes[*index];
+            let mut foreign_keys = vec![];
+            for reference in self.references.iter().filter(|r| r.holder == *index) {
+                let referenced = objects[reference.referenced].as_ref().ok_or_else(|| Error::new("nested write is out of order"))?;
+                for (field, referenced_field) in &reference.fields {
+                    foreign_keys.push((field.clone(), referenced.get_value(referenced_field)?));
+                }
+            }
+            // whether the record is an existing one which is only connected
+            let (object, connected) = match &node.operation {
+                Operation::Create(scalars) => (create(node.model, scalars, &foreign_keys, main_namespace, ctx).await?, false),
+                Operation::Connect(finder) => (find_unique(node.model, finder, main_namespace, &ctx.transaction_ctx(), Some(ctx)).await?
+                    .ok_or_else(|| Error::invalid_request_message(format!("the `{}` record to connect is not found", node.model.path().join("."))))?, true),
+                Operation::Update(finder, scalars) => {
+                    let object = find_unique(node.model, finder, main_namespace, &ctx.transaction_ctx(), Some(ctx)).await?.ok_or_else(|| Error::not_found())?;
+                    update(node.model, &object, finder, scalars, main_namespace).await?;
+                    (object, false)
+                }
+                Operation::NestedUpdate { finder, update: scalars, relation, parent } => {
+                    let parent_object = objects[*parent].as_ref().ok_or_else(|| Error::new("nested write is out of order"))?;
+                    let filter = finder.clone().unwrap_or(json!({}));
+                    let object = linked(*relation, parent_object, node.model, &filter, main_namespace, ctx).await?.into_iter().next()
+                        .ok_or_else(|| Error::invalid_request_message(format!("the `{}` record to update is not found", node.model.path().join("."))))?;
+                    let finder = JsonValue::try_from(&object.identifier())?;
+                    update(node.model, &object, &finder, scalars, main_namespace).await?;
+                    (object, false)
+                }
+                Operation::ConnectOrCreate(finder, scalars) => match find_unique(node.model, finder, main_namespace, &ctx.transaction_ctx(), Some(ctx)).await? {
+                    Some(object) => (object, true),
+                    None => (create(node.model, scalars, &foreign_keys, main_namespace, ctx).await?, false),
+                },
+                Operation::Upsert { finder, create: create_scalars, update: update_scalars, parent } => {
+                    let existing = match (finder, parent) {
+                        (Some(finder), _) => find_unique(node.model, finder, main_namespace, &ctx.transaction_ctx(), Some(ctx)).await?,
+                        (None, Some(parent)) => self.find_by_parent(node.model, parent, &objects, main_namespace, ctx).await?,
+                        (None, None) => None,
+                    };
+                    match existing {
+                        Some(object) => {
+                            let finder = JsonValue::try_from(&object.identifier())?;
+                            update(node.model, &object, &finder, update_scalars, main_namespace).await?;
+                            (object, false)
+                        }
+                        None => (create(node.model, create_scalars, &foreign_keys, main_namespace, ctx).await?, false),
+                    }
+                }
+            };
+            let changed = !connected || !foreign_keys.is_empty();
+            for (field, value) in foreign_keys {
+                object.set(field.as_str(), value)?;
+            }
+            if changed {
+                object.save().await?;
+            }
+            for prune in self.prunes.iter().filter(|p| p.index == *index) {
+                prune.execute(&object, main_namespace, ctx).await?;
+            }
+            objects[*index] = Some(object);
+        }
+        for link in &self.links {
+            let mut record = teon!({});
+            for (index, fields) in &link.records {
+                let object = objects[*index].as_ref().ok_or_else(|| Error::new("nested write is out of order"))?;
+                for (field, linked_field) in fields {
+                    record.as_dictionary_mut().unwrap().insert(field.clone(), object.get_value(linked_field)?);
+                }
+            }
+            let existing: Vec<Object> = ctx.transaction_ctx().find_many(link.through_model, &teon!({ "where": record.clone(), "take": 1 }), Some(ctx.clone()), path![]).await?;
+            if existing.is_empty() {
+                ctx.transaction_ctx().create_object(link.through_model, &record, Some(ctx.clone())).await?.save().await?;
+            }
+        }
+        objects.swap_remove(0).ok_or_else(|| Error::not_found())
+    }
+
+    /// Find the related record of a to-one upsert through its parent. The
+    /// parent is either written already, or it's an existing record which
+    /// holds the foreign key and is looked up by its where input.
+    async fn find_by_parent(&self, model: &'static Model, parent: &Parent, objects: &Vec<Option<Object>>, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Option<Object>> {
+        let parent_object = match &objects[parent.index] {
+            Some(object) => object.clone(),
+            None => {
+                let node = &self.nodes[parent.index];
+                let finder = match &node.operation {
+                    Operation::Update(finder, _) | Operation::Connect(finder) | Operation::ConnectOrCreate(finder, _) => finder,
+                    Operation::Upsert { finder: Some(finder), .. } => finder,
+                    _ => return Ok(None),
+                };
+                match find_unique(node.model, finder, main_namespace, &ctx.transaction_ctx(), Some(ctx)).await? {
+                    Some(object) => object,
+                    None => return Ok(None),
+                }
+            }
+        };
+        let mut finder = teon!({});
+        for (parent_field, field) in &parent.fields {
+            let value = parent_object.get_value(parent_field)?;
+            if value.is_null() {
+                return Ok(None);
+            }
+            finder.as_dictionary_mut().unwrap().insert(field.clone(), value);
+        }
+        let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": finder, "take": 1 }), Some(ctx.clone()), path![]).await?;
+        Ok(found.into_iter().next())
+    }
+}
+
+impl Prune {
+
+    async fn execute(&self, object: &Object, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<()> {
+        let related_model = main_namespace.model_at_path(&self.relation.model_path()).ok_or_else(|| Error::not_found())?;
+        match &self.kind {
+            PruneKind::Disconnect(filters) => {
+                for record in linked(self.relation, object, related_model, &json!({ "OR": filters }), main_namespace, ctx).await? {
+                    self.unlink(object, &record, related_model, main_namespace, ctx).await?;
+                }
+            }
+            PruneKind::Set(filters) => {
+                let targets = find(related_model, &json!({ "where": { "OR": filters } }), main_namespace, &ctx.transaction_ctx(), Some(ctx)).await?;
+                let linked = linked(self.relation, object, related_model, &json!({}), main_namespace, ctx).await?;
+                for record in &linked {
+                    if !targets.iter().any(|t| t.identifier() == record.identifier()) {
+                        self.unlink(object, record, related_model, main_namespace, ctx).await?;
+                    }
+                }
+                for record in &targets {
+                    if !linked.iter().any(|l| l.identifier() == record.identifier()) {
+                        self.link(object, record, main_namespace, ctx).await?;
+                    }
+                }
+            }
+            PruneKind::DeleteMany(filter) => {
+                for record in linked(self.relation, object, related_model, filter, main_namespace, ctx).await? {
+                    if self.relation.has_join_table() {
+                        self.unlink(object, &record, related_model, main_namespace, ctx).await?;
+                    }
+                    record.delete().await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn link(&self, object: &Object, record: &Object, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<()> {
+        if self.relation.has_join_table() {
+            let finder = self.join_finder(object, record, main_namespace)?;
+            let existing: Vec<Object> = ctx.transaction_ctx().find_many(main_namespace.through_relation(self.relation).0, &teon!({ "where": finder.clone(), "take": 1 }), Some(ctx.clone()), path![]).await?;
+            if existing.is_empty() {
+                ctx.transaction_ctx().create_object(main_namespace.through_relation(self.relation).0, &finder, Some(ctx.clone())).await?.save().await?;
+            }
+        } else {
+            for (field, related_field) in self.relation.iter() {
+                record.set(&related_field.to_string(), object.get_value(field)?)?;
+            }
+            record.save().await?;
+        }
+        Ok(())
+    }
+
+    async fn unlink(&self, object: &Object, record: &Object, related_model: &'static Model, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<()> {
+        if self.relation.has_join_table() {
+            let finder = self.join_finder(object, record, main_namespace)?;
+            let joins: Vec<Object> = ctx.transaction_ctx().find_many(main_namespace.through_relation(self.relation).0, &teon!({ "where": finder }), Some(ctx.clone()), path![]).await?;
+            for join in joins {
+                join.delete().await?;
+            }
+        } else {
+            for (_, related_field) in self.relation.iter() {
+                let optional = related_model.field(&related_field.to_string()).map_or(false, |f| f.is_optional());
+                if !optional {
+                    Err(Error::invalid_request_message(format!("cannot disconnect `{}` records, `{}` is required", related_model.path().join("."), related_field.to_string())))?
+                }
+                record.set(&related_field.to_string(), Value::Null)?;
+            }
+            record.save().await?;
+        }
+        Ok(())
+    }
+
+    /// The where input of the join records between `object` and `record`.
+    fn join_finder(&self, object: &Object, record: &Object, main_namespace: &'static Namespace) -> Result<Value> {
+        let (_, through_relation) = main_namespace.through_relation(self.relation);
+        let (_, through_opposite_relation) = main_namespace.through_opposite_relation(self.relation);
+        let mut finder = teon!({});
+        for (field, owner_field) in through_relation.iter() {
+            finder.as_dictionary_mut().unwrap().insert(field.to_string(), object.get_value(owner_field)?);
+        }
+        for (field, related_field) in through_opposite_relation.iter() {
+            finder.as_dictionary_mut().unwrap().insert(field.to_string(), record.get_value(related_field)?);
+        }
+        Ok(finder)
+    }
+}
+
+/// The records linked to `object` through a relation which match a where
+/// filter.
+async fn linked(relation: &'static Relation, object: &Object, related_model: &'static Model, filter: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Vec<Object>> {
+    let mut link = Map::new();
+    if relation.has_join_table() {
+        let (through_model, through_relation) = main_namespace.through_relation(relation);
+        let (_, through_opposite_relation) = main_namespace.through_opposite_relation(relation);
+        let mut finder = teon!({});
+        for (field, owner_field) in through_relation.iter() {
+            finder.as_dictionary_mut().unwrap().insert(field.to_string(), object.get_value(owner_field)?);
+        }
+        let joins: Vec<Object> = ctx.transaction_ctx().find_many(through_model, &teon!({ "where": finder }), Some(ctx.clone()), path![]).await?;
+        if joins.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut keys = vec![];
+        for join in joins {
+            let mut key = Map::new();
+            for (field, related_field) in through_opposite_relation.iter() {
+                key.insert(related_field.to_string(), JsonValue::try_from(&join.get_value(field)?)?);
+            }
+            keys.push(JsonValue::Object(key));
+        }
+        link.insert("OR".to_owned(), JsonValue::Array(keys));
+    } else {
+        for (field, related_field) in relation.iter() {
+            link.insert(related_field.to_string(), JsonValue::try_from(&object.get_value(field)?)?);
+        }
+    }
+    find(related_model, &json!({ "where": { "AND": [filter, JsonValue::Object(link)] } }), main_namespace, &ctx.transaction_ctx(), Some(ctx)).await
+}
+
+fn pairs<A: ToString, B: ToString>(iter: impl Iterator<Item = (A, B)>) -> Vec<(String, String)> {
+    iter.map(|(a, b)| (a.to_string(), b.to_string())).collect()
+}
+
+/// How many times the transaction of a write is retried after a unique
+/// violation. Graphs which look records up before creating them race with
+/// concurrent writes creating the same records, and succeed on a retry.
+pub(super) fn unique_violation_retries(model: &'static Model, action: &str, args: &JsonValue, main_namespace: &'static Namespace) -> usize {
+    match WriteGraph::plan(model, action, args, main_namespace) {
+        Ok(graph) if needs_write_graph(model, args, main_namespace) && graph.finds_before_create() => UNIQUE_VIOLATION_RETRIES,
+        _ => 0,
+    }
+}
+
+/// Whether an error is a violation of a unique constraint. Both connectors
+/// report one with `unique_value_duplicated`, so it's recognized by the
+/// error that builds, not by the wording of the database.
+pub(super) fn is_unique_violation(error: &Error) -> bool {
+    let violation = unique_value_duplicated(path![], "");
+    let Some(messages) = violation.errors.as_ref() else { return false };
+    error.code == violation.code && error.errors.as_ref().map_or(false, |errors| errors.values().any(|message| messages.values().any(|m| m == message)))
+}
+
+/// Create a record with its scalar fields and foreign keys.
+async fn create(model: &'static Model, scalars: &Map<String, JsonValue>, foreign_keys: &Vec<(String, Value)>, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Object> {
+    let mut record = scalars.clone();
+    for (field, value) in foreign_keys {
+        record.insert(field.clone(), JsonValue::try_from(value)?);
+    }
+    let action = builtin_action_handler_from_name("create").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "create": record }), main_namespace)?;
+    ctx.transaction_ctx().create_object(model, input.get("create").unwrap_or(&Value::Null), Some(ctx.clone())).await
+}
+
+/// Set the updated scalar fields of a record like the builtin update does,
+/// with their `@onSet` pipelines, readonly checks and update operators. Only
+/// the fields sent are set, a field sent as null is set to null and an
+/// absent field is left as is.
+async fn update(model: &'static Model, object: &Object, finder: &JsonValue, scalars: &Map<String, JsonValue>, main_namespace: &'static Namespace) -> Result<()> {
+    let action = builtin_action_handler_from_name("update").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "where": finder, "update": scalars }), main_namespace)?;
+    if let Some(Value::Dictionary(update)) = input.get("update") {
+        let sent = update.iter().filter(|(key, _)| scalars.contains_key(key.as_str())).map(|(key, value)| (key.clone(), value.clone())).collect();
+        object.set_teon(&Value::Dictionary(sent)).await?;
+    }
+    Ok(())
+}
+
+/// Split a record of a nested write into its scalar fields and its nested
+/// relation inputs.
+fn split(model: &'static Model, record: &JsonValue) -> Result<(Map<String, JsonValue>, Vec<(&'static Relation, JsonValue)>)> {
+    let record = record.as_object().ok_or_else(|| Error::invalid_request_message("expect a record to be an object"))?;
+    let mut scalars = Map::new();
+    let mut relations = vec![];
+    for (key, value) in record {
+        match model.relations().into_iter().find(|r| r.name() == key.as_str()) {
+            Some(relation) => relations.push((relation, value.clone())),
+            None => {
+                scalars.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Ok((scalars, relations))
+}
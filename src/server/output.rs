@@ -0,0 +1,66 @@
+use key_path::path;
+use teo_result::{Error, Result};
+use teo_runtime::handler::default::{find_first, find_many, find_unique};
+use teo_runtime::model::{Model, Object};
+use teo_runtime::pipeline::{self, Pipeline};
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::stdlib::decorators::on_output::model_output_pipeline;
+
+/// Run `findUnique`, `findFirst` or `findMany`, through the `@@onOutput`
+/// pipeline of the model if it has one.
+pub(super) async fn find(model: &'static Model, action: &str, ctx: &request::Ctx) -> Result<Response> {
+    if let Some(pipeline) = model_output_pipeline(model) {
+        return find_with_output(model, action, pipeline, ctx).await;
+    }
+    match action {
+        "findMany" => find_many(ctx).await,
+        "findFirst" => find_first(ctx).await,
+        _ => find_unique(ctx).await,
+    }
+}
+
+/// Run a find action of a model with an output pipeline. The records are
+/// fetched like the builtin handler does, then each record goes through the
+/// pipeline before it's serialized.
+async fn find_with_output(model: &'static Model, action: &str, pipeline: &Pipeline, ctx: &request::Ctx) -> Result<Response> {
+    let objects: Vec<Object> = ctx.transaction_ctx().find_many(model, ctx.body(), Some(ctx.clone()), path![]).await?;
+    let mut records = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        records.push(run_output_pipeline(model, pipeline, object, index, ctx).await?);
+    }
+    match action {
+        "findMany" => {
+            let finder = teon!({ "where": ctx.body().get("where").cloned().unwrap_or(teon!({})) });
+            let count = ctx.transaction_ctx().count_objects(model, &finder, path![]).await?;
+            Ok(Response::data_meta(Value::Array(records), teon!({ "count": count as i64 })))
+        }
+        _ => match records.into_iter().next() {
+            Some(record) => Ok(Response::data(record)),
+            None if action == "findUnique" => Err(Error::not_found()),
+            None => Ok(Response::data(Value::Null)),
+        },
+    }
+}
+
+/// Serialize a record for a response the way find actions do, through the
+/// `@@onOutput` pipeline of the model if it has one. `index` is the position
+/// of the record in the response.
+pub(super) async fn output_record(model: &'static Model, object: &Object, index: usize, ctx: &request::Ctx) -> Result<Value> {
+    match model_output_pipeline(model) {
+        Some(pipeline) => run_output_pipeline(model, pipeline, object, index, ctx).await,
+        None => object.to_teon().await,
+    }
+}
+
+async fn run_output_pipeline(model: &'static Model, pipeline: &Pipeline, object: &Object, index: usize, ctx: &request::Ctx) -> Result<Value> {
+    let value = object.to_teon().await?;
+    let pipeline_ctx = pipeline::Ctx::new(value, object.clone(), path![index], object.action(), ctx.transaction_ctx(), Some(ctx.clone()));
+    let output = pipeline_ctx.run_pipeline(pipeline).await?;
+    if !output.is_dictionary() {
+        Err(Error::new(format!("@@onOutput of `{}` must return a dictionary", model.path().join("."))))?
+    }
+    Ok(output)
+}
@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::path::Path;
 use actix_multipart::Multipart;
 use actix_web::{FromRequest, HttpRequest, web};
 use futures_util::{StreamExt, TryStreamExt};
@@ -6,16 +7,8 @@ use regex::Regex;
 use serde_json::{json, Value as JsonValue};
 use teo_result::{Result, Error};
 
-pub(super) async fn parse_json_body(mut payload: web::Payload) -> Result<JsonValue> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk.unwrap();
-        // limit max size of in-memory payload
-        if (body.len() + chunk.len()) > 262_144usize {
-            return Err(Error::internal_server_error_message("memory overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+pub(super) async fn parse_json_body(payload: web::Payload, limit: usize) -> Result<JsonValue> {
+    let body = read_body(payload, limit).await?;
     let parsed_json_body_result: std::result::Result<JsonValue, serde_json::Error> = serde_json::from_slice(&body);
     let parsed_json_body = match parsed_json_body_result {
         Ok(b) => b,
@@ -29,67 +22,84 @@ pub(super) async fn parse_json_body(mut payload: web::Payload) -> Result<JsonVal
     Ok(parsed_json_body)
 }
 
-pub(super) async fn parse_form_body(http_request: HttpRequest, mut payload: web::Payload) -> Result<JsonValue> {
+/// Read the whole request body, rejecting bodies over `limit` bytes with 413.
+pub(super) async fn read_body(mut payload: web::Payload, limit: usize) -> Result<web::BytesMut> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_| Error::invalid_request_message("cannot read request body"))?;
+        // limit max size of in-memory payload
+        if (body.len() + chunk.len()) > limit {
+            let mut error = Error::new(format!("request body is too large, the limit is {} bytes", limit));
+            error.code = 413;
+            return Err(error);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+pub(super) async fn parse_form_body(http_request: HttpRequest, payload: web::Payload) -> Result<JsonValue> {
     let mut inner_payload = payload.into_inner();
     let multipart_result = Multipart::from_request(&http_request, &mut inner_payload).await;
     let mut multipart = match multipart_result {
         Ok(multipart) => multipart,
-        Err(err) => return Err(Error::invalid_request_message("incorrect form format")),
+        Err(_) => return Err(Error::invalid_request_message("incorrect form format")),
     };
-    let mut result_value = json!({});
-    while let Some(mut field) = multipart.try_next().await.unwrap() {
+    let mut result_value = serde_json::Map::new();
+    while let Some(mut field) = multipart.try_next().await.map_err(|_| Error::invalid_request_message("incorrect form format"))? {
         // A multipart/form-data stream has to contain `content_disposition`
         if let Some(filename) = field.content_disposition().get_filename().map(|f| f.to_owned()) {
-            let filepath = std::env::temp_dir().join(filename.clone()).to_str().unwrap().to_owned();
+            // only keep the last component so that the file cannot escape the temp dir
+            let Some(basename) = Path::new(&filename).file_name().map(|n| n.to_owned()) else {
+                return Err(Error::invalid_request_message("invalid filename"));
+            };
+            let filepath = std::env::temp_dir().join(basename).to_string_lossy().to_string();
             let filepath2 = filepath.clone();
             // File::create is blocking operation, use threadpool
-            let mut f = web::block(move || std::fs::File::create(&filepath)).await.unwrap().unwrap();
+            let mut f = web::block(move || std::fs::File::create(&filepath)).await.map_err(file_error)?.map_err(file_error)?;
             // Field in turn is stream of *Bytes* object
-            while let Some(chunk) = field.try_next().await.unwrap() {
+            while let Some(chunk) = field.try_next().await.map_err(|_| Error::invalid_request_message("incorrect form format"))? {
                 // filesystem operations are blocking, we have to use threadpool
-                f = web::block(move || f.write_all(&chunk).map(|_| f)).await.unwrap().unwrap();
+                f = web::block(move || f.write_all(&chunk).map(|_| f)).await.map_err(file_error)?.map_err(file_error)?;
             }
+            let file_value = json!({
+                "filepath": filepath2,
+                "contentType": field.content_type().map(|c| c.to_string()),
+                "filename": filename,
+                "filenameExt": field.content_disposition().get_filename_ext().map(|e| e.to_string()),
+            });
             let owned_field_name = field.name().to_owned();
-            if owned_field_name.ends_with("[]") {
-                let field_name_without_suffix = owned_field_name.strip_suffix("[]").unwrap();
-                if !result_value.as_object_mut().unwrap().contains_key(field_name_without_suffix) {
-                    result_value.as_object_mut().unwrap().insert(field_name_without_suffix.to_owned(), json!([]));
+            if let Some(field_name_without_suffix) = owned_field_name.strip_suffix("[]") {
+                match result_value.entry(field_name_without_suffix.to_owned()).or_insert_with(|| json!([])) {
+                    JsonValue::Array(files) => files.push(file_value),
+                    _ => return Err(Error::invalid_request_message(format!("form field `{}` is both a list and a single value", field_name_without_suffix))),
                 }
-                result_value.as_object_mut().unwrap().get_mut(field_name_without_suffix).unwrap().as_array_mut().unwrap().push(json!({
-                    "filepath": filepath2,
-                    "contentType": field.content_type().map(|c| c.to_string()),
-                    "filename": filename,
-                    "filenameExt": field.content_disposition().get_filename_ext().map(|e| e.to_string()),
-                }));
             } else if owned_field_name.ends_with("]") {
                 let regex = Regex::new("(.*)\\[(.*)\\]").unwrap();
-                let found = regex.captures(&owned_field_name).unwrap();
-                let field_name = found.get(1).unwrap().as_str().to_owned();
-                let dict_name = found.get(2).unwrap().as_str().to_owned();
-                if !result_value.as_object_mut().unwrap().contains_key(&field_name) {
-                    result_value.as_object_mut().unwrap().insert(field_name.clone(), json!([]));
+                let Some(found) = regex.captures(&owned_field_name) else {
+                    return Err(Error::invalid_request_message(format!("invalid form field name `{}`", owned_field_name)));
+                };
+                let field_name = found[1].to_owned();
+                let dict_name = found[2].to_owned();
+                match result_value.entry(field_name.clone()).or_insert_with(|| json!({})) {
+                    JsonValue::Object(files) => { files.insert(dict_name, file_value); },
+                    _ => return Err(Error::invalid_request_message(format!("form field `{}` is both a dictionary and a single value", field_name))),
                 }
-                result_value.as_object_mut().unwrap().get_mut(&field_name).unwrap().as_object_mut().unwrap().insert(dict_name, json!({
-                    "filepath": filepath2,
-                    "contentType": field.content_type().map(|c| c.to_string()),
-                    "filename": filename,
-                    "filenameExt": field.content_disposition().get_filename_ext().map(|e| e.to_string()),
-                }));
             } else {
-                result_value.as_object_mut().unwrap().insert(field.name().to_owned(), json!({
-                    "filepath": filepath2,
-                    "contentType": field.content_type().map(|c| c.to_string()),
-                    "filename": filename,
-                    "filenameExt": field.content_disposition().get_filename_ext().map(|e| e.to_string()),
-                }));
+                result_value.insert(owned_field_name, file_value);
             }
         } else {
             let mut body = web::BytesMut::new();
-            while let Some(chunk) = field.try_next().await.unwrap() {
+            while let Some(chunk) = field.try_next().await.map_err(|_| Error::invalid_request_message("incorrect form format"))? {
                 body.extend_from_slice(&chunk);
             }
-            result_value.as_object_mut().unwrap().insert(field.name().to_owned(), serde_json::Value::String(String::from_utf8(body.as_ref().to_vec()).unwrap()));
+            let text = String::from_utf8(body.to_vec()).map_err(|_| Error::invalid_request_message(format!("form field `{}` is not valid utf-8", field.name())))?;
+            result_value.insert(field.name().to_owned(), JsonValue::String(text));
         }
     }
-    Ok(result_value)
+    Ok(JsonValue::Object(result_value))
+}
+
+fn file_error(err: impl std::fmt::Display) -> Error {
+    Error::internal_server_error_message(format!("cannot save uploaded file: {}", err))
 }
@@ -0,0 +1,107 @@
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+use crate::server::lookup::find_unique;
+
+/// The update operator which sets a field only if it's null.
+const SET_IF_MISSING: &str = "setIfMissing";
+
+/// Whether the `update` argument of an action, nested relation updates
+/// included, uses `setIfMissing`.
+pub(super) fn has_set_if_missing(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(object) => object.contains_key(SET_IF_MISSING) || object.values().any(has_set_if_missing),
+        JsonValue::Array(list) => list.iter().any(has_set_if_missing),
+        _ => false,
+    }
+}
+
+/// Resolve the `setIfMissing` operators in the `update` argument of an
+/// `update` or `upsert` action, and in its nested relation updates, against
+/// the records read with the transaction of the action, so they can't change
+/// before the update is written. A field which is null is set to the value,
+/// and other fields are left out of the update, so they're not written at
+/// all.
+pub(super) async fn resolve_set_if_missing(model: &'static Model, action: &str, args: &mut JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<()> {
+    if !args.get("update").map_or(false, has_set_if_missing) {
+        return Ok(());
+    }
+    if action == "updateMany" {
+        Err(Error::invalid_request_message("`setIfMissing` is not supported in `updateMany`, update the records one by one"))?
+    }
+    let finder = args.get("where").cloned().unwrap_or(json!({}));
+    let object = find_unique(model, &finder, main_namespace, ctx, None).await?;
+    let Some(update) = args.get_mut("update") else { return Ok(()) };
+    resolve(model, object, update, main_namespace, ctx).await
+}
+
+fn resolve<'a>(model: &'static Model, object: Option<Object>, update: &'a mut JsonValue, main_namespace: &'static Namespace, ctx: &'a transaction::Ctx) -> BoxFuture<'a, Result<()>> {
+    async move {
+        let Some(update) = update.as_object_mut() else { return Ok(()) };
+        let keys: Vec<String> = update.keys().cloned().collect();
+        for key in keys {
+            if model.field(&key).is_some() {
+                let Some(value) = update.get(&key).and_then(|v| v.as_object()).and_then(|o| o.get(SET_IF_MISSING)).cloned() else { continue };
+                let missing = match &object {
+                    Some(object) => object.get_value(&key)?.is_null(),
+                    // the record doesn't exist, the update is not applied
+                    None => false,
+                };
+                if missing {
+                    update.insert(key, value);
+                } else {
+                    update.remove(&key);
+                }
+            } else if let Some(relation) = model.relations().into_iter().find(|r| r.name() == key.as_str()) {
+                let Some(nested) = update.get_mut(&key).and_then(|n| n.as_object_mut()) else { continue };
+                if nested.get("updateMany").map_or(false, has_set_if_missing) {
+                    Err(Error::invalid_request_message(format!("`setIfMissing` is not supported in the `updateMany` of `{}`", key)))?
+                }
+                let related_model = main_namespace.model_at_path(&relation.model_path()).ok_or_else(|| Error::not_found())?;
+                for operation in ["update", "upsert"] {
+                    let items: Vec<&mut JsonValue> = match nested.get_mut(operation) {
+                        Some(JsonValue::Array(items)) => items.iter_mut().collect(),
+                        Some(item) => vec![item],
+                        None => continue,
+                    };
+                    for item in items {
+                        if !has_set_if_missing(item) {
+                            continue
+                        }
+                        if relation.is_vec {
+                            // `{ where, update }` with a unique where of the related model
+                            let finder = item.get("where").cloned().unwrap_or(json!({}));
+                            let related = find_unique(related_model, &finder, main_namespace, ctx, None).await?;
+                            if let Some(update) = item.get_mut("update") {
+                                resolve(related_model, related, update, main_namespace, ctx).await?;
+                            }
+                        } else {
+                            let related = match &object {
+                                Some(object) => {
+                                    let mut finder = teon!({});
+                                    for (field, reference) in relation.iter() {
+                                        finder.as_dictionary_mut().unwrap().insert(reference.to_owned(), object.get_value(field)?);
+                                    }
+                                    let found: Vec<Object> = ctx.find_many(related_model, &teon!({ "where": finder, "take": 1 }), None, path![]).await?;
+                                    found.into_iter().next()
+                                }
+                                None => None,
+                            };
+                            let update = if operation == "upsert" { item.get_mut("update") } else { Some(item) };
+                            if let Some(update) = update {
+                                resolve(related_model, related, update, main_namespace, ctx).await?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }.boxed()
+}
@@ -0,0 +1,38 @@
+use teo_result::Result;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::request;
+use teo_runtime::Value;
+use crate::server::error::UserError;
+use crate::server::impersonation::effective_identity;
+use crate::server::signed_url::has_valid_url_signature;
+use crate::stdlib::decorators::permissions::{model_guard, Guard};
+
+/// Check the `@@permissions` guard of a model action against the identity of
+/// a request, or the identity it acts as. Fails with 401 if the action requires an identity and there
+/// is none, and with 403 if the identity isn't allowed. `findUnique` through
+/// a signed URL is always allowed.
+pub(crate) async fn check_permission(model: &Model, action: &str, ctx: &request::Ctx) -> Result<()> {
+    if action == "findUnique" && has_valid_url_signature(ctx.request().path(), ctx.request().query_string()) {
+        return Ok(());
+    }
+    let identity = effective_identity(ctx).await?;
+    let Some(guard) = model_guard(model, action) else { return Ok(()) };
+    match (&guard, identity) {
+        (Guard::Everyone, _) => Ok(()),
+        (Guard::Nobody, _) => Err(UserError::new("ACTION_NOT_ALLOWED", format!("`{}` of `{}` is not allowed", action, model.path().join("."))).with_status(403).into()),
+        (_, None) => Err(UserError::new("IDENTITY_REQUIRED", "identity is required").with_status(401).into()),
+        (Guard::Identity, Some(_)) => Ok(()),
+        (Guard::Role(role), Some(identity)) => {
+            if has_role(&identity, role)? { Ok(()) } else { Err(UserError::new("ROLE_REQUIRED", format!("`{}` of `{}` requires the role `{}`", action, model.path().join("."), role)).with_status(403).into()) }
+        }
+    }
+}
+
+/// Whether the `role` field of an identity is, or contains, the role.
+pub(crate) fn has_role(identity: &Object, role: &str) -> Result<bool> {
+    Ok(match identity.get_value("role")? {
+        Value::String(value) => value == role,
+        Value::Array(values) => values.iter().any(|v| v.as_str() == Some(role)),
+        _ => false,
+    })
+}
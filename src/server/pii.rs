@@ -0,0 +1,84 @@
+use key_path::path;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use ring::digest::{digest, SHA256};
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::events::object_json;
+use crate::generate::mobile::collect_models;
+use crate::seeder::fake::fake_value;
+use crate::stdlib::decorators::pii_strategy::{field_pii_strategy, PiiStrategy};
+
+/// The `anonymize` action of a model with personal data fields. The body is
+/// `{ where }` of a unique record, whose `@piiStrategy` fields are scrubbed
+/// in place. Keys are never scrubbed, so records referencing it stay
+/// related. Responds with the scrubbed record.
+pub(super) async fn anonymize(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: transaction::Ctx) -> Result<Response> {
+    let object = find_record(model, body, main_namespace, &ctx).await?;
+    let mut rng = StdRng::from_entropy();
+    for field in model.fields.values() {
+        let Some(strategy) = field_pii_strategy(field) else { continue };
+        if field.foreign_key {
+            Err(Error::new(format!("`{}.{}` is a foreign key, it cannot be anonymized", model.path().join("."), field.name())))?
+        }
+        let value = match strategy {
+            PiiStrategy::Null => {
+                if !field.is_optional() {
+                    Err(Error::new(format!("`{}.{}` isn't optional, it cannot be anonymized with .null", model.path().join("."), field.name())))?
+                }
+                Value::Null
+            }
+            PiiStrategy::Hash => match object.get_value(field.name())? {
+                Value::Null => Value::Null,
+                Value::String(value) => Value::String(digest(&SHA256, value.as_bytes()).as_ref().iter().map(|b| format!("{:02x}", b)).collect()),
+                _ => Err(Error::new(format!("`{}.{}` isn't a string, it cannot be anonymized with .hash", model.path().join("."), field.name())))?,
+            },
+            PiiStrategy::Fake => fake_value(field, field.r#type(), main_namespace, &mut rng),
+        };
+        object.set_value(field.name(), value)?;
+    }
+    object.save().await?;
+    Ok(Response::data(Value::from(object_json(&object)?)))
+}
+
+/// The `export` action of a model with personal data fields. The body is
+/// `{ where }` of a unique record. Responds with the record and the records
+/// of all models whose relations reference it, keyed by model path.
+pub(super) async fn export(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: transaction::Ctx) -> Result<Response> {
+    let object = find_record(model, body, main_namespace, &ctx).await?;
+    let mut models = vec![];
+    collect_models(main_namespace, &mut models);
+    let mut linked = Map::new();
+    for other in models {
+        for relation in other.relations().into_iter().filter(|r| r.has_foreign_key && r.model_path() == model.path()) {
+            let mut finder = teon!({});
+            for (field, reference) in relation.iter() {
+                finder.as_dictionary_mut().unwrap().insert(field.to_owned(), object.get_value(reference)?);
+            }
+            let records: Vec<Object> = ctx.find_many(other, &teon!({ "where": finder }), None, path![]).await?;
+            let JsonValue::Array(entry) = linked.entry(other.path().join(".")).or_insert(json!([])) else { continue };
+            for record in records {
+                entry.push(object_json(&record)?);
+            }
+        }
+    }
+    Ok(Response::data(Value::from(json!({ "record": object_json(&object)?, "linked": linked }))))
+}
+
+async fn find_record(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<Object> {
+    let finder = body.get("where").cloned().ok_or_else(|| Error::invalid_request_message("expect `where` to be an object"))?;
+    let find_unique = builtin_action_handler_from_name("findUnique").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_unique, &json!({ "where": finder }), main_namespace)?;
+    let found: Vec<Object> = ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+    found.into_iter().next().ok_or_else(|| Error::not_found())
+}
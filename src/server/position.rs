@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use crate::server::lookup::{find, find_unique};
+use crate::stdlib::decorators::position::model_position_fields;
+
+/// A lock per list, so that moves within a list don't interleave. Locks only
+/// serialize the moves of this process.
+static LOCKS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Append the records in the `create` argument of an action which don't have
+/// a position to the end of their lists, read with the transaction of the
+/// action.
+pub(super) async fn fill_positions(model: &'static Model, args: &mut JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<()> {
+    let position_fields = model_position_fields(model);
+    let records: Vec<&mut JsonValue> = match args.get_mut("create") {
+        Some(JsonValue::Array(records)) => records.iter_mut().collect(),
+        Some(record) => vec![record],
+        None => return Ok(()),
+    };
+    // the next position of each list, records of the same request are
+    // appended one after another
+    let mut next: HashMap<String, i64> = HashMap::new();
+    for record in records {
+        let Some(record) = record.as_object_mut() else { continue };
+        for (field, scope) in &position_fields {
+            if record.get(field.name()).map_or(false, |p| !p.is_null()) {
+                continue
+            }
+            let mut scope_where = Map::new();
+            for name in scope {
+                let value = record.get(name).ok_or_else(|| Error::invalid_request_message(format!("`{}` is required to position `{}`", name, field.name())))?;
+                scope_where.insert(name.clone(), value.clone());
+            }
+            let key = format!("{}{}", field.name(), JsonValue::Object(scope_where.clone()));
+            let position = match next.get(&key) {
+                Some(position) => *position,
+                None => {
+                    let last = find(model, &json!({ "where": scope_where, "orderBy": { field.name(): "desc" }, "take": 1 }), main_namespace, ctx, None).await?;
+                    match last.first().map(|object| object.get_value(field.name())).transpose()? {
+                        Some(Value::Int(position)) => position as i64 + 1,
+                        Some(Value::Int64(position)) => position + 1,
+                        _ => 0,
+                    }
+                }
+            };
+            next.insert(key, position + 1);
+            record.insert(field.name().to_owned(), json!(position));
+        }
+    }
+    Ok(())
+}
+
+/// Apply the `moveBefore`, `moveAfter` and `moveTo` operators in the `update`
+/// argument of an action with its transaction, so the renumbering is undone
+/// if the update fails. The siblings are renumbered from zero without gaps,
+/// the moved record is saved with its new position, and the operator is
+/// replaced with the position.
+pub(super) async fn apply_moves(model: &'static Model, args: &mut JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<()> {
+    let finder = args.get("where").cloned().unwrap_or(json!({}));
+    let Some(update) = args.get_mut("update").and_then(|u| u.as_object_mut()) else { return Ok(()) };
+    for (field, scope) in model_position_fields(model) {
+        let Some(operator) = update.get(field.name()).and_then(|o| o.as_object()).cloned() else { continue };
+        if !["moveBefore", "moveAfter", "moveTo"].iter().any(|key| operator.contains_key(*key)) {
+            continue
+        }
+        let object = find_unique(model, &finder, main_namespace, ctx, None).await?.ok_or_else(|| Error::not_found())?;
+        let mut scope_where = Map::new();
+        for name in &scope {
+            let value = match update.get(name) {
+                Some(value) => value.clone(),
+                None => JsonValue::try_from(&object.get_value(name)?)?,
+            };
+            scope_where.insert(name.clone(), value);
+        }
+        let lock = lock_for(format!("{}.{}{}", model.path().join("."), field.name(), JsonValue::Object(scope_where.clone())));
+        let _guard = lock.lock().await;
+        let identifier = object.identifier();
+        let siblings: Vec<Object> = find(model, &json!({ "where": scope_where, "orderBy": { field.name(): "asc" } }), main_namespace, ctx, None).await?
+            .into_iter().filter(|sibling| sibling.identifier() != identifier).collect();
+        let index = if let Some(index) = operator.get("moveTo") {
+            index.as_u64().ok_or_else(|| Error::invalid_request_message("expect `moveTo` to be an index"))?.min(siblings.len() as u64) as usize
+        } else {
+            let (key, offset) = if operator.contains_key("moveBefore") { ("moveBefore", 0) } else { ("moveAfter", 1) };
+            let reference = find_unique(model, &operator[key], main_namespace, ctx, None).await?.ok_or_else(|| Error::invalid_request_message(format!("the record of `{}` is not found", key)))?;
+            let reference_identifier = reference.identifier();
+            let position = siblings.iter().position(|sibling| sibling.identifier() == reference_identifier)
+                .ok_or_else(|| Error::invalid_request_message(format!("the record of `{}` is not in the same list", key)))?;
+            position + offset
+        };
+        for (i, sibling) in siblings.iter().enumerate() {
+            let position = Value::Int((if i < index { i } else { i + 1 }) as i32);
+            if sibling.get_value(field.name())? != position {
+                sibling.set(field.name(), position)?;
+                sibling.save().await?;
+            }
+        }
+        object.set(field.name(), Value::Int(index as i32))?;
+        object.save().await?;
+        update.insert(field.name().to_owned(), json!(index));
+    }
+    Ok(())
+}
+
+fn lock_for(key: String) -> Arc<tokio::sync::Mutex<()>> {
+    LOCKS.lock().unwrap().entry(key).or_insert_with(|| Arc::new(tokio::sync::Mutex::new(()))).clone()
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use serde_json::{Map, Value as JsonValue};
+
+/// The fields requested for the records of a response, through any depth of
+/// `include`.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Projection {
+    select: Option<Vec<String>>,
+    omit: Option<Vec<String>>,
+    include: BTreeMap<String, Projection>,
+}
+
+impl Projection {
+
+    /// Build the projection of query arguments. `omit` isn't understood by the
+    /// query engine, so it's removed from the arguments and applied to the
+    /// response instead.
+    pub(super) fn take(args: &mut JsonValue) -> Self {
+        let Some(object) = args.as_object_mut() else { return Self::default() };
+        let mut omit = object.remove("omit").and_then(|omit| keys_set_to(&omit, true));
+        let mut select = object.get("select").and_then(|select| keys_set_to(select, true));
+        if select.as_ref().map_or(false, |s| s.is_empty()) {
+            // a select with only `false` values excludes fields
+            select = None;
+            let excluded = object.get("select").and_then(|select| keys_set_to(select, false)).unwrap_or_default();
+            omit.get_or_insert_with(Vec::new).extend(excluded);
+        }
+        let mut include = BTreeMap::new();
+        if let Some(JsonValue::Object(relations)) = object.get_mut("include") {
+            for (name, args) in relations.iter_mut() {
+                include.insert(name.clone(), if args.is_object() { Self::take(args) } else { Self::default() });
+            }
+        }
+        Self { select, omit, include }
+    }
+
+    /// Whether applying the projection can change a response.
+    pub(super) fn is_effective(&self) -> bool {
+        self.select.is_some() || self.omit.is_some() || self.include.values().any(|p| p.is_effective())
+    }
+
+    /// Apply the projection to a record or a list of records.
+    pub(super) fn apply(&self, value: &mut JsonValue) {
+        match value {
+            JsonValue::Array(records) => records.iter_mut().for_each(|record| self.apply(record)),
+            JsonValue::Object(record) => {
+                if let Some(select) = &self.select {
+                    record.retain(|key, _| select.contains(key) || self.include.contains_key(key));
+                }
+                if let Some(omit) = &self.omit {
+                    record.retain(|key, _| !omit.contains(key));
+                }
+                for (name, projection) in &self.include {
+                    if let Some(related) = record.get_mut(name) {
+                        projection.apply(related);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn keys_set_to(value: &JsonValue, flag: bool) -> Option<Vec<String>> {
+    let object: &Map<String, JsonValue> = value.as_object()?;
+    Some(object.iter().filter(|(_, v)| v.as_bool() == Some(flag)).map(|(k, _)| k.clone()).collect())
+}
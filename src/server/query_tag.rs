@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+use std::future::Future;
+use crate::app::Ctx;
+
+/// What the statements of a request are tagged with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct QueryTag {
+    pub(crate) request_id: String,
+    /// The model path or handler group path, once the request is matched.
+    pub(crate) model: Option<String>,
+    pub(crate) action: Option<String>,
+}
+
+impl QueryTag {
+
+    /// The tag as a SQL comment in the sqlcommenter format, keys sorted and
+    /// values URL encoded, so that the comment can't be closed early.
+    pub(crate) fn comment(&self) -> String {
+        let pairs: Vec<String> = [("action", self.action.as_deref()), ("model", self.model.as_deref()), ("request_id", Some(self.request_id.as_str()))]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{}='{}'", key, url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>())))
+            .collect();
+        format!("/*{}*/", pairs.join(","))
+    }
+}
+
+tokio::task_local! {
+    static QUERY_TAG: RefCell<QueryTag>;
+}
+
+/// Run a request with a query tag carrying its id.
+pub(crate) async fn with_query_tag<F: Future>(request_id: String, future: F) -> F::Output {
+    QUERY_TAG.scope(RefCell::new(QueryTag { request_id, model: None, action: None }), future).await
+}
+
+/// Record the model and action the current request is matched to.
+pub(crate) fn set_query_target(model: String, action: String) {
+    let _ = QUERY_TAG.try_with(|tag| {
+        let mut tag = tag.borrow_mut();
+        tag.model = Some(model);
+        tag.action = Some(action);
+    });
+}
+
+/// Append the tag of the current request to a SQL statement when query tags
+/// are enabled. Statements run outside of requests are left alone.
+pub(crate) fn tagged(sql: String) -> String {
+    if !Ctx::query_tags() {
+        return sql;
+    }
+    match QUERY_TAG.try_with(|tag| tag.borrow().comment()) {
+        Ok(comment) => format!("{} {}", sql.trim_end().trim_end_matches(';'), comment),
+        Err(_) => sql,
+    }
+}
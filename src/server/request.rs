@@ -26,14 +26,14 @@ impl CookieImpl {
             value: cookie.value().to_owned(),
             expires_datetime: if let Some(expires) = cookie.expires() {
                 if let Some(datetime) = expires.datetime() {
-                    Some(Utc.timestamp_opt(datetime.unix_timestamp(), 0).unwrap())
+                    Utc.timestamp_opt(datetime.unix_timestamp(), 0).single()
                 } else {
                     None
                 }
             } else {
                 None
             },
-            expires_session: cookie.expires().is_some() && cookie.expires().unwrap().is_session(),
+            expires_session: cookie.expires().map_or(false, |e| e.is_session()),
             secure: cookie.secure(),
             max_age: cookie.max_age().map(|v| v.as_seconds_f64()),
         }
@@ -90,7 +90,7 @@ impl teo_runtime::request::header::readonly::r#trait::HeaderMap for HeadersImpl
     }
 
     fn get(&self, key: &str) -> Option<&str> {
-        self.http_headers.get(key).map(|v| v.to_str().unwrap())
+        self.http_headers.get(key).and_then(|v| v.to_str().ok())
     }
 }
 
@@ -0,0 +1,23 @@
+use actix_http::header::{HeaderMap, HeaderName, HeaderValue};
+use uuid::Uuid;
+
+/// The header which carries the request id in both directions.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The incoming request id if it's acceptable, otherwise a new one. Incoming
+/// ids are limited to 128 visible ASCII characters.
+pub(crate) fn request_id_for(headers: &HeaderMap) -> String {
+    headers.get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic()))
+        .map(|id| id.to_owned())
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Set the request id header, so that handlers can read the id from the
+/// request headers and clients can read it from the response headers.
+pub(crate) fn insert_request_id(headers: &mut HeaderMap, request_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        headers.insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+}
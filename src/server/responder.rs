@@ -1,8 +1,10 @@
-use actix_web::{HttpRequest, HttpResponse};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
 use actix_web::http::StatusCode;
 use teo_runtime::response::body::BodyInner;
 use teo_runtime::response::Response;
 use actix_files::NamedFile;
+use teo_result::Error;
+use crate::server::error::WrapError;
 
 pub trait IntoHttpResponse {
     fn into_http_response(self, http_request: HttpRequest) -> HttpResponse;
@@ -12,19 +14,25 @@ impl IntoHttpResponse for Response {
 
     fn into_http_response(self, http_request: HttpRequest) -> HttpResponse {
         let mut builder = HttpResponse::Ok();
-        builder.status(StatusCode::from_u16(self.code()).unwrap());
+        builder.status(StatusCode::from_u16(self.code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR));
         for key in self.headers().keys() {
-            builder.insert_header((key.clone(), self.headers().get(&key).unwrap().as_str()));
+            if let Some(value) = self.headers().get(&key) {
+                builder.insert_header((key.clone(), value.as_str()));
+            }
         }
         match self.body().inner.as_ref() {
             BodyInner::Empty => (),
             BodyInner::String(content) => return builder.body(content.to_string()),
-            BodyInner::File(file) => return NamedFile::open(file).unwrap().into_response(&http_request),
+            BodyInner::File(file) => return match NamedFile::open(file) {
+                Ok(file) => file.into_response(&http_request),
+                Err(_) => WrapError::from(Error::not_found()).error_response(),
+            },
             BodyInner::Teon(value) => {
                 builder.content_type("application/json");
-                let json_value = serde_json::Value::try_from(value).unwrap();
-                let string_value = serde_json::to_string(&json_value).unwrap();
-                return builder.body(string_value);
+                return match serde_json::Value::try_from(value) {
+                    Ok(json_value) => builder.body(json_value.to_string()),
+                    Err(err) => WrapError::from(err).error_response(),
+                };
             }
         }
         builder.finish()
@@ -0,0 +1,230 @@
+use serde_json::{json, Value as JsonValue};
+use teo_runtime::handler::Handler;
+use teo_runtime::handler::handler::Method;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use crate::app::Ctx;
+use crate::generate::mobile::{action_path, collect_models};
+use crate::server::batch::BATCH_PATH;
+use crate::server::json_rpc::JSON_RPC_PATH;
+use crate::server::magic_link::model_token_issuer;
+use crate::server::maintenance::MAINTENANCE_PATH;
+use crate::server::sessions::SESSIONS_PATH;
+use crate::stdlib::decorators::credential::model_credential;
+use crate::stdlib::decorators::dedupe::model_dedupe_fields;
+use crate::stdlib::decorators::merge::model_merge;
+use crate::stdlib::decorators::permissions::model_guard;
+use crate::stdlib::decorators::pii_strategy::has_pii_fields;
+use crate::stdlib::decorators::search_index::model_search_index;
+use crate::stdlib::decorators::slug::model_slug_fields;
+use crate::stdlib::decorators::sync::model_sync;
+use crate::stdlib::decorators::taggable::model_taggable;
+use crate::stdlib::decorators::validate_action::has_validate_action;
+use crate::stdlib::decorators::view::{is_materialized_view, model_view, WRITE_ACTIONS};
+
+/// The builtin actions every model is served with.
+const BUILTIN_ACTIONS: [&str; 15] = [
+    "findUnique", "findFirst", "findMany", "create", "update", "upsert", "delete",
+    "createMany", "updateMany", "deleteMany", "copy", "copyMany", "count", "aggregate", "groupBy",
+];
+
+/// An endpoint of the server.
+#[derive(Debug, Clone)]
+pub(crate) struct Route {
+    pub(crate) method: &'static str,
+    pub(crate) path: String,
+    pub(crate) model: Option<String>,
+    pub(crate) action: String,
+    /// The `@@permissions` guards checked before the action runs, e.g.
+    /// `update: role:admin`.
+    pub(crate) permissions: Vec<String>,
+}
+
+impl Route {
+
+    fn to_json(&self) -> JsonValue {
+        json!({
+            "method": self.method,
+            "path": self.path,
+            "model": self.model,
+            "action": self.action,
+            "permissions": self.permissions,
+        })
+    }
+}
+
+/// Every endpoint served for `namespace`: the builtin and decorator actions
+/// of the models, the declared handlers and the internal endpoints. Paths
+/// include the configured path prefix.
+pub(crate) fn routes(namespace: &'static Namespace) -> Vec<Route> {
+    let mut routes = vec![];
+    let mut models = vec![];
+    collect_models(namespace, &mut models);
+    for model in models {
+        model_routes(model, namespace, &mut routes);
+    }
+    handler_routes(namespace, &mut routes);
+    let internal: [(&'static str, &str, bool); 4] = [
+        ("POST", BATCH_PATH, true),
+        ("POST", JSON_RPC_PATH, Ctx::json_rpc()),
+        ("GET", MAINTENANCE_PATH, true),
+        ("POST", MAINTENANCE_PATH, true),
+    ];
+    for (method, path, enabled) in internal {
+        if enabled {
+            routes.push(Route { method, path: path.to_owned(), model: None, action: path.trim_start_matches('/').to_owned(), permissions: vec![] });
+        }
+    }
+    if Ctx::sessions() {
+        for action in ["list", "revoke", "revokeAll"] {
+            routes.push(Route { method: "POST", path: format!("{}{}", SESSIONS_PATH, action), model: None, action: action.to_owned(), permissions: vec![] });
+        }
+    }
+    let prefix = namespace.server.as_ref().and_then(|s| s.path_prefix.as_ref()).map(|p| p.trim_end_matches('/').to_owned()).unwrap_or_default();
+    for route in &mut routes {
+        route.path = format!("{}{}", prefix, route.path);
+    }
+    routes
+}
+
+fn model_routes(model: &'static Model, namespace: &'static Namespace, routes: &mut Vec<Route>) {
+    let model_path = model.path().join(".");
+    let path: Vec<&str> = model.path().iter().map(AsRef::as_ref).collect();
+    let custom = path.split_last().and_then(|(name, namespace_path)| namespace.namespace_at_path(&namespace_path.to_vec()).and_then(|n| n.model_handler_groups.get(*name)));
+    let mut push = |action: &str, guarded: &[&str]| {
+        if custom.map_or(false, |group| group.handlers.contains_key(action)) {
+            return;
+        }
+        routes.push(Route {
+            method: "POST",
+            path: action_path(model, action),
+            model: Some(model_path.clone()),
+            action: action.to_owned(),
+            permissions: guarded.iter().filter_map(|a| model_guard(model, a).map(|g| format!("{}: {}", a, g.desc()))).collect(),
+        });
+    };
+    for action in BUILTIN_ACTIONS {
+        if model_view(model).is_some() && WRITE_ACTIONS.contains(&action) {
+            continue
+        }
+        push(action, &[action]);
+    }
+    push("findByIds", &["findMany"]);
+    if has_validate_action(model) && model_view(model).is_none() {
+        push("validate", &["create", "update"]);
+    }
+    if is_materialized_view(model) {
+        push("refresh", &["refresh"]);
+    }
+    if model_search_index(model).is_some() {
+        push("search", &["findMany"]);
+    }
+    if model_sync(model).is_some() {
+        push("pull", &["findMany"]);
+        push("push", &["update"]);
+    }
+    if has_pii_fields(model) {
+        push("anonymize", &["anonymize"]);
+        push("export", &["export"]);
+    }
+    if model_slug_fields(model).iter().any(|(_, policy)| policy.history) {
+        push("resolveSlug", &["findUnique"]);
+    }
+    if !model_dedupe_fields(model).is_empty() {
+        push("findDuplicates", &["findMany"]);
+    }
+    if model_merge(model).is_some() {
+        push("merge", &["update", "delete"]);
+    }
+    if model_taggable(model).is_some() {
+        push("suggestTags", &["findMany"]);
+    }
+    if model_credential(model).is_some() {
+        push("link", &[]);
+        push("unlink", &[]);
+    }
+    if Ctx::magic_links().is_some() && model_token_issuer(model).is_some() {
+        push("requestMagicLink", &[]);
+        push("verifyMagicLink", &[]);
+    }
+    for query in Ctx::named_queries().iter().filter(|q| q.model.join(".") == model_path) {
+        push(&query.name, &["findMany"]);
+    }
+}
+
+fn handler_routes(namespace: &'static Namespace, routes: &mut Vec<Route>) {
+    for handler in namespace.handlers.values() {
+        routes.push(handler_route(handler, None));
+    }
+    for group in namespace.handler_groups.values() {
+        for handler in group.handlers.values() {
+            routes.push(handler_route(handler, None));
+        }
+    }
+    for (name, group) in &namespace.model_handler_groups {
+        let model = namespace.models.get(name).map(|m| m.path().join("."));
+        for handler in group.handlers.values() {
+            routes.push(handler_route(handler, model.clone()));
+        }
+    }
+    for child in namespace.namespaces.values() {
+        handler_routes(child, routes);
+    }
+}
+
+fn handler_route(handler: &Handler, model: Option<String>) -> Route {
+    let path = match &handler.url {
+        Some(url) if handler.ignore_prefix => url.clone(),
+        Some(url) => format!("/{}/{}", handler.path[..handler.path.len() - 1].join("/"), url.trim_start_matches('/')),
+        None => format!("/{}", handler.path.join("/")),
+    };
+    Route {
+        method: method_name(&handler.method),
+        path: path.replace("//", "/"),
+        model,
+        action: handler.path.last().cloned().unwrap_or_default(),
+        permissions: vec![],
+    }
+}
+
+fn method_name(method: &Method) -> &'static str {
+    match method {
+        Method::Get => "GET",
+        Method::Post => "POST",
+        Method::Patch => "PATCH",
+        Method::Put => "PUT",
+        Method::Delete => "DELETE",
+        Method::Options => "OPTIONS",
+    }
+}
+
+/// Print the routes as a table, or as a JSON array with `json`.
+pub(crate) fn print_routes(routes: &[Route], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&JsonValue::Array(routes.iter().map(Route::to_json).collect())).unwrap());
+        return;
+    }
+    let rows: Vec<[String; 5]> = routes.iter().map(|r| [
+        r.method.to_owned(),
+        r.path.clone(),
+        r.model.clone().unwrap_or_else(|| "-".to_owned()),
+        r.action.clone(),
+        if r.permissions.is_empty() { "-".to_owned() } else { r.permissions.join(", ") },
+    ]).collect();
+    let header = ["Method", "Path", "Model", "Action", "Permissions"].map(|h| h.to_owned());
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+    let line = format!("+{}+", widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>().join("+"));
+    let print_row = |row: &[String; 5]| println!("| {} |", row.iter().enumerate().map(|(i, c)| format!("{:<width$}", c, width = widths[i])).collect::<Vec<_>>().join(" | "));
+    println!("{}", line);
+    print_row(&header);
+    println!("{}", line);
+    for row in &rows {
+        print_row(row);
+    }
+    println!("{}", line);
+}
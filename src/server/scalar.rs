@@ -0,0 +1,49 @@
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use crate::app::ctx::Ctx;
+use crate::stdlib::decorators::scalar::field_scalar;
+
+/// Parse the custom scalar values of the `create` and `update` arguments of
+/// an action with the codecs of their types. Values of nested relation writes
+/// are left to the validation of the related model.
+pub(super) fn parse_input(model: &Model, args: &mut JsonValue) -> Result<()> {
+    for key in ["create", "update"] {
+        match args.get_mut(key) {
+            Some(JsonValue::Array(records)) => for record in records.iter_mut() {
+                parse_record(model, record)?;
+            }
+            Some(record) => parse_record(model, record)?,
+            None => (),
+        }
+    }
+    Ok(())
+}
+
+fn parse_record(model: &Model, record: &mut JsonValue) -> Result<()> {
+    let Some(record) = record.as_object_mut() else { return Ok(()) };
+    for field in model.fields.values() {
+        let Some(name) = field_scalar(field) else { continue };
+        let Some(value) = record.get_mut(field.name()) else { continue };
+        if value.is_null() {
+            continue
+        }
+        let codec = Ctx::scalar(name).ok_or_else(|| Error::new(format!("scalar type `{}` is not registered", name)))?;
+        *value = codec.parse(value).map_err(|e| Error::invalid_request_message(format!("`{}`: {}", field.name(), e.message)))?;
+    }
+    Ok(())
+}
+
+/// Serialize the custom scalar values of a record or a list of records.
+pub(super) fn serialize_records(model: &Model, value: &mut JsonValue) {
+    match value {
+        JsonValue::Array(records) => records.iter_mut().for_each(|record| serialize_records(model, record)),
+        JsonValue::Object(record) => for field in model.fields.values() {
+            let Some(codec) = field_scalar(field).and_then(Ctx::scalar) else { continue };
+            if let Some(value) = record.get_mut(field.name()).filter(|v| !v.is_null()) {
+                *value = codec.serialize(value);
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use key_path::path;
+use serde_json::{Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::model_connection;
+use crate::message::info_message;
+use crate::migrate::backfill::DEFAULT_BACKFILL_BATCH_SIZE;
+use crate::stdlib::decorators::sequence::{model_sequence_fields, SequencePolicy};
+use crate::utils::sql::{identifier, quote};
+use crate::server::query_tag::tagged;
+
+/// The table the counters of sequence fields are kept in.
+pub(crate) const SEQUENCES_TABLE: &str = "_teo_sequences";
+
+/// Number the records in the `create` argument of an action which don't have
+/// a number yet. Each number is taken from the counter of the record's scope
+/// with a single atomic statement in the transaction of the action, so
+/// concurrent requests never receive the same number and numbers of a failed
+/// create are given back.
+pub(super) async fn fill_sequences(model: &'static Model, args: &mut JsonValue, ctx: &transaction::Ctx) -> Result<()> {
+    let sequence_fields = model_sequence_fields(model);
+    let records: Vec<&mut JsonValue> = match args.get_mut("create") {
+        Some(JsonValue::Array(records)) => records.iter_mut().collect(),
+        Some(record) => vec![record],
+        None => return Ok(()),
+    };
+    for record in records {
+        let Some(record) = record.as_object_mut() else { continue };
+        for (field, policy) in &sequence_fields {
+            if record.get(field.name()).map_or(false, |n| !n.is_null()) {
+                continue
+            }
+            let mut scope = vec![];
+            for name in &policy.scope {
+                let value = record.get(name).ok_or_else(|| Error::invalid_request_message(format!("`{}` is required to number `{}`", name, field.name())))?;
+                scope.push(value.clone());
+            }
+            let number = next_number(model, field, policy, &JsonValue::Array(scope).to_string(), ctx).await?;
+            record.insert(field.name().to_owned(), JsonValue::from(number));
+        }
+    }
+    Ok(())
+}
+
+/// Number the existing records of a sequence field which don't have a
+/// number, in the order of `order_by` or in the order the database returns
+/// them. The counters are first raised to the largest numbers in use, so
+/// backfilled numbers follow them.
+pub(crate) async fn backfill_sequence(model: &'static Model, field_name: &str, order_by: Option<&str>, silent: bool) -> Result<usize> {
+    let (field, policy) = model_sequence_fields(model).into_iter().find(|(field, _)| field.name() == field_name)
+        .ok_or_else(|| Error::new(format!("`{}` is not a sequence field of `{}`", field_name, model.path().join("."))))?;
+    sync_counters(model, field, &policy).await?;
+    let ctx = transaction::Ctx::new(Ctx::conn_ctx().clone());
+    let mut done = 0;
+    loop {
+        let mut finder = teon!({
+            "where": { field_name: Value::Null },
+            "take": DEFAULT_BACKFILL_BATCH_SIZE as i64,
+        });
+        if let (Some(order_by), Value::Dictionary(map)) = (order_by, &mut finder) {
+            map.insert("orderBy".to_owned(), teon!({ order_by: "asc" }));
+        }
+        let policy = policy.clone();
+        // a batch is numbered and saved in one transaction, which the
+        // counters are taken with
+        let numbered = ctx.run_transaction(move |ctx: transaction::Ctx| {
+            let (finder, policy) = (finder.clone(), policy.clone());
+            async move {
+                let objects: Vec<Object> = ctx.find_many(model, &finder, None, path![]).await?;
+                for object in &objects {
+                    let mut scope = vec![];
+                    for name in &policy.scope {
+                        scope.push(JsonValue::try_from(&object.get_value(name)?)?);
+                    }
+                    let number = next_number(model, field, &policy, &JsonValue::Array(scope).to_string(), &ctx).await?;
+                    object.set(field.name(), if matches!(field.r#type(), Type::Int) { Value::Int(number as i32) } else { Value::Int64(number) })?;
+                    object.save().await?;
+                }
+                Ok(objects.len())
+            }
+        }).await?;
+        if numbered == 0 {
+            break;
+        }
+        done += numbered;
+        if !silent {
+            info_message(format!("sequence {}.{}: {} records numbered", model.path().join("."), field_name, done));
+        }
+    }
+    Ok(done)
+}
+
+/// Create the counters table through `transaction` if any of `models` has
+/// sequence fields.
+pub(crate) async fn create_sequences_table(transaction: Arc<dyn Transaction>, models: &Vec<&Model>) -> Result<()> {
+    if !models.iter().any(|model| !model_sequence_fields(model).is_empty()) {
+        return Ok(());
+    }
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (model VARCHAR(255) NOT NULL, field VARCHAR(255) NOT NULL, scope VARCHAR(255) NOT NULL, value BIGINT NOT NULL, PRIMARY KEY (model, field, scope))",
+        SEQUENCES_TABLE,
+    ))).await?;
+    Ok(())
+}
+
+/// Increment the counter of a scope with the transaction of `ctx` and return
+/// the new number, or create the counter with the start number.
+async fn next_number(model: &Model, field: &Field, policy: &SequencePolicy, scope: &str, ctx: &transaction::Ctx) -> Result<i64> {
+    let transaction = ctx.transaction_for_model(model).await?;
+    let (_, database) = model_connection(model)?;
+    let keys = format!("{}, {}, {}", quote(&model.path().join("."), &database)?, quote(field.name(), &database)?, quote(scope, &database)?);
+    let rows = match database {
+        Database::PostgreSQL | Database::SQLite => transaction.query_raw(&Value::String(tagged(format!(
+            "INSERT INTO {table} (model, field, scope, value) VALUES ({}, {}) ON CONFLICT (model, field, scope) DO UPDATE SET value = {table}.value + 1 RETURNING value",
+            keys, policy.start, table = SEQUENCES_TABLE,
+        )))).await?,
+        Database::MySQL => {
+            // `LAST_INSERT_ID(expr)` makes the new value readable on the
+            // same connection, which the transaction holds
+            transaction.query_raw(&Value::String(tagged(format!(
+                "INSERT INTO {} (model, field, scope, value) VALUES ({}, LAST_INSERT_ID({})) ON DUPLICATE KEY UPDATE value = LAST_INSERT_ID(value + 1)",
+                SEQUENCES_TABLE, keys, policy.start,
+            )))).await?;
+            transaction.query_raw(&Value::String(tagged("SELECT LAST_INSERT_ID() AS value".to_owned()))).await?
+        }
+        _ => Err(Error::new("sequence fields are only supported by SQL databases"))?,
+    };
+    first_int(&rows).ok_or_else(|| Error::new(format!("cannot number `{}.{}`", model.path().join("."), field.name())))
+}
+
+/// Raise the counters of a sequence field to the largest numbers stored per
+/// scope.
+async fn sync_counters(model: &Model, field: &Field, policy: &SequencePolicy) -> Result<()> {
+    let (connection, database) = model_connection(model)?;
+    let transaction = connection.no_transaction().await?;
+    let scope_columns: Vec<String> = policy.scope.iter().map(|name| {
+        model.field(name).map(|f| f.column_name.clone()).ok_or_else(|| Error::new(format!("scope field `{}` is not found", name)))
+    }).collect::<Result<_>>()?;
+    let quoted_columns: Vec<String> = scope_columns.iter().map(|column| identifier(column, &database)).collect();
+    let statement = if scope_columns.is_empty() {
+        format!("SELECT MAX({}) AS value FROM {}", identifier(&field.column_name, &database), identifier(&model.table_name, &database))
+    } else {
+        format!("SELECT {columns}, MAX({}) AS value FROM {} GROUP BY {columns}", identifier(&field.column_name, &database), identifier(&model.table_name, &database), columns = quoted_columns.join(", "))
+    };
+    let rows = match transaction.query_raw(&Value::String(statement)).await? {
+        Value::Array(rows) => rows,
+        Value::Null => vec![],
+        row => vec![row],
+    };
+    let mut maximums: BTreeMap<String, i64> = BTreeMap::new();
+    for row in rows {
+        let Some(max) = row.get("value").and_then(int) else { continue };
+        let scope = scope_columns.iter().map(|column| JsonValue::try_from(row.get(column).unwrap_or(&Value::Null))).collect::<Result<Vec<JsonValue>>>()?;
+        maximums.insert(JsonValue::Array(scope).to_string(), max);
+    }
+    for (scope, max) in maximums {
+        let keys = format!("{}, {}, {}", quote(&model.path().join("."), &database)?, quote(field.name(), &database)?, quote(&scope, &database)?);
+        transaction.query_raw(&Value::String(match database {
+            Database::PostgreSQL => format!("INSERT INTO {table} (model, field, scope, value) VALUES ({}, {}) ON CONFLICT (model, field, scope) DO UPDATE SET value = GREATEST({table}.value, EXCLUDED.value)", keys, max, table = SEQUENCES_TABLE),
+            Database::SQLite => format!("INSERT INTO {table} (model, field, scope, value) VALUES ({}, {}) ON CONFLICT (model, field, scope) DO UPDATE SET value = MAX({table}.value, excluded.value)", keys, max, table = SEQUENCES_TABLE),
+            Database::MySQL => format!("INSERT INTO {} (model, field, scope, value) VALUES ({}, {}) ON DUPLICATE KEY UPDATE value = GREATEST(value, VALUES(value))", SEQUENCES_TABLE, keys, max),
+            _ => Err(Error::new("sequence fields are only supported by SQL databases"))?,
+        })).await?;
+    }
+    Ok(())
+}
+
+fn first_int(rows: &Value) -> Option<i64> {
+    match rows {
+        Value::Array(rows) => rows.first().and_then(|row| row.get("value")).and_then(int),
+        row => row.get("value").and_then(int),
+    }
+}
+
+fn int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(i) => Some(*i as i64),
+        Value::Int64(i) => Some(*i),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,174 @@
+use std::net::IpAddr;
+use actix_web::HttpRequest;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::response::body::BodyInner;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::main_connection;
+use crate::server::client_ip::CLIENT_IP_HEADER;
+use crate::server::error::UserError;
+use crate::server::query_tag::tagged;
+use crate::utils::hex::sha256_hex;
+use crate::utils::sql::quote;
+
+/// The table issued tokens are recorded in.
+pub(crate) const SESSIONS_TABLE: &str = "_teo_sessions";
+
+/// The path prefix of the session endpoints, `list`, `revoke` and
+/// `revokeAll`.
+pub(super) const SESSIONS_PATH: &str = "/_sessions/";
+
+/// Record the token of a `signIn` or `verifyMagicLink` response as a session
+/// with the device and the IP address of the request. Expired sessions are removed on the way.
+pub(super) async fn record_session(http_request: &HttpRequest, response: &Response) -> Result<()> {
+    let BodyInner::Teon(value) = response.body().inner.as_ref() else { return Ok(()) };
+    let Some(token) = value.get("meta").and_then(|m| m.get("token")).and_then(|t| t.as_str()) else { return Ok(()) };
+    let claims = claims(token)?;
+    let identity = json!({ "model": claims.get("model"), "id": claims.get("id") }).to_string();
+    let expires_at = claims.get("exp").and_then(|e| e.as_i64()).map_or("NULL".to_owned(), |e| (e * 1000).to_string());
+    let jti = claims.get("jti").and_then(|j| j.as_str()).map(|j| j.to_owned()).unwrap_or_else(|| sha256_hex(token.as_bytes()));
+    let device = device(http_request);
+    let ip = http_request.headers().get(CLIENT_IP_HEADER).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<IpAddr>().ok()).map_or(String::new(), |ip| ip.to_string());
+    let now = Utc::now().timestamp_millis();
+    let (connection, database) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(tagged(format!(
+        "DELETE FROM {} WHERE expires_at IS NOT NULL AND expires_at < {}",
+        SESSIONS_TABLE, now,
+    )))).await?;
+    transaction.query_raw(&Value::String(tagged(format!(
+        "INSERT INTO {} (id, jti, identity, device, ip, created_at, expires_at, revoked_at) VALUES ({}, {}, {}, {}, {}, {}, {}, NULL)",
+        SESSIONS_TABLE, quote(&sha256_hex(token.as_bytes()), &database)?, quote(&jti, &database)?, quote(&identity, &database)?, quote(&device, &database)?, quote(&ip, &database)?, now, expires_at,
+    )))).await?;
+    Ok(())
+}
+
+/// Reject requests whose bearer token isn't a recorded session, or whose
+/// session is revoked or expired. Requests without a bearer token pass, so
+/// do sign ins and magic link verifications, which may carry a stale token.
+pub(super) async fn check_session(http_request: &HttpRequest, path: &str) -> Result<()> {
+    if path.ends_with("/signIn") || path.ends_with("/verifyMagicLink") {
+        return Ok(());
+    }
+    let Some(token) = bearer_token(http_request) else { return Ok(()) };
+    session(&token).await?;
+    Ok(())
+}
+
+/// Handle a request to a session endpoint. The sessions are those of the
+/// identity of the bearer token:
+///
+/// * `list` responds with the sessions which aren't revoked or expired
+/// * `revoke` revokes the session `{ "id": ... }`
+/// * `revokeAll` revokes all sessions, the current one included, to log out
+///   all devices
+pub(super) async fn handle_sessions(http_request: &HttpRequest, action: &str, json_body: &JsonValue) -> Result<Response> {
+    let token = bearer_token(http_request).ok_or_else(|| UserError::new("IDENTITY_REQUIRED", "identity is required").with_status(401))?;
+    let current = session(&token).await?;
+    let identity = current.get("identity").and_then(|i| i.as_str()).ok_or_else(|| Error::new("invalid session row, missing `identity`"))?.to_owned();
+    let (connection, database) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    let now = Utc::now().timestamp_millis();
+    match action {
+        "list" => {
+            let rows = transaction.query_raw(&Value::String(tagged(format!(
+                "SELECT id, device, ip, created_at, expires_at FROM {} WHERE identity = {} AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > {}) ORDER BY created_at DESC",
+                SESSIONS_TABLE, quote(&identity, &database)?, now,
+            )))).await?;
+            let Value::Array(rows) = rows else { return Ok(Response::data(Value::Array(vec![]))) };
+            let current_id = sha256_hex(token.as_bytes());
+            let mut sessions = vec![];
+            for row in rows {
+                let mut session = JsonValue::try_from(&row)?;
+                if let Some(session) = session.as_object_mut() {
+                    let is_current = session.get("id").and_then(|i| i.as_str()) == Some(current_id.as_str());
+                    session.insert("current".to_owned(), json!(is_current));
+                }
+                sessions.push(session);
+            }
+            Ok(Response::data(Value::from(JsonValue::Array(sessions))))
+        }
+        "revoke" => {
+            let id = json_body.get("id").and_then(|i| i.as_str()).ok_or_else(|| Error::invalid_request_message("expect `id` to be a string"))?;
+            transaction.query_raw(&Value::String(tagged(format!(
+                "UPDATE {} SET revoked_at = {} WHERE id = {} AND identity = {} AND revoked_at IS NULL",
+                SESSIONS_TABLE, now, quote(id, &database)?, quote(&identity, &database)?,
+            )))).await?;
+            Ok(Response::data(Value::Null))
+        }
+        "revokeAll" => {
+            transaction.query_raw(&Value::String(tagged(format!(
+                "UPDATE {} SET revoked_at = {} WHERE identity = {} AND revoked_at IS NULL",
+                SESSIONS_TABLE, now, quote(&identity, &database)?,
+            )))).await?;
+            Ok(Response::data(Value::Null))
+        }
+        _ => Err(Error::not_found()),
+    }
+}
+
+/// Create the sessions table if sessions are enabled.
+pub(crate) async fn create_sessions_table() -> Result<()> {
+    if !Ctx::sessions() {
+        return Ok(());
+    }
+    let (connection, _) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (id VARCHAR(64) PRIMARY KEY, jti VARCHAR(255) NOT NULL, identity VARCHAR(255) NOT NULL, device VARCHAR(255) NOT NULL, ip VARCHAR(64) NOT NULL, created_at BIGINT NOT NULL, expires_at BIGINT NULL, revoked_at BIGINT NULL)",
+        SESSIONS_TABLE,
+    ))).await?;
+    Ok(())
+}
+
+/// The live session of a token.
+async fn session(token: &str) -> Result<Value> {
+    let (connection, database) = main_connection()?;
+    let transaction = connection.no_transaction().await?;
+    let rows = transaction.query_raw(&Value::String(tagged(format!(
+        "SELECT identity, expires_at, revoked_at FROM {} WHERE id = {}",
+        SESSIONS_TABLE, quote(&sha256_hex(token.as_bytes()), &database)?,
+    )))).await?;
+    let row = match rows {
+        Value::Array(rows) => rows.into_iter().next(),
+        _ => None,
+    };
+    let Some(row) = row else { Err(UserError::new("SESSION_NOT_FOUND", "session is not found").with_status(401))? };
+    if !matches!(row.get("revoked_at"), None | Some(Value::Null)) {
+        Err(UserError::new("SESSION_REVOKED", "session is revoked").with_status(401))?
+    }
+    let expires_at = match row.get("expires_at") {
+        Some(Value::Int64(expires_at)) => Some(*expires_at),
+        Some(Value::Int(expires_at)) => Some(*expires_at as i64),
+        _ => None,
+    };
+    if expires_at.map_or(false, |e| e <= Utc::now().timestamp_millis()) {
+        Err(UserError::new("SESSION_EXPIRED", "session is expired").with_status(401))?
+    }
+    Ok(row)
+}
+
+/// The device of a session, the `User-Agent` of the request. Only printable
+/// ASCII is kept, so the client controlled value reaches the raw statement
+/// as plain text.
+fn device(http_request: &HttpRequest) -> String {
+    let user_agent = http_request.headers().get("User-Agent").and_then(|v| v.to_str().ok()).unwrap_or("");
+    user_agent.chars().filter(|c| (c.is_ascii_graphic() || *c == ' ') && *c != '\\').take(255).collect()
+}
+
+fn bearer_token(http_request: &HttpRequest) -> Option<String> {
+    let header = http_request.headers().get("Authorization")?.to_str().ok()?;
+    header.strip_prefix("Bearer ").map(|t| t.trim().to_owned())
+}
+
+/// The claims of a JWT, the signature is verified by the identity middleware.
+fn claims(token: &str) -> Result<JsonValue> {
+    let payload = token.split('.').nth(1).ok_or_else(|| Error::new("invalid token"))?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).map_err(|_| Error::new("invalid token"))?;
+    serde_json::from_slice(&bytes).map_err(|_| Error::new("invalid token"))
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use actix_web::{FromRequest, HttpRequest, web};
+use actix_http::Payload;
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use ring::hmac;
+use teo_result::{Error, Result};
+use crate::app::Ctx;
+use crate::server::error::UserError;
+use crate::server::parse::read_body;
+use crate::utils::hex::{sha256_hex, unhex};
+
+/// The header carrying the id of the API key a request is signed with.
+pub const SIGNATURE_KEY_HEADER: &str = "X-Teo-Key";
+/// The header carrying the unix timestamp in seconds of a signed request.
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "X-Teo-Timestamp";
+/// The header carrying the nonce of a signed request.
+pub const SIGNATURE_NONCE_HEADER: &str = "X-Teo-Nonce";
+/// The header carrying the hex encoded signature of a request.
+pub const SIGNATURE_HEADER: &str = "X-Teo-Signature";
+
+/// How far the timestamp of a signed request may be off the server clock.
+const SIGNATURE_WINDOW: Duration = Duration::from_secs(300);
+
+/// Remembers the nonces of signed requests for `ttl`, keyed by the key id
+/// and the nonce. Returns whether the nonce is new.
+///
+/// The builtin store is kept in process, so a request replayed to another
+/// instance of the app is accepted. Apps running several instances plug in
+/// a shared store with `App::nonce_store`, e.g. Redis with
+/// `SET key 1 NX EX ttl`.
+pub trait NonceStore: Send + Sync {
+    fn remember(&self, key: String, ttl: Duration) -> BoxFuture<'static, Result<bool>>;
+}
+
+impl<F, Fut> NonceStore for F where
+    F: Fn(String, Duration) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<bool>> + Send + 'static {
+    fn remember(&self, key: String, ttl: Duration) -> BoxFuture<'static, Result<bool>> {
+        Box::pin(self(key, ttl))
+    }
+}
+
+/// Nonces of verified requests, kept until their timestamp falls out of the
+/// window.
+static NONCES: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The builtin in process nonce store.
+pub(crate) struct MemoryNonceStore;
+
+impl NonceStore for MemoryNonceStore {
+
+    fn remember(&self, key: String, ttl: Duration) -> BoxFuture<'static, Result<bool>> {
+        let mut nonces = NONCES.lock().unwrap();
+        let now = Instant::now();
+        nonces.retain(|_, expires| *expires > now);
+        let new = !nonces.contains_key(&key);
+        if new {
+            nonces.insert(key, now + ttl);
+        }
+        Box::pin(async move { Ok(new) })
+    }
+}
+
+/// Verify the HMAC signature of a request signed with an API key.
+///
+/// The signature is the hex encoded HMAC-SHA256 of
+/// `"{timestamp}\n{nonce}\n{method}\n{path}\n{canonical query}\n{hex sha256 of body}"`
+/// with the secret of the key. The canonical query is the query string
+/// with its parameters sorted by name and value and form encoded again.
+/// Requests outside the timestamp window and replayed nonces are rejected.
+/// Unsigned requests pass unless signatures are required. The body is read
+/// with `limit`, the limit of the handler the request is routed to. Returns
+/// the payload to read the body from.
+pub(super) async fn verify_signature(http_request: &HttpRequest, payload: web::Payload, limit: usize) -> Result<web::Payload> {
+    let header = |name: &str| http_request.headers().get(name).and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+    let Some(key_id) = header(SIGNATURE_KEY_HEADER) else {
+        if Ctx::signature_required() {
+            Err(unauthorized("SIGNATURE_REQUIRED", "request signature is required"))?
+        }
+        return Ok(payload);
+    };
+    let secret = Ctx::signing_keys().get(&key_id).ok_or_else(|| unauthorized("SIGNING_KEY_UNKNOWN", "unknown signing key"))?;
+    let (Some(timestamp), Some(nonce), Some(signature)) = (header(SIGNATURE_TIMESTAMP_HEADER), header(SIGNATURE_NONCE_HEADER), header(SIGNATURE_HEADER)) else {
+        Err(unauthorized("SIGNATURE_INVALID", "incomplete request signature"))?
+    };
+    let seconds: i64 = timestamp.parse().map_err(|_| unauthorized("SIGNATURE_INVALID", "invalid signature timestamp"))?;
+    if (Utc::now().timestamp() - seconds).unsigned_abs() > SIGNATURE_WINDOW.as_secs() {
+        Err(unauthorized("SIGNATURE_EXPIRED", "signature timestamp is out of the allowed window"))?
+    }
+    let body = read_body(payload, limit).await?;
+    let message = format!("{}\n{}\n{}\n{}\n{}\n{}", timestamp, nonce, http_request.method().as_str(), http_request.path(), canonical_query(http_request.query_string()), sha256_hex(&body));
+    let signature = unhex(&signature).ok_or_else(|| unauthorized("SIGNATURE_INVALID", "invalid request signature"))?;
+    hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()), message.as_bytes(), &signature).map_err(|_| unauthorized("SIGNATURE_INVALID", "invalid request signature"))?;
+    // a nonce is kept as long as a request with it could be within the window
+    if !Ctx::nonce_store().remember(format!("{}:{}", key_id, nonce), SIGNATURE_WINDOW * 2).await? {
+        Err(unauthorized("SIGNATURE_REPLAYED", "request nonce is already used"))?
+    }
+    web::Payload::from_request(http_request, &mut Payload::from(body.freeze())).await.map_err(|_| Error::invalid_request_message("cannot read request body"))
+}
+
+/// The parameters of a query string sorted by name and value and form
+/// encoded again, so that equivalent encodings sign the same.
+pub(crate) fn canonical_query(query_string: &str) -> String {
+    let mut params: Vec<(String, String)> = url::form_urlencoded::parse(query_string.as_bytes()).into_owned().collect();
+    params.sort();
+    url::form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish()
+}
+
+fn unauthorized(code: &str, message: &str) -> Error {
+    UserError::new(code, message).with_status(401).into()
+}
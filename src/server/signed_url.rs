@@ -0,0 +1,94 @@
+use std::time::Duration;
+use actix_web::HttpRequest;
+use chrono::Utc;
+use ring::hmac;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::app::Ctx;
+use crate::server::error::UserError;
+use crate::utils::hex::{hex, unhex};
+
+/// The query parameter carrying the JSON body of a signed action URL. The
+/// parameters of signed URLs are prefixed with `_`, so they don't collide
+/// with the parameters of handlers.
+const BODY_PARAM: &str = "_body";
+/// The query parameter carrying the unix timestamp a signed URL expires at.
+const EXPIRES_PARAM: &str = "_expires";
+/// The query parameter carrying the hex encoded signature of a URL. It's
+/// always the last parameter.
+const SIGNATURE_PARAM: &str = "_sig";
+
+/// Sign `path` for access without authentication until `expires_in` from now.
+///
+/// `body` is the input of the action at `path`, e.g. `{ "where": { "id": 1 } }`
+/// for `/User/findUnique`. Signed action URLs are opened with `GET` and run
+/// as the `POST` action; guards of `findUnique` given with `@@permissions`
+/// are skipped. Other handlers receive the request as is, after the
+/// signature is verified.
+pub fn sign_url(path: &str, body: Option<&JsonValue>, expires_in: Duration) -> Result<String> {
+    let expires = Utc::now().timestamp() + expires_in.as_secs() as i64;
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(body) = body {
+        query.append_pair(BODY_PARAM, &body.to_string());
+    }
+    query.append_pair(EXPIRES_PARAM, &expires.to_string());
+    let query = query.finish();
+    let signature = signature(path, &query)?;
+    Ok(format!("{}?{}&{}={}", path, query, SIGNATURE_PARAM, signature))
+}
+
+/// Verify the signature of a signed URL. Returns `None` if URL signing isn't
+/// configured or the URL isn't signed, and the JSON body of the URL, or
+/// null, if it's signed and not expired. Invalid and expired signatures are
+/// rejected with 401.
+pub(super) fn verify_signed_url(http_request: &HttpRequest) -> Result<Option<JsonValue>> {
+    let Some((path, query, provided)) = signed_parts(http_request.path(), http_request.query_string()) else { return Ok(None) };
+    if !signature_matches(&path, &query, &provided)? {
+        Err(UserError::new("URL_SIGNATURE_INVALID", "url signature doesn't match").with_status(401))?
+    }
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+    let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+    let expires: i64 = param(EXPIRES_PARAM).and_then(|e| e.parse().ok()).ok_or_else(|| UserError::new("URL_SIGNATURE_INVALID", "url signature doesn't have an expiry").with_status(401))?;
+    if expires < Utc::now().timestamp() {
+        Err(UserError::new("URL_SIGNATURE_EXPIRED", "url signature is expired").with_status(401))?
+    }
+    match param(BODY_PARAM) {
+        Some(body) => Ok(Some(serde_json::from_str(&body).map_err(|_| Error::invalid_request_message("incorrect json format"))?)),
+        None => Ok(Some(JsonValue::Null)),
+    }
+}
+
+/// Whether the request of a handler comes from a valid signed URL.
+pub(crate) fn has_valid_url_signature(path: &str, query_string: &str) -> bool {
+    let Some((path, query, provided)) = signed_parts(path, query_string) else { return false };
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+    let expires: Option<i64> = params.iter().find(|(k, _)| k == EXPIRES_PARAM).and_then(|(_, v)| v.parse().ok());
+    matches!(signature_matches(&path, &query, &provided), Ok(true)) && expires.map_or(false, |e| e >= Utc::now().timestamp())
+}
+
+/// Split a signed URL into the path, the signed query and the signature,
+/// which is the last parameter and named exactly `_sig`. URLs are only
+/// treated as signed when a signing secret is configured.
+fn signed_parts(path: &str, query_string: &str) -> Option<(String, String, String)> {
+    Ctx::url_signing_secret()?;
+    let (query, last) = query_string.rsplit_once('&').unwrap_or(("", query_string));
+    let (name, provided) = last.split_once('=')?;
+    if name != SIGNATURE_PARAM {
+        return None;
+    }
+    Some((path.to_owned(), query.to_owned(), provided.to_owned()))
+}
+
+fn signature(path: &str, query: &str) -> Result<String> {
+    Ok(hex(hmac::sign(&key()?, format!("{}?{}", path, query).as_bytes()).as_ref()))
+}
+
+fn signature_matches(path: &str, query: &str, provided: &str) -> Result<bool> {
+    let Some(provided) = unhex(provided) else { return Ok(false) };
+    Ok(hmac::verify(&key()?, format!("{}?{}", path, query).as_bytes(), &provided).is_ok())
+}
+
+fn key() -> Result<hmac::Key> {
+    let secret = Ctx::url_signing_secret().ok_or_else(|| Error::new("url signing secret is not configured"))?;
+    Ok(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()))
+}
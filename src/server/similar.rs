@@ -0,0 +1,95 @@
+use serde_json::{json, Value as JsonValue};
+
+/// The threshold used when a `similar` filter doesn't specify one, the same
+/// as the default of `pg_trgm`.
+const DEFAULT_THRESHOLD: f64 = 0.3;
+
+/// The `similar: { value, threshold }` string filters of a `findMany`.
+///
+/// The query engine doesn't understand the operator, so the filters are
+/// removed from the arguments and applied to the response. Pagination is
+/// applied after filtering.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SimilarFilter {
+    conditions: Vec<(String, String, f64)>,
+    skip: Option<usize>,
+    take: Option<usize>,
+}
+
+impl SimilarFilter {
+
+    /// Take the `similar` filters out of the top level `where` of query
+    /// arguments.
+    pub(super) fn take(args: &mut JsonValue) -> Self {
+        let mut conditions = vec![];
+        if let Some(JsonValue::Object(r#where)) = args.get_mut("where") {
+            let mut emptied = vec![];
+            for (field, filter) in r#where.iter_mut() {
+                let Some(filter) = filter.as_object_mut() else { continue };
+                let Some(similar) = filter.remove("similar") else { continue };
+                let value = similar.get("value").and_then(|v| v.as_str()).unwrap_or_default().to_owned();
+                let threshold = similar.get("threshold").and_then(|t| t.as_f64()).unwrap_or(DEFAULT_THRESHOLD);
+                conditions.push((field.clone(), value, threshold));
+                if filter.is_empty() {
+                    emptied.push(field.clone());
+                }
+            }
+            emptied.iter().for_each(|field| { r#where.remove(field); });
+        }
+        if conditions.is_empty() {
+            return Self::default();
+        }
+        let Some(object) = args.as_object_mut() else { return Self::default() };
+        let skip = object.remove("skip").and_then(|s| s.as_u64()).map(|s| s as usize);
+        let take = object.remove("take").and_then(|t| t.as_u64()).map(|t| t as usize);
+        Self { conditions, skip, take }
+    }
+
+    pub(super) fn is_effective(&self) -> bool {
+        !self.conditions.is_empty()
+    }
+
+    fn matches(&self, record: &JsonValue) -> bool {
+        self.conditions.iter().all(|(field, value, threshold)| {
+            record.get(field).and_then(|v| v.as_str()).map_or(false, |v| similarity(v, value) >= *threshold)
+        })
+    }
+
+    /// Filter and paginate the `data` of a response.
+    pub(super) fn apply(&self, json_value: &mut JsonValue) {
+        if let Some(JsonValue::Array(records)) = json_value.get_mut("data") {
+            records.retain(|record| self.matches(record));
+            let count = records.len();
+            let page: Vec<JsonValue> = records.drain(..).skip(self.skip.unwrap_or(0)).take(self.take.unwrap_or(usize::MAX)).collect();
+            *records = page;
+            if let Some(meta) = json_value.get_mut("meta").and_then(|m| m.as_object_mut()) {
+                meta.insert("count".to_owned(), json!(count));
+            }
+        }
+    }
+}
+
+/// The similarity of two strings between 0 and 1, based on the Levenshtein
+/// distance of their lowercase forms.
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use chrono::Utc;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::database::database::Database;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::database::model_connection;
+use crate::stdlib::decorators::slug::model_slug_fields;
+use crate::utils::sql::quote;
+use crate::server::query_tag::tagged;
+
+/// The table slugs replaced by updates are recorded in.
+pub(crate) const SLUG_HISTORY_TABLE: &str = "_teo_slug_history";
+
+/// Generate the missing slugs of the records in the `create` argument of an
+/// action. A slug which is taken, by a stored record or by another record of
+/// the same request, gets the first free numeric suffix. The unique index of
+/// the field rejects a slug taken by a concurrent request in between. Taken
+/// slugs are read with the transaction of the action.
+pub(super) async fn fill_slugs(model: &'static Model, args: &mut JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<()> {
+    let slug_fields = model_slug_fields(model);
+    let records: Vec<&mut JsonValue> = match args.get_mut("create") {
+        Some(JsonValue::Array(records)) => records.iter_mut().collect(),
+        Some(record) => vec![record],
+        None => return Ok(()),
+    };
+    let mut assigned: BTreeSet<String> = BTreeSet::new();
+    for record in records {
+        let Some(record) = record.as_object_mut() else { continue };
+        for (field, policy) in &slug_fields {
+            if record.get(field.name()).and_then(|s| s.as_str()).map_or(false, |s| !s.is_empty()) {
+                continue
+            }
+            let Some(source) = record.get(&policy.from).and_then(|s| s.as_str()) else { continue };
+            let base = slugify(source);
+            if base.is_empty() {
+                continue
+            }
+            let mut taken = taken_slugs(model, field.name(), &base, main_namespace, ctx).await?;
+            taken.extend(assigned.iter().cloned());
+            let slug = free_slug(&base, &taken);
+            assigned.insert(slug.clone());
+            record.insert(field.name().to_owned(), JsonValue::String(slug));
+        }
+    }
+    Ok(())
+}
+
+/// Record the slugs an `update` replaces, for the slug fields with history,
+/// with the transaction of the update, so the history only changes if the
+/// update is committed. A slug given back to its record is removed from the
+/// history.
+pub(super) async fn record_slug_changes(model: &'static Model, args: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<()> {
+    let Some(update) = args.get("update").and_then(|u| u.as_object()) else { return Ok(()) };
+    let changed: Vec<(&str, &str)> = model_slug_fields(model).into_iter()
+        .filter(|(_, policy)| policy.history)
+        .filter_map(|(field, _)| update.get(field.name()).and_then(|s| s.as_str()).map(|slug| (field.name(), slug)))
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    let Some(object) = find_object(model, &args.get("where").cloned().unwrap_or(json!({})), main_namespace, ctx).await? else { return Ok(()) };
+    let identifier = JsonValue::try_from(&object.identifier())?.to_string();
+    let transaction = ctx.transaction_for_model(model).await?;
+    let (_, database) = model_connection(model)?;
+    let model_path = model.path().join(".");
+    for (field, slug) in changed {
+        let Some(old) = object.get_value(field)?.as_str().map(|s| s.to_owned()) else { continue };
+        if old == slug || old.is_empty() {
+            continue
+        }
+        transaction.query_raw(&Value::String(tagged(format!(
+            "DELETE FROM {} WHERE model = {} AND field = {} AND slug IN ({}, {})",
+            SLUG_HISTORY_TABLE, quote(&model_path, &database)?, quote(field, &database)?, quote(&old, &database)?, quote(slug, &database)?,
+        )))).await?;
+        transaction.query_raw(&Value::String(tagged(format!(
+            "INSERT INTO {} (model, field, slug, identifier, created_at) VALUES ({}, {}, {}, {}, {})",
+            SLUG_HISTORY_TABLE, quote(&model_path, &database)?, quote(field, &database)?, quote(&old, &database)?, quote(&identifier, &database)?, Utc::now().timestamp_millis(),
+        )))).await?;
+    }
+    Ok(())
+}
+
+/// The `resolveSlug` action of a model with slug history. The body is
+/// `{ slug, field? }`, `field` defaults to the first slug field with history.
+/// Responds with the current slug of the record the slug belongs or belonged
+/// to, so that clients can redirect old URLs.
+pub(super) async fn resolve_slug(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: transaction::Ctx) -> Result<Response> {
+    let slug = body.get("slug").and_then(|s| s.as_str()).ok_or_else(|| Error::invalid_request_message("expect `slug` to be a string"))?;
+    let history_fields: Vec<&str> = model_slug_fields(model).into_iter().filter(|(_, policy)| policy.history).map(|(field, _)| field.name()).collect();
+    let field = match body.get("field").and_then(|f| f.as_str()) {
+        Some(field) if history_fields.contains(&field) => field,
+        Some(field) => Err(Error::invalid_request_message(format!("`{}` is not a slug field with history", field)))?,
+        None => history_fields.first().copied().ok_or_else(|| Error::not_found())?,
+    };
+    if find_object(model, &json!({ field: slug }), main_namespace, &ctx).await?.is_some() {
+        return Ok(Response::data(Value::from(json!({ "slug": slug, "moved": false }))));
+    }
+    let (transaction, database) = history_transaction(model).await?;
+    let rows = transaction.query_raw(&Value::String(tagged(format!(
+        "SELECT identifier FROM {} WHERE model = {} AND field = {} AND slug = {}",
+        SLUG_HISTORY_TABLE, quote(&model.path().join("."), &database)?, quote(field, &database)?, quote(slug, &database)?,
+    )))).await?;
+    let identifier = match rows {
+        Value::Array(rows) => rows.into_iter().next().and_then(|row| row.get("identifier").and_then(|i| i.as_str()).map(|i| i.to_owned())),
+        _ => None,
+    }.ok_or_else(|| Error::not_found())?;
+    let identifier: JsonValue = serde_json::from_str(&identifier).map_err(|_| Error::new("invalid slug history row"))?;
+    let object = find_object(model, &identifier, main_namespace, &ctx).await?.ok_or_else(|| Error::not_found())?;
+    Ok(Response::data(Value::from(json!({ "slug": JsonValue::try_from(&object.get_value(field)?)?, "moved": true }))))
+}
+
+/// Create the slug history table through `transaction` if any of `models`
+/// keeps slug history.
+pub(crate) async fn create_slug_history_table(transaction: Arc<dyn Transaction>, models: &Vec<&Model>) -> Result<()> {
+    if !models.iter().any(|model| model_slug_fields(model).iter().any(|(_, policy)| policy.history)) {
+        return Ok(());
+    }
+    transaction.query_raw(&Value::String(format!(
+        "CREATE TABLE IF NOT EXISTS {} (model VARCHAR(255) NOT NULL, field VARCHAR(255) NOT NULL, slug VARCHAR(255) NOT NULL, identifier TEXT NOT NULL, created_at BIGINT NOT NULL, PRIMARY KEY (model, field, slug))",
+        SLUG_HISTORY_TABLE,
+    ))).await?;
+    Ok(())
+}
+
+/// Turn a string into a URL-safe slug, e.g. `"Crème Brûlée!"` into
+/// `"creme-brulee"`.
+pub(crate) fn slugify(source: &str) -> String {
+    let mut slug = String::new();
+    for c in source.chars().flat_map(|c| c.to_lowercase()) {
+        let c = transliterate(c);
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').chars().take(200).collect::<String>().trim_end_matches('-').to_owned()
+}
+
+fn transliterate(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => 'e',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => 'i',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ś' | 'š' => 's',
+        'ź' | 'ż' | 'ž' => 'z',
+        'ł' => 'l',
+        'ř' => 'r',
+        'ď' => 'd',
+        'ť' => 't',
+        _ => c,
+    }
+}
+
+fn free_slug(base: &str, taken: &BTreeSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_owned();
+    }
+    (2..).map(|n| format!("{}-{}", base, n)).find(|slug| !taken.contains(slug)).unwrap()
+}
+
+async fn taken_slugs(model: &'static Model, field: &str, base: &str, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<BTreeSet<String>> {
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &json!({ "where": { field: { "startsWith": base } } }), main_namespace)?;
+    let objects: Vec<Object> = ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null) }), None, path![]).await?;
+    let mut taken = BTreeSet::new();
+    for object in objects {
+        if let Some(slug) = object.get_value(field)?.as_str() {
+            taken.insert(slug.to_owned());
+        }
+    }
+    Ok(taken)
+}
+
+async fn find_object(model: &'static Model, finder: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<Option<Object>> {
+    let find_first = builtin_action_handler_from_name("findFirst").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_first, &json!({ "where": finder }), main_namespace)?;
+    let found: Vec<Object> = ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+    Ok(found.into_iter().next())
+}
+
+async fn history_transaction(model: &Model) -> Result<(Arc<dyn Transaction>, Database)> {
+    let (connection, database) = model_connection(model)?;
+    Ok((connection.no_transaction().await?, database))
+}
@@ -0,0 +1,117 @@
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::server::output::output_record;
+use crate::stdlib::decorators::sync::SyncFields;
+
+const DEFAULT_PULL_TAKE: i64 = 100;
+const MAX_PULL_TAKE: i64 = 1000;
+
+/// The `pull` action of a syncable model. The body is `{ since, take? }` and
+/// the response contains the records changed after the sequence number
+/// `since` in sequence order, tombstones included. `meta.cursor` is the
+/// `since` of the next pull. Records are serialized like find actions do.
+pub(super) async fn pull(model: &'static Model, fields: &SyncFields, body: &JsonValue, ctx: &request::Ctx) -> Result<Response> {
+    let since = body.get("since").map_or(Some(0), |s| s.as_i64()).ok_or_else(|| Error::invalid_request_message("expect `since` to be an integer"))?;
+    let take = body.get("take").map_or(Some(DEFAULT_PULL_TAKE), |t| t.as_i64()).ok_or_else(|| Error::invalid_request_message("expect `take` to be an integer"))?.clamp(1, MAX_PULL_TAKE);
+    let objects: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({
+        "where": { fields.sequence.as_str(): { "gt": Value::Int64(since) } },
+        "orderBy": { fields.sequence.as_str(): "asc" },
+        "take": take + 1,
+    }), Some(ctx.clone()), path![]).await?;
+    let has_more = objects.len() as i64 > take;
+    let mut cursor = since;
+    let mut records = vec![];
+    for (index, object) in objects.iter().take(take as usize).enumerate() {
+        if let Value::Int64(sequence) = object.get_value(&fields.sequence)? {
+            cursor = sequence;
+        }
+        records.push(output_record(model, object, index, ctx).await?);
+    }
+    Ok(Response::data_meta(Value::Array(records), teon!({ "cursor": cursor, "hasMore": has_more })))
+}
+
+/// The `push` action of a syncable model. The body is `{ changes: [{ where,
+/// data, version?, deleted? }] }`. A change whose `version` differs from the
+/// version of the stored record is a conflict and is not applied, the stored
+/// record is returned instead so the client can resolve it. Deleted records
+/// are kept as tombstones. All changes are applied in one transaction.
+pub(super) async fn push(model: &'static Model, fields: &SyncFields, body: &JsonValue, main_namespace: &'static Namespace, req_ctx: &request::Ctx) -> Result<Response> {
+    let changes = body.get("changes").and_then(|c| c.as_array()).cloned().ok_or_else(|| Error::invalid_request_message("expect `changes` to be an array"))?;
+    let fields = fields.clone();
+    let req_ctx = req_ctx.clone();
+    let result = req_ctx.transaction_ctx().run_transaction(move |ctx: transaction::Ctx| {
+        let changes = changes.clone();
+        let fields = fields.clone();
+        let req_ctx = req_ctx.clone();
+        async move {
+            let mut applied = 0;
+            let mut conflicts = vec![];
+            for (index, change) in changes.iter().enumerate() {
+                match apply_change(model, &fields, change, main_namespace, &ctx, &req_ctx).await.map_err(|e| {
+                    let mut error = Error::new(format!("change {} failed: {}", index, e.message));
+                    error.code = e.code;
+                    error.errors = e.errors;
+                    error
+                })? {
+                    Some(record) => conflicts.push(teon!({ "index": index as i64, "record": record })),
+                    None => applied += 1,
+                }
+            }
+            Ok(teon!({ "applied": applied as i64, "conflicts": Value::Array(conflicts) }))
+        }
+    }).await?;
+    Ok(Response::data(result))
+}
+
+/// Apply a pushed change. Returns the stored record on conflict.
+async fn apply_change(model: &'static Model, fields: &SyncFields, change: &JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx, req_ctx: &request::Ctx) -> Result<Option<Value>> {
+    let finder = change.get("where").cloned().ok_or_else(|| Error::invalid_request_message("expect `where` to be an object"))?;
+    let mut data = change.get("data").cloned().unwrap_or(json!({}));
+    let deleted = change.get("deleted").and_then(|d| d.as_bool()).unwrap_or(false);
+    if let Some(data) = data.as_object_mut() {
+        // assigned by the server
+        data.remove(&fields.sequence);
+        data.remove(&fields.version);
+        data.insert(fields.deleted.clone(), json!(deleted));
+    }
+    let mut create = data.clone();
+    if let (Some(create), Some(finder)) = (create.as_object_mut(), finder.as_object()) {
+        for (key, value) in finder {
+            create.entry(key.clone()).or_insert(value.clone());
+        }
+    }
+    let upsert = builtin_action_handler_from_name("upsert").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, upsert, &json!({ "where": finder, "create": create, "update": data }), main_namespace)?;
+    let existing: Vec<Object> = ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), Some(req_ctx.clone()), path![]).await?;
+    match existing.into_iter().next() {
+        Some(object) => {
+            if let Some(version) = change.get("version").and_then(|v| v.as_i64()) {
+                if !matches!(object.get_value(&fields.version)?, Value::Int(stored) if stored as i64 == version) {
+                    return Ok(Some(output_record(model, &object, 0, req_ctx).await?));
+                }
+            }
+            if let Some(update) = input.get("update") {
+                object.set_teon(update).await?;
+            }
+            object.save().await?;
+        }
+        None => {
+            if deleted {
+                return Ok(None);
+            }
+            let object = ctx.create_object(model, input.get("create").unwrap_or(&Value::Null), Some(req_ctx.clone())).await?;
+            object.save().await?;
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::stdlib::decorators::taggable::Taggable;
+
+/// The number of suggestions returned when the request doesn't specify it.
+const DEFAULT_SUGGESTIONS: usize = 10;
+
+/// Rewrite `connectOrCreateByName` inputs of the tag relation in the `create`
+/// and `update` arguments of an action into `connectOrCreate` inputs. Names
+/// are trimmed, and empty and repeated names are dropped.
+pub(super) fn rewrite_tag_inputs(taggable: &Taggable, args: &mut JsonValue) -> Result<()> {
+    let mut records: Vec<&mut JsonValue> = vec![];
+    let Some(object) = args.as_object_mut() else { return Ok(()) };
+    for (key, value) in object.iter_mut() {
+        match (key.as_str(), value) {
+            ("create", JsonValue::Array(many)) => records.extend(many.iter_mut()),
+            ("create" | "update", record) => records.push(record),
+            _ => (),
+        }
+    }
+    for record in records {
+        let Some(input) = record.get_mut(&taggable.relation).and_then(|i| i.as_object_mut()) else { continue };
+        let Some(names) = input.remove("connectOrCreateByName") else { continue };
+        let names = names.as_array().ok_or_else(|| Error::invalid_request_message("expect `connectOrCreateByName` to be an array of names"))?;
+        let mut seen: Vec<&str> = vec![];
+        for name in names {
+            let name = name.as_str().ok_or_else(|| Error::invalid_request_message("expect `connectOrCreateByName` to be an array of names"))?.trim();
+            if !name.is_empty() && !seen.contains(&name) {
+                seen.push(name);
+            }
+        }
+        let connect_or_create = input.entry("connectOrCreate").or_insert(json!([]));
+        if !connect_or_create.is_array() {
+            *connect_or_create = json!([connect_or_create.clone()]);
+        }
+        let list = connect_or_create.as_array_mut().unwrap();
+        for name in seen {
+            list.push(json!({ "where": { taggable.name.as_str(): name }, "create": { taggable.name.as_str(): name } }));
+        }
+    }
+    Ok(())
+}
+
+/// The `suggestTags` action of a taggable model. The body is
+/// `{ prefix, take? }`. Responds with the tags whose names start with the
+/// prefix as `{ name, count }`, by the number of records tagged with them,
+/// then by name.
+pub(super) async fn suggest_tags(model: &'static Model, taggable: &Taggable, body: &JsonValue, main_namespace: &'static Namespace, ctx: transaction::Ctx) -> Result<Response> {
+    let prefix = body.get("prefix").and_then(|p| p.as_str()).ok_or_else(|| Error::invalid_request_message("expect `prefix` to be a string"))?;
+    let take = body.get("take").and_then(|t| t.as_u64()).map(|t| t as usize).unwrap_or(DEFAULT_SUGGESTIONS);
+    let relation = model.relations().into_iter().find(|r| r.name() == taggable.relation.as_str())
+        .filter(|r| r.has_join_table())
+        .ok_or_else(|| Error::new(format!("@@taggable: `{}` is not a many-to-many relation of `{}`", taggable.relation, model.path().join("."))))?;
+    let tag_model = main_namespace.model_at_path(&relation.model_path()).ok_or_else(|| Error::not_found())?;
+    let (through_model, through_relation) = main_namespace.through_opposite_relation(relation);
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(tag_model, find_many, &json!({ "where": { taggable.name.as_str(): { "startsWith": prefix } } }), main_namespace)?;
+    let tags: Vec<Object> = ctx.find_many(tag_model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null) }), None, path![]).await?;
+    if tags.is_empty() {
+        return Ok(Response::data(Value::Array(vec![])));
+    }
+    // the join records of the candidate tags, counted by the tag they link
+    let mut links = vec![];
+    for tag in &tags {
+        let mut link = teon!({});
+        for (local, foreign) in through_relation.iter() {
+            link.as_dictionary_mut().unwrap().insert(local.to_owned(), tag.get_value(foreign)?);
+        }
+        links.push(link);
+    }
+    let joins: Vec<Object> = ctx.find_many(through_model, &teon!({ "where": { "OR": Value::Array(links) } }), None, path![]).await?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for join in joins {
+        let mut key = vec![];
+        for (local, _) in through_relation.iter() {
+            key.push(JsonValue::try_from(&join.get_value(local)?)?);
+        }
+        *counts.entry(JsonValue::Array(key).to_string()).or_default() += 1;
+    }
+    let mut suggestions = vec![];
+    for tag in tags {
+        let mut key = vec![];
+        for (_, foreign) in through_relation.iter() {
+            key.push(JsonValue::try_from(&tag.get_value(foreign)?)?);
+        }
+        let name = JsonValue::try_from(&tag.get_value(&taggable.name)?)?;
+        suggestions.push((name, counts.get(&JsonValue::Array(key).to_string()).copied().unwrap_or(0)));
+    }
+    suggestions.sort_by(|(a_name, a_count), (b_name, b_count)| b_count.cmp(a_count).then_with(|| a_name.as_str().cmp(&b_name.as_str())));
+    let result: Vec<JsonValue> = suggestions.into_iter().take(take).map(|(name, count)| json!({ "name": name, "count": count })).collect();
+    Ok(Response::data(Value::from(JsonValue::Array(result))))
+}
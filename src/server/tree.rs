@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::connection;
+use teo_runtime::database::database::Database;
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::database::is_provider_connector;
+use crate::stdlib::decorators::tree::TreeFields;
+use crate::utils::sql::sql_literal;
+use crate::server::query_tag::tagged;
+
+/// Which part of a tree a filter selects, relative to a node.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    Ancestors,
+    Descendants,
+    SubtreeOf,
+}
+
+/// An `ancestors`, `descendants` or `subtreeOf` input in the `where` of a
+/// query on a `@@tree` model.
+///
+/// The query engine doesn't understand the inputs, so they are removed and
+/// the ids of the selected records are looked up, with a recursive CTE on SQL
+/// databases and level by level on MongoDB. The `where` is then narrowed to
+/// these ids, so pagination and counts work as usual.
+#[derive(Debug, Clone)]
+pub(super) struct TreeFilter {
+    kind: Kind,
+    node: JsonValue,
+}
+
+impl TreeFilter {
+
+    /// Take the tree input out of the top level `where` of query arguments.
+    pub(super) fn take(args: &mut JsonValue) -> Result<Option<Self>> {
+        let Some(JsonValue::Object(r#where)) = args.get_mut("where") else { return Ok(None) };
+        let mut found = None;
+        for (key, kind) in [("ancestors", Kind::Ancestors), ("descendants", Kind::Descendants), ("subtreeOf", Kind::SubtreeOf)] {
+            let Some(node) = r#where.remove(key) else { continue };
+            if found.is_some() {
+                Err(Error::invalid_request_message("expect only one of `ancestors`, `descendants` and `subtreeOf`"))?
+            }
+            if node.is_null() || node.is_object() || node.is_array() {
+                Err(Error::invalid_request_message(format!("expect `{}` to be the id of a node", key)))?
+            }
+            found = Some(Self { kind, node });
+        }
+        Ok(found)
+    }
+
+    /// Narrow the `where` of query arguments to the selected records.
+    pub(super) async fn apply(&self, model: &'static Model, fields: &TreeFields, args: &mut JsonValue, main_namespace: &'static Namespace) -> Result<()> {
+        let ids = match connection_for(model).await? {
+            (transaction, database) if !database.is_mongo() => self.ids_with_cte(model, fields, transaction, &database).await?,
+            _ => self.ids_level_by_level(model, fields, main_namespace).await?,
+        };
+        let Some(object) = args.as_object_mut() else { return Ok(()) };
+        let r#where = object.remove("where").unwrap_or(json!({}));
+        let narrowed = json!({ fields.id.as_str(): { "in": ids } });
+        let is_empty = r#where.as_object().map_or(true, |w| w.is_empty());
+        object.insert("where".to_owned(), if is_empty { narrowed } else { json!({ "AND": [r#where, narrowed] }) });
+        Ok(())
+    }
+
+    async fn ids_with_cte(&self, model: &Model, fields: &TreeFields, transaction: Arc<dyn Transaction>, database: &Database) -> Result<Vec<JsonValue>> {
+        let id = &model.field(&fields.id).ok_or_else(|| Error::not_found())?.column_name;
+        let parent = &model.field(&fields.parent).ok_or_else(|| Error::not_found())?.column_name;
+        let table = &model.table_name;
+        let node = sql_literal(&Value::from(self.node.clone()), database)?;
+        // `UNION` drops rows which are already found, so the recursion ends
+        // even if the stored tree has a cycle
+        let statement = match self.kind {
+            Kind::Ancestors => format!(
+                "WITH RECURSIVE _teo_tree (id, parent) AS (SELECT {id}, {parent} FROM {table} WHERE {id} = {node} UNION SELECT p.{id}, p.{parent} FROM {table} p JOIN _teo_tree t ON p.{id} = t.parent) SELECT id FROM _teo_tree WHERE id <> {node}",
+            ),
+            Kind::Descendants | Kind::SubtreeOf => format!(
+                "WITH RECURSIVE _teo_tree (id) AS (SELECT {id} FROM {table} WHERE {root} = {node} UNION SELECT c.{id} FROM {table} c JOIN _teo_tree t ON c.{parent} = t.id) SELECT id FROM _teo_tree",
+                root = if self.kind == Kind::SubtreeOf { id } else { parent },
+            ),
+        };
+        let rows = match transaction.query_raw(&Value::String(tagged(statement))).await? {
+            Value::Array(rows) => rows,
+            Value::Null => vec![],
+            row => vec![row],
+        };
+        rows.iter().map(|row| JsonValue::try_from(row.get("id").unwrap_or(&Value::Null))).collect()
+    }
+
+    async fn ids_level_by_level(&self, model: &'static Model, fields: &TreeFields, main_namespace: &'static Namespace) -> Result<Vec<JsonValue>> {
+        let ctx = transaction::Ctx::new(connection::Ctx::from_namespace(main_namespace));
+        let mut ids: Vec<JsonValue> = vec![];
+        if self.kind == Kind::Ancestors {
+            let mut current = self.node.clone();
+            loop {
+                let found = find(model, json!({ fields.id.as_str(): current }), main_namespace, &ctx).await?;
+                let Some(object) = found.first() else { break };
+                current = JsonValue::try_from(&object.get_value(&fields.parent)?)?;
+                if current.is_null() || current == self.node || ids.contains(&current) {
+                    break;
+                }
+                ids.push(current.clone());
+            }
+        } else {
+            if self.kind == Kind::SubtreeOf {
+                ids.push(self.node.clone());
+            }
+            let mut level = vec![self.node.clone()];
+            while !level.is_empty() {
+                let children = find(model, json!({ fields.parent.as_str(): { "in": level } }), main_namespace, &ctx).await?;
+                level = vec![];
+                for child in children {
+                    let id = JsonValue::try_from(&child.get_value(&fields.id)?)?;
+                    if !ids.contains(&id) {
+                        ids.push(id.clone());
+                        level.push(id);
+                    }
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+async fn find(model: &'static Model, r#where: JsonValue, main_namespace: &'static Namespace, ctx: &transaction::Ctx) -> Result<Vec<Object>> {
+    let find_many = builtin_action_handler_from_name("findMany").ok_or_else(|| Error::not_found())?;
+    let input = validate_and_transform_json_input_for_builtin_action(model, find_many, &json!({ "where": r#where }), main_namespace)?;
+    ctx.find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null) }), None, path![]).await
+}
+
+async fn connection_for(model: &Model) -> Result<(Arc<dyn Transaction>, Database)> {
+    let path = model.path();
+    let namespace_path: Vec<String> = path[..path.len() - 1].iter().map(|s| s.to_string()).collect();
+    let conn_ctx = Ctx::conn_ctx();
+    let (connection_path, connection) = conn_ctx.connections_iter()
+        .filter(|(connection_path, _)| namespace_path.starts_with(connection_path))
+        .max_by_key(|(connection_path, _)| connection_path.len())
+        .ok_or_else(|| Error::new("no connection is found for tree queries"))?;
+    let namespace = conn_ctx.namespace().namespace_at_path(&connection_path.iter().map(AsRef::as_ref).collect()).ok_or_else(|| Error::not_found())?;
+    let connector = namespace.connector.as_ref().filter(|connector| is_provider_connector(connector)).ok_or_else(|| Error::new("no connector running SQL or MongoDB commands is found for tree queries"))?;
+    let database = connector.provider.clone();
+    Ok((connection.no_transaction().await?, database))
+}
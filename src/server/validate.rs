@@ -0,0 +1,72 @@
+use key_path::path;
+use serde_json::{json, Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::handler::action::builtin_action_handler_from_name;
+use teo_runtime::handler::input::validate_and_transform_json_input_for_builtin_action;
+use teo_runtime::model::field::is_optional::IsOptional;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::server::error::UserError;
+
+/// The `validate` action of a model with `@@validateAction`, guarded like
+/// the `update` or `create` it checks. The body is `{ create }` or
+/// `{ where, update }`, like the bodies of `create` and `update`. The input
+/// is parsed and run through the `onSet` and `onSave` pipelines of its
+/// fields, and required fields of a create are checked, but nothing is
+/// saved. Errors are the errors `create` and `update` would respond with;
+/// unique constraints and model hooks aren't checked. Responds with
+/// `{ valid: true }`.
+pub(super) async fn validate(model: &'static Model, body: &JsonValue, main_namespace: &'static Namespace, ctx: &request::Ctx) -> Result<Response> {
+    let (object, set) = match body.get("update") {
+        Some(update) => {
+            let action = builtin_action_handler_from_name("update").ok_or_else(|| Error::not_found())?;
+            let input = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "where": body.get("where").cloned().unwrap_or(JsonValue::Null), "update": update }), main_namespace)?;
+            let found: Vec<Object> = ctx.transaction_ctx().find_many(model, &teon!({ "where": input.get("where").cloned().unwrap_or(Value::Null), "take": 1 }), None, path![]).await?;
+            let object = found.into_iter().next().ok_or_else(|| Error::not_found())?;
+            let update = input.get("update").cloned().unwrap_or(teon!({}));
+            object.set_teon(&update).await?;
+            (object, keys(&update))
+        }
+        None => {
+            let action = builtin_action_handler_from_name("create").ok_or_else(|| Error::not_found())?;
+            let input = validate_and_transform_json_input_for_builtin_action(model, action, &json!({ "create": body.get("create").cloned().unwrap_or(json!({})) }), main_namespace)?;
+            let create = input.get("create").cloned().unwrap_or(teon!({}));
+            let object = ctx.transaction_ctx().create_object(model, &create, Some(ctx.clone())).await?;
+            for field in model.fields.values() {
+                // foreign keys are set by relation inputs on save
+                if field.is_optional() || field.default.is_some() || field.auto || field.auto_increment || field.foreign_key || field.r#virtual {
+                    continue
+                }
+                if object.get_value(field.name())?.is_null() {
+                    Err(Error::invalid_request_pathed(path![field.name()], "value is required"))?
+                }
+            }
+            (object, model.fields.keys().cloned().collect())
+        }
+    };
+    for name in set {
+        let Some(field) = model.field(&name) else { continue };
+        if field.on_save.items.is_empty() {
+            continue
+        }
+        let pipeline_ctx = pipeline::Ctx::new(object.get_value(&name)?, object.clone(), path![name.as_str()], object.action(), ctx.transaction_ctx(), Some(ctx.clone()));
+        pipeline_ctx.run_pipeline(&field.on_save).await.map_err(|e| match e.platform_native_object::<UserError>() {
+            // errors with codes name their fields already
+            Some(_) => e,
+            None => Error::invalid_request_pathed(path![name.as_str()], e.message),
+        })?;
+    }
+    Ok(Response::data(teon!({ "valid": true })))
+}
+
+fn keys(value: &Value) -> Vec<String> {
+    match value {
+        Value::Dictionary(map) => map.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,58 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use teo_runtime::teon;
+use crate::stdlib::decorators::expires::parse_duration;
+
+/// The key under which the archival policy of a model is recorded in the
+/// model data.
+pub(crate) const ARCHIVE_KEY: &str = "archive";
+
+/// Where and when the records of a model are archived.
+#[derive(Debug, Clone)]
+pub(crate) struct ArchivePolicy {
+    /// The age in seconds after which records are archived.
+    pub(crate) after: i64,
+    /// A `s3://bucket/prefix` or `file:///path` url.
+    pub(crate) to: String,
+    /// The `DateTime` field the age is measured from.
+    pub(crate) field: String,
+}
+
+/// `@@archive(after: "1y", to: "s3://bucket/prefix", field: "createdAt")`
+///
+/// Move records older than `after` to JSONL files in object storage and
+/// delete them from the database. `after` is a number of seconds or a
+/// duration like `"30d"` or `"1y"`, `to` is a `s3://` or `file://` url and
+/// `field` defaults to `createdAt`. Records are archived by a background job
+/// while the server runs and `teo restore` brings them back.
+pub(super) fn load_archive_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("archive", |arguments: Arguments, model: &mut Model| {
+        let after = match arguments.get::<i64>("after") {
+            Ok(seconds) => seconds,
+            Err(_) => parse_duration(&arguments.get::<String>("after")?).map_err(|e| Error::new(format!("@@archive: {}", e.message)))?,
+        };
+        let to: String = arguments.get("to")?;
+        if !to.starts_with("s3://") && !to.starts_with("file://") {
+            Err(Error::new(format!("@@archive: unsupported destination `{}`, expect a s3:// or file:// url", to)))?
+        }
+        let field: String = arguments.get_optional("field")?.unwrap_or_else(|| "createdAt".to_owned());
+        if model.field(&field).is_none() {
+            Err(Error::new(format!("@@archive: field `{}` is not found", field)))?
+        }
+        model.data.insert(ARCHIVE_KEY.to_owned(), teon!({ "after": after, "to": to, "field": field }).into());
+        Ok(())
+    });
+}
+
+/// The archival policy of a model.
+pub(crate) fn model_archive(model: &Model) -> Option<ArchivePolicy> {
+    let value: &Value = model.data.get(ARCHIVE_KEY)?.as_teon()?;
+    Some(ArchivePolicy {
+        after: value.get("after")?.as_int64()?,
+        to: value.get("to")?.as_str()?.to_owned(),
+        field: value.get("field")?.as_str()?.to_owned(),
+    })
+}
@@ -0,0 +1,42 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+
+/// The collation recorded by `@caseInsensitive`. Migrations map it to CITEXT
+/// on PostgreSQL and to the `utf8mb4_0900_as_ci` collation on MySQL.
+pub(crate) const CASE_INSENSITIVE_COLLATION: &str = "caseInsensitive";
+
+/// The key under which the collation of a field is stored in its data.
+pub(crate) const COLLATION_KEY: &str = "collation";
+
+/// `@collation("und-x-icu")` and `@caseInsensitive`
+///
+/// Set the collation of the column of a string field. Migrations alter the
+/// column after the connector creates it, and the indexes of the column
+/// use the collation too. Filters like `equals` and `in` compare the field
+/// by its collation, so `@caseInsensitive` fields match regardless of case
+/// without `mode: .caseInsensitive`, which can't use the indexes. Only
+/// PostgreSQL and MySQL support collations.
+pub(super) fn load_collation_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("collation", |arguments: Arguments, field: &mut Field| {
+        let collation: String = arguments.get("collation")?;
+        field.data.insert(COLLATION_KEY.to_owned(), Value::String(collation).into());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("caseInsensitive", |_arguments: Arguments, field: &mut Field| {
+        field.data.insert(COLLATION_KEY.to_owned(), Value::String(CASE_INSENSITIVE_COLLATION.to_owned()).into());
+        Ok(())
+    });
+}
+
+/// The collation of a field.
+pub(crate) fn field_collation(field: &Field) -> Option<&str> {
+    field.data.get(COLLATION_KEY)?.as_teon()?.as_str()
+}
+
+/// Whether a model has fields with collations.
+pub(crate) fn has_collated_fields(model: &Model) -> bool {
+    model.fields.values().any(|field| field_collation(field).is_some())
+}
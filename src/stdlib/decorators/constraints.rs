@@ -0,0 +1,165 @@
+use std::sync::Arc;
+use regex::Regex;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// The key under which the constraints of a field are recorded in its data.
+pub(crate) const CONSTRAINTS_KEY: &str = "constraints";
+
+/// A rule on the values of a field, checked on set and by the database.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Constraint {
+    MinLength(i64),
+    MaxLength(i64),
+    Min(f64),
+    Max(f64),
+    Regex(String),
+}
+
+impl Constraint {
+
+    /// The name of the rule, as in the decorator.
+    pub(crate) fn rule(&self) -> &'static str {
+        match self {
+            Constraint::MinLength(_) => "minLength",
+            Constraint::MaxLength(_) => "maxLength",
+            Constraint::Min(_) => "min",
+            Constraint::Max(_) => "max",
+            Constraint::Regex(_) => "regex",
+        }
+    }
+
+    /// The rule as declared, e.g. `maxLength(255)`.
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Constraint::MinLength(n) | Constraint::MaxLength(n) => format!("{}({})", self.rule(), n),
+            Constraint::Min(n) | Constraint::Max(n) => format!("{}({})", self.rule(), n),
+            Constraint::Regex(pattern) => format!("{}({:?})", self.rule(), pattern),
+        }
+    }
+
+    fn to_teon(&self) -> Value {
+        match self {
+            Constraint::MinLength(n) | Constraint::MaxLength(n) => teon!({ "rule": self.rule(), "value": *n }),
+            Constraint::Min(n) | Constraint::Max(n) => teon!({ "rule": self.rule(), "value": *n }),
+            Constraint::Regex(pattern) => teon!({ "rule": self.rule(), "value": pattern.as_str() }),
+        }
+    }
+
+    fn from_teon(value: &Value) -> Option<Self> {
+        let rule = value.get("rule")?.as_str()?;
+        let value = value.get("value")?;
+        Some(match rule {
+            "minLength" => Constraint::MinLength(value.as_int64()?),
+            "maxLength" => Constraint::MaxLength(value.as_int64()?),
+            "min" => Constraint::Min(number(value)?),
+            "max" => Constraint::Max(number(value)?),
+            "regex" => Constraint::Regex(value.as_str()?.to_owned()),
+            _ => None?,
+        })
+    }
+
+    fn check(&self, value: &Value, regex: Option<&Regex>) -> Result<()> {
+        let valid = match (self, value) {
+            (Constraint::MinLength(n), Value::String(s)) => s.chars().count() as i64 >= *n,
+            (Constraint::MaxLength(n), Value::String(s)) => s.chars().count() as i64 <= *n,
+            (Constraint::Min(n), value) => number(value).map_or(true, |v| v >= *n),
+            (Constraint::Max(n), value) => number(value).map_or(true, |v| v <= *n),
+            (Constraint::Regex(_), Value::String(s)) => regex.map_or(true, |r| r.is_match(s)),
+            _ => true,
+        };
+        if valid {
+            return Ok(());
+        }
+        Err(Error::new(match self {
+            Constraint::MinLength(n) => format!("expect at least {} characters", n),
+            Constraint::MaxLength(n) => format!("expect at most {} characters", n),
+            Constraint::Min(n) => format!("expect a value of at least {}", n),
+            Constraint::Max(n) => format!("expect a value of at most {}", n),
+            Constraint::Regex(pattern) => format!("expect a value matching `{}`", pattern),
+        }))
+    }
+}
+
+/// `@minLength(1)`, `@maxLength(255)`, `@min(0)`, `@max(100)` and
+/// `@regex("^[a-z0-9-]+$")`
+///
+/// Constrain the values of a string or number field. The rules are checked
+/// in `onSet`, and compiled to `CHECK` constraints on migration where the
+/// database supports them, so writes from outside the app can't break them:
+/// PostgreSQL takes all rules, MySQL all but `regex`. SQLite and MongoDB
+/// only check them in the app. `regex` matches anywhere in the value, anchor
+/// it with `^` and `$` to match the whole value, and stick to the syntax
+/// Rust and PostgreSQL share.
+pub(super) fn load_constraint_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("minLength", |arguments: Arguments, field: &mut Field| {
+        add_constraint(field, Constraint::MinLength(arguments.get("length")?))
+    });
+    namespace.define_model_field_decorator("maxLength", |arguments: Arguments, field: &mut Field| {
+        add_constraint(field, Constraint::MaxLength(arguments.get("length")?))
+    });
+    namespace.define_model_field_decorator("min", |arguments: Arguments, field: &mut Field| {
+        let value: Value = arguments.get("value")?;
+        add_constraint(field, Constraint::Min(number(&value).ok_or_else(|| Error::new("@min: expect a number"))?))
+    });
+    namespace.define_model_field_decorator("max", |arguments: Arguments, field: &mut Field| {
+        let value: Value = arguments.get("value")?;
+        add_constraint(field, Constraint::Max(number(&value).ok_or_else(|| Error::new("@max: expect a number"))?))
+    });
+    namespace.define_model_field_decorator("regex", |arguments: Arguments, field: &mut Field| {
+        add_constraint(field, Constraint::Regex(arguments.get("pattern")?))
+    });
+}
+
+fn add_constraint(field: &mut Field, constraint: Constraint) -> Result<()> {
+    let regex = match &constraint {
+        Constraint::Regex(pattern) => Some(Regex::new(pattern).map_err(|e| Error::new(format!("@regex: invalid pattern: {}", e)))?),
+        _ => None,
+    };
+    let mut constraints: Vec<Value> = field_constraints(field).iter().map(Constraint::to_teon).collect();
+    constraints.push(constraint.to_teon());
+    field.data.insert(CONSTRAINTS_KEY.to_owned(), Value::Array(constraints).into());
+    field.on_set.items.push(BoundedItem {
+        path: vec![constraint.rule().to_owned()],
+        arguments: Arguments::default(),
+        call: Arc::new(move |_args: Arguments, ctx: Ctx| {
+            let (constraint, regex) = (constraint.clone(), regex.clone());
+            async move {
+                constraint.check(ctx.value(), regex.as_ref())?;
+                Ok(ctx.value().clone())
+            }
+        }),
+    });
+    Ok(())
+}
+
+/// The constraints of a field.
+pub(crate) fn field_constraints(field: &Field) -> Vec<Constraint> {
+    match field.data.get(CONSTRAINTS_KEY).and_then(|v| v.as_teon()) {
+        Some(Value::Array(values)) => values.iter().filter_map(Constraint::from_teon).collect(),
+        _ => vec![],
+    }
+}
+
+/// Whether a model has constrained fields.
+pub(crate) fn has_constrained_fields(model: &Model) -> bool {
+    model.fields.values().any(|field| !field_constraints(field).is_empty())
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Int64(i) => Some(*i as f64),
+        Value::Float32(f) => Some(*f as f64),
+        Value::Float(f) => Some(*f),
+        Value::Decimal(d) => d.to_string().parse().ok(),
+        _ => None,
+    }
+}
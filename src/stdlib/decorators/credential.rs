@@ -0,0 +1,52 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+
+/// The key under which the credential fields of a model are recorded in the
+/// model data.
+pub(crate) const CREDENTIAL_KEY: &str = "credential";
+
+/// The fields of a credential model.
+#[derive(Debug, Clone)]
+pub(crate) struct CredentialFields {
+    /// The relation to the identity model the credential belongs to.
+    pub(crate) owner: String,
+    /// The string field naming the provider, e.g. `password` or `google`.
+    pub(crate) provider: String,
+    /// The string field identifying the account at the provider, e.g. the
+    /// email address or the OIDC subject.
+    pub(crate) subject: String,
+}
+
+/// `@@credential(owner: "user", provider: "provider", subject: "subject")`
+///
+/// Record the credentials an identity signs in with, one record per
+/// credential, so that an identity can have several. Enables the `link` and
+/// `unlink` actions, and an identity never loses its last credential, through
+/// these actions or the builtin ones.
+pub(super) fn load_credential_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("credential", |arguments: Arguments, model: &mut Model| {
+        let owner: String = arguments.get_optional("owner")?.unwrap_or_else(|| "user".to_owned());
+        let provider: String = arguments.get_optional("provider")?.unwrap_or_else(|| "provider".to_owned());
+        let subject: String = arguments.get_optional("subject")?.unwrap_or_else(|| "subject".to_owned());
+        for field in [&provider, &subject] {
+            if !model.fields.contains_key(field.as_str()) {
+                Err(Error::new(format!("@@credential: field `{}` is not found", field)))?
+            }
+        }
+        model.data.insert(CREDENTIAL_KEY.to_owned(), teon!({ "owner": owner, "provider": provider, "subject": subject }).into());
+        Ok(())
+    });
+}
+
+/// The credential fields of a model, if it's a credential model.
+pub(crate) fn model_credential(model: &Model) -> Option<CredentialFields> {
+    let value = model.data.get(CREDENTIAL_KEY)?.as_teon()?;
+    Some(CredentialFields {
+        owner: value.get("owner")?.as_str()?.to_owned(),
+        provider: value.get("provider")?.as_str()?.to_owned(),
+        subject: value.get("subject")?.as_str()?.to_owned(),
+    })
+}
@@ -0,0 +1,31 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Pipeline;
+use teo_runtime::Value;
+
+/// The key under which `@dedupe` is recorded in the field data.
+pub(crate) const DEDUPE_KEY: &str = "dedupe";
+
+/// `@dedupe($trim.toLowerCase.phonetic)`
+///
+/// Compare a field in `findDuplicates`. The optional pipeline normalizes the
+/// values before they are compared, values are compared as they are stored
+/// without it.
+pub(super) fn load_dedupe_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("dedupe", |arguments: Arguments, field: &mut Field| {
+        let normalize: Option<Pipeline> = arguments.get_optional("normalize")?;
+        field.data.insert(DEDUPE_KEY.to_owned(), normalize.map_or(Value::Bool(true), Value::Pipeline).into());
+        Ok(())
+    });
+}
+
+/// The fields of a model compared by `findDuplicates`, with their
+/// normalization pipelines.
+pub(crate) fn model_dedupe_fields(model: &Model) -> Vec<(&Field, Option<&Pipeline>)> {
+    model.fields.values().filter_map(|field| match field.data.get(DEDUPE_KEY)?.as_teon()? {
+        Value::Pipeline(pipeline) => Some((field, Some(pipeline))),
+        _ => Some((field, None)),
+    }).collect()
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::Value;
+
+/// The key under which the dimensions of a vector field are recorded in the
+/// field data.
+pub(crate) const DIMENSIONS_KEY: &str = "dimensions";
+
+/// `@dimensions(1536)`
+///
+/// Mark a `Float[]` field as an embedding vector with a fixed number of
+/// dimensions. Vectors of other lengths are rejected on save, and the field
+/// can be ranked with `nearestTo` in `findMany`.
+pub(super) fn load_dimensions_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("dimensions", |arguments: Arguments, field: &mut Field| {
+        let dimensions: i64 = arguments.get("dimensions")?;
+        field.data.insert(DIMENSIONS_KEY.to_owned(), Value::Int64(dimensions).into());
+        field.on_save.items.push(BoundedItem {
+            path: vec!["dimensions".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(move |_args: Arguments, ctx: Ctx| async move {
+                match ctx.value() {
+                    Value::Array(vector) if vector.len() as i64 != dimensions => {
+                        Err(Error::new(format!("expect a vector of {} dimensions, found {}", dimensions, vector.len())))
+                    }
+                    value => Ok(value.clone()),
+                }
+            }),
+        });
+        Ok(())
+    });
+}
+
+/// The dimensions of a vector field.
+pub(crate) fn field_dimensions(field: &Field) -> Option<i64> {
+    field.data.get(DIMENSIONS_KEY)?.as_teon()?.as_int64()
+}
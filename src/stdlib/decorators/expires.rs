@@ -0,0 +1,57 @@
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use teo_runtime::teon;
+
+/// The key under which the expiry of a model is recorded in the model data.
+pub(crate) const EXPIRES_KEY: &str = "expires";
+
+/// `@@expires(after: "30d", field: "createdAt")`
+///
+/// Delete records once `field`, a `DateTime` field, is older than `after`.
+/// `after` is a number of seconds or a duration like `"90s"`, `"30m"`, `"12h"`
+/// or `"7d"`. Expired records are deleted by a background sweeper while the
+/// server runs.
+pub(super) fn load_expires_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("expires", |arguments: Arguments, model: &mut Model| {
+        let after = match arguments.get::<i64>("after") {
+            Ok(seconds) => seconds,
+            Err(_) => parse_duration(&arguments.get::<String>("after")?).map_err(|e| Error::new(format!("@@expires: {}", e.message)))?,
+        };
+        let field: String = arguments.get("field")?;
+        if model.field(&field).is_none() {
+            Err(Error::new(format!("@@expires: field `{}` is not found", field)))?
+        }
+        model.data.insert(EXPIRES_KEY.to_owned(), teon!({ "after": after, "field": field }).into());
+        Ok(())
+    });
+}
+
+/// Parse a number of seconds or a duration like `"90s"`, `"30m"`, `"12h"`,
+/// `"7d"`, `"2w"` or `"1y"`, where a year is 365 days.
+pub(crate) fn parse_duration(duration: &str) -> Result<i64> {
+    let duration = duration.trim();
+    let (number, unit) = duration.split_at(duration.find(|c: char| !c.is_ascii_digit()).unwrap_or(duration.len()));
+    let number: i64 = number.parse().map_err(|_| Error::new(format!("invalid duration `{}`", duration)))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "y" => 365 * 86400,
+        _ => Err(Error::new(format!("invalid duration unit `{}`, expect s, m, h, d, w or y", unit)))?,
+    };
+    Ok(number * multiplier)
+}
+
+/// The expiry of a model: the seconds after which records expire and the
+/// field the age is measured from.
+pub(crate) fn model_expiry(model: &Model) -> Option<(i64, String)> {
+    let value: &Value = model.data.get(EXPIRES_KEY)?.as_teon()?;
+    let after = value.get("after")?.as_int64()?;
+    let field = value.get("field")?.as_str()?.to_owned();
+    Some((after, field))
+}
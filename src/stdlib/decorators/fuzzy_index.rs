@@ -0,0 +1,23 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+
+/// The key under which `@fuzzyIndex` is recorded in the field data.
+pub(crate) const FUZZY_INDEX_KEY: &str = "fuzzyIndex";
+
+/// `@fuzzyIndex`
+///
+/// Mark a string field for `similar` filters. Migrations create a `pg_trgm`
+/// GIN index for it on PostgreSQL.
+pub(super) fn load_fuzzy_index_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("fuzzyIndex", |_arguments: Arguments, field: &mut Field| {
+        field.data.insert(FUZZY_INDEX_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+/// Whether a field has `@fuzzyIndex`.
+pub(crate) fn has_fuzzy_index(field: &Field) -> bool {
+    field.data.contains_key(FUZZY_INDEX_KEY)
+}
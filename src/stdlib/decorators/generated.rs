@@ -0,0 +1,50 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::readwrite::write::Write;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// The key under which the generation of a field is stored in its data.
+pub(crate) const GENERATED_KEY: &str = "generated";
+
+/// How the database computes a generated column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Generated {
+    /// The SQL expression, over the columns of the same row.
+    pub(crate) expression: String,
+    /// Whether the value is computed on write and stored, or computed on
+    /// read.
+    pub(crate) stored: bool,
+}
+
+/// `@generated("price * quantity", stored: true)`
+///
+/// Compute a field in the database with `GENERATED ALWAYS AS (expr)`. The
+/// field is readonly in the API. Columns are stored by default; PostgreSQL
+/// only supports stored columns, and SQLite can only add virtual ones to
+/// existing tables.
+pub(super) fn load_generated_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("generated", |arguments: Arguments, field: &mut Field| {
+        let expression: String = arguments.get("expr")?;
+        let stored: bool = arguments.get_optional("stored")?.unwrap_or(true);
+        field.data.insert(GENERATED_KEY.to_owned(), teon!({ "expression": expression, "stored": stored }).into());
+        field.write = Write::NoWrite;
+        Ok(())
+    });
+}
+
+/// The generation of a field.
+pub(crate) fn field_generated(field: &Field) -> Option<Generated> {
+    let value: &Value = field.data.get(GENERATED_KEY)?.as_teon()?;
+    Some(Generated {
+        expression: value.get("expression")?.as_str()?.to_owned(),
+        stored: value.get("stored")?.as_bool()?,
+    })
+}
+
+/// Whether a model has generated fields.
+pub(crate) fn has_generated_fields(model: &Model) -> bool {
+    model.fields.values().any(|field| field_generated(field).is_some())
+}
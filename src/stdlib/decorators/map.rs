@@ -0,0 +1,40 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+
+/// The key under which a model or field records that its table or column
+/// name is set with `@map`.
+pub(crate) const MAPPED_KEY: &str = "mapped";
+
+/// `@@map("posts")` and `@map("created_at")`
+///
+/// Set the table name of a model or the column name of a field like the
+/// standard decorators, and record that it's set, so that the naming
+/// convention of the app leaves it alone even when it equals the model or
+/// field name.
+pub(super) fn load_map_decorators(namespace: &mut Namespace) {
+    namespace.define_model_decorator("map", |arguments: Arguments, model: &mut Model| {
+        let table_name: String = arguments.get("tableName")?;
+        model.table_name = table_name;
+        model.data.insert(MAPPED_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("map", |arguments: Arguments, field: &mut Field| {
+        let column_name: String = arguments.get("columnName")?;
+        field.column_name = column_name;
+        field.data.insert(MAPPED_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+/// Whether the table name of a model is set with `@@map`.
+pub(crate) fn is_model_mapped(model: &Model) -> bool {
+    model.data.get(MAPPED_KEY).and_then(|v| v.as_teon()).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Whether the column name of a field is set with `@map`.
+pub(crate) fn is_field_mapped(field: &Field) -> bool {
+    field.data.get(MAPPED_KEY).and_then(|v| v.as_teon()).and_then(|v| v.as_bool()).unwrap_or(false)
+}
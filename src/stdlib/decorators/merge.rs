@@ -0,0 +1,49 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// The key under which the merge fields of a model are recorded in the model
+/// data.
+pub(crate) const MERGE_KEY: &str = "merge";
+
+/// The fields the `merge` action of a model works with.
+#[derive(Debug, Clone)]
+pub(crate) struct MergeFields {
+    /// A `Bool` or `DateTime` field marking merged source records deleted.
+    pub(crate) deleted: String,
+    /// A `DateTime` field telling which record is newer, for the `newest`
+    /// strategy.
+    pub(crate) updated_at: Option<String>,
+}
+
+/// `@@merge(deleted: "deletedAt", updatedAt: "updatedAt")`
+///
+/// Enable the `merge` action, which folds a source record into a target
+/// record. Records referencing the source are re-pointed to the target and
+/// the source is kept with its `deleted` field set, `true` or the current
+/// time.
+pub(super) fn load_merge_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("merge", |arguments: Arguments, model: &mut Model| {
+        let deleted: String = arguments.get("deleted")?;
+        let updated_at: Option<String> = arguments.get_optional("updatedAt")?;
+        for field in [Some(&deleted), updated_at.as_ref()].into_iter().flatten() {
+            if !model.fields.contains_key(field.as_str()) {
+                Err(Error::new(format!("@@merge: field `{}` is not found", field)))?
+            }
+        }
+        model.data.insert(MERGE_KEY.to_owned(), teon!({ "deleted": deleted, "updatedAt": updated_at.map_or(Value::Null, Value::String) }).into());
+        Ok(())
+    });
+}
+
+/// The merge fields of a model.
+pub(crate) fn model_merge(model: &Model) -> Option<MergeFields> {
+    let value = model.data.get(MERGE_KEY)?.as_teon()?;
+    Some(MergeFields {
+        deleted: value.get("deleted")?.as_str()?.to_owned(),
+        updated_at: value.get("updatedAt").and_then(|u| u.as_str()).map(|u| u.to_owned()),
+    })
+}
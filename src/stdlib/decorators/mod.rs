@@ -0,0 +1,63 @@
+pub(crate) mod archive;
+pub(crate) mod collation;
+pub(crate) mod constraints;
+pub(crate) mod credential;
+pub(crate) mod dedupe;
+pub(crate) mod dimensions;
+pub(crate) mod expires;
+pub(crate) mod fuzzy_index;
+pub(crate) mod generated;
+pub(crate) mod map;
+pub(crate) mod merge;
+pub(crate) mod normalize;
+pub(crate) mod on_output;
+pub(crate) mod online_alter;
+pub(crate) mod permissions;
+pub(crate) mod pii_strategy;
+pub(crate) mod position;
+pub(crate) mod publish;
+pub(crate) mod scalar;
+pub(crate) mod search_index;
+pub(crate) mod sequence;
+pub(crate) mod slug;
+pub(crate) mod sync;
+pub(crate) mod taggable;
+pub(crate) mod transitions;
+pub(crate) mod tree;
+pub(crate) mod validate_action;
+pub(crate) mod version;
+pub(crate) mod view;
+
+use teo_runtime::namespace::Namespace;
+
+pub(super) fn load_decorators(namespace: &mut Namespace) {
+    archive::load_archive_decorator(namespace);
+    collation::load_collation_decorators(namespace);
+    constraints::load_constraint_decorators(namespace);
+    credential::load_credential_decorator(namespace);
+    dedupe::load_dedupe_decorator(namespace);
+    dimensions::load_dimensions_decorator(namespace);
+    expires::load_expires_decorator(namespace);
+    fuzzy_index::load_fuzzy_index_decorator(namespace);
+    generated::load_generated_decorator(namespace);
+    map::load_map_decorators(namespace);
+    merge::load_merge_decorator(namespace);
+    normalize::load_normalize_decorators(namespace);
+    on_output::load_on_output_decorator(namespace);
+    online_alter::load_online_alter_decorator(namespace);
+    permissions::load_permissions_decorator(namespace);
+    pii_strategy::load_pii_strategy_decorator(namespace);
+    position::load_position_decorator(namespace);
+    publish::load_publish_decorator(namespace);
+    scalar::load_scalar_decorators(namespace);
+    search_index::load_search_index_decorator(namespace);
+    sequence::load_sequence_decorator(namespace);
+    slug::load_slug_decorator(namespace);
+    sync::load_sync_decorator(namespace);
+    taggable::load_taggable_decorator(namespace);
+    transitions::load_transitions_decorator(namespace);
+    tree::load_tree_decorator(namespace);
+    validate_action::load_validate_action_decorator(namespace);
+    version::load_version_decorator(namespace);
+    view::load_view_decorator(namespace);
+}
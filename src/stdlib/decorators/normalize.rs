@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::Value;
+
+/// The key under which the number of normalizers of a field is recorded in
+/// its data, so that they're kept in the declared order.
+const NORMALIZERS_KEY: &str = "normalizers";
+
+static SCRIPT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<(script|style)\b.*?</(script|style)\s*>").unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|</?[a-zA-Z][^>]*>").unwrap());
+
+/// `@trim`, `@lowercase`, `@normalizeEmail` and `@stripHtml`
+///
+/// Normalize the input of a string field. The normalizers run first in
+/// `onSet`, before the validators, in the order they are declared, and apply
+/// to each string of a `String[]` field. Other values are left alone.
+///
+/// * `@trim` removes leading and trailing whitespace
+/// * `@lowercase` lowercases
+/// * `@normalizeEmail` trims and lowercases an email address
+/// * `@stripHtml` removes tags, comments, scripts and styles, keeping the text
+pub(super) fn load_normalize_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("trim", |_arguments: Arguments, field: &mut Field| {
+        add_normalizer(field, "trim", |s| s.trim().to_owned());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("lowercase", |_arguments: Arguments, field: &mut Field| {
+        add_normalizer(field, "lowercase", |s| s.to_lowercase());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("normalizeEmail", |_arguments: Arguments, field: &mut Field| {
+        add_normalizer(field, "normalizeEmail", |s| s.trim().to_lowercase());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("stripHtml", |_arguments: Arguments, field: &mut Field| {
+        add_normalizer(field, "stripHtml", strip_html);
+        Ok(())
+    });
+}
+
+/// Insert a normalizer after the normalizers already declared on the field,
+/// ahead of the rest of `onSet`.
+fn add_normalizer(field: &mut Field, name: &str, normalize: fn(&str) -> String) {
+    let index = field.data.get(NORMALIZERS_KEY).and_then(|v| v.as_teon()).and_then(|v| v.as_int64()).unwrap_or(0);
+    field.on_set.items.insert(index as usize, BoundedItem {
+        path: vec![name.to_owned()],
+        arguments: Arguments::default(),
+        call: Arc::new(move |_args: Arguments, ctx: Ctx| async move {
+            Ok(apply(ctx.value(), normalize))
+        }),
+    });
+    field.data.insert(NORMALIZERS_KEY.to_owned(), Value::Int64(index + 1).into());
+}
+
+fn apply(value: &Value, normalize: fn(&str) -> String) -> Value {
+    match value {
+        Value::String(string) => Value::String(normalize(string)),
+        Value::Array(values) => Value::Array(values.iter().map(|v| apply(v, normalize)).collect()),
+        value => value.clone(),
+    }
+}
+
+fn strip_html(string: &str) -> String {
+    TAG.replace_all(&SCRIPT.replace_all(string, ""), "").into_owned()
+}
@@ -0,0 +1,32 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Pipeline;
+use teo_runtime::Value;
+
+/// The key under which the output pipeline of a model is recorded in the
+/// model data.
+pub(crate) const ON_OUTPUT_KEY: &str = "onOutput";
+
+/// `@@onOutput($do($identity.then(...)))`
+///
+/// Shape the records returned by the find actions. The pipeline receives each
+/// record as a dictionary after it's decoded, and returns the dictionary to
+/// respond with, so it can rename, derive and strip fields. The object and
+/// the request are available to the pipeline, so the shape can depend on the
+/// identity.
+pub(super) fn load_on_output_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("onOutput", |arguments: Arguments, model: &mut Model| {
+        let pipeline: Pipeline = arguments.get("pipeline")?;
+        model.data.insert(ON_OUTPUT_KEY.to_owned(), Value::Pipeline(pipeline).into());
+        Ok(())
+    });
+}
+
+/// The output pipeline of a model.
+pub(crate) fn model_output_pipeline(model: &Model) -> Option<&Pipeline> {
+    match model.data.get(ON_OUTPUT_KEY)?.as_teon()? {
+        Value::Pipeline(pipeline) => Some(pipeline),
+        _ => None,
+    }
+}
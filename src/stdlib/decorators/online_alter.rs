@@ -0,0 +1,36 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+
+/// The key under which the online alteration settings of a model are stored
+/// in its data.
+pub(crate) const ONLINE_ALTER_KEY: &str = "onlineAlter";
+
+/// The number of rows copied at a time by default.
+pub(crate) const DEFAULT_ONLINE_ALTER_BATCH_SIZE: usize = 1000;
+
+/// `@@onlineAlter(batchSize: 5000)`
+///
+/// Change the type or optionality of columns of a large MySQL or PostgreSQL
+/// table without a blocking `ALTER TABLE`. Migrations add a new column, keep
+/// it in sync with a trigger, copy the existing rows in batches and swap the
+/// columns. An interrupted alteration resumes where it stopped.
+pub(super) fn load_online_alter_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("onlineAlter", |arguments: Arguments, model: &mut Model| {
+        let batch_size: Option<i64> = arguments.get_optional("batchSize")?;
+        if batch_size.map_or(false, |size| size < 1) {
+            Err(Error::new("@@onlineAlter: batchSize must be positive"))?
+        }
+        let batch_size = batch_size.unwrap_or(DEFAULT_ONLINE_ALTER_BATCH_SIZE as i64);
+        model.data.insert(ONLINE_ALTER_KEY.to_owned(), teon!({ "batchSize": batch_size }).into());
+        Ok(())
+    });
+}
+
+/// The batch size of a model whose columns are altered online.
+pub(crate) fn model_online_alter(model: &Model) -> Option<usize> {
+    let value = model.data.get(ONLINE_ALTER_KEY)?.as_teon()?;
+    Some(value.get("batchSize").and_then(|b| b.as_int64()).map_or(DEFAULT_ONLINE_ALTER_BATCH_SIZE, |b| b as usize))
+}
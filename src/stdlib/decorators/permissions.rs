@@ -0,0 +1,104 @@
+use indexmap::IndexMap;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::value::interface_enum_variant::InterfaceEnumVariant;
+use teo_runtime::Value;
+
+/// The key under which the guards of a model are recorded in the model data.
+pub(crate) const PERMISSIONS_KEY: &str = "permissions";
+
+/// Who may run an action.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Guard {
+    Everyone,
+    /// Any signed in identity.
+    Identity,
+    /// Identities whose `role` field is, or contains, the role.
+    Role(String),
+    Nobody,
+}
+
+impl Guard {
+
+    fn from_variant(variant: &InterfaceEnumVariant) -> Result<Self> {
+        match variant.value.as_str() {
+            "everyone" => Ok(Guard::Everyone),
+            "identity" => Ok(Guard::Identity),
+            "nobody" => Ok(Guard::Nobody),
+            "role" => {
+                let name: String = variant.args.as_ref().ok_or_else(|| Error::new("@@permissions: .role expects a role name"))?.get("name")?;
+                Ok(Guard::Role(name))
+            }
+            other => Err(Error::new(format!("@@permissions: unknown guard `.{}`", other))),
+        }
+    }
+
+    /// The guard as recorded in model data and generated clients, e.g.
+    /// `role:admin`.
+    pub(crate) fn desc(&self) -> String {
+        match self {
+            Guard::Everyone => "everyone".to_owned(),
+            Guard::Identity => "identity".to_owned(),
+            Guard::Role(name) => format!("role:{}", name),
+            Guard::Nobody => "nobody".to_owned(),
+        }
+    }
+
+    fn from_desc(desc: &str) -> Option<Self> {
+        match desc {
+            "everyone" => Some(Guard::Everyone),
+            "identity" => Some(Guard::Identity),
+            "nobody" => Some(Guard::Nobody),
+            _ => desc.strip_prefix("role:").map(|name| Guard::Role(name.to_owned())),
+        }
+    }
+}
+
+/// The actions a guard can be given for. A group, e.g. `find`, covers the
+/// actions listed with it; a guard for a single action wins over its group.
+pub(crate) const PERMISSION_ACTIONS: [(&str, &[&str]); 10] = [
+    ("find", &["findUnique", "findFirst", "findMany"]),
+    ("create", &["create", "createMany"]),
+    ("update", &["update", "updateMany", "upsert"]),
+    ("delete", &["delete", "deleteMany"]),
+    ("copy", &["copy", "copyMany"]),
+    ("count", &["count"]),
+    ("aggregate", &["aggregate", "groupBy"]),
+    ("anonymize", &["anonymize"]),
+    ("export", &["export"]),
+    ("refresh", &["refresh"]),
+];
+
+/// `@@permissions(find: .everyone, create: .identity, delete: .role("admin"))`
+///
+/// Give the actions of a model guards, which are checked before the action
+/// runs. Actions without a guard are not restricted.
+pub(super) fn load_permissions_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("permissions", |arguments: Arguments, model: &mut Model| {
+        let mut guards = IndexMap::new();
+        for (group, actions) in PERMISSION_ACTIONS {
+            if let Some(variant) = arguments.get_optional::<InterfaceEnumVariant>(group)? {
+                let guard = Guard::from_variant(&variant)?;
+                for action in actions.iter() {
+                    guards.insert(action.to_string(), Value::String(guard.desc()));
+                }
+            }
+        }
+        for (group, actions) in PERMISSION_ACTIONS {
+            for action in actions.iter().filter(|a| **a != group) {
+                if let Some(variant) = arguments.get_optional::<InterfaceEnumVariant>(action)? {
+                    guards.insert(action.to_string(), Value::String(Guard::from_variant(&variant)?.desc()));
+                }
+            }
+        }
+        model.data.insert(PERMISSIONS_KEY.to_owned(), Value::Dictionary(guards).into());
+        Ok(())
+    });
+}
+
+/// The guard of an action of a model.
+pub(crate) fn model_guard(model: &Model, action: &str) -> Option<Guard> {
+    Guard::from_desc(model.data.get(PERMISSIONS_KEY)?.as_teon()?.get(action)?.as_str()?)
+}
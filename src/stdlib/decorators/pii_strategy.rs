@@ -0,0 +1,68 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::value::interface_enum_variant::InterfaceEnumVariant;
+use teo_runtime::Value;
+
+/// The key under which the strategy of a personal data field is stored in
+/// its data.
+pub(crate) const PII_STRATEGY_KEY: &str = "piiStrategy";
+
+/// How a personal data field is scrubbed by the `anonymize` action.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum PiiStrategy {
+    /// Set the field to null, the field must be optional.
+    Null,
+    /// Replace the value with its SHA-256 hash, so equal values stay equal.
+    Hash,
+    /// Replace the value with a fake one like `teo seed fake` generates.
+    Fake,
+}
+
+impl PiiStrategy {
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(PiiStrategy::Null),
+            "hash" => Some(PiiStrategy::Hash),
+            "fake" => Some(PiiStrategy::Fake),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PiiStrategy::Null => "null",
+            PiiStrategy::Hash => "hash",
+            PiiStrategy::Fake => "fake",
+        }
+    }
+}
+
+/// `@piiStrategy(.null)`, `@piiStrategy(.hash)` and `@piiStrategy(.fake)`
+///
+/// Mark a field as personal data. Models with such fields get an `anonymize`
+/// action, which scrubs these fields of a record in place so that the
+/// relations to it are kept, and an `export` action, which returns a record
+/// with the records of other models referencing it.
+pub(super) fn load_pii_strategy_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("piiStrategy", |arguments: Arguments, field: &mut Field| {
+        let variant: InterfaceEnumVariant = arguments.get("strategy")?;
+        let strategy = PiiStrategy::from_name(variant.value.as_str()).ok_or_else(|| Error::new(format!("@piiStrategy: unknown strategy `.{}`", variant.value)))?;
+        field.data.insert(PII_STRATEGY_KEY.to_owned(), Value::String(strategy.name().to_owned()).into());
+        Ok(())
+    });
+}
+
+/// The personal data strategy of a field.
+pub(crate) fn field_pii_strategy(field: &Field) -> Option<PiiStrategy> {
+    PiiStrategy::from_name(field.data.get(PII_STRATEGY_KEY)?.as_teon()?.as_str()?)
+}
+
+/// Whether a model has personal data fields.
+pub(crate) fn has_pii_fields(model: &Model) -> bool {
+    model.fields.values().any(|field| field_pii_strategy(field).is_some())
+}
+
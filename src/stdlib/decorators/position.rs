@@ -0,0 +1,36 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use teo_runtime::teon;
+
+/// The key under which the position scope of a field is stored in its data.
+pub(crate) const POSITION_KEY: &str = "position";
+
+/// `@position(scope: ["listId"])`
+///
+/// Keep the records with the same `scope` values in an order, by an `Int`
+/// field holding their index. Created records without a position are
+/// appended. Updates move a record with `moveBefore` or `moveAfter`, which
+/// take the unique where input of a sibling, or with `moveTo`, which takes
+/// an index, and the siblings are renumbered on the server.
+pub(super) fn load_position_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("position", |arguments: Arguments, field: &mut Field| {
+        let scope: Vec<String> = arguments.get_optional("scope")?.unwrap_or_default();
+        let scope = Value::Array(scope.into_iter().map(Value::String).collect());
+        field.data.insert(POSITION_KEY.to_owned(), teon!({ "scope": scope }).into());
+        Ok(())
+    });
+}
+
+/// The scope fields of a position field.
+pub(crate) fn field_position_scope(field: &Field) -> Option<Vec<String>> {
+    let value: &Value = field.data.get(POSITION_KEY)?.as_teon()?;
+    Some(value.get("scope")?.as_array()?.iter().filter_map(|s| s.as_str().map(|s| s.to_owned())).collect())
+}
+
+/// The position fields of a model with their scope fields.
+pub(crate) fn model_position_fields(model: &Model) -> Vec<(&Field, Vec<String>)> {
+    model.fields.values().filter_map(|field| field_position_scope(field).map(|scope| (field, scope))).collect()
+}
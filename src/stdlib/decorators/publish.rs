@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::Value;
+use crate::events::{change_event, publish_in_background};
+use crate::events::outbox::write_outbox;
+
+/// The key under which whether a model publishes through the outbox is
+/// recorded in the model data.
+pub(crate) const PUBLISH_OUTBOX_KEY: &str = "publishOutbox";
+
+/// `@@publish("kafka", topic: "orders", outbox: true)`
+///
+/// Publish a versioned JSON change event through the named event sink after a
+/// record of the model is saved or deleted. The topic defaults to the model
+/// path, e.g. `blog.Post`. The event is published in the background and
+/// dropped if the sink keeps failing. With `outbox: true` the event is
+/// written to the outbox table in the transaction of the change instead, and
+/// a relay worker publishes it, so events of committed changes are never
+/// lost. The outbox requires a SQL database.
+pub(super) fn load_publish_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("publish", |arguments: Arguments, model: &mut Model| {
+        let sink: String = arguments.get("sink")?;
+        let topic: Option<String> = arguments.get_optional("topic")?;
+        let outbox = arguments.get_optional::<bool>("outbox")?.unwrap_or(false);
+        model.data.insert(PUBLISH_OUTBOX_KEY.to_owned(), Value::Bool(outbox).into());
+        let topic = Arc::new(topic.unwrap_or_else(|| model.path().join(".")));
+        let sink = Arc::new(sink);
+        for (action, pipeline) in [("save", &mut model.after_save), ("delete", &mut model.after_delete)] {
+            let sink = sink.clone();
+            let topic = topic.clone();
+            pipeline.items.push(BoundedItem {
+                path: vec!["publish".to_owned()],
+                arguments: Arguments::default(),
+                call: Arc::new(move |_args: Arguments, ctx: Ctx| {
+                    let sink = sink.clone();
+                    let topic = topic.clone();
+                    async move {
+                        let event = change_event(ctx.object(), action).await?;
+                        if outbox {
+                            write_outbox(&ctx, &sink, &topic, &event).await?;
+                        } else {
+                            publish_in_background(sink.as_ref().clone(), topic.as_ref().clone(), event);
+                        }
+                        Ok(ctx.value().clone())
+                    }
+                }),
+            });
+        }
+        Ok(())
+    });
+}
+
+/// Whether a model publishes its change events through the outbox.
+pub(crate) fn uses_outbox(model: &Model) -> bool {
+    model.data.get(PUBLISH_OUTBOX_KEY).and_then(|v| v.as_teon()).and_then(|v| v.as_bool()).unwrap_or(false)
+}
@@ -0,0 +1,43 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::app::money::MONEY_SCALAR;
+
+/// The key under which the custom scalar type of a field is stored in its
+/// data.
+pub(crate) const SCALAR_KEY: &str = "scalar";
+
+/// `@scalar("PhoneNumber")` and `@money`
+///
+/// Declare a field with a custom scalar type. The codec of the type must be
+/// registered with `App::scalar` before the app runs. `@money` declares a
+/// `Json` field with the builtin `Money` type, which is written as
+/// `{ "amount": "12.34", "currency": "USD" }` and stored in minor units.
+pub(super) fn load_scalar_decorators(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("scalar", |arguments: Arguments, field: &mut Field| {
+        let name: String = arguments.get("name")?;
+        if Ctx::scalar(&name).is_none() {
+            Err(Error::new(format!("@scalar: scalar type `{}` is not registered", name)))?
+        }
+        field.data.insert(SCALAR_KEY.to_owned(), Value::String(name).into());
+        Ok(())
+    });
+    namespace.define_model_field_decorator("money", |_arguments: Arguments, field: &mut Field| {
+        field.data.insert(SCALAR_KEY.to_owned(), Value::String(MONEY_SCALAR.to_owned()).into());
+        Ok(())
+    });
+}
+
+/// The custom scalar type of a field.
+pub(crate) fn field_scalar(field: &Field) -> Option<&str> {
+    field.data.get(SCALAR_KEY)?.as_teon()?.as_str()
+}
+
+/// Whether a model has fields with custom scalar types.
+pub(crate) fn has_scalar_fields(model: &Model) -> bool {
+    model.fields.values().any(|field| field_scalar(field).is_some())
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::Value;
+use crate::search::{index_object, remove_object};
+
+/// The key under which the search index of a model is recorded in the model
+/// data.
+pub(crate) const SEARCH_INDEX_KEY: &str = "searchIndex";
+
+/// `@@searchIndex("posts")`
+///
+/// Mirror the records of a model into a search engine index, which defaults
+/// to the table name. Saved records are indexed and deleted ones are removed,
+/// migrations create the index with a mapping generated from the field types,
+/// and the model gets a `search` action backed by the search engine. Nothing
+/// is mirrored unless the app configures a search engine.
+pub(super) fn load_search_index_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("searchIndex", |arguments: Arguments, model: &mut Model| {
+        let index: Option<String> = arguments.get_optional("index")?;
+        model.data.insert(SEARCH_INDEX_KEY.to_owned(), Value::String(index.unwrap_or_else(|| model.table_name.clone())).into());
+        model.after_save.items.push(BoundedItem {
+            path: vec!["searchIndex".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(|_args: Arguments, ctx: Ctx| async move {
+                index_object(ctx.object()).await?;
+                Ok(ctx.value().clone())
+            }),
+        });
+        model.after_delete.items.push(BoundedItem {
+            path: vec!["searchIndex".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(|_args: Arguments, ctx: Ctx| async move {
+                remove_object(ctx.object()).await?;
+                Ok(ctx.value().clone())
+            }),
+        });
+        Ok(())
+    });
+}
+
+/// The search index of a model.
+pub(crate) fn model_search_index(model: &Model) -> Option<&str> {
+    model.data.get(SEARCH_INDEX_KEY)?.as_teon()?.as_str()
+}
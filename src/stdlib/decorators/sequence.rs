@@ -0,0 +1,49 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use teo_runtime::teon;
+
+/// The key under which the sequence policy of a field is stored in its data.
+pub(crate) const SEQUENCE_KEY: &str = "sequence";
+
+/// How the numbers of a sequence field are issued.
+#[derive(Debug, Clone)]
+pub(crate) struct SequencePolicy {
+    /// The fields whose values select the counter, e.g. the tenant.
+    pub(crate) scope: Vec<String>,
+    /// The first number of each counter.
+    pub(crate) start: i64,
+}
+
+/// `@sequence(scope: ["tenantId"], start: 1)`
+///
+/// Number records on create from a counter per distinct value of the `scope`
+/// fields, like invoice numbers per tenant. Numbers increase by one and are
+/// only skipped when a create fails after its number is issued. A number
+/// given in the input is kept. Existing records are numbered with
+/// `teo sequence`.
+pub(super) fn load_sequence_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("sequence", |arguments: Arguments, field: &mut Field| {
+        let scope: Vec<String> = arguments.get_optional("scope")?.unwrap_or_default();
+        let start: i64 = arguments.get_optional("start")?.unwrap_or(1);
+        let scope = Value::Array(scope.into_iter().map(Value::String).collect());
+        field.data.insert(SEQUENCE_KEY.to_owned(), teon!({ "scope": scope, "start": start }).into());
+        Ok(())
+    });
+}
+
+/// The sequence policy of a field.
+pub(crate) fn field_sequence(field: &Field) -> Option<SequencePolicy> {
+    let value: &Value = field.data.get(SEQUENCE_KEY)?.as_teon()?;
+    Some(SequencePolicy {
+        scope: value.get("scope")?.as_array()?.iter().filter_map(|s| s.as_str().map(|s| s.to_owned())).collect(),
+        start: value.get("start")?.as_int64()?,
+    })
+}
+
+/// The sequence fields of a model with their policies.
+pub(crate) fn model_sequence_fields(model: &Model) -> Vec<(&Field, SequencePolicy)> {
+    model.fields.values().filter_map(|field| field_sequence(field).map(|policy| (field, policy))).collect()
+}
@@ -0,0 +1,48 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+use teo_runtime::teon;
+
+/// The key under which the slug policy of a field is stored in its data.
+pub(crate) const SLUG_KEY: &str = "slug";
+
+/// How the slug of a field is generated.
+#[derive(Debug, Clone)]
+pub(crate) struct SlugPolicy {
+    /// The string field the slug is generated from.
+    pub(crate) from: String,
+    /// Whether replaced slugs are kept for redirects.
+    pub(crate) history: bool,
+}
+
+/// `@slug(from: "title", history: true)`
+///
+/// Generate a URL-safe slug from the `from` field when a record is created
+/// without one. A numeric suffix like `-2` is appended when the slug is
+/// taken, so the field should be `@unique`. With `history`, slugs replaced by
+/// updates are recorded, and the `resolveSlug` action maps them to the
+/// current ones for redirects.
+pub(super) fn load_slug_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("slug", |arguments: Arguments, field: &mut Field| {
+        let from: String = arguments.get("from")?;
+        let history: bool = arguments.get_optional("history")?.unwrap_or(false);
+        field.data.insert(SLUG_KEY.to_owned(), teon!({ "from": from, "history": history }).into());
+        Ok(())
+    });
+}
+
+/// The slug policy of a field.
+pub(crate) fn field_slug(field: &Field) -> Option<SlugPolicy> {
+    let value: &Value = field.data.get(SLUG_KEY)?.as_teon()?;
+    Some(SlugPolicy {
+        from: value.get("from")?.as_str()?.to_owned(),
+        history: value.get("history")?.as_bool()?,
+    })
+}
+
+/// The slug fields of a model with their policies.
+pub(crate) fn model_slug_fields(model: &Model) -> Vec<(&Field, SlugPolicy)> {
+    model.fields.values().filter_map(|field| field_slug(field).map(|policy| (field, policy))).collect()
+}
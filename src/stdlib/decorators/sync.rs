@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use chrono::Utc;
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// The key under which the sync fields of a model are recorded in the model
+/// data.
+pub(crate) const SYNC_KEY: &str = "sync";
+
+static LAST_SEQUENCE: AtomicI64 = AtomicI64::new(0);
+
+/// The fields an offline-first client syncs a model with.
+#[derive(Debug, Clone)]
+pub(crate) struct SyncFields {
+    /// An `Int64` field assigned the change sequence number on every save.
+    pub(crate) sequence: String,
+    /// An `Int` field incremented on every save, for conflict detection.
+    pub(crate) version: String,
+    /// A `Bool` field marking deleted records, which are kept as tombstones.
+    pub(crate) deleted: String,
+}
+
+/// `@@sync(sequence: "seq", version: "version", deleted: "deleted")`
+///
+/// Make a model syncable by offline-first clients through the `pull` and
+/// `push` actions. Every save assigns the next change sequence number and
+/// increments the version. Records pushed as deleted are kept with the
+/// deleted flag set, so that pulls return them as tombstones.
+pub(super) fn load_sync_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("sync", |arguments: Arguments, model: &mut Model| {
+        let sequence: String = arguments.get("sequence")?;
+        let version: String = arguments.get("version")?;
+        let deleted: String = arguments.get("deleted")?;
+        for field in [&sequence, &version, &deleted] {
+            if !model.fields.contains_key(field.as_str()) {
+                Err(Error::new(format!("@@sync: field `{}` is not found", field)))?
+            }
+        }
+        model.data.insert(SYNC_KEY.to_owned(), teon!({ "sequence": sequence.clone(), "version": version.clone(), "deleted": deleted }).into());
+        model.before_save.items.push(BoundedItem {
+            path: vec!["sync".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(move |_args: Arguments, ctx: Ctx| {
+                let sequence = sequence.clone();
+                let version = version.clone();
+                async move {
+                    let object = ctx.object();
+                    let next_version = match object.get_value(&version)? {
+                        Value::Int(current) if !object.is_new() => current + 1,
+                        _ => 1,
+                    };
+                    object.set(&version, Value::Int(next_version))?;
+                    object.set(&sequence, Value::Int64(next_sequence()))?;
+                    Ok(ctx.value().clone())
+                }
+            }),
+        });
+        Ok(())
+    });
+}
+
+/// The sync fields of a model.
+pub(crate) fn model_sync(model: &Model) -> Option<SyncFields> {
+    let value = model.data.get(SYNC_KEY)?.as_teon()?;
+    Some(SyncFields {
+        sequence: value.get("sequence")?.as_str()?.to_owned(),
+        version: value.get("version")?.as_str()?.to_owned(),
+        deleted: value.get("deleted")?.as_str()?.to_owned(),
+    })
+}
+
+/// The next change sequence number. Sequence numbers are based on the clock
+/// in microseconds and strictly increase within the process.
+fn next_sequence() -> i64 {
+    let now = Utc::now().timestamp_micros();
+    let mut last = LAST_SEQUENCE.load(Ordering::SeqCst);
+    loop {
+        let next = now.max(last + 1);
+        match LAST_SEQUENCE.compare_exchange(last, next, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return next,
+            Err(actual) => last = actual,
+        }
+    }
+}
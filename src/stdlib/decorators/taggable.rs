@@ -0,0 +1,42 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+
+/// The key under which the tag relation of a model is recorded in the model
+/// data.
+pub(crate) const TAGGABLE_KEY: &str = "taggable";
+
+/// How a model is tagged.
+#[derive(Debug, Clone)]
+pub(crate) struct Taggable {
+    /// The many-to-many relation to the tag model, through a join model.
+    pub(crate) relation: String,
+    /// The unique string field of the tag model holding the tag name.
+    pub(crate) name: String,
+}
+
+/// `@@taggable(relation: "tags", name: "name")`
+///
+/// Tag the records of a model through a many-to-many relation to a tag model
+/// with a unique name field. Writes accept
+/// `tags: { connectOrCreateByName: ["rust", "web"] }`, which connects the
+/// tags with these names and creates the missing ones, and the
+/// `suggestTags` action suggests tags by prefix, most used first.
+pub(super) fn load_taggable_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("taggable", |arguments: Arguments, model: &mut Model| {
+        let relation: String = arguments.get_optional("relation")?.unwrap_or_else(|| "tags".to_owned());
+        let name: String = arguments.get_optional("name")?.unwrap_or_else(|| "name".to_owned());
+        model.data.insert(TAGGABLE_KEY.to_owned(), teon!({ "relation": relation, "name": name }).into());
+        Ok(())
+    });
+}
+
+/// How a model is tagged, if it's taggable.
+pub(crate) fn model_taggable(model: &Model) -> Option<Taggable> {
+    let value = model.data.get(TAGGABLE_KEY)?.as_teon()?;
+    Some(Taggable {
+        relation: value.get("relation")?.as_str()?.to_owned(),
+        name: value.get("name")?.as_str()?.to_owned(),
+    })
+}
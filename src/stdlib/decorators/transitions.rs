@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::Value;
+use crate::server::error::UserError;
+
+/// The code of the error a change which isn't listed fails with.
+pub(crate) const INVALID_TRANSITION: &str = "INVALID_TRANSITION";
+
+/// `@transitions(draft -> published, published -> archived)`
+///
+/// Restrict how an enum field changes on update. Setting the field to its
+/// current value is always allowed, any other change must be listed. `*` on
+/// the left side matches any previous value. Other changes fail with an
+/// `INVALID_TRANSITION` error of the field.
+///
+/// The arguments are rewritten into a list of strings before the schema is
+/// parsed, see `rewrite_transitions`.
+pub(super) fn load_transitions_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("transitions", |arguments: Arguments, field: &mut Field| {
+        let definitions: Vec<String> = arguments.get("transitions")?;
+        let transitions = Arc::new(definitions.iter().map(|d| parse_transition(d)).collect::<Result<Vec<(String, String)>>>()?);
+        let field_name = field.name().to_owned();
+        field.on_save.items.push(BoundedItem {
+            path: vec!["transitions".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(move |_args: Arguments, ctx: Ctx| {
+                let transitions = transitions.clone();
+                let field_name = field_name.clone();
+                async move {
+                    let object = ctx.object();
+                    if object.is_new() {
+                        return Ok(ctx.value().clone());
+                    }
+                    let (Value::String(from), Value::String(to)) = (object.get_previous_value(&field_name)?, ctx.value().clone()) else {
+                        return Ok(ctx.value().clone());
+                    };
+                    if from == to || transitions.iter().any(|(f, t)| (f == "*" || *f == from) && *t == to) {
+                        Ok(ctx.value().clone())
+                    } else {
+                        let message = format!("`{}` cannot change from `{}` to `{}`", field_name, from, to);
+                        Err(UserError::new(INVALID_TRANSITION, message.clone()).with_field_error(field_name.as_str(), INVALID_TRANSITION, message).into())
+                    }
+                }
+            }),
+        });
+        Ok(())
+    });
+}
+
+fn parse_transition(definition: &str) -> Result<(String, String)> {
+    match definition.split_once("->") {
+        Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+            Ok((from.trim().trim_start_matches('.').to_owned(), to.trim().trim_start_matches('.').to_owned()))
+        }
+        _ => Err(Error::new(format!("@transitions: invalid transition `{}`, expect `from -> to`", definition))),
+    }
+}
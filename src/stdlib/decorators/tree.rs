@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use key_path::path;
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::pipeline::item::BoundedItem;
+use teo_runtime::teon;
+use teo_runtime::Value;
+
+/// The key under which the tree fields of a model are recorded in the model
+/// data.
+pub(crate) const TREE_KEY: &str = "tree";
+
+/// The deepest tree walked when checking for cycles.
+const MAX_DEPTH: usize = 10_000;
+
+/// The fields a model is arranged in a tree with.
+#[derive(Debug, Clone)]
+pub(crate) struct TreeFields {
+    /// The optional field referencing the parent record.
+    pub(crate) parent: String,
+    /// The field the parent field references.
+    pub(crate) id: String,
+}
+
+/// `@@tree(parent: "parentId", id: "id")`
+///
+/// Arrange the records of a model in a tree by their parent field. Queries
+/// accept `ancestors`, `descendants` and `subtreeOf` in `where`, each taking
+/// the id of a node. Saves which make a record its own ancestor are
+/// rejected.
+pub(super) fn load_tree_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("tree", |arguments: Arguments, model: &mut Model| {
+        let parent: String = arguments.get("parent")?;
+        let id: String = arguments.get_optional("id")?.unwrap_or_else(|| "id".to_owned());
+        for field in [&parent, &id] {
+            if !model.fields.contains_key(field.as_str()) {
+                Err(Error::new(format!("@@tree: field `{}` is not found", field)))?
+            }
+        }
+        model.data.insert(TREE_KEY.to_owned(), teon!({ "parent": parent.clone(), "id": id.clone() }).into());
+        model.before_save.items.push(BoundedItem {
+            path: vec!["tree".to_owned()],
+            arguments: Arguments::default(),
+            call: Arc::new(move |_args: Arguments, ctx: Ctx| {
+                let parent = parent.clone();
+                let id = id.clone();
+                async move {
+                    let object = ctx.object();
+                    if object.is_new() || object.get_previous_value(&parent)? == object.get_value(&parent)? {
+                        return Ok(ctx.value().clone());
+                    }
+                    let own_id = object.get_value(&id)?;
+                    let mut current = object.get_value(&parent)?;
+                    let mut depth = 0;
+                    while !current.is_null() {
+                        if current == own_id {
+                            Err(Error::new(format!("@@tree: `{}` cannot be moved under its own subtree", parent)))?
+                        }
+                        depth += 1;
+                        if depth > MAX_DEPTH {
+                            Err(Error::new("@@tree: the tree is too deep"))?
+                        }
+                        let found: Vec<Object> = ctx.transaction_ctx().find_many(object.model(), &teon!({ "where": { id.as_str(): current }, "take": 1 }), None, path![]).await?;
+                        current = match found.first() {
+                            Some(ancestor) => ancestor.get_value(&parent)?,
+                            None => Value::Null,
+                        };
+                    }
+                    Ok(ctx.value().clone())
+                }
+            }),
+        });
+        Ok(())
+    });
+}
+
+/// The tree fields of a model.
+pub(crate) fn model_tree(model: &Model) -> Option<TreeFields> {
+    let value = model.data.get(TREE_KEY)?.as_teon()?;
+    Some(TreeFields {
+        parent: value.get("parent")?.as_str()?.to_owned(),
+        id: value.get("id")?.as_str()?.to_owned(),
+    })
+}
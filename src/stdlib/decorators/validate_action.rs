@@ -0,0 +1,24 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::Value;
+
+/// The key under which `@@validateAction` is recorded in the model data.
+pub(crate) const VALIDATE_ACTION_KEY: &str = "validateAction";
+
+/// `@@validateAction`
+///
+/// Add the `validate` action to a model, which runs the input of a `create`
+/// or `update` through parsing and the field pipelines without saving it.
+/// A `validate` handler declared by the model replaces it.
+pub(super) fn load_validate_action_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("validateAction", |_arguments: Arguments, model: &mut Model| {
+        model.data.insert(VALIDATE_ACTION_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+/// Whether a model has the `validate` action.
+pub(crate) fn has_validate_action(model: &Model) -> bool {
+    model.data.get(VALIDATE_ACTION_KEY).and_then(|value| value.as_teon()).and_then(Value::as_bool).unwrap_or(false)
+}
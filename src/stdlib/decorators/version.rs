@@ -0,0 +1,32 @@
+use teo_parser::r#type::Type;
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::field::Field;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::traits::named::Named;
+use teo_runtime::Value;
+
+/// The key under which a field records that it's the version of its record.
+pub(crate) const VERSION_KEY: &str = "version";
+
+/// `@version`
+///
+/// Mark an `Int` or `Int64` field as the version of the record for
+/// optimistic locking. Connectors with conditional writes, like DynamoDB,
+/// increase it on every update and reject the update if the stored record
+/// has another version than the one which was read.
+pub(super) fn load_version_decorator(namespace: &mut Namespace) {
+    namespace.define_model_field_decorator("version", |_arguments: Arguments, field: &mut Field| {
+        if !matches!(field.r#type(), Type::Int | Type::Int64) {
+            Err(Error::new(format!("@version: `{}` must be an Int or Int64 field", field.name())))?
+        }
+        field.data.insert(VERSION_KEY.to_owned(), Value::Bool(true).into());
+        Ok(())
+    });
+}
+
+/// Whether a field is the version of its record.
+pub(crate) fn is_version_field(field: &Field) -> bool {
+    field.data.get(VERSION_KEY).and_then(|v| v.as_teon()).and_then(|v| v.as_bool()).unwrap_or(false)
+}
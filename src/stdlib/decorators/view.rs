@@ -0,0 +1,40 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::model::Model;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::teon;
+
+/// The key under which the query of a view model is recorded in the model
+/// data.
+pub(crate) const VIEW_KEY: &str = "view";
+
+/// The actions which write records, rejected for view models.
+pub(crate) const WRITE_ACTIONS: [&str; 9] = ["create", "update", "upsert", "delete", "createMany", "updateMany", "deleteMany", "copy", "copyMany"];
+
+/// `@@view("SELECT id, title FROM Post WHERE published")`
+///
+/// Back a model with a SQL view instead of a table. Migrations create or
+/// replace the view with the query, and the model rejects writes. With
+/// `materialized: true` a PostgreSQL materialized view is created, which is
+/// refreshed with the `refresh` action or `teo refresh`.
+pub(super) fn load_view_decorator(namespace: &mut Namespace) {
+    namespace.define_model_decorator("view", |arguments: Arguments, model: &mut Model| {
+        let query: String = arguments.get("query")?;
+        if query.trim().is_empty() {
+            Err(Error::new("@@view: the query is empty"))?
+        }
+        let materialized: Option<bool> = arguments.get_optional("materialized")?;
+        model.data.insert(VIEW_KEY.to_owned(), teon!({ "query": query, "materialized": materialized.unwrap_or(false) }).into());
+        Ok(())
+    });
+}
+
+/// The query of a view model.
+pub(crate) fn model_view(model: &Model) -> Option<&str> {
+    model.data.get(VIEW_KEY)?.as_teon()?.get("query")?.as_str()
+}
+
+/// Whether a model is backed by a materialized view.
+pub(crate) fn is_materialized_view(model: &Model) -> bool {
+    model.data.get(VIEW_KEY).and_then(|v| v.as_teon()).and_then(|v| v.get("materialized")).and_then(|m| m.as_bool()).unwrap_or(false)
+}
@@ -0,0 +1,11 @@
+pub(crate) mod decorators;
+pub(crate) mod pipeline_items;
+
+use teo_runtime::namespace::Namespace;
+
+/// Load the decorators and pipeline items provided by this crate. This is
+/// called after the runtime's standard library is loaded.
+pub(crate) fn load(namespace: &mut Namespace) {
+    decorators::load_decorators(namespace);
+    pipeline_items::load_pipeline_items(namespace);
+}
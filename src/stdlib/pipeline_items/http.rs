@@ -0,0 +1,97 @@
+use std::time::Duration;
+use regex::{Captures, Regex};
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::Value;
+
+const DEFAULT_TIMEOUT_MS: i32 = 10_000;
+
+/// `$httpFetch(url, method?, body?, timeout?, retry?, extract?)`
+///
+/// Call an external HTTP API with the current value and replace the value
+/// with the JSON response. `{{value}}` and `{{value.some.path}}` placeholders
+/// in `url` and `body` are substituted with the current value. Requests
+/// failing with network errors or 5xx responses are retried `retry` times with
+/// exponential backoff. `extract` picks a dotted path out of the response.
+pub(super) fn load_http_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("httpFetch", |args: Arguments, ctx: Ctx| async move {
+        let url: String = args.get("url")?;
+        let method: Option<String> = args.get_optional("method")?;
+        let body: Option<String> = args.get_optional("body")?;
+        let timeout: Option<i32> = args.get_optional("timeout")?;
+        let retry: Option<i32> = args.get_optional("retry")?;
+        let extract: Option<String> = args.get_optional("extract")?;
+        let value = JsonValue::try_from(ctx.value())?;
+        let result = http_fetch(&value, &url, method.as_deref(), body.as_deref(), timeout, retry, extract.as_deref()).await?;
+        Ok(Value::from(result))
+    });
+}
+
+/// Fetch `url` with `value` substituted into it and `body`, and pick
+/// `extract` out of the response.
+pub(crate) async fn http_fetch(value: &JsonValue, url: &str, method: Option<&str>, body: Option<&str>, timeout: Option<i32>, retry: Option<i32>, extract: Option<&str>) -> Result<JsonValue> {
+    let url = render_template(url, value, false);
+    let body = body.map(|b| render_template(b, value, true));
+    let response = fetch(
+        method.unwrap_or(if body.is_some() { "POST" } else { "GET" }),
+        &url,
+        body,
+        Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT_MS) as u64),
+        retry.unwrap_or(0).max(0) as u32,
+    ).await?;
+    match extract {
+        Some(path) => json_at_path(&response, path).cloned().ok_or_else(|| Error::new(format!("httpFetch: response doesn't contain `{}`", path))),
+        None => Ok(response),
+    }
+}
+
+async fn fetch(method: &str, url: &str, body: Option<String>, timeout: Duration, retry: u32) -> Result<JsonValue> {
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| Error::new(format!("httpFetch: invalid method `{}`", method)))?;
+    let client = reqwest::Client::builder().timeout(timeout).build().map_err(|e| Error::new(format!("httpFetch: {}", e)))?;
+    let mut attempt = 0;
+    loop {
+        let mut request = client.request(method.clone(), url);
+        if let Some(body) = &body {
+            request = request.header("Content-Type", "application/json").body(body.clone());
+        }
+        let error = match request.send().await {
+            Ok(response) if response.status().is_server_error() => format!("server responds with {}", response.status()),
+            Ok(response) if !response.status().is_success() => Err(Error::new(format!("httpFetch: {} responds with {}", url, response.status())))?,
+            Ok(response) => return response.json().await.map_err(|e| Error::new(format!("httpFetch: invalid json response: {}", e))),
+            Err(err) => format!("{}", err),
+        };
+        if attempt >= retry {
+            Err(Error::new(format!("httpFetch: request to {} failed: {}", url, error)))?
+        }
+        tokio::time::sleep(Duration::from_millis(200 * 2u64.pow(attempt))).await;
+        attempt += 1;
+    }
+}
+
+/// Substitute `{{value}}` and `{{value.path}}` placeholders. In JSON mode the
+/// values are encoded as JSON, otherwise strings are inserted percent encoded.
+fn render_template(template: &str, value: &JsonValue, json: bool) -> String {
+    let regex = Regex::new(r"\{\{\s*value((?:\.[A-Za-z0-9_]+)*)\s*\}\}").unwrap();
+    regex.replace_all(template, |captures: &Captures| {
+        let path = captures.get(1).map(|m| m.as_str().trim_start_matches('.')).unwrap_or("");
+        let found = if path.is_empty() { Some(value) } else { json_at_path(value, path) };
+        match (found, json) {
+            (None, true) => "null".to_owned(),
+            (None, false) => "".to_owned(),
+            (Some(found), true) => found.to_string(),
+            (Some(JsonValue::String(s)), false) => url::form_urlencoded::byte_serialize(s.as_bytes()).collect(),
+            (Some(found), false) => found.to_string(),
+        }
+    }).to_string()
+}
+
+fn json_at_path<'a>(value: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    path.split('.').filter(|s| !s.is_empty()).try_fold(value, |current, key| match current {
+        JsonValue::Object(map) => map.get(key),
+        JsonValue::Array(array) => key.parse::<usize>().ok().and_then(|index| array.get(index)),
+        _ => None,
+    })
+}
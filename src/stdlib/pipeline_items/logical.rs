@@ -0,0 +1,73 @@
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::{Ctx, Pipeline};
+use teo_runtime::Value;
+
+/// Conditional combinators. A condition is met when its pipeline runs
+/// without error and doesn't return `false`. Any error of a condition, a
+/// failing validator or not, means the condition isn't met.
+///
+/// * `$if(cond, then?, else?)` runs `then` or `else` depending on `cond`
+/// * `$allOf([...])` fails with the error of the first condition not met
+/// * `$anyOf([...])` passes when any condition is met
+/// * `$not(pipeline)` passes when the condition isn't met
+///
+/// All of them keep the input value unless a branch of `$if` transforms it.
+pub(super) fn load_logical_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("if", |args: Arguments, ctx: Ctx| async move {
+        let cond: Pipeline = args.get("cond")?;
+        let then: Option<Pipeline> = args.get_optional("then")?;
+        let otherwise: Option<Pipeline> = args.get_optional("else")?;
+        let branch = match condition(&ctx, &cond).await {
+            Condition::Met => then,
+            Condition::NotMet(_) => otherwise,
+        };
+        match branch {
+            Some(pipeline) => ctx.run_pipeline(&pipeline).await,
+            None => Ok(ctx.value().clone()),
+        }
+    });
+    namespace.define_pipeline_item("allOf", |args: Arguments, ctx: Ctx| async move {
+        let pipelines: Vec<Pipeline> = args.get("pipelines")?;
+        for pipeline in &pipelines {
+            if let Condition::NotMet(error) = condition(&ctx, pipeline).await {
+                return Err(error.unwrap_or_else(|| Error::invalid_request_message("a condition of `allOf` is not met")));
+            }
+        }
+        Ok(ctx.value().clone())
+    });
+    namespace.define_pipeline_item("anyOf", |args: Arguments, ctx: Ctx| async move {
+        let pipelines: Vec<Pipeline> = args.get("pipelines")?;
+        let mut messages = vec![];
+        for pipeline in &pipelines {
+            match condition(&ctx, pipeline).await {
+                Condition::Met => return Ok(ctx.value().clone()),
+                Condition::NotMet(error) => messages.push(error.map_or("the value is false".to_owned(), |e| e.message)),
+            }
+        }
+        Err(Error::invalid_request_message(format!("none of the conditions of `anyOf` is met: {}", messages.join("; "))))
+    });
+    namespace.define_pipeline_item("not", |args: Arguments, ctx: Ctx| async move {
+        let pipeline: Pipeline = args.get("pipeline")?;
+        match condition(&ctx, &pipeline).await {
+            Condition::Met => Err(Error::invalid_request_message("the condition of `not` is met")),
+            Condition::NotMet(_) => Ok(ctx.value().clone()),
+        }
+    });
+}
+
+/// The outcome of running a condition.
+enum Condition {
+    Met,
+    /// The condition returned `false`, or failed with the error.
+    NotMet(Option<Error>),
+}
+
+async fn condition(ctx: &Ctx, pipeline: &Pipeline) -> Condition {
+    match ctx.run_pipeline(pipeline).await {
+        Ok(Value::Bool(false)) => Condition::NotMet(None),
+        Ok(_) => Condition::Met,
+        Err(error) => Condition::NotMet(Some(error)),
+    }
+}
@@ -0,0 +1,19 @@
+pub(crate) mod http;
+pub(crate) mod logical;
+pub(crate) mod money;
+pub(crate) mod object;
+pub(crate) mod request;
+pub(crate) mod signed_url;
+pub(crate) mod text;
+
+use teo_runtime::namespace::Namespace;
+
+pub(super) fn load_pipeline_items(namespace: &mut Namespace) {
+    http::load_http_items(namespace);
+    logical::load_logical_items(namespace);
+    money::load_money_items(namespace);
+    object::load_object_items(namespace);
+    request::load_request_items(namespace);
+    signed_url::load_signed_url_items(namespace);
+    text::load_text_items(namespace);
+}
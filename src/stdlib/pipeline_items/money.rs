@@ -0,0 +1,27 @@
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline;
+use teo_runtime::Value;
+use crate::app::money::Money;
+
+/// Work with `@money` values in pipelines, e.g. in `@@onOutput`.
+///
+/// * `$formatMoney` replaces a money value with its display string, like
+///   `"$1,234.50"` or `"1,234.50 CHF"`
+/// * `$moneyAmount` replaces a money value with its decimal amount string
+pub(super) fn load_money_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("formatMoney", |_args: Arguments, ctx: pipeline::Ctx| async move {
+        let money = money_value(ctx.value()).map_err(|e| Error::new(format!("formatMoney: {}", e.message)))?;
+        Ok(Value::String(money.format()))
+    });
+    namespace.define_pipeline_item("moneyAmount", |_args: Arguments, ctx: pipeline::Ctx| async move {
+        let money = money_value(ctx.value()).map_err(|e| Error::new(format!("moneyAmount: {}", e.message)))?;
+        Ok(Value::String(money.amount()))
+    });
+}
+
+fn money_value(value: &Value) -> Result<Money> {
+    Money::from_json(&JsonValue::try_from(value)?)
+}
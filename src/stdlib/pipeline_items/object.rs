@@ -0,0 +1,29 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::Value;
+
+/// Access the object being validated from a field pipeline.
+///
+/// * `$self` returns the object, so `$self.get("startDate")` reads a sibling
+///   field with the value being written
+/// * `$previous("status")` returns the value of a field before this update, or
+///   `null` when the object is being created
+pub(super) fn load_object_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("self", |_args: Arguments, ctx: Ctx| async move {
+        Ok(Value::ModelObject(ctx.object().clone()))
+    });
+    namespace.define_pipeline_item("previous", |args: Arguments, ctx: Ctx| async move {
+        let key: String = args.get("key")?;
+        let object = ctx.object();
+        if !object.model().fields().iter().any(|f| f.name() == key.as_str()) {
+            Err(Error::new(format!("previous: model `{}` doesn't have field `{}`", object.model().name(), key)))?
+        }
+        if object.is_new() {
+            Ok(Value::Null)
+        } else {
+            object.get_previous_value(&key)
+        }
+    });
+}
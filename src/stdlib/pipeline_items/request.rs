@@ -0,0 +1,32 @@
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::Value;
+use crate::server::client_ip::CLIENT_IP_HEADER;
+use crate::server::i18n::accept_languages;
+
+/// Read the request a pipeline runs for, so that validators and defaults can
+/// depend on it, e.g. `@default($requestIp)` for audit fields. Outside of a
+/// request, like in seeding or programs, these return `null`.
+///
+/// * `$requestIp` returns the client IP address
+/// * `$requestHeader("User-Agent")` returns a request header
+/// * `$requestLocale` returns the preferred language of `Accept-Language`,
+///   like `"en-us"`
+pub(super) fn load_request_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("requestIp", |_args: Arguments, ctx: Ctx| async move {
+        Ok(header(&ctx, CLIENT_IP_HEADER).filter(|ip| !ip.is_empty()).map_or(Value::Null, Value::String))
+    });
+    namespace.define_pipeline_item("requestHeader", |args: Arguments, ctx: Ctx| async move {
+        let name: String = args.get("name")?;
+        Ok(header(&ctx, &name.to_lowercase()).map_or(Value::Null, Value::String))
+    });
+    namespace.define_pipeline_item("requestLocale", |_args: Arguments, ctx: Ctx| async move {
+        let locale = header(&ctx, "accept-language").and_then(|header| accept_languages(&header).into_iter().next());
+        Ok(locale.map_or(Value::Null, Value::String))
+    });
+}
+
+fn header(ctx: &Ctx, name: &str) -> Option<String> {
+    ctx.request().and_then(|request| request.headers().get(name).map(|value| value.to_owned()))
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+use serde_json::json;
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline;
+use teo_runtime::Value;
+use crate::app::ctx::Ctx;
+use crate::server::signed_url::sign_url;
+
+/// `$signedUrl(expiresIn)`
+///
+/// Replace the value with a URL giving access to it without authentication
+/// for `expiresIn` seconds, e.g. for email and share links. An object gets a
+/// signed `findUnique` URL of itself, a string is taken as the path of a
+/// file or handler to sign. The server path prefix is prepended.
+pub(super) fn load_signed_url_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("signedUrl", |args: Arguments, ctx: pipeline::Ctx| async move {
+        let expires_in: i32 = args.get("expiresIn")?;
+        let expires_in = Duration::from_secs(expires_in.max(0) as u64);
+        let prefix = Ctx::main_namespace().server.as_ref().and_then(|s| s.path_prefix.clone()).unwrap_or_default();
+        let prefix = prefix.trim_end_matches('/');
+        let url = match ctx.value() {
+            Value::ModelObject(object) => {
+                let path = format!("{}/{}/findUnique", prefix, object.model().path().join("/"));
+                let identifier = serde_json::Value::try_from(&object.identifier())?;
+                sign_url(&path, Some(&json!({ "where": identifier })), expires_in)?
+            }
+            Value::String(path) => {
+                let path = if path.starts_with('/') { path.clone() } else { format!("/{}", path) };
+                sign_url(&format!("{}{}", prefix, path), None, expires_in)?
+            }
+            _ => Err(Error::new("signedUrl: expect the value to be an object or a path"))?,
+        };
+        Ok(Value::String(url))
+    });
+}
@@ -0,0 +1,55 @@
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::namespace::Namespace;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::Value;
+
+/// Normalize strings for comparison, e.g. in `@dedupe`.
+///
+/// * `$phonetic` replaces each word of a string with its Soundex code, so
+///   `"Jon Smyth"` and `"John Smith"` both become `"J500 S530"`
+pub(super) fn load_text_items(namespace: &mut Namespace) {
+    namespace.define_pipeline_item("phonetic", |_args: Arguments, ctx: Ctx| async move {
+        let Some(string) = ctx.value().as_str() else {
+            Err(Error::new("phonetic: value is not a string"))?
+        };
+        Ok(Value::String(string.split_whitespace().filter_map(soundex).collect::<Vec<String>>().join(" ")))
+    });
+}
+
+/// The Soundex code of a word, `None` if it has no ASCII letters.
+pub(crate) fn soundex(word: &str) -> Option<String> {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).map(|c| c.to_ascii_uppercase()).collect();
+    let first = *letters.first()?;
+    let mut code = String::from(first);
+    let mut last = digit(first);
+    for c in letters.into_iter().skip(1) {
+        let current = digit(c);
+        // `H` and `W` don't separate letters with the same code
+        if current != '0' && current != last {
+            code.push(current);
+            if code.len() == 4 {
+                break;
+            }
+        }
+        if c != 'H' && c != 'W' {
+            last = current;
+        }
+    }
+    while code.len() < 4 {
+        code.push('0');
+    }
+    Some(code)
+}
+
+fn digit(c: char) -> char {
+    match c {
+        'B' | 'F' | 'P' | 'V' => '1',
+        'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => '2',
+        'D' | 'T' => '3',
+        'L' => '4',
+        'M' | 'N' => '5',
+        'R' => '6',
+        _ => '0',
+    }
+}
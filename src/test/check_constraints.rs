@@ -0,0 +1,38 @@
+use teo_runtime::database::database::Database;
+use crate::migrate::constraints::{constraint_name, expression};
+use crate::stdlib::decorators::constraints::Constraint;
+
+#[test]
+fn rules_compile_to_check_expressions() {
+    let pg = Database::PostgreSQL;
+    assert_eq!(expression("code", &Constraint::MinLength(2), &pg).unwrap(), r#"char_length("code") >= 2"#);
+    assert_eq!(expression("code", &Constraint::MaxLength(255), &pg).unwrap(), r#"char_length("code") <= 255"#);
+    assert_eq!(expression("price", &Constraint::Min(0.0), &pg).unwrap(), r#""price" >= 0"#);
+    assert_eq!(expression("price", &Constraint::Max(2.5), &Database::MySQL).unwrap(), "`price` <= 2.5");
+}
+
+#[test]
+fn regex_is_only_checked_by_postgres() {
+    let pg = Database::PostgreSQL;
+    assert_eq!(expression("code", &Constraint::Regex("^[a-z']+$".to_owned()), &pg).unwrap(), r#""code" ~ '^[a-z'']+$'"#);
+    assert_eq!(expression("code", &Constraint::Regex(r"^\d+$".to_owned()), &pg).unwrap(), r#""code" ~ E'^\\d+$'"#);
+    assert_eq!(expression("code", &Constraint::Regex("^[a-z]+$".to_owned()), &Database::MySQL), None);
+}
+
+#[test]
+fn names_change_with_their_expressions() {
+    let name = constraint_name("Product", "code", "minLength", "char_length(\"code\") >= 2");
+    assert!(name.starts_with("teo_ck_Product_code_minlength_"), "{}", name);
+    assert_eq!(name.len(), "teo_ck_Product_code_minlength_".len() + 8);
+    assert_eq!(name, constraint_name("Product", "code", "minLength", "char_length(\"code\") >= 2"));
+    assert_ne!(name, constraint_name("Product", "code", "minLength", "char_length(\"code\") >= 3"));
+}
+
+#[test]
+fn long_names_keep_their_hashes() {
+    let table = "é".repeat(40);
+    let name = constraint_name(&table, "code", "maxLength", "expression");
+    let short = constraint_name("t", "code", "maxLength", "expression");
+    assert!(name.len() <= 63, "{}", name);
+    assert_eq!(name[name.len() - 9..], short[short.len() - 9..]);
+}
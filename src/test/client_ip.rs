@@ -0,0 +1,44 @@
+use std::net::IpAddr;
+use crate::server::client_ip::Cidr;
+
+fn contains(cidr: &str, address: &str) -> bool {
+    Cidr::parse(cidr).unwrap().contains(&address.parse::<IpAddr>().unwrap())
+}
+
+#[test]
+fn ipv4_ranges_match_their_prefix() {
+    assert!(contains("10.0.0.0/8", "10.255.1.2"));
+    assert!(!contains("10.0.0.0/8", "11.0.0.1"));
+    assert!(contains("192.168.1.0/24", "192.168.1.200"));
+    assert!(!contains("192.168.1.0/24", "192.168.2.1"));
+    assert!(contains("0.0.0.0/0", "8.8.8.8"));
+}
+
+#[test]
+fn addresses_are_ranges_of_one() {
+    assert!(contains(" 203.0.113.7 ", "203.0.113.7"));
+    assert!(!contains("203.0.113.7", "203.0.113.8"));
+    assert!(contains("2001:db8::1", "2001:db8::1"));
+}
+
+#[test]
+fn ipv6_ranges_match_their_prefix() {
+    assert!(contains("2001:db8::/32", "2001:db8:ffff::1"));
+    assert!(!contains("2001:db8::/32", "2001:db9::1"));
+    assert!(contains("::/0", "fe80::1"));
+}
+
+#[test]
+fn mapped_addresses_match_both_families() {
+    assert!(contains("::ffff:10.0.0.0/104", "10.1.2.3"));
+    assert!(contains("10.0.0.0/8", "::ffff:10.1.2.3"));
+    assert!(!contains("10.0.0.0/8", "2001:db8::1"));
+}
+
+#[test]
+fn invalid_ranges_are_rejected() {
+    assert_eq!(Cidr::parse("10.0.0/8").unwrap_err().message, "invalid proxy address `10.0.0/8`");
+    assert_eq!(Cidr::parse("10.0.0.0/33").unwrap_err().message, "invalid prefix length in `10.0.0.0/33`");
+    assert_eq!(Cidr::parse("::/129").unwrap_err().message, "invalid prefix length in `::/129`");
+    assert_eq!(Cidr::parse("10.0.0.0/x").unwrap_err().message, "invalid prefix length in `10.0.0.0/x`");
+}
@@ -0,0 +1,52 @@
+use teo_runtime::database::database::Database;
+use teo_runtime::teon;
+use teo_runtime::Value;
+use crate::app::db_function::db_function_statement;
+use crate::utils::db_functions::extract_from_source;
+
+#[test]
+fn clauses_are_taken_out_of_handler_declarations() {
+    let source = "declare handler stats(StatsInput): Stat[] from dbFunction(\"analytics.stats\")\n\nnamespace admin {\n  declare handler top(TopInput): Stat from dbFunction(\"top_stat\")\n}\n";
+    let (rewritten, handlers) = extract_from_source(source).unwrap().unwrap();
+    assert_eq!(rewritten, "declare handler stats(StatsInput): Stat[]\n\nnamespace admin {\n  declare handler top(TopInput): Stat\n}\n");
+    assert_eq!(handlers.len(), 2);
+    assert_eq!((handlers[0].name.as_str(), handlers[0].function.as_str(), handlers[0].many), ("stats", "analytics.stats", true));
+    assert!(handlers[0].namespace_path.is_empty());
+    assert_eq!((handlers[1].name.as_str(), handlers[1].function.as_str(), handlers[1].many), ("top", "top_stat", false));
+    assert_eq!(handlers[1].namespace_path, vec!["admin"]);
+}
+
+#[test]
+fn sources_without_clauses_are_untouched() {
+    assert!(extract_from_source("declare handler stats(StatsInput): Stat[]\n").unwrap().is_none());
+}
+
+#[test]
+fn invalid_declarations_are_rejected() {
+    let error = extract_from_source("declare handler stats(StatsInput): Stat from dbFunction(\"stats; DROP TABLE users\")\n").unwrap_err();
+    assert_eq!(error.message, "handler `stats`: invalid database function name `stats; DROP TABLE users`");
+    let error = extract_from_source("declare handler group admin {\n  declare handler stats(StatsInput): Stat from dbFunction(\"stats\")\n}\n").unwrap_err();
+    assert_eq!(error.message, "handler `stats`: `from dbFunction` is not supported in handler groups");
+}
+
+#[test]
+fn postgres_passes_arguments_by_name() {
+    let input = teon!({ "from": "2024-01-01", "limit": 10, "label": "it's" });
+    let statement = db_function_statement(Some(&Database::PostgreSQL), "analytics.stats", &input).unwrap();
+    assert_eq!(statement, "SELECT * FROM analytics.stats(\"from\" => '2024-01-01', \"limit\" => 10, \"label\" => 'it''s')");
+}
+
+#[test]
+fn mysql_calls_procedures_with_arguments_in_order() {
+    let input = teon!({ "from": "2024-01-01", "limit": 10 });
+    assert_eq!(db_function_statement(Some(&Database::MySQL), "stats", &input).unwrap(), "CALL stats('2024-01-01', 10)");
+    assert_eq!(db_function_statement(Some(&Database::MySQL), "stats", &Value::Null).unwrap(), "CALL stats()");
+}
+
+#[test]
+fn other_databases_and_inputs_are_rejected() {
+    let error = db_function_statement(Some(&Database::SQLite), "stats", &Value::Null).unwrap_err();
+    assert_eq!(error.message, "database function handlers are only supported by PostgreSQL and MySQL");
+    let error = db_function_statement(Some(&Database::PostgreSQL), "stats", &teon!([1])).unwrap_err();
+    assert_eq!(error.message, "expect the input of a database function handler to be an object");
+}
@@ -0,0 +1,42 @@
+use crate::utils::delimiters::{check_delimiters, recover_delimiters, Recovery};
+
+fn recover(source: &str) -> String {
+    let errors = check_delimiters(source);
+    assert!(!errors.is_empty());
+    let recovered = recover_delimiters(source, &errors);
+    assert_eq!(check_delimiters(&recovered), vec![]);
+    recovered
+}
+
+#[test]
+fn missing_brace_is_closed_before_the_next_declaration() {
+    let source = "model A {\n  id: Int\n\nmodel B {\n  id: Int\n}\n";
+    let errors = check_delimiters(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].line, errors[0].column), (1, 9));
+    assert_eq!(errors[0].expected, vec!['}']);
+    assert_eq!(errors[0].recoveries, vec![Recovery::Insert { line: 4, column: 1, text: "} ".to_owned() }]);
+    assert_eq!(recover(source), "model A {\n  id: Int\n\n} model B {\n  id: Int\n}\n");
+}
+
+#[test]
+fn missing_brace_at_the_end_is_closed_on_a_new_line() {
+    assert_eq!(recover("model A {\n  id: Int // the id\n"), "model A {\n  id: Int // the id\n}\n");
+}
+
+#[test]
+fn missing_parenthesis_is_closed_at_the_end_of_its_line() {
+    let source = "model A {\n  @map(\"a\" // the column\n  id: Int\n}\n";
+    let errors = check_delimiters(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].expected, vec![')']);
+    assert_eq!(recover(source), "model A {\n  @map(\"a\") // the column\n  id: Int\n}\n");
+}
+
+#[test]
+fn stray_closer_is_removed() {
+    let source = "model A {\n  id: Int\n}\n}\n";
+    let errors = check_delimiters(source);
+    assert_eq!(errors[0].message, "unexpected `}`");
+    assert_eq!(recover(source), "model A {\n  id: Int\n}\n \n");
+}
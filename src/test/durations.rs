@@ -0,0 +1,22 @@
+use crate::stdlib::decorators::expires::parse_duration;
+
+#[test]
+fn numbers_are_seconds() {
+    assert_eq!(parse_duration("90").unwrap(), 90);
+    assert_eq!(parse_duration(" 90s ").unwrap(), 90);
+}
+
+#[test]
+fn units_multiply_the_number() {
+    assert_eq!(parse_duration("30m").unwrap(), 30 * 60);
+    assert_eq!(parse_duration("12h").unwrap(), 12 * 3600);
+    assert_eq!(parse_duration("7d").unwrap(), 7 * 86400);
+    assert_eq!(parse_duration("2w").unwrap(), 14 * 86400);
+    assert_eq!(parse_duration("1y").unwrap(), 365 * 86400);
+}
+
+#[test]
+fn invalid_durations_are_rejected() {
+    assert_eq!(parse_duration("d").unwrap_err().message, "invalid duration `d`");
+    assert_eq!(parse_duration("3 days").unwrap_err().message, "invalid duration unit ` days`, expect s, m, h, d, w or y");
+}
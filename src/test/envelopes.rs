@@ -0,0 +1,47 @@
+use serde_json::json;
+use crate::server::envelope::{Envelope, Envelopes};
+
+#[test]
+fn the_most_specific_override_wins() {
+    let mut envelopes = Envelopes::default();
+    assert!(envelopes.is_default());
+    envelopes.set_default(Envelope::default().data_key("result"));
+    envelopes.insert("findMany", Envelope::bare());
+    envelopes.insert("User.findMany", Envelope::default().meta_key("pagination"));
+    assert!(!envelopes.is_default());
+    assert_eq!(envelopes.envelope_for("User", "findMany"), &Envelope::default().meta_key("pagination"));
+    assert_eq!(envelopes.envelope_for("Post", "findMany"), &Envelope::bare());
+    assert_eq!(envelopes.envelope_for("User", "create"), &Envelope::default().data_key("result"));
+    assert_eq!(envelopes.envelope_for("", ""), &Envelope::default().data_key("result"));
+}
+
+#[test]
+fn overrides_with_the_standard_shape_are_default() {
+    let mut envelopes = Envelopes::default();
+    envelopes.insert("User.findMany", Envelope::default());
+    assert!(envelopes.is_default());
+}
+
+#[test]
+fn envelopes_are_described_for_the_clients() {
+    let mut envelopes = Envelopes::default();
+    envelopes.insert("User.findMany", Envelope::bare().error_key("errors"));
+    assert_eq!(envelopes.to_json(), json!({
+        "default": { "data": "data", "meta": "meta", "error": "error" },
+        "overrides": { "User.findMany": { "data": null, "meta": "meta", "error": "errors" } },
+    }));
+}
+
+#[test]
+fn responses_are_opened() {
+    let envelope = Envelope::default().data_key("result").meta_key("pagination").error_key("errors");
+    let (data, meta, error) = envelope.open(json!({ "result": [1], "pagination": { "count": 1 } }), None, false);
+    assert_eq!((data, meta, error), (json!([1]), Some(json!({ "count": 1 })), None));
+    let (data, meta, error) = envelope.open(json!({ "errors": { "message": "not found" } }), None, true);
+    assert_eq!((data, meta, error), (json!(null), None, Some(json!({ "message": "not found" }))));
+    // bare responses are the data, with the meta of the header
+    let (data, meta, error) = Envelope::bare().open(json!({ "id": 1 }), Some(json!({ "count": 1 })), false);
+    assert_eq!((data, meta, error), (json!({ "id": 1 }), Some(json!({ "count": 1 })), None));
+    let (data, meta, _) = Envelope::bare().open(json!([1, 2]), None, false);
+    assert_eq!((data, meta), (json!([1, 2]), None));
+}
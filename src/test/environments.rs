@@ -0,0 +1,92 @@
+use std::fs;
+use crate::utils::environments::{apply_environment_overlay, apply_environment_overlays};
+
+const SCHEMA: &str = r#"connector {
+  provider .sqlite
+  url "sqlite::memory:"
+}
+
+server {
+  bind ("0.0.0.0", 5050)
+}
+
+environment "production" {
+  connector {
+    // the database of production
+    provider: .postgres
+    url "postgres://db/{app}"
+  }
+  server {
+    pathPrefix "/api"
+  }
+}
+
+environment "staging" {
+  server {
+    bind ("0.0.0.0", 80)
+  }
+}
+"#;
+
+/// The blank lines the environment blocks of `SCHEMA` are replaced with.
+fn removed_blocks() -> String {
+    format!("{}\n\n{}\n", "\n".repeat(9), "\n".repeat(4))
+}
+
+#[test]
+fn selected_environment_overrides_and_adds_entries() {
+    let expected = format!(r#"connector {{
+  provider .postgres
+  url "postgres://db/{{app}}"
+}}
+
+server {{
+  bind ("0.0.0.0", 5050)
+  pathPrefix "/api"
+}}
+
+{}"#, removed_blocks());
+    assert_eq!(apply_environment_overlay(SCHEMA, Some("production")).unwrap(), expected);
+}
+
+#[test]
+fn other_environments_are_removed() {
+    let expected = format!(r#"connector {{
+  provider .sqlite
+  url "sqlite::memory:"
+}}
+
+server {{
+  bind ("0.0.0.0", 80)
+}}
+
+{}"#, removed_blocks());
+    assert_eq!(apply_environment_overlay(SCHEMA, Some("staging")).unwrap(), expected);
+}
+
+#[test]
+fn without_a_matching_environment_lines_are_kept() {
+    for environment in [None, Some("test")] {
+        let result = apply_environment_overlay(SCHEMA, environment).unwrap();
+        assert!(!result.contains("environment"), "{}", result);
+        assert!(result.contains(r#"bind ("0.0.0.0", 5050)"#), "{}", result);
+        assert_eq!(result.lines().count(), SCHEMA.lines().count());
+    }
+}
+
+#[test]
+fn sources_without_environments_are_not_rewritten() {
+    assert!(apply_environment_overlay("server {\n  bind (\"0.0.0.0\", 5050)\n}\n", Some("production")).is_none());
+}
+
+#[test]
+fn only_files_with_environments_are_returned() {
+    let dir = std::env::temp_dir().join(format!("teo-environments-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(dir.join("models")).unwrap();
+    fs::write(dir.join("schema.teo"), SCHEMA).unwrap();
+    fs::write(dir.join("models/user.teo"), "model User {\n  @id\n  id: Int\n}\n").unwrap();
+    let result = apply_environment_overlays(&dir, Some("production"));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(result.len(), 1);
+    assert!(result[dir.join("schema.teo").to_str().unwrap()].contains(r#"pathPrefix "/api""#));
+}
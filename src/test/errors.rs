@@ -0,0 +1,117 @@
+use std::fs;
+use indexmap::IndexMap;
+use serde_json::json;
+use teo_result::Error;
+use crate::server::error::{error_code, field_errors, field_errors_json, ErrorFormat, FieldError, UserError};
+use crate::server::i18n::{accept_languages, MessageCatalogs};
+
+fn user_error() -> Error {
+    UserError::new("SIGN_UP_INVALID", "the sign up is invalid")
+        .with_field_error("password", "TOO_SHORT", "password is too short")
+        .with_field_error("password", "NO_DIGIT", "password has no digit")
+        .with_field_error("posts.0.title", "REQUIRED", "title is required")
+        .into()
+}
+
+fn unlocalized(_code: Option<&str>, message: &str) -> String {
+    message.to_owned()
+}
+
+#[test]
+fn user_error_code_is_not_a_field_error() {
+    let error: Error = UserError::new("SESSION_EXPIRED", "session is expired").with_status(401).into();
+    assert_eq!(error.code, 401);
+    assert_eq!(error_code(&error), Some("SESSION_EXPIRED"));
+    assert!(error.errors.as_ref().map_or(true, |errors| errors.is_empty()));
+    assert!(field_errors(&error).is_empty());
+}
+
+#[test]
+fn fields_keep_every_error_with_its_code() {
+    let error = user_error();
+    assert_eq!(error.errors.as_ref().unwrap().get("password").unwrap(), "password is too short, password has no digit");
+    let errors = field_errors(&error);
+    assert_eq!(errors.get("password").unwrap(), &vec![
+        FieldError { code: Some("TOO_SHORT".to_owned()), message: "password is too short".to_owned() },
+        FieldError { code: Some("NO_DIGIT".to_owned()), message: "password has no digit".to_owned() },
+    ]);
+}
+
+#[test]
+fn structured_errors_are_lists_with_codes_and_paths() {
+    let json = field_errors_json(field_errors(&user_error()), ErrorFormat::Structured, unlocalized);
+    assert_eq!(json["password"], json!([
+        { "code": "TOO_SHORT", "message": "password is too short", "path": ["password"] },
+        { "code": "NO_DIGIT", "message": "password has no digit", "path": ["password"] },
+    ]));
+    assert_eq!(json["posts.0.title"], json!([{ "code": "REQUIRED", "message": "title is required", "path": ["posts", 0, "title"] }]));
+}
+
+#[test]
+fn legacy_errors_are_joined_messages() {
+    let json = field_errors_json(field_errors(&user_error()), ErrorFormat::Legacy, unlocalized);
+    assert_eq!(json["password"], json!("password is too short, password has no digit"));
+}
+
+#[test]
+fn errors_without_codes_have_no_code() {
+    let mut error = Error::new("invalid input");
+    error.errors = Some(IndexMap::from([("email".to_owned(), "email is invalid".to_owned())]));
+    let json = field_errors_json(field_errors(&error), ErrorFormat::Structured, unlocalized);
+    assert_eq!(json["email"], json!([{ "message": "email is invalid", "path": ["email"] }]));
+}
+
+#[test]
+fn messages_are_localized_by_code() {
+    let localize = |code: Option<&str>, message: &str| if code == Some("TOO_SHORT") { "mot de passe trop court".to_owned() } else { message.to_owned() };
+    let json = field_errors_json(field_errors(&user_error()), ErrorFormat::Structured, localize);
+    assert_eq!(json["password"][0]["message"], json!("mot de passe trop court"));
+    assert_eq!(json["password"][1]["message"], json!("password has no digit"));
+}
+
+fn catalogs() -> MessageCatalogs {
+    let dir = std::env::temp_dir().join(format!("teo-catalogs-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("fr.json"), r#"{ "TOO_SHORT": "mot de passe trop court", "title is required": "titre requis" }"#).unwrap();
+    fs::write(dir.join("zh-TW.json"), r#"{ "TOO_SHORT": "密碼太短" }"#).unwrap();
+    fs::write(dir.join("README.md"), "not a catalog").unwrap();
+    let catalogs = MessageCatalogs::load(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    catalogs
+}
+
+#[test]
+fn accept_languages_are_ordered_by_quality() {
+    assert_eq!(accept_languages("de;q=0.5, FR-ca, *;q=0.1, en;q=0.8"), vec!["fr-ca", "en", "de"]);
+}
+
+#[test]
+fn catalogs_are_negotiated_with_regional_fallback() {
+    let catalogs = catalogs();
+    let fr = catalogs.negotiate(Some("de, fr-CA;q=0.9")).unwrap();
+    assert_eq!(fr.translate(Some("TOO_SHORT"), "password is too short"), Some("mot de passe trop court"));
+    let zh = catalogs.negotiate(Some("zh-tw")).unwrap();
+    assert_eq!(zh.translate(Some("TOO_SHORT"), "password is too short"), Some("密碼太短"));
+    assert!(catalogs.negotiate(Some("zh")).is_none());
+    assert!(catalogs.negotiate(Some("de")).is_none());
+    assert!(catalogs.negotiate(None).is_none());
+}
+
+#[test]
+fn catalogs_translate_codes_before_messages() {
+    let catalogs = catalogs();
+    let fr = catalogs.negotiate(Some("fr")).unwrap();
+    assert_eq!(fr.translate(Some("REQUIRED"), "title is required"), Some("titre requis"));
+    assert_eq!(fr.translate(None, "title is required"), Some("titre requis"));
+    assert_eq!(fr.translate(Some("NO_DIGIT"), "password has no digit"), None);
+}
+
+#[test]
+fn invalid_catalogs_fail_to_load() {
+    let dir = std::env::temp_dir().join(format!("teo-catalogs-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("fr.json"), "[]").unwrap();
+    let result = MessageCatalogs::load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(result.unwrap_err().message.contains("invalid message catalog"));
+}
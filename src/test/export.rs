@@ -0,0 +1,80 @@
+use std::io::Cursor;
+use actix_web::HttpResponse;
+use actix_web::body::to_bytes;
+use actix_web::web::Bytes;
+use arrow::array::{Array, Int64Array, StringArray};
+use arrow::ipc::reader::StreamReader;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde_json::{json, Value as JsonValue};
+use crate::export::{encode, export_response, ExportFormat};
+
+fn records() -> Vec<JsonValue> {
+    vec![
+        json!({ "id": 1, "title": "first", "createdAt": "2024-01-01T00:00:00.000Z" }),
+        json!({ "id": 2, "title": null, "createdAt": "2024-01-02T00:00:00.000Z" }),
+    ]
+}
+
+fn read_arrow(bytes: Vec<u8>) -> Vec<RecordBatch> {
+    StreamReader::try_new(Cursor::new(bytes), None).unwrap().map(|batch| batch.unwrap()).collect()
+}
+
+fn assert_records(batches: &[RecordBatch]) {
+    assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 2);
+    let batch = &batches[0];
+    let ids = batch.column_by_name("id").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(ids.values().to_vec(), vec![1, 2]);
+    let titles = batch.column_by_name("title").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(titles.value(0), "first");
+    assert!(titles.is_null(1));
+    let dates = batch.column_by_name("createdAt").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(dates.value(1), "2024-01-02T00:00:00.000Z");
+}
+
+#[test]
+fn formats_are_negotiated() {
+    assert_eq!(ExportFormat::from_accept("application/vnd.apache.arrow.stream"), Some(ExportFormat::Arrow));
+    assert_eq!(ExportFormat::from_accept("application/json;q=0.5, application/vnd.apache.parquet;q=0.9"), Some(ExportFormat::Parquet));
+    assert_eq!(ExportFormat::from_accept("application/json, */*"), None);
+    assert_eq!(ExportFormat::from_name("parquet"), Some(ExportFormat::Parquet));
+    assert_eq!(ExportFormat::from_name("csv"), None);
+}
+
+#[test]
+fn arrow_streams_keep_the_records() {
+    assert_records(&read_arrow(encode(&records(), ExportFormat::Arrow).unwrap()));
+}
+
+#[test]
+fn parquet_files_keep_the_records() {
+    let bytes = Bytes::from(encode(&records(), ExportFormat::Parquet).unwrap());
+    let reader = ParquetRecordBatchReaderBuilder::try_new(bytes).unwrap().build().unwrap();
+    let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+    assert_records(&batches);
+}
+
+#[test]
+fn records_are_split_into_batches() {
+    let records: Vec<JsonValue> = (0..2500).map(|id| json!({ "id": id })).collect();
+    let batches = read_arrow(encode(&records, ExportFormat::Arrow).unwrap());
+    assert_eq!(batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>(), vec![1024, 1024, 452]);
+}
+
+#[tokio::test]
+async fn responses_are_replaced_with_their_encoding() {
+    let response = HttpResponse::Ok().json(json!({ "data": records() }));
+    let exported = export_response(response, ExportFormat::Arrow).await;
+    assert_eq!(exported.headers().get("Content-Type").unwrap(), "application/vnd.apache.arrow.stream");
+    let bytes = to_bytes(exported.into_body()).await.unwrap();
+    assert_records(&read_arrow(bytes.to_vec()));
+}
+
+#[tokio::test]
+async fn errors_are_kept() {
+    let response = HttpResponse::BadRequest().json(json!({ "error": { "message": "bad" } }));
+    let exported = export_response(response, ExportFormat::Parquet).await;
+    assert_eq!(exported.status().as_u16(), 400);
+    let bytes = to_bytes(exported.into_body()).await.unwrap();
+    assert_eq!(serde_json::from_slice::<JsonValue>(&bytes).unwrap(), json!({ "error": { "message": "bad" } }));
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::Path;
+use crate::fmt::format_schema;
+
+fn fixture(name: &str) -> (String, String) {
+    let path = Path::new(file!()).parent().unwrap().join(name);
+    let source = fs::read_to_string(&path).unwrap();
+    (path.to_str().unwrap().to_owned(), source)
+}
+
+#[test]
+fn formats_to_golden_file() {
+    let (path, source) = fixture("unformatted.teo");
+    let (_, expected) = fixture("formatted.teo");
+    assert_eq!(format_schema(&path, &source).unwrap(), expected);
+}
+
+#[test]
+fn formatted_file_is_unchanged() {
+    let (path, source) = fixture("formatted.teo");
+    assert_eq!(format_schema(&path, &source).unwrap(), source);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let (path, source) = fixture("unformatted.teo");
+    let once = format_schema(&path, &source).unwrap();
+    let twice = format_schema(&path, &once).unwrap();
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn only_known_decorators_are_sorted() {
+    let (path, _) = fixture("unformatted.teo");
+    let source = "model User {\n  @readonly @map(\"uid\") @id\n  id: Int\n}\n";
+    assert_eq!(format_schema(&path, source).unwrap(), "model User {\n  @readonly @id @map(\"uid\")\n  id: Int\n}\n");
+}
+
+#[test]
+fn comments_are_kept() {
+    let (path, source) = fixture("unformatted.teo");
+    let formatted = format_schema(&path, &source).unwrap();
+    assert!(formatted.contains("\n// roles of users\n"));
+    assert!(formatted.contains("\n  /// The display name\n"));
+    assert!(formatted.contains("\n  name: String // trimmed\n"));
+}
+
+#[test]
+fn files_with_syntax_errors_are_not_formatted() {
+    let (path, _) = fixture("unformatted.teo");
+    assert!(format_schema(&path, "model User {\n  id: Int\n").is_err());
+}
@@ -0,0 +1,185 @@
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde_json::{json, Map, Value as JsonValue};
+use teo_parser::r#type::Type;
+use teo_result::{Error, Result};
+use teo_runtime::model::Model;
+use teo_runtime::model::field::typed::Typed;
+use teo_runtime::traits::named::Named;
+use crate::app::ctx::Ctx;
+use crate::server::make::make_server_app;
+use crate::test::TestApp;
+
+const STRING_FILTERS: [&str; 7] = ["equals", "not", "contains", "startsWith", "endsWith", "in", "notIn"];
+const NUMBER_FILTERS: [&str; 7] = ["equals", "not", "gt", "gte", "lt", "lte", "in"];
+
+/// Generates arbitrary query arguments for a model.
+///
+/// Arguments are mostly valid with some malformed values mixed in, so that
+/// both the query builders and the input validation are exercised. The same
+/// seed generates the same arguments.
+#[derive(Debug)]
+pub struct Fuzzer {
+    rng: StdRng,
+    max_depth: usize,
+}
+
+impl Fuzzer {
+
+    pub fn new(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed), max_depth: 3 }
+    }
+
+    /// Limit how deep `AND`, `OR`, `NOT` and relation filters are nested.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Arguments for `findMany`.
+    pub fn find_many_args(&mut self, model: &Model) -> JsonValue {
+        let mut args = Map::new();
+        if self.rng.gen_bool(0.8) {
+            args.insert("where".to_owned(), self.r#where(model, 0));
+        }
+        if self.rng.gen_bool(0.5) {
+            args.insert("orderBy".to_owned(), self.order_by(model));
+        }
+        if self.rng.gen_bool(0.3) {
+            args.insert("include".to_owned(), self.include(model, 0));
+        }
+        if self.rng.gen_bool(0.3) {
+            args.insert("take".to_owned(), json!(self.rng.gen_range(-5..20)));
+        }
+        if self.rng.gen_bool(0.3) {
+            args.insert("skip".to_owned(), json!(self.rng.gen_range(-5..20)));
+        }
+        JsonValue::Object(args)
+    }
+
+    /// A `where` input.
+    pub fn r#where(&mut self, model: &Model, depth: usize) -> JsonValue {
+        let mut filter = Map::new();
+        let fields: Vec<_> = model.fields.values().collect();
+        for _ in 0..self.rng.gen_range(0..3) {
+            let Some(field) = fields.choose(&mut self.rng) else { break };
+            let value = self.field_filter(field.r#type());
+            filter.insert(field.name().to_owned(), value);
+        }
+        if depth < self.max_depth && self.rng.gen_bool(0.3) {
+            let key = *["AND", "OR", "NOT"].choose(&mut self.rng).unwrap();
+            let nested = (0..self.rng.gen_range(1..3)).map(|_| self.r#where(model, depth + 1)).collect::<Vec<_>>();
+            filter.insert(key.to_owned(), if key == "NOT" { nested[0].clone() } else { JsonValue::Array(nested) });
+        }
+        if depth < self.max_depth && self.rng.gen_bool(0.2) {
+            if let Some(relation) = model.relations().choose(&mut self.rng) {
+                if let Some(related) = Ctx::main_namespace().model_at_path(&relation.model_path()) {
+                    let key = if relation.is_vec { *["some", "every", "none"].choose(&mut self.rng).unwrap() } else { "is" };
+                    filter.insert(relation.name().to_owned(), json!({ key: self.r#where(related, depth + 1) }));
+                }
+            }
+        }
+        JsonValue::Object(filter)
+    }
+
+    /// An `orderBy` input.
+    pub fn order_by(&mut self, model: &Model) -> JsonValue {
+        let fields: Vec<_> = model.fields.values().collect();
+        let orders = (0..self.rng.gen_range(1..3)).filter_map(|_| {
+            let field = fields.choose(&mut self.rng)?;
+            let direction = *["asc", "desc", "sideways"].choose(&mut self.rng).unwrap();
+            Some(json!({ field.name(): direction }))
+        }).collect();
+        JsonValue::Array(orders)
+    }
+
+    /// An `include` input.
+    pub fn include(&mut self, model: &Model, depth: usize) -> JsonValue {
+        let mut include = Map::new();
+        for relation in model.relations() {
+            if !self.rng.gen_bool(0.5) {
+                continue
+            }
+            let value = match Ctx::main_namespace().model_at_path(&relation.model_path()) {
+                Some(related) if depth < self.max_depth && self.rng.gen_bool(0.5) => {
+                    let mut args = Map::new();
+                    args.insert("where".to_owned(), self.r#where(related, depth + 1));
+                    args.insert("include".to_owned(), self.include(related, depth + 1));
+                    JsonValue::Object(args)
+                }
+                _ => json!(true),
+            };
+            include.insert(relation.name().to_owned(), value);
+        }
+        JsonValue::Object(include)
+    }
+
+    fn field_filter(&mut self, t: &Type) -> JsonValue {
+        let value = self.value(t);
+        if self.rng.gen_bool(0.3) {
+            return value;
+        }
+        let operators: &[&str] = match t {
+            Type::String => &STRING_FILTERS,
+            _ => &NUMBER_FILTERS,
+        };
+        let operator = *operators.choose(&mut self.rng).unwrap();
+        if operator == "in" || operator == "notIn" {
+            let values: Vec<JsonValue> = (0..self.rng.gen_range(0..4)).map(|_| self.value(t)).collect();
+            json!({ operator: values })
+        } else {
+            json!({ operator: value })
+        }
+    }
+
+    fn value(&mut self, t: &Type) -> JsonValue {
+        // a malformed value now and then
+        if self.rng.gen_bool(0.1) {
+            return [json!(null), json!({}), json!([]), json!("\u{0}'\"; --"), json!(1e308), json!(-1)].choose(&mut self.rng).unwrap().clone();
+        }
+        match t {
+            Type::Optional(inner) => if self.rng.gen_bool(0.2) { JsonValue::Null } else { self.value(inner) },
+            Type::Bool => json!(self.rng.gen::<bool>()),
+            Type::Int | Type::Int64 => json!(self.rng.gen_range(-1000..1000)),
+            Type::Float32 | Type::Float | Type::Decimal => json!(self.rng.gen_range(-1000.0..1000.0)),
+            Type::String => json!((0..self.rng.gen_range(0..12)).map(|_| self.rng.gen_range(' '..'~')).collect::<String>()),
+            Type::Date => json!("2024-02-30"),
+            Type::DateTime => json!("2024-01-01T00:00:00.000Z"),
+            Type::Array(inner) => JsonValue::Array((0..self.rng.gen_range(0..3)).map(|_| self.value(inner)).collect()),
+            _ => json!("value"),
+        }
+    }
+}
+
+impl TestApp {
+
+    /// Send `iterations` generated `findMany` requests to the model at
+    /// `model_path`, e.g. `"User"` or `"blog.Post"`.
+    ///
+    /// Fails on the first request answered with a server error or with a body
+    /// which isn't a well-formed response, along with the arguments to
+    /// reproduce it.
+    pub async fn fuzz(&self, model_path: &str, iterations: usize, seed: u64) -> Result<()> {
+        let namespace = Ctx::main_namespace();
+        let conf = namespace.server.as_ref().ok_or_else(|| Error::new("fuzz: server is not configured"))?;
+        let path: Vec<&str> = model_path.split('.').collect();
+        let model = namespace.model_at_path(&path).ok_or_else(|| Error::new(format!("fuzz: model `{}` is not found", model_path)))?;
+        let uri = format!("{}/{}/findMany", conf.path_prefix.as_deref().unwrap_or("").trim_end_matches('/'), path.join("/"));
+        let service = init_service(make_server_app(namespace, conf)).await;
+        let mut fuzzer = Fuzzer::new(seed);
+        for iteration in 0..iterations {
+            let args = fuzzer.find_many_args(model);
+            let request = TestRequest::post().uri(&uri).set_json(&args).to_request();
+            let response = call_service(&service, request).await;
+            let status = response.status();
+            let body: Option<JsonValue> = serde_json::from_slice(&read_body(response).await).ok();
+            let well_formed = body.as_ref().map_or(false, |b| if status.is_success() { b.get("data").is_some() } else { b.get("error").is_some() });
+            if status.is_server_error() || !well_formed {
+                Err(Error::new(format!("fuzz: iteration {} with seed {} answered {} with {}, arguments: {}", iteration, seed, status.as_u16(), body.unwrap_or(JsonValue::Null), args)))?
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use teo_result::{Error, Result};
+use crate::generate::{generate_incrementally, MANIFEST_FILE_NAME};
+
+/// A destination directory of its own, removed when the test ends.
+struct Dest(PathBuf);
+
+impl Dest {
+
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("teo-generate-test-{}", uuid::Uuid::new_v4())))
+    }
+
+    fn read(&self, relative: &str) -> Option<String> {
+        fs::read_to_string(self.0.join(relative)).ok()
+    }
+
+    fn modified(&self, relative: &str) -> SystemTime {
+        fs::metadata(self.0.join(relative)).unwrap().modified().unwrap()
+    }
+
+    /// Set the modification time of a file far in the past, so that a write
+    /// shows however coarse the clock of the file system is.
+    fn age(&self, relative: &str) -> SystemTime {
+        let time = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        File::options().write(true).open(self.0.join(relative)).unwrap().set_modified(time).unwrap();
+        time
+    }
+
+    fn manifest(&self) -> BTreeMap<String, String> {
+        serde_json::from_str(&self.read(MANIFEST_FILE_NAME).unwrap()).unwrap()
+    }
+}
+
+impl Drop for Dest {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Generate `files` into `dest`, like a generator writing into the staging
+/// directory it's given.
+async fn generate(dest: &Dest, full: bool, files: &[(&str, &str)]) -> Result<()> {
+    let files: Vec<(String, String)> = files.iter().map(|(path, content)| (path.to_string(), content.to_string())).collect();
+    generate_incrementally(&dest.0, full, |staging| async move {
+        for (path, content) in files {
+            let path = Path::new(&staging).join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        Ok(())
+    }).await
+}
+
+#[tokio::test]
+async fn manifest_records_the_sha256_of_each_file() {
+    let dest = Dest::new();
+    generate(&dest, false, &[("index.ts", "abc"), ("models/user.ts", "")]).await.unwrap();
+    assert_eq!(dest.read("index.ts").unwrap(), "abc");
+    assert_eq!(dest.read("models/user.ts").unwrap(), "");
+    assert_eq!(dest.manifest(), BTreeMap::from([
+        ("index.ts".to_owned(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_owned()),
+        ("models/user.ts".to_owned(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_owned()),
+    ]));
+}
+
+#[tokio::test]
+async fn unchanged_files_are_not_rewritten() {
+    let dest = Dest::new();
+    generate(&dest, false, &[("index.ts", "index"), ("user.ts", "user")]).await.unwrap();
+    let index = dest.age("index.ts");
+    let user = dest.age("user.ts");
+    generate(&dest, false, &[("index.ts", "index"), ("user.ts", "user 2")]).await.unwrap();
+    assert_eq!(dest.modified("index.ts"), index);
+    assert_ne!(dest.modified("user.ts"), user);
+    assert_eq!(dest.read("user.ts").unwrap(), "user 2");
+}
+
+#[tokio::test]
+async fn full_rewrites_unchanged_files() {
+    let dest = Dest::new();
+    generate(&dest, false, &[("index.ts", "index")]).await.unwrap();
+    let index = dest.age("index.ts");
+    generate(&dest, true, &[("index.ts", "index")]).await.unwrap();
+    assert_ne!(dest.modified("index.ts"), index);
+    assert_eq!(dest.manifest().len(), 1);
+}
+
+#[tokio::test]
+async fn stale_files_are_removed() {
+    let dest = Dest::new();
+    generate(&dest, false, &[("index.ts", "index"), ("models/post.ts", "post")]).await.unwrap();
+    fs::write(dest.0.join("README.md"), "kept").unwrap();
+    generate(&dest, false, &[("index.ts", "index")]).await.unwrap();
+    assert!(dest.read("models/post.ts").is_none());
+    assert_eq!(dest.read("README.md").unwrap(), "kept");
+    assert!(!dest.manifest().contains_key("models/post.ts"));
+}
+
+#[tokio::test]
+async fn failed_generation_leaves_dest_alone() {
+    let dest = Dest::new();
+    generate(&dest, false, &[("index.ts", "index")]).await.unwrap();
+    let result = generate_incrementally(&dest.0, false, |staging| async move {
+        fs::write(Path::new(&staging).join("index.ts"), "broken").unwrap();
+        Err(Error::new("the generator fails"))
+    }).await;
+    assert!(result.is_err());
+    assert_eq!(dest.read("index.ts").unwrap(), "index");
+    assert_eq!(dest.manifest().len(), 1);
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use crate::stdlib::pipeline_items::http::http_fetch;
+
+/// A response of the mock server, sent after `delay`.
+#[derive(Clone)]
+pub(super) struct Response {
+    status: u16,
+    body: &'static str,
+    delay: Duration,
+}
+
+pub(super) fn response(status: u16, body: &'static str) -> Response {
+    Response { status, body, delay: Duration::ZERO }
+}
+
+/// An HTTP server on a free local port. It answers the requests with the
+/// responses in order, repeating the last one, and records the requests.
+pub(super) struct MockServer {
+    pub(super) url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+
+    pub(super) async fn start(responses: Vec<Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else { return };
+                let response = {
+                    let mut requests = recorded.lock().unwrap();
+                    let response = responses[requests.len().min(responses.len() - 1)].clone();
+                    requests.push(String::new());
+                    (requests.len() - 1, response)
+                };
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let (index, response) = response;
+                    let request = read_request(&mut stream).await;
+                    recorded.lock().unwrap()[index] = request;
+                    respond(stream, response).await;
+                });
+            }
+        });
+        Self { url, requests }
+    }
+
+    pub(super) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> String {
+    let mut request = vec![];
+    let mut buf = [0u8; 1024];
+    loop {
+        let Ok(read) = stream.read(&mut buf).await else { break };
+        if read == 0 {
+            break
+        }
+        request.extend_from_slice(&buf[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text.lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|length| length.trim().parse::<usize>().unwrap_or(0)))
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                break
+            }
+        }
+    }
+    String::from_utf8_lossy(&request).to_string()
+}
+
+async fn respond(mut stream: TcpStream, response: Response) {
+    tokio::time::sleep(response.delay).await;
+    let reply = format!("HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", response.status, response.body.len(), response.body);
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn fetch(url: &str, timeout: Option<i32>, retry: Option<i32>, extract: Option<&str>) -> teo_result::Result<JsonValue> {
+    http_fetch(&json!({}), url, None, None, timeout, retry, extract).await
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    let server = MockServer::start(vec![response(503, "{}"), response(500, "{}"), response(200, r#"{"ok":true}"#)]).await;
+    let result = fetch(&server.url, None, Some(2), None).await.unwrap();
+    assert_eq!(result, json!({ "ok": true }));
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn retries_run_out() {
+    let server = MockServer::start(vec![response(503, "{}")]).await;
+    let error = fetch(&server.url, None, Some(1), None).await.unwrap_err();
+    assert!(error.message.contains("503"), "{}", error.message);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let server = MockServer::start(vec![response(404, "{}"), response(200, "{}")]).await;
+    let error = fetch(&server.url, None, Some(3), None).await.unwrap_err();
+    assert!(error.message.contains("404"), "{}", error.message);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn slow_responses_time_out() {
+    let slow = Response { status: 200, body: "{}", delay: Duration::from_millis(1000) };
+    let server = MockServer::start(vec![slow]).await;
+    let error = fetch(&server.url, Some(100), None, None).await.unwrap_err();
+    assert!(error.message.contains("failed"), "{}", error.message);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn timed_out_requests_are_retried() {
+    let slow = Response { status: 200, body: "{}", delay: Duration::from_millis(1000) };
+    let server = MockServer::start(vec![slow, response(200, r#"{"ok":true}"#)]).await;
+    let result = fetch(&server.url, Some(100), Some(1), None).await.unwrap();
+    assert_eq!(result, json!({ "ok": true }));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn extract_picks_a_path() {
+    let server = MockServer::start(vec![response(200, r#"{"data":{"items":[{"name":"teo"}]}}"#)]).await;
+    assert_eq!(fetch(&server.url, None, None, Some("data.items.0.name")).await.unwrap(), json!("teo"));
+    let error = fetch(&server.url, None, None, Some("data.items.1.name")).await.unwrap_err();
+    assert!(error.message.contains("doesn't contain `data.items.1.name`"), "{}", error.message);
+}
+
+#[tokio::test]
+async fn value_is_substituted_into_url_and_body() {
+    let server = MockServer::start(vec![response(200, "{}")]).await;
+    let value = json!({ "id": 5, "name": "a b" });
+    let url = format!("{}/users/{{{{value.id}}}}?name={{{{value.name}}}}", server.url);
+    http_fetch(&value, &url, None, Some(r#"{"user": {{value}}}"#), None, None, None).await.unwrap();
+    let request = server.requests().pop().unwrap();
+    assert!(request.starts_with("POST /users/5?name=a+b HTTP/1.1"), "{}", request);
+    assert!(request.ends_with(r#"{"user": {"id":5,"name":"a b"}}"#), "{}", request);
+}
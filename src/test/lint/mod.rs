@@ -0,0 +1,38 @@
+use std::path::Path;
+use teo_parser::parse as schema_parse;
+use crate::lint::{check_arguments, LintError};
+
+fn check(file: &str) -> Vec<LintError> {
+    let path = Path::new(file!()).parent().unwrap().join(file);
+    let (schema, _) = schema_parse(path.to_str().unwrap(), None, None);
+    check_arguments(&schema)
+}
+
+#[test]
+fn valid_arguments() {
+    assert_eq!(check("valid.teo"), vec![]);
+}
+
+#[test]
+fn pipeline_item_argument_type() {
+    let errors = check("invalid.teo");
+    let error = errors.iter().find(|e| e.message.contains("$minLength")).unwrap();
+    assert_eq!(error.line, 9);
+    assert!(error.message.contains("expects Int"));
+}
+
+#[test]
+fn decorator_argument_type() {
+    let errors = check("invalid.teo");
+    let error = errors.iter().find(|e| e.message.contains("@map")).unwrap();
+    assert_eq!(error.line, 11);
+    assert!(error.message.contains("expects String"));
+}
+
+#[test]
+fn too_many_arguments() {
+    let errors = check("invalid.teo");
+    let error = errors.iter().find(|e| e.message.contains("$trim")).unwrap();
+    assert_eq!(error.line, 13);
+    assert!(error.message.contains("at most 0 argument(s)"));
+}
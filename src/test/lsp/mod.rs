@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value as JsonValue};
+use url::Url;
+use crate::lsp::Server;
+
+fn dir() -> PathBuf {
+    fs::canonicalize(Path::new(file!()).parent().unwrap()).unwrap()
+}
+
+fn uri(name: &str) -> String {
+    Url::from_file_path(dir().join(name)).unwrap().to_string()
+}
+
+/// A server with `schema.teo` open, `status.teo` is only on disk.
+fn server() -> Server {
+    let mut server = Server::new(Some(dir()));
+    server.open(&uri("schema.teo"), &fs::read_to_string(dir().join("schema.teo")).unwrap());
+    server
+}
+
+fn at(line: usize, character: usize) -> JsonValue {
+    json!({ "textDocument": { "uri": uri("schema.teo") }, "position": { "line": line, "character": character } })
+}
+
+fn labels(items: JsonValue) -> Vec<String> {
+    items.as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap().to_owned()).collect()
+}
+
+#[test]
+fn definition_follows_imports() {
+    let definitions = server().definition(&at(12, 11));
+    assert_eq!(definitions[0]["uri"], json!(uri("status.teo")));
+    assert_eq!(definitions[0]["range"]["start"], json!({ "line": 2, "character": 5 }));
+}
+
+#[test]
+fn hover_shows_doc_comments() {
+    let hover = server().hover(&at(12, 11));
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("Status"));
+    assert!(contents.contains("Publication status"));
+    assert!(contents.contains("Whether readers can see a post."));
+}
+
+#[test]
+fn hover_shows_builtin_docs() {
+    let hover = server().hover(&at(10, 4));
+    assert!(hover["contents"]["value"].as_str().unwrap().contains("map"));
+}
+
+#[test]
+fn decorator_completion() {
+    let labels = labels(server().completion(&at(10, 3)));
+    assert!(labels.iter().any(|label| label == "map"));
+    assert!(labels.iter().all(|label| label != "Post"));
+}
+
+#[test]
+fn type_completion() {
+    let labels = labels(server().completion(&at(11, 9)));
+    assert!(labels.iter().any(|label| label == "Status"));
+}
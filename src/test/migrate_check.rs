@@ -0,0 +1,81 @@
+use serde_json::{json, Value as JsonValue};
+use crate::migrate::check::{diff, Change};
+
+fn field(column: &str, r#type: &str, optional: bool) -> JsonValue {
+    json!({ "column": column, "type": r#type, "optional": optional, "default": false, "generated": null, "constraints": [] })
+}
+
+fn schema(table: &str, fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    let fields: serde_json::Map<String, JsonValue> = fields.into_iter().map(|(name, field)| (name.to_owned(), field)).collect();
+    json!({ "Post": { "table": table, "fields": fields } })
+}
+
+fn changes(recorded: JsonValue, current: JsonValue) -> Vec<(bool, String)> {
+    diff(&recorded, &current).into_iter().map(|Change { description, breaking }| (breaking, description)).collect()
+}
+
+#[test]
+fn same_schemas_have_no_changes() {
+    let post = schema("Post", vec![("title", field("title", "String", false))]);
+    assert!(changes(post.clone(), post).is_empty());
+}
+
+#[test]
+fn removals_and_renames_are_breaking() {
+    let recorded = schema("Post", vec![("title", field("title", "String", false)), ("body", field("body", "String", true))]);
+    let current = schema("posts", vec![("title", field("headline", "String", false))]);
+    assert_eq!(changes(recorded, current), vec![
+        (true, r#"table of `Post` is renamed from "Post" to "posts""#.to_owned()),
+        (true, r#"column of `Post.title` is renamed from "title" to "headline""#.to_owned()),
+        (true, "field `Post.body` is removed".to_owned()),
+    ]);
+    assert_eq!(changes(schema("Post", vec![]), json!({})), vec![(true, "model `Post` is removed".to_owned())]);
+}
+
+#[test]
+fn optionality_changes_one_way() {
+    let optional = schema("Post", vec![("title", field("title", "String", true))]);
+    let required = schema("Post", vec![("title", field("title", "String", false))]);
+    assert_eq!(changes(optional.clone(), required.clone()), vec![(true, "field `Post.title` becomes required".to_owned())]);
+    assert_eq!(changes(required, optional), vec![(false, "field `Post.title` becomes optional".to_owned())]);
+}
+
+#[test]
+fn added_fields_are_breaking_when_required_without_default() {
+    let recorded = schema("Post", vec![]);
+    let mut with_default = field("status", "String", false);
+    with_default["default"] = json!(true);
+    let current = schema("Post", vec![("code", field("code", "String", false)), ("memo", field("memo", "String", true)), ("status", with_default)]);
+    assert_eq!(changes(recorded, current), vec![
+        (true, "required field `Post.code` without a default is added".to_owned()),
+        (false, "field `Post.memo` is added".to_owned()),
+        (false, "field `Post.status` is added".to_owned()),
+    ]);
+}
+
+#[test]
+fn added_constraints_are_breaking() {
+    let mut checked = field("views", "Int", false);
+    checked["constraints"] = json!(["CHECK (views >= 0)"]);
+    let plain = schema("Post", vec![("views", field("views", "Int", false))]);
+    let checked = schema("Post", vec![("views", checked)]);
+    assert_eq!(changes(plain.clone(), checked.clone()), vec![(true, "constraint \"CHECK (views >= 0)\" is added to `Post.views`".to_owned())]);
+    assert_eq!(changes(checked, plain), vec![(false, "constraint \"CHECK (views >= 0)\" is removed from `Post.views`".to_owned())]);
+}
+
+#[test]
+fn changed_types_are_breaking_and_generations_are_not() {
+    let mut generated = field("total", "Float", false);
+    generated["generated"] = json!("price * 2 stored");
+    let recorded = schema("Post", vec![("total", field("total", "Int", false))]);
+    assert_eq!(changes(recorded, schema("Post", vec![("total", generated)])), vec![
+        (true, r#"type of `Post.total` changes from "Int" to "Float""#.to_owned()),
+        (false, "generation of `Post.total` changes from none to price * 2 stored".to_owned()),
+    ]);
+}
+
+#[test]
+fn added_models_are_compatible() {
+    let recorded = json!({});
+    assert_eq!(changes(recorded, schema("Post", vec![])), vec![(false, "model `Post` is added".to_owned())]);
+}
@@ -0,0 +1,218 @@
+pub mod fuzz;
+#[cfg(test)]
+mod check_constraints;
+#[cfg(test)]
+mod client_ip;
+#[cfg(test)]
+mod db_functions;
+#[cfg(test)]
+mod delimiters;
+#[cfg(test)]
+mod durations;
+#[cfg(test)]
+mod envelopes;
+#[cfg(test)]
+mod environments;
+#[cfg(test)]
+mod errors;
+#[cfg(all(test, feature = "arrow"))]
+mod export;
+#[cfg(test)]
+mod fmt;
+#[cfg(test)]
+mod generate;
+#[cfg(test)]
+mod http;
+#[cfg(test)]
+mod lint;
+#[cfg(test)]
+mod lsp;
+#[cfg(test)]
+mod migrate_check;
+#[cfg(test)]
+mod money;
+#[cfg(test)]
+mod named_queries;
+#[cfg(test)]
+mod naming;
+#[cfg(test)]
+mod plugins;
+#[cfg(test)]
+mod query_tags;
+mod rollback;
+#[cfg(test)]
+mod s3;
+#[cfg(test)]
+mod secrets;
+#[cfg(test)]
+mod security;
+#[cfg(test)]
+mod similar;
+#[cfg(test)]
+mod slugs;
+#[cfg(test)]
+mod soundex;
+#[cfg(test)]
+mod transitions;
+
+use std::future::Future;
+use std::panic::{resume_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+use actix_http::Request;
+use actix_web::dev::ServiceResponse;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use educe::Educe;
+use futures_util::future::LocalBoxFuture;
+use futures_util::FutureExt;
+use once_cell::sync::Lazy;
+use serde_json::{Value as JsonValue};
+use teo_result::Result;
+use teo_runtime::connection::Ctx as ConnCtx;
+use tokio::sync::Mutex;
+use crate::app::App;
+use crate::app::ctx::Ctx;
+use crate::app::database::connect_databases;
+use crate::cli::entrance::Entrance;
+use crate::migrate::migrate;
+use crate::purge::purge;
+use crate::server::make::make_server_app;
+use crate::test::rollback::{supports_rollback, with_rollback, wrap_connection};
+
+/// Held by the `run` call in progress when the databases can't roll back,
+/// so that tests running concurrently take turns on the database instead of
+/// seeing each other's records.
+static ISOLATION: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Calls the in process server.
+type CallService = Rc<dyn Fn(Request) -> LocalBoxFuture<'static, ServiceResponse>>;
+
+/// A test app which runs the server in process. The databases are migrated
+/// from scratch when it's created. Each `run` call runs its test body in a
+/// transaction which is rolled back afterwards, even when the body panics,
+/// so tests don't see each other's records and can run concurrently.
+///
+/// MongoDB and connector builders like the memory connector have no
+/// savepoints to roll back to. With them, the databases are connected again
+/// and migrated before each body and purged after it instead, and `run`
+/// calls take turns.
+///
+/// Only one test app can exist in a process, just like `App`.
+#[derive(Educe)]
+#[educe(Debug)]
+pub struct TestApp {
+    app: App,
+    #[educe(Debug(ignore))]
+    service: CallService,
+    rollback: bool,
+}
+
+impl TestApp {
+
+    /// Create a test app with the schema file at `schema`.
+    pub async fn new(schema: impl AsRef<str>) -> Result<Self> {
+        Self::new_with(schema, |_| ()).await
+    }
+
+    /// Create a test app with the schema file at `schema`, which `configure`
+    /// sets up before the schema is loaded, e.g. to define the pipeline items
+    /// the schema refers to.
+    pub async fn new_with<F>(schema: impl AsRef<str>, configure: F) -> Result<Self> where F: FnOnce(&App) {
+        let argv = vec!["teo".to_owned(), "--silent".to_owned(), "--schema".to_owned(), schema.as_ref().to_owned(), "serve".to_owned()];
+        let app = App::new_with_entrance_and_runtime_version(Some(Entrance::APP), None, Some(argv))?;
+        configure(&app);
+        app.prepare_for_run().await?;
+        fresh_database().await?;
+        let rollback = rollback_connections();
+        let namespace = Ctx::main_namespace();
+        let service = Rc::new(init_service(make_server_app(namespace, namespace.server.as_ref().unwrap())).await);
+        let service: CallService = Rc::new(move |request| {
+            let service = service.clone();
+            async move { call_service(&*service, request).await.map_into_boxed_body() }.boxed_local()
+        });
+        Ok(Self { app, service, rollback })
+    }
+
+    /// The underlying app.
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    /// Run a test body and roll back its writes afterwards. A panic of the
+    /// body is resumed after the rollback. The requests of the body must be
+    /// sent from the task running it, as its transaction is found by a task
+    /// local.
+    pub async fn run<F, Fut, T>(&self, f: F) -> Result<T> where F: FnOnce() -> Fut, Fut: Future<Output = T> {
+        let result = if self.rollback {
+            let (result, rolled_back) = with_rollback(AssertUnwindSafe(f()).catch_unwind()).await;
+            rolled_back?;
+            result
+        } else {
+            let _isolation = ISOLATION.lock().await;
+            fresh_database().await?;
+            let result = AssertUnwindSafe(f()).catch_unwind().await;
+            self.reset().await?;
+            result
+        };
+        match result {
+            Ok(result) => Ok(result),
+            Err(panic) => resume_unwind(panic),
+        }
+    }
+
+    /// Remove all records from the database.
+    pub async fn reset(&self) -> Result<()> {
+        purge().await
+    }
+
+    /// Send a request to a model action and return the json response.
+    pub async fn req(&self, model: &str, action: &str, body: JsonValue) -> JsonValue {
+        self.post(&format!("/{}/{}", model, action), body).await
+    }
+
+    /// Send a post request to `path` and return the json response. Panics
+    /// with the response if it isn't json.
+    pub async fn post(&self, path: &str, body: JsonValue) -> JsonValue {
+        let request = TestRequest::post().uri(&self.uri(path)).set_json(body).to_request();
+        let response = self.call(request).await;
+        let status = response.status();
+        let bytes = read_body(response).await;
+        serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            panic!("the response of `{}` is not json ({}): {} {}", path, err, status, String::from_utf8_lossy(&bytes))
+        })
+    }
+
+    /// Send a request with its own method and headers, e.g. a signed one.
+    pub async fn call(&self, request: Request) -> ServiceResponse {
+        (self.service)(request).await
+    }
+
+    /// The uri of `path` with the path prefix of the server.
+    pub fn uri(&self, path: &str) -> String {
+        let conf = Ctx::main_namespace().server.as_ref().unwrap();
+        match conf.path_prefix.as_ref() {
+            Some(prefix) => format!("{}{}", prefix.trim_end_matches('/'), path),
+            None => path.to_owned(),
+        }
+    }
+}
+
+/// Wrap the connections to roll back the writes of each test body, if all
+/// of the databases can.
+fn rollback_connections() -> bool {
+    let main = Ctx::main_namespace_mut();
+    if !supports_rollback(main) || !main.namespaces.values().all(supports_rollback) {
+        return false;
+    }
+    wrap_connection(main);
+    for namespace in main.namespaces.values_mut() {
+        wrap_connection(namespace);
+    }
+    Ctx::get_mut().conn_ctx = Some(ConnCtx::from_namespace(Ctx::main_namespace()));
+    true
+}
+
+/// Connect the databases again and migrate them from scratch.
+async fn fresh_database() -> Result<()> {
+    connect_databases(Ctx::main_namespace_mut(), true).await?;
+    migrate(false, true, true).await
+}
@@ -0,0 +1,64 @@
+use serde_json::json;
+use crate::app::money::{sum_money, Money};
+
+fn money(minor: i64, currency: &str) -> Money {
+    Money { minor, currency: currency.to_owned() }
+}
+
+#[test]
+fn amounts_are_read_in_minor_units() {
+    assert_eq!(Money::from_json(&json!({ "amount": "12.5", "currency": "USD" })).unwrap(), money(1250, "USD"));
+    assert_eq!(Money::from_json(&json!({ "amount": 12.34, "currency": "EUR" })).unwrap(), money(1234, "EUR"));
+    assert_eq!(Money::from_json(&json!({ "amount": "-0.05", "currency": "USD" })).unwrap(), money(-5, "USD"));
+    assert_eq!(Money::from_json(&json!({ "amount": "1000", "currency": "JPY" })).unwrap(), money(1000, "JPY"));
+    assert_eq!(Money::from_json(&json!({ "amount": "1.250", "currency": "KWD" })).unwrap(), money(1250, "KWD"));
+    assert_eq!(Money::from_json(&json!({ "minor": 99, "currency": "GBP" })).unwrap(), money(99, "GBP"));
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    let error = |value| Money::from_json(&value).unwrap_err().message;
+    assert_eq!(error(json!({ "amount": "1" })), "expect a money value to have a `currency`");
+    assert_eq!(error(json!({ "amount": "1", "currency": "usd" })), "invalid currency code `usd`, expect an ISO 4217 code like `USD`");
+    assert_eq!(error(json!({ "currency": "USD" })), "expect a money value to have an `amount`");
+    assert_eq!(error(json!({ "amount": "1.2.3", "currency": "USD" })), "invalid amount `1.2.3`");
+    assert_eq!(error(json!({ "amount": ".5", "currency": "USD" })), "invalid amount `.5`");
+    assert_eq!(error(json!({ "amount": "1.005", "currency": "USD" })), "amount `1.005` has more than 2 decimal places, which USD doesn't allow");
+    assert_eq!(error(json!({ "amount": "1.5", "currency": "JPY" })), "amount `1.5` has more than 0 decimal places, which JPY doesn't allow");
+    assert_eq!(error(json!({ "amount": "99999999999999999999", "currency": "USD" })), "amount `99999999999999999999` is too large");
+}
+
+#[test]
+fn trailing_zeros_dont_count_as_decimal_places() {
+    assert_eq!(Money::from_json(&json!({ "amount": "1.500", "currency": "USD" })).unwrap(), money(150, "USD"));
+}
+
+#[test]
+fn amounts_are_written_as_decimal_strings() {
+    assert_eq!(money(1250, "USD").to_json(), json!({ "amount": "12.50", "currency": "USD" }));
+    assert_eq!(money(5, "USD").amount(), "0.05");
+    assert_eq!(money(-5, "USD").amount(), "-0.05");
+    assert_eq!(money(1000, "JPY").amount(), "1000");
+    assert_eq!(money(1, "KWD").amount(), "0.001");
+    assert_eq!(money(1250, "USD").to_stored_json(), json!({ "minor": 1250, "currency": "USD" }));
+}
+
+#[test]
+fn amounts_are_formatted_for_display() {
+    assert_eq!(money(123450, "USD").format(), "$1,234.50");
+    assert_eq!(money(-100000000, "EUR").format(), "-€1,000,000.00");
+    assert_eq!(money(123450, "CHF").format(), "1,234.50 CHF");
+    assert_eq!(money(1000, "JPY").format(), "¥1,000");
+    assert_eq!(money(99, "GBP").format(), "£0.99");
+}
+
+#[test]
+fn sums_are_per_currency() {
+    let values = vec![money(150, "USD"), money(-50, "USD"), money(1, "USD")];
+    assert_eq!(sum_money(values.iter()).unwrap(), Some(money(101, "USD")));
+    assert_eq!(sum_money(vec![].iter()).unwrap(), None);
+    let values = vec![money(150, "USD"), money(100, "JPY")];
+    assert_eq!(sum_money(values.iter()).unwrap_err().message, "cannot sum amounts in USD and JPY, filter the records by currency");
+    let values = vec![money(i64::MAX, "USD"), money(1, "USD")];
+    assert_eq!(sum_money(values.iter()).unwrap_err().message, "money sum overflows");
+}
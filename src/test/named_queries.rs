@@ -0,0 +1,58 @@
+use serde_json::json;
+use crate::utils::named_queries::extract_from_source;
+
+const SCHEMA: &str = r#"model User {
+  @id
+  id: Int
+  role: Role
+}
+
+query activeAdmins on User {
+  // the newest first
+  where: { role: .admin, name: { contains: $name }, OR: [{ age: { gte: $age } }, { age: null }] }
+  orderBy: { createdAt: "desc" }
+  take: 10
+}
+
+query byRole on auth.User { where: { role: $role, OR: [{ name: $role }] } }
+"#;
+
+#[test]
+fn query_blocks_are_taken_out_of_the_source() {
+    let (rewritten, queries) = extract_from_source(SCHEMA).unwrap().unwrap();
+    assert_eq!(rewritten, format!("{}{}", &SCHEMA[..SCHEMA.find("query").unwrap()], "\n".repeat(8)));
+    assert_eq!(rewritten.lines().count(), SCHEMA.lines().count());
+    assert_eq!(queries.len(), 2);
+    assert_eq!((queries[0].name.as_str(), queries[0].model.clone()), ("activeAdmins", vec!["User".to_owned()]));
+    assert_eq!(queries[0].arguments, json!({
+        "where": { "role": "admin", "name": { "contains": { "$param": "name" } }, "OR": [{ "age": { "gte": { "$param": "age" } } }, { "age": null }] },
+        "orderBy": { "createdAt": "desc" },
+        "take": 10,
+    }));
+    assert_eq!(queries[0].parameters, vec!["name", "age"]);
+    assert_eq!(queries[1].model, vec!["auth", "User"]);
+    // repeated placeholders are one parameter
+    assert_eq!(queries[1].parameters, vec!["role"]);
+}
+
+#[test]
+fn sources_without_queries_are_left_alone() {
+    assert!(extract_from_source("model User {\n  // query all on User {}\n  id: Int\n}\n").unwrap().is_none());
+}
+
+#[test]
+fn placeholders_are_substituted() {
+    let (_, queries) = extract_from_source(SCHEMA).unwrap().unwrap();
+    assert_eq!(queries[1].substitute(&json!({ "role": "admin", "other": 1 })).unwrap(), json!({ "where": { "role": "admin", "OR": [{ "name": "admin" }] } }));
+    assert_eq!(queries[1].substitute(&json!({})).unwrap_err().message, "parameter `role` is required");
+}
+
+#[test]
+fn invalid_queries_are_errors() {
+    let error = extract_from_source("query all on User { where: {}, limit: 1 }\n").unwrap_err();
+    assert_eq!(error.message, "query `all`: unexpected argument `limit`");
+    let error = extract_from_source("query all on User { where: { id: 1 }\n").unwrap_err();
+    assert_eq!(error.message, "query `all` is not closed");
+    let error = extract_from_source("query all on User { where: { id: $ } }\n").unwrap_err();
+    assert_eq!(error.message, "query `all`: expect a parameter name after `$`");
+}
@@ -0,0 +1,23 @@
+use crate::app::naming::NamingConvention;
+
+#[test]
+fn as_is_keeps_names() {
+    assert_eq!(NamingConvention::AsIs.apply("BlogPost"), "BlogPost");
+    assert_eq!(NamingConvention::AsIs.apply("created_at"), "created_at");
+}
+
+#[test]
+fn snake_case_splits_words_at_case_changes() {
+    assert_eq!(NamingConvention::SnakeCase.apply("BlogPost"), "blog_post");
+    assert_eq!(NamingConvention::SnakeCase.apply("createdAt"), "created_at");
+    assert_eq!(NamingConvention::SnakeCase.apply("HTTPRequest_id"), "http_request_id");
+    assert_eq!(NamingConvention::SnakeCase.apply("address2Line"), "address2_line");
+    assert_eq!(NamingConvention::SnakeCase.apply("id"), "id");
+}
+
+#[test]
+fn camel_case_joins_words() {
+    assert_eq!(NamingConvention::CamelCase.apply("BlogPost"), "blogPost");
+    assert_eq!(NamingConvention::CamelCase.apply("created_at"), "createdAt");
+    assert_eq!(NamingConvention::CamelCase.apply("HTTPRequest_id"), "httpRequestId");
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+use crate::utils::plugins::{extract_from_source, PluginDeclaration, PluginRuntime, PluginTarget};
+
+const SCHEMA: &str = r#"declare pipeline item upper: String -> String from wasm("./plugin.wat")
+
+namespace text {
+  declare handler shout(ShoutInput): ShoutOutput from wasm( "../plugin.wat" , "upper" )
+}
+"#;
+
+#[test]
+fn declarations_are_taken_out_of_the_schema() {
+    let (rewritten, declarations) = extract_from_source(SCHEMA, Path::new("/app")).unwrap().unwrap();
+    assert_eq!(rewritten, "declare pipeline item upper: String -> String\n\nnamespace text {\n  declare handler shout(ShoutInput): ShoutOutput\n}\n");
+    assert_eq!(declarations, vec![
+        PluginDeclaration {
+            namespace_path: vec![],
+            target: PluginTarget::PipelineItem,
+            name: "upper".to_owned(),
+            runtime: PluginRuntime::Wasm,
+            path: Path::new("/app/./plugin.wat").to_owned(),
+            export: "upper".to_owned(),
+        },
+        PluginDeclaration {
+            namespace_path: vec!["text".to_owned()],
+            target: PluginTarget::Handler,
+            name: "shout".to_owned(),
+            runtime: PluginRuntime::Wasm,
+            path: Path::new("/app/../plugin.wat").to_owned(),
+            export: "upper".to_owned(),
+        },
+    ]);
+}
+
+#[test]
+fn script_declarations_are_taken_out_of_the_schema() {
+    let source = "declare pipeline item shout: String -> String from js(\"./plugin.js\")\n";
+    let (rewritten, declarations) = extract_from_source(source, Path::new("/app")).unwrap().unwrap();
+    assert_eq!(rewritten, "declare pipeline item shout: String -> String\n");
+    assert_eq!(declarations[0].runtime, PluginRuntime::Js);
+    assert_eq!(declarations[0].export, "shout");
+}
+
+#[test]
+fn sources_without_declarations_are_kept() {
+    assert!(extract_from_source("model User {\n  id: Int\n}\n", Path::new("/app")).unwrap().is_none());
+}
+
+#[test]
+fn handler_groups_are_rejected() {
+    let source = "declare handler group Text {\n  declare handler shout(ShoutInput): ShoutOutput from wasm(\"./plugin.wat\")\n}\n";
+    assert!(extract_from_source(source, Path::new("/app")).is_err());
+}
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::wasm::{WasmLimits, WasmPlugin};
+
+    fn plugin() -> WasmPlugin {
+        WasmPlugin::load(Path::new(file!()).parent().unwrap().join("plugin.wat"), WasmLimits::default()).unwrap()
+    }
+
+    #[test]
+    fn calls_exports_with_json() {
+        let plugin = plugin();
+        assert_eq!(plugin.call("echo", &json!({ "a": [1, 2] })).unwrap(), json!({ "a": [1, 2] }));
+        assert_eq!(plugin.call("upper", &json!("news")).unwrap(), json!("NEWS"));
+        assert_eq!(plugin.call("ok", &json!(null)).unwrap(), json!({ "ok": true }));
+    }
+
+    #[test]
+    fn calls_run_out_of_fuel() {
+        let error = plugin().call("spin", &json!(null)).unwrap_err();
+        assert!(error.message.contains("wasm plugin error"));
+    }
+
+    #[test]
+    fn unknown_exports_are_errors() {
+        assert!(plugin().call("missing", &json!(null)).is_err());
+    }
+}
+
+#[cfg(feature = "js")]
+mod js {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::js::JsScript;
+
+    fn script() -> JsScript {
+        JsScript::load(Path::new(file!()).parent().unwrap().join("plugin.js")).unwrap()
+    }
+
+    #[test]
+    fn calls_functions_with_json() {
+        let script = script();
+        assert_eq!(script.call("shout", &json!("news")).unwrap(), json!("NEWS!"));
+        assert_eq!(script.call("greet", &json!({ "name": "teo" })).unwrap(), json!({ "greeting": "hello, teo" }));
+    }
+
+    #[test]
+    fn loops_are_limited() {
+        assert!(script().with_loop_iteration_limit(1000).call("spin", &json!(null)).is_err());
+    }
+
+    #[test]
+    fn typescript_is_rejected() {
+        let error = JsScript::load("plugin.ts").unwrap_err();
+        assert!(error.message.contains("compile TypeScript sources to JavaScript first"));
+    }
+}
@@ -0,0 +1,13 @@
+// A JavaScript plugin for tests.
+
+function shout(value) {
+  return value.toUpperCase() + "!";
+}
+
+function greet(input) {
+  return { greeting: "hello, " + input.name };
+}
+
+function spin() {
+  while (true) {}
+}
@@ -0,0 +1,45 @@
+;; A WASM plugin for tests. Inputs and outputs are JSON in linear memory,
+;; outputs are located by `(ptr << 32) | len`.
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (data (i32.const 0) "{\"ok\":true}")
+
+  ;; a bump allocator, memory isn't freed as each call gets an instance
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+
+  (func $output (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+
+  ;; returns the input
+  (func (export "echo") (param $ptr i32) (param $len i32) (result i64)
+    (call $output (local.get $ptr) (local.get $len)))
+
+  ;; returns the input with ASCII letters in upper case
+  (func (export "upper") (param $ptr i32) (param $len i32) (result i64)
+    (local $i i32)
+    (local $c i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $c (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+        (if (i32.and (i32.ge_u (local.get $c) (i32.const 97)) (i32.le_u (local.get $c) (i32.const 122)))
+          (then (i32.store8 (i32.add (local.get $ptr) (local.get $i)) (i32.sub (local.get $c) (i32.const 32)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $output (local.get $ptr) (local.get $len)))
+
+  ;; returns {"ok":true}
+  (func (export "ok") (param i32 i32) (result i64)
+    (call $output (i32.const 0) (i32.const 11)))
+
+  ;; never returns
+  (func (export "spin") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const 0)))
@@ -0,0 +1,19 @@
+use crate::server::query_tag::QueryTag;
+
+#[test]
+fn comment_has_sorted_keys() {
+    let tag = QueryTag { request_id: "abc".to_owned(), model: Some("blog.Post".to_owned()), action: Some("findMany".to_owned()) };
+    assert_eq!(tag.comment(), "/*action='findMany',model='blog.Post',request_id='abc'*/");
+}
+
+#[test]
+fn unmatched_requests_have_only_an_id() {
+    let tag = QueryTag { request_id: "abc".to_owned(), model: None, action: None };
+    assert_eq!(tag.comment(), "/*request_id='abc'*/");
+}
+
+#[test]
+fn values_cannot_close_the_comment() {
+    let tag = QueryTag { request_id: "a*/ DROP TABLE users; '".to_owned(), model: None, action: None };
+    assert_eq!(tag.comment(), "/*request_id='a*%2F+DROP+TABLE+users%3B+%27'*/");
+}
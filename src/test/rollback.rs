@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use async_trait::async_trait;
+use educe::Educe;
+use key_path::KeyPath;
+use teo_result::Result;
+use teo_runtime::action::Action;
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::connection::transaction::{self, Transaction};
+use teo_runtime::model::{Model, Object};
+use teo_runtime::namespace::Namespace;
+use teo_runtime::request;
+use teo_runtime::Value;
+use crate::app::database::is_provider_connector;
+
+tokio::task_local! {
+    /// The transactions of the test body in progress, by the ids of their
+    /// connections. They are aborted when the body ends.
+    static TRANSACTIONS: RefCell<HashMap<usize, Arc<dyn Transaction>>>;
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the writes to the database of a namespace can be rolled back.
+/// SQL databases can, MongoDB and connector builders like the memory
+/// connector can't, as they have no savepoints.
+pub(super) fn supports_rollback(namespace: &Namespace) -> bool {
+    namespace.connector.as_ref().map_or(true, |connector| is_provider_connector(connector) && !connector.provider.is_mongo())
+}
+
+/// Wrap the connection of a namespace, so that a test body runs in a
+/// transaction of its own.
+pub(super) fn wrap_connection(namespace: &mut Namespace) {
+    if let Some(connection) = namespace.connection.take() {
+        namespace.connection = Some(Arc::new(RollbackConnection::new(connection)));
+    }
+}
+
+/// Run a test body in transactions which are aborted when it ends, even
+/// when it panics. The body must not be spawned on other tasks, as the
+/// transactions are found by a task local.
+pub(super) async fn with_rollback<Fut>(body: Fut) -> (Fut::Output, Result<()>) where Fut: Future {
+    TRANSACTIONS.scope(RefCell::new(HashMap::new()), async {
+        let output = body.await;
+        let transactions = TRANSACTIONS.with(|transactions| transactions.take());
+        let mut result = Ok(());
+        for transaction in transactions.into_values() {
+            if let Err(err) = transaction.abort().await {
+                result = Err(err);
+            }
+        }
+        (output, result)
+    }).await
+}
+
+/// A connection which runs the queries of a test body in a transaction,
+/// begun on first use and aborted when the body ends. Transactions of the
+/// app become savepoints of it, and queries without a transaction get a
+/// savepoint each, so a failed query only rolls back itself, like it does
+/// outside of the test transaction. Outside of a test body, queries go to
+/// the wrapped connection.
+#[derive(Educe)]
+#[educe(Debug)]
+struct RollbackConnection {
+    id: usize,
+    #[educe(Debug(ignore))]
+    connection: Arc<dyn Connection>,
+    savepoints: Arc<AtomicUsize>,
+}
+
+impl RollbackConnection {
+
+    fn new(connection: Arc<dyn Connection>) -> Self {
+        Self { id: NEXT_ID.fetch_add(1, Ordering::SeqCst), connection, savepoints: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// The transaction of the test body in progress.
+    async fn test_transaction(&self) -> Result<Option<Arc<dyn Transaction>>> {
+        let Ok(existing) = TRANSACTIONS.try_with(|transactions| transactions.borrow().get(&self.id).cloned()) else {
+            return Ok(None);
+        };
+        if let Some(transaction) = existing {
+            return Ok(Some(transaction));
+        }
+        let transaction = self.connection.transaction().await?;
+        TRANSACTIONS.with(|transactions| transactions.borrow_mut().insert(self.id, transaction.clone()));
+        Ok(Some(transaction))
+    }
+}
+
+#[async_trait]
+impl Connection for RollbackConnection {
+
+    async fn transaction(&self) -> Result<Arc<dyn Transaction>> {
+        match self.test_transaction().await? {
+            Some(transaction) => Ok(Arc::new(Savepoint::begin(transaction, self.savepoints.clone()).await?)),
+            None => self.connection.transaction().await,
+        }
+    }
+
+    async fn no_transaction(&self) -> Result<Arc<dyn Transaction>> {
+        match self.test_transaction().await? {
+            Some(transaction) => Ok(Arc::new(Savepoint::statements(transaction, self.savepoints.clone()))),
+            None => self.connection.no_transaction().await,
+        }
+    }
+}
+
+/// A transaction of the app in the test transaction.
+#[derive(Educe)]
+#[educe(Debug)]
+struct Savepoint {
+    #[educe(Debug(ignore))]
+    transaction: Arc<dyn Transaction>,
+    /// The savepoint committing and aborting release and roll back. Without
+    /// one, each query gets a savepoint of its own.
+    name: Option<String>,
+    savepoints: Arc<AtomicUsize>,
+    committed: AtomicBool,
+}
+
+impl Savepoint {
+
+    async fn begin(transaction: Arc<dyn Transaction>, savepoints: Arc<AtomicUsize>) -> Result<Self> {
+        let name = savepoint_name(&savepoints);
+        transaction.query_raw(&Value::String(format!("SAVEPOINT {}", name))).await?;
+        Ok(Self { transaction, name: Some(name), savepoints, committed: AtomicBool::new(false) })
+    }
+
+    fn statements(transaction: Arc<dyn Transaction>, savepoints: Arc<AtomicUsize>) -> Self {
+        Self { transaction, name: None, savepoints, committed: AtomicBool::new(false) }
+    }
+
+    /// Run a query, in a savepoint of its own without a transaction.
+    async fn query<T>(&self, query: impl Future<Output = Result<T>> + Send) -> Result<T> {
+        if self.name.is_some() {
+            return query.await;
+        }
+        let name = savepoint_name(&self.savepoints);
+        self.transaction.query_raw(&Value::String(format!("SAVEPOINT {}", name))).await?;
+        let result = query.await;
+        let end = if result.is_ok() { format!("RELEASE SAVEPOINT {}", name) } else { format!("ROLLBACK TO SAVEPOINT {}", name) };
+        self.transaction.query_raw(&Value::String(end)).await?;
+        result
+    }
+}
+
+fn savepoint_name(savepoints: &AtomicUsize) -> String {
+    format!("teo_test_{}", savepoints.fetch_add(1, Ordering::SeqCst))
+}
+
+#[async_trait]
+impl Transaction for Savepoint {
+
+    async fn migrate(&self, models: Vec<&Model>, dry_run: bool, reset_database: bool, silent: bool) -> Result<()> {
+        self.query(self.transaction.migrate(models, dry_run, reset_database, silent)).await
+    }
+
+    async fn purge(&self, models: Vec<&Model>) -> Result<()> {
+        self.query(self.transaction.purge(models)).await
+    }
+
+    async fn query_raw(&self, query: &Value) -> Result<Value> {
+        self.query(self.transaction.query_raw(query)).await
+    }
+
+    async fn save_object(&self, object: &Object) -> Result<()> {
+        self.query(self.transaction.save_object(object)).await
+    }
+
+    async fn delete_object(&self, object: &Object) -> Result<()> {
+        self.query(self.transaction.delete_object(object)).await
+    }
+
+    async fn find_unique(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> Result<Option<Object>> {
+        self.query(self.transaction.find_unique(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path)).await
+    }
+
+    async fn find_many(&self, model: &'static Model, finder: &Value, ignore_select_and_include: bool, action: Action, transaction_ctx: transaction::Ctx, req_ctx: Option<request::Ctx>, path: KeyPath) -> Result<Vec<Object>> {
+        self.query(self.transaction.find_many(model, finder, ignore_select_and_include, action, transaction_ctx, req_ctx, path)).await
+    }
+
+    async fn count(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Value> {
+        self.query(self.transaction.count(model, finder, transaction_ctx)).await
+    }
+
+    async fn count_objects(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<usize> {
+        self.query(self.transaction.count_objects(model, finder, transaction_ctx)).await
+    }
+
+    async fn count_fields(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Value> {
+        self.query(self.transaction.count_fields(model, finder, transaction_ctx)).await
+    }
+
+    async fn aggregate(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Value> {
+        self.query(self.transaction.aggregate(model, finder, transaction_ctx)).await
+    }
+
+    async fn group_by(&self, model: &'static Model, finder: &Value, transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        self.query(self.transaction.group_by(model, finder, transaction_ctx)).await
+    }
+
+    async fn sql(&self, model: &'static Model, sql: &str, transaction_ctx: transaction::Ctx) -> Result<Vec<Value>> {
+        self.query(self.transaction.sql(model, sql, transaction_ctx)).await
+    }
+
+    fn is_committed(&self) -> bool {
+        self.committed.load(Ordering::SeqCst)
+    }
+
+    fn is_transaction(&self) -> bool {
+        self.name.is_some()
+    }
+
+    async fn commit(&self) -> Result<()> {
+        if let Some(name) = &self.name {
+            self.transaction.query_raw(&Value::String(format!("RELEASE SAVEPOINT {}", name))).await?;
+        }
+        self.committed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        if let Some(name) = &self.name {
+            self.transaction.query_raw(&Value::String(format!("ROLLBACK TO SAVEPOINT {}", name))).await?;
+        }
+        Ok(())
+    }
+
+    async fn spawn(&self) -> Result<Arc<dyn Transaction>> {
+        Ok(Arc::new(Self::statements(self.transaction.clone(), self.savepoints.clone())))
+    }
+}
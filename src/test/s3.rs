@@ -0,0 +1,46 @@
+use chrono::Utc;
+use crate::archive::s3::S3Bucket;
+use crate::test::http::{response, MockServer};
+
+const FIRST_PAGE: &str = "<ListBucketResult><Contents><Key>Post/1.jsonl</Key></Contents><Contents><Key>Post/a&amp;b.jsonl</Key></Contents><NextContinuationToken>next+1</NextContinuationToken></ListBucketResult>";
+const LAST_PAGE: &str = "<ListBucketResult><Contents><Key>Post/2.jsonl</Key></Contents></ListBucketResult>";
+
+/// The tests share the process environment, so they run in one test which
+/// points the bucket to the mock server it starts.
+#[tokio::test]
+async fn requests_are_signed_path_style_requests() {
+    std::env::set_var("AWS_ACCESS_KEY_ID", "AKID");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "secret");
+    std::env::set_var("AWS_REGION", "eu-west-1");
+    let bucket = S3Bucket::new("archive");
+
+    let server = MockServer::start(vec![response(200, ""), response(200, "line\n")]).await;
+    std::env::set_var("AWS_ENDPOINT_URL", format!("{}/", server.url));
+    bucket.put("Post/a b.jsonl", b"line\n".to_vec()).await.unwrap();
+    assert_eq!(bucket.get("Post/a b.jsonl").await.unwrap(), b"line\n".to_vec());
+    let requests = server.requests();
+    assert!(requests[0].starts_with("PUT /archive/Post/a%20b.jsonl HTTP/1.1"), "{}", requests[0]);
+    assert!(requests[0].ends_with("\r\n\r\nline\n"), "{}", requests[0]);
+    assert!(requests[1].starts_with("GET /archive/Post/a%20b.jsonl HTTP/1.1"), "{}", requests[1]);
+    let authorization = requests[0].lines().find(|line| line.to_lowercase().starts_with("authorization:")).unwrap();
+    let scope = format!("Credential=AKID/{}/eu-west-1/s3/aws4_request", Utc::now().format("%Y%m%d"));
+    assert!(authorization.contains("AWS4-HMAC-SHA256"), "{}", authorization);
+    assert!(authorization.contains(&scope), "{}", authorization);
+    assert!(authorization.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date"), "{}", authorization);
+
+    let server = MockServer::start(vec![response(200, FIRST_PAGE), response(200, LAST_PAGE)]).await;
+    std::env::set_var("AWS_ENDPOINT_URL", &server.url);
+    assert_eq!(bucket.list("Post/").await.unwrap(), vec!["Post/1.jsonl", "Post/a&b.jsonl", "Post/2.jsonl"]);
+    let requests = server.requests();
+    assert!(requests[0].starts_with("GET /archive?list-type=2&prefix=Post%2F HTTP/1.1"), "{}", requests[0]);
+    assert!(requests[1].starts_with("GET /archive?continuation-token=next%2B1&list-type=2&prefix=Post%2F HTTP/1.1"), "{}", requests[1]);
+
+    let server = MockServer::start(vec![response(404, "<Error><Code>NoSuchKey</Code></Error>")]).await;
+    std::env::set_var("AWS_ENDPOINT_URL", &server.url);
+    let error = bucket.get("Post/3.jsonl").await.unwrap_err();
+    assert_eq!(error.code, 404);
+    assert_eq!(error.message, format!("s3: {}/archive/Post/3.jsonl responds with 404 Not Found", server.url));
+
+    std::env::remove_var("AWS_SECRET_ACCESS_KEY");
+    assert_eq!(bucket.get("Post/3.jsonl").await.unwrap_err().message, "s3: AWS_SECRET_ACCESS_KEY is not set");
+}
@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Arc;
+use crate::app::secrets::{builtin_secret_providers, resolve_secrets, SecretProvider};
+
+fn providers() -> BTreeMap<String, Arc<dyn SecretProvider>> {
+    let mut providers = builtin_secret_providers();
+    providers.insert("vault".to_owned(), Arc::new(|key: String| async move {
+        Ok((key == "db-password").then(|| "p@ss".to_owned()))
+    }));
+    providers
+}
+
+#[tokio::test]
+async fn urls_without_references_are_kept() {
+    let url = "postgres://app:plain@db/app";
+    assert_eq!(resolve_secrets(url, &providers()).await.unwrap(), url);
+}
+
+#[tokio::test]
+async fn env_file_and_custom_providers_are_resolved() {
+    std::env::set_var("TEO_TEST_SECRET_USER", "app");
+    let path = std::env::temp_dir().join(format!("teo-secret-{}", uuid::Uuid::new_v4()));
+    fs::write(&path, "db.internal\n").unwrap();
+    let url = format!("postgres://${{env:TEO_TEST_SECRET_USER}}:${{vault:db-password}}@${{file:{}}}/app", path.display());
+    let resolved = resolve_secrets(&url, &providers()).await;
+    fs::remove_file(&path).unwrap();
+    assert_eq!(resolved.unwrap(), "postgres://app:p@ss@db.internal/app");
+}
+
+#[tokio::test]
+async fn missing_secrets_are_reported_together() {
+    let url = "postgres://${env:TEO_TEST_SECRET_MISSING}:${vault:other}@${aws:host}/app";
+    let error = resolve_secrets(url, &providers()).await.unwrap_err();
+    assert_eq!(error.message, "`env:TEO_TEST_SECRET_MISSING` is not set, `vault:other` is not set, unknown secret provider `aws`");
+}
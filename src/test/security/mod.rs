@@ -0,0 +1,2144 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix_web::dev::ServiceResponse;
+use actix_web::test::{read_body, TestRequest};
+use chrono::Utc;
+use futures_util::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use ring::hmac;
+use serde_json::{json, Value as JsonValue};
+use teo_result::Error;
+use teo_runtime::arguments::Arguments;
+use teo_runtime::connection::connection::Connection;
+use teo_runtime::model::Object;
+use teo_runtime::pipeline::Ctx;
+use teo_runtime::request;
+use teo_runtime::response::Response;
+use teo_runtime::Value;
+use uuid::Uuid;
+use crate::advise::{advices, Advice, SHAPES_FILE};
+use crate::app::ctx::Ctx as AppCtx;
+use crate::app::database::{connection_for_connector, is_provider_connector, main_connection};
+use crate::app::database::memory::MemoryConnection;
+use crate::app::expiry::sweep_expired;
+use crate::app::scalar::ScalarCodec;
+use crate::archive::{archive, restore};
+use crate::events::consumer::start_consumers;
+use crate::events::outbox::{outbox_connections, relay, OUTBOX_TABLE};
+use crate::generate::hooks::{generate_hooks, HooksLibrary, HOOKS_FILE_NAME};
+use crate::generate::permissions::{generate_permissions, PERMISSIONS_FILE_NAME};
+use crate::generate::mobile::{generate_mobile_client, MobileLanguage};
+use crate::generate::scalars::{generate_scalars, SCALARS_FILE_NAME};
+use crate::generate::transport::{generate_transport, TRANSPORT_FILE_NAME};
+use crate::migrate::backfill::run_backfills;
+use crate::migrate::views::refresh_view;
+use crate::server::body_limit::DEFAULT_BODY_LIMIT;
+use crate::server::credentials::VerifiedCredential;
+use crate::server::envelope::{Envelope, META_HEADER};
+use crate::server::estimate::int;
+use crate::server::impersonation::{ACT_AS_HEADER, IMPERSONATIONS_TABLE};
+use crate::server::lockout::SignInLockout;
+use crate::server::magic_link::{MagicLink, MagicLinks};
+use crate::server::maintenance::MAINTENANCE_SECRET_HEADER;
+use crate::server::request_id::REQUEST_ID_HEADER;
+use crate::server::sequence::backfill_sequence;
+use crate::server::signature::{canonical_query, MemoryNonceStore, SIGNATURE_HEADER, SIGNATURE_KEY_HEADER, SIGNATURE_NONCE_HEADER, SIGNATURE_TIMESTAMP_HEADER};
+use crate::search::mapping;
+use crate::server::signed_url::sign_url;
+use crate::server::slug::SLUG_HISTORY_TABLE;
+use crate::stdlib::decorators::archive::model_archive;
+use crate::stdlib::decorators::expires::model_expiry;
+use crate::test::http::{response, MockServer};
+use crate::test::TestApp;
+use crate::utils::hex::{hex, sha256_hex};
+
+const SIGNING_SECRET: &str = "signing-secret";
+const PASSWORD: &str = "correct horse";
+
+/// The urls connected by the `recorded` connector builder.
+static CONNECTED: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// The inputs the `orderCreated` handler is called with.
+static CONSUMED: Lazy<Mutex<Vec<Value>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// The payloads published through the `test` event sink.
+static PUBLISHED: Lazy<Mutex<Vec<JsonValue>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// The magic links handed to the sender.
+static MAGIC_LINKS: Lazy<Mutex<Vec<MagicLink>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// Only one app can exist in a process, so the cases share one and run one
+/// after another, each with a database of its own.
+#[tokio::test]
+async fn security() {
+    let schema = Path::new(file!()).parent().unwrap().join("schema.teo");
+    let app = TestApp::new_with(schema.to_str().unwrap(), |app| {
+        app.pipeline_item("slugify", |args: Arguments, ctx: Ctx| async move {
+            let separator: Option<String> = args.get_optional("separator")?;
+            let Some(string) = ctx.value().as_str() else {
+                Err(Error::new("slugify: value is not a string"))?
+            };
+            let string = string.to_owned();
+            // custom items may await, like the builtin ones
+            tokio::task::yield_now().await;
+            let words: Vec<String> = string.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect();
+            Ok(Value::String(words.join(separator.as_deref().unwrap_or("-"))))
+        });
+        // renames `displayName`, derives `initials` and strips `secret` but
+        // for admins
+        app.pipeline_item("shapeProfile", |_args: Arguments, ctx: Ctx| async move {
+            let Value::Dictionary(mut record) = ctx.value().clone() else {
+                Err(Error::new("shapeProfile: value is not a dictionary"))?
+            };
+            let admin = ctx.request().and_then(|request| request.headers().get("x-role").map(|role| role == "admin")).unwrap_or(false);
+            let name = record.shift_remove("displayName").unwrap_or(Value::Null);
+            if name.as_str() == Some("broken") {
+                return Ok(Value::Null);
+            }
+            let initials: String = name.as_str().unwrap_or("").split_whitespace().filter_map(|word| word.chars().next()).collect();
+            record.insert("name".to_owned(), name);
+            record.insert("initials".to_owned(), Value::String(initials));
+            if !admin {
+                record.shift_remove("secret");
+            }
+            Ok(Value::Dictionary(record))
+        });
+        app.scalar("PhoneNumber", PhoneNumberCodec);
+        // the audit table is created by migrations
+        app.impersonation_role("support");
+        // the magic links table is created by migrations
+        app.magic_links(magic_links(Duration::from_secs(60)), record_magic_link);
+    }).await.unwrap();
+    app.app().signing_key("test-key", SIGNING_SECRET);
+    app.app().url_signing_secret("url-secret");
+    app.app().sessions(true);
+    app.app().sign_in_lockout(SignInLockout { max_attempts_per_account: 3, max_attempts_per_ip: 5, ..SignInLockout::default() });
+    // `google` proofs are valid when they are `valid:<subject>`
+    app.app().credential_verifier("google", |proof: JsonValue| async move {
+        Ok::<_, Error>(proof["idToken"].as_str().and_then(|token| token.strip_prefix("valid:")).map(|subject| VerifiedCredential { subject: subject.to_owned(), fields: Default::default() }))
+    });
+    app.app().event_sink("test", |_topic: String, payload: String| async move {
+        PUBLISHED.lock().unwrap().push(serde_json::from_str(&payload).unwrap());
+        Ok(())
+    });
+    app.run(|| signature_replay(&app)).await.unwrap();
+    app.run(|| signed_url_expiry(&app)).await.unwrap();
+    app.run(|| session_revocation(&app)).await.unwrap();
+    app.run(|| lockout(&app)).await.unwrap();
+    app.run(|| nested_write_rollback(&app)).await.unwrap();
+    app.run(|| idempotent_replay(&app)).await.unwrap();
+    app.run(|| change_event_redaction(&app)).await.unwrap();
+    app.run(|| outbox_dead_letter(&app)).await.unwrap();
+    app.run(|| logical_combinators(&app)).await.unwrap();
+    app.run(|| status_transitions(&app)).await.unwrap();
+    app.run(|| custom_pipeline_item(&app)).await.unwrap();
+    app.run(|| sibling_and_previous_values(&app)).await.unwrap();
+    app.run(|| malformed_bodies(&app)).await.unwrap();
+    app.run(|| request_ids(&app)).await.unwrap();
+    app.run(|| etags(&app)).await.unwrap();
+    app.run(|| body_limits(&app)).await.unwrap();
+    app.run(|| nested_projection(&app)).await.unwrap();
+    app.run(|| date_buckets(&app)).await.unwrap();
+    app.run(|| similar_filter(&app)).await.unwrap();
+    app.run(|| nearest_vectors(&app)).await.unwrap();
+    app.run(|| react_hooks()).await.unwrap();
+    app.run(|| client_transport(&app)).await.unwrap();
+    app.run(|| mobile_clients()).await.unwrap();
+    app.run(|| debug_timings(&app)).await.unwrap();
+    app.run(|| index_advice(&app)).await.unwrap();
+    app.run(|| backfills(&app)).await.unwrap();
+    app.run(|| expiring_records(&app)).await.unwrap();
+    app.run(|| view_models(&app)).await.unwrap();
+    app.run(|| custom_connectors(&app)).await.unwrap();
+    app.run(|| search_index(&app)).await.unwrap();
+    app.run(|| message_consumers(&app)).await.unwrap();
+    app.run(|| json_rpc(&app)).await.unwrap();
+    app.run(|| offline_sync(&app)).await.unwrap();
+    app.run(|| permissions(&app)).await.unwrap();
+    app.run(|| impersonation(&app)).await.unwrap();
+    app.run(|| personal_data(&app)).await.unwrap();
+    app.run(|| archival(&app)).await.unwrap();
+    app.run(|| output_pipelines(&app)).await.unwrap();
+    app.run(|| custom_scalars(&app)).await.unwrap();
+    app.run(|| money_fields(&app)).await.unwrap();
+    app.run(|| slug_fields(&app)).await.unwrap();
+    app.run(|| request_context(&app)).await.unwrap();
+    app.run(|| trusted_proxies(&app)).await.unwrap();
+    app.run(|| maintenance_mode(&app)).await.unwrap();
+    app.run(|| sequence_fields(&app)).await.unwrap();
+    app.run(|| duplicates(&app)).await.unwrap();
+    app.run(|| merging(&app)).await.unwrap();
+    app.run(|| tree_queries(&app)).await.unwrap();
+    app.run(|| positions(&app)).await.unwrap();
+    app.run(|| tagging(&app)).await.unwrap();
+    app.run(|| named_queries(&app)).await.unwrap();
+    app.run(|| response_envelopes(&app)).await.unwrap();
+    app.run(|| partial_updates(&app)).await.unwrap();
+    app.run(|| deep_nested_writes(&app)).await.unwrap();
+    app.run(|| nested_upserts(&app)).await.unwrap();
+    app.run(|| relation_pruning(&app)).await.unwrap();
+    app.run(|| generated_columns(&app)).await.unwrap();
+    app.run(|| query_tags(&app)).await.unwrap();
+    app.run(|| magic_link_sign_in(&app)).await.unwrap();
+    app.run(|| credential_linking(&app)).await.unwrap();
+    app.run(|| input_normalization(&app)).await.unwrap();
+    app.run(|| constraint_rules(&app)).await.unwrap();
+    app.run(|| validate_action(&app)).await.unwrap();
+    app.run(|| find_by_ids(&app)).await.unwrap();
+    app.run(|| export_cursors(&app)).await.unwrap();
+    app.run(|| estimated_counts(&app)).await.unwrap();
+}
+
+async fn signature_replay(app: &TestApp) {
+    let signed_with = |key: &str, timestamp: i64, nonce: &str, query: &str, signed_body: &str, body: &str| {
+        let path = app.uri("/Note/findMany");
+        let message = format!("{}\n{}\nPOST\n{}\n{}\n{}", timestamp, nonce, path, canonical_query(query), sha256_hex(signed_body.as_bytes()));
+        let signature = hex(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, SIGNING_SECRET.as_bytes()), message.as_bytes()).as_ref());
+        let uri = if query.is_empty() { path } else { format!("{}?{}", path, query) };
+        TestRequest::post().uri(&uri)
+            .insert_header(("Content-Type", "application/json"))
+            .insert_header((SIGNATURE_KEY_HEADER, key.to_owned()))
+            .insert_header((SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string()))
+            .insert_header((SIGNATURE_NONCE_HEADER, nonce.to_owned()))
+            .insert_header((SIGNATURE_HEADER, signature))
+            .set_payload(body.to_owned())
+    };
+    let signed = |timestamp: i64, nonce: &str| signed_with("test-key", timestamp, nonce, "", "{}", "{}");
+    let now = Utc::now().timestamp();
+    assert_eq!(send(app, signed(now, "nonce-1")).await.0, 200);
+    let (status, response) = send(app, signed(now, "nonce-1")).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("SIGNATURE_REPLAYED"));
+    let (status, response) = send(app, signed(now - 600, "nonce-2")).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("SIGNATURE_EXPIRED"));
+    // the query is signed in its canonical order
+    assert_eq!(canonical_query("b=2&a=1&a=0&q=a%20b"), "a=0&a=1&b=2&q=a+b");
+    assert_eq!(send(app, signed_with("test-key", now, "nonce-3", "b=2&a=1", "{}", "{}")).await.0, 200);
+    let (_, response) = send(app, signed_with("test-key", now, "nonce-4", "", "{}", r#"{"take":1}"#)).await;
+    assert_eq!(error_code(&response), Some("SIGNATURE_INVALID"));
+    let (_, response) = send(app, signed_with("other-key", now, "nonce-5", "", "{}", "{}")).await;
+    assert_eq!(error_code(&response), Some("SIGNING_KEY_UNKNOWN"));
+    app.app().require_signed_requests(true);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Note/findMany")).set_json(json!({}))).await;
+    app.app().require_signed_requests(false);
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("SIGNATURE_REQUIRED"));
+    // a plugged in store decides which nonces are new
+    let remembered = Arc::new(Mutex::new(vec![]));
+    let store = remembered.clone();
+    app.app().nonce_store(move |key: String, ttl: Duration| {
+        let mut remembered = store.lock().unwrap();
+        let new = !remembered.contains(&(key.clone(), ttl));
+        remembered.push((key, ttl));
+        async move { Ok(new) }
+    });
+    assert_eq!(send(app, signed(now, "nonce-1")).await.0, 200);
+    let (_, response) = send(app, signed(now, "nonce-1")).await;
+    AppCtx::set_nonce_store(MemoryNonceStore);
+    assert_eq!(error_code(&response), Some("SIGNATURE_REPLAYED"));
+    assert_eq!(*remembered.lock().unwrap(), vec![("test-key:nonce-1".to_owned(), Duration::from_secs(600)); 2]);
+}
+
+async fn signed_url_expiry(app: &TestApp) {
+    let url = sign_url(&app.uri("/Note/findMany"), Some(&json!({})), Duration::from_secs(60)).unwrap();
+    assert_eq!(send(app, TestRequest::get().uri(&url)).await.0, 200);
+    let url = sign_url(&app.uri("/Note/findMany"), Some(&json!({})), Duration::ZERO).unwrap();
+    // the expiry has a precision of seconds
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let (status, response) = send(app, TestRequest::get().uri(&url)).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("URL_SIGNATURE_EXPIRED"));
+    let url = sign_url(&app.uri("/Note/findMany"), Some(&json!({})), Duration::from_secs(60)).unwrap();
+    let tampered = url.replace("_expires=", "_expires=9");
+    let (status, response) = send(app, TestRequest::get().uri(&tampered)).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("URL_SIGNATURE_INVALID"));
+    // the guard of `findUnique` is skipped for signed urls only
+    let memo = app.req("Memo", "findMany", json!({})).await;
+    assert_eq!(memo["data"], json!([]));
+    let signed_in = {
+        create_user(app, "signed-url@example.com").await;
+        let (_, response) = send(app, sign_in(app, "signed-url@example.com", PASSWORD, "10.0.5.1")).await;
+        response["meta"]["token"].as_str().unwrap().to_owned()
+    };
+    let (_, created) = send(app, TestRequest::post().uri(&app.uri("/Memo/create")).insert_header(("Authorization", format!("Bearer {}", signed_in))).set_json(json!({ "create": { "body": "shared" } }))).await;
+    let finder = json!({ "where": { "id": created["data"]["id"] } });
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Memo/findUnique")).set_json(finder.clone())).await;
+    assert_eq!(status, 401);
+    let url = sign_url(&app.uri("/Memo/findUnique"), Some(&finder), Duration::from_secs(60)).unwrap();
+    let (status, response) = send(app, TestRequest::get().uri(&url)).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["data"]["body"], "shared");
+    // `$signedUrl` signs a path for the same access
+    let share = app.req("Share", "create", json!({ "create": { "link": "Note/findMany" } })).await;
+    let link = share["data"]["link"].as_str().unwrap();
+    assert!(link.starts_with(&format!("{}?_expires=", app.uri("/Note/findMany"))), "{}", link);
+    assert_eq!(send(app, TestRequest::get().uri(link)).await.0, 200);
+}
+
+async fn session_revocation(app: &TestApp) {
+    create_user(app, "session@example.com").await;
+    let mut tokens = vec![];
+    for user_agent in ["Phone\u{7}App/1.0", "Laptop"] {
+        let (status, response) = send(app, sign_in(app, "session@example.com", PASSWORD, "10.0.0.1").insert_header(("User-Agent", user_agent))).await;
+        assert_eq!(status, 200);
+        tokens.push(response["meta"]["token"].as_str().unwrap().to_owned());
+    }
+    let authorized = |path: &str, token: &str, body: JsonValue| TestRequest::post().uri(&app.uri(path)).insert_header(("Authorization", format!("Bearer {}", token))).set_json(body);
+    assert_eq!(send(app, authorized("/Note/findMany", &tokens[0], json!({}))).await.0, 200);
+    let (_, response) = send(app, authorized("/_sessions/list", &tokens[0], json!({}))).await;
+    let sessions = response["data"].as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    // control characters of the user agent are dropped
+    let phone = sessions.iter().find(|session| session["device"] == "PhoneApp/1.0").unwrap();
+    let laptop = sessions.iter().find(|session| session["device"] == "Laptop").unwrap();
+    assert_eq!((phone["current"].as_bool(), laptop["current"].as_bool()), (Some(true), Some(false)));
+    let (status, _) = send(app, authorized("/_sessions/revoke", &tokens[0], json!({ "id": laptop["id"] }))).await;
+    assert_eq!(status, 200);
+    let (status, response) = send(app, authorized("/Note/findMany", &tokens[1], json!({}))).await;
+    assert_eq!((status, error_code(&response)), (401, Some("SESSION_REVOKED")));
+    let (_, response) = send(app, authorized("/_sessions/list", &tokens[0], json!({}))).await;
+    assert_eq!(response["data"].as_array().unwrap().len(), 1);
+    assert_eq!(send(app, authorized("/_sessions/revokeAll", &tokens[0], json!({}))).await.0, 200);
+    let (status, response) = send(app, authorized("/Note/findMany", &tokens[0], json!({}))).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("SESSION_REVOKED"));
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/_sessions/list")).set_json(json!({}))).await;
+    assert_eq!((status, error_code(&response)), (401, Some("IDENTITY_REQUIRED")));
+}
+
+async fn lockout(app: &TestApp) {
+    create_user(app, "lockout@example.com").await;
+    for _ in 0..3 {
+        let (status, _) = send(app, sign_in(app, "lockout@example.com", "wrong", "10.0.0.2")).await;
+        assert!((400..500).contains(&status) && status != 423);
+    }
+    let (status, response) = send(app, sign_in(app, "lockout@example.com", PASSWORD, "10.0.0.3")).await;
+    assert_eq!(status, 423);
+    assert_eq!(error_code(&response), Some("ACCOUNT_LOCKED"));
+    for i in 0..5 {
+        let (status, _) = send(app, sign_in(app, &format!("nobody{}@example.com", i), "wrong", "10.0.0.4")).await;
+        assert!((400..500).contains(&status) && status != 429);
+    }
+    let (status, response) = send(app, sign_in(app, "nobody5@example.com", "wrong", "10.0.0.4")).await;
+    assert_eq!(status, 429);
+    assert_eq!(error_code(&response), Some("TOO_MANY_SIGN_IN_ATTEMPTS"));
+}
+
+async fn nested_write_rollback(app: &TestApp) {
+    let response = app.req("Author", "create", json!({
+        "create": {
+            "name": "Ada",
+            "posts": {
+                "create": [{ "slug": "same" }, { "slug": "same" }],
+            },
+        },
+    })).await;
+    assert!(response.get("error").is_some());
+    assert_eq!(app.req("Author", "count", json!({})).await, json!({ "data": 0 }));
+    assert_eq!(app.req("Post", "count", json!({})).await, json!({ "data": 0 }));
+}
+
+async fn idempotent_replay(app: &TestApp) {
+    let create = |title: &str| TestRequest::post().uri(&app.uri("/Note/create")).insert_header(("Idempotency-Key", "note-1")).set_json(json!({ "create": { "title": title } }));
+    let first = app.call(create("first").to_request()).await;
+    assert_eq!(first.status().as_u16(), 200);
+    assert!(first.headers().get("Idempotent-Replayed").is_none());
+    let first = read_body(first).await;
+    let replayed = app.call(create("first").to_request()).await;
+    assert_eq!(replayed.status().as_u16(), 200);
+    assert_eq!(replayed.headers().get("Idempotent-Replayed").and_then(|v| v.to_str().ok()), Some("true"));
+    assert_eq!(read_body(replayed).await, first);
+    assert_eq!(app.req("Note", "count", json!({})).await, json!({ "data": 1 }));
+    assert_eq!(send(app, create("second")).await.0, 422);
+    // responses are only replayed to the client they were made for
+    create_user(app, "idempotency@example.com").await;
+    let (_, response) = send(app, sign_in(app, "idempotency@example.com", PASSWORD, "10.0.0.5")).await;
+    let token = response["meta"]["token"].as_str().unwrap().to_owned();
+    let other = app.call(create("first").insert_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+    assert_eq!(other.status().as_u16(), 200);
+    assert!(other.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(app.req("Note", "count", json!({})).await, json!({ "data": 2 }));
+}
+
+async fn change_event_redaction(app: &TestApp) {
+    create_user(app, "events@example.com").await;
+    let mut event = None;
+    // events are published in the background
+    for _ in 0..50 {
+        event = PUBLISHED.lock().unwrap().iter().find(|e| e["data"]["email"] == "events@example.com").cloned();
+        if event.is_some() { break }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let event = event.expect("the change event is not published");
+    assert_eq!(event["model"], "User");
+    assert_eq!(event["action"], "save");
+    assert!(event["data"].get("password").is_none());
+}
+
+async fn outbox_dead_letter(app: &TestApp) {
+    // the sink of refunds isn't registered, so their events always fail
+    assert!(app.req("Refund", "create", json!({ "create": { "number": "R1" } })).await.get("data").is_some());
+    assert!(app.req("Order", "create", json!({ "create": { "number": "O1" } })).await.get("data").is_some());
+    let (connection, database) = outbox_connections().unwrap().pop().unwrap();
+    assert_eq!(relay(connection.clone(), &database).await.unwrap(), 1);
+    assert!(PUBLISHED.lock().unwrap().iter().any(|e| e["model"] == "Order" && e["data"]["number"] == "O1"));
+    for _ in 1..10 {
+        assert_eq!(relay(connection.clone(), &database).await.unwrap(), 0);
+    }
+    let rows = connection.no_transaction().await.unwrap().query_raw(&Value::String(format!(
+        "SELECT attempts, last_error, dead_at FROM {} WHERE published_at IS NULL", OUTBOX_TABLE,
+    ))).await.unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get("attempts").and_then(int), Some(10));
+    assert!(rows[0].get("last_error").and_then(|e| e.as_str()).unwrap().contains("missing"));
+    assert!(!rows[0].get("dead_at").unwrap().is_null());
+}
+
+async fn logical_combinators(app: &TestApp) {
+    let create = |email: &str, reach: &str, nickname: &str| app.req("Contact", "create", json!({
+        "create": { "email": email, "reach": reach, "nickname": nickname },
+    }));
+    assert!(create("ada@example.com", "ada@example.com", "ada").await.get("data").is_some());
+    assert!(create("ada@example.com", "12345", "ada").await.get("data").is_some());
+    // `allOf` fails with the error of the validator which fails
+    let response = create("ada@example.org", "12345", "ada").await;
+    assert!(response["error"].to_string().contains("@example.com"));
+    let response = create("ada", "12345", "ada").await;
+    assert!(response.get("error").is_some());
+    // `anyOf` fails when every validator fails
+    let response = create("ada@example.com", "ada", "ada").await;
+    assert!(response["error"].to_string().contains("none of the conditions of `anyOf` is met"));
+    // `not` fails when its validator passes
+    let response = create("ada@example.com", "12345", "ada@example.com").await;
+    assert!(response["error"].to_string().contains("the condition of `not` is met"));
+    assert_eq!(app.req("Contact", "count", json!({})).await, json!({ "data": 2 }));
+}
+
+async fn status_transitions(app: &TestApp) {
+    let id = app.req("Article", "create", json!({ "create": {} })).await["data"]["id"].clone();
+    let update = |status: &str| app.req("Article", "update", json!({ "where": { "id": id }, "update": { "status": status } }));
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Article/update")).set_json(json!({
+        "where": { "id": id }, "update": { "status": "archived" },
+    }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(error_code(&response), Some("INVALID_TRANSITION"));
+    assert!(response["error"]["errors"]["status"].as_str().unwrap().contains("from `draft` to `archived`"));
+    assert_eq!(update("draft").await["data"]["status"], "draft");
+    assert_eq!(update("published").await["data"]["status"], "published");
+    assert_eq!(update("archived").await["data"]["status"], "archived");
+    assert_eq!(error_code(&update("draft").await), Some("INVALID_TRANSITION"));
+}
+
+async fn custom_pipeline_item(app: &TestApp) {
+    let response = app.req("Page", "create", json!({ "create": { "slug": "Hello, World!", "key": "Hello, World!" } })).await;
+    assert_eq!(response["data"]["slug"], "hello-world");
+    assert_eq!(response["data"]["key"], "hello_world");
+    let response = app.req("Page", "create", json!({ "create": { "slug": 5, "key": "five" } })).await;
+    assert!(response.get("error").is_some());
+}
+
+async fn sibling_and_previous_values(app: &TestApp) {
+    let response = app.req("Revision", "create", json!({ "create": { "title": "first" } })).await;
+    assert_eq!(response["data"]["current"], "first");
+    assert!(response["data"]["former"].is_null());
+    let id = response["data"]["id"].clone();
+    let response = app.req("Revision", "update", json!({ "where": { "id": id }, "update": { "title": "second" } })).await;
+    assert_eq!(response["data"]["current"], "second");
+    assert_eq!(response["data"]["former"], "first");
+}
+
+async fn malformed_bodies(app: &TestApp) {
+    let post = |payload: &'static str| TestRequest::post().uri(&app.uri("/Note/findMany"))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload(payload);
+    let (status, response) = send(app, post("{")).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "incorrect json format");
+    let (status, response) = send(app, post("[]")).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect json root object");
+    // the worker is still serving
+    assert_eq!(send(app, post("{}")).await.0, 200);
+}
+
+async fn request_ids(app: &TestApp) {
+    let post = |payload: &'static str| TestRequest::post().uri(&app.uri("/Note/findMany"))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload(payload);
+    let request_id = |response: &ServiceResponse| response.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).map(|s| s.to_owned());
+    let response = app.call(post("{}").insert_header((REQUEST_ID_HEADER, "trace-1")).to_request()).await;
+    assert_eq!(request_id(&response).as_deref(), Some("trace-1"));
+    for request in [post("{}"), post("{}").insert_header((REQUEST_ID_HEADER, "not acceptable"))] {
+        let response = app.call(request.to_request()).await;
+        assert!(Uuid::parse_str(&request_id(&response).unwrap()).is_ok());
+    }
+    let response = app.call(post("{").insert_header((REQUEST_ID_HEADER, "trace-2")).to_request()).await;
+    assert_eq!(request_id(&response).as_deref(), Some("trace-2"));
+    let body: JsonValue = serde_json::from_slice(&read_body(response).await).unwrap();
+    assert_eq!(body["error"]["requestId"], "trace-2");
+}
+
+async fn etags(app: &TestApp) {
+    let response = app.req("Note", "create", json!({ "create": { "title": "first" } })).await;
+    let id = response["data"]["id"].clone();
+    let request = |action: &str, body: JsonValue, header: Option<(&'static str, &str)>| {
+        let request = TestRequest::post().uri(&app.uri(&format!("/Note/{}", action))).set_json(body);
+        match header {
+            Some((name, value)) => request.insert_header((name, value.to_owned())),
+            None => request,
+        }
+    };
+    let find = |header: Option<&str>| request("findUnique", json!({ "where": { "id": id } }), header.map(|value| ("If-None-Match", value)));
+    let update = |title: &str, header: &str| request("update", json!({ "where": { "id": id }, "update": { "title": title } }), Some(("If-Match", header)));
+    let etag = |response: &ServiceResponse| response.headers().get("ETag").and_then(|v| v.to_str().ok()).map(|s| s.to_owned()).unwrap();
+    let first = etag(&app.call(find(None).to_request()).await);
+    assert!(first.starts_with("W/\""), "{}", first);
+    let not_modified = app.call(find(Some(&first)).to_request()).await;
+    assert_eq!(not_modified.status().as_u16(), 304);
+    assert_eq!(etag(&not_modified), first);
+    assert_eq!(send(app, update("second", "W/\"stale\"")).await.0, 412);
+    assert_eq!(send(app, update("second", &first)).await.0, 200);
+    let modified = app.call(find(Some(&first)).to_request()).await;
+    assert_eq!(modified.status().as_u16(), 200);
+    assert_ne!(etag(&modified), first);
+    assert_eq!(send(app, update("third", &first)).await.0, 412);
+    assert_eq!(send(app, request("delete", json!({ "where": { "id": id } }), Some(("If-Match", &first)))).await.0, 412);
+    assert_eq!(app.req("Note", "count", json!({})).await, json!({ "data": 1 }));
+}
+
+async fn body_limits(app: &TestApp) {
+    let find_many = || TestRequest::post().uri(&app.uri("/Note/findMany")).set_json(json!({
+        "where": { "title": { "contains": "a".repeat(64) } },
+    }));
+    app.app().body_limit("findMany", 32);
+    let (status, response) = send(app, find_many()).await;
+    assert_eq!(status, 413);
+    assert_eq!(response["error"]["message"], "request body is too large, the limit is 32 bytes");
+    // the override of the model wins over the one of every model
+    app.app().body_limit("Note.findMany", 1024);
+    assert_eq!(send(app, find_many()).await.0, 200);
+    app.app().body_limit("findMany", DEFAULT_BODY_LIMIT);
+    app.app().body_limit("Note.findMany", DEFAULT_BODY_LIMIT);
+}
+
+async fn nested_projection(app: &TestApp) {
+    let response = app.req("Author", "create", json!({
+        "create": { "name": "Ada", "posts": { "create": [{ "slug": "first" }, { "slug": "second" }] } },
+    })).await;
+    assert!(response.get("data").is_some());
+    let keys = |value: &JsonValue| {
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    };
+    let response = app.req("Author", "findMany", json!({
+        "select": { "name": true },
+        "include": { "posts": { "omit": { "authorId": true }, "orderBy": { "slug": "asc" } } },
+    })).await;
+    let author = &response["data"][0];
+    assert_eq!(keys(author), vec!["name", "posts"]);
+    assert_eq!(author["posts"].as_array().unwrap().len(), 2);
+    assert_eq!(keys(&author["posts"][0]), vec!["id", "slug"]);
+    assert_eq!(author["posts"][0]["slug"], "first");
+    // a select with only `false` values excludes fields
+    let response = app.req("Post", "findMany", json!({
+        "select": { "authorId": false },
+        "include": { "author": { "select": { "posts": false, "id": false } } },
+    })).await;
+    assert_eq!(keys(&response["data"][0]), vec!["author", "id", "slug"]);
+    assert_eq!(response["data"][0]["author"], json!({ "name": "Ada" }));
+}
+
+async fn date_buckets(app: &TestApp) {
+    let mut taken_at = vec![];
+    // Monday, Wednesday, the next Monday and a Thursday in February
+    for (sensor, date, value) in [("a", "2024-01-01T10:00:00.000Z", 1.0), ("a", "2024-01-03T23:59:00.000Z", 3.0), ("b", "2024-01-02T00:00:00.000Z", 5.0), ("a", "2024-01-08T00:00:00.000Z", 2.0), ("a", "2024-02-01T12:00:00.000Z", 4.0)] {
+        let response = app.req("Reading", "create", json!({ "create": { "sensor": sensor, "takenAt": date, "value": value } })).await;
+        taken_at.push(response["data"]["takenAt"].clone());
+    }
+    let group_by = |unit: &str| app.req("Reading", "groupBy", json!({
+        "by": ["sensor"],
+        "where": { "value": { "lt": 5 } },
+        "bucket": { "field": "takenAt", "unit": unit },
+        "_count": { "_all": true },
+        "_min": { "takenAt": true },
+        "_max": { "value": true },
+    }));
+    let response = group_by("week").await;
+    let groups = response["data"].as_array().unwrap();
+    let buckets: Vec<(&str, &str, u64)> = groups.iter().map(|g| (g["bucket"].as_str().unwrap(), g["sensor"].as_str().unwrap(), g["_count"]["_all"].as_u64().unwrap())).collect();
+    assert_eq!(buckets, vec![
+        ("2024-01-01T00:00:00.000Z", "a", 2),
+        ("2024-01-08T00:00:00.000Z", "a", 1),
+        ("2024-01-29T00:00:00.000Z", "a", 1),
+    ]);
+    assert_eq!(groups[0]["_min"]["takenAt"], taken_at[0]);
+    assert_eq!(groups[0]["_max"]["value"].as_f64(), Some(3.0));
+    let response = group_by("month").await;
+    let buckets: Vec<&str> = response["data"].as_array().unwrap().iter().map(|g| g["bucket"].as_str().unwrap()).collect();
+    assert_eq!(buckets, vec!["2024-01-01T00:00:00.000Z", "2024-02-01T00:00:00.000Z"]);
+    assert_eq!(response["data"][0]["_count"]["_all"], 3);
+    let response = group_by("year").await;
+    assert_eq!(response["error"]["message"], "expect `bucket.unit` to be one of day, week and month");
+}
+
+async fn similar_filter(app: &TestApp) {
+    for title in ["Teo", "Theo", "Leo", "Rust"] {
+        assert!(app.req("Note", "create", json!({ "create": { "title": title } })).await.get("data").is_some());
+    }
+    let find = |threshold: Option<f64>, skip: u64| {
+        let mut similar = json!({ "value": "teo" });
+        if let Some(threshold) = threshold {
+            similar["threshold"] = json!(threshold);
+        }
+        app.req("Note", "findMany", json!({ "where": { "title": { "similar": similar } }, "orderBy": { "title": "asc" }, "skip": skip, "take": 2 }))
+    };
+    let titles = |response: &JsonValue| response["data"].as_array().unwrap().iter().map(|n| n["title"].as_str().unwrap().to_owned()).collect::<Vec<String>>();
+    // pagination is applied to the matching records
+    let response = find(Some(0.6), 0).await;
+    assert_eq!(titles(&response), vec!["Leo", "Teo"]);
+    assert_eq!(response["meta"]["count"], 3);
+    assert_eq!(titles(&find(Some(0.6), 2).await), vec!["Theo"]);
+    assert_eq!(titles(&find(Some(0.7), 0).await), vec!["Teo", "Theo"]);
+    // the default threshold is the one of pg_trgm
+    assert_eq!(find(None, 0).await["meta"]["count"], 3);
+}
+
+async fn nearest_vectors(app: &TestApp) {
+    let response = app.req("Document", "create", json!({ "create": { "title": "flat", "embedding": [1.0, 0.0] } })).await;
+    assert!(response.to_string().contains("expect a vector of 3 dimensions, found 2"), "{}", response);
+    for (title, embedding) in [("a", [1.0, 0.0, 0.0]), ("b", [0.0, 1.0, 0.0]), ("c", [1.0, 1.0, 0.0]), ("d", [3.0, 0.0, 1.0])] {
+        assert!(app.req("Document", "create", json!({ "create": { "title": title, "embedding": embedding } })).await.get("data").is_some());
+    }
+    let nearest = |input: JsonValue| app.req("Document", "findMany", json!({ "nearestTo": { "embedding": input }, "take": 3 }));
+    let titles = |response: &JsonValue| response["data"].as_array().unwrap().iter().map(|d| d["title"].as_str().unwrap().to_owned()).collect::<Vec<String>>();
+    let response = nearest(json!({ "vector": [1, 0, 0] })).await;
+    assert_eq!(titles(&response), vec!["a", "d", "c"]);
+    assert_eq!(response["meta"]["count"], 4);
+    assert_eq!(titles(&nearest(json!({ "vector": [1, 0, 0], "metric": "euclidean", "take": 2 })).await), vec!["a", "c"]);
+    assert_eq!(titles(&nearest(json!({ "vector": [1, 0, 0], "metric": "dotProduct", "take": 1 })).await), vec!["d"]);
+    let response = nearest(json!({ "vector": [1, 0, 0], "metric": "manhattan" })).await;
+    assert_eq!(response["error"]["message"], "unknown metric `manhattan`, expect cosine, euclidean or dotProduct");
+}
+
+async fn react_hooks() {
+    let dest = std::env::temp_dir().join(format!("teo-hooks-test-{}", Uuid::new_v4()));
+    let generate = |library: &str| {
+        generate_hooks(AppCtx::main_namespace(), &dest, HooksLibrary::from_name(library).unwrap()).unwrap();
+        std::fs::read_to_string(dest.join(HOOKS_FILE_NAME)).unwrap()
+    };
+    let react_query = generate("react-query");
+    assert!(react_query.contains("from \"@tanstack/react-query\""));
+    assert!(react_query.contains(r#"export function useNoteFindMany(input: Parameters<Teo["note"]["findMany"]>[0]) {
+    const teo = useTeo()
+    return useQuery({ queryKey: teoQueryKey("Note", "findMany", input), queryFn: () => teo.note.findMany(input) })
+}"#), "{}", react_query);
+    assert!(react_query.contains("export function useNoteCreate() {"));
+    assert!(react_query.contains(r#"onSuccess: () => queryClient.invalidateQueries({ queryKey: ["Note"] }),"#));
+    let swr = generate("swr");
+    assert!(swr.contains("import useSWRMutation from \"swr/mutation\""));
+    assert!(swr.contains(r#"return useSWR(input === null ? null : teoQueryKey("Note", "findMany", input), () => teo.note.findMany(input!))"#), "{}", swr);
+    assert!(swr.contains(r#"await mutate((cacheKey: any) => Array.isArray(cacheKey) && cacheKey[0] === "Note")"#));
+    std::fs::remove_dir_all(&dest).unwrap();
+    let error = HooksLibrary::from_name("vue-query").unwrap_err();
+    assert_eq!(error.message, "unknown hooks library `vue-query`, expect react-query or swr");
+}
+
+async fn client_transport(app: &TestApp) {
+    app.app().action_envelope("Reading.aggregate", Envelope::bare());
+    let dest = std::env::temp_dir().join(format!("teo-transport-test-{}", Uuid::new_v4()));
+    generate_transport(&dest).unwrap();
+    let source = std::fs::read_to_string(dest.join(TRANSPORT_FILE_NAME)).unwrap();
+    std::fs::remove_dir_all(&dest).unwrap();
+    app.app().action_envelope("Reading.aggregate", Envelope::default());
+    assert!(source.contains("export function installTransport(options: TransportOptions): () => void {"));
+    // the client is told the envelopes of the server
+    let envelopes = source.lines().find_map(|line| line.strip_prefix("const ENVELOPES: ")).unwrap();
+    let envelopes: JsonValue = serde_json::from_str(&envelopes[envelopes.find(" = ").unwrap() + 3..]).unwrap();
+    assert_eq!(envelopes, json!({
+        "default": { "data": "data", "meta": "meta", "error": "error" },
+        "overrides": { "Reading.aggregate": { "data": null, "meta": "meta", "error": "error" } },
+    }));
+}
+
+async fn mobile_clients() {
+    let dest = std::env::temp_dir().join(format!("teo-mobile-test-{}", Uuid::new_v4()));
+    let generate = |language: &str, file: &str| {
+        generate_mobile_client(AppCtx::main_namespace(), MobileLanguage::from_name(language).unwrap(), &dest, "io.teo.test", "http://127.0.0.1:5050").unwrap();
+        std::fs::read_to_string(dest.join(file)).unwrap()
+    };
+    let kotlin = generate("kotlin", "Teo.kt");
+    assert!(kotlin.contains("package io.teo.test\n"));
+    assert!(kotlin.contains(r#"class Teo(private val host: String = "http://127.0.0.1:5050""#));
+    assert!(kotlin.contains(r#"    "" to Envelope("data", "meta"),"#), "{}", kotlin);
+    assert!(kotlin.contains("@Serializable\ndata class Note(\n    val id: Int,\n    val title: String,\n)\n"), "{}", kotlin);
+    assert!(kotlin.contains("    val current: String? = null,\n"));
+    assert!(kotlin.contains("    val embedding: List<Double>,\n"));
+    assert!(kotlin.contains(r#"    fun findMany(input: JsonObject = buildJsonObject {}): Response<List<Note>> = teo.request("/Note/findMany", input, ListSerializer(Note.serializer()))"#));
+    assert!(kotlin.contains(r#"    fun findByIds(input: JsonObject = buildJsonObject {}): Response<List<Note?>> = teo.request("/Note/findByIds", input, ListSerializer(Note.serializer().nullable))"#));
+    assert!(kotlin.contains(r#"    fun count(input: JsonObject = buildJsonObject {}): Response<Int> = teo.request("/Note/count", input, Int.serializer())"#));
+    let swift = generate("swift", "Teo.swift");
+    assert!(swift.contains(r#"    "": Envelope(data: "data", meta: "meta"),"#), "{}", swift);
+    assert!(swift.contains("public struct Note: Codable {\n    public let id: Int\n    public let title: String\n}\n"), "{}", swift);
+    assert!(swift.contains("    public lazy var note: NoteDelegate = NoteDelegate(teo: self)\n"));
+    assert!(swift.contains("    public func findUnique(_ input: [String: JSONValue] = [:]) async throws -> Response<Note?> {\n        try await teo.request(\"/Note/findUnique\", input)\n    }\n"));
+    assert!(swift.contains("    public let embedding: [Double]\n"));
+    std::fs::remove_dir_all(&dest).unwrap();
+    let error = MobileLanguage::from_name("dart").unwrap_err();
+    assert_eq!(error.message, "unknown mobile client language `dart`, expect kotlin or swift");
+}
+
+async fn debug_timings(app: &TestApp) {
+    std::env::set_var("TEO_ENV", "development");
+    let response = app.req("Note", "findMany", json!({ "_debug": true })).await;
+    assert_eq!(response["meta"]["count"], 0);
+    let stages: Vec<&str> = response["meta"]["debug"]["stages"].as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(stages, vec!["action", "postprocess"]);
+    assert!(response["meta"]["debug"]["totalMs"].as_f64().unwrap() >= 0.0);
+    // elsewhere the flag is removed without a trace
+    std::env::set_var("TEO_ENV", "production");
+    let response = app.req("Note", "findMany", json!({ "_debug": true })).await;
+    std::env::remove_var("TEO_ENV");
+    assert_eq!(response, json!({ "data": [], "meta": { "count": 0 } }));
+}
+
+async fn index_advice(app: &TestApp) {
+    // queries are observed in development, which is the default
+    app.req("Reading", "findMany", json!({ "where": { "takenAt": { "gt": "2024-01-01T00:00:00.000Z" }, "sensor": "a" }, "orderBy": { "value": "asc" } })).await;
+    app.req("Reading", "findFirst", json!({ "where": { "sensor": { "equals": "a" } } })).await;
+    // served by the declared index
+    app.req("Reading", "count", json!({ "where": { "value": { "gte": 1 } } })).await;
+    let advices: Vec<Advice> = advices().unwrap().into_iter().filter(|advice| advice.model_path == "Reading").collect();
+    let _ = std::fs::remove_file(SHAPES_FILE);
+    assert_eq!(advices.len(), 1, "{:?}", advices);
+    assert_eq!(advices[0].fields, vec!["sensor", "takenAt", "value"]);
+    assert!(advices[0].hits >= 2);
+}
+
+async fn backfills(app: &TestApp) {
+    let mut ids = vec![];
+    for name in ["Rust Lang", "Teo", "skip", "Axum", "Tokio"] {
+        ids.push(app.req("Tag", "create", json!({ "create": { "name": name } })).await["data"]["id"].clone());
+    }
+    app.req("Tag", "update", json!({ "where": { "id": ids[1] }, "update": { "slug": "kept" } })).await;
+    // a batch size smaller than the records makes it take several batches
+    app.app().backfill("Tag", "slug", Some(2), |object: Object| async move {
+        let name = object.get_value("name")?;
+        let name = name.as_str().unwrap();
+        Ok(if name == "skip" { Value::Null } else { Value::String(name.to_lowercase().replace(' ', "-")) })
+    });
+    let slugs = || async {
+        let response = app.req("Tag", "findMany", json!({ "orderBy": { "id": "asc" } })).await;
+        response["data"].as_array().unwrap().iter().map(|tag| tag["slug"].clone()).collect::<Vec<JsonValue>>()
+    };
+    run_backfills(true).await.unwrap();
+    assert_eq!(slugs().await, vec![json!("rust-lang"), json!("kept"), JsonValue::Null, json!("axum"), json!("tokio")]);
+    // records backfilled already are not touched again
+    app.req("Tag", "update", json!({ "where": { "id": ids[0] }, "update": { "slug": "changed" } })).await;
+    run_backfills(true).await.unwrap();
+    assert_eq!(slugs().await, vec![json!("changed"), json!("kept"), JsonValue::Null, json!("axum"), json!("tokio")]);
+}
+
+async fn expiring_records(app: &TestApp) {
+    let model = AppCtx::main_namespace().model_at_path(&vec!["Token"]).unwrap();
+    assert_eq!(model_expiry(model), Some((3600, "createdAt".to_owned())));
+    let now = Utc::now();
+    for (value, age) in [("old", 7200), ("recent", 600), ("new", 0)] {
+        let created_at = (now - chrono::Duration::seconds(age)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        app.req("Token", "create", json!({ "create": { "value": value, "createdAt": created_at } })).await;
+    }
+    sweep_expired(true).await;
+    let response = app.req("Token", "findMany", json!({ "orderBy": { "id": "asc" } })).await;
+    let values: Vec<&str> = response["data"].as_array().unwrap().iter().map(|token| token["value"].as_str().unwrap()).collect();
+    assert_eq!(values, vec!["recent", "new"]);
+}
+
+async fn view_models(app: &TestApp) {
+    for title in ["public a", "private b", "public c"] {
+        app.req("Note", "create", json!({ "create": { "title": title } })).await;
+    }
+    let response = app.req("PublicNote", "findMany", json!({ "orderBy": { "title": "desc" } })).await;
+    let titles: Vec<&str> = response["data"].as_array().unwrap().iter().map(|note| note["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, vec!["public c", "public a"]);
+    assert_eq!(app.req("PublicNote", "count", json!({})).await["data"], 2);
+    for (action, body) in [("create", json!({ "create": { "id": 9, "title": "public d" } })), ("deleteMany", json!({}))] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri(&format!("/PublicNote/{}", action))).set_json(body)).await;
+        assert_eq!(status, 405);
+        assert_eq!(response["error"]["message"], "`PublicNote` is a view, writes are not allowed");
+    }
+    assert_eq!(app.req("Note", "count", json!({})).await["data"], 3);
+    let model = AppCtx::main_namespace().model_at_path(&vec!["PublicNote"]).unwrap();
+    assert_eq!(refresh_view(model).await.unwrap_err().message, "`PublicNote` is not a materialized view");
+}
+
+async fn custom_connectors(app: &TestApp) {
+    app.app().connector("recorded", |url: String| async move {
+        CONNECTED.lock().unwrap().push(url.clone());
+        if url.ends_with("fail") {
+            return Err(Error::new("the store is unreachable"));
+        }
+        Ok(Arc::new(MemoryConnection::new()) as Arc<dyn Connection>)
+    });
+    let mut connector = AppCtx::main_namespace().connector.as_ref().unwrap().clone();
+    assert!(is_provider_connector(&connector));
+    connector.url = "recorded://store".to_owned();
+    assert!(!is_provider_connector(&connector));
+    let connection = connection_for_connector(&connector).await.unwrap();
+    // the connection serves the runtime like the builtin ones
+    assert!(!connection.no_transaction().await.unwrap().is_transaction());
+    connector.url = "recorded://fail".to_owned();
+    assert_eq!(connection_for_connector(&connector).await.unwrap_err().message, "the store is unreachable");
+    assert_eq!(*CONNECTED.lock().unwrap(), vec!["recorded://store", "recorded://fail"]);
+    // other schemes are still connected by the connectors of their providers
+    connector.url = "unregistered://store".to_owned();
+    assert!(is_provider_connector(&connector));
+}
+
+async fn search_index(app: &TestApp) {
+    let model = AppCtx::main_namespace().model_at_path(&vec!["Entry"]).unwrap();
+    assert_eq!(mapping(model), json!({ "id": { "type": "integer" }, "title": { "type": "text" }, "views": { "type": "integer" } }));
+    let engine = MockServer::start(vec![response(200, "{}")]).await;
+    app.app().search_engine(&engine.url);
+    let first = app.req("Entry", "create", json!({ "create": { "title": "first", "views": 1 } })).await["data"]["id"].as_i64().unwrap();
+    let second = app.req("Entry", "create", json!({ "create": { "title": "second", "views": 2 } })).await["data"]["id"].as_i64().unwrap();
+    app.req("Entry", "delete", json!({ "where": { "id": second } })).await;
+    let requests = engine.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].starts_with(&format!("PUT /entries_index/_doc/{} HTTP/1.1", first)), "{}", requests[0]);
+    assert!(requests[0].contains(r#""title":"first""#), "{}", requests[0]);
+    assert!(requests[2].starts_with(&format!("DELETE /entries_index/_doc/{} HTTP/1.1", second)), "{}", requests[2]);
+    // the hit of the deleted record is left out, the total is the engine's
+    let hits: &'static str = Box::leak(json!({ "hits": { "total": { "value": 2 }, "hits": [
+        { "_source": { "id": first, "title": "first", "views": 1 } },
+        { "_source": { "id": second, "title": "second", "views": 2 } },
+    ] } }).to_string().into_boxed_str());
+    let engine = MockServer::start(vec![response(200, hits)]).await;
+    app.app().search_engine(&engine.url);
+    let query = json!({ "query": { "match": { "title": "first second" } } });
+    let found = app.req("Entry", "search", query.clone()).await;
+    AppCtx::get_mut().search_engine = None;
+    let request = engine.requests().pop().unwrap();
+    assert!(request.starts_with("POST /entries_index/_search HTTP/1.1"), "{}", request);
+    assert!(request.ends_with(&query.to_string()), "{}", request);
+    assert_eq!(found, json!({ "data": [{ "id": first, "title": "first", "views": 1 }], "meta": { "count": 2 } }));
+}
+
+async fn message_consumers(app: &TestApp) {
+    AppCtx::main_namespace_mut().define_handler("orderCreated", |ctx: request::Ctx| async move {
+        CONSUMED.lock().unwrap().push(ctx.body().clone());
+        Ok(Response::data(ctx.body().clone()))
+    });
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<Result<String, Error>>();
+    let receiver = Arc::new(Mutex::new(Some(receiver)));
+    app.app().event_source("test", move |topic: String| {
+        let receiver = receiver.lock().unwrap().take();
+        async move {
+            assert_eq!(topic, "orders");
+            let Some(receiver) = receiver else {
+                return Err(Error::new("already subscribed"));
+            };
+            Ok(stream::unfold(receiver, |mut receiver| async move { receiver.recv().await.map(|message| (message, receiver)) }).boxed())
+        }
+    });
+    app.app().consumer("test", "orders", "orderCreated");
+    start_consumers(true).await.unwrap();
+    // messages which fail are skipped
+    for message in [r#"{"orderId":1}"#, "{", r#"{"orderId":"one"}"#, r#"{"orderId":2}"#] {
+        sender.send(Ok(message.to_owned())).unwrap();
+    }
+    sender.send(Err(Error::new("the broker is gone"))).unwrap();
+    sender.send(Ok(r#"{"orderId":3}"#.to_owned())).unwrap();
+    for _ in 0..100 {
+        if CONSUMED.lock().unwrap().len() >= 3 {
+            break
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let consumed: Vec<JsonValue> = CONSUMED.lock().unwrap().iter().map(|input| JsonValue::try_from(input).unwrap()).collect();
+    assert_eq!(consumed, vec![json!({ "orderId": 1 }), json!({ "orderId": 2 }), json!({ "orderId": 3 })]);
+    // consumers of unknown sources and handlers don't start
+    AppCtx::get_mut().consumers.clear();
+    app.app().consumer("kafka", "orders", "orderCreated");
+    assert_eq!(start_consumers(true).await.unwrap_err().message, "event source `kafka` is not registered");
+    AppCtx::get_mut().consumers.clear();
+    app.app().consumer("test", "orders", "orderDeleted");
+    assert_eq!(start_consumers(true).await.unwrap_err().message, "handler `orderDeleted` is not found");
+    AppCtx::get_mut().consumers.clear();
+}
+
+async fn json_rpc(app: &TestApp) {
+    let rpc = |body: JsonValue| TestRequest::post().uri(&app.uri("/_rpc")).set_json(body);
+    let call = json!({ "jsonrpc": "2.0", "method": "Note.create", "params": { "create": { "title": "rpc" } }, "id": 1 });
+    assert_eq!(send(app, rpc(call.clone())).await.0, 404);
+    app.app().json_rpc(true);
+    let (status, response) = send(app, rpc(call)).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["data"]["title"], "rpc");
+    let (_, responses) = send(app, rpc(json!([
+        { "jsonrpc": "2.0", "method": "Note.count", "params": {}, "id": "count" },
+        { "jsonrpc": "2.0", "method": "Note.create", "params": { "create": { "title": "notified" } } },
+        { "jsonrpc": "2.0", "method": "Note.publish", "id": 3 },
+        { "jsonrpc": "2.0", "method": "Note.findMany", "params": [], "id": 4 },
+        { "jsonrpc": "1.0", "method": "Note.findMany", "id": 5 },
+        { "jsonrpc": "2.0", "method": "Note.create", "params": { "create": {} }, "id": 6 },
+    ]))).await;
+    let responses = responses.as_array().unwrap();
+    // the notification is run, but not answered
+    assert_eq!(responses.len(), 5);
+    assert_eq!(responses[0], json!({ "jsonrpc": "2.0", "result": { "data": 1 }, "id": "count" }));
+    assert_eq!(responses[1], json!({ "jsonrpc": "2.0", "error": { "code": -32601, "message": "method `Note.publish` is not found" }, "id": 3 }));
+    assert_eq!(responses[2], json!({ "jsonrpc": "2.0", "error": { "code": -32602, "message": "expect `params` to be an object" }, "id": 4 }));
+    assert_eq!(responses[3], json!({ "jsonrpc": "2.0", "error": { "code": -32600, "message": "expect `jsonrpc` to be \"2.0\"" }, "id": 5 }));
+    assert_eq!(responses[4]["error"]["code"], -32602);
+    assert_eq!(responses[4]["error"]["message"], responses[4]["error"]["data"]["message"]);
+    assert_eq!(app.req("Note", "count", json!({})).await["data"], 2);
+    let (_, response) = send(app, TestRequest::post().uri(&app.uri("/_rpc")).insert_header(("Content-Type", "application/json")).set_payload("{")).await;
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], JsonValue::Null);
+    let (_, response) = send(app, rpc(json!([]))).await;
+    assert_eq!(response["error"], json!({ "code": -32600, "message": "empty batch" }));
+    let (status, _) = send(app, rpc(json!({ "jsonrpc": "2.0", "method": "Note.count" }))).await;
+    app.app().json_rpc(false);
+    assert_eq!(status, 204);
+}
+
+async fn offline_sync(app: &TestApp) {
+    let push = |changes: JsonValue| app.req("Task", "push", json!({ "changes": changes }));
+    let pull = |since: i64, take: i64| app.req("Task", "pull", json!({ "since": since, "take": take }));
+    let summary = |records: &JsonValue| records.as_array().unwrap().iter().map(|task| {
+        (task["id"].as_str().unwrap().to_owned(), task["title"].as_str().unwrap().to_owned(), task["version"].as_i64().unwrap(), task["deleted"].as_bool().unwrap())
+    }).collect::<Vec<_>>();
+    let pushed = push(json!([
+        { "where": { "id": "a" }, "data": { "title": "A" } },
+        { "where": { "id": "b" }, "data": { "title": "B", "seq": 1, "version": 9 } },
+    ])).await;
+    assert_eq!(pushed["data"], json!({ "applied": 2, "conflicts": [] }));
+    let pulled = pull(0, 10).await;
+    assert_eq!(summary(&pulled["data"]), vec![("a".to_owned(), "A".to_owned(), 1, false), ("b".to_owned(), "B".to_owned(), 1, false)]);
+    let (first, second) = (pulled["data"][0]["seq"].as_i64().unwrap(), pulled["data"][1]["seq"].as_i64().unwrap());
+    assert!(first > 1 && second > first);
+    assert_eq!(pulled["meta"], json!({ "cursor": second, "hasMore": false }));
+    let pulled = pull(0, 1).await;
+    assert_eq!(pulled["data"].as_array().unwrap().len(), 1);
+    assert_eq!(pulled["meta"], json!({ "cursor": first, "hasMore": true }));
+    // a change made on a stale version is a conflict
+    let pushed = push(json!([{ "where": { "id": "a" }, "data": { "title": "A2" }, "version": 1 }])).await;
+    assert_eq!(pushed["data"], json!({ "applied": 1, "conflicts": [] }));
+    let pushed = push(json!([
+        { "where": { "id": "a" }, "data": { "title": "A3" }, "version": 1 },
+        { "where": { "id": "b" }, "deleted": true, "version": 1 },
+    ])).await;
+    assert_eq!(pushed["data"]["applied"], 1);
+    assert_eq!(pushed["data"]["conflicts"][0]["index"], 0);
+    assert_eq!(pushed["data"]["conflicts"][0]["record"]["title"], "A2");
+    // deleted records are pulled as tombstones
+    let pulled = pull(second, 10).await;
+    assert_eq!(summary(&pulled["data"]), vec![("a".to_owned(), "A2".to_owned(), 2, false), ("b".to_owned(), "B".to_owned(), 2, true)]);
+    // a failed change rolls back the others
+    let pushed = push(json!([{ "where": { "id": "a" }, "data": { "title": "A4" } }, { "data": { "title": "C" } }])).await;
+    assert_eq!(pushed["error"]["message"], "change 1 failed: expect `where` to be an object");
+    assert_eq!(app.req("Task", "findUnique", json!({ "where": { "id": "a" } })).await["data"]["title"], "A2");
+    let pushed = app.req("Task", "push", json!({})).await;
+    assert_eq!(pushed["error"]["message"], "expect `changes` to be an array");
+}
+
+async fn permissions(app: &TestApp) {
+    create_user(app, "member@example.com").await;
+    app.req("User", "create", json!({ "create": { "email": "admin@example.com", "password": PASSWORD, "role": "admin" } })).await;
+    let token = |email: &'static str| async move {
+        let (_, response) = send(app, sign_in(app, email, PASSWORD, "10.0.3.1")).await;
+        response["meta"]["token"].as_str().unwrap().to_owned()
+    };
+    let (member, admin) = (token("member@example.com").await, token("admin@example.com").await);
+    let memo = |action: &str, token: Option<&str>, body: JsonValue| {
+        let request = TestRequest::post().uri(&app.uri(&format!("/Memo/{}", action))).set_json(body);
+        send(app, match token {
+            Some(token) => request.insert_header(("Authorization", format!("Bearer {}", token))),
+            None => request,
+        })
+    };
+    let (status, response) = memo("create", None, json!({ "create": { "body": "a" } })).await;
+    assert_eq!((status, error_code(&response)), (401, Some("IDENTITY_REQUIRED")));
+    let (status, response) = memo("create", Some(&member), json!({ "create": { "body": "a" } })).await;
+    assert_eq!(status, 200);
+    let id = response["data"]["id"].clone();
+    assert_eq!(memo("findMany", None, json!({})).await.0, 200);
+    assert_eq!(memo("count", None, json!({})).await.0, 200);
+    // a guard of a single action wins over the guard of its group
+    let (status, response) = memo("findUnique", None, json!({ "where": { "id": id } })).await;
+    assert_eq!((status, error_code(&response)), (401, Some("IDENTITY_REQUIRED")));
+    assert_eq!(memo("findUnique", Some(&member), json!({ "where": { "id": id } })).await.0, 200);
+    let (status, response) = memo("update", Some(&admin), json!({ "where": { "id": id }, "update": { "body": "b" } })).await;
+    assert_eq!((status, error_code(&response)), (403, Some("ACTION_NOT_ALLOWED")));
+    assert_eq!(response["error"]["message"], "`update` of `Memo` is not allowed");
+    let (status, response) = memo("delete", Some(&member), json!({ "where": { "id": id } })).await;
+    assert_eq!((status, error_code(&response)), (403, Some("ROLE_REQUIRED")));
+    assert_eq!(response["error"]["message"], "`delete` of `Memo` requires the role `admin`");
+    // batches are guarded like single actions
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/_batch")).set_json(json!({
+        "actions": [{ "model": "Memo", "action": "deleteMany", "body": {} }],
+    }))).await;
+    assert_eq!(status, 401);
+    assert_eq!(response["error"]["message"], "batch action 0 failed: identity is required");
+    assert_eq!(memo("delete", Some(&admin), json!({ "where": { "id": id } })).await.0, 200);
+    let dest = std::env::temp_dir().join(format!("teo-permissions-test-{}", Uuid::new_v4()));
+    generate_permissions(AppCtx::main_namespace(), &dest).unwrap();
+    let source = std::fs::read_to_string(dest.join(PERMISSIONS_FILE_NAME)).unwrap();
+    std::fs::remove_dir_all(&dest).unwrap();
+    let map = source.split_once("} } = ").unwrap().1.split("\n\nexport interface").next().unwrap();
+    let map: JsonValue = serde_json::from_str(map).unwrap();
+    assert_eq!(map["Memo"], json!({
+        "findUnique": "identity", "findFirst": "everyone", "findMany": "everyone",
+        "create": "identity", "createMany": "identity",
+        "update": "nobody", "updateMany": "nobody", "upsert": "nobody",
+        "delete": "role:admin", "deleteMany": "role:admin",
+    }));
+    assert!(map.get("Note").is_none());
+}
+
+async fn impersonation(app: &TestApp) {
+    let mut ids = vec![];
+    for (email, role) in [("support@example.com", Some("support")), ("admin2@example.com", Some("admin")), ("member2@example.com", None)] {
+        ids.push(app.req("User", "create", json!({ "create": { "email": email, "password": PASSWORD, "role": role } })).await["data"]["id"].clone());
+    }
+    let mut tokens = vec![];
+    for email in ["support@example.com", "member2@example.com"] {
+        let (_, response) = send(app, sign_in(app, email, PASSWORD, "10.0.4.1")).await;
+        tokens.push(response["meta"]["token"].as_str().unwrap().to_owned());
+    }
+    let memo = app.req("Memo", "findMany", json!({})).await;
+    assert_eq!(memo["data"], json!([]));
+    let delete = |token: Option<&str>, act_as: Option<&str>| {
+        let mut request = TestRequest::post().uri(&app.uri("/Memo/deleteMany")).set_json(json!({}));
+        if let Some(token) = token {
+            request = request.insert_header(("Authorization", format!("Bearer {}", token)));
+        }
+        if let Some(act_as) = act_as {
+            request = request.insert_header((ACT_AS_HEADER, act_as.to_owned()));
+        }
+        send(app, request)
+    };
+    let act_as_admin = json!({ "email": "admin2@example.com" }).to_string();
+    let (status, response) = delete(Some(&tokens[0]), None).await;
+    assert_eq!((status, error_code(&response)), (403, Some("ROLE_REQUIRED")));
+    // permissions are checked against the identity acted as
+    assert_eq!(delete(Some(&tokens[0]), Some(&act_as_admin)).await.0, 200);
+    let (status, response) = delete(Some(&tokens[1]), Some(&act_as_admin)).await;
+    assert_eq!((status, error_code(&response)), (403, Some("IMPERSONATION_NOT_ALLOWED")));
+    let (status, response) = delete(None, Some(&act_as_admin)).await;
+    assert_eq!((status, error_code(&response)), (401, Some("IDENTITY_REQUIRED")));
+    let (status, response) = delete(Some(&tokens[0]), Some(r#"{"email":"nobody@example.com"}"#)).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "the identity to act as is not found");
+    let (_, response) = delete(Some(&tokens[0]), Some("admin")).await;
+    assert_eq!(response["error"]["message"], "expect `X-Teo-Act-As` to be a json object");
+    // both identities are recorded
+    let (connection, _) = main_connection().unwrap();
+    let rows = connection.no_transaction().await.unwrap().query_raw(&Value::String(format!("SELECT principal, identity, method, path FROM {}", IMPERSONATIONS_TABLE))).await.unwrap();
+    assert_eq!(JsonValue::try_from(&rows).unwrap(), json!([{
+        "principal": format!("User{}", json!({ "id": ids[0] })),
+        "identity": format!("User{}", json!({ "id": ids[1] })),
+        "method": "POST",
+        "path": app.uri("/Memo/deleteMany"),
+    }]));
+}
+
+async fn personal_data(app: &TestApp) {
+    let customer = app.req("Customer", "create", json!({ "create": {
+        "email": "jane@example.com",
+        "phone": "555-0100",
+        "name": "Jane",
+        "purchases": { "create": [{ "total": 10 }, { "total": 20 }] },
+    } })).await;
+    let id = customer["data"]["id"].clone();
+    app.req("Customer", "create", json!({ "create": { "email": "joe@example.com", "name": "Joe", "purchases": { "create": { "total": 5 } } } })).await;
+    let exported = app.req("Customer", "export", json!({ "where": { "id": id } })).await;
+    assert_eq!(exported["data"]["record"]["email"], "jane@example.com");
+    let totals: Vec<i64> = exported["data"]["linked"]["Purchase"].as_array().unwrap().iter().map(|p| p["total"].as_i64().unwrap()).collect();
+    assert_eq!(totals, vec![10, 20]);
+    let anonymized = app.req("Customer", "anonymize", json!({ "where": { "id": id } })).await;
+    let record = &anonymized["data"];
+    assert_eq!(record["email"], sha256_hex(b"jane@example.com"));
+    assert_eq!(record["phone"], JsonValue::Null);
+    assert!(record["name"].as_str().map_or(false, |name| !name.is_empty()), "{}", record);
+    // the relations to the record are kept
+    let purchases = app.req("Purchase", "count", json!({ "where": { "customerId": id } })).await;
+    assert_eq!(purchases["data"], 2);
+    let stored = app.req("Customer", "findUnique", json!({ "where": { "id": id } })).await;
+    assert_eq!(stored["data"]["email"], record["email"]);
+    assert_eq!(app.req("Customer", "count", json!({ "where": { "email": "joe@example.com" } })).await["data"], 1);
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Customer/anonymize")).set_json(json!({ "where": { "id": 0 } }))).await;
+    assert_eq!(status, 404);
+    let missing = app.req("Customer", "export", json!({})).await;
+    assert_eq!(missing["error"]["message"], "expect `where` to be an object");
+}
+
+async fn archival(app: &TestApp) {
+    let dir = Path::new("/tmp/teo-security-archive");
+    let _ = std::fs::remove_dir_all(dir);
+    let model = AppCtx::main_namespace().model_at_path(&vec!["Receipt"]).unwrap();
+    let policy = model_archive(model).unwrap();
+    assert_eq!((policy.after, policy.to.as_str(), policy.field.as_str()), (30 * 86400, "file:///tmp/teo-security-archive", "issuedAt"));
+    let now = Utc::now();
+    for (number, age) in [("R-1", 90), ("R-2", 45), ("R-3", 1)] {
+        let issued_at = (now - chrono::Duration::days(age)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        app.req("Receipt", "create", json!({ "create": { "number": number, "issuedAt": issued_at } })).await;
+    }
+    assert_eq!(archive(model, &policy, true).await.unwrap(), 2);
+    let numbers = |response: JsonValue| -> Vec<String> {
+        response["data"].as_array().unwrap().iter().map(|receipt| receipt["number"].as_str().unwrap().to_owned()).collect()
+    };
+    assert_eq!(numbers(app.req("Receipt", "findMany", json!({ "orderBy": { "number": "asc" } })).await), vec!["R-3"]);
+    let files: Vec<_> = std::fs::read_dir(dir.join("Receipt")).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    let lines: Vec<JsonValue> = std::fs::read_to_string(&files[0]).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.iter().map(|line| line["number"].as_str().unwrap()).collect::<Vec<_>>(), vec!["R-1", "R-2"]);
+    // nothing is left to archive
+    assert_eq!(archive(model, &policy, true).await.unwrap(), 0);
+    assert_eq!(restore(model, &vec![], true).await.unwrap(), 2);
+    assert_eq!(numbers(app.req("Receipt", "findMany", json!({ "orderBy": { "number": "asc" } })).await), vec!["R-1", "R-2", "R-3"]);
+    let key = format!("Receipt/{}", files[0].file_name().unwrap().to_string_lossy());
+    assert!(restore(model, &vec![key], true).await.unwrap_err().message.starts_with("cannot restore a record of `Receipt/"));
+    let error = restore(AppCtx::main_namespace().model_at_path(&vec!["Token"]).unwrap(), &vec![], true).await.unwrap_err();
+    assert_eq!(error.message, "`Token` is not archived");
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+async fn output_pipelines(app: &TestApp) {
+    let ada = app.req("Profile", "create", json!({ "create": { "displayName": "Ada Lovelace", "secret": "s1" } })).await;
+    let id = ada["data"]["id"].clone();
+    app.req("Profile", "create", json!({ "create": { "displayName": "Alan Turing", "secret": "s2" } })).await;
+    let response = app.req("Profile", "findMany", json!({ "where": { "displayName": { "startsWith": "A" } }, "orderBy": { "id": "asc" }, "take": 1 })).await;
+    assert_eq!(response["data"], json!([{ "id": id, "name": "Ada Lovelace", "initials": "AL" }]));
+    assert_eq!(response["meta"]["count"], 2);
+    let response = app.req("Profile", "findFirst", json!({ "where": { "displayName": "Alan Turing" } })).await;
+    assert_eq!(response["data"]["initials"], "AT");
+    assert!(response["data"].get("secret").is_none());
+    let find_unique = |role: &str| TestRequest::post().uri(&app.uri("/Profile/findUnique"))
+        .insert_header(("x-role", role.to_owned()))
+        .set_json(json!({ "where": { "id": id } }));
+    let (_, response) = send(app, find_unique("admin")).await;
+    assert_eq!(response["data"]["secret"], "s1");
+    let (_, response) = send(app, find_unique("member")).await;
+    assert!(response["data"].get("secret").is_none());
+    assert!(response["data"].get("displayName").is_none());
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Profile/findUnique")).set_json(json!({ "where": { "id": 0 } }))).await;
+    assert_eq!(status, 404);
+    let response = app.req("Profile", "findFirst", json!({ "where": { "displayName": "nobody" } })).await;
+    assert_eq!(response["data"], JsonValue::Null);
+    // batches respond with the same shape
+    let response = app.post("/_batch", json!({ "actions": [{ "model": "Profile", "action": "findUnique", "body": { "where": { "id": id } } }] })).await;
+    assert_eq!(response["data"][0]["data"], json!({ "id": id, "name": "Ada Lovelace", "initials": "AL" }));
+    // writes aren't shaped
+    let response = app.req("Profile", "update", json!({ "where": { "id": id }, "update": { "displayName": "broken" } })).await;
+    assert_eq!(response["data"]["displayName"], "broken");
+    let (status, response) = send(app, find_unique("member")).await;
+    assert_eq!(status, 500);
+    assert_eq!(response["error"]["message"], "@@onOutput of `Profile` must return a dictionary");
+}
+
+/// Stores North American numbers as `+1` and ten digits, and responds with
+/// them formatted.
+struct PhoneNumberCodec;
+
+impl ScalarCodec for PhoneNumberCodec {
+
+    fn parse(&self, value: &JsonValue) -> teo_result::Result<JsonValue> {
+        let digits: String = value.as_str().ok_or_else(|| Error::new("expect a string"))?.chars().filter(char::is_ascii_digit).collect();
+        let digits = digits.strip_prefix('1').filter(|rest| rest.len() == 10).unwrap_or(&digits);
+        if digits.len() != 10 {
+            Err(Error::new("expect a phone number of 10 digits"))?
+        }
+        Ok(JsonValue::String(format!("+1{}", digits)))
+    }
+
+    fn serialize(&self, value: &JsonValue) -> JsonValue {
+        let digits = value.as_str().unwrap_or("").trim_start_matches("+1");
+        JsonValue::String(format!("+1 ({}) {}-{}", &digits[..3], &digits[3..6], &digits[6..]))
+    }
+
+    fn typescript_type(&self) -> String {
+        "`+1 (${number}) ${number}-${number}`".to_owned()
+    }
+}
+
+async fn custom_scalars(app: &TestApp) {
+    let response = app.req("Caller", "create", json!({ "create": { "phone": "555.010.0100" } })).await;
+    assert_eq!(response["data"]["phone"], "+1 (555) 010-0100");
+    assert_eq!(response["data"]["fax"], JsonValue::Null);
+    let id = response["data"]["id"].clone();
+    // values are stored normalized, so filters match them
+    let response = app.req("Caller", "findMany", json!({ "where": { "phone": "+15550100100" } })).await;
+    assert_eq!(response["data"].as_array().unwrap().len(), 1);
+    assert_eq!(response["data"][0]["phone"], "+1 (555) 010-0100");
+    let response = app.req("Caller", "update", json!({ "where": { "id": id }, "update": { "fax": "1 (555) 010 0199" } })).await;
+    assert_eq!(response["data"]["fax"], "+1 (555) 010-0199");
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Caller/create")).set_json(json!({ "create": { "phone": "12345" } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`phone`: expect a phone number of 10 digits");
+    let response = app.req("Caller", "createMany", json!({ "create": [{ "phone": "555 010 0101" }, { "phone": "555 010 0102" }] })).await;
+    assert_eq!(response["data"][1]["phone"], "+1 (555) 010-0102");
+    // batches parse and serialize like single actions
+    let response = app.post("/_batch", json!({ "actions": [
+        { "model": "Caller", "action": "create", "body": { "create": { "phone": "(555) 010-0103" } } },
+    ] })).await;
+    assert_eq!(response["data"][0]["data"]["phone"], "+1 (555) 010-0103");
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/_batch")).set_json(json!({ "actions": [
+        { "model": "Caller", "action": "create", "body": { "create": { "phone": "0" } } },
+    ] }))).await;
+    assert_eq!(status, 400);
+    let dest = std::env::temp_dir().join(format!("teo-scalars-test-{}", Uuid::new_v4()));
+    generate_scalars(AppCtx::main_namespace(), &dest).unwrap();
+    let source = std::fs::read_to_string(dest.join(SCALARS_FILE_NAME)).unwrap();
+    std::fs::remove_dir_all(&dest).unwrap();
+    assert!(source.contains("export type PhoneNumber = `+1 (${number}) ${number}-${number}`\n"), "{}", source);
+    assert!(source.contains(r#""Caller": {
+    "fax": "PhoneNumber",
+    "phone": "PhoneNumber"
+  }"#), "{}", source);
+}
+
+async fn money_fields(app: &TestApp) {
+    let response = app.req("Invoice", "create", json!({ "create": { "label": "us", "total": { "amount": "12.5", "currency": "USD" } } })).await;
+    assert_eq!(response["data"]["total"], json!({ "amount": "12.50", "currency": "USD" }));
+    app.req("Invoice", "create", json!({ "create": { "label": "us", "total": { "amount": 0.75, "currency": "USD" }, "refund": { "amount": "1", "currency": "USD" } } })).await;
+    app.req("Invoice", "create", json!({ "create": { "label": "jp", "total": { "amount": "1000", "currency": "JPY" } } })).await;
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Invoice/create")).set_json(json!({
+        "create": { "label": "us", "total": { "amount": "1.005", "currency": "USD" } },
+    }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`total`: amount `1.005` has more than 2 decimal places, which USD doesn't allow");
+    let response = app.req("Invoice", "aggregate", json!({ "where": { "label": "us" }, "_sum": { "total": true, "refund": true }, "_count": { "_all": true } })).await;
+    assert_eq!(response["data"]["_sum"], json!({ "total": { "amount": "13.25", "currency": "USD" }, "refund": { "amount": "1.00", "currency": "USD" } }));
+    assert_eq!(response["data"]["_count"]["_all"], 2);
+    let response = app.req("Invoice", "aggregate", json!({ "where": { "label": "none" }, "_sum": { "total": true } })).await;
+    assert_eq!(response["data"]["_sum"]["total"], JsonValue::Null);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Invoice/aggregate")).set_json(json!({ "_sum": { "total": true } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`total`: cannot sum amounts in USD and JPY, filter the records by currency");
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Invoice/aggregate")).set_json(json!({ "_avg": { "total": true } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`_avg` of money field `total` is not supported, use `_sum`");
+}
+
+async fn slug_fields(app: &TestApp) {
+    let create = |title: &str| app.req("Story", "create", json!({ "create": { "title": title } }));
+    let dessert = create("Crème Brûlée!").await;
+    assert_eq!(dessert["data"]["slug"], "creme-brulee");
+    let id = dessert["data"]["id"].clone();
+    assert_eq!(create("Creme brulee").await["data"]["slug"], "creme-brulee-2");
+    // records of one request don't take the same slug
+    let response = app.req("Story", "createMany", json!({ "create": [{ "title": "Hello World" }, { "title": "hello, world" }, { "title": "Hi", "slug": "kept" }] })).await;
+    let slugs: Vec<&str> = response["data"].as_array().unwrap().iter().map(|article| article["slug"].as_str().unwrap()).collect();
+    assert_eq!(slugs, vec!["hello-world", "hello-world-2", "kept"]);
+    let resolve = |slug: &str| app.req("Story", "resolveSlug", json!({ "slug": slug }));
+    assert_eq!(resolve("creme-brulee").await["data"], json!({ "slug": "creme-brulee", "moved": false }));
+    app.req("Story", "update", json!({ "where": { "id": id }, "update": { "slug": "dessert" } })).await;
+    assert_eq!(resolve("creme-brulee").await["data"], json!({ "slug": "dessert", "moved": true }));
+    app.req("Story", "update", json!({ "where": { "id": id }, "update": { "slug": "sweet" } })).await;
+    assert_eq!(resolve("creme-brulee").await["data"], json!({ "slug": "sweet", "moved": true }));
+    assert_eq!(resolve("dessert").await["data"], json!({ "slug": "sweet", "moved": true }));
+    // a failed update doesn't record its slug
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Story/update")).set_json(json!({ "where": { "id": id }, "update": { "slug": "kept" } }))).await;
+    assert!(status >= 400, "{}", status);
+    let (connection, _) = main_connection().unwrap();
+    let rows = connection.no_transaction().await.unwrap().query_raw(&Value::String(format!("SELECT slug FROM {} ORDER BY slug", SLUG_HISTORY_TABLE))).await.unwrap();
+    assert_eq!(JsonValue::try_from(&rows).unwrap(), json!([{ "slug": "creme-brulee" }, { "slug": "dessert" }]));
+    // a slug given back to its record leaves the history
+    app.req("Story", "update", json!({ "where": { "id": id }, "update": { "slug": "dessert" } })).await;
+    assert_eq!(resolve("dessert").await["data"], json!({ "slug": "dessert", "moved": false }));
+    assert_eq!(resolve("sweet").await["data"], json!({ "slug": "dessert", "moved": true }));
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Story/resolveSlug")).set_json(json!({ "slug": "unknown" }))).await;
+    assert_eq!(status, 404);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Story/resolveSlug")).set_json(json!({ "slug": "x", "field": "title" }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`title` is not a slug field with history");
+}
+
+async fn request_context(app: &TestApp) {
+    let visit = |path: &str| TestRequest::post().uri(&app.uri("/Visit/create")).set_json(json!({ "create": { "path": path } }));
+    let (_, response) = send(app, visit("/a")
+        .peer_addr("203.0.113.7:4000".parse().unwrap())
+        .insert_header(("User-Agent", "Browser/1.0"))
+        .insert_header(("Accept-Language", "de;q=0.5, pt-BR, en;q=0.8"))).await;
+    assert_eq!(response["data"]["ip"], "203.0.113.7");
+    assert_eq!(response["data"]["agent"], "Browser/1.0");
+    assert_eq!(response["data"]["locale"], "pt-br");
+    // the address can't be claimed by the client
+    let (_, response) = send(app, visit("/b")
+        .peer_addr("[2001:db8::1]:4000".parse().unwrap())
+        .insert_header(("X-Teo-Client-Ip", "10.0.0.1"))
+        .insert_header(("X-Forwarded-For", "10.0.0.2"))).await;
+    assert_eq!(response["data"]["ip"], "2001:db8::1");
+    let (_, response) = send(app, visit("/c")).await;
+    assert_eq!(response["data"]["ip"], JsonValue::Null);
+    assert_eq!(response["data"]["agent"], JsonValue::Null);
+    assert_eq!(response["data"]["locale"], JsonValue::Null);
+    // given values are kept
+    let response = app.req("Visit", "create", json!({ "create": { "path": "/d", "ip": "192.0.2.1", "locale": "fr" } })).await;
+    assert_eq!((response["data"]["ip"].as_str(), response["data"]["locale"].as_str()), (Some("192.0.2.1"), Some("fr")));
+}
+
+async fn trusted_proxies(app: &TestApp) {
+    app.app().trusted_proxies(&["10.0.0.0/8", "2001:db8::/32"]).unwrap();
+    let ip = |peer: &str, headers: Vec<(&str, &str)>| {
+        let mut request = TestRequest::post().uri(&app.uri("/Visit/create"))
+            .peer_addr(peer.parse().unwrap())
+            .set_json(json!({ "create": { "path": "/" } }));
+        for (name, value) in headers {
+            request = request.append_header((name.to_owned(), value.to_owned()));
+        }
+        async move { send(app, request).await.1["data"]["ip"].as_str().unwrap().to_owned() }
+    };
+    // the nearest address which isn't a trusted proxy is the client
+    assert_eq!(ip("10.0.0.1:80", vec![("X-Forwarded-For", "198.51.100.1, 203.0.113.7, 10.1.1.1")]).await, "203.0.113.7");
+    assert_eq!(ip("10.0.0.1:80", vec![("X-Forwarded-For", "198.51.100.1"), ("X-Forwarded-For", "10.2.2.2")]).await, "198.51.100.1");
+    // `Forwarded` takes precedence over `X-Forwarded-For`
+    assert_eq!(ip("10.0.0.1:80", vec![
+        ("Forwarded", r#"for=198.51.100.2;proto=https, for="[2001:db8::5]:4711""#),
+        ("X-Forwarded-For", "203.0.113.9"),
+    ]).await, "198.51.100.2");
+    assert_eq!(ip("[2001:db8::1]:80", vec![("Forwarded", r#"for="192.0.2.43:4711""#)]).await, "192.0.2.43");
+    // an unknown hop stops the walk at the last known address
+    assert_eq!(ip("10.0.0.1:80", vec![("Forwarded", "for=198.51.100.3, for=_hidden, for=10.3.3.3")]).await, "10.3.3.3");
+    // the headers of untrusted peers are ignored
+    assert_eq!(ip("203.0.113.50:80", vec![("X-Forwarded-For", "198.51.100.1")]).await, "203.0.113.50");
+    // a request through trusted proxies only is from the farthest one
+    assert_eq!(ip("10.0.0.1:80", vec![("X-Forwarded-For", "10.9.9.9")]).await, "10.9.9.9");
+    assert_eq!(app.app().trusted_proxies(&["10.0.0.0/40"]).unwrap_err().message, "invalid prefix length in `10.0.0.0/40`");
+    app.app().trusted_proxies(&[]).unwrap();
+    assert_eq!(ip("10.0.0.1:80", vec![("X-Forwarded-For", "198.51.100.1")]).await, "10.0.0.1");
+}
+
+async fn maintenance_mode(app: &TestApp) {
+    let toggle = |secret: &str, body: Option<JsonValue>| {
+        let request = match body {
+            Some(body) => TestRequest::post().uri(&app.uri("/_maintenance")).set_json(body),
+            None => TestRequest::get().uri(&app.uri("/_maintenance")),
+        };
+        send(app, request.insert_header((MAINTENANCE_SECRET_HEADER, secret.to_owned())))
+    };
+    // the endpoint is off without a secret
+    assert_eq!(toggle("", None).await.0, 404);
+    app.app().maintenance_secret("maintenance-secret");
+    assert_eq!(toggle("wrong", None).await.0, 401);
+    let (_, response) = toggle("maintenance-secret", None).await;
+    assert_eq!(response["data"], json!({ "enabled": false, "allowReads": false, "retryAfter": null }));
+    let (status, response) = toggle("maintenance-secret", Some(json!({ "enabled": true, "allowReads": true, "retryAfter": 120 }))).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["data"], json!({ "enabled": true, "allowReads": true, "retryAfter": 120 }));
+    let call = |path: &str, body: JsonValue| app.call(TestRequest::post().uri(&app.uri(path)).set_json(body).to_request());
+    let response = call("/Note/create", json!({ "create": { "title": "t" } })).await;
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "120");
+    let body: JsonValue = serde_json::from_slice(&read_body(response).await).unwrap();
+    assert_eq!(error_code(&body), Some("MAINTENANCE"));
+    assert_eq!(body["error"]["message"], "the service is under maintenance, try again later");
+    let response = call("/Note/findMany", json!({})).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers().get("Retry-After").is_none());
+    assert_eq!(call("/Story/resolveSlug", json!({ "slug": "none" })).await.status().as_u16(), 404);
+    // batches count as writes, and custom handlers unless marked as reads
+    assert_eq!(call("/_batch", json!({ "actions": [{ "model": "Note", "action": "findMany" }] })).await.status().as_u16(), 503);
+    assert_eq!(call("/orderCreated", json!({ "orderId": 1 })).await.status().as_u16(), 503);
+    app.app().read_handler("orderCreated");
+    assert_eq!(call("/orderCreated", json!({ "orderId": 1 })).await.status().as_u16(), 200);
+    CONSUMED.lock().unwrap().clear();
+    // without reads, reads are rejected too
+    toggle("maintenance-secret", Some(json!({ "enabled": true }))).await;
+    let response = call("/Note/findMany", json!({})).await;
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "60");
+    let (status, response) = toggle("maintenance-secret", Some(json!({ "enabled": "no" }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect `enabled` to be a bool");
+    // the endpoint stays available
+    let (_, response) = toggle("maintenance-secret", Some(json!({ "enabled": false }))).await;
+    assert_eq!(response["data"]["enabled"], false);
+    assert_eq!(call("/Note/create", json!({ "create": { "title": "t" } })).await.status().as_u16(), 200);
+}
+
+async fn sequence_fields(app: &TestApp) {
+    let bill = |tenant: i64, code: &str| {
+        let request = TestRequest::post().uri(&app.uri("/Bill/create")).set_json(json!({ "create": { "tenantId": tenant, "code": code } }));
+        async move { send(app, request).await.1["data"]["number"].clone() }
+    };
+    assert_eq!(bill(1, "a").await, 100);
+    assert_eq!(bill(1, "b").await, 101);
+    assert_eq!(bill(2, "c").await, 100);
+    // given numbers are kept and don't move the counter
+    let response = app.req("Bill", "create", json!({ "create": { "tenantId": 1, "code": "d", "number": 500 } })).await;
+    assert_eq!(response["data"]["number"], 500);
+    let response = app.req("Bill", "createMany", json!({ "create": [{ "tenantId": 1, "code": "e" }, { "tenantId": 2, "code": "f" }, { "tenantId": 1, "code": "g" }] })).await;
+    let numbers: Vec<i64> = response["data"].as_array().unwrap().iter().map(|bill| bill["number"].as_i64().unwrap()).collect();
+    assert_eq!(numbers, vec![102, 101, 103]);
+    // the number of a failed create is given back
+    assert_eq!(bill(1, "a").await, JsonValue::Null);
+    assert_eq!(bill(1, "h").await, 104);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Bill/create")).set_json(json!({ "create": { "code": "i" } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`tenantId` is required to number `number`");
+    // existing records are numbered after the numbers in use
+    let (connection, _) = main_connection().unwrap();
+    connection.no_transaction().await.unwrap().query_raw(&Value::String(r#"INSERT INTO "Bill" ("tenantId", "code") VALUES (2, 'z'), (3, 'y'), (3, 'x'), (1, 'w')"#.to_owned())).await.unwrap();
+    let model = AppCtx::main_namespace().model_at_path(&vec!["Bill"]).unwrap();
+    assert_eq!(backfill_sequence(model, "number", Some("code"), true).await.unwrap(), 4);
+    let response = app.req("Bill", "findMany", json!({ "where": { "code": { "in": ["w", "x", "y", "z"] } }, "orderBy": { "code": "asc" } })).await;
+    let numbers: Vec<i64> = response["data"].as_array().unwrap().iter().map(|bill| bill["number"].as_i64().unwrap()).collect();
+    assert_eq!(numbers, vec![501, 100, 101, 102]);
+    assert_eq!(backfill_sequence(model, "number", None, true).await.unwrap(), 0);
+    assert_eq!(backfill_sequence(model, "code", None, true).await.unwrap_err().message, "`code` is not a sequence field of `Bill`");
+}
+
+async fn duplicates(app: &TestApp) {
+    for (name, email, city) in [
+        ("Jon Smyth", Some("jon@example.com"), "Paris"),
+        ("John Smith ", Some(" JON@example.com"), "Paris"),
+        ("Mary Major", None, "London"),
+        ("Marie Mayor", Some(""), "London"),
+        ("Mary Mayor", None, "London"),
+        ("Zed", Some("zed@example.com"), "Paris"),
+    ] {
+        app.req("Lead", "create", json!({ "create": { "name": name, "email": email, "city": city } })).await;
+    }
+    let clusters = |body: JsonValue| async move {
+        let response = app.req("Lead", "findDuplicates", body).await;
+        let mut clusters: Vec<(f64, Vec<String>)> = response["data"].as_array().unwrap().iter().map(|cluster| {
+            let names = cluster["records"].as_array().unwrap().iter().map(|lead| lead["name"].as_str().unwrap().to_owned()).collect();
+            ((cluster["score"].as_f64().unwrap() * 1000.0).round() / 1000.0, names)
+        }).collect();
+        clusters.sort_by(|a, b| a.1.cmp(&b.1));
+        clusters
+    };
+    // names are compared phonetically and empty emails don't count
+    assert_eq!(clusters(json!({})).await, vec![
+        (1.0, vec!["Jon Smyth".to_owned(), "John Smith ".to_owned()]),
+        (1.0, vec!["Marie Mayor".to_owned(), "Mary Mayor".to_owned()]),
+    ]);
+    // pairs join into clusters with the mean score of their pairs
+    let response = app.req("Lead", "findDuplicates", json!({ "threshold": 0.75 })).await;
+    let scores: Vec<f64> = response["data"].as_array().unwrap().iter().map(|cluster| cluster["score"].as_f64().unwrap()).collect();
+    assert_eq!(scores.len(), 2);
+    assert_eq!(scores[0], 1.0);
+    assert!((scores[1] - (1.0 + 2.0 * (1.0 - 2.0 / 9.0)) / 3.0).abs() < 1e-9, "{:?}", scores);
+    assert_eq!(response["data"][1]["records"].as_array().unwrap().len(), 3);
+    assert_eq!(clusters(json!({ "where": { "city": "London" }, "fields": ["email"] })).await, vec![]);
+    assert_eq!(clusters(json!({ "fields": ["email"] })).await, vec![(1.0, vec!["Jon Smyth".to_owned(), "John Smith ".to_owned()])]);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Lead/findDuplicates")).set_json(json!({ "fields": ["city"] }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect `fields` to contain @dedupe fields");
+}
+
+async fn merging(app: &TestApp) {
+    let now = Utc::now();
+    let company = |name: &str, phone: Option<&str>, website: Option<&str>, age: i64, employees: Vec<&str>| {
+        let updated_at = (now - chrono::Duration::days(age)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let employees: Vec<JsonValue> = employees.into_iter().map(|name| json!({ "name": name })).collect();
+        let body = json!({ "create": { "name": name, "phone": phone, "website": website, "updatedAt": updated_at, "employees": { "create": employees } } });
+        async move { app.req("Company", "create", body).await["data"]["id"].clone() }
+    };
+    let merge = |body: JsonValue| send(app, TestRequest::post().uri(&app.uri("/Company/merge")).set_json(body));
+    let acme = company("Acme Inc", None, Some("acme.com"), 1, vec!["ann", "bob"]).await;
+    let target = company("ACME", Some("555-0100"), Some("acme.org"), 0, vec!["cid"]).await;
+    let (status, response) = merge(json!({ "source": { "id": acme }, "target": { "id": target } })).await;
+    assert_eq!(status, 200);
+    // the target keeps its values and takes the ones it misses
+    assert_eq!((&response["data"]["name"], &response["data"]["phone"], &response["data"]["website"]), (&json!("ACME"), &json!("555-0100"), &json!("acme.org")));
+    assert_eq!(response["meta"]["relinked"], 2);
+    let employees = app.req("Employee", "count", json!({ "where": { "companyId": target } })).await;
+    assert_eq!(employees["data"], 3);
+    let source = app.req("Company", "findUnique", json!({ "where": { "id": acme } })).await;
+    assert!(source["data"]["deletedAt"].is_string(), "{}", source);
+    let (status, response) = merge(json!({ "source": { "id": acme }, "target": { "id": target } })).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "the source record is already deleted");
+    // strategies pick values per field
+    let newer = company("New Name", None, Some("new.com"), 0, vec![]).await;
+    let older = company("Old Name", Some("555-0101"), None, 5, vec![]).await;
+    let (_, response) = merge(json!({ "source": { "id": newer }, "target": { "id": older }, "strategy": "newest", "fields": { "website": "keepTarget" } })).await;
+    assert_eq!((&response["data"]["name"], &response["data"]["phone"], &response["data"]["website"]), (&json!("New Name"), &json!("555-0101"), &json!("new.com")));
+    assert_eq!(response["meta"]["relinked"], 0);
+    let source = company("Source", None, Some("source.com"), 0, vec![]).await;
+    let (_, response) = merge(json!({ "source": { "id": source }, "target": { "id": older }, "strategy": "keepSource" })).await;
+    assert_eq!((&response["data"]["name"], &response["data"]["phone"], &response["data"]["website"]), (&json!("Source"), &json!("555-0101"), &json!("source.com")));
+    for (body, message) in [
+        (json!({ "source": { "id": target }, "target": { "id": target } }), "expect `source` and `target` to be different records"),
+        (json!({ "source": { "id": target }, "target": { "id": 0 } }), "the target record is not found"),
+        (json!({ "target": { "id": target } }), "expect `source` to be an object"),
+        (json!({ "source": { "id": older }, "target": { "id": target }, "strategy": "longest" }), "expect a merge strategy to be one of keepTarget, keepSource and newest"),
+    ] {
+        let (status, response) = merge(body).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn tree_queries(app: &TestApp) {
+    let category = |name: &'static str, parent: JsonValue| async move {
+        app.req("Category", "create", json!({ "create": { "name": name, "parentId": parent } })).await["data"]["id"].clone()
+    };
+    let root = category("root", JsonValue::Null).await;
+    let books = category("books", root.clone()).await;
+    let music = category("music", root.clone()).await;
+    let novels = category("novels", books.clone()).await;
+    let crime = category("crime", novels.clone()).await;
+    category("other root", JsonValue::Null).await;
+    let names = |r#where: JsonValue| async move {
+        let response = app.req("Category", "findMany", json!({ "where": r#where, "orderBy": { "id": "asc" } })).await;
+        response["data"].as_array().unwrap().iter().map(|category| category["name"].as_str().unwrap().to_owned()).collect::<Vec<String>>()
+    };
+    assert_eq!(names(json!({ "descendants": root })).await, vec!["books", "music", "novels", "crime"]);
+    assert_eq!(names(json!({ "subtreeOf": books })).await, vec!["books", "novels", "crime"]);
+    assert_eq!(names(json!({ "ancestors": crime })).await, vec!["root", "books", "novels"]);
+    assert_eq!(names(json!({ "descendants": crime })).await, Vec::<String>::new());
+    // the tree input narrows the rest of the `where`
+    assert_eq!(names(json!({ "descendants": root, "name": { "in": ["music", "crime", "other root"] } })).await, vec!["music", "crime"]);
+    let response = app.req("Category", "count", json!({ "where": { "subtreeOf": root } })).await;
+    assert_eq!(response["data"], 5);
+    for (r#where, message) in [
+        (json!({ "ancestors": crime, "descendants": root }), "expect only one of `ancestors`, `descendants` and `subtreeOf`"),
+        (json!({ "descendants": { "id": root } }), "expect `descendants` to be the id of a node"),
+        (json!({ "subtreeOf": null }), "expect `subtreeOf` to be the id of a node"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Category/findMany")).set_json(json!({ "where": r#where }))).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+    // records can't move under their own subtree
+    for parent in [&crime, &books] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Category/update")).set_json(json!({ "where": { "id": books }, "update": { "parentId": parent } }))).await;
+        assert_ne!(status, 200);
+        assert!(response["error"].to_string().contains("cannot be moved under its own subtree"), "{}", response);
+    }
+    let response = app.req("Category", "update", json!({ "where": { "id": novels }, "update": { "parentId": music } })).await;
+    assert_eq!(response["data"]["parentId"], music);
+    assert_eq!(names(json!({ "subtreeOf": music })).await, vec!["music", "novels", "crime"]);
+    assert_eq!(names(json!({ "ancestors": crime })).await, vec!["root", "music", "novels"]);
+}
+
+async fn positions(app: &TestApp) {
+    let mut ids = HashMap::new();
+    for (title, list) in [("a", 1), ("b", 1), ("c", 1), ("d", 2)] {
+        let response = app.req("Card", "create", json!({ "create": { "title": title, "listId": list } })).await;
+        ids.insert(title.to_owned(), response["data"]["id"].clone());
+    }
+    let response = app.req("Card", "createMany", json!({ "create": [{ "title": "e", "listId": 1 }, { "title": "f", "listId": 1 }, { "title": "g", "listId": 2, "position": 7 }] })).await;
+    for card in response["data"].as_array().unwrap() {
+        ids.insert(card["title"].as_str().unwrap().to_owned(), card["id"].clone());
+    }
+    let list = |list: i64| async move {
+        let response = app.req("Card", "findMany", json!({ "where": { "listId": list }, "orderBy": { "position": "asc" } })).await;
+        response["data"].as_array().unwrap().iter().map(|card| (card["title"].as_str().unwrap().to_owned(), card["position"].as_i64().unwrap())).collect::<Vec<(String, i64)>>()
+    };
+    let order = |cards: &[(&str, i64)]| cards.iter().map(|(title, position)| (title.to_string(), *position)).collect::<Vec<(String, i64)>>();
+    assert_eq!(list(1).await, order(&[("a", 0), ("b", 1), ("c", 2), ("e", 3), ("f", 4)]));
+    assert_eq!(list(2).await, order(&[("d", 0), ("g", 7)]));
+    let update = |title: &'static str, update: JsonValue| {
+        let request = TestRequest::post().uri(&app.uri("/Card/update")).set_json(json!({ "where": { "id": ids[title] }, "update": update }));
+        send(app, request)
+    };
+    // the siblings are renumbered without gaps
+    let (status, response) = update("c", json!({ "position": { "moveTo": 0 } })).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["data"]["position"], 0);
+    assert_eq!(list(1).await, order(&[("c", 0), ("a", 1), ("b", 2), ("e", 3), ("f", 4)]));
+    update("a", json!({ "position": { "moveAfter": { "id": ids["f"] } } })).await;
+    assert_eq!(list(1).await, order(&[("c", 0), ("b", 1), ("e", 2), ("f", 3), ("a", 4)]));
+    update("b", json!({ "position": { "moveBefore": { "id": ids["c"] } } })).await;
+    assert_eq!(list(1).await, order(&[("b", 0), ("c", 1), ("e", 2), ("f", 3), ("a", 4)]));
+    update("c", json!({ "position": { "moveTo": 99 } })).await;
+    assert_eq!(list(1).await, order(&[("b", 0), ("e", 1), ("f", 2), ("a", 3), ("c", 4)]));
+    // a record moves into the list given with the update
+    update("a", json!({ "listId": 2, "position": { "moveTo": 1 } })).await;
+    assert_eq!(list(2).await, order(&[("d", 0), ("a", 1), ("g", 2)]));
+    for (title, update_input, message) in [
+        ("b", json!({ "position": { "moveBefore": { "id": ids["d"] } } }), "the record of `moveBefore` is not in the same list"),
+        ("b", json!({ "position": { "moveAfter": { "id": 0 } } }), "the record of `moveAfter` is not found"),
+        ("b", json!({ "position": { "moveTo": -1 } }), "expect `moveTo` to be an index"),
+    ] {
+        let (status, response) = update(title, update_input).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+    assert_eq!(list(1).await, order(&[("b", 0), ("e", 1), ("f", 2), ("c", 4)]));
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Card/create")).set_json(json!({ "create": { "title": "h" } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "`listId` is required to position `position`");
+}
+
+async fn tagging(app: &TestApp) {
+    let recipe = |title: &str, tags: JsonValue| {
+        let body = json!({ "create": { "title": title, "tags": { "connectOrCreateByName": tags } }, "include": { "tags": true } });
+        async move { app.req("Recipe", "create", body).await }
+    };
+    let tag_names = |recipe: &JsonValue| {
+        let mut names: Vec<String> = recipe["data"]["tags"].as_array().unwrap().iter().map(|tag| tag["name"].as_str().unwrap().to_owned()).collect();
+        names.sort();
+        names
+    };
+    // names are trimmed, and empty and repeated names are dropped
+    let soup = recipe("soup", json!(["vegan", " vegan ", "", "soup"])).await;
+    assert_eq!(tag_names(&soup), vec!["soup", "vegan"]);
+    // existing tags are connected instead of created
+    let curry = recipe("curry", json!(["vegan", "spicy"])).await;
+    assert_eq!(tag_names(&curry), vec!["spicy", "vegan"]);
+    recipe("stew", json!(["vegetables"])).await;
+    assert_eq!(app.req("Label", "count", json!({})).await["data"], 4);
+    let response = app.req("Recipe", "update", json!({ "where": { "id": soup["data"]["id"] }, "update": { "tags": { "connectOrCreateByName": ["spicy", "vegetables"] } }, "include": { "tags": true } })).await;
+    assert_eq!(tag_names(&response), vec!["soup", "spicy", "vegan", "vegetables"]);
+    // suggestions are ordered by usage, then by name
+    let suggest = |body: JsonValue| app.req("Recipe", "suggestTags", body);
+    assert_eq!(suggest(json!({ "prefix": "ve" })).await["data"], json!([{ "name": "vegan", "count": 2 }, { "name": "vegetables", "count": 2 }]));
+    assert_eq!(suggest(json!({ "prefix": "" })).await["data"], json!([
+        { "name": "spicy", "count": 2 },
+        { "name": "vegan", "count": 2 },
+        { "name": "vegetables", "count": 2 },
+        { "name": "soup", "count": 1 },
+    ]));
+    assert_eq!(suggest(json!({ "prefix": "s", "take": 1 })).await["data"], json!([{ "name": "spicy", "count": 2 }]));
+    assert_eq!(suggest(json!({ "prefix": "x" })).await["data"], json!([]));
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Recipe/suggestTags")).set_json(json!({}))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect `prefix` to be a string");
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Recipe/create")).set_json(json!({ "create": { "title": "pie", "tags": { "connectOrCreateByName": "sweet" } } }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect `connectOrCreateByName` to be an array of names");
+}
+
+async fn named_queries(app: &TestApp) {
+    for (title, list) in [("a", 1), ("b", 1), ("c", 1), ("d", 2)] {
+        app.req("Card", "create", json!({ "create": { "title": title, "listId": list } })).await;
+    }
+    let titles = |body: JsonValue| async move {
+        let response = app.req("Card", "cardsOfList", body).await;
+        response["data"].as_array().unwrap().iter().map(|card| card["title"].as_str().unwrap().to_owned()).collect::<Vec<String>>()
+    };
+    assert_eq!(titles(json!({ "params": { "list": 1 } })).await, vec!["a", "b"]);
+    assert_eq!(titles(json!({ "params": { "list": 2 } })).await, vec!["d"]);
+    // `skip` and `take` of the request replace the stored ones
+    assert_eq!(titles(json!({ "params": { "list": 1 }, "skip": 1, "take": 5 })).await, vec!["b", "c"]);
+    for (body, message) in [
+        (json!({}), "parameter `list` is required"),
+        (json!({ "params": [1] }), "expect `params` to be an object"),
+        (json!({ "params": { "list": 1 }, "take": "2" }), "expect `take` to be an integer"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Card/cardsOfList")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+    // the parameters are validated like `findMany` input
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Card/cardsOfList")).set_json(json!({ "params": { "list": "one" } }))).await;
+    assert_eq!(status, 400);
+}
+
+async fn response_envelopes(app: &TestApp) {
+    app.app().envelope(Envelope::default().data_key("result").meta_key("pagination").error_key("errors"));
+    let response = app.req("Note", "create", json!({ "create": { "title": "enveloped" } })).await;
+    assert_eq!(response["result"]["title"], "enveloped");
+    assert!(response.get("data").is_none(), "{}", response);
+    let response = app.req("Note", "findMany", json!({})).await;
+    assert!(response["result"].is_array(), "{}", response);
+    assert!(response["pagination"]["count"].is_number(), "{}", response);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Note/create")).set_json(json!({ "create": {} }))).await;
+    assert_eq!(status, 400);
+    assert!(response["errors"]["message"].is_string(), "{}", response);
+    assert!(response.get("error").is_none(), "{}", response);
+    // bare responses carry their meta in a header
+    app.app().action_envelope("Note.findMany", Envelope::bare());
+    app.app().action_envelope("findMany", Envelope::default().data_key("items"));
+    let response = app.call(TestRequest::post().uri(&app.uri("/Note/findMany")).set_json(json!({})).to_request()).await;
+    let meta: JsonValue = serde_json::from_str(response.headers().get(META_HEADER).unwrap().to_str().unwrap()).unwrap();
+    assert!(meta["count"].is_number(), "{}", meta);
+    let body: JsonValue = serde_json::from_slice(&read_body(response).await).unwrap();
+    assert!(body.as_array().unwrap().iter().any(|note| note["title"] == "enveloped"), "{}", body);
+    assert!(app.req("Tag", "findMany", json!({})).await["items"].is_array());
+    app.app().envelope(Envelope::default());
+    app.app().action_envelope("Note.findMany", Envelope::default());
+    app.app().action_envelope("findMany", Envelope::default());
+    assert!(app.req("Note", "findMany", json!({})).await["data"].is_array());
+}
+
+async fn partial_updates(app: &TestApp) {
+    let response = app.req("Company", "create", json!({
+        "create": { "name": "Initech", "phone": "555-0100", "updatedAt": Utc::now().to_rfc3339(), "employees": { "create": [{ "name": "Peter" }, { "name": "Milton", "title": "collator" }] } },
+        "include": { "employees": { "orderBy": { "id": "asc" } } },
+    })).await;
+    let id = response["data"]["id"].clone();
+    let (peter, milton) = (response["data"]["employees"][0]["id"].clone(), response["data"]["employees"][1]["id"].clone());
+    let update = |update: JsonValue| app.req("Company", "update", json!({ "where": { "id": id }, "update": update, "include": { "employees": { "orderBy": { "id": "asc" } } } }));
+    // only fields which are null are set
+    let response = update(json!({ "phone": { "setIfMissing": "555-0199" }, "website": { "setIfMissing": "initech.com" } })).await;
+    assert_eq!((&response["data"]["phone"], &response["data"]["website"]), (&json!("555-0100"), &json!("initech.com")));
+    // null is written, absent fields are left alone
+    let response = update(json!({ "phone": null })).await;
+    assert_eq!((&response["data"]["phone"], &response["data"]["website"]), (&JsonValue::Null, &json!("initech.com")));
+    let response = update(json!({ "name": "Initrode" })).await;
+    assert_eq!((&response["data"]["phone"], &response["data"]["website"]), (&JsonValue::Null, &json!("initech.com")));
+    // nested updates only write the fields sent
+    let response = update(json!({ "employees": { "update": [
+        { "where": { "id": peter }, "update": { "title": { "setIfMissing": "engineer" } } },
+        { "where": { "id": milton }, "update": { "name": "Milton Waddams", "title": { "setIfMissing": "engineer" } } },
+    ] } })).await;
+    assert_eq!(response["data"]["employees"], json!([
+        { "id": peter, "name": "Peter", "title": "engineer", "companyId": id },
+        { "id": milton, "name": "Milton Waddams", "title": "collator", "companyId": id },
+    ]));
+    let response = app.req("Employee", "update", json!({ "where": { "id": peter }, "update": { "company": { "update": { "phone": { "setIfMissing": "555-0123" }, "website": { "setIfMissing": "initrode.com" } } } }, "include": { "company": true } })).await;
+    assert_eq!((&response["data"]["company"]["phone"], &response["data"]["company"]["website"]), (&json!("555-0123"), &json!("initech.com")));
+    for (path, body, message) in [
+        ("/Company/updateMany", json!({ "where": {}, "update": { "phone": { "setIfMissing": "0" } } }), "`setIfMissing` is not supported in `updateMany`, update the records one by one"),
+        ("/Company/update", json!({ "where": { "id": id }, "update": { "employees": { "updateMany": { "where": {}, "update": { "title": { "setIfMissing": "0" } } } } } }), "`setIfMissing` is not supported in the `updateMany` of `employees`"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri(path)).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn deep_nested_writes(app: &TestApp) {
+    let include = json!({ "stores": { "include": { "shelves": { "include": { "items": { "orderBy": { "id": "asc" } } }, "orderBy": { "id": "asc" } } } } });
+    let response = app.req("Region", "create", json!({
+        "create": { "name": "north", "stores": { "create": [{ "name": "oslo", "shelves": { "create": [
+            { "code": "A1", "items": { "create": [{ "name": "bolt" }, { "name": "nut", "note": "m8" }] } },
+            { "code": "A2" },
+        ] } }] } },
+        "include": include,
+    })).await;
+    let region = response["data"]["id"].clone();
+    let store = &response["data"]["stores"][0];
+    assert_eq!(store["regionId"], region);
+    assert_eq!(store["shelves"][0]["storeId"], store["id"]);
+    let items: Vec<(&JsonValue, &JsonValue, &JsonValue)> = store["shelves"][0]["items"].as_array().unwrap().iter().map(|item| (&item["name"], &item["note"], &item["shelfId"])).collect();
+    assert_eq!(items, vec![(&json!("bolt"), &JsonValue::Null, &store["shelves"][0]["id"]), (&json!("nut"), &json!("m8"), &store["shelves"][0]["id"])]);
+    assert_eq!(store["shelves"][1]["items"], json!([]));
+    let store_id = store["id"].clone();
+    // records holding the foreign keys are written after the records they reference
+    let response = app.req("Item", "create", json!({
+        "create": { "name": "washer", "shelf": { "create": { "code": "B1", "store": { "create": { "name": "bergen", "region": { "connect": { "id": region } } } } } } },
+        "include": { "shelf": { "include": { "store": true } } },
+    })).await;
+    assert_eq!(response["data"]["shelf"]["code"], "B1");
+    assert_eq!(response["data"]["shelf"]["store"]["name"], "bergen");
+    assert_eq!(response["data"]["shelf"]["store"]["regionId"], region);
+    // nested updates create records under existing ones
+    let response = app.req("Region", "update", json!({
+        "where": { "id": region },
+        "update": { "name": "nord", "stores": { "update": { "where": { "id": store_id }, "update": { "shelves": { "create": { "code": "A3", "items": { "create": { "name": "screw" } } } } } } } },
+        "include": include,
+    })).await;
+    assert_eq!(response["data"]["name"], "nord");
+    let shelves = response["data"]["stores"].as_array().unwrap().iter().find(|store| store["id"] == store_id).unwrap()["shelves"].clone();
+    assert_eq!(shelves.as_array().unwrap().len(), 3);
+    assert_eq!(shelves[2]["items"][0]["name"], "screw");
+    // a failed write leaves no records behind
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Region/create")).set_json(json!({
+        "create": { "name": "south", "stores": { "create": { "name": "rome", "shelves": { "create": [{ "code": "C1", "items": { "create": { "name": "pin" } } }, { "code": "A1" }] } } } },
+    }))).await;
+    assert!(status >= 400, "{}", status);
+    assert_eq!(app.req("Region", "count", json!({ "where": { "name": "south" } })).await["data"], 0);
+    assert_eq!(app.req("Shelf", "count", json!({ "where": { "code": "C1" } })).await["data"], 0);
+    assert_eq!(app.req("Item", "count", json!({ "where": { "name": "pin" } })).await["data"], 0);
+    for (body, message) in [
+        (json!({ "where": { "id": region }, "update": { "stores": { "update": { "where": { "id": store_id }, "update": { "shelves": { "delete": { "code": "A2" }, "create": { "code": "A4", "items": { "create": { "name": "rivet" } } } } } } } } }), "`delete` of `Store.shelves` is not supported in nested writes deeper than 2 relations"),
+        (json!({ "where": { "id": region }, "update": { "stores": { "update": { "update": { "name": "x" } } } } }), "expect `where` in `update` of `stores`"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Region/update")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn nested_upserts(app: &TestApp) {
+    let region = app.req("Region", "create", json!({ "create": { "name": "east" } })).await["data"]["id"].clone();
+    // a to-one relation connects the record found, or creates one
+    let response = app.req("Store", "create", json!({ "create": { "name": "tokyo", "region": { "connectOrCreate": { "where": { "id": region }, "create": { "name": "unused" } } } } })).await;
+    assert_eq!(response["data"]["regionId"], region);
+    let store = response["data"]["id"].clone();
+    let response = app.req("Store", "create", json!({ "create": { "name": "lima", "region": { "connectOrCreate": { "where": { "id": 0 }, "create": { "name": "west" } } } }, "include": { "region": true } })).await;
+    assert_eq!(response["data"]["region"]["name"], "west");
+    assert_eq!(app.req("Region", "count", json!({ "where": { "name": "unused" } })).await["data"], 0);
+    // a list relation takes the records it creates
+    let response = app.req("Region", "update", json!({
+        "where": { "id": region },
+        "update": { "stores": { "connectOrCreate": [
+            { "where": { "id": store }, "create": { "name": "unused" } },
+            { "where": { "id": 0 }, "create": { "name": "osaka" } },
+        ] } },
+        "include": { "stores": { "orderBy": { "id": "asc" } } },
+    })).await;
+    let names: Vec<&str> = response["data"]["stores"].as_array().unwrap().iter().map(|store| store["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["tokyo", "osaka"]);
+    // upserts update the record found and create the missing ones
+    let response = app.req("Region", "update", json!({
+        "where": { "id": region },
+        "update": { "stores": { "upsert": [
+            { "where": { "id": store }, "create": { "name": "unused" }, "update": { "name": "kyoto" } },
+            { "where": { "id": 0 }, "create": { "name": "nagoya" }, "update": { "name": "unused" } },
+        ] } },
+        "include": { "stores": { "orderBy": { "id": "asc" } } },
+    })).await;
+    let names: Vec<&str> = response["data"]["stores"].as_array().unwrap().iter().map(|store| store["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["kyoto", "osaka", "nagoya"]);
+    // a to-one upsert without `where` finds the record through the relation
+    let shelf = app.req("Shelf", "create", json!({ "create": { "code": "D1", "storeId": store } })).await["data"]["id"].clone();
+    let item = app.req("Item", "create", json!({ "create": { "name": "gear", "shelfId": shelf } })).await["data"]["id"].clone();
+    let response = app.req("Item", "update", json!({ "where": { "id": item }, "update": { "shelf": { "upsert": { "create": { "code": "unused", "storeId": store }, "update": { "code": "D2" } } } }, "include": { "shelf": true } })).await;
+    assert_eq!((&response["data"]["shelf"]["id"], &response["data"]["shelf"]["code"]), (&shelf, &json!("D2")));
+    let response = app.req("Item", "create", json!({ "create": { "name": "cog", "shelf": { "upsert": { "create": { "code": "D3", "storeId": store }, "update": { "code": "unused" } } } }, "include": { "shelf": true } })).await;
+    assert_eq!(response["data"]["shelf"]["code"], "D3");
+    assert_eq!(app.req("Shelf", "count", json!({ "where": { "code": "unused" } })).await["data"], 0);
+    for (body, message) in [
+        (json!({ "where": { "id": region }, "update": { "stores": { "upsert": { "create": { "name": "x" }, "update": { "name": "x" } } } } }), "expect `where` in `upsert` of `stores`"),
+        (json!({ "where": { "id": region }, "update": { "stores": { "connectOrCreate": { "create": { "name": "x" } } } } }), "expect `where` in `connectOrCreate` of `stores`"),
+        (json!({ "where": { "id": region }, "update": { "stores": { "connectOrCreate": { "where": { "id": 0 }, "create": { "name": "x", "shelves": { "create": { "code": "x" } } } } } } }), "nested relation writes are not supported inside `connectOrCreate` of `stores`"),
+        (json!({ "where": { "id": region }, "update": { "stores": { "upsert": { "where": { "id": 0 }, "create": { "name": "x", "shelves": { "create": { "code": "x" } } }, "update": {} } } } }), "nested relation writes are not supported inside `upsert` of `stores`"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Region/update")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn relation_pruning(app: &TestApp) {
+    app.req("Recipe", "create", json!({ "create": { "title": "salad", "tags": { "connectOrCreateByName": ["green"] } } })).await;
+    let recipe = app.req("Recipe", "create", json!({ "create": { "title": "pasta", "tags": { "connectOrCreateByName": ["apple", "avocado", "basil", "cheese"] } } })).await["data"]["id"].clone();
+    let update = |update: JsonValue| async move {
+        let response = app.req("Recipe", "update", json!({ "where": { "id": recipe }, "update": update, "include": { "tags": { "orderBy": { "name": "asc" } } } })).await;
+        response["data"]["tags"].as_array().unwrap().iter().map(|tag| tag["name"].as_str().unwrap().to_owned()).collect::<Vec<String>>()
+    };
+    // disconnect unlinks the matching records and keeps them
+    assert_eq!(update(json!({ "tags": { "disconnect": [{ "where": { "name": { "startsWith": "a" } } }] } })).await, vec!["basil", "cheese"]);
+    assert_eq!(app.req("Label", "count", json!({ "where": { "name": { "startsWith": "a" } } })).await["data"], 2);
+    // set links the matching records and unlinks the others
+    assert_eq!(update(json!({ "tags": { "set": [{ "where": { "name": { "in": ["cheese", "green"] } } }, { "where": { "name": "apple" } }] } })).await, vec!["apple", "cheese", "green"]);
+    // deleteMany deletes the matching related records
+    assert_eq!(update(json!({ "tags": { "deleteMany": { "where": { "name": { "in": ["apple", "basil"] } } } } })).await, vec!["cheese", "green"]);
+    // only linked records are deleted
+    let response = app.req("Label", "findMany", json!({ "where": { "name": { "in": ["apple", "basil"] } } })).await;
+    assert_eq!(response["data"].as_array().unwrap().iter().map(|tag| tag["name"].as_str().unwrap()).collect::<Vec<&str>>(), vec!["basil"]);
+    let region = app.req("Region", "create", json!({ "create": { "name": "pruned", "stores": { "create": { "name": "store", "shelves": { "create": { "code": "P1", "items": { "create": [{ "name": "a" }, { "name": "b", "note": "kept" }, { "name": "c" }] } } } } } }, "include": { "stores": { "include": { "shelves": true } } } })).await;
+    let shelf = region["data"]["stores"][0]["shelves"][0]["id"].clone();
+    let response = app.req("Shelf", "update", json!({ "where": { "id": shelf }, "update": { "items": { "deleteMany": { "where": { "note": null } } } }, "include": { "items": true } })).await;
+    let names: Vec<&str> = response["data"]["items"].as_array().unwrap().iter().map(|item| item["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["b"]);
+    assert_eq!(app.req("Item", "count", json!({ "where": { "shelfId": shelf } })).await["data"], 1);
+    for (path, body, message) in [
+        ("/Region/update", json!({ "where": { "id": region["data"]["id"] }, "update": { "stores": { "disconnect": [{ "where": {} }] } } }), "cannot disconnect `Store` records, `regionId` is required"),
+        ("/Item/update", json!({ "where": { "id": 0 }, "update": { "shelf": { "set": [{ "where": {} }] } } }), "`set` of `Item.shelf` is only supported by list relations"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri(path)).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn generated_columns(app: &TestApp) {
+    // SQLite computes virtual columns when they are read
+    let item = app.req("LineItem", "create", json!({ "create": { "price": 3, "quantity": 4 } })).await["data"]["id"].clone();
+    let total = |response: JsonValue| response["data"]["total"].clone();
+    assert_eq!(total(app.req("LineItem", "findUnique", json!({ "where": { "id": item } })).await), 12);
+    app.req("LineItem", "update", json!({ "where": { "id": item }, "update": { "quantity": 5 } })).await;
+    assert_eq!(total(app.req("LineItem", "findUnique", json!({ "where": { "id": item } })).await), 15);
+    assert_eq!(app.req("LineItem", "count", json!({ "where": { "total": { "gt": 14 } } })).await["data"], 1);
+    // generated columns can't be written
+    for (path, body) in [
+        ("/LineItem/create", json!({ "create": { "price": 1, "quantity": 1, "total": 2 } })),
+        ("/LineItem/update", json!({ "where": { "id": item }, "update": { "total": 2 } })),
+    ] {
+        let (status, _) = send(app, TestRequest::post().uri(&app.uri(path)).set_json(body)).await;
+        assert_eq!(status, 400);
+    }
+    assert_eq!(total(app.req("LineItem", "findUnique", json!({ "where": { "id": item } })).await), 15);
+}
+
+async fn query_tags(app: &TestApp) {
+    app.app().query_tags(true);
+    let root = app.req("Category", "create", json!({ "create": { "name": "tagged root" } })).await["data"]["id"].clone();
+    app.req("Category", "create", json!({ "create": { "name": "tagged child", "parentId": root } })).await;
+    // the tree filter runs a tagged statement, a request id can't break it
+    for request_id in ["tagged", "a*/DROP';--", ""] {
+        let request = TestRequest::post().uri(&app.uri("/Category/findMany"))
+            .insert_header((REQUEST_ID_HEADER, request_id))
+            .set_json(json!({ "where": { "descendants": root } }));
+        let (status, response) = send(app, request).await;
+        assert_eq!(status, 200);
+        assert_eq!(response["data"][0]["name"], "tagged child");
+    }
+    app.app().query_tags(false);
+}
+
+fn magic_links(ttl: Duration) -> MagicLinks {
+    MagicLinks { url: "https://app.example.com/sign-in?token={token}".to_owned(), ttl, ..MagicLinks::default() }
+}
+
+async fn record_magic_link(link: MagicLink) -> teo_result::Result<()> {
+    MAGIC_LINKS.lock().unwrap().push(link);
+    Ok(())
+}
+
+async fn magic_link_sign_in(app: &TestApp) {
+    MAGIC_LINKS.lock().unwrap().clear();
+    app.req("User", "create", json!({ "create": { "email": "magic@example.com", "password": PASSWORD } })).await;
+    let verify = |token: String| async move {
+        send(app, TestRequest::post().uri(&app.uri("/User/verifyMagicLink")).set_json(json!({ "token": token }))).await
+    };
+    // unknown identities get the same response and no link
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/User/requestMagicLink")).set_json(json!({ "email": "nobody@example.com" }))).await;
+    assert_eq!(status, 200);
+    assert!(MAGIC_LINKS.lock().unwrap().is_empty());
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/User/requestMagicLink")).set_json(json!({ "email": "magic@example.com" }))).await;
+    assert_eq!(status, 200);
+    let link = MAGIC_LINKS.lock().unwrap().pop().unwrap();
+    assert_eq!(link.model, "User");
+    assert_eq!(link.recipient, "magic@example.com");
+    assert_eq!(link.url, format!("https://app.example.com/sign-in?token={}", link.token));
+    assert!(link.expires_at > Utc::now().timestamp_millis());
+    let (status, response) = verify(link.token.clone()).await;
+    assert_eq!(status, 200);
+    assert_eq!(response["data"]["email"], "magic@example.com");
+    assert!(response["meta"]["token"].is_string());
+    // a token is used once
+    let (status, response) = verify(link.token).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("MAGIC_LINK_INVALID"));
+    let (status, response) = verify("forged".to_owned()).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("MAGIC_LINK_INVALID"));
+    // expired tokens are rejected
+    app.app().magic_links(magic_links(Duration::ZERO), record_magic_link);
+    send(app, TestRequest::post().uri(&app.uri("/User/requestMagicLink")).set_json(json!({ "email": "magic@example.com" }))).await;
+    let link = MAGIC_LINKS.lock().unwrap().pop().unwrap();
+    let (status, response) = verify(link.token).await;
+    assert_eq!(status, 401);
+    assert_eq!(error_code(&response), Some("MAGIC_LINK_INVALID"));
+    app.app().magic_links(magic_links(Duration::from_secs(60)), record_magic_link);
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/User/requestMagicLink")).set_json(json!({ "email": 1 }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "expect `email` to be a string");
+}
+
+async fn credential_linking(app: &TestApp) {
+    let mut users = vec![];
+    for (email, ip) in [("linked-a@example.com", "10.0.9.1"), ("linked-b@example.com", "10.0.9.2")] {
+        let id = app.req("User", "create", json!({ "create": { "email": email, "password": PASSWORD } })).await["data"]["id"].clone();
+        let (_, response) = send(app, sign_in(app, email, PASSWORD, ip)).await;
+        users.push((id, response["meta"]["token"].as_str().unwrap().to_owned()));
+    }
+    let ((a, token_a), (b, token_b)) = (users[0].clone(), users[1].clone());
+    let authorized = |path: &str, token: &str, body: JsonValue| TestRequest::post().uri(&app.uri(path)).insert_header(("Authorization", format!("Bearer {}", token))).set_json(body);
+    let count = |user: JsonValue| async move { app.req("Credential", "count", json!({ "where": { "userId": user } })).await["data"].clone() };
+    let password = app.req("Credential", "create", json!({ "create": { "provider": "password", "subject": "linked-a@example.com", "userId": a } })).await["data"]["id"].clone();
+    let google = json!({ "provider": "google", "proof": { "idToken": "valid:sub-a" } });
+    let (status, response) = send(app, authorized("/Credential/link", &token_a, google.clone())).await;
+    assert_eq!(status, 200);
+    assert_eq!((&response["data"]["provider"], &response["data"]["subject"], &response["data"]["userId"]), (&json!("google"), &json!("sub-a"), &a));
+    let linked = response["data"]["id"].clone();
+    // linking again responds with the same credential
+    let (status, response) = send(app, authorized("/Credential/link", &token_a, google.clone())).await;
+    assert_eq!((status, &response["data"]["id"]), (200, &linked));
+    assert_eq!(count(a.clone()).await, 2);
+    for (token, body, status, code) in [
+        (token_b.as_str(), google.clone(), 409, "CREDENTIAL_LINKED"),
+        (token_a.as_str(), json!({ "provider": "google", "proof": { "idToken": "forged" } }), 401, "CREDENTIAL_INVALID"),
+        (token_b.as_str(), json!({ "where": { "id": linked } }), 404, "CREDENTIAL_NOT_FOUND"),
+    ] {
+        let path = if body.get("where").is_some() { "/Credential/unlink" } else { "/Credential/link" };
+        let (actual, response) = send(app, authorized(path, token, body)).await;
+        assert_eq!((actual, error_code(&response)), (status, Some(code)));
+    }
+    let (status, response) = send(app, authorized("/Credential/link", &token_a, json!({ "provider": "apple", "proof": {} }))).await;
+    assert_eq!(status, 400);
+    assert_eq!(response["error"]["message"], "provider `apple` is not supported");
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Credential/link")).set_json(google.clone())).await;
+    assert_eq!((status, error_code(&response)), (401, Some("IDENTITY_REQUIRED")));
+    // the last credential stays, through `unlink` and the builtin actions
+    let (status, _) = send(app, authorized("/Credential/unlink", &token_a, json!({ "where": { "id": linked } }))).await;
+    assert_eq!(status, 200);
+    let (status, response) = send(app, authorized("/Credential/unlink", &token_a, json!({ "where": { "id": password } }))).await;
+    assert_eq!((status, error_code(&response)), (409, Some("LAST_CREDENTIAL")));
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Credential/delete")).set_json(json!({ "where": { "id": password } }))).await;
+    assert_eq!((status, error_code(&response)), (409, Some("LAST_CREDENTIAL")));
+    assert_eq!(count(a.clone()).await, 1);
+    let (status, response) = send(app, authorized("/Credential/link", &token_b, json!({ "provider": "google", "proof": { "idToken": "valid:sub-b" } }))).await;
+    assert_eq!(status, 200);
+    let moved = json!({ "where": { "id": response["data"]["id"] }, "update": { "userId": a } });
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Credential/update")).set_json(moved)).await;
+    assert_eq!((status, error_code(&response)), (409, Some("LAST_CREDENTIAL")));
+    assert_eq!(count(b).await, 1);
+}
+
+async fn input_normalization(app: &TestApp) {
+    // the email is normalized before it's validated
+    let response = app.req("Subscriber", "create", json!({ "create": { "email": "  Reader@Example.COM ", "bio": " <p>Hello <b>world</b></p><script>alert(1)</script> ", "handle": "ReaderOne" } })).await;
+    assert_eq!(response["data"]["email"], "reader@example.com");
+    assert_eq!(response["data"]["bio"], "Hello world");
+    assert_eq!(response["data"]["handle"], "readerone");
+    let id = response["data"]["id"].clone();
+    // normalizers run on updates and leave null alone
+    let response = app.req("Subscriber", "update", json!({ "where": { "id": id }, "update": { "bio": null, "handle": "ÄBC" } })).await;
+    assert_eq!(response["data"]["bio"], JsonValue::Null);
+    assert_eq!(response["data"]["handle"], "äbc");
+    // unique lookups see the normalized value
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Subscriber/create")).set_json(json!({ "create": { "email": "READER@example.com" } }))).await;
+    assert_eq!(status, 400);
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Subscriber/create")).set_json(json!({ "create": { "email": " not an email " } }))).await;
+    assert_eq!(status, 400);
+}
+
+async fn constraint_rules(app: &TestApp) {
+    let response = app.req("Product", "create", json!({ "create": { "code": "tea-set", "discount": 0 } })).await;
+    assert_eq!(response["data"]["code"], "tea-set");
+    let id = response["data"]["id"].clone();
+    // SQLite has no check constraints for them, the app checks the rules
+    for (body, message) in [
+        (json!({ "code": "t" }), "expect at least 2 characters"),
+        (json!({ "code": "tea-sets-x" }), "expect at most 8 characters"),
+        (json!({ "code": "Tea" }), "expect a value matching `^[a-z-]+$`"),
+        (json!({ "code": "tea", "discount": -1 }), "expect a value of at least 0"),
+        (json!({ "code": "tea", "discount": 101 }), "expect a value of at most 100"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Product/create")).set_json(json!({ "create": body }))).await;
+        assert_eq!(status, 400);
+        assert!(response["error"].to_string().contains(message), "{}", response);
+    }
+    let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Product/update")).set_json(json!({ "where": { "id": id }, "update": { "discount": 200 } }))).await;
+    assert_eq!(status, 400);
+    assert!(response["error"].to_string().contains("expect a value of at most 100"), "{}", response);
+    // optional fields may be null
+    let response = app.req("Product", "update", json!({ "where": { "id": id }, "update": { "discount": null } })).await;
+    assert_eq!(response["data"]["discount"], JsonValue::Null);
+}
+
+async fn validate_action(app: &TestApp) {
+    let id = app.req("Product", "create", json!({ "create": { "code": "cup" } })).await["data"]["id"].clone();
+    let validate = |body: JsonValue| async move {
+        send(app, TestRequest::post().uri(&app.uri("/Product/validate")).set_json(body)).await
+    };
+    let (status, response) = validate(json!({ "create": { "code": "mug", "discount": 10 } })).await;
+    assert_eq!((status, &response["data"]), (200, &json!({ "valid": true })));
+    let (status, response) = validate(json!({ "where": { "id": id }, "update": { "discount": 50 } })).await;
+    assert_eq!((status, &response["data"]), (200, &json!({ "valid": true })));
+    // nothing is saved
+    assert_eq!(app.req("Product", "count", json!({})).await["data"], 1);
+    assert_eq!(app.req("Product", "findUnique", json!({ "where": { "id": id } })).await["data"]["discount"], JsonValue::Null);
+    // errors are the ones of create and update
+    for body in [json!({ "create": { "code": "Mug" } }), json!({ "where": { "id": id }, "update": { "discount": 101 } }), json!({ "create": { "discount": 1 } })] {
+        let (status, validated) = validate(body.clone()).await;
+        let path = if body.get("update").is_some() { "/Product/update" } else { "/Product/create" };
+        let (_, written) = send(app, TestRequest::post().uri(&app.uri(path)).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(validated["error"], written["error"]);
+    }
+    let (status, _) = validate(json!({ "where": { "id": 0 }, "update": { "discount": 1 } })).await;
+    assert_eq!(status, 404);
+    // models without `@@validateAction` have no validate action
+    let (status, _) = send(app, TestRequest::post().uri(&app.uri("/Note/validate")).set_json(json!({ "create": { "title": "a" } }))).await;
+    assert_eq!(status, 404);
+}
+
+async fn find_by_ids(app: &TestApp) {
+    let mut ids = vec![];
+    for title in ["first", "second", "third"] {
+        ids.push(app.req("Note", "create", json!({ "create": { "title": title } })).await["data"]["id"].as_i64().unwrap());
+    }
+    let titles = |response: JsonValue| response["data"].as_array().unwrap().iter().map(|note| note["title"].as_str().map(str::to_owned)).collect::<Vec<Option<String>>>();
+    // the requested order, null for missing ids, string ids match too
+    let response = app.req("Note", "findByIds", json!({ "ids": [ids[2], 0, ids[0], ids[1].to_string(), ids[2]] })).await;
+    assert_eq!(titles(response), vec![Some("third".to_owned()), None, Some("first".to_owned()), Some("second".to_owned()), Some("third".to_owned())]);
+    let response = app.req("Note", "findByIds", json!({ "ids": [ids[1]], "select": { "id": true } })).await;
+    assert_eq!(response["data"], json!([{ "id": ids[1] }]));
+    assert_eq!(app.req("Note", "findByIds", json!({ "ids": [] })).await["data"], json!([]));
+    for (body, message) in [
+        (json!({ "ids": ids[0] }), "expect `ids` to be an array".to_owned()),
+        (json!({ "ids": vec![1; 1001] }), "expect at most 1000 ids".to_owned()),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Note/findByIds")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn export_cursors(app: &TestApp) {
+    for index in 0..5 {
+        app.req("Note", "create", json!({ "create": { "title": format!("scan {}", index) } })).await;
+    }
+    app.req("Note", "create", json!({ "create": { "title": "skipped" } })).await;
+    let page = |cursor: JsonValue| async move {
+        let response = app.req("Note", "findMany", json!({ "where": { "title": { "startsWith": "scan" } }, "exportCursor": cursor, "take": 2 })).await;
+        let titles: Vec<String> = response["data"].as_array().unwrap().iter().map(|note| note["title"].as_str().unwrap().to_owned()).collect();
+        (titles, response["meta"]["cursor"].clone())
+    };
+    let (first, cursor) = page(json!(true)).await;
+    assert_eq!(first, vec!["scan 0", "scan 1"]);
+    let (second, next) = page(cursor.clone()).await;
+    assert_eq!(second, vec!["scan 2", "scan 3"]);
+    // a cursor resumes the scan as often as needed
+    assert_eq!(page(cursor).await.0, second);
+    let (last, end) = page(next).await;
+    assert_eq!((last, end), (vec!["scan 4".to_owned()], JsonValue::Null));
+    let category = app.req("Category", "create", json!({ "create": { "name": "scanned" } })).await["data"]["id"].clone();
+    app.req("Category", "create", json!({ "create": { "name": "scanned too", "parentId": category } })).await;
+    let other = app.req("Category", "findMany", json!({ "exportCursor": true, "take": 1 })).await["meta"]["cursor"].clone();
+    for (body, message) in [
+        (json!({ "exportCursor": true, "orderBy": { "title": "asc" } }), "`orderBy` can't be used with `exportCursor`, records are ordered by the primary key"),
+        (json!({ "exportCursor": true, "take": 0 }), "expect `take` to be between 1 and 10000 with `exportCursor`"),
+        (json!({ "exportCursor": 5 }), "expect `exportCursor` to be true or a cursor token"),
+        (json!({ "exportCursor": "forged" }), "the export cursor is invalid"),
+        (json!({ "exportCursor": other }), "the export cursor isn't a cursor of `Note`"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Note/findMany")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn estimated_counts(app: &TestApp) {
+    for title in ["counted", "counted", "other"] {
+        app.req("Note", "create", json!({ "create": { "title": title } })).await;
+    }
+    // SQLite has no table statistics, records are counted exactly
+    let response = app.req("Note", "count", json!({ "estimate": true })).await;
+    assert_eq!((&response["data"], &response["meta"]["estimated"]), (&json!(3), &json!(false)));
+    let response = app.req("Note", "count", json!({ "estimate": true, "where": { "title": "counted" } })).await;
+    assert_eq!((&response["data"], &response["meta"]["estimated"]), (&json!(2), &json!(false)));
+    let response = app.req("Note", "count", json!({ "estimate": false })).await;
+    assert_eq!(response["data"], 3);
+    assert!(response["meta"].get("estimated").is_none());
+    for (body, message) in [
+        (json!({ "estimate": true, "select": { "_all": true } }), "`estimate` can't be used with `select`"),
+        (json!({ "estimate": "yes" }), "expect `estimate` to be a bool"),
+    ] {
+        let (status, response) = send(app, TestRequest::post().uri(&app.uri("/Note/count")).set_json(body)).await;
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["message"], message);
+    }
+}
+
+async fn send(app: &TestApp, request: TestRequest) -> (u16, JsonValue) {
+    let response = app.call(request.to_request()).await;
+    let status = response.status().as_u16();
+    let bytes = read_body(response).await;
+    (status, serde_json::from_slice(&bytes).unwrap_or(JsonValue::Null))
+}
+
+fn error_code(response: &JsonValue) -> Option<&str> {
+    response["error"]["code"].as_str()
+}
+
+async fn create_user(app: &TestApp, email: &str) {
+    let response = app.req("User", "create", json!({ "create": { "email": email, "password": PASSWORD } })).await;
+    assert!(response.get("data").is_some());
+}
+
+fn sign_in(app: &TestApp, email: &str, password: &str, ip: &str) -> TestRequest {
+    TestRequest::post().uri(&app.uri("/User/signIn"))
+        .peer_addr(format!("{}:4000", ip).parse().unwrap())
+        .set_json(json!({ "credentials": { "email": email, "password": password } }))
+}
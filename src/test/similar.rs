@@ -0,0 +1,20 @@
+use crate::server::similar::similarity;
+
+#[test]
+fn equal_strings_are_similar_regardless_of_case() {
+    assert_eq!(similarity("Teo", "teo"), 1.0);
+    assert_eq!(similarity("", ""), 1.0);
+}
+
+#[test]
+fn similarity_is_the_share_of_unchanged_characters() {
+    assert_eq!(similarity("teo", "theo"), 0.75);
+    assert_eq!(similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+    assert_eq!(similarity("abc", "xyz"), 0.0);
+    assert_eq!(similarity("abc", ""), 0.0);
+}
+
+#[test]
+fn similarity_is_symmetric() {
+    assert_eq!(similarity("schema", "scheme"), similarity("scheme", "schema"));
+}
@@ -0,0 +1,30 @@
+use crate::server::slug::slugify;
+
+#[test]
+fn words_are_joined_with_hyphens() {
+    assert_eq!(slugify("Hello World"), "hello-world");
+    assert_eq!(slugify("  Rust -- is   fun!  "), "rust-is-fun");
+    assert_eq!(slugify("100% Pure"), "100-pure");
+}
+
+#[test]
+fn accents_are_transliterated() {
+    assert_eq!(slugify("Crème Brûlée!"), "creme-brulee");
+    assert_eq!(slugify("Łódź Świętokrzyska"), "lodz-swietokrzyska");
+}
+
+#[test]
+fn other_characters_are_dropped() {
+    assert_eq!(slugify("日本語"), "");
+    assert_eq!(slugify("東京 Tokyo"), "tokyo");
+    assert_eq!(slugify("!!!"), "");
+}
+
+#[test]
+fn slugs_are_cut_at_200_characters() {
+    let slug = slugify(&"ab ".repeat(100));
+    assert_eq!(slug.len(), 200);
+    assert!(!slug.ends_with('-'));
+    let slug = slugify(&format!("{} b", "a".repeat(199)));
+    assert_eq!(slug, "a".repeat(199));
+}
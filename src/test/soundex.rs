@@ -0,0 +1,32 @@
+use crate::stdlib::pipeline_items::text::soundex;
+
+#[test]
+fn words_are_coded_by_their_consonants() {
+    assert_eq!(soundex("Robert").as_deref(), Some("R163"));
+    assert_eq!(soundex("Rupert").as_deref(), Some("R163"));
+    assert_eq!(soundex("Tymczak").as_deref(), Some("T522"));
+    assert_eq!(soundex("Honeyman").as_deref(), Some("H555"));
+}
+
+#[test]
+fn codes_are_padded_to_four_characters() {
+    assert_eq!(soundex("Lee").as_deref(), Some("L000"));
+    assert_eq!(soundex("jon").as_deref(), Some("J500"));
+}
+
+#[test]
+fn letters_with_the_first_letter_code_are_skipped() {
+    assert_eq!(soundex("Pfister").as_deref(), Some("P236"));
+}
+
+#[test]
+fn h_and_w_dont_separate_letters_with_the_same_code() {
+    assert_eq!(soundex("Ashcraft").as_deref(), Some("A261"));
+}
+
+#[test]
+fn other_characters_are_ignored() {
+    assert_eq!(soundex("O'Brien").as_deref(), Some("O165"));
+    assert_eq!(soundex("123"), None);
+    assert_eq!(soundex(""), None);
+}
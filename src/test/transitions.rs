@@ -0,0 +1,26 @@
+use crate::utils::transitions::rewrite_source;
+
+#[test]
+fn arguments_are_rewritten_into_strings() {
+    let source = "model Article {\n  @transitions(draft -> published, .published -> archived, * -> draft)\n  status: Status\n}\n";
+    assert_eq!(rewrite_source(source).unwrap().unwrap(), "model Article {\n  @transitions([\"draft -> published\", \".published -> archived\", \"* -> draft\"])\n  status: Status\n}\n");
+}
+
+#[test]
+fn line_breaks_are_kept() {
+    let source = "@transitions(\n  draft -> published,\n  published -> archived,\n)\n";
+    assert_eq!(rewrite_source(source).unwrap().unwrap(), "@transitions([\n  \"draft -> published\",\n  \"published -> archived\",\n])\n");
+}
+
+#[test]
+fn sources_without_transitions_are_untouched() {
+    assert_eq!(rewrite_source("model A {\n  @map(\"a\")\n  a: Int\n}\n").unwrap(), None);
+}
+
+#[test]
+fn invalid_transitions_are_rejected() {
+    let error = rewrite_source("model A {\n  @transitions(draft published)\n  status: Status\n}\n").unwrap_err();
+    assert!(error.message.contains("line 2"));
+    assert!(error.message.contains("`draft published`"));
+    assert!(rewrite_source("@transitions(draft -> published\n").is_err());
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_result::{Error, Result};
+use crate::utils::{find_schema_files, matching_brace};
+
+static DB_FUNCTION_HANDLER: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?m)^([ \t]*declare[ \t]+handler[ \t]+([A-Za-z_]\w*)\b[^\n]*?)[ \t]+from[ \t]+dbFunction[ \t]*\([ \t]*"([^"\n]*)"[ \t]*\)"#).unwrap());
+pub(crate) static NAMESPACE_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*namespace[ \t]+([A-Za-z_]\w*)[ \t]*\{").unwrap());
+pub(crate) static HANDLER_GROUP_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*declare[ \t]+handler[ \t]+group[ \t]+([A-Za-z_]\w*)[ \t]*\{").unwrap());
+
+/// A handler declared in the schema with
+/// `declare handler stats(StatsInput): Stat[] from dbFunction("analytics_stats")`.
+///
+/// It's implemented by calling the database function with the validated
+/// input. A handler whose output type is a list responds with all rows the
+/// function returns, others with the first row.
+#[derive(Debug, Clone)]
+pub(crate) struct DbFunctionHandler {
+    pub(crate) namespace_path: Vec<String>,
+    pub(crate) name: String,
+    pub(crate) function: String,
+    pub(crate) many: bool,
+}
+
+/// Take the `from dbFunction(...)` clauses out of the handler declarations
+/// of the schema files under `dir`. Files which contain them are added to
+/// `overlays` with the clauses removed, so that the parser sees plain
+/// handler declarations. Sources already in `overlays` are read from there.
+pub(crate) fn extract_db_function_handlers(dir: &Path, overlays: &mut HashMap<String, String>) -> Result<Vec<DbFunctionHandler>> {
+    let mut result = vec![];
+    for path in find_schema_files(dir) {
+        let key = path.to_string_lossy().to_string();
+        let source = match overlays.get(&key) {
+            Some(source) => source.clone(),
+            None => match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            },
+        };
+        if let Some((rewritten, handlers)) = extract_from_source(&source)? {
+            overlays.insert(key, rewritten);
+            result.extend(handlers);
+        }
+    }
+    Ok(result)
+}
+
+/// Take the `from dbFunction(...)` clauses out of a single source. Returns
+/// `None` if it doesn't contain any.
+pub(crate) fn extract_from_source(source: &str) -> Result<Option<(String, Vec<DbFunctionHandler>)>> {
+    if !DB_FUNCTION_HANDLER.is_match(source) {
+        return Ok(None);
+    }
+    let chars: Vec<char> = source.chars().collect();
+    let namespaces = blocks(&NAMESPACE_BLOCK, source, &chars);
+    let groups = blocks(&HANDLER_GROUP_BLOCK, source, &chars);
+    let mut handlers = vec![];
+    for captures in DB_FUNCTION_HANDLER.captures_iter(source) {
+        let position = source[..captures.get(0).unwrap().start()].chars().count();
+        let name = captures[2].to_owned();
+        if groups.iter().any(|(_, start, end)| (*start..*end).contains(&position)) {
+            Err(Error::new(format!("handler `{}`: `from dbFunction` is not supported in handler groups", name)))?
+        }
+        let function = captures[3].to_owned();
+        if function.is_empty() || !function.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            Err(Error::new(format!("handler `{}`: invalid database function name `{}`", name, function)))?
+        }
+        handlers.push(DbFunctionHandler {
+            namespace_path: namespaces.iter().filter(|(_, start, end)| (*start..*end).contains(&position)).map(|(name, _, _)| name.clone()).collect(),
+            name,
+            function,
+            many: captures[1].trim_end().ends_with("[]"),
+        });
+    }
+    let rewritten = DB_FUNCTION_HANDLER.replace_all(source, "$1").to_string();
+    Ok(Some((rewritten, handlers)))
+}
+
+/// The names and char ranges of the blocks opened by a pattern, outer ones
+/// first.
+pub(crate) fn blocks(pattern: &Regex, source: &str, chars: &[char]) -> Vec<(String, usize, usize)> {
+    pattern.captures_iter(source).map(|captures| {
+        let start = source[..captures.get(0).unwrap().start()].chars().count();
+        let open = source[..captures.get(0).unwrap().end()].chars().count() - 1;
+        (captures[1].to_owned(), start, matching_brace(chars, open))
+    }).collect()
+}
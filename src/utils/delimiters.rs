@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use colored::Colorize;
+use teo_parser::diagnostics::diagnostics::Diagnostics;
+use teo_parser::diagnostics::printer::print_diagnostics;
+use teo_parser::parse as schema_parse;
+use crate::utils::{find_schema_files, skip_literal};
+
+/// An unbalanced delimiter found in a schema source. Lines and columns are
+/// 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DelimiterError {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) message: String,
+    pub(crate) expected: Vec<char>,
+    pub(crate) recoveries: Vec<Recovery>,
+}
+
+/// How the source is patched to parse on from an unbalanced delimiter.
+/// Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Recovery {
+    /// Insert `text` before the character at `line` and `column`.
+    Insert { line: usize, column: usize, text: String },
+    /// Blank out the character at `line` and `column`.
+    Remove { line: usize, column: usize },
+}
+
+/// Find unbalanced brackets, braces and parentheses. A single missing brace
+/// makes the parser emit many cascading errors; this reports the root cause
+/// instead, with the recoveries which let the parser go on. Strings, regular
+/// expressions and comments are skipped.
+pub(crate) fn check_delimiters(source: &str) -> Vec<DelimiterError> {
+    let mut errors = vec![];
+    let mut stack: Vec<(char, usize, usize)> = vec![];
+    let lines: Vec<&str> = source.lines().collect();
+    // the length of the code of each line, without its comment
+    let mut code_lens = vec![];
+    for (line_index, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut code_len = chars.len();
+        let mut i = 0;
+        let mut previous_significant: Option<char> = None;
+        while i < chars.len() {
+            let c = chars[i];
+            let (line_no, column) = (line_index + 1, i + 1);
+            match c {
+                '/' if chars.get(i + 1) == Some(&'/') => {
+                    code_len = i;
+                    break
+                }
+                '"' => i = skip_literal(&chars, i, '"'),
+                '/' if previous_significant.map_or(true, |p| matches!(p, '(' | ',' | '[' | '=' | ':')) => i = skip_literal(&chars, i, '/'),
+                '{' | '(' | '[' => stack.push((c, line_no, column)),
+                '}' | ')' | ']' => {
+                    let opener = opener_of(c);
+                    match stack.last() {
+                        Some((open, _, _)) if *open == opener => { stack.pop(); }
+                        Some((open, open_line, open_column)) => {
+                            let (open, open_line, open_column) = (*open, *open_line, *open_column);
+                            if let Some(index) = stack.iter().rposition(|(o, _, _)| *o == opener) {
+                                // assume the closers are missing and recover at the matching opener
+                                errors.push(DelimiterError {
+                                    line: line_no,
+                                    column,
+                                    message: format!("expected `{}` to close `{}` opened at {}:{}, found `{}`", closer_of(open), open, open_line, open_column, c),
+                                    expected: vec![closer_of(open)],
+                                    recoveries: stack[index + 1..].iter().rev().map(|(o, o_line, _)| if *o != '{' && *o_line < line_no {
+                                        Recovery::Insert { line: *o_line, column: code_lens[*o_line - 1] + 1, text: closer_of(*o).to_string() }
+                                    } else {
+                                        Recovery::Insert { line: line_no, column, text: closer_of(*o).to_string() }
+                                    }).collect(),
+                                });
+                                stack.truncate(index);
+                            } else {
+                                errors.push(DelimiterError {
+                                    line: line_no,
+                                    column,
+                                    message: format!("unexpected `{}`", c),
+                                    expected: vec![closer_of(open)],
+                                    recoveries: vec![Recovery::Remove { line: line_no, column }],
+                                });
+                            }
+                        }
+                        None => errors.push(DelimiterError {
+                            line: line_no,
+                            column,
+                            message: format!("unexpected `{}`", c),
+                            expected: vec![],
+                            recoveries: vec![Recovery::Remove { line: line_no, column }],
+                        }),
+                    }
+                }
+                _ => (),
+            }
+            if !c.is_whitespace() {
+                previous_significant = Some(c);
+            }
+            i += 1;
+        }
+        while code_len > 0 && chars[code_len - 1].is_whitespace() {
+            code_len -= 1;
+        }
+        code_lens.push(code_len);
+    }
+    for (open, line, column) in stack.into_iter().rev() {
+        errors.push(DelimiterError {
+            line,
+            column,
+            message: format!("unclosed `{}`", open),
+            expected: vec![closer_of(open)],
+            recoveries: vec![unclosed_recovery(&lines, &code_lens, open, line)],
+        });
+    }
+    errors
+}
+
+/// Where the closer of an unclosed opener is assumed to be missing. A brace
+/// is closed before the next line which is indented as far as the line it's
+/// opened on, which starts the next declaration, or at the end of the source.
+/// Brackets and parentheses are closed at the end of the code of their line.
+fn unclosed_recovery(lines: &[&str], code_lens: &[usize], open: char, line: usize) -> Recovery {
+    let closer = closer_of(open);
+    if open == '{' {
+        let indentation = |content: &str| content.chars().take_while(|c| c.is_whitespace()).count();
+        let opener_indentation = indentation(lines[line - 1]);
+        for (index, content) in lines.iter().enumerate().skip(line) {
+            let trimmed = content.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with(|c| matches!(c, '}' | ')' | ']')) {
+                continue
+            }
+            if indentation(content) <= opener_indentation {
+                return Recovery::Insert { line: index + 1, column: indentation(content) + 1, text: format!("{} ", closer) };
+            }
+        }
+        let last = lines.len();
+        return Recovery::Insert { line: last, column: lines[last - 1].chars().count() + 1, text: format!("\n{}", closer) };
+    }
+    Recovery::Insert { line, column: code_lens[line - 1] + 1, text: closer.to_string() }
+}
+
+/// Apply the recoveries of the delimiter errors of a source.
+pub(crate) fn recover_delimiters(source: &str, errors: &[DelimiterError]) -> String {
+    let mut lines: Vec<Vec<char>> = source.split('\n').map(|line| line.chars().collect()).collect();
+    let position = |recovery: &Recovery| match recovery {
+        Recovery::Insert { line, column, .. } | Recovery::Remove { line, column } => (*line, *column),
+    };
+    // from the end, so that positions stay valid, and inserts at a position
+    // keep their order
+    let mut recoveries: Vec<&Recovery> = errors.iter().flat_map(|error| error.recoveries.iter()).collect();
+    recoveries.reverse();
+    recoveries.sort_by(|a, b| position(b).cmp(&position(a)));
+    for recovery in recoveries {
+        let (line, column) = position(recovery);
+        let Some(chars) = lines.get_mut(line - 1) else { continue };
+        let index = (column - 1).min(chars.len());
+        match recovery {
+            Recovery::Insert { text, .. } => { chars.splice(index..index, text.chars()); }
+            Recovery::Remove { .. } => if index < chars.len() {
+                chars[index] = ' ';
+            },
+        }
+    }
+    lines.into_iter().map(|line| line.into_iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+fn opener_of(closer: char) -> char {
+    match closer {
+        '}' => '{',
+        ')' => '(',
+        _ => '[',
+    }
+}
+
+fn closer_of(opener: char) -> char {
+    match opener {
+        '{' => '}',
+        '(' => ')',
+        _ => ']',
+    }
+}
+
+/// Print the errors of a schema which doesn't parse. The unbalanced
+/// delimiters of the schema files under `dir` are printed with the closers
+/// expected. The schema is parsed again with them recovered, and the
+/// diagnostics of that parse are printed, which leaves out the errors
+/// cascading from the delimiters.
+pub(crate) fn print_schema_errors(dir: &Path, main: &Path, overlays: &HashMap<String, String>, diagnostics: &Diagnostics) {
+    let mut recovered = overlays.clone();
+    let mut found = false;
+    for path in find_schema_files(dir) {
+        let key = path.to_string_lossy().to_string();
+        let Some(source) = overlays.get(&key).cloned().or_else(|| fs::read_to_string(&path).ok()) else { continue };
+        let errors = check_delimiters(&source);
+        for error in &errors {
+            println!("{} {}:{}:{} {}", "Error".red().bold(), path.display(), error.line, error.column, error.message);
+            if !error.expected.is_empty() {
+                let expected: Vec<String> = error.expected.iter().map(|c| format!("`{}`", c)).collect();
+                println!("  {} expected {}", "help:".cyan().bold(), expected.join(" or "));
+            }
+        }
+        if !errors.is_empty() {
+            recovered.insert(key, recover_delimiters(&source, &errors));
+            found = true;
+        }
+    }
+    if found {
+        let (_, diagnostics) = schema_parse(main.to_str().unwrap(), None, Some(recovered));
+        print_diagnostics(&diagnostics, true);
+    } else {
+        print_diagnostics(diagnostics, true);
+    }
+}
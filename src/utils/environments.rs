@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use crate::utils::{find_schema_files, matching_brace, skip_line, skip_literal};
+
+/// A `keyword name? { ... }` block. `body` is the range between the braces.
+#[derive(Debug, Clone)]
+struct Block {
+    keyword: String,
+    name: Option<String>,
+    whole: Range<usize>,
+    body: Range<usize>,
+}
+
+/// A `key value` entry of a config block.
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    value: Range<usize>,
+}
+
+/// Apply the `environment "name" { ... }` blocks of schema sources.
+///
+/// The config blocks inside the block of the selected environment override
+/// the entries of the top level config blocks with the same keyword and name,
+/// entries missing from the top level block are added. All environment blocks
+/// are removed afterwards. Returns the rewritten sources of files which
+/// contain environment blocks, keyed by path, to be passed to the parser as
+/// unsaved files.
+pub(crate) fn apply_environment_overlays(dir: &Path, environment: Option<&str>) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    for path in find_schema_files(dir) {
+        let Ok(source) = fs::read_to_string(&path) else { continue };
+        if let Some(rewritten) = apply_environment_overlay(&source, environment) {
+            result.insert(path.to_string_lossy().to_string(), rewritten);
+        }
+    }
+    result
+}
+
+/// Apply the environment blocks of a single source. Returns `None` if the
+/// source doesn't contain any.
+pub(crate) fn apply_environment_overlay(source: &str, environment: Option<&str>) -> Option<String> {
+    let chars: Vec<char> = source.chars().collect();
+    let blocks = top_level_blocks(&chars, 0..chars.len());
+    let environment_blocks: Vec<&Block> = blocks.iter().filter(|b| b.keyword == "environment").collect();
+    if environment_blocks.is_empty() {
+        return None;
+    }
+    // (range, replacement) edits on the original source
+    let mut edits: Vec<(Range<usize>, String)> = vec![];
+    if let Some(selected) = environment_blocks.iter().find(|b| b.name.as_deref() == environment) {
+        for overlay in top_level_blocks(&chars, selected.body.clone()) {
+            let Some(target) = blocks.iter().find(|b| b.keyword == overlay.keyword && b.name == overlay.name) else { continue };
+            let target_entries = entries(&chars, target.body.clone());
+            let mut appended = String::new();
+            for entry in entries(&chars, overlay.body.clone()) {
+                let value: String = chars[entry.value.clone()].iter().collect();
+                match target_entries.iter().find(|e| e.key == entry.key) {
+                    Some(existing) => edits.push((existing.value.clone(), value)),
+                    None => appended.push_str(&format!("  {} {}\n", entry.key, value)),
+                }
+            }
+            if !appended.is_empty() {
+                let mut close = target.body.end;
+                while close > target.body.start && chars[close - 1].is_whitespace() && chars[close - 1] != '\n' {
+                    close -= 1;
+                }
+                let prefix = if chars[target.body.start..close].iter().rev().find(|c| !c.is_whitespace() || **c == '\n') == Some(&'\n') { "" } else { "\n" };
+                edits.push((close..close, format!("{}{}", prefix, appended)));
+            }
+        }
+    }
+    for block in environment_blocks {
+        // keep the line count so that diagnostics point at the right lines
+        let newlines = chars[block.whole.clone()].iter().filter(|c| **c == '\n').count();
+        edits.push((block.whole.clone(), "\n".repeat(newlines)));
+    }
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut result = String::new();
+    let mut last = 0;
+    for (range, replacement) in edits {
+        if range.start < last {
+            continue
+        }
+        result.extend(&chars[last..range.start]);
+        result.push_str(&replacement);
+        last = range.end;
+    }
+    result.extend(&chars[last..]);
+    Some(result)
+}
+
+/// Find the blocks directly inside `range`.
+fn top_level_blocks(chars: &[char], range: Range<usize>) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut i = range.start;
+    while i < range.end {
+        let c = chars[i];
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            i = skip_line(chars, i);
+            continue
+        }
+        if c == '"' {
+            i = skip_literal(chars, i, '"') + 1;
+            continue
+        }
+        if c.is_alphabetic() && (i == 0 || !is_identifier_char(chars[i - 1])) {
+            let start = i;
+            let keyword = identifier(chars, &mut i);
+            let mut j = skip_whitespace(chars, i);
+            let name = if j < range.end && chars[j] == '"' {
+                let end = skip_literal(chars, j, '"');
+                let name: String = chars[j + 1..end.min(chars.len())].iter().collect();
+                j = skip_whitespace(chars, end + 1);
+                Some(name)
+            } else if j < range.end && chars[j].is_alphabetic() {
+                let mut k = j;
+                let name = identifier(chars, &mut k);
+                let after = skip_whitespace(chars, k);
+                if after < range.end && chars[after] == '{' { j = after; Some(name) } else { None }
+            } else {
+                None
+            };
+            if j < range.end && chars[j] == '{' {
+                let close = matching_brace(chars, j);
+                blocks.push(Block { keyword, name, whole: start..(close + 1).min(chars.len()), body: j + 1..close });
+                i = close + 1;
+                continue
+            }
+            continue
+        }
+        if c == '{' || c == '(' || c == '[' {
+            i = matching_brace(chars, i) + 1;
+            continue
+        }
+        i += 1;
+    }
+    blocks
+}
+
+/// Find the `key value` entries of a block body. A value ends at a newline or
+/// comma outside of brackets.
+fn entries(chars: &[char], body: Range<usize>) -> Vec<Entry> {
+    let mut result = vec![];
+    let mut i = body.start;
+    while i < body.end {
+        let c = chars[i];
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            i = skip_line(chars, i);
+            continue
+        }
+        if c.is_alphabetic() || c == '_' {
+            let key = identifier(chars, &mut i);
+            let mut start = i;
+            while start < body.end && (chars[start] == ' ' || chars[start] == '\t') {
+                start += 1;
+            }
+            if start < body.end && chars[start] == ':' {
+                start = skip_whitespace(chars, start + 1);
+            }
+            if start >= body.end || chars[start] == '\n' {
+                continue
+            }
+            let mut j = start;
+            while j < body.end {
+                match chars[j] {
+                    '\n' | ',' => break,
+                    '/' if chars.get(j + 1) == Some(&'/') => break,
+                    '"' => j = skip_literal(chars, j, '"') + 1,
+                    '(' | '[' | '{' => j = matching_brace(chars, j) + 1,
+                    _ => j += 1,
+                }
+            }
+            let mut end = j.min(body.end);
+            while end > start && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            result.push(Entry { key, value: start..end });
+            i = j;
+            continue
+        }
+        i += 1;
+    }
+    result
+}
+
+fn identifier(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while *i < chars.len() && is_identifier_char(chars[*i]) {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn skip_whitespace(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Whether the app runs in development, that is `TEO_ENV` is unset, `dev` or
+/// `development`.
+pub(crate) fn is_development() -> bool {
+    match std::env::var("TEO_ENV") {
+        Ok(environment) => environment == "dev" || environment == "development",
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,20 @@
+use ring::digest::{digest, SHA256};
+
+/// Encode bytes as lowercase hex.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex, `None` if it isn't valid hex.
+pub(crate) fn unhex(string: &str) -> Option<Vec<u8>> {
+    if string.len() % 2 != 0 {
+        return None;
+    }
+    (0..string.len()).step_by(2).map(|i| u8::from_str_radix(string.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// The hex encoded SHA-256 of `data`. Tokens are stored by this hash, so a
+/// leaked table doesn't leak usable tokens.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    hex(digest(&SHA256, data).as_ref())
+}
@@ -0,0 +1,151 @@
+use serde_json::{json, Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::utils::{skip_line, skip_literal};
+
+/// Parses a JSON-like literal with bare keys, optional commas, line
+/// comments, `.variant` enum values and `$name` placeholders, which become
+/// `{ "$param": name }`.
+pub(crate) struct LiteralParser<'a> {
+    chars: &'a [char],
+    i: usize,
+    /// The placeholder names in order of appearance.
+    pub(crate) parameters: Vec<String>,
+}
+
+impl<'a> LiteralParser<'a> {
+
+    pub(crate) fn new(chars: &'a [char], start: usize) -> Self {
+        Self { chars, i: start, parameters: vec![] }
+    }
+
+    /// The index of the char after the parsed values.
+    pub(crate) fn position(&self) -> usize {
+        self.i
+    }
+
+    pub(crate) fn value(&mut self) -> Result<JsonValue> {
+        self.skip_whitespace();
+        let Some(c) = self.chars.get(self.i).copied() else { Err(Error::new("unexpected end of input"))? };
+        match c {
+            '{' => {
+                self.i += 1;
+                let mut map = Map::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.get(self.i) {
+                        Some('}') => {
+                            self.i += 1;
+                            return Ok(JsonValue::Object(map));
+                        }
+                        Some('"') => {
+                            let key = self.string()?;
+                            self.colon()?;
+                            map.insert(key, self.value()?);
+                        }
+                        Some(c) if c.is_alphabetic() || *c == '_' => {
+                            let key = self.identifier();
+                            self.colon()?;
+                            map.insert(key, self.value()?);
+                        }
+                        _ => Err(Error::new("expect a key"))?,
+                    }
+                }
+            }
+            '[' => {
+                self.i += 1;
+                let mut list = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.get(self.i) == Some(&']') {
+                        self.i += 1;
+                        return Ok(JsonValue::Array(list));
+                    }
+                    list.push(self.value()?);
+                }
+            }
+            '"' => Ok(JsonValue::String(self.string()?)),
+            '$' => {
+                self.i += 1;
+                let name = self.identifier();
+                if name.is_empty() {
+                    Err(Error::new("expect a parameter name after `$`"))?
+                }
+                if !self.parameters.contains(&name) {
+                    self.parameters.push(name.clone());
+                }
+                Ok(json!({ "$param": name }))
+            }
+            '.' => {
+                self.i += 1;
+                Ok(JsonValue::String(self.identifier()))
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = self.i;
+                self.i += 1;
+                while self.chars.get(self.i).map_or(false, |c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')) {
+                    self.i += 1;
+                }
+                let literal: String = self.chars[start..self.i].iter().collect();
+                serde_json::from_str(&literal).map_err(|_| Error::new(format!("invalid number `{}`", literal)))
+            }
+            _ => match self.identifier().as_str() {
+                "true" => Ok(JsonValue::Bool(true)),
+                "false" => Ok(JsonValue::Bool(false)),
+                "null" => Ok(JsonValue::Null),
+                other => Err(Error::new(format!("unexpected `{}`", if other.is_empty() { c.to_string() } else { other.to_owned() }))),
+            },
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let end = skip_literal(self.chars, self.i, '"');
+        if end >= self.chars.len() {
+            Err(Error::new("string is not closed"))?
+        }
+        let literal: String = self.chars[self.i..=end].iter().collect();
+        self.i = end + 1;
+        serde_json::from_str(&literal).map_err(|_| Error::new(format!("invalid string {}", literal)))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.i;
+        while self.chars.get(self.i).map_or(false, |c| c.is_alphanumeric() || *c == '_') {
+            self.i += 1;
+        }
+        self.chars[start..self.i].iter().collect()
+    }
+
+    fn colon(&mut self) -> Result<()> {
+        self.skip_whitespace();
+        if self.chars.get(self.i) != Some(&':') {
+            Err(Error::new("expect `:` after a key"))?
+        }
+        self.i += 1;
+        Ok(())
+    }
+
+    /// Skip whitespace, commas and line comments.
+    pub(crate) fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.get(self.i) {
+            if c.is_whitespace() || *c == ',' {
+                self.i += 1;
+            } else if *c == '/' && self.chars.get(self.i + 1) == Some(&'/') {
+                self.i = skip_line(self.chars, self.i);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Parse a whole literal.
+pub(crate) fn parse_literal(source: &str) -> Result<JsonValue> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut parser = LiteralParser::new(&chars, 0);
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position() < chars.len() {
+        Err(Error::new(format!("unexpected `{}`", chars[parser.position()..].iter().collect::<String>())))?
+    }
+    Ok(value)
+}
@@ -0,0 +1,88 @@
+pub(crate) mod db_functions;
+pub(crate) mod delimiters;
+pub(crate) mod environments;
+pub(crate) mod hex;
+pub(crate) mod literal;
+pub(crate) mod named_queries;
+pub(crate) mod plugins;
+pub(crate) mod sql;
+pub(crate) mod transitions;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Find all schema files under `dir`. Hidden directories, `node_modules` and
+/// `target` are skipped.
+pub(crate) fn find_schema_files(dir: &Path) -> Vec<PathBuf> {
+    let mut result = vec![];
+    find_schema_files_internal(dir, &mut result);
+    result.sort();
+    result
+}
+
+fn find_schema_files_internal(dir: &Path, result: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name == "node_modules" || name == "target" {
+            continue
+        }
+        if path.is_dir() {
+            find_schema_files_internal(&path, result);
+        } else if name.ends_with(".teo") {
+            result.push(path);
+        }
+    }
+}
+
+/// Skip a string or regular expression literal starting at `start` and return
+/// the index of its closing delimiter.
+pub(crate) fn skip_literal(chars: &[char], start: usize, delimiter: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue
+        }
+        if chars[i] == delimiter {
+            return i;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Find the bracket closing the one at `open`, skipping string literals and
+/// line comments. Returns the length of `chars` if it's not closed.
+pub(crate) fn matching_brace(chars: &[char], open: usize) -> usize {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => i = skip_literal(chars, i, '"'),
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                i = skip_line(chars, i);
+                continue
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Return the index of the end of the line `i` is on.
+pub(crate) fn skip_line(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Map, Value as JsonValue};
+use teo_result::{Error, Result};
+use crate::utils::{find_schema_files, matching_brace};
+use crate::utils::literal::LiteralParser;
+
+static QUERY_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*query[ \t]+([A-Za-z_]\w*)[ \t]+on[ \t]+([A-Za-z_][\w.]*)[ \t]*\{").unwrap());
+
+/// The arguments a named query may store.
+const ARGUMENTS: [&str; 7] = ["where", "orderBy", "include", "select", "distinct", "skip", "take"];
+
+/// A query declared in the schema with
+/// `query activeUsers on User { where: { role: $role }, orderBy: { createdAt: "desc" } }`.
+///
+/// It's served as the `activeUsers` action of `User`, which runs `findMany`
+/// with the stored arguments. `$name` placeholders are replaced with the
+/// `params` of the request.
+#[derive(Debug, Clone)]
+pub(crate) struct NamedQuery {
+    pub(crate) name: String,
+    pub(crate) model: Vec<String>,
+    /// The stored `findMany` arguments, placeholders are `{ "$param": name }`.
+    pub(crate) arguments: JsonValue,
+    pub(crate) parameters: Vec<String>,
+}
+
+impl NamedQuery {
+
+    /// The `findMany` arguments with the placeholders replaced by `params`.
+    pub(crate) fn substitute(&self, params: &JsonValue) -> Result<JsonValue> {
+        substitute(&self.arguments, params)
+    }
+}
+
+/// Take the `query` blocks out of the schema files under `dir`. Files which
+/// contain them are added to `overlays` with the blocks blanked out, so that
+/// the parser doesn't see them. Sources already in `overlays` are read from
+/// there.
+pub(crate) fn extract_named_queries(dir: &Path, overlays: &mut HashMap<String, String>) -> Result<Vec<NamedQuery>> {
+    let mut result = vec![];
+    for path in find_schema_files(dir) {
+        let key = path.to_string_lossy().to_string();
+        let source = match overlays.get(&key) {
+            Some(source) => source.clone(),
+            None => match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            },
+        };
+        if let Some((rewritten, queries)) = extract_from_source(&source)? {
+            overlays.insert(key, rewritten);
+            result.extend(queries);
+        }
+    }
+    Ok(result)
+}
+
+/// Take the `query` blocks out of a single source. Returns `None` if it
+/// doesn't contain any.
+pub(crate) fn extract_from_source(source: &str) -> Result<Option<(String, Vec<NamedQuery>)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut queries = vec![];
+    // (start, end) char ranges of the blocks
+    let mut blocks = vec![];
+    for captures in QUERY_BLOCK.captures_iter(source) {
+        let whole = captures.get(0).unwrap();
+        let start = source[..whole.start()].chars().count();
+        let open = source[..whole.end()].chars().count() - 1;
+        if blocks.last().map_or(false, |(_, end)| start < *end) {
+            continue
+        }
+        let name = captures[1].to_owned();
+        let close = matching_brace(&chars, open);
+        if close >= chars.len() {
+            Err(Error::new(format!("query `{}` is not closed", name)))?
+        }
+        let mut parser = LiteralParser::new(&chars[..close + 1], open);
+        let arguments = parser.value().map_err(|e| Error::new(format!("query `{}`: {}", name, e.message)))?;
+        if let Some(key) = arguments.as_object().and_then(|a| a.keys().find(|k| !ARGUMENTS.contains(&k.as_str()))) {
+            Err(Error::new(format!("query `{}`: unexpected argument `{}`", name, key)))?
+        }
+        queries.push(NamedQuery {
+            name,
+            model: captures[2].split('.').map(|s| s.to_owned()).collect(),
+            arguments,
+            parameters: parser.parameters,
+        });
+        blocks.push((start, close + 1));
+    }
+    if blocks.is_empty() {
+        return Ok(None);
+    }
+    let mut rewritten = String::new();
+    let mut last = 0;
+    for (start, end) in blocks {
+        rewritten.extend(&chars[last..start]);
+        // keep the line count so that diagnostics point at the right lines
+        rewritten.push_str(&"\n".repeat(chars[start..end].iter().filter(|c| **c == '\n').count()));
+        last = end;
+    }
+    rewritten.extend(&chars[last..]);
+    Ok(Some((rewritten, queries)))
+}
+
+fn substitute(value: &JsonValue, params: &JsonValue) -> Result<JsonValue> {
+    Ok(match value {
+        JsonValue::Object(map) => {
+            if let (1, Some(JsonValue::String(name))) = (map.len(), map.get("$param")) {
+                return params.get(name).cloned().ok_or_else(|| Error::invalid_request_message(format!("parameter `{}` is required", name)));
+            }
+            JsonValue::Object(map.iter().map(|(k, v)| Ok((k.clone(), substitute(v, params)?))).collect::<Result<Map<String, JsonValue>>>()?)
+        }
+        JsonValue::Array(list) => JsonValue::Array(list.iter().map(|v| substitute(v, params)).collect::<Result<Vec<JsonValue>>>()?),
+        value => value.clone(),
+    })
+}
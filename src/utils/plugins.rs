@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_result::{Error, Result};
+use crate::utils::db_functions::{blocks, HANDLER_GROUP_BLOCK, NAMESPACE_BLOCK};
+use crate::utils::find_schema_files;
+
+static PLUGIN_DECLARATION: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?m)^([ \t]*declare[ \t]+(handler|pipeline[ \t]+item)[ \t]+([A-Za-z_]\w*)\b[^\n]*?)[ \t]+from[ \t]+(wasm|js)[ \t]*\([ \t]*"([^"\n]*)"[ \t]*(?:,[ \t]*"([^"\n]*)"[ \t]*)?\)"#).unwrap());
+
+/// What a plugin declaration implements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PluginTarget {
+    Handler,
+    PipelineItem,
+}
+
+/// The runtime which runs a plugin module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PluginRuntime {
+    Wasm,
+    Js,
+}
+
+/// A handler or pipeline item declared in the schema with a module, e.g.
+/// `declare handler resize(ResizeInput): Image from wasm("./plugins/image.wasm", "resize")`
+/// or `declare pipeline item slugify: String -> String from js("./plugins/text.js")`.
+///
+/// The module path is relative to the schema file. The export, or function
+/// of a script, defaults to the declared name. Scripts are JavaScript,
+/// TypeScript sources must be compiled first.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PluginDeclaration {
+    pub(crate) namespace_path: Vec<String>,
+    pub(crate) target: PluginTarget,
+    pub(crate) name: String,
+    pub(crate) runtime: PluginRuntime,
+    pub(crate) path: PathBuf,
+    pub(crate) export: String,
+}
+
+/// Take the `from wasm(...)` and `from js(...)` clauses out of the handler and pipeline item
+/// declarations of the schema files under `dir`, like
+/// `extract_db_function_handlers` does with `from dbFunction(...)`. Sources
+/// already in `overlays` are read from there.
+pub(crate) fn extract_plugin_declarations(dir: &Path, overlays: &mut HashMap<String, String>) -> Result<Vec<PluginDeclaration>> {
+    let mut result = vec![];
+    for path in find_schema_files(dir) {
+        let key = path.to_string_lossy().to_string();
+        let source = match overlays.get(&key) {
+            Some(source) => source.clone(),
+            None => match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            },
+        };
+        if let Some((rewritten, declarations)) = extract_from_source(&source, path.parent().unwrap_or(dir))? {
+            overlays.insert(key, rewritten);
+            result.extend(declarations);
+        }
+    }
+    Ok(result)
+}
+
+/// Take the plugin clauses out of a single source, whose module paths are
+/// relative to `base`. Returns `None` if it doesn't contain any.
+pub(crate) fn extract_from_source(source: &str, base: &Path) -> Result<Option<(String, Vec<PluginDeclaration>)>> {
+    if !PLUGIN_DECLARATION.is_match(source) {
+        return Ok(None);
+    }
+    let chars: Vec<char> = source.chars().collect();
+    let namespaces = blocks(&NAMESPACE_BLOCK, source, &chars);
+    let groups = blocks(&HANDLER_GROUP_BLOCK, source, &chars);
+    let mut declarations = vec![];
+    for captures in PLUGIN_DECLARATION.captures_iter(source) {
+        let position = source[..captures.get(0).unwrap().start()].chars().count();
+        let name = captures[3].to_owned();
+        let target = if &captures[2] == "handler" { PluginTarget::Handler } else { PluginTarget::PipelineItem };
+        if target == PluginTarget::Handler && groups.iter().any(|(_, start, end)| (*start..*end).contains(&position)) {
+            Err(Error::new(format!("handler `{}`: `from {}` is not supported in handler groups", name, &captures[4])))?
+        }
+        if captures[5].is_empty() {
+            Err(Error::new(format!("`{}`: the module path is empty", name)))?
+        }
+        declarations.push(PluginDeclaration {
+            namespace_path: namespaces.iter().filter(|(_, start, end)| (*start..*end).contains(&position)).map(|(name, _, _)| name.clone()).collect(),
+            target,
+            runtime: if &captures[4] == "wasm" { PluginRuntime::Wasm } else { PluginRuntime::Js },
+            path: base.join(&captures[5]),
+            export: captures.get(6).map_or(name.clone(), |export| export.as_str().to_owned()),
+            name,
+        });
+    }
+    let rewritten = PLUGIN_DECLARATION.replace_all(source, "$1").to_string();
+    Ok(Some((rewritten, declarations)))
+}
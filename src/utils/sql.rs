@@ -0,0 +1,79 @@
+use teo_result::{Error, Result};
+use teo_parser::r#type::Type;
+use teo_runtime::database::database::Database;
+use teo_runtime::Value;
+use crate::utils::hex::hex;
+
+/// Render a value as a SQL literal of the database for raw statements.
+pub(crate) fn sql_literal(value: &Value, database: &Database) -> Result<String> {
+    Ok(match value {
+        Value::Null => "NULL".to_owned(),
+        Value::Bool(b) => if *b { "TRUE".to_owned() } else { "FALSE".to_owned() },
+        Value::Int(i) => i.to_string(),
+        Value::Int64(i) => i.to_string(),
+        Value::Float32(f) if f.is_finite() => f.to_string(),
+        Value::Float(f) if f.is_finite() => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::String(s) => quote(s, database)?,
+        Value::Date(d) => quote(&d.format("%Y-%m-%d").to_string(), database)?,
+        Value::DateTime(d) => quote(&d.to_rfc3339(), database)?,
+        value => quote(&serde_json::Value::try_from(value)?.to_string(), database)?,
+    })
+}
+
+/// Quote a string as a SQL string literal of the database.
+///
+/// Raw statements can't bind parameters through the connectors, so strings
+/// are escaped by the rules of the dialect. MySQL reads backslashes as
+/// escapes unless `NO_BACKSLASH_ESCAPES` is set, which isn't known here, so
+/// strings with backslashes never go into a MySQL literal. They are sent as
+/// hex, which has nothing to escape. PostgreSQL strings with backslashes are
+/// written as `E''` strings, which don't depend on
+/// `standard_conforming_strings`.
+pub(crate) fn quote(string: &str, database: &Database) -> Result<String> {
+    if string.contains('\0') {
+        Err(Error::new("SQL string literals cannot contain NUL characters"))?
+    }
+    let escaped = string.replace('\'', "''");
+    Ok(match database {
+        Database::MySQL if string.contains('\\') => format!("CONVERT(X'{}' USING utf8mb4)", hex(string.as_bytes())),
+        Database::PostgreSQL if string.contains('\\') => format!("E'{}'", escaped.replace('\\', "\\\\")),
+        _ => format!("'{}'", escaped),
+    })
+}
+
+/// Quote a table or column name as a SQL identifier of the database.
+pub(crate) fn identifier(name: &str, database: &Database) -> String {
+    if database.is_mysql() {
+        format!("`{}`", name.replace('`', "``"))
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// The column type the SQL connector creates for a scalar field type, for
+/// raw statements which add columns.
+pub(crate) fn column_type(r#type: &Type, database: &Database) -> Result<String> {
+    let r#type = match r#type {
+        Type::Optional(inner) => inner.as_ref(),
+        r#type => r#type,
+    };
+    Ok(match (r#type, database) {
+        (Type::Int, Database::MySQL) => "INT",
+        (Type::Int, _) => "INTEGER",
+        (Type::Int64, _) => "BIGINT",
+        (Type::Float32, Database::MySQL) => "FLOAT",
+        (Type::Float32, _) => "REAL",
+        (Type::Float, Database::MySQL) => "DOUBLE",
+        (Type::Float, _) => "DOUBLE PRECISION",
+        (Type::Decimal, _) => "DECIMAL(65, 30)",
+        (Type::String, Database::MySQL) => "VARCHAR(191)",
+        (Type::String, _) => "TEXT",
+        (Type::Bool, Database::MySQL) => "TINYINT(1)",
+        (Type::Bool, _) => "BOOLEAN",
+        (Type::Date, _) => "DATE",
+        (Type::DateTime, Database::MySQL) => "DATETIME(3)",
+        (Type::DateTime, _) => "TIMESTAMP(3)",
+        (r#type, _) => Err(Error::new(format!("no column type is known for {:?}", r#type)))?,
+    }.to_owned())
+}
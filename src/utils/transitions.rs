@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use teo_result::{Error, Result};
+use crate::utils::{find_schema_files, matching_brace};
+
+static TRANSITIONS_DECORATOR: Lazy<Regex> = Lazy::new(|| Regex::new(r"@transitions[ \t]*\(").unwrap());
+static TRANSITION: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\*|\.?[A-Za-z_]\w*)[ \t\r\n]*->[ \t\r\n]*(\.?[A-Za-z_]\w*)$").unwrap());
+
+/// Rewrite the arguments of `@transitions(draft -> published, ...)` in the
+/// schema files under `dir` into the list of strings the decorator is
+/// declared with, `@transitions(["draft -> published", ...])`, as the parser
+/// doesn't know `->` in arguments. Files which contain the decorator are
+/// added to `overlays` rewritten. Sources already in `overlays` are read from
+/// there. Line breaks are kept, so the lines of diagnostics don't move.
+pub(crate) fn rewrite_transitions(dir: &Path, overlays: &mut HashMap<String, String>) -> Result<()> {
+    for path in find_schema_files(dir) {
+        let key = path.to_string_lossy().to_string();
+        let source = match overlays.get(&key) {
+            Some(source) => source.clone(),
+            None => match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            },
+        };
+        if let Some(rewritten) = rewrite_source(&source).map_err(|e| Error::new(format!("{}: {}", path.display(), e.message)))? {
+            overlays.insert(key, rewritten);
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the `@transitions` arguments of a single source. Returns `None`
+/// if it doesn't contain any.
+pub(crate) fn rewrite_source(source: &str) -> Result<Option<String>> {
+    if !TRANSITIONS_DECORATOR.is_match(source) {
+        return Ok(None);
+    }
+    let chars: Vec<char> = source.chars().collect();
+    let mut result = String::new();
+    let mut copied = 0;
+    for found in TRANSITIONS_DECORATOR.find_iter(source) {
+        let open = source[..found.end()].chars().count() - 1;
+        if open < copied {
+            continue
+        }
+        let close = matching_brace(&chars, open);
+        let line = chars[..open].iter().filter(|c| **c == '\n').count() + 1;
+        if close == chars.len() {
+            Err(Error::new(format!("line {}: `@transitions` is not closed", line)))?
+        }
+        let arguments: String = chars[open + 1..close].iter().collect();
+        result.extend(&chars[copied..=open]);
+        result.push_str(&rewrite_arguments(&arguments).map_err(|e| Error::new(format!("line {}: {}", line, e.message)))?);
+        copied = close;
+    }
+    result.extend(&chars[copied..]);
+    Ok(Some(result))
+}
+
+/// `draft -> published, published -> archived` as
+/// `["draft -> published", "published -> archived"]`.
+fn rewrite_arguments(arguments: &str) -> Result<String> {
+    let mut items = vec![];
+    for item in arguments.split(',') {
+        let trimmed = item.trim();
+        if trimmed.is_empty() && !items.is_empty() {
+            // a trailing comma
+            items.push(item.to_owned());
+            continue
+        }
+        let captures = TRANSITION.captures(trimmed).ok_or_else(|| Error::new(format!("`@transitions`: invalid transition `{}`, expect `from -> to`", trimmed)))?;
+        let leading = &item[..item.len() - item.trim_start().len()];
+        let trailing = &item[item.trim_end().len()..];
+        items.push(format!("{}\"{} -> {}\"{}", leading, &captures[1], &captures[2], trailing));
+    }
+    Ok(format!("[{}]", items.join(",")))
+}
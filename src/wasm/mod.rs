@@ -0,0 +1,72 @@
+use std::path::Path;
+use serde_json::{Value as JsonValue};
+use teo_result::{Error, Result};
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Resource limits of a WASM plugin call.
+#[derive(Debug, Copy, Clone)]
+pub struct WasmLimits {
+    /// The fuel available to a call, roughly the number of instructions.
+    pub fuel: u64,
+    /// The maximum linear memory size in bytes.
+    pub memory: usize,
+}
+
+impl Default for WasmLimits {
+
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            memory: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// A compiled WASM plugin.
+///
+/// A plugin module must export `memory`, an `alloc(len: i32) -> i32` function
+/// and the called functions with the signature `(ptr: i32, len: i32) -> i64`.
+/// Input and output are UTF-8 JSON; the output location is packed as
+/// `(ptr << 32) | len`. Each call runs in a fresh instance, so plugins cannot
+/// keep state between calls.
+pub struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+    limits: WasmLimits,
+}
+
+impl WasmPlugin {
+
+    /// Compile the WASM module at `path`.
+    pub fn load(path: impl AsRef<Path>, limits: WasmLimits) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(wasm_error)?;
+        let module = Module::from_file(&engine, path.as_ref()).map_err(wasm_error)?;
+        Ok(Self { engine, module, limits })
+    }
+
+    /// Call an exported function with a JSON input.
+    pub fn call(&self, export: &str, input: &JsonValue) -> Result<JsonValue> {
+        let limits = StoreLimitsBuilder::new().memory_size(self.limits.memory).build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.limits.fuel).map_err(wasm_error)?;
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(wasm_error)?;
+        let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| Error::new("wasm plugin doesn't export memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc").map_err(wasm_error)?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&mut store, export).map_err(wasm_error)?;
+        let input_bytes = serde_json::to_vec(input).unwrap();
+        let input_ptr = alloc.call(&mut store, input_bytes.len() as i32).map_err(wasm_error)?;
+        memory.write(&mut store, input_ptr as usize, &input_bytes).map_err(wasm_error)?;
+        let packed = func.call(&mut store, (input_ptr, input_bytes.len() as i32)).map_err(wasm_error)? as u64;
+        let (output_ptr, output_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut output_bytes = vec![0; output_len];
+        memory.read(&store, output_ptr, &mut output_bytes).map_err(wasm_error)?;
+        serde_json::from_slice(&output_bytes).map_err(|e| Error::new(format!("wasm plugin returns invalid json: {}", e)))
+    }
+}
+
+fn wasm_error(err: impl std::fmt::Display) -> Error {
+    Error::new(format!("wasm plugin error: {}", err))
+}
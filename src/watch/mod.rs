@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::env::{args_os, current_dir, current_exe};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+use tokio::process::{Child, Command};
+use teo_parser::{parse as schema_parse};
+use teo_parser::diagnostics::printer::print_diagnostics;
+use teo_result::{Error, Result};
+use teo_runtime::utils::find_main_schema_file;
+use crate::cli::command::CLI;
+use crate::message::info_message;
+use crate::utils::find_schema_files;
+use crate::utils::delimiters::print_schema_errors;
+use crate::utils::environments::apply_environment_overlays;
+use crate::utils::db_functions::extract_db_function_handlers;
+use crate::utils::plugins::extract_plugin_declarations;
+use crate::utils::transitions::rewrite_transitions;
+use crate::utils::named_queries::extract_named_queries;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Set in the environment of the server started by `--watch`, to the id of
+/// the watching process. The server ignores `--watch`, and exits when the
+/// watching process is gone.
+const WATCH_PARENT_ENV: &str = "TEO_WATCH_PARENT";
+
+/// Run the server in a child process and restart it whenever a schema file
+/// changes. The schema is parsed before restarting; if it has errors, the
+/// diagnostics are printed and the running server is kept. The server is
+/// stopped with the watching process, on ctrl-c or when it's killed.
+pub(crate) async fn watch(cli: &CLI) -> Result<()> {
+    let current_dir = current_dir().map_err(|e| Error::new(format!("{}", e)))?;
+    let main_schema_file = find_main_schema_file(cli.main(), &current_dir)?;
+    let watch_dir = main_schema_file.parent().unwrap_or(current_dir.as_path()).to_owned();
+    let mut snapshot = schema_files_snapshot(&watch_dir);
+    let mut child = spawn_server()?;
+    info_message(format!("watching schema files in \"{}\"", watch_dir.display()));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                let _ = child.kill().await;
+                return Ok(());
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => (),
+        }
+        let new_snapshot = schema_files_snapshot(&watch_dir);
+        if new_snapshot == snapshot {
+            continue
+        }
+        snapshot = new_snapshot;
+        info_message("schema changed, reloading");
+        let mut overlays = apply_environment_overlays(&watch_dir, std::env::var("TEO_ENV").ok().as_deref());
+        if let Err(e) = extract_named_queries(&watch_dir, &mut overlays)
+            .and_then(|_| extract_db_function_handlers(&watch_dir, &mut overlays))
+            .and_then(|_| extract_plugin_declarations(&watch_dir, &mut overlays))
+            .and_then(|_| rewrite_transitions(&watch_dir, &mut overlays)) {
+            info_message(format!("{}, server is not restarted", e.message));
+            continue
+        }
+        let (_, diagnostics) = schema_parse(main_schema_file.as_path().to_str().unwrap(), None, if overlays.is_empty() { None } else { Some(overlays.clone()) });
+        if diagnostics.has_errors() {
+            print_schema_errors(&watch_dir, &main_schema_file, &overlays, &diagnostics);
+            info_message("schema has errors, server is not restarted");
+            continue
+        }
+        print_diagnostics(&diagnostics, true);
+        let _ = child.kill().await;
+        child = spawn_server()?;
+    }
+}
+
+/// Start the server with the command line this process was started with,
+/// so that it's started by the same launcher, e.g. `node app.js serve -w`
+/// or `python app.py serve -w`, and the app passes Teo the same argv.
+fn spawn_server() -> Result<Child> {
+    let exe = current_exe().map_err(|e| Error::new(format!("{}", e)))?;
+    Command::new(exe)
+        .args(args_os().skip(1))
+        .env(WATCH_PARENT_ENV, process::id().to_string())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::new(format!("cannot start server: {}", e)))
+}
+
+/// Whether this process is the server started by a watching process.
+pub(crate) fn is_watched() -> bool {
+    std::env::var_os(WATCH_PARENT_ENV).is_some()
+}
+
+/// Exit when the watching process is gone, so that the server isn't left
+/// running when the watching process is killed without stopping it.
+pub(crate) fn exit_with_watching_process() {
+    let Some(parent) = std::env::var(WATCH_PARENT_ENV).ok().and_then(|id| id.parse::<u32>().ok()) else { return };
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            if std::os::unix::process::parent_id() != parent {
+                process::exit(0);
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    let _ = parent;
+}
+
+fn schema_files_snapshot(dir: &Path) -> BTreeMap<PathBuf, SystemTime> {
+    find_schema_files(dir).into_iter().filter_map(|path| {
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        Some((path, modified))
+    }).collect()
+}
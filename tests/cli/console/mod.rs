@@ -0,0 +1,54 @@
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use crate::lib::{run_with_input, run_with_output};
+
+    /// Expressions are read from stdin line by line, and an expression
+    /// continues while its brackets are open.
+    #[test]
+    fn expressions_are_evaluated() {
+        let schema = Path::new(file!()).parent().unwrap().join("schema.teo");
+        let _ = fs::remove_file("test_console.sqlite");
+        let (migrated, output) = run_with_output(schema.clone(), "migrate");
+        assert!(migrated, "{}", output);
+        let input = r#"User.create({ create: { email: " Ada@Example.com ", name: "Ada" } })
+User.create({ create: { email: "grace@example.com" } })
+User.count()
+User.findMany({
+  where: { name: null }
+  orderBy: { id: "desc" }
+})
+User.update({ where: { email: "grace@example.com" }, update: { name: "Grace" } })
+User.email.onSet("  Linus@Example.com")
+User.frobnicate({})
+Nope.findMany()
+User.findMany({ where: { name: } })
+.models
+.exit
+User.count()
+"#;
+        let (succeeded, output) = run_with_input(schema.clone(), "console", input);
+        assert!(succeeded, "{}", output);
+        assert!(output.contains(r#""email": "ada@example.com""#), "{}", output);
+        assert!(output.contains("teo> 2\n"), "{}", output);
+        assert!(output.contains("...> "), "{}", output);
+        assert!(output.contains(r#"[
+  {
+    "email": "grace@example.com",
+    "id": 2,
+    "name": null
+  }
+]"#), "{}", output);
+        assert!(output.contains(r#""name": "Grace""#), "{}", output);
+        assert!(output.contains(r#""linus@example.com""#), "{}", output);
+        assert!(output.contains("error: unknown action `frobnicate`"), "{}", output);
+        assert!(output.contains("error: model `Nope` is not found"), "{}", output);
+        assert!(output.contains("error: unexpected `}`"), "{}", output);
+        assert!(output.contains("teo> User\n"), "{}", output);
+        // nothing is evaluated after `.exit`
+        assert_eq!(output.matches("teo> 2\n").count(), 1, "{}", output);
+        // the record of the pipeline isn't saved
+        let (_, output) = run_with_input(schema, "console", "User.count()\n");
+        assert!(output.contains("teo> 2\n"), "{}", output);
+    }
+}
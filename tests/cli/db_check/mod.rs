@@ -0,0 +1,63 @@
+mod test {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::lib::{run_with_output, ExecutionHandle, req};
+
+    static PORT: i32 = 4040;
+
+    /// `db check` reports orphaned references and duplicate unique values
+    /// written while the schema didn't declare them, and `--fix` fixes the
+    /// orphans by the `onDelete` rules of their relations.
+    #[test]
+    fn orphans_and_duplicates_are_reported_and_fixed() {
+        let dir = Path::new(file!()).parent().unwrap();
+        for args in ["migrate", "purge"] {
+            let (succeeded, output) = run_with_output(dir.join("before.teo"), args);
+            assert!(succeeded, "{}", output);
+        }
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("before.teo"), "serve --no-migration");
+        let author = req(PORT, "create", "Author", json!({ "create": { "name": "kept" } }))["data"]["id"].clone();
+        let post = req(PORT, "create", "Post", json!({ "create": { "title": "kept", "authorId": author } }))["data"]["id"].clone();
+        req(PORT, "create", "Post", json!({ "create": { "title": "orphan", "authorId": -1 } }));
+        req(PORT, "create", "Comment", json!({ "create": { "body": "kept", "postId": post } }));
+        req(PORT, "create", "Comment", json!({ "create": { "body": "orphan", "postId": -1 } }));
+        req(PORT, "create", "Comment", json!({ "create": { "body": "unlinked", "postId": null } }));
+        req(PORT, "create", "Review", json!({ "create": { "body": "orphan", "authorId": -2 } }));
+        for code in ["a", "a", "b"] {
+            req(PORT, "create", "Tag", json!({ "create": { "code": code } }));
+        }
+        handle.exit();
+        let (checked, output) = run_with_output(dir.join("after.teo"), "db check");
+        assert!(!checked, "{}", output);
+        for line in [
+            "Post.author: 1 records reference 1 missing `Author` records by authorId",
+            "fix: delete the records (onDelete: .cascade)",
+            "Comment.post: 1 records reference 1 missing `Post` records by postId",
+            "fix: set the references to null (onDelete: .nullify)",
+            "Review.author: 1 records reference 1 missing `Author` records by authorId",
+            "fix: none, the relation isn't .cascade or optional .nullify on delete, fix the records by hand",
+            "Tag: 2 records share 1 values of @@unique([code])",
+            "[\"a\"] (2 records)",
+            "4 integrity issues are found, fixable ones are fixed with `--fix`",
+        ] {
+            assert!(output.contains(line), "{}", output);
+        }
+        let (fixed, output) = run_with_output(dir.join("after.teo"), "db check --fix");
+        assert!(!fixed, "{}", output);
+        assert_eq!(output.matches("Fixed").count(), 2, "{}", output);
+        assert!(output.contains("2 integrity issues are found"), "{}", output);
+        // the fixed orphans are gone, the rest are left to be fixed by hand
+        let (checked, output) = run_with_output(dir.join("after.teo"), "db check");
+        assert!(!checked, "{}", output);
+        assert!(!output.contains("Post.author") && !output.contains("Comment.post"), "{}", output);
+        assert!(output.contains("Review.author") && output.contains("Tag: 2 records"), "{}", output);
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("after.teo"), "serve --no-migration");
+        let posts = req(PORT, "findMany", "Post", json!({}));
+        let comments = req(PORT, "findMany", "Comment", json!({ "orderBy": { "id": "asc" } }));
+        handle.exit();
+        assert_eq!(posts["data"].as_array().unwrap().len(), 1);
+        assert_eq!(comments["data"].as_array().unwrap().iter().map(|comment| comment["postId"].clone()).collect::<Vec<_>>(), vec![post, json!(null), json!(null)]);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod console;
+pub mod db_check;
+pub mod routes;
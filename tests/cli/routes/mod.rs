@@ -0,0 +1,43 @@
+mod test {
+    use std::path::Path;
+    use serde_json::{json, Value};
+    use crate::lib::run_with_output;
+
+    fn schema() -> std::path::PathBuf {
+        Path::new(file!()).parent().unwrap().join("schema.teo")
+    }
+
+    fn route<'a>(routes: &'a Value, path: &str) -> &'a Value {
+        routes.as_array().unwrap().iter().find(|route| route["path"] == path).unwrap_or_else(|| panic!("`{}` is not listed: {}", path, routes))
+    }
+
+    #[test]
+    fn routes_are_listed_as_json() {
+        let (listed, output) = run_with_output(schema(), "routes --json");
+        assert!(listed, "{}", output);
+        let routes: Value = serde_json::from_str(&output[output.find('[').unwrap()..]).unwrap();
+        assert_eq!(route(&routes, "/api/Memo/findMany"), &json!({ "method": "POST", "path": "/api/Memo/findMany", "model": "Memo", "action": "findMany", "permissions": ["findMany: everyone"] }));
+        assert_eq!(route(&routes, "/api/Memo/findUnique")["permissions"], json!(["findUnique: identity"]));
+        assert_eq!(route(&routes, "/api/Memo/upsert")["permissions"], json!(["upsert: nobody"]));
+        assert_eq!(route(&routes, "/api/Memo/create")["permissions"], json!([]));
+        assert_eq!(route(&routes, "/api/Memo/findByIds")["permissions"], json!(["findMany: everyone"]));
+        // decorator actions are listed with the models which have them
+        assert_eq!(route(&routes, "/api/Article/resolveSlug")["action"], "resolveSlug");
+        assert!(routes.as_array().unwrap().iter().all(|route| route["path"] != "/api/Memo/resolveSlug"), "{}", routes);
+        assert_eq!(route(&routes, "/api/ping"), &json!({ "method": "POST", "path": "/api/ping", "model": null, "action": "ping", "permissions": [] }));
+        assert_eq!(route(&routes, "/api/_batch")["method"], "POST");
+        let maintenance: Vec<&Value> = routes.as_array().unwrap().iter().filter(|route| route["path"] == "/api/_maintenance").map(|route| &route["method"]).collect();
+        assert_eq!(maintenance, vec!["GET", "POST"]);
+    }
+
+    #[test]
+    fn routes_are_listed_as_a_table() {
+        let (listed, output) = run_with_output(schema(), "routes");
+        assert!(listed, "{}", output);
+        let lines: Vec<&str> = output.lines().filter(|line| line.starts_with('|') || line.starts_with('+')).collect();
+        assert!(lines[1].starts_with("| Method | Path "), "{}", output);
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()), "{}", output);
+        assert!(lines.iter().any(|line| line.contains("| /api/Memo/delete ") && line.contains("| delete: role:admin ")), "{}", output);
+        assert!(lines.iter().any(|line| line.starts_with("| POST   | /api/ping ") && line.contains("| -  ")), "{}", output);
+    }
+}
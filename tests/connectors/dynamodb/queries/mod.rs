@@ -0,0 +1,124 @@
+//! Needs the Teo executable built with the `dynamodb` feature, DynamoDB
+//! Local on port 8000 and any AWS credentials in the environment.
+
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use serial_test::serial;
+    use std::sync::Mutex;
+    use serde_json::{json, Value};
+    use crate::lib::{ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+    use once_cell::sync::Lazy;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4028;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), "serve");
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    fn create_post(slug: &str, author_id: &str, position: i64) -> Value {
+        req(PORT, "create", "Post", json!({
+            "create": { "slug": slug, "authorId": author_id, "position": position, "title": slug },
+        }))
+    }
+
+    #[serial]
+    #[test]
+    fn find_unique_by_key() {
+        create_post("key-1", "key", 1);
+        let res = req(PORT, "findUnique", "Post", json!({ "where": { "slug": "key-1" } }));
+        assert_json!(res.get("data").unwrap(), matcher!({
+            "slug": "key-1",
+            "authorId": "key",
+            "position": 1,
+            "title": "key-1",
+            "version": 0,
+        }));
+    }
+
+    #[serial]
+    #[test]
+    fn find_many_on_index_with_cursor() {
+        for position in 1..=3 {
+            create_post(&format!("page-{}", position), "page", position);
+        }
+        let res = req(PORT, "findMany", "Post", json!({
+            "where": { "authorId": "page" },
+            "orderBy": { "position": "asc" },
+            "take": 2,
+            "select": { "slug": true },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "slug": "page-1" },
+            { "slug": "page-2" },
+        ]));
+        let res = req(PORT, "findMany", "Post", json!({
+            "where": { "authorId": "page" },
+            "orderBy": { "position": "asc" },
+            "cursor": { "slug": "page-2" },
+            "skip": 1,
+            "take": 2,
+            "select": { "slug": true },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "slug": "page-3" },
+        ]));
+    }
+
+    #[serial]
+    #[test]
+    fn query_without_key_is_rejected() {
+        let res = req(PORT, "findMany", "Post", json!({ "where": { "title": "page-1" } }));
+        assert!(res.get("error").is_some());
+    }
+
+    #[serial]
+    #[test]
+    fn update_is_conditional_on_version() {
+        create_post("version", "version", 1);
+        let res = req(PORT, "update", "Post", json!({ "where": { "slug": "version" }, "update": { "title": "first" } }));
+        assert_json!(res.get("data").unwrap(), matcher!({
+            "slug": "version",
+            "authorId": "version",
+            "position": 1,
+            "title": "first",
+            "version": 1,
+        }));
+        let res = req(PORT, "update", "Post", json!({ "where": { "slug": "version" }, "update": { "title": "stale", "version": 0 } }));
+        assert!(res.get("error").is_some());
+        let res = req(PORT, "findUnique", "Post", json!({ "where": { "slug": "version" }, "select": { "title": true } }));
+        assert_json!(res.get("data").unwrap(), matcher!({ "title": "first" }));
+    }
+
+    #[serial]
+    #[test]
+    fn duplicated_key_is_rejected() {
+        create_post("duplicated", "duplicated", 1);
+        let res = create_post("duplicated", "duplicated", 2);
+        assert!(res.get("error").is_some());
+    }
+
+    #[serial]
+    #[test]
+    fn transaction_writes_all_or_none() {
+        let res = req(PORT, "createMany", "Post", json!({
+            "create": [
+                { "slug": "rollback-1", "authorId": "rollback", "position": 1, "title": "1" },
+                { "slug": "rollback-1", "authorId": "rollback", "position": 2, "title": "2" },
+            ],
+        }));
+        assert!(res.get("error").is_some());
+        let res = req(PORT, "count", "Post", json!({ "where": { "authorId": "rollback" } }));
+        assert_json!(res, matcher!({ "data": 0 }));
+    }
+}
@@ -0,0 +1,184 @@
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use serial_test::serial;
+    use std::sync::Mutex;
+    use serde_json::{json, Value};
+    use crate::lib::{ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+    use once_cell::sync::Lazy;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4016;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), "serve");
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    fn create_author(name: &str, posts: Value) -> Value {
+        req(PORT, "create", "Author", json!({
+            "create": {
+                "name": name,
+                "posts": {
+                    "createMany": posts,
+                },
+            },
+        }))
+    }
+
+    #[serial]
+    #[test]
+    fn find_many_with_include() {
+        create_author("Ada", json!([
+            { "slug": "ada-1", "views": 3, "published": true },
+            { "slug": "ada-2", "views": 5, "published": false },
+        ]));
+        let res = req(PORT, "findMany", "Author", json!({
+            "where": { "name": "Ada" },
+            "include": {
+                "posts": {
+                    "where": { "views": { "gt": 1 } },
+                    "orderBy": { "views": "desc" },
+                },
+            },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([{
+            "id": ignore,
+            "name": "Ada",
+            "posts": [
+                { "id": ignore, "slug": "ada-2", "views": 5, "published": false, "authorId": ignore },
+                { "id": ignore, "slug": "ada-1", "views": 3, "published": true, "authorId": ignore },
+            ],
+        }]));
+    }
+
+    #[serial]
+    #[test]
+    fn filter_order_and_paginate() {
+        create_author("Page", json!([
+            { "slug": "page-1", "views": 1, "published": true },
+            { "slug": "page-2", "views": 2, "published": true },
+            { "slug": "page-3", "views": 3, "published": true },
+            { "slug": "page-4", "views": 4, "published": true },
+        ]));
+        let res = req(PORT, "findMany", "Post", json!({
+            "where": { "slug": { "startsWith": "page-" } },
+            "orderBy": { "slug": "desc" },
+            "skip": 1,
+            "take": 2,
+            "select": { "slug": true },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "slug": "page-3" },
+            { "slug": "page-2" },
+        ]));
+        let res = req(PORT, "findMany", "Post", json!({
+            "where": {
+                "OR": [{ "slug": "page-1" }, { "slug": "page-4" }],
+                "author": { "is": { "name": "Page" } },
+            },
+            "select": { "slug": true },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "slug": "page-1" },
+            { "slug": "page-4" },
+        ]));
+    }
+
+    #[serial]
+    #[test]
+    fn unique_index() {
+        create_author("Unique", json!([{ "slug": "unique", "views": 0, "published": true }]));
+        let res = req(PORT, "create", "Post", json!({
+            "create": {
+                "slug": "unique",
+                "views": 0,
+                "published": true,
+                "author": { "create": { "name": "Unique" } },
+            },
+        }));
+        assert!(res.get("error").is_some());
+    }
+
+    #[serial]
+    #[test]
+    fn rollback() {
+        let res = create_author("Rollback", json!([
+            { "slug": "rollback", "views": 0, "published": true },
+            { "slug": "rollback", "views": 0, "published": true },
+        ]));
+        assert!(res.get("error").is_some());
+        let res = req(PORT, "count", "Author", json!({ "where": { "name": "Rollback" } }));
+        assert_json!(res, matcher!({ "data": 0 }));
+    }
+
+    #[serial]
+    #[test]
+    fn aggregate_and_group_by() {
+        create_author("Stats", json!([
+            { "slug": "stats-1", "views": 2, "published": true },
+            { "slug": "stats-2", "views": 4, "published": true },
+            { "slug": "stats-3", "views": 9, "published": false },
+        ]));
+        let res = req(PORT, "aggregate", "Post", json!({
+            "where": { "slug": { "startsWith": "stats-" } },
+            "_count": { "_all": true },
+            "_sum": { "views": true },
+            "_max": { "views": true },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!({
+            "_count": { "_all": 3 },
+            "_sum": { "views": 15 },
+            "_max": { "views": 9 },
+        }));
+        let res = req(PORT, "groupBy", "Post", json!({
+            "by": ["published"],
+            "where": { "slug": { "startsWith": "stats-" } },
+            "_sum": { "views": true },
+            "orderBy": { "published": "asc" },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "published": false, "_sum": { "views": 9 } },
+            { "published": true, "_sum": { "views": 6 } },
+        ]));
+    }
+
+    #[serial]
+    #[test]
+    fn many_to_many() {
+        create_author("Tagger", json!([{ "slug": "tagged", "views": 0, "published": true }]));
+        req(PORT, "update", "Post", json!({
+            "where": { "slug": "tagged" },
+            "update": {
+                "tags": {
+                    "create": [{ "name": "rust" }, { "name": "teo" }],
+                },
+            },
+        }));
+        let res = req(PORT, "findUnique", "Post", json!({
+            "where": { "slug": "tagged" },
+            "include": { "tags": { "orderBy": { "name": "desc" } } },
+        }));
+        assert_json!(res.get("data").unwrap(), matcher!({
+            "id": ignore,
+            "slug": "tagged",
+            "views": 0,
+            "published": true,
+            "authorId": ignore,
+            "tags": [
+                { "id": ignore, "name": "teo" },
+                { "id": ignore, "name": "rust" },
+            ],
+        }));
+        let res = req(PORT, "count", "Tag", json!({ "where": { "posts": { "some": { "slug": "tagged" } } } }));
+        assert_json!(res, matcher!({ "data": 2 }));
+    }
+}
@@ -1,5 +1,9 @@
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+pub mod memory;
 pub mod mongodb;
 pub mod mysql;
 pub mod postgres;
 pub mod sqlite;
 pub mod sqlite_memory;
+pub mod unreachable;
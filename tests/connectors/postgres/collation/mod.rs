@@ -0,0 +1,59 @@
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use serial_test::serial;
+    use std::sync::Mutex;
+    use serde_json::json;
+    use crate::lib::{ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+    use once_cell::sync::Lazy;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4023;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), "serve");
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    #[serial]
+    #[test]
+    fn case_insensitive_equals() {
+        req(PORT, "create", "Account", json!({ "create": { "email": "Ada@Example.com", "handle": "ada" } }));
+        let res = req(PORT, "findFirst", "Account", json!({ "where": { "email": "ada@example.com" } }));
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "email": "Ada@Example.com",
+                "handle": "ada",
+            }
+        }))
+    }
+
+    #[serial]
+    #[test]
+    fn case_insensitive_unique() {
+        req(PORT, "create", "Account", json!({ "create": { "email": "grace@example.com", "handle": "grace" } }));
+        let res = req(PORT, "create", "Account", json!({ "create": { "email": "GRACE@example.com", "handle": "grace2" } }));
+        assert!(res.get("error").is_some());
+    }
+
+    #[serial]
+    #[test]
+    fn explicit_collation_orders_by_bytes() {
+        req(PORT, "create", "Account", json!({ "create": { "email": "b@example.com", "handle": "b" } }));
+        req(PORT, "create", "Account", json!({ "create": { "email": "upper-b@example.com", "handle": "B" } }));
+        let res = req(PORT, "findMany", "Account", json!({ "where": { "handle": { "in": ["b", "B"] } }, "orderBy": { "handle": "asc" }, "select": { "handle": true } }));
+        assert_json!(res.get("data").unwrap(), matcher!([
+            { "handle": "B" },
+            { "handle": "b" },
+        ]))
+    }
+}
@@ -1,4 +1,5 @@
 pub mod relations;
 pub mod types;
+pub mod collation;
 pub mod queries;
 pub mod mutations;
\ No newline at end of file
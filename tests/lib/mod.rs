@@ -5,6 +5,7 @@ use std::process::{Child, Command, Stdio};
 use std::{env, thread};
 use std::borrow::Borrow;
 use std::collections::HashSet;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use key_path::{KeyPath, path};
 use serde_json::{Map, Number, Value};
@@ -47,11 +48,15 @@ impl ExecutionHandle {
     }
 
     pub fn execute(&mut self, file: &str, args: &str) {
+        self.execute_schema(schema_from_file(file), args)
+    }
+
+    pub fn execute_schema(&mut self, schema: PathBuf, args: &str) {
         env::set_var("TEO_ENV", "test");
         self.child = Some(Command::new(teo_exe_path())
             .arg("-s")
-            .arg(schema_from_file(file))
-            .arg(args)
+            .arg(schema)
+            .args(args.split_whitespace())
             .stdout(Stdio::null()).spawn().unwrap());
         thread::sleep(std::time::Duration::from_secs(3));
     }
@@ -65,6 +70,46 @@ impl ExecutionHandle {
 
 unsafe impl Sync for ExecutionHandle { }
 
+/// Run a command with a schema to the end, returns whether it succeeds.
+pub fn run(schema: PathBuf, args: &str) -> bool {
+    env::set_var("TEO_ENV", "test");
+    Command::new(teo_exe_path())
+        .arg("-s")
+        .arg(schema)
+        .args(args.split_whitespace())
+        .stdout(Stdio::null()).status().unwrap().success()
+}
+
+/// Run a command with a schema to the end, returns whether it succeeds and
+/// what it prints to stdout and stderr.
+pub fn run_with_output(schema: PathBuf, args: &str) -> (bool, String) {
+    env::set_var("TEO_ENV", "test");
+    let output = Command::new(teo_exe_path())
+        .arg("-s")
+        .arg(schema)
+        .args(args.split_whitespace())
+        .output().unwrap();
+    (output.status.success(), format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Run a command with a schema to the end with `input` on stdin, returns
+/// whether it succeeds and what it prints to stdout and stderr.
+pub fn run_with_input(schema: PathBuf, args: &str, input: &str) -> (bool, String) {
+    env::set_var("TEO_ENV", "test");
+    let mut child = Command::new(teo_exe_path())
+        .arg("-s")
+        .arg(schema)
+        .args(args.split_whitespace())
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.status.success(), format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
 pub fn req<J: Borrow<Value>>(port: i32, action: &str, model: &str, data: J) -> Value {
     let url = format!("http://127.0.0.1:{}/{}/{}", port, model, action);
     let client = reqwest::blocking::Client::new();
@@ -72,6 +117,13 @@ pub fn req<J: Borrow<Value>>(port: i32, action: &str, model: &str, data: J) -> V
     res.json().unwrap()
 }
 
+pub fn post<J: Borrow<Value>>(port: i32, path: &str, data: J) -> Value {
+    let url = format!("http://127.0.0.1:{}{}", port, path);
+    let client = reqwest::blocking::Client::new();
+    let res = client.post(url).json(data.borrow()).send().unwrap();
+    res.json().unwrap()
+}
+
 pub fn purge_and_seed(port: i32) {
     println!("purge_and_seed start");
     let url = format!("http://127.0.0.1:{}/danger/purge_seed", port);
@@ -0,0 +1,38 @@
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use crate::lib::run_with_output;
+
+    /// Pending schema changes are compared with the schema recorded by the
+    /// last migration, and only breaking ones fail the check.
+    #[test]
+    fn breaking_changes_fail_the_check() {
+        let dir = Path::new(file!()).parent().unwrap();
+        let _ = fs::remove_file("test_migrate_check.sqlite");
+        let (checked, output) = run_with_output(dir.join("before.teo"), "migrate check");
+        assert!(checked, "{}", output);
+        assert!(output.contains("main: no migration is recorded, run `teo migrate` first"), "{}", output);
+        let (migrated, output) = run_with_output(dir.join("before.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        let (checked, output) = run_with_output(dir.join("before.teo"), "migrate check");
+        assert!(checked, "{}", output);
+        assert!(output.contains("main: the schema is up to date"), "{}", output);
+        let (checked, output) = run_with_output(dir.join("compatible.teo"), "migrate check");
+        assert!(checked, "{}", output);
+        for change in ["field `Order.status` is added", "field `Order.memo` is added", "model `Refund` is added"] {
+            assert!(output.contains(&format!("main: compatible: {}", change)), "{}", output);
+        }
+        assert!(!output.contains("breaking:"), "{}", output);
+        let (checked, output) = run_with_output(dir.join("breaking.teo"), "migrate check");
+        assert!(!checked, "{}", output);
+        for change in ["field `Order.note` is removed", "type of `Order.total` changes from", "required field `Order.code` without a default is added"] {
+            assert!(output.contains(&format!("main: breaking:   {}", change)), "{}", output);
+        }
+        assert!(output.contains("main: compatible: field `Order.number` becomes optional"), "{}", output);
+        assert!(output.contains("3 breaking schema changes are pending"), "{}", output);
+        // checking doesn't migrate
+        let (checked, output) = run_with_output(dir.join("before.teo"), "migrate check");
+        assert!(checked, "{}", output);
+        assert!(output.contains("main: the schema is up to date"), "{}", output);
+    }
+}
@@ -0,0 +1,45 @@
+mod test {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::lib::{run_with_output, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4032;
+
+    /// The trigram index is created once for `@fuzzyIndex`, and dropped
+    /// when the decorator is removed.
+    #[test]
+    fn fuzzy_index_is_synced() {
+        let dir = Path::new(file!()).parent().unwrap();
+        // the index of an earlier run is dropped
+        assert!(run_with_output(dir.join("before.teo"), "migrate").0);
+        let (migrated, output) = run_with_output(dir.join("after.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        assert!(output.contains("created trigram index Product_name_trgm_idx"), "{}", output);
+        let (migrated, output) = run_with_output(dir.join("after.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        assert!(!output.contains("trigram index"), "{}", output);
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("after.teo"), "serve");
+        req(PORT, "deleteMany", "Product", json!({}));
+        for name in ["keyboard", "keyboards", "mouse"] {
+            req(PORT, "create", "Product", json!({ "create": { "name": name } }));
+        }
+        let products = req(PORT, "findMany", "Product", json!({
+            "where": { "name": { "similar": { "value": "keybord", "threshold": 0.7 } } },
+            "orderBy": { "name": "asc" },
+            "select": { "name": true },
+        }));
+        handle.exit();
+        assert_json!(products, matcher!({
+            "data": [
+                { "name": "keyboard" },
+                { "name": "keyboards" },
+            ],
+            "meta": { "count": 2 },
+        }));
+        let (migrated, output) = run_with_output(dir.join("before.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        assert!(output.contains("dropped trigram index Product_name_trgm_idx"), "{}", output);
+    }
+}
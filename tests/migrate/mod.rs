@@ -0,0 +1,6 @@
+pub mod rebuild;
+pub mod online;
+pub mod fuzzy;
+pub mod rename;
+pub mod check;
+pub mod verify;
@@ -0,0 +1,46 @@
+mod test {
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::lib::{run, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4029;
+
+    /// The rows are copied in batches to the new column, which replaces the
+    /// old one. Then the progress is gone, so the next migration doesn't
+    /// alter the column again.
+    #[test]
+    fn online_alteration_copies_every_row() {
+        let dir = Path::new(file!()).parent().unwrap();
+        // the rows of an earlier run don't fit the old type
+        let _ = run(dir.join("after.teo"), "purge");
+        assert!(run(dir.join("before.teo"), "migrate"));
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("before.teo"), "serve");
+        for views in 1..=5 {
+            let res = req(PORT, "create", "Post", json!({ "create": { "title": format!("post {}", views), "views": views } }));
+            assert!(res.get("data").is_some());
+        }
+        handle.exit();
+        thread::sleep(Duration::from_secs(1));
+        assert!(run(dir.join("after.teo"), "migrate"));
+        assert!(run(dir.join("after.teo"), "migrate"));
+        handle.execute_schema(dir.join("after.teo"), "serve");
+        let res = req(PORT, "create", "Post", json!({ "create": { "title": "big", "views": 5000000000i64 } }));
+        let posts = req(PORT, "findMany", "Post", json!({ "orderBy": { "id": "asc" }, "select": { "views": true } }));
+        handle.exit();
+        assert!(res.get("data").is_some());
+        assert_json!(posts, matcher!({
+            "data": [
+                { "views": 1 },
+                { "views": 2 },
+                { "views": 3 },
+                { "views": 4 },
+                { "views": 5 },
+                { "views": 5000000000i64 },
+            ]
+        }));
+    }
+}
@@ -0,0 +1,38 @@
+mod test {
+    use std::path::Path;
+    use std::{fs, thread};
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::lib::{run, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4017;
+
+    /// Rebuilding a table drops it, which must not delete the records of
+    /// tables referencing it with cascading foreign keys.
+    #[test]
+    fn rebuild_keeps_cascading_children() {
+        let dir = Path::new(file!()).parent().unwrap();
+        let _ = fs::remove_file("test_migrate_rebuild.sqlite");
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("before.teo"), "serve");
+        let res = req(PORT, "create", "Author", json!({
+            "create": {
+                "name": "Ada",
+                "posts": {
+                    "createMany": [{ "title": "first" }, { "title": "second" }],
+                },
+            },
+        }));
+        handle.exit();
+        assert!(res.get("data").is_some());
+        thread::sleep(Duration::from_secs(1));
+        assert!(run(dir.join("after.teo"), "migrate"));
+        handle.execute_schema(dir.join("after.teo"), "serve");
+        let authors = req(PORT, "count", "Author", json!({}));
+        let posts = req(PORT, "count", "Post", json!({}));
+        handle.exit();
+        assert_json!(authors, matcher!({ "data": 1 }));
+        assert_json!(posts, matcher!({ "data": 2 }));
+    }
+}
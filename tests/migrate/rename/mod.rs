@@ -0,0 +1,47 @@
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::lib::{run, run_with_output, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4034;
+
+    /// Tables and columns whose names change are renamed, so their records
+    /// are kept. A dry run only prints the statements.
+    #[test]
+    fn renamed_tables_and_columns_keep_records() {
+        let dir = Path::new(file!()).parent().unwrap();
+        let _ = fs::remove_file("test_migrate_rename.sqlite");
+        assert!(run(dir.join("before.teo"), "migrate"));
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("before.teo"), "serve");
+        for title in ["first", "second"] {
+            let res = req(PORT, "create", "Article", json!({ "create": { "title": title } }));
+            assert!(res.get("data").is_some());
+        }
+        handle.exit();
+        thread::sleep(Duration::from_secs(1));
+        let statements = [r#"ALTER TABLE "Article" RENAME TO "articles""#, r#"ALTER TABLE "articles" RENAME COLUMN "title" TO "headline""#];
+        let (migrated, output) = run_with_output(dir.join("after.teo"), "migrate --dry");
+        assert!(migrated, "{}", output);
+        assert!(statements.iter().all(|statement| output.contains(statement)), "{}", output);
+        let (migrated, output) = run_with_output(dir.join("after.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        assert!(statements.iter().all(|statement| output.contains(statement)), "{}", output);
+        let (migrated, output) = run_with_output(dir.join("after.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        assert!(!output.contains("RENAME"), "{}", output);
+        handle.execute_schema(dir.join("after.teo"), "serve");
+        let articles = req(PORT, "findMany", "Article", json!({ "orderBy": { "id": "asc" }, "select": { "title": true } }));
+        handle.exit();
+        assert_json!(articles, matcher!({
+            "data": [
+                { "title": "first" },
+                { "title": "second" },
+            ]
+        }));
+    }
+}
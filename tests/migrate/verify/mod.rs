@@ -0,0 +1,36 @@
+mod test {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::lib::{run_with_output, ExecutionHandle, req};
+
+    static PORT: i32 = 4039;
+
+    /// `serve --verify-schema` compares the live database with the schema
+    /// and fails with every difference before the server starts.
+    #[test]
+    fn differences_stop_the_server() {
+        let dir = Path::new(file!()).parent().unwrap();
+        let (migrated, output) = run_with_output(dir.join("before.teo"), "migrate");
+        assert!(migrated, "{}", output);
+        let (served, output) = run_with_output(dir.join("after.teo"), "serve --no-migration --verify-schema");
+        assert!(!served, "{}", output);
+        assert!(output.contains("the databases don't match the schema, run `teo migrate` or fix the schema"), "{}", output);
+        for difference in [
+            "main: column `number` of `Order.number` is NOT NULL, but the field is optional",
+            "main: column `note` of `Order.note` is nullable, but the field is required",
+            "main: column `total` of `Order.total` is integer, expect text",
+            "main: column `memo` of `Order.memo` is missing",
+            "main: index on (number, total) of `Order` is missing",
+            "main: table `Refund` of `Refund` is missing",
+        ] {
+            assert!(output.contains(difference), "{}", output);
+        }
+        assert!(!output.contains("`Order.id`"), "{}", output);
+        // a matching database is served
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(dir.join("before.teo"), "serve --no-migration --verify-schema");
+        let orders = req(PORT, "findMany", "Order", json!({}));
+        handle.exit();
+        assert_eq!(orders["data"], json!([]));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod lib;
+pub mod cli;
 pub mod connectors;
 pub mod core;
+pub mod migrate;
 pub mod server;
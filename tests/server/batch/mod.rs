@@ -0,0 +1,64 @@
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use std::sync::Mutex;
+    use serde_json::{json};
+    use crate::lib::{ExecutionHandle, post, req};
+
+    use crate::{assert_json, matcher};
+    use once_cell::sync::Lazy;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4022;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), "serve");
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    #[test]
+    fn batch() {
+        let res = post(PORT, "/_batch", json!({
+            "actions": [
+                { "model": "Support", "action": "create", "body": { "create": { "string": "a" } } },
+                { "model": "Support", "action": "create", "body": { "create": { "string": 5 } } },
+            ]
+        }));
+        assert_json!(res, matcher!({
+            "error": ignore
+        }));
+        let res = req(PORT, "count", "Support", json!({}));
+        assert_json!(res, matcher!({
+            "data": 0
+        }));
+        let res = post(PORT, "/_batch", json!({
+            "actions": [
+                { "model": "Support", "action": "create", "body": { "create": { "string": "a" } } },
+                { "model": "Support", "action": "create", "body": { "create": { "string": "b" } } },
+                { "model": "Support", "action": "count", "body": {} },
+            ]
+        }));
+        assert_json!(res, matcher!({
+            "data": [
+                { "data": { "id": ignore, "string": "a" } },
+                { "data": { "id": ignore, "string": "b" } },
+                { "data": 2 },
+            ]
+        }));
+        let res = post(PORT, "/_batch", json!({
+            "actions": [
+                { "model": "Support", "action": "create", "body": { "create": { "string": "parent" } } },
+                { "model": "Support", "action": "create", "body": { "create": { "string": "child", "parentId": { "$ref": "0.data.id" } } } },
+            ]
+        }));
+        let parent_id = res["data"][0]["data"]["id"].clone();
+        assert_eq!(res["data"][1]["data"]["parentId"], parent_id);
+    }
+}
@@ -0,0 +1,49 @@
+mod test {
+    use std::fs;
+    use std::path::Path;
+    use serde_json::{json, Value};
+    use crate::lib::{run, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4033;
+
+    fn records(model: &str, select: Value) -> Vec<Value> {
+        req(PORT, "findMany", model, json!({ "orderBy": { "id": "asc" }, "select": select }))["data"].as_array().unwrap().clone()
+    }
+
+    /// Random records follow the field types, validators and relations, and
+    /// the same seed inserts the same records.
+    #[test]
+    fn fake_records() {
+        let schema = Path::new(file!()).parent().unwrap().join("schema.teo");
+        let _ = fs::remove_file("test_server_fake.sqlite");
+        assert!(run(schema.clone(), "migrate"));
+        assert!(run(schema.clone(), "seed --fake 3 --count Post=5 --fake-seed 7"));
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(schema.clone(), "serve");
+        let authors = records("Author", json!({ "id": true, "name": true, "contact": true, "role": true }));
+        let posts = records("Post", json!({ "title": true, "embedding": true, "authorId": true }));
+        handle.exit();
+        assert_eq!(authors.len(), 3);
+        assert_eq!(posts.len(), 5);
+        for author in &authors {
+            assert!(author["contact"].as_str().unwrap().contains('@'), "{}", author);
+            assert!(["ADMIN", "MEMBER"].contains(&author["role"].as_str().unwrap()), "{}", author);
+        }
+        for post in &posts {
+            let length = post["title"].as_str().unwrap().chars().count();
+            assert!((20..=30).contains(&length), "{}", post);
+            assert_eq!(post["embedding"].as_array().unwrap().len(), 4, "{}", post);
+            assert!(authors.iter().any(|author| author["id"] == post["authorId"]), "{}", post);
+        }
+        assert!(run(schema.clone(), "purge"));
+        assert!(run(schema.clone(), "seed --fake 3 --fake-seed 7"));
+        handle.execute_schema(schema.clone(), "serve");
+        let reseeded = records("Author", json!({ "name": true, "contact": true, "role": true }));
+        let count = req(PORT, "count", "Post", json!({}));
+        handle.exit();
+        let names = |records: &Vec<Value>| records.iter().map(|r| (r["name"].clone(), r["contact"].clone(), r["role"].clone())).collect::<Vec<_>>();
+        assert_eq!(names(&reseeded), names(&authors));
+        assert_json!(count, matcher!({ "data": 3 }));
+    }
+}
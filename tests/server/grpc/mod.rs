@@ -0,0 +1,137 @@
+//! Needs the Teo executable built with the `grpc` feature.
+
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use serial_test::serial;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+    use once_cell::sync::Lazy;
+    use prost_types::value::Kind;
+    use serde_json::{json, Value as JsonValue};
+    use tonic::codec::ProstCodec;
+    use tonic::codegen::http::uri::PathAndQuery;
+    use tonic::transport::Channel;
+    use tonic::{Code, Status};
+    use crate::lib::ExecutionHandle;
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static GRPC_PORT: u16 = 4025;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), &format!("serve --grpc {}", GRPC_PORT));
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Note {
+        #[prost(int32, tag = "1")]
+        id: i32,
+        #[prost(string, tag = "2")]
+        title: String,
+        #[prost(int32, optional, tag = "3")]
+        views: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct NoteResponse {
+        #[prost(message, optional, tag = "1")]
+        data: Option<Note>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Meta {
+        #[prost(int64, optional, tag = "1")]
+        count: Option<i64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct NoteListResponse {
+        #[prost(message, repeated, tag = "1")]
+        data: Vec<Note>,
+        #[prost(message, optional, tag = "2")]
+        meta: Option<Meta>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct CountResponse {
+        #[prost(int64, tag = "1")]
+        data: i64,
+    }
+
+    fn value(json: &JsonValue) -> prost_types::Value {
+        let kind = match json {
+            JsonValue::Null => Kind::NullValue(0),
+            JsonValue::Bool(b) => Kind::BoolValue(*b),
+            JsonValue::Number(n) => Kind::NumberValue(n.as_f64().unwrap()),
+            JsonValue::String(s) => Kind::StringValue(s.clone()),
+            JsonValue::Array(items) => Kind::ListValue(prost_types::ListValue { values: items.iter().map(value).collect() }),
+            JsonValue::Object(_) => Kind::StructValue(structure(json)),
+        };
+        prost_types::Value { kind: Some(kind) }
+    }
+
+    fn structure(json: &JsonValue) -> prost_types::Struct {
+        prost_types::Struct { fields: json.as_object().unwrap().iter().map(|(k, v)| (k.clone(), value(v))).collect() }
+    }
+
+    fn call<R>(method: &str, input: JsonValue) -> Result<R, Status> where R: prost::Message + Default + Send + 'static {
+        tokio::runtime::Runtime::new().unwrap().block_on(async move {
+            let channel = Channel::from_shared(format!("http://127.0.0.1:{}", GRPC_PORT)).unwrap().connect().await.unwrap();
+            let mut grpc = tonic::client::Grpc::new(channel);
+            grpc.ready().await.unwrap();
+            let path = PathAndQuery::from_str(&format!("/teo.NoteService/{}", method)).unwrap();
+            grpc.unary(tonic::Request::new(structure(&input)), path, ProstCodec::<prost_types::Struct, R>::default()).await.map(|response| response.into_inner())
+        })
+    }
+
+    #[serial]
+    #[test]
+    fn create_and_find() {
+        let created: NoteResponse = call("Create", json!({ "create": { "title": "gRPC", "views": 3 } })).unwrap();
+        let created = created.data.unwrap();
+        assert_eq!(created.title, "gRPC");
+        assert_eq!(created.views, Some(3));
+        let found: NoteListResponse = call("FindMany", json!({ "where": { "id": created.id } })).unwrap();
+        assert_eq!(found.data, vec![created]);
+        assert_eq!(found.meta.unwrap().count, Some(1));
+    }
+
+    #[serial]
+    #[test]
+    fn count() {
+        let before: CountResponse = call("Count", json!({})).unwrap();
+        let _: NoteResponse = call("Create", json!({ "create": { "title": "counted" } })).unwrap();
+        let after: CountResponse = call("Count", json!({})).unwrap();
+        assert_eq!(after.data, before.data + 1);
+    }
+
+    #[serial]
+    #[test]
+    fn not_found() {
+        let status = call::<NoteResponse>("FindUnique", json!({ "where": { "id": 10000 } })).unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+
+    #[serial]
+    #[test]
+    fn guarded_action() {
+        let created: NoteResponse = call("Create", json!({ "create": { "title": "kept" } })).unwrap();
+        let status = call::<NoteResponse>("Delete", json!({ "where": { "id": created.data.unwrap().id } })).unwrap_err();
+        assert!(matches!(status.code(), Code::PermissionDenied | Code::Unauthenticated));
+    }
+
+    #[serial]
+    #[test]
+    fn unknown_method() {
+        let status = call::<NoteResponse>("Rename", json!({})).unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+    }
+}
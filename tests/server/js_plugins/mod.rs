@@ -0,0 +1,50 @@
+//! Needs the Teo executable built with the `js` feature.
+
+use test_helpers::*;
+
+#[before_all]
+#[after_all]
+mod test {
+    use serial_test::serial;
+    use std::sync::Mutex;
+    use once_cell::sync::Lazy;
+    use serde_json::json;
+    use crate::lib::{ExecutionHandle, post, req};
+    use crate::{assert_json, matcher};
+
+    static HANDLE: Lazy<Mutex<ExecutionHandle>> = Lazy::new(|| {
+        Mutex::new(ExecutionHandle::new())
+    });
+    static PORT: i32 = 4027;
+
+    fn before_all() {
+        HANDLE.lock().unwrap().execute(file!(), "serve");
+    }
+
+    fn after_all() {
+        HANDLE.lock().unwrap().exit();
+    }
+
+    #[test]
+    #[serial]
+    fn pipeline_item_from_script() {
+        let res = req(PORT, "create", "Tag", json!({ "create": { "name": "news" } }));
+        assert_json!(res, matcher!({
+            "data": {
+                "id": ignore,
+                "name": "NEWS!",
+            }
+        }));
+    }
+
+    #[test]
+    #[serial]
+    fn handler_from_script() {
+        let res = post(PORT, "/greet", json!({ "name": "teo" }));
+        assert_json!(res, matcher!({
+            "data": {
+                "greeting": "hello, teo",
+            }
+        }));
+    }
+}
@@ -0,0 +1,43 @@
+mod test {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::lib::{run, run_with_output, ExecutionHandle, req};
+    use crate::{assert_json, matcher};
+
+    static PORT: i32 = 4035;
+
+    fn total() -> i64 {
+        req(PORT, "findMany", "SalesTotal", json!({}))["data"][0]["total"].as_i64().unwrap()
+    }
+
+    /// A materialized view keeps its rows until it's refreshed, by the
+    /// `refresh` action or `teo refresh`.
+    #[test]
+    fn materialized_views_are_refreshed() {
+        let schema = Path::new(file!()).parent().unwrap().join("schema.teo");
+        assert!(run(schema.clone(), "migrate"));
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(schema.clone(), "serve");
+        req(PORT, "deleteMany", "Sale", json!({}));
+        req(PORT, "refresh", "SalesTotal", json!({}));
+        assert_eq!(total(), 0);
+        for amount in [3, 4] {
+            req(PORT, "create", "Sale", json!({ "create": { "amount": amount } }));
+        }
+        assert_eq!(total(), 0);
+        assert_json!(req(PORT, "refresh", "SalesTotal", json!({})), matcher!({ "data": null }));
+        assert_eq!(total(), 7);
+        req(PORT, "create", "Sale", json!({ "create": { "amount": 5 } }));
+        let (refreshed, output) = run_with_output(schema.clone(), "refresh SalesTotal");
+        assert!(refreshed, "{}", output);
+        assert!(output.contains("refreshed `SalesTotal`"), "{}", output);
+        let total_after_cli = total();
+        let rejected = req(PORT, "create", "SalesTotal", json!({ "create": { "id": 2, "total": 1 } }));
+        handle.exit();
+        assert_eq!(total_after_cli, 12);
+        assert_eq!(rejected["error"]["message"], "`SalesTotal` is a view, writes are not allowed");
+        let (refreshed, output) = run_with_output(schema, "refresh Sale");
+        assert!(!refreshed);
+        assert!(output.contains("`Sale` is not a materialized view"), "{}", output);
+    }
+}
@@ -1 +1,12 @@
 pub mod actions;
+pub mod batch;
+pub mod fake;
+pub mod materialized;
+pub mod test_app;
+pub mod watch;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "wasm")]
+pub mod plugins;
+#[cfg(feature = "js")]
+pub mod js_plugins;
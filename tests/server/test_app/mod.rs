@@ -0,0 +1,58 @@
+mod test {
+    use std::panic::AssertUnwindSafe;
+    use std::path::Path;
+    use futures_util::FutureExt;
+    use serde_json::json;
+    use teo::test::TestApp;
+    use crate::{assert_json, matcher};
+
+    #[tokio::test]
+    async fn create_then_reset() {
+        let schema = Path::new(file!()).parent().unwrap().join("schema.teo");
+        let app = TestApp::new(schema.to_str().unwrap()).await.unwrap();
+        app.run(|| async {
+            let res = app.req("Support", "create", json!({
+                "create": {
+                    "string": "tiunglong",
+                },
+            })).await;
+            assert_json!(res, matcher!({
+                "data": {
+                    "id": ignore,
+                    "string": "tiunglong",
+                }
+            }));
+        }).await.unwrap();
+        let res = app.req("Support", "count", json!({})).await;
+        assert_json!(res, matcher!({
+            "data": 0
+        }));
+        let panicked = AssertUnwindSafe(app.run(|| async {
+            app.req("Support", "create", json!({
+                "create": {
+                    "string": "panicked",
+                },
+            })).await;
+            panic!("the test body fails");
+        })).catch_unwind().await;
+        assert!(panicked.is_err());
+        let res = app.req("Support", "count", json!({})).await;
+        assert_json!(res, matcher!({
+            "data": 0
+        }));
+        let create_and_count = || async {
+            app.req("Support", "create", json!({
+                "create": {
+                    "string": "concurrent",
+                },
+            })).await;
+            app.req("Support", "count", json!({})).await
+        };
+        let (first, second) = futures::join!(app.run(create_and_count), app.run(create_and_count));
+        for res in [first.unwrap(), second.unwrap()] {
+            assert_json!(res, matcher!({
+                "data": 1
+            }));
+        }
+    }
+}
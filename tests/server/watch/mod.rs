@@ -0,0 +1,43 @@
+mod test {
+    use std::{env, fs, thread};
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::lib::{ExecutionHandle, req};
+
+    static PORT: i32 = 4030;
+
+    fn schema(models: &str) -> String {
+        format!("connector {{\n  provider .sqlite\n  url \"sqlite::memory:\"\n}}\n\nserver {{\n  bind (\"0.0.0.0\", {})\n}}\n\n{}", PORT, models)
+    }
+
+    const NOTE: &str = "model Note {\n  @id @autoIncrement @readonly\n  id: Int\n  title: String\n}\n";
+    const TAG: &str = "model Tag {\n  @id @autoIncrement @readonly\n  id: Int\n  name: String\n}\n";
+
+    fn is_serving() -> bool {
+        reqwest::blocking::Client::new().post(format!("http://127.0.0.1:{}/Note/count", PORT)).json(&json!({})).send().is_ok()
+    }
+
+    /// The server is restarted when the schema changes, kept when the new
+    /// schema has errors, and stopped with the watching process.
+    #[test]
+    fn restart_on_schema_change() {
+        let dir = env::temp_dir().join("teo_test_watch");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("schema.teo");
+        fs::write(&path, schema(NOTE)).unwrap();
+        let mut handle = ExecutionHandle::new();
+        handle.execute_schema(path.clone(), "serve --watch");
+        thread::sleep(Duration::from_secs(2));
+        assert!(req(PORT, "create", "Note", json!({ "create": { "title": "a" } })).get("data").is_some());
+        assert!(req(PORT, "create", "Tag", json!({ "create": { "name": "a" } })).get("error").is_some());
+        fs::write(&path, schema(&format!("{}\n{}", NOTE, TAG))).unwrap();
+        thread::sleep(Duration::from_secs(5));
+        assert!(req(PORT, "create", "Tag", json!({ "create": { "name": "a" } })).get("data").is_some());
+        fs::write(&path, schema(&format!("{}\n{}\nmodel Broken {{", NOTE, TAG))).unwrap();
+        thread::sleep(Duration::from_secs(5));
+        assert!(req(PORT, "create", "Tag", json!({ "create": { "name": "b" } })).get("data").is_some());
+        handle.exit();
+        thread::sleep(Duration::from_secs(3));
+        assert!(!is_serving());
+    }
+}